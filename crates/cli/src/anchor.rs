@@ -23,7 +23,7 @@ pub fn anchor_discriminator(method: &str) -> [u8; 8] {
 
 /// GlobalConfig PDA導出。seeds = [b"global-config"]
 pub fn find_global_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[b"global-config"], program_id)
+    title_types::pda::global_config_pda(program_id)
 }
 
 /// TeeNodeAccount PDA導出。seeds = [b"tee-node", &signing_pubkey]
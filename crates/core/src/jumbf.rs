@@ -2,10 +2,15 @@
 
 //! JUMBF（ISO 19566-5）の最小限パーサー。
 //!
-//! C2PA JUMBF データから特定マニフェストの COSE 署名バイト列を抽出する。
+//! C2PA JUMBF データから特定マニフェストの COSE 署名バイト列と
+//! ハードバインディング（`c2pa.hash.data`）アサーションを抽出する。
 //! 仕様書 §2.1: content_hash = SHA-256(Active Manifestの署名)
+//!
+//! 入力は攻撃者が自由に作れるバイト列であるため、ボックスのサイズ・オフセットの計算は
+//! すべて検査付きで行い、不正な値はパニックではなく `CoreError` として返す
+//! （リリースビルドでもオーバーフロー検査が有効なため、演算の溢れはパニックになる）。
 
-use crate::{CoreError, MAX_SIGNATURE_SIZE};
+use crate::{CoreError, ManifestLocation, MAX_SIGNATURE_SIZE};
 use std::io::{Cursor, Read, Seek, SeekFrom};
 
 /// JUMBF ボックスヘッダサイズ（4バイトsize + 4バイトtype）
@@ -25,10 +30,41 @@ const CAI_SIGNATURE_UUID: [u8; 16] = [
     0x71,
 ];
 
+/// アサーションストアのラベル
+const ASSERTION_STORE_LABEL: &str = "c2pa.assertions";
+
+/// データハッシュ（ハードバインディング）アサーションのラベル
+const DATA_HASH_LABEL: &str = "c2pa.hash.data";
+
 /// ボックスヘッダ情報
 struct BoxHeader {
     box_type: u32,
+    /// ヘッダを含むボックス全体のサイズ
     size: u64,
+    /// ヘッダ自体のサイズ（通常8バイト、拡張サイズ使用時16バイト）
+    header_len: u64,
+}
+
+impl BoxHeader {
+    /// ヘッダを除いた内容のサイズを返す。宣言サイズがヘッダより小さい場合はエラー。
+    fn content_size(&self) -> Result<u64, CoreError> {
+        self.size.checked_sub(self.header_len).ok_or_else(|| {
+            CoreError::ContentHashExtractionFailed(format!(
+                "JUMBFボックスのサイズがヘッダより小さいです: {}",
+                self.size
+            ))
+        })
+    }
+
+    /// `start` から始まるこのボックスの終端位置を返す。
+    fn end(&self, start: u64) -> Result<u64, CoreError> {
+        start.checked_add(self.size).ok_or_else(|| {
+            CoreError::ContentHashExtractionFailed(format!(
+                "JUMBFボックスのサイズが不正です: {}",
+                self.size
+            ))
+        })
+    }
 }
 
 /// Description box から読み取った情報
@@ -48,29 +84,40 @@ fn read_header(reader: &mut Cursor<&[u8]>) -> Result<BoxHeader, CoreError> {
         return Ok(BoxHeader {
             box_type: 0,
             size: 0,
+            header_len: HEADER_SIZE,
         });
     }
 
     let size = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
     let box_type = u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]);
 
-    if size == 1 {
+    let header = if size == 1 {
         // Extended size (u64)
         let mut ext_buf = [0u8; 8];
         reader.read_exact(&mut ext_buf).map_err(|e| {
             CoreError::ContentHashExtractionFailed(format!("JUMBF拡張サイズ読み取りエラー: {e}"))
         })?;
-        let large_size = u64::from_be_bytes(ext_buf);
-        Ok(BoxHeader {
+        BoxHeader {
             box_type,
-            size: large_size,
-        })
+            size: u64::from_be_bytes(ext_buf),
+            header_len: HEADER_SIZE + 8,
+        }
     } else {
-        Ok(BoxHeader {
+        BoxHeader {
             box_type,
             size: size as u64,
-        })
+            header_len: HEADER_SIZE,
+        }
+    };
+
+    // size=0は終端として扱う。それ以外でヘッダに満たないサイズは不正
+    if header.size != 0 && header.size < header.header_len {
+        return Err(CoreError::ContentHashExtractionFailed(format!(
+            "JUMBFボックスのサイズがヘッダより小さいです: {}",
+            header.size
+        )));
     }
+    Ok(header)
 }
 
 /// Description box の内容（UUID + ラベル）を読み取る。
@@ -92,14 +139,16 @@ fn read_desc_info(reader: &mut Cursor<&[u8]>, content_size: u64) -> Result<DescI
     })?;
 
     let mut label = String::new();
+    // ラベルとして読み取ったバイト数（null終端を含む）
+    let mut label_bytes: u64 = 0;
     if toggles[0] & 0x02 != 0 {
         // ラベル文字列がある（null終端）
         // C2PAラベルはASCII文字列のみ使用するため、バイト単位での処理で十分。
         // content_sizeを超えないようにガード（不正データによる無限ループ防止）
-        let max_label_len = (content_size - 17) as usize;
+        let max_label_len = content_size - 17;
         let mut byte = [0u8; 1];
         loop {
-            if label.len() >= max_label_len {
+            if label_bytes >= max_label_len {
                 return Err(CoreError::ContentHashExtractionFailed(
                     "JUMBFラベルがnull終端されていません".to_string(),
                 ));
@@ -107,6 +156,7 @@ fn read_desc_info(reader: &mut Cursor<&[u8]>, content_size: u64) -> Result<DescI
             reader.read_exact(&mut byte).map_err(|e| {
                 CoreError::ContentHashExtractionFailed(format!("ラベル読み取りエラー: {e}"))
             })?;
+            label_bytes += 1;
             if byte[0] == 0 {
                 break;
             }
@@ -115,10 +165,14 @@ fn read_desc_info(reader: &mut Cursor<&[u8]>, content_size: u64) -> Result<DescI
     }
 
     // 残りのバイトをスキップ（padding, salt hash等）
-    let read_so_far = 16 + 1 + if label.is_empty() { 0 } else { label.len() + 1 } as u64;
+    let read_so_far = 16 + 1 + label_bytes;
     if read_so_far < content_size {
-        let skip = content_size - read_so_far;
-        reader.seek(SeekFrom::Current(skip as i64)).map_err(|e| {
+        let skip = i64::try_from(content_size - read_so_far).map_err(|_| {
+            CoreError::ContentHashExtractionFailed(format!(
+                "JUMBF description boxのサイズが不正です: {content_size}"
+            ))
+        })?;
+        reader.seek(SeekFrom::Current(skip)).map_err(|e| {
             CoreError::ContentHashExtractionFailed(format!("スキップエラー: {e}"))
         })?;
     }
@@ -136,11 +190,90 @@ fn read_desc_info(reader: &mut Cursor<&[u8]>, content_size: u64) -> Result<DescI
 pub fn extract_signature_from_jumbf(
     jumbf_data: &[u8],
     manifest_label: &str,
+) -> Result<Vec<u8>, CoreError> {
+    let location = locate_manifest(jumbf_data, manifest_label)?;
+    let start = location.signature_offset as usize;
+    Ok(jumbf_data[start..start + location.signature_length as usize].to_vec())
+}
+
+/// 指定されたマニフェストラベルのsuperboxと署名CBORの、JUMBFデータ内での位置を返す。
+///
+/// 返す位置はいずれも `jumbf_data` の先頭からのバイトオフセットで、
+/// [`ManifestLocation::store_offset`] は設定しない（呼び出し側がファイル内の位置を補う）。
+pub fn locate_manifest(
+    jumbf_data: &[u8],
+    manifest_label: &str,
+) -> Result<ManifestLocation, CoreError> {
+    let mut reader = Cursor::new(jumbf_data);
+    let (manifest_offset, manifest_end) = seek_to_manifest_span(&mut reader, manifest_label)?;
+    // このマニフェスト内でc2pa.signatureボックスを探す
+    let (signature_offset, signature_length) =
+        find_signature_in_manifest(&mut reader, manifest_end)?;
+    Ok(ManifestLocation {
+        store_offset: None,
+        manifest_offset,
+        manifest_length: manifest_end - manifest_offset,
+        signature_offset,
+        signature_length,
+    })
+}
+
+/// `c2pa.hash.data` アサーションのCBOR表現（必要なフィールドのみ）
+#[derive(serde::Deserialize)]
+struct DataHashCbor {
+    #[serde(with = "serde_bytes")]
+    hash: Vec<u8>,
+}
+
+/// 指定されたマニフェストラベルの `c2pa.hash.data` アサーションのハッシュ値を JUMBF データから抽出する。
+///
+/// 仕様書 §2.1: ハードバインディング
+///
+/// 同一ラベルのアサーションが複数ある場合（`c2pa.hash.data__1` 等）は最初のものを返す。
+pub fn extract_data_hash_from_jumbf(
+    jumbf_data: &[u8],
+    manifest_label: &str,
 ) -> Result<Vec<u8>, CoreError> {
     let mut reader = Cursor::new(jumbf_data);
+    let manifest_end = seek_to_manifest(&mut reader, manifest_label)?;
+
+    let assertions_end =
+        find_labeled_superbox(&mut reader, manifest_end, |l| l == ASSERTION_STORE_LABEL)?
+            .ok_or_else(|| {
+                CoreError::ContentHashExtractionFailed(
+                    "c2pa.assertions boxが見つかりません".to_string(),
+                )
+            })?;
+    let hash_end = find_labeled_superbox(&mut reader, assertions_end, |l| {
+        l == DATA_HASH_LABEL || l.starts_with(&format!("{DATA_HASH_LABEL}__"))
+    })?
+    .ok_or_else(|| {
+        CoreError::ContentHashExtractionFailed(
+            "c2pa.hash.dataアサーションが見つかりません".to_string(),
+        )
+    })?;
+
+    let cbor = find_cbor_in_box(&mut reader, hash_end)?;
+    let parsed: DataHashCbor = ciborium::from_reader(cbor.as_slice()).map_err(|e| {
+        CoreError::ContentHashExtractionFailed(format!("c2pa.hash.dataのCBORパースエラー: {e}"))
+    })?;
+
+    Ok(parsed.hash)
+}
+
+/// トップレベルのc2pa storeから指定ラベルのマニフェストsuperboxを探し、
+/// readerをそのDescription boxの直後に位置させる。マニフェストの終端位置を返す。
+fn seek_to_manifest(reader: &mut Cursor<&[u8]>, manifest_label: &str) -> Result<u64, CoreError> {
+    seek_to_manifest_span(reader, manifest_label).map(|(_, end)| end)
+}
 
+/// [`seek_to_manifest`] と同じく位置決めし、マニフェストsuperboxの開始・終端位置を返す。
+fn seek_to_manifest_span(
+    reader: &mut Cursor<&[u8]>,
+    manifest_label: &str,
+) -> Result<(u64, u64), CoreError> {
     // トップレベルのsuperbox（c2pa store）を読む
-    let top_header = read_header(&mut reader)?;
+    let top_header = read_header(reader)?;
     if top_header.box_type != BOX_TYPE_JUMB {
         return Err(CoreError::ContentHashExtractionFailed(
             "トップレベルがJUMBF superboxではありません".to_string(),
@@ -148,57 +281,72 @@ pub fn extract_signature_from_jumbf(
     }
 
     // Description boxを読む
-    let desc_header = read_header(&mut reader)?;
+    let desc_header = read_header(reader)?;
     if desc_header.box_type != BOX_TYPE_JUMD {
         return Err(CoreError::ContentHashExtractionFailed(
             "Description boxが見つかりません".to_string(),
         ));
     }
-    let _top_desc = read_desc_info(&mut reader, desc_header.size - HEADER_SIZE)?;
+    let _top_desc = read_desc_info(reader, desc_header.content_size()?)?;
 
     // 各マニフェスト（子superbox）をスキャンして対象ラベルを探す
-    let top_end = top_header.size;
-    while reader.position() < top_end {
+    find_labeled_superbox_span(reader, top_header.size, |l| l == manifest_label)?.ok_or_else(|| {
+        CoreError::ContentHashExtractionFailed(format!(
+            "マニフェスト '{manifest_label}' が見つかりません"
+        ))
+    })
+}
+
+/// 現在位置から `end` までの子superboxのうち、ラベルが `matches` を満たす最初のものを探す。
+/// 見つかった場合はreaderをそのDescription boxの直後に位置させ、superboxの終端位置を返す。
+fn find_labeled_superbox(
+    reader: &mut Cursor<&[u8]>,
+    end: u64,
+    matches: impl Fn(&str) -> bool,
+) -> Result<Option<u64>, CoreError> {
+    Ok(find_labeled_superbox_span(reader, end, matches)?.map(|(_, box_end)| box_end))
+}
+
+/// [`find_labeled_superbox`] と同じく探索し、superboxの開始・終端位置を返す。
+fn find_labeled_superbox_span(
+    reader: &mut Cursor<&[u8]>,
+    end: u64,
+    matches: impl Fn(&str) -> bool,
+) -> Result<Option<(u64, u64)>, CoreError> {
+    while reader.position() < end {
         let child_start = reader.position();
-        let child_header = read_header(&mut reader)?;
+        let child_header = read_header(reader)?;
         if child_header.box_type == 0 || child_header.size == 0 {
             break;
         }
 
         if child_header.box_type == BOX_TYPE_JUMB {
-            // マニフェストsuperbox: description boxからラベルを読む
-            let desc_header = read_header(&mut reader)?;
+            // superbox: description boxからラベルを読む
+            let desc_header = read_header(reader)?;
             if desc_header.box_type == BOX_TYPE_JUMD {
-                let desc = read_desc_info(&mut reader, desc_header.size - HEADER_SIZE)?;
-
-                if desc.label == manifest_label {
-                    // このマニフェスト内でc2pa.signatureボックスを探す
-                    return find_signature_in_manifest(
-                        &mut reader,
-                        child_start + child_header.size,
-                    );
+                let desc = read_desc_info(reader, desc_header.content_size()?)?;
+                if matches(&desc.label) {
+                    return Ok(Some((child_start, child_header.end(child_start)?)));
                 }
             }
         }
 
         // このボックスの残りをスキップ
         reader
-            .seek(SeekFrom::Start(child_start + child_header.size))
+            .seek(SeekFrom::Start(child_header.end(child_start)?))
             .map_err(|e| {
                 CoreError::ContentHashExtractionFailed(format!("シークエラー: {e}"))
             })?;
     }
 
-    Err(CoreError::ContentHashExtractionFailed(format!(
-        "マニフェスト '{manifest_label}' が見つかりません"
-    )))
+    Ok(None)
 }
 
-/// マニフェストsuperbox内からc2pa.signature boxのCBORデータを抽出する。
+/// マニフェストsuperbox内からc2pa.signature boxのCBORデータの位置（オフセット, 長さ）を返す。
 fn find_signature_in_manifest(
     reader: &mut Cursor<&[u8]>,
     manifest_end: u64,
-) -> Result<Vec<u8>, CoreError> {
+) -> Result<(u64, u64), CoreError> {
     while reader.position() < manifest_end {
         let box_start = reader.position();
         let header = read_header(reader)?;
@@ -210,18 +358,18 @@ fn find_signature_in_manifest(
             // Description boxを読んでUUIDを確認
             let desc_header = read_header(reader)?;
             if desc_header.box_type == BOX_TYPE_JUMD {
-                let desc = read_desc_info(reader, desc_header.size - HEADER_SIZE)?;
+                let desc = read_desc_info(reader, desc_header.content_size()?)?;
 
                 if desc.uuid == CAI_SIGNATURE_UUID {
                     // c2pa.signature superbox内のCBOR boxを探す
-                    return find_cbor_in_box(reader, box_start + header.size);
+                    return find_cbor_span_in_box(reader, header.end(box_start)?);
                 }
             }
         }
 
         // このボックスの残りをスキップ
         reader
-            .seek(SeekFrom::Start(box_start + header.size))
+            .seek(SeekFrom::Start(header.end(box_start)?))
             .map_err(|e| {
                 CoreError::ContentHashExtractionFailed(format!("シークエラー: {e}"))
             })?;
@@ -237,6 +385,20 @@ fn find_cbor_in_box(
     reader: &mut Cursor<&[u8]>,
     box_end: u64,
 ) -> Result<Vec<u8>, CoreError> {
+    let (_, data_len) = find_cbor_span_in_box(reader, box_end)?;
+    let mut data = vec![0u8; data_len as usize];
+    reader.read_exact(&mut data).map_err(|e| {
+        CoreError::ContentHashExtractionFailed(format!("CBOR読み取りエラー: {e}"))
+    })?;
+    Ok(data)
+}
+
+/// superbox内から最初のCBOR boxを探し、データの位置（オフセット, 長さ）を返す。
+/// readerはデータの先頭に位置する。
+fn find_cbor_span_in_box(
+    reader: &mut Cursor<&[u8]>,
+    box_end: u64,
+) -> Result<(u64, u64), CoreError> {
     while reader.position() < box_end {
         let box_start = reader.position();
         let header = read_header(reader)?;
@@ -245,23 +407,26 @@ fn find_cbor_in_box(
         }
 
         if header.box_type == BOX_TYPE_CBOR {
-            let data_len = header.size - HEADER_SIZE;
+            let data_len = header.content_size()?;
             // 不正な巨大サイズによるOOMパニックを防止
             if data_len > MAX_SIGNATURE_SIZE {
                 return Err(CoreError::ContentHashExtractionFailed(format!(
                     "CBOR boxのサイズが上限を超えています: {data_len} > {MAX_SIGNATURE_SIZE}"
                 )));
             }
-            let mut data = vec![0u8; data_len as usize];
-            reader.read_exact(&mut data).map_err(|e| {
-                CoreError::ContentHashExtractionFailed(format!("CBOR読み取りエラー: {e}"))
-            })?;
-            return Ok(data);
+            // 宣言サイズがデータ末尾を超える場合は読み取り時と同様にエラーとする
+            let data_start = reader.position();
+            if data_start + data_len > reader.get_ref().len() as u64 {
+                return Err(CoreError::ContentHashExtractionFailed(
+                    "CBOR読み取りエラー: データが途中で終わっています".to_string(),
+                ));
+            }
+            return Ok((data_start, data_len));
         }
 
         // このボックスをスキップ
         reader
-            .seek(SeekFrom::Start(box_start + header.size))
+            .seek(SeekFrom::Start(header.end(box_start)?))
             .map_err(|e| {
                 CoreError::ContentHashExtractionFailed(format!("シークエラー: {e}"))
             })?;
@@ -271,3 +436,119 @@ fn find_cbor_in_box(
         "CBOR boxが見つかりません".to_string(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::sync::OnceLock;
+
+    /// 実際のC2PA署名済みJPEG（コーパスの種）
+    const SEED_IMAGE: &[u8] = include_bytes!("../../../tests/fixtures/expired_signer.jpg");
+
+    /// 種画像のJUMBFとActive Manifestのラベル。
+    fn seed() -> &'static (Vec<u8>, String) {
+        static SEED: OnceLock<(Vec<u8>, String)> = OnceLock::new();
+        SEED.get_or_init(|| {
+            let jumbf = c2pa::jumbf_io::load_jumbf_from_memory("image/jpeg", SEED_IMAGE).unwrap();
+            let reader = c2pa::Reader::from_stream("image/jpeg", Cursor::new(SEED_IMAGE)).unwrap();
+            (jumbf, reader.active_label().unwrap().to_string())
+        })
+    }
+
+    /// 全ての抽出関数を実行する。パニックせずにResultを返すこと自体を検査する。
+    fn parse_all(data: &[u8], label: &str) {
+        if let Ok(sig) = extract_signature_from_jumbf(data, label) {
+            assert!(sig.len() as u64 <= MAX_SIGNATURE_SIZE);
+        }
+        let _ = locate_manifest(data, label);
+        let _ = extract_data_hash_from_jumbf(data, label);
+    }
+
+    /// ヘッダを組み立てる（size, type）。
+    fn box_header(size: u32, box_type: u32) -> Vec<u8> {
+        [size.to_be_bytes(), box_type.to_be_bytes()].concat()
+    }
+
+    #[test]
+    fn test_seed_extracts_signature() {
+        let (jumbf, label) = seed();
+        let sig = extract_signature_from_jumbf(jumbf, label).unwrap();
+        assert!(!sig.is_empty());
+        assert!(extract_data_hash_from_jumbf(jumbf, label).is_ok());
+    }
+
+    /// ヘッダより小さい・溢れる長さフィールドはパニックせずエラーになることを確認
+    #[test]
+    fn test_malformed_length_fields_are_errors() {
+        let cases: Vec<Vec<u8>> = vec![
+            // description boxのサイズがヘッダ未満（content_sizeの減算が負になる）
+            [box_header(64, BOX_TYPE_JUMB), box_header(4, BOX_TYPE_JUMD)].concat(),
+            // 拡張サイズがヘッダ（16バイト）未満
+            [box_header(1, BOX_TYPE_JUMB), 9u64.to_be_bytes().to_vec()].concat(),
+            // 子ボックスの拡張サイズが u64::MAX（開始位置との加算が溢れる）
+            [
+                box_header(u32::MAX, BOX_TYPE_JUMB),
+                box_header(25, BOX_TYPE_JUMD),
+                vec![0u8; 16],
+                vec![0x03],
+                b"c2pa\0".to_vec(),
+                box_header(1, BOX_TYPE_JUMB),
+                u64::MAX.to_be_bytes().to_vec(),
+            ]
+            .concat(),
+            // description boxのサイズが i64 を超える
+            [
+                box_header(u32::MAX, BOX_TYPE_JUMB),
+                box_header(1, BOX_TYPE_JUMD),
+                u64::MAX.to_be_bytes().to_vec(),
+                vec![0u8; 17],
+            ]
+            .concat(),
+        ];
+        for (i, data) in cases.iter().enumerate() {
+            assert!(extract_signature_from_jumbf(data, "any").is_err(), "case {i}");
+        }
+    }
+
+    proptest! {
+        /// 任意のバイト列でパニックしない
+        #[test]
+        fn prop_random_bytes_never_panic(data in proptest::collection::vec(any::<u8>(), 0..1024)) {
+            parse_all(&data, &seed().1);
+        }
+
+        /// 実データの任意の位置を書き換え・切り詰めてもパニックしない
+        #[test]
+        fn prop_mutated_seed_never_panic(
+            flips in proptest::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 1..16),
+            truncate in any::<prop::sample::Index>(),
+        ) {
+            let (jumbf, label) = seed();
+            let mut data = jumbf.clone();
+            for (index, byte) in flips {
+                data[index.index(jumbf.len())] = byte;
+            }
+            data.truncate(truncate.index(jumbf.len() + 1));
+            parse_all(&data, label);
+        }
+
+        /// 実データの長さフィールドになり得る位置に極端な値を書き込んでもパニックしない
+        #[test]
+        fn prop_corrupted_length_fields_never_panic(
+            offset in any::<prop::sample::Index>(),
+            value in prop_oneof![
+                Just(0u32), Just(1u32), 2u32..24, Just(u32::MAX), Just(i32::MAX as u32), any::<u32>()
+            ],
+            extended in any::<u64>(),
+        ) {
+            let (jumbf, label) = seed();
+            let mut data = jumbf.clone();
+            let offset = offset.index(jumbf.len().saturating_sub(16));
+            data[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+            // size=1 の場合に読まれる拡張サイズも任意の値にする
+            data[offset + 8..offset + 16].copy_from_slice(&extended.to_be_bytes());
+            parse_all(&data, label);
+        }
+    }
+}
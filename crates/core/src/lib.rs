@@ -11,12 +11,16 @@
 //! 4. 来歴グラフ（ノードとエッジ）を構築する
 
 mod jumbf;
+pub mod settings;
+pub mod signed_json;
+pub mod signer_cert;
+mod store_size;
 pub mod tsa;
 
-use std::io::Cursor;
+use std::io::{Cursor, Read, Seek, SeekFrom};
 
-use c2pa::validation_results::ValidationState;
-use title_types::{GraphLink, GraphNode};
+use c2pa::validation_results::{validation_codes, ValidationState};
+use title_types::{ClaimGenerator, GraphLink, GraphNode};
 
 /// Coreモジュールのエラー型
 #[derive(Debug, thiserror::Error)]
@@ -24,36 +28,136 @@ pub enum CoreError {
     /// C2PA検証エラー
     #[error("C2PA検証に失敗しました: {0}")]
     C2paVerificationFailed(String),
+    /// ハードバインディング不一致エラー。
+    /// Manifestは存在するが、コンテンツ本体がManifestのハッシュと一致しない
+    /// （他コンテンツのManifestの流用、Manifest付与後の改変等）。
+    #[error("コンテンツがManifestのハードバインディングと一致しません: {0}")]
+    HardBindingMismatch(String),
     /// コンテンツハッシュ抽出エラー
     #[error("コンテンツハッシュの抽出に失敗しました: {0}")]
     ContentHashExtractionFailed(String),
     /// 来歴グラフ構築エラー
     #[error("来歴グラフの構築に失敗しました: {0}")]
     GraphBuildFailed(String),
+    /// マニフェストストア（JUMBF）サイズ超過エラー
+    #[error("マニフェストストアのサイズが上限を超えました: {size} > {max} bytes")]
+    ManifestStoreTooLarge {
+        /// マニフェストストアのバイト数
+        size: u64,
+        /// 上限値
+        max: u64,
+    },
     /// グラフサイズ超過エラー
     #[error("来歴グラフのサイズが上限を超えました: {nodes_and_links} > {max}")]
     GraphSizeExceeded {
-        /// 実際のノード+エッジ数
+        /// 構築を打ち切った時点のノード+エッジ数
         nodes_and_links: usize,
         /// 上限値
         max: usize,
     },
+    /// signed_json構築エラー
+    #[error("signed_jsonの構築に失敗しました: {0}")]
+    SignedJsonBuildFailed(String),
 }
 
 /// JUMBF署名データの最大サイズ（16 MiB）。
 /// これを超えるCBORデータは不正とみなす。
 const MAX_SIGNATURE_SIZE: u64 = 16 * 1024 * 1024;
 
+/// マニフェストストア（JUMBF全体）の既定の最大サイズ（32 MiB）。
+/// 仕様書 §2.1
+///
+/// [`MAX_SIGNATURE_SIZE`] は単一の署名を制限するのみで、多数のManifest・アサーションを
+/// 含む巨大なストアは制限しない。C2PAの解析・来歴グラフ構築の前にストア全体の大きさで拒否する。
+pub const DEFAULT_MAX_MANIFEST_STORE_BYTES: u64 = 32 * 1024 * 1024;
+
+/// ハードバインディング不一致を示すC2PA検証ステータスコード。
+/// 仕様書 §2.1 — コンテンツの同一性
+const HARD_BINDING_MISMATCH_CODES: &[&str] = &[
+    validation_codes::ASSERTION_DATAHASH_MISMATCH,
+    validation_codes::ASSERTION_BMFFHASH_MISMATCH,
+    validation_codes::ASSERTION_BOXHASH_MISMATCH,
+    validation_codes::ASSERTION_COLLECTIONHASH_MISMATCH,
+];
+
 /// ingredient再帰処理の最大深度。
 /// スタックオーバーフロー防止のため制限する。
 const MAX_INGREDIENT_DEPTH: usize = 32;
 
+/// Active Manifestの格納位置。
+/// 仕様書 §2.1
+///
+/// 検証者が署名を独立に再抽出できるよう、JUMBF解析で得た位置をそのまま公開する。
+/// `manifest_*` / `signature_*` はマニフェストストア（JUMBF）先頭からのバイトオフセット。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ManifestLocation {
+    /// ファイル内でのマニフェストストアの開始位置。
+    /// ストアがファイル内に連続して格納されている場合のみ `Some`
+    /// （複数のAPP11セグメントに分割されたJPEG等では `None`）。
+    pub store_offset: Option<u64>,
+    /// Active Manifestのsuperboxのオフセット（ボックスヘッダを含む）
+    pub manifest_offset: u64,
+    /// Active Manifestのsuperboxのバイト長
+    pub manifest_length: u64,
+    /// `c2pa.signature` のCBORデータ（COSE署名）のオフセット
+    pub signature_offset: u64,
+    /// `c2pa.signature` のCBORデータのバイト長
+    pub signature_length: u64,
+}
+
+/// C2PA検証状態。
+/// 仕様書 §2.1
+///
+/// C2PA仕様の検証状態に対応し、`Invalid < Valid < Trusted` の順に強い。
+/// ノードは受け入れる下限（[`C2paValidationState::parse`]）を設定でき、
+/// 下限に満たないコンテンツのCore処理を拒否する。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum C2paValidationState {
+    /// 署名・アサーションの検証に失敗した（下限としては「検証状態で拒否しない」を表す）
+    #[default]
+    Invalid,
+    /// 構造的に有効（署名者が信頼リストに連ならない自己署名等を含む）
+    Valid,
+    /// 有効かつ署名者が信頼リストに連なる
+    Trusted,
+}
+
+impl C2paValidationState {
+    /// 設定値（`invalid` / `valid` / `trusted`）をパースする。
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        match spec.trim().to_ascii_lowercase().as_str() {
+            "invalid" => Ok(Self::Invalid),
+            "valid" => Ok(Self::Valid),
+            "trusted" => Ok(Self::Trusted),
+            other => Err(format!(
+                "C2PA検証状態はinvalid/valid/trustedのいずれかです: {other}"
+            )),
+        }
+    }
+
+    /// 検証が成功したか（`Valid` 以上）。[`C2paVerificationResult::is_valid`] はこの値とする
+    pub fn is_valid(self) -> bool {
+        self >= Self::Valid
+    }
+
+    /// 設定値・エラーメッセージに用いる文字列表現
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Invalid => "invalid",
+            Self::Valid => "valid",
+            Self::Trusted => "trusted",
+        }
+    }
+}
+
 /// C2PA検証の結果。
 /// 仕様書 §2.1
 #[derive(Debug)]
 pub struct C2paVerificationResult {
-    /// 検証が成功したか
+    /// 検証が成功したか（`validation_state` が `Valid` 以上）
     pub is_valid: bool,
+    /// C2PA検証状態
+    pub validation_state: C2paValidationState,
     /// Active Manifestの署名バイト列
     pub active_manifest_signature: Vec<u8>,
     /// コンテンツのMIMEタイプ
@@ -62,6 +166,48 @@ pub struct C2paVerificationResult {
     /// `None` の場合、TSAタイムスタンプは存在しない。
     /// 仕様書 §2.4
     pub tsa_info: Option<tsa::TsaInfo>,
+    /// Active Manifest署名者証明書の有効期間（COSE x5chainから抽出）。
+    /// 期限切れでも検証は失敗させない（TSAタイムスタンプにより署名時点の有効性が示され得るため）。
+    /// 仕様書 §2.1
+    pub signer_cert_validity: Option<signer_cert::CertValidity>,
+    /// Active Manifest署名者証明書が自己署名（issuer == subject）か。
+    /// 自己署名のコンテンツは `is_valid` でも信頼リストに連なることはない。
+    /// 仕様書 §2.1
+    pub signer_self_signed: bool,
+    /// Active Manifestと署名の格納位置
+    pub manifest_location: ManifestLocation,
+    /// Active Manifestに含まれるアサーションのラベル一覧（記録順）。
+    /// 例: `c2pa.actions`, `c2pa.training-mining`, `stds.schema-org.CreativeWork`
+    pub assertion_labels: Vec<String>,
+    /// `c2pa.actions` アサーションに記録された編集アクションの種別（記録順）。
+    /// 例: `c2pa.created`, `c2pa.color_adjustments`, `c2pa.cropped`
+    /// 仕様書 §5.1 Step 4
+    pub actions: Vec<String>,
+    /// 生成AIで作成されたコンテンツか（`c2pa.actions` の `digitalSourceType` から判定）。
+    /// `None` は判定不能（unknown）。判定規則は [`classify_ai_generated`] を参照。
+    /// 仕様書 §5.1 Step 4
+    pub ai_generated: Option<bool>,
+}
+
+/// 検証時に解析したC2PAデータ（Readerとマニフェストストア）。
+/// 仕様書 §2.1, §2.2
+///
+/// [`verify_c2pa_parsed`] / [`verify_c2pa_manifest_only_parsed`] が検証結果とあわせて返し、
+/// [`build_provenance_graph_from_parsed`] で再解析せずに来歴グラフを構築するために使う。
+pub struct ParsedC2pa {
+    /// 検証に使用したReader
+    reader: c2pa::Reader,
+    /// マニフェストストア（JUMBF）
+    jumbf_data: Vec<u8>,
+}
+
+impl std::fmt::Debug for ParsedC2pa {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParsedC2pa")
+            .field("active_label", &self.reader.active_label())
+            .field("jumbf_len", &self.jumbf_data.len())
+            .finish()
+    }
 }
 
 /// 来歴グラフ（有向非巡回グラフ）。
@@ -103,6 +249,17 @@ pub struct TokenRecord {
     pub is_burned: bool,
 }
 
+/// 信頼TSAリストが空の場合の扱い。
+/// 仕様書 §2.4
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TsaTrustPolicy {
+    /// 空リストは「全てのTSAを信頼する」を意味する（開発環境向け、従来の挙動）
+    TrustAllWhenEmpty,
+    /// 空リストは「どのTSAも信頼しない」を意味する（本番環境向け）。
+    /// 全トークンの作成時刻はSolana block timeになる。
+    TrustNoneWhenEmpty,
+}
+
 /// 同一content_hashに対する複数の権利トークンから正当な所有者を決定する。
 /// 仕様書 §2.4 重複の解決
 ///
@@ -115,10 +272,26 @@ pub struct TokenRecord {
 /// 4. 同一作成時刻の場合、登録時刻（Solana block time）が最古のものを選択する
 ///
 /// `trusted_tsa_keys` が指定された場合、TSA公開鍵ハッシュがリストに含まれるもののみ
-/// TSAタイムスタンプを信頼する。リストが空の場合、全てのTSAを信頼する。
+/// TSAタイムスタンプを信頼する。リストが空の場合、全てのTSAを信頼する
+/// （[`TsaTrustPolicy::TrustAllWhenEmpty`]）。空リストの扱いを選ぶ場合は
+/// [`resolve_duplicate_with_policy`] を使用する。
 pub fn resolve_duplicate<'a>(
     tokens: &'a [TokenRecord],
     trusted_tsa_keys: &[String],
+) -> Option<&'a TokenRecord> {
+    resolve_duplicate_with_policy(tokens, trusted_tsa_keys, TsaTrustPolicy::TrustAllWhenEmpty)
+}
+
+/// 空の信頼TSAリストの扱いを指定して重複を解決する。
+/// 仕様書 §2.4 重複の解決
+///
+/// 判定ロジックは [`resolve_duplicate`] と同一。`trusted_tsa_keys` が空のとき、
+/// `policy` に従って全TSAを信頼するか、どのTSAも信頼しない（全トークンで
+/// Solana block timeを使用する）かを決める。
+pub fn resolve_duplicate_with_policy<'a>(
+    tokens: &'a [TokenRecord],
+    trusted_tsa_keys: &[String],
+    policy: TsaTrustPolicy,
 ) -> Option<&'a TokenRecord> {
     let active: Vec<&TokenRecord> = tokens.iter().filter(|t| !t.is_burned).collect();
 
@@ -127,8 +300,8 @@ pub fn resolve_duplicate<'a>(
     }
 
     active.into_iter().min_by(|a, b| {
-        let a_time = effective_creation_time(a, trusted_tsa_keys);
-        let b_time = effective_creation_time(b, trusted_tsa_keys);
+        let a_time = effective_creation_time(a, trusted_tsa_keys, policy);
+        let b_time = effective_creation_time(b, trusted_tsa_keys, policy);
 
         a_time
             .cmp(&b_time)
@@ -138,14 +311,18 @@ pub fn resolve_duplicate<'a>(
 
 /// トークンの有効な作成時刻を決定する。
 /// 仕様書 §2.4
-fn effective_creation_time(token: &TokenRecord, trusted_tsa_keys: &[String]) -> u64 {
+fn effective_creation_time(
+    token: &TokenRecord,
+    trusted_tsa_keys: &[String],
+    policy: TsaTrustPolicy,
+) -> u64 {
     if let Some(tsa_ts) = token.tsa_timestamp {
         // TSA証明書ハッシュが信頼リストに含まれるか確認
         let is_trusted = if trusted_tsa_keys.is_empty() {
-            // 信頼リストが空の場合は全てのTSAを信頼
-            true
+            // 信頼リストが空の場合はポリシーに従う
+            policy == TsaTrustPolicy::TrustAllWhenEmpty
         } else if let Some(ref hash) = token.tsa_cert_hash {
-            trusted_tsa_keys.contains(hash)
+            tsa::is_trusted_tsa(hash, trusted_tsa_keys)
         } else {
             false
         };
@@ -161,14 +338,179 @@ fn effective_creation_time(token: &TokenRecord, trusted_tsa_keys: &[String]) ->
 
 /// JUMBFデータからマニフェストの署名バイト列を取得する。
 /// 仕様書 §2.1
-fn extract_manifest_signature(
-    content_bytes: &[u8],
+///
+/// JUMBFの格納位置はフォーマットごとに異なる（JPEG: APP11セグメント、
+/// WEBP: RIFFの `C2PA` チャンク等）。コンテナの解析は `mime_type` に応じて
+/// c2pa-rsのフォーマットハンドラが行うため、VP8X拡張形式のWEBPも同じ経路で扱える。
+///
+/// 署名とあわせてActive Manifestの格納位置を返す。`jumbf_data` は `content` から
+/// 抽出済みのマニフェストストア（[`load_jumbf`]）。
+fn extract_manifest_signature<R: Read + Seek>(
+    content: &mut R,
     mime_type: &str,
+    jumbf_data: &[u8],
     manifest_label: &str,
-) -> Result<Vec<u8>, CoreError> {
-    let jumbf_data = c2pa::jumbf_io::load_jumbf_from_memory(mime_type, content_bytes)
-        .map_err(|e| CoreError::ContentHashExtractionFailed(format!("JUMBF抽出エラー: {e}")))?;
-    jumbf::extract_signature_from_jumbf(&jumbf_data, manifest_label)
+) -> Result<(Vec<u8>, ManifestLocation), CoreError> {
+    let mut location = jumbf::locate_manifest(jumbf_data, manifest_label)?;
+    location.store_offset = locate_store(content, mime_type, jumbf_data).map_err(|e| {
+        CoreError::ContentHashExtractionFailed(format!("JUMBF抽出エラー: {e}"))
+    })?;
+    let start = location.signature_offset as usize;
+    let signature = jumbf_data[start..start + location.signature_length as usize].to_vec();
+    Ok((signature, location))
+}
+
+/// ファイル内でのマニフェストストアの開始位置を返す。
+///
+/// コンテナのヘッダから読んだストアの位置（[`store_size::declared_store_size`]）を用い、
+/// コンテンツ全体を走査しない。その位置のバイト列が抽出済みの `jumbf_data` と一致する
+/// 場合のみ `Some` とする（ストアが連続して格納されていない・ヘッダから位置を読めない
+/// フォーマットでは `None`）。
+fn locate_store<R: Read + Seek>(
+    content: &mut R,
+    mime_type: &str,
+    jumbf_data: &[u8],
+) -> std::io::Result<Option<u64>> {
+    let offset = match store_size::declared_store_size(content, mime_type)? {
+        store_size::DeclaredSize::Found(store_size::DeclaredStore {
+            offset: Some(offset),
+            size,
+        }) if size == jumbf_data.len() as u64 => offset,
+        _ => return Ok(None),
+    };
+    content.seek(SeekFrom::Start(offset))?;
+    let mut stored = vec![0u8; jumbf_data.len()];
+    match content.read_exact(&mut stored) {
+        Ok(()) => Ok((stored == jumbf_data).then_some(offset)),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// コンテナからマニフェストストア（JUMBF）を抽出する。
+fn load_jumbf<R: Read + Seek + Send>(content: &mut R, mime_type: &str) -> Result<Vec<u8>, CoreError> {
+    let extraction_error =
+        |e: &dyn std::fmt::Display| CoreError::ContentHashExtractionFailed(format!("JUMBF抽出エラー: {e}"));
+    content.rewind().map_err(|e| extraction_error(&e))?;
+    c2pa::jumbf_io::load_jumbf_from_stream(mime_type, content).map_err(|e| extraction_error(&e))
+}
+
+/// コンテンツ内のマニフェストストア（JUMBF）のサイズが上限以下であることを確認する。
+/// 仕様書 §2.1
+///
+/// c2pa-rsによる解析の前に呼び出し、巨大なストアを早期に拒否する。
+/// コンテナとJUMBF superboxのヘッダに宣言された長さで判定し、ストア本体は読み込まない。
+/// ストアがない場合は確認せず、後続の読み込みでエラーとする。
+/// ヘッダのみで判定できないフォーマットに限り、JUMBFを抽出して長さを確認する。
+fn check_manifest_store_size<R: Read + Seek + Send>(
+    content: &mut R,
+    mime_type: &str,
+    max_bytes: u64,
+) -> Result<(), CoreError> {
+    let read_error = |e: std::io::Error| CoreError::C2paVerificationFailed(format!("読み込みエラー: {e}"));
+    let size = if mime_type == SIDECAR_MIME_TYPE {
+        // サイドカーはストアそのもの
+        content.seek(SeekFrom::End(0)).map_err(read_error)?
+    } else {
+        match store_size::declared_store_size(content, mime_type).map_err(read_error)? {
+            store_size::DeclaredSize::Found(store) => store.size,
+            store_size::DeclaredSize::NotFound => return Ok(()),
+            store_size::DeclaredSize::Unsupported => {
+                let jumbf_data = content
+                    .rewind()
+                    .ok()
+                    .and_then(|()| c2pa::jumbf_io::load_jumbf_from_stream(mime_type, content).ok());
+                match jumbf_data {
+                    Some(jumbf_data) => jumbf_data.len() as u64,
+                    None => return Ok(()),
+                }
+            }
+        }
+    };
+    if size > max_bytes {
+        return Err(CoreError::ManifestStoreTooLarge {
+            size,
+            max: max_bytes,
+        });
+    }
+    Ok(())
+}
+
+/// Active Manifestの検証結果から失敗ステータスコードを抽出する。
+fn active_manifest_failures(reader: &c2pa::Reader) -> Vec<String> {
+    let statuses = match reader
+        .validation_results()
+        .and_then(|results| results.active_manifest())
+    {
+        Some(codes) => codes.failure().as_slice(),
+        None => reader.validation_status().unwrap_or_default(),
+    };
+
+    statuses.iter().map(|status| status.code().to_string()).collect()
+}
+
+/// Active Manifestの署名者が信頼リストに連なるか（`signingCredential.trusted`）。
+fn has_trusted_credential(reader: &c2pa::Reader) -> bool {
+    reader
+        .validation_results()
+        .and_then(|results| results.active_manifest())
+        .is_some_and(|codes| {
+            codes
+                .success()
+                .iter()
+                .any(|status| status.code() == validation_codes::SIGNING_CREDENTIAL_TRUSTED)
+        })
+}
+
+/// Active Manifestの検証結果からハードバインディング不一致のステータスコードを抽出する。
+/// 仕様書 §2.1
+fn hard_binding_mismatches(reader: &c2pa::Reader) -> Vec<String> {
+    active_manifest_failures(reader)
+        .into_iter()
+        .filter(|code| HARD_BINDING_MISMATCH_CODES.contains(&code.as_str()))
+        .collect()
+}
+
+/// 指定したContextの設定でC2PAデータを読み込む。
+fn read_c2pa(
+    context: &std::sync::Arc<c2pa::Context>,
+    content_bytes: &[u8],
+    mime_type: &str,
+) -> c2pa::Result<c2pa::Reader> {
+    c2pa::Reader::from_shared_context(context).with_stream(mime_type, Cursor::new(content_bytes))
+}
+
+/// C2PA署名チェーンを検証し、結果を返す。
+/// 仕様書 §2.1 コンテンツの識別子
+///
+/// 検証内容は [`verify_c2pa_stream`] と同じ。
+pub fn verify_c2pa(
+    content_bytes: &[u8],
+    mime_type: &str,
+    trusted_tsa_keys: &[String],
+    max_manifest_store_bytes: u64,
+) -> Result<C2paVerificationResult, CoreError> {
+    verify_c2pa_parsed(content_bytes, mime_type, trusted_tsa_keys, max_manifest_store_bytes)
+        .map(|(result, _)| result)
+}
+
+/// C2PA署名チェーンを検証し、結果と解析済みのC2PAデータを返す。
+/// 仕様書 §2.1, §2.2
+///
+/// 検証内容は [`verify_c2pa`] と同じ。続けて来歴グラフを構築する場合は、返された
+/// [`ParsedC2pa`] を [`build_provenance_graph_from_parsed`] に渡すことでReader・JUMBFの再解析を省ける。
+pub fn verify_c2pa_parsed(
+    content_bytes: &[u8],
+    mime_type: &str,
+    trusted_tsa_keys: &[String],
+    max_manifest_store_bytes: u64,
+) -> Result<(C2paVerificationResult, ParsedC2pa), CoreError> {
+    verify_c2pa_reader(
+        Cursor::new(content_bytes),
+        mime_type,
+        trusted_tsa_keys,
+        max_manifest_store_bytes,
+    )
 }
 
 /// C2PA署名チェーンを検証し、結果を返す。
@@ -177,20 +519,57 @@ fn extract_manifest_signature(
 /// TEEはC2PA署名チェーンの正当性を検証し、以下を確認する:
 /// - 署名チェーンの正当性（コンテンツの出自が改ざんされていない）
 /// - コンテンツの同一性（Manifestが付与された時点から変更されていない）
-pub fn verify_c2pa(
-    content_bytes: &[u8],
+///
+/// コンテンツ本体がManifestのハードバインディングと一致しない場合は
+/// `CoreError::HardBindingMismatch` を返す（C2PAデータが存在しない場合の
+/// `CoreError::C2paVerificationFailed` とは区別される）。
+///
+/// `trusted_tsa_keys` はTSA証明書ハッシュの信頼リスト（仕様書 §2.4）。
+/// TSAタイムスタンプは信頼の可否に関わらず抽出し、[`tsa::TsaInfo::trusted`] に判定結果を記録する。
+///
+/// マニフェストストアが `max_manifest_store_bytes` を超える場合は、解析前に
+/// `CoreError::ManifestStoreTooLarge` を返す。
+///
+/// コンテンツは `Read + Seek` なリーダーから読み込む（ファイル等、呼び出し側がコンテンツを
+/// メモリに保持していない場合向け）。TEEの `/verify` は復号済みのコンテンツをメモリ上に保持するため、
+/// スライス版の [`verify_c2pa`] を使用する。
+pub fn verify_c2pa_stream<R: Read + Seek + Send>(
+    content: R,
     mime_type: &str,
+    trusted_tsa_keys: &[String],
+    max_manifest_store_bytes: u64,
 ) -> Result<C2paVerificationResult, CoreError> {
-    // c2pa::Readerでコンテンツを読み込み・検証する
-    let reader = c2pa::Reader::from_stream(mime_type, Cursor::new(content_bytes))
+    verify_c2pa_reader(content, mime_type, trusted_tsa_keys, max_manifest_store_bytes)
+        .map(|(result, _)| result)
+}
+
+/// [`verify_c2pa_stream`] の実装。検証に使用したReaderとJUMBFもあわせて返す。
+fn verify_c2pa_reader<R: Read + Seek + Send>(
+    mut content: R,
+    mime_type: &str,
+    trusted_tsa_keys: &[String],
+    max_manifest_store_bytes: u64,
+) -> Result<(C2paVerificationResult, ParsedC2pa), CoreError> {
+    check_manifest_store_size(&mut content, mime_type, max_manifest_store_bytes)?;
+
+    // c2pa::Readerでコンテンツを読み込み・検証する（固定設定を使用）
+    let context = settings::verification_context()?;
+    let reader = c2pa::Reader::from_shared_context(&context)
+        .with_stream(mime_type, &mut content)
         .map_err(|e| CoreError::C2paVerificationFailed(format!("C2PAデータ読み込みエラー: {e}")))?;
 
+    // ハードバインディング検証（Manifestとコンテンツ本体の一致）
+    let mismatches = hard_binding_mismatches(&reader);
+    if !mismatches.is_empty() {
+        return Err(CoreError::HardBindingMismatch(mismatches.join(", ")));
+    }
+
     // 検証状態を確認
-    let validation_state = reader.validation_state();
-    let is_valid = matches!(
-        validation_state,
-        ValidationState::Valid | ValidationState::Trusted
-    );
+    let validation_state = match reader.validation_state() {
+        ValidationState::Trusted => C2paValidationState::Trusted,
+        ValidationState::Valid => C2paValidationState::Valid,
+        ValidationState::Invalid => C2paValidationState::Invalid,
+    };
 
     // Active Manifestを取得
     let active_label = reader
@@ -211,28 +590,293 @@ pub fn verify_c2pa(
         .to_string();
 
     // JUMBFから署名バイト列を抽出
-    let signature = extract_manifest_signature(content_bytes, mime_type, &active_label)?;
+    let jumbf_data = load_jumbf(&mut content, mime_type)?;
+    let (signature, manifest_location) =
+        extract_manifest_signature(&mut content, mime_type, &jumbf_data, &active_label)?;
 
     // TSAタイムスタンプ抽出（仕様書 §2.4）
     // COSE署名のunprotected headersからsigTst/sigTst2を検索し、
     // RFC 3161トークンからTSA証明済み時刻を抽出する。
-    let tsa_info = tsa::extract_tsa_from_cose(&signature)?;
+    let tsa_info = tsa::extract_tsa_from_cose(&signature, trusted_tsa_keys)?;
+    let signer_cert_validity = signer_cert::extract_signer_validity(&signature)?;
+    let signer_self_signed = signer_cert::is_signer_self_signed(&signature)?;
 
-    Ok(C2paVerificationResult {
-        is_valid,
+    let result = C2paVerificationResult {
+        is_valid: validation_state.is_valid(),
+        validation_state,
         active_manifest_signature: signature,
         content_type,
         tsa_info,
-    })
+        signer_cert_validity,
+        signer_self_signed,
+        manifest_location,
+        assertion_labels: assertion_labels(manifest),
+        actions: action_types(manifest),
+        ai_generated: classify_ai_generated(&reader),
+    };
+    Ok((result, ParsedC2pa { reader, jumbf_data }))
+}
+
+/// サイドカーManifest（`.c2pa`）のMIMEタイプ
+pub const SIDECAR_MIME_TYPE: &str = "application/c2pa";
+
+/// Manifest内部の整合性違反（アサーションがclaimの参照と一致しない等）を示す検証ステータスコード。
+/// manifest-only検証では、照合対象のハードバインディングアサーションがclaimに
+/// 束縛されていることの保証となるため、これらを致命的エラーとして扱う。
+const MANIFEST_INTEGRITY_FAILURE_CODES: &[&str] = &[
+    validation_codes::ASSERTION_HASHEDURI_MISMATCH,
+    validation_codes::ASSERTION_MISSING,
+    validation_codes::CLAIM_MISSING,
+];
+
+/// サイドカーManifestのみでC2PA検証を行う（コンテンツ本体なし）。
+/// 仕様書 §2.1, §5.1 Step 4（manifest-onlyモード）
+///
+/// コンテンツ本体を受け取らないため、ハードバインディングはクライアントが主張する
+/// `asserted_hash`（コンテンツ本体の `c2pa.hash.data` ハッシュ値）との照合で代替する:
+/// - Manifestの内部整合性（アサーションとclaimの参照の一致）を検証する
+/// - Active Manifestの `c2pa.hash.data` の値が `asserted_hash` と一致することを確認する
+///
+/// 本体を見ていないため、`asserted_hash` が実際のコンテンツ本体のハッシュであることは保証されない。
+/// `trusted_tsa_keys` と `max_manifest_store_bytes` の扱いは [`verify_c2pa`] と同じ。
+pub fn verify_c2pa_manifest_only(
+    manifest_store: &[u8],
+    asserted_hash: &[u8],
+    trusted_tsa_keys: &[String],
+    max_manifest_store_bytes: u64,
+) -> Result<C2paVerificationResult, CoreError> {
+    verify_c2pa_manifest_only_parsed(
+        manifest_store,
+        asserted_hash,
+        trusted_tsa_keys,
+        max_manifest_store_bytes,
+    )
+    .map(|(result, _)| result)
+}
+
+/// サイドカーManifestのみでC2PA検証を行い、結果と解析済みのC2PAデータを返す。
+/// 仕様書 §2.1, §2.2, §5.1 Step 4（manifest-onlyモード）
+///
+/// 検証内容は [`verify_c2pa_manifest_only`] と同じ（[`verify_c2pa_parsed`] を参照）。
+pub fn verify_c2pa_manifest_only_parsed(
+    manifest_store: &[u8],
+    asserted_hash: &[u8],
+    trusted_tsa_keys: &[String],
+    max_manifest_store_bytes: u64,
+) -> Result<(C2paVerificationResult, ParsedC2pa), CoreError> {
+    check_manifest_store_size(&mut Cursor::new(manifest_store), SIDECAR_MIME_TYPE, max_manifest_store_bytes)?;
+
+    let context = settings::verification_context()?;
+    let reader = read_c2pa(&context, manifest_store, SIDECAR_MIME_TYPE)
+        .map_err(|e| CoreError::C2paVerificationFailed(format!("C2PAデータ読み込みエラー: {e}")))?;
+
+    // 本体がないためハードバインディング不一致は必ず報告される。照合はasserted_hashで行う
+    let failures: Vec<String> = active_manifest_failures(&reader)
+        .into_iter()
+        .filter(|code| !HARD_BINDING_MISMATCH_CODES.contains(&code.as_str()))
+        .collect();
+    let integrity_failures: Vec<&String> = failures
+        .iter()
+        .filter(|code| MANIFEST_INTEGRITY_FAILURE_CODES.contains(&code.as_str()))
+        .collect();
+    if !integrity_failures.is_empty() {
+        return Err(CoreError::C2paVerificationFailed(format!(
+            "Manifestの内部整合性が不正です: {}",
+            integrity_failures
+                .iter()
+                .map(|c| c.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+
+    let active_label = reader
+        .active_label()
+        .ok_or_else(|| {
+            CoreError::C2paVerificationFailed("Active Manifestが見つかりません".to_string())
+        })?
+        .to_string();
+    let manifest = reader.active_manifest().ok_or_else(|| {
+        CoreError::C2paVerificationFailed("Active Manifestが見つかりません".to_string())
+    })?;
+    // 本体がないため、MIMEタイプはManifestに記録されている場合のみ判明する
+    let content_type = manifest
+        .format()
+        .unwrap_or("application/octet-stream")
+        .to_string();
+
+    // ハードバインディングの照合（主張されたハッシュ vs Manifestの記録値）
+    let data_hash = jumbf::extract_data_hash_from_jumbf(manifest_store, &active_label)
+        .map_err(|e| CoreError::C2paVerificationFailed(e.to_string()))?;
+    if data_hash != asserted_hash {
+        return Err(CoreError::HardBindingMismatch(format!(
+            "主張されたコンテンツハッシュ({})がc2pa.hash.data({})と一致しません",
+            hex::encode(asserted_hash),
+            hex::encode(&data_hash)
+        )));
+    }
+
+    // サイドカーはJUMBFそのものなので、コンテナ解析なしで署名を抽出できる
+    let manifest_location = ManifestLocation {
+        store_offset: Some(0),
+        ..jumbf::locate_manifest(manifest_store, &active_label)?
+    };
+    let start = manifest_location.signature_offset as usize;
+    let signature =
+        manifest_store[start..start + manifest_location.signature_length as usize].to_vec();
+    let tsa_info = tsa::extract_tsa_from_cose(&signature, trusted_tsa_keys)?;
+    let signer_cert_validity = signer_cert::extract_signer_validity(&signature)?;
+    let signer_self_signed = signer_cert::is_signer_self_signed(&signature)?;
+
+    // 本体がないためc2pa-rsの検証状態は常にInvalidとなる。ハードバインディング以外の結果から判定する
+    let validation_state = if !failures
+        .iter()
+        .all(|code| code == validation_codes::SIGNING_CREDENTIAL_UNTRUSTED)
+    {
+        C2paValidationState::Invalid
+    } else if failures.is_empty() && has_trusted_credential(&reader) {
+        C2paValidationState::Trusted
+    } else {
+        C2paValidationState::Valid
+    };
+
+    let result = C2paVerificationResult {
+        // 通常モードと同じく検証状態から導出する
+        is_valid: validation_state.is_valid(),
+        validation_state,
+        active_manifest_signature: signature,
+        content_type,
+        tsa_info,
+        signer_cert_validity,
+        signer_self_signed,
+        manifest_location,
+        assertion_labels: assertion_labels(manifest),
+        actions: action_types(manifest),
+        ai_generated: classify_ai_generated(&reader),
+    };
+    // サイドカーはJUMBFそのもの
+    let jumbf_data = manifest_store.to_vec();
+    Ok((result, ParsedC2pa { reader, jumbf_data }))
+}
+
+/// コンテンツのActive Manifestに含まれるアサーションのラベルを記録順に返す。
+/// 仕様書 §7.1, §7.4
+///
+/// 署名チェーンの検証結果は問わない（ラベルの列挙のみ）。
+/// [`C2paVerificationResult::assertion_labels`] と同じ列挙規則で、ハードバインディング等の
+/// c2pa-rsが内部で扱うアサーションは含まない。
+/// マニフェストストアが `max_manifest_store_bytes` を超える場合は、解析前に
+/// [`CoreError::ManifestStoreTooLarge`] を返す。
+pub fn active_assertion_labels(
+    content_bytes: &[u8],
+    mime_type: &str,
+    max_manifest_store_bytes: u64,
+) -> Result<Vec<String>, CoreError> {
+    check_manifest_store_size(&mut Cursor::new(content_bytes), mime_type, max_manifest_store_bytes)?;
+    let context = settings::verification_context()?;
+    let reader = read_c2pa(&context, content_bytes, mime_type)
+        .map_err(|e| CoreError::C2paVerificationFailed(format!("C2PAデータ読み込みエラー: {e}")))?;
+    let manifest = reader.active_manifest().ok_or_else(|| {
+        CoreError::C2paVerificationFailed("Active Manifestが見つかりません".to_string())
+    })?;
+    Ok(assertion_labels(manifest))
+}
+
+/// Manifestに含まれるアサーションのラベルを記録順に返す。
+fn assertion_labels(manifest: &c2pa::Manifest) -> Vec<String> {
+    manifest
+        .assertions()
+        .iter()
+        .map(|assertion| assertion.label().to_string())
+        .collect()
+}
+
+/// `c2pa.actions` アサーションのラベル（v1 / v2）
+const ACTIONS_LABELS: &[&str] = &["c2pa.actions", "c2pa.actions.v2"];
+
+/// Manifestの `c2pa.actions` アサーションからアクション種別を記録順に返す。
+/// 複数のアクションアサーションがある場合はアサーションの記録順に連結する。
+fn action_types(manifest: &c2pa::Manifest) -> Vec<String> {
+    manifest_actions(manifest)
+        .filter_map(|action| action.get("action").and_then(|a| a.as_str()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Manifestの `c2pa.actions` アサーションに含まれるアクションを記録順に返す。
+fn manifest_actions(manifest: &c2pa::Manifest) -> impl Iterator<Item = &serde_json::Value> {
+    manifest
+        .assertions()
+        .iter()
+        .filter(|assertion| ACTIONS_LABELS.contains(&assertion.label()))
+        .filter_map(|assertion| assertion.value().ok())
+        .filter_map(|value| value.get("actions").and_then(|a| a.as_array()))
+        .flatten()
+}
+
+/// 生成AIによる作成を示すIPTC digitalSourceType（URIの末尾部分）
+const GENERATIVE_AI_SOURCE_TYPES: &[&str] =
+    &["trainedAlgorithmicMedia", "compositeWithTrainedAlgorithmicMedia"];
+
+/// 生成AIによらない作成を示すIPTC digitalSourceType（URIの末尾部分）。
+/// `compositeSynthetic`・`algorithmicallyEnhanced`・`dataDrivenMedia` 等、
+/// 生成AIの関与を否定できない種別は含めない。
+const NON_AI_SOURCE_TYPES: &[&str] = &[
+    "digitalCapture",
+    "computationalCapture",
+    "negativeFilm",
+    "positiveFilm",
+    "print",
+    "screenCapture",
+    "virtualRecording",
+    "compositeCapture",
+    "humanEdits",
+    "minorHumanEdits",
+    "digitalArt",
+    "algorithmicMedia",
+];
+
+/// 生成AIで作成されたコンテンツかをManifestストア全体の `c2pa.actions` から判定する。
+/// 仕様書 §5.1 Step 4
+///
+/// ingredientのManifestもストアに含まれるため、素材に生成AIコンテンツを含む場合も検出できる。
+/// - いずれかのアクションの `digitalSourceType` が生成AIを示す → `Some(true)`
+/// - `c2pa.created` が1つ以上あり、`digitalSourceType` を持つ全アクションが
+///   生成AIによらない種別で、かつ全ての `c2pa.created` が種別を持つ → `Some(false)`
+/// - それ以外（`c2pa.created` がない、種別が未記載・判断できない等） → `None`
+fn classify_ai_generated(reader: &c2pa::Reader) -> Option<bool> {
+    let mut has_created = false;
+    let mut all_known_non_ai = true;
+
+    for action in reader.iter_manifests().flat_map(manifest_actions) {
+        let source_type = action
+            .get("digitalSourceType")
+            .and_then(|t| t.as_str())
+            .map(|t| t.rsplit('/').next().unwrap_or(t));
+        let is_created = action.get("action").and_then(|a| a.as_str()) == Some("c2pa.created");
+        has_created |= is_created;
+
+        match source_type {
+            Some(t) if GENERATIVE_AI_SOURCE_TYPES.contains(&t) => return Some(true),
+            Some(t) if NON_AI_SOURCE_TYPES.contains(&t) => {}
+            Some(_) => all_known_non_ai = false,
+            None if is_created => all_known_non_ai = false,
+            None => {}
+        }
+    }
+
+    (has_created && all_known_non_ai).then_some(false)
 }
 
 /// Active Manifestの署名からcontent_hashを抽出する。
 /// 仕様書 §2.1 コンテンツの識別子: `content_hash = SHA-256(Active Manifestの署名)`
+///
+/// 既定（空）のデプロイメント名前空間で算出する。
 pub fn extract_content_hash(
     content_bytes: &[u8],
     mime_type: &str,
 ) -> Result<[u8; 32], CoreError> {
-    let result = verify_c2pa(content_bytes, mime_type)?;
+    let result = verify_c2pa(content_bytes, mime_type, &[], DEFAULT_MAX_MANIFEST_STORE_BYTES)?;
     Ok(title_crypto::content_hash_from_manifest_signature(
         &result.active_manifest_signature,
     ))
@@ -244,15 +888,51 @@ pub fn extract_content_hash(
 /// 各ノードはcontent_hashで識別され、各エッジは
 /// 「この素材がこのコンテンツの作成に使われた」という関係を表す。
 /// グラフはC2PAデータから客観的・機械的に構築される。
+/// 各ノードには、そのManifestの `claim_generator_info`（生成ツールの名前とバージョン）を付与する。
+///
+/// マニフェストストアが `max_manifest_store_bytes` を超える場合は、グラフ構築前に
+/// `CoreError::ManifestStoreTooLarge` を返す。
+///
+/// ノード+エッジ数が `max_graph_size` を超えた時点で走査を打ち切り、
+/// `CoreError::GraphSizeExceeded` を返す（大量の直接ingredientを持つ幅の広いグラフを
+/// 最後まで構築しない）。
+///
+/// 各ノードのcontent_hashは `namespace`（デプロイメント名前空間、既定は空）で算出する
+/// （`title_crypto::content_hash_with_namespace` を参照）。
 pub fn build_provenance_graph(
     content_bytes: &[u8],
     mime_type: &str,
+    namespace: &str,
     max_graph_size: usize,
+    max_manifest_store_bytes: u64,
 ) -> Result<ProvenanceGraph, CoreError> {
-    // Readerでコンテンツを読み込む
-    let reader = c2pa::Reader::from_stream(mime_type, Cursor::new(content_bytes))
+    check_manifest_store_size(&mut Cursor::new(content_bytes), mime_type, max_manifest_store_bytes)?;
+
+    // Readerでコンテンツを読み込む（固定設定を使用）
+    let context = settings::verification_context()?;
+    let reader = read_c2pa(&context, content_bytes, mime_type)
         .map_err(|e| CoreError::GraphBuildFailed(format!("C2PAデータ読み込みエラー: {e}")))?;
 
+    // JUMBFデータを読み込む
+    let jumbf_data = c2pa::jumbf_io::load_jumbf_from_memory(mime_type, content_bytes)
+        .map_err(|e| CoreError::GraphBuildFailed(format!("JUMBF抽出エラー: {e}")))?;
+
+    build_provenance_graph_from_parsed(&ParsedC2pa { reader, jumbf_data }, namespace, max_graph_size)
+}
+
+/// 検証時に解析済みのC2PAデータから来歴グラフを構築する。
+/// 仕様書 §2.2 来歴グラフの導出
+///
+/// グラフの構築規則・上限は [`build_provenance_graph`] と同じ。Reader・JUMBFを再解析しない
+/// （マニフェストストアのサイズは検証時に確認済み）。
+pub fn build_provenance_graph_from_parsed(
+    parsed: &ParsedC2pa,
+    namespace: &str,
+    max_graph_size: usize,
+) -> Result<ProvenanceGraph, CoreError> {
+    let reader = &parsed.reader;
+    let jumbf_data = &parsed.jumbf_data;
+
     let active_label = reader
         .active_label()
         .ok_or_else(|| {
@@ -264,13 +944,9 @@ pub fn build_provenance_graph(
         CoreError::GraphBuildFailed("Active Manifestが見つかりません".to_string())
     })?;
 
-    // JUMBFデータを読み込む
-    let jumbf_data = c2pa::jumbf_io::load_jumbf_from_memory(mime_type, content_bytes)
-        .map_err(|e| CoreError::GraphBuildFailed(format!("JUMBF抽出エラー: {e}")))?;
-
     // ルートノードのcontent_hashを算出
-    let root_sig = jumbf::extract_signature_from_jumbf(&jumbf_data, &active_label)?;
-    let root_hash = title_crypto::content_hash_from_manifest_signature(&root_sig);
+    let root_sig = jumbf::extract_signature_from_jumbf(jumbf_data, &active_label)?;
+    let root_hash = title_crypto::content_hash_with_namespace(namespace, &root_sig);
     let root_hash_str = format_content_hash(&root_hash);
 
     let mut nodes = Vec::new();
@@ -280,20 +956,50 @@ pub fn build_provenance_graph(
     nodes.push(GraphNode {
         id: root_hash_str.clone(),
         node_type: "final".to_string(),
+        claim_generators: claim_generators(manifest),
+        manifest_unresolved: false,
     });
 
+    check_graph_size(&nodes, &links, max_graph_size)?;
+
     // ingredientsを再帰的に処理する（深度0から開始）
     process_ingredients(
-        &reader,
+        &|label| reader.get_manifest(label),
         manifest,
-        &jumbf_data,
+        jumbf_data,
+        namespace,
         &root_hash_str,
         &mut nodes,
         &mut links,
         0,
+        max_graph_size,
     )?;
 
-    // グラフサイズチェック
+    let mut graph = ProvenanceGraph { nodes, links };
+    canonicalize_provenance_graph(&mut graph);
+    Ok(graph)
+}
+
+/// 来歴グラフのノードとリンクを正規順序に並べ替える。
+/// 仕様書 §2.2
+///
+/// ノードは `id` の昇順、リンクは `(source, target, role)` の昇順に並べる。
+/// この順序はsigned_jsonの正規形の一部であり、ingredientの記載順などC2PAデータ上の
+/// 順序が異なっても、同じグラフからは同じペイロード（および署名対象バイト列）が得られる。
+pub fn canonicalize_provenance_graph(graph: &mut ProvenanceGraph) {
+    graph.nodes.sort_by(|a, b| a.id.cmp(&b.id));
+    graph.links.sort_by(|a, b| {
+        (&a.source, &a.target, &a.role).cmp(&(&b.source, &b.target, &b.role))
+    });
+}
+
+/// 構築中の来歴グラフのノード+エッジ数が上限を超えていないか確認する。
+/// 仕様書 §5.1 Step 4
+fn check_graph_size(
+    nodes: &[GraphNode],
+    links: &[GraphLink],
+    max_graph_size: usize,
+) -> Result<(), CoreError> {
     let total = nodes.len() + links.len();
     if total > max_graph_size {
         return Err(CoreError::GraphSizeExceeded {
@@ -301,8 +1007,115 @@ pub fn build_provenance_graph(
             max: max_graph_size,
         });
     }
+    Ok(())
+}
+
+/// 来歴グラフをルートから近い順に最大 `max_nodes` ノードへ切り詰める。
+/// 仕様書 §5.1 Step 4
+///
+/// ルートノード（"final"）から素材方向（リンクの target → source）へ幅優先で辿り、
+/// 深さの浅いノードから採用する。両端が採用されたリンクのみを残すため、
+/// 結果は有効な部分グラフとなる。ルートノードは `max_nodes` が0でも常に含める。
+/// 切り詰めが発生した場合は2番目の戻り値がtrueとなる。
+pub fn truncate_provenance_graph(
+    graph: ProvenanceGraph,
+    max_nodes: usize,
+) -> (ProvenanceGraph, bool) {
+    if graph.nodes.len() <= max_nodes {
+        return (graph, false);
+    }
+
+    let Some(root) = graph.nodes.iter().find(|n| n.node_type == "final") else {
+        return (graph, false);
+    };
+
+    // 幅優先探索で採用するノードIDを決定
+    let mut kept: Vec<&str> = vec![root.id.as_str()];
+    let mut cursor = 0;
+    while cursor < kept.len() && kept.len() < max_nodes {
+        let current = kept[cursor];
+        for link in graph.links.iter().filter(|l| l.target == current) {
+            if kept.len() >= max_nodes {
+                break;
+            }
+            if !kept.contains(&link.source.as_str()) {
+                kept.push(link.source.as_str());
+            }
+        }
+        cursor += 1;
+    }
+
+    let kept: std::collections::HashSet<String> = kept.into_iter().map(str::to_string).collect();
+    let nodes = graph
+        .nodes
+        .into_iter()
+        .filter(|n| kept.contains(&n.id))
+        .collect();
+    let links = graph
+        .links
+        .into_iter()
+        .filter(|l| kept.contains(&l.source) && kept.contains(&l.target))
+        .collect();
+
+    (ProvenanceGraph { nodes, links }, true)
+}
+
+/// 来歴グラフのノードのMerkle葉ハッシュ。
+/// 仕様書 §2.2
+///
+/// 葉データはJSON配列 `["node", id, type]` の正規化JSON（[`title_types::canonical_json`]）。
+pub fn graph_node_leaf(node: &GraphNode) -> [u8; 32] {
+    let data = serde_json::json!(["node", node.id, node.node_type]);
+    title_crypto::merkle_leaf_hash(&title_types::canonical_json(&data))
+}
+
+/// 来歴グラフのリンクのMerkle葉ハッシュ。
+/// 仕様書 §2.2
+///
+/// 葉データはJSON配列 `["link", source, target, role]` の正規化JSON（[`title_types::canonical_json`]）。
+pub fn graph_link_leaf(link: &GraphLink) -> [u8; 32] {
+    let data = serde_json::json!(["link", link.source, link.target, link.role]);
+    title_crypto::merkle_leaf_hash(&title_types::canonical_json(&data))
+}
+
+/// 来歴グラフのMerkle葉ハッシュを正規順序（昇順・重複なし）で返す。
+/// 仕様書 §2.2
+///
+/// ノード・リンクの並び順に依存しないよう、葉ハッシュを昇順に整列する。
+/// 包含証明はこの順序のインデックスに対して生成する。
+pub fn provenance_graph_leaves(graph: &ProvenanceGraph) -> Vec<[u8; 32]> {
+    let mut leaves: Vec<[u8; 32]> = graph
+        .nodes
+        .iter()
+        .map(graph_node_leaf)
+        .chain(graph.links.iter().map(graph_link_leaf))
+        .collect();
+    leaves.sort_unstable();
+    leaves.dedup();
+    leaves
+}
+
+/// 来歴グラフのMerkle rootを計算する。
+/// 仕様書 §2.2
+///
+/// グラフ全体はオフチェーン（signed_jsonのURI）に置き、このrootのみをオンチェーンに
+/// 記録することで、ノード・リンクの包含をコンパクトに証明できる。
+pub fn provenance_graph_merkle_root(graph: &ProvenanceGraph) -> [u8; 32] {
+    title_crypto::merkle_root(&provenance_graph_leaves(graph))
+}
 
-    Ok(ProvenanceGraph { nodes, links })
+/// Manifestの `claim_generator_info` からツール名とバージョンを抽出する。
+/// 仕様書 §2.2
+fn claim_generators(manifest: &c2pa::Manifest) -> Vec<ClaimGenerator> {
+    manifest
+        .claim_generator_info
+        .iter()
+        .flatten()
+        .map(|info| ClaimGenerator {
+            name: info.name.clone(),
+            version: info.version.clone(),
+        })
+        .collect()
 }
 
 /// ingredientのMIMEタイプをroleとして返す。
@@ -317,14 +1130,22 @@ fn ingredient_role(ingredient: &c2pa::Ingredient) -> String {
 /// C2PAマニフェストを持つingredientのみグラフに含める。
 /// マニフェストを持たない or 署名を抽出できないingredientは
 /// フォールバックIDを使わず、スキップする（安全性優先）。
-fn process_ingredients(
-    reader: &c2pa::Reader,
-    manifest: &c2pa::Manifest,
+/// Active Manifestのラベルを持つのに `get_manifest` で解決できないingredientは、
+/// 葉として追加した上で `manifest_unresolved` を付与する（不整合を黙って隠さない）。
+///
+/// 深さは [`MAX_INGREDIENT_DEPTH`] で、幅はingredientを追加するたびに
+/// `max_graph_size` で制限する（超過した時点で走査を打ち切る）。
+#[allow(clippy::too_many_arguments)]
+fn process_ingredients<'a>(
+    get_manifest: &dyn Fn(&str) -> Option<&'a c2pa::Manifest>,
+    manifest: &'a c2pa::Manifest,
     jumbf_data: &[u8],
+    namespace: &str,
     parent_hash_str: &str,
     nodes: &mut Vec<GraphNode>,
     links: &mut Vec<GraphLink>,
     depth: usize,
+    max_graph_size: usize,
 ) -> Result<(), CoreError> {
     if depth > MAX_INGREDIENT_DEPTH {
         return Err(CoreError::GraphBuildFailed(format!(
@@ -350,14 +1171,20 @@ fn process_ingredients(
             Err(_) => continue,
         };
 
-        let hash = title_crypto::content_hash_from_manifest_signature(&sig);
+        let hash = title_crypto::content_hash_with_namespace(namespace, &sig);
         let hash_str = format_content_hash(&hash);
 
+        // ingredient自身のManifest（再帰処理と生成ツールの取得に使用）。
+        // ラベルがあるのにストアから解決できない場合は、黙って葉とせず不整合として印を付ける
+        let nested_manifest = get_manifest(ingredient_label);
+
         // 重複ノードを防ぐ
         if !nodes.iter().any(|n| n.id == hash_str) {
             nodes.push(GraphNode {
                 id: hash_str.clone(),
                 node_type: "ingredient".to_string(),
+                claim_generators: nested_manifest.map(claim_generators).unwrap_or_default(),
+                manifest_unresolved: nested_manifest.is_none(),
             });
         }
 
@@ -366,17 +1193,20 @@ fn process_ingredients(
             target: parent_hash_str.to_string(),
             role,
         });
+        check_graph_size(nodes, links, max_graph_size)?;
 
         // ingredientのマニフェストが存在する場合、再帰的に処理
-        if let Some(nested_manifest) = reader.get_manifest(ingredient_label) {
+        if let Some(nested_manifest) = nested_manifest {
             process_ingredients(
-                reader,
+                get_manifest,
                 nested_manifest,
                 jumbf_data,
+                namespace,
                 &hash_str,
                 nodes,
                 links,
                 depth + 1,
+                max_graph_size,
             )?;
         }
     }
@@ -391,6 +1221,18 @@ mod tests {
     const CERTS: &[u8] = include_bytes!("../../../tests/fixtures/certs/chain.pem");
     const PRIVATE_KEY: &[u8] = include_bytes!("../../../tests/fixtures/certs/ee.key");
     const TEST_IMAGE: &[u8] = include_bytes!("../../../tests/fixtures/test.jpg");
+    /// 期限切れの署名者証明書（`certs/expired_chain.pem`）で署名済みのJPEG
+    const EXPIRED_SIGNER_IMAGE: &[u8] = include_bytes!("../../../tests/fixtures/expired_signer.jpg");
+    /// 自己署名の署名者証明書（issuer == subject、鍵は `ee.key`）
+    const SELF_SIGNED_CERT: &[u8] = include_bytes!("../../../tests/fixtures/certs/self_signed.pem");
+    /// VP8X（拡張フォーマット）のWEBP
+    const TEST_WEBP: &[u8] = include_bytes!("../../../tests/fixtures/test.webp");
+    /// 無音のWAV（8kHz・モノラル・16bit）
+    const TEST_WAV: &[u8] = include_bytes!("../../../tests/fixtures/test.wav");
+    /// 無音のMP3（MPEG-1 Layer III、4フレーム）
+    const TEST_MP3: &[u8] = include_bytes!("../../../tests/fixtures/test.mp3");
+    /// 最小構成のMP4（ftyp + moov + mdat）
+    const TEST_MP4: &[u8] = include_bytes!("../../../tests/fixtures/test.mp4");
 
     /// テスト用のsignerを作成する
     fn test_signer() -> Box<dyn c2pa::Signer> {
@@ -400,12 +1242,17 @@ mod tests {
 
     /// テスト用のC2PA署名済みコンテンツを作成する
     fn create_signed_content(title: &str) -> Vec<u8> {
+        create_signed_content_as(title, TEST_IMAGE, "image/jpeg")
+    }
+
+    /// 指定フォーマットのコンテンツにC2PA署名を付与する
+    fn create_signed_content_as(title: &str, source: &[u8], mime_type: &str) -> Vec<u8> {
         use c2pa::Builder;
         use serde_json::json;
 
         let manifest_json = json!({
             "title": title,
-            "format": "image/jpeg",
+            "format": mime_type,
             "claim_generator_info": [{
                 "name": "title-core-test",
                 "version": "0.1.0"
@@ -416,10 +1263,10 @@ mod tests {
         let mut builder = Builder::from_json(&manifest_json).unwrap();
         let signer = test_signer();
 
-        let mut source = Cursor::new(TEST_IMAGE);
+        let mut source = Cursor::new(source);
         let mut dest = Cursor::new(Vec::new());
         builder
-            .sign(signer.as_ref(), "image/jpeg", &mut source, &mut dest)
+            .sign(signer.as_ref(), mime_type, &mut source, &mut dest)
             .unwrap();
         dest.into_inner()
     }
@@ -428,6 +1275,16 @@ mod tests {
     fn create_signed_content_with_ingredient(
         title: &str,
         ingredient_bytes: &[u8],
+    ) -> Vec<u8> {
+        create_signed_content_with_ingredient_by(title, ingredient_bytes, "title-core-test", "0.1.0")
+    }
+
+    /// 生成ツール（claim_generator_info）を指定してingredient付きコンテンツを作成する
+    fn create_signed_content_with_ingredient_by(
+        title: &str,
+        ingredient_bytes: &[u8],
+        generator_name: &str,
+        generator_version: &str,
     ) -> Vec<u8> {
         use c2pa::Builder;
         use serde_json::json;
@@ -436,8 +1293,8 @@ mod tests {
             "title": title,
             "format": "image/jpeg",
             "claim_generator_info": [{
-                "name": "title-core-test",
-                "version": "0.1.0"
+                "name": generator_name,
+                "version": generator_version
             }]
         })
         .to_string();
@@ -471,7 +1328,7 @@ mod tests {
     #[test]
     fn test_verify_c2pa_valid() {
         let signed = create_signed_content("test-valid.jpg");
-        let result = verify_c2pa(&signed, "image/jpeg").unwrap();
+        let result = verify_c2pa(&signed, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
 
         // 自己署名証明書なのでTrustedではないが、構造的に有効
         assert!(!result.active_manifest_signature.is_empty());
@@ -481,20 +1338,165 @@ mod tests {
     }
 
     #[test]
-    fn test_verify_c2pa_no_c2pa() {
-        // C2PAデータなしの生画像
-        let result = verify_c2pa(TEST_IMAGE, "image/jpeg");
-        assert!(result.is_err());
-        match result {
-            Err(CoreError::C2paVerificationFailed(_)) => {} // 期待通り
-            other => panic!("予期しない結果: {other:?}"),
-        }
+    fn test_verify_c2pa_reports_signer_cert_validity() {
+        let signed = create_signed_content("test-validity.jpg");
+        let validity = verify_c2pa(&signed, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES)
+            .unwrap()
+            .signer_cert_validity
+            .expect("署名者証明書の有効期間が抽出されるべき");
+        // テスト用証明書: 2026-02-19 〜 2036-02-17
+        assert_eq!(validity.not_before, 1771512240);
+        assert_eq!(validity.not_after, 2086872240);
+        assert_eq!(validity.expiry_warning(validity.not_before, 30 * 86400), None);
     }
 
     #[test]
-    fn test_extract_content_hash() {
-        let signed = create_signed_content("test-hash.jpg");
-        let hash = extract_content_hash(&signed, "image/jpeg").unwrap();
+    fn test_verify_c2pa_expired_signer_cert_warns_without_failing() {
+        // 有効期間が 2024-01-01 〜 2025-01-01 の証明書で署名されたフィクスチャ
+        let result = verify_c2pa(EXPIRED_SIGNER_IMAGE, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        let validity = result.signer_cert_validity.unwrap();
+        assert_eq!(validity.not_before, 1704067200); // 2024-01-01T00:00:00Z
+        assert_eq!(validity.not_after, 1735689600); // 2025-01-01T00:00:00Z
+
+        let now = 1767225600; // 2026-01-01T00:00:00Z
+        assert_eq!(
+            validity.expiry_warning(now, 30 * 86400),
+            Some(signer_cert::CertExpiryWarning::Expired)
+        );
+        // 期限の10日前は期限間近
+        assert_eq!(
+            validity.expiry_warning(validity.not_after - 10 * 86400, 30 * 86400),
+            Some(signer_cert::CertExpiryWarning::ExpiringSoon)
+        );
+    }
+
+    /// 自己署名の署名者証明書を区別して報告することを確認
+    #[test]
+    fn test_verify_c2pa_classifies_self_signed_signer() {
+        // CAが発行した証明書（chain.pem）は自己署名ではない
+        let signed = create_signed_content("test-ca-issued.jpg");
+        let result = verify_c2pa(&signed, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert!(!result.signer_self_signed);
+
+        let signer =
+            c2pa::create_signer::from_keys(SELF_SIGNED_CERT, PRIVATE_KEY, c2pa::SigningAlg::Ed25519, None)
+                .unwrap();
+        let manifest_json = serde_json::json!({
+            "title": "test-self-signed.jpg",
+            "format": "image/jpeg",
+            "claim_generator_info": [{"name": "title-core-test", "version": "0.1.0"}]
+        })
+        .to_string();
+        let mut dest = Cursor::new(Vec::new());
+        c2pa::Builder::from_json(&manifest_json)
+            .unwrap()
+            .sign(signer.as_ref(), "image/jpeg", &mut Cursor::new(TEST_IMAGE), &mut dest)
+            .unwrap();
+        let self_signed = dest.into_inner();
+
+        let result =
+            verify_c2pa(&self_signed, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert!(result.signer_self_signed);
+        assert!(result.signer_cert_validity.is_some());
+        // 自己署名の署名者は信頼リストに連ならない
+        assert!(result.validation_state < C2paValidationState::Trusted);
+    }
+
+    #[test]
+    fn test_c2pa_validation_state_parse_and_order() {
+        assert_eq!(C2paValidationState::parse("trusted"), Ok(C2paValidationState::Trusted));
+        assert_eq!(C2paValidationState::parse(" Valid "), Ok(C2paValidationState::Valid));
+        assert_eq!(C2paValidationState::parse("invalid"), Ok(C2paValidationState::Invalid));
+        assert!(C2paValidationState::parse("strict").is_err());
+        assert_eq!(C2paValidationState::default(), C2paValidationState::Invalid);
+        assert!(C2paValidationState::Invalid < C2paValidationState::Valid);
+        assert!(C2paValidationState::Valid < C2paValidationState::Trusted);
+    }
+
+    #[test]
+    fn test_verify_c2pa_reports_manifest_location() {
+        let signed = create_signed_content("test-location.jpg");
+        let result = verify_c2pa(&signed, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        let location = result.manifest_location;
+
+        // 小さなManifestは単一のAPP11セグメントに連続して格納される
+        let store_offset = location.store_offset.expect("ストアの格納位置が判明するべき") as usize;
+        let store = &signed[store_offset..];
+
+        // manifest_offsetはActive Manifestのsuperbox（jumb）を指す
+        let manifest_start = location.manifest_offset as usize;
+        assert_eq!(&store[manifest_start + 4..manifest_start + 8], b"jumb");
+        let manifest_size =
+            u32::from_be_bytes(store[manifest_start..manifest_start + 4].try_into().unwrap());
+        assert_eq!(manifest_size as u64, location.manifest_length);
+
+        // 署名の位置からファイルを読み直すと同一の署名が得られる
+        let sig_start = store_offset + location.signature_offset as usize;
+        let sig_end = sig_start + location.signature_length as usize;
+        assert_eq!(&signed[sig_start..sig_end], result.active_manifest_signature.as_slice());
+        assert!(location.signature_offset > location.manifest_offset);
+        assert!(
+            location.signature_offset + location.signature_length
+                <= location.manifest_offset + location.manifest_length
+        );
+    }
+
+    #[test]
+    fn test_verify_c2pa_no_c2pa() {
+        // C2PAデータなしの生画像
+        let result = verify_c2pa(TEST_IMAGE, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES);
+        assert!(result.is_err());
+        match result {
+            Err(CoreError::C2paVerificationFailed(_)) => {} // 期待通り
+            other => panic!("予期しない結果: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_verify_c2pa_swapped_content_is_hard_binding_mismatch() {
+        // 署名済みコンテンツの画像データ部分を改変する（Manifestはそのまま）
+        let mut tampered = create_signed_content("test-swapped.jpg");
+        // EOIマーカー(FFD9)直前のスキャンデータを書き換える
+        let idx = tampered.len() - 16;
+        tampered[idx] ^= 0xFF;
+
+        match verify_c2pa(&tampered, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES) {
+            Err(CoreError::HardBindingMismatch(codes)) => {
+                assert!(
+                    codes.contains(validation_codes::ASSERTION_DATAHASH_MISMATCH),
+                    "dataHash不一致コードが含まれるべき: {codes}"
+                );
+            }
+            other => panic!("HardBindingMismatchが期待される: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_pinned_settings_enable_hard_binding_check() {
+        let mut tampered = create_signed_content("test-settings.jpg");
+        let idx = tampered.len() - 16;
+        tampered[idx] ^= 0xFF;
+
+        // 固定設定（verify_after_reading=true）ではハードバインディング不一致が検出される
+        let pinned = settings::verification_context().unwrap();
+        let reader = read_c2pa(&pinned, &tampered, "image/jpeg").unwrap();
+        assert!(!hard_binding_mismatches(&reader).is_empty());
+
+        // verify_after_readingを無効化すると検出されない（設定が検証挙動を決定していることの確認）
+        let mut unverified = pinned.settings().clone();
+        unverified.verify.verify_after_reading = false;
+        let context = c2pa::Context::new()
+            .with_settings(unverified)
+            .unwrap()
+            .into_shared();
+        let reader = read_c2pa(&context, &tampered, "image/jpeg").unwrap();
+        assert!(hard_binding_mismatches(&reader).is_empty());
+    }
+
+    #[test]
+    fn test_extract_content_hash() {
+        let signed = create_signed_content("test-hash.jpg");
+        let hash = extract_content_hash(&signed, "image/jpeg").unwrap();
 
         // ハッシュは32バイト
         assert_eq!(hash.len(), 32);
@@ -506,6 +1508,73 @@ mod tests {
         assert_eq!(hash, hash2);
     }
 
+    /// 異なる署名済みコンテンツは異なるcontent_hashを持ち、署名の1バイトの違いもcontent_hashに反映される
+    /// 仕様書 §2.1
+    #[test]
+    fn test_extract_content_hash_distinguishes_inputs() {
+        let signed_a = create_signed_content("title-a.jpg");
+        let signed_b = create_signed_content("title-b.jpg");
+        let signed_webp = create_signed_content_as("title-a.jpg", TEST_WEBP, "image/webp");
+
+        let hash_a = extract_content_hash(&signed_a, "image/jpeg").unwrap();
+        let hash_b = extract_content_hash(&signed_b, "image/jpeg").unwrap();
+        let hash_webp = extract_content_hash(&signed_webp, "image/webp").unwrap();
+        assert_ne!(hash_a, hash_b, "タイトルが異なればcontent_hashも異なるべき");
+        assert_ne!(hash_a, hash_webp, "コンテンツが異なればcontent_hashも異なるべき");
+        assert_ne!(hash_b, hash_webp);
+
+        // content_hashはActive Manifestの署名そのものから導出される
+        let signature = verify_c2pa(&signed_a, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES)
+            .unwrap()
+            .active_manifest_signature;
+        assert_eq!(hash_a, title_crypto::content_hash_from_manifest_signature(&signature));
+
+        // 署名の1バイトを変えるとcontent_hashも変わる
+        let mut flipped = signature.clone();
+        *flipped.last_mut().unwrap() ^= 0x01;
+        assert_ne!(hash_a, title_crypto::content_hash_from_manifest_signature(&flipped));
+
+        // ファイル内の署名を1バイト改変したコンテンツが、元と同じcontent_hashで受理されることはない
+        let offset = signed_a
+            .windows(signature.len())
+            .position(|w| w == signature.as_slice())
+            .expect("署名がファイル内に見つかるべき");
+        let mut tampered = signed_a.clone();
+        tampered[offset + signature.len() - 1] ^= 0x01;
+        if let Ok(hash) = extract_content_hash(&tampered, "image/jpeg") {
+            assert_ne!(hash, hash_a);
+        }
+    }
+
+    /// ファイルから読み込むストリーム検証が、スライス版と同じ結果を返すことを確認
+    #[test]
+    fn test_verify_c2pa_stream_from_file_matches_slice() {
+        let signed = create_signed_content("test-stream.jpg");
+        let path = std::env::temp_dir().join(format!("title-core-stream-{}.jpg", std::process::id()));
+        std::fs::write(&path, &signed).unwrap();
+
+        let file = std::fs::File::open(&path).unwrap();
+        let streamed = verify_c2pa_stream(file, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES);
+        let _ = std::fs::remove_file(&path);
+        let streamed = streamed.unwrap();
+        let sliced = verify_c2pa(&signed, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+
+        assert_eq!(streamed.active_manifest_signature, sliced.active_manifest_signature);
+        assert_eq!(
+            title_crypto::content_hash_from_manifest_signature(&streamed.active_manifest_signature),
+            extract_content_hash(&signed, "image/jpeg").unwrap()
+        );
+        assert_eq!(streamed.manifest_location, sliced.manifest_location);
+        assert!(streamed.manifest_location.store_offset.is_some());
+        assert_eq!(streamed.is_valid, sliced.is_valid);
+
+        // ストアの上限もストリームから判定する
+        match verify_c2pa_stream(Cursor::new(&signed), "image/jpeg", &[], 1) {
+            Err(CoreError::ManifestStoreTooLarge { max: 1, .. }) => {}
+            other => panic!("ManifestStoreTooLargeが期待される: {other:?}"),
+        }
+    }
+
     #[test]
     fn test_extract_content_hash_no_c2pa() {
         let result = extract_content_hash(TEST_IMAGE, "image/jpeg");
@@ -515,7 +1584,7 @@ mod tests {
     #[test]
     fn test_build_provenance_graph_simple() {
         let signed = create_signed_content("test-graph.jpg");
-        let graph = build_provenance_graph(&signed, "image/jpeg", 1000).unwrap();
+        let graph = build_provenance_graph(&signed, "image/jpeg", "", 1000, DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
 
         // ルートノードのみ（ingredientなし）
         assert_eq!(graph.nodes.len(), 1);
@@ -524,6 +1593,52 @@ mod tests {
         assert_eq!(graph.links.len(), 0);
     }
 
+    /// 検証時の解析結果から構築したグラフが、再解析して構築したグラフと一致することを確認
+    #[test]
+    fn test_build_provenance_graph_from_parsed_matches() {
+        let ingredient = create_signed_content("parsed-ingredient.jpg");
+        let final_content = create_signed_content_with_ingredient("parsed-final.jpg", &ingredient);
+
+        let (result, parsed) =
+            verify_c2pa_parsed(&final_content, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES)
+                .unwrap();
+        let from_parsed = build_provenance_graph_from_parsed(&parsed, "", 1000).unwrap();
+        let reparsed =
+            build_provenance_graph(&final_content, "image/jpeg", "", 1000, DEFAULT_MAX_MANIFEST_STORE_BYTES)
+                .unwrap();
+
+        assert_eq!(from_parsed.nodes, reparsed.nodes);
+        assert_eq!(from_parsed.links, reparsed.links);
+        // ノードはid順に並ぶため、種別でルートノードを探す
+        let root = from_parsed.nodes.iter().find(|n| n.node_type == "final").unwrap();
+        assert_eq!(
+            root.id,
+            format_content_hash(&title_crypto::content_hash_from_manifest_signature(
+                &result.active_manifest_signature
+            ))
+        );
+    }
+
+    #[test]
+    fn test_build_provenance_graph_uses_namespace() {
+        let signed = create_signed_content("test-graph-ns.jpg");
+        let signature = verify_c2pa(&signed, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES)
+            .unwrap()
+            .active_manifest_signature;
+
+        let graph =
+            build_provenance_graph(&signed, "image/jpeg", "private", 1000, DEFAULT_MAX_MANIFEST_STORE_BYTES)
+                .unwrap();
+        assert_eq!(
+            graph.nodes[0].id,
+            format_content_hash(&title_crypto::content_hash_with_namespace("private", &signature))
+        );
+        assert_ne!(
+            graph.nodes[0].id,
+            format_content_hash(&title_crypto::content_hash_from_manifest_signature(&signature))
+        );
+    }
+
     #[test]
     fn test_build_provenance_graph_with_ingredient() {
         // まずingredient用のC2PA付きコンテンツを作成
@@ -533,7 +1648,7 @@ mod tests {
             create_signed_content_with_ingredient("final.jpg", &ingredient);
 
         let graph =
-            build_provenance_graph(&final_content, "image/jpeg", 1000).unwrap();
+            build_provenance_graph(&final_content, "image/jpeg", "", 1000, DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
 
         // ルートノード + ingredientノード
         assert!(graph.nodes.len() >= 2);
@@ -548,11 +1663,250 @@ mod tests {
         assert!(graph.links.iter().any(|l| l.target == root.id));
     }
 
+    /// ingredientのActive Manifestラベルが解決できない不整合は、葉ノードに印を付けて報告されることを確認
+    #[test]
+    fn test_process_ingredients_flags_unresolved_nested_manifest() {
+        let ingredient = create_signed_content("ingredient.jpg");
+        let final_content = create_signed_content_with_ingredient("final.jpg", &ingredient);
+
+        let context = settings::verification_context().unwrap();
+        let reader = read_c2pa(&context, &final_content, "image/jpeg").unwrap();
+        let manifest = reader.active_manifest().unwrap();
+        let jumbf_data =
+            c2pa::jumbf_io::load_jumbf_from_memory("image/jpeg", &final_content).unwrap();
+
+        fn build<'a>(
+            get_manifest: &dyn Fn(&str) -> Option<&'a c2pa::Manifest>,
+            manifest: &'a c2pa::Manifest,
+            jumbf_data: &[u8],
+        ) -> Vec<GraphNode> {
+            let mut nodes = Vec::new();
+            let mut links = Vec::new();
+            process_ingredients(get_manifest, manifest, jumbf_data, "", "root", &mut nodes, &mut links, 0, 1000)
+                .unwrap();
+            nodes
+        }
+
+        // 通常はストアから解決でき、印は付かない
+        let resolved = build(&|label| reader.get_manifest(label), manifest, &jumbf_data);
+        assert_eq!(resolved.len(), 1);
+        assert!(!resolved[0].manifest_unresolved);
+
+        // ラベルがあるのにManifestを解決できない場合は、葉として印を付ける
+        let unresolved = build(&|_| None, manifest, &jumbf_data);
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].id, resolved[0].id);
+        assert!(unresolved[0].manifest_unresolved);
+        let json = serde_json::to_value(&unresolved[0]).unwrap();
+        assert_eq!(json["manifest_unresolved"], true);
+        assert!(serde_json::to_value(&resolved[0]).unwrap().get("manifest_unresolved").is_none());
+    }
+
+    /// 各ノードに、そのノード自身のManifestの生成ツールが付与されることを確認
+    #[test]
+    fn test_build_provenance_graph_records_claim_generators() {
+        // 3段の来歴: raw（title-core-test） → edited（editor 2.1） → final（exporter 5.0）
+        let raw = create_signed_content("raw.jpg");
+        let edited = create_signed_content_with_ingredient_by("edited.jpg", &raw, "editor", "2.1");
+        let final_content =
+            create_signed_content_with_ingredient_by("final.jpg", &edited, "exporter", "5.0");
+
+        let graph = build_provenance_graph(
+            &final_content,
+            "image/jpeg",
+            "",
+            1000,
+            DEFAULT_MAX_MANIFEST_STORE_BYTES,
+        )
+        .unwrap();
+        assert_eq!(graph.nodes.len(), 3);
+
+        let generator_of = |name: &str| ClaimGenerator {
+            name: name.to_string(),
+            version: Some(match name {
+                "exporter" => "5.0",
+                "editor" => "2.1",
+                _ => "0.1.0",
+            }
+            .to_string()),
+        };
+        let root = graph.nodes.iter().find(|n| n.node_type == "final").unwrap();
+        assert_eq!(root.claim_generators, vec![generator_of("exporter")]);
+
+        // リンクを素材方向に辿り、各段のノードが自身の生成ツールを持つことを確認
+        let source_of = |target: &str| {
+            let link = graph.links.iter().find(|l| l.target == target).unwrap();
+            graph.nodes.iter().find(|n| n.id == link.source).unwrap()
+        };
+        let edited_node = source_of(&root.id);
+        assert_eq!(edited_node.claim_generators, vec![generator_of("editor")]);
+        let raw_node = source_of(&edited_node.id);
+        assert_eq!(raw_node.claim_generators, vec![generator_of("title-core-test")]);
+
+        // 生成ツールは来歴グラフのMerkle葉に含まれない
+        let stripped = ProvenanceGraph {
+            nodes: graph
+                .nodes
+                .iter()
+                .map(|n| GraphNode {
+                    claim_generators: Vec::new(),
+                    ..n.clone()
+                })
+                .collect(),
+            links: graph.links.clone(),
+        };
+        assert_eq!(
+            provenance_graph_merkle_root(&graph),
+            provenance_graph_merkle_root(&stripped)
+        );
+    }
+
+    #[test]
+    fn test_webp_vp8x_content_hash_and_graph() {
+        // 署名前のVP8X WEBPにはC2PAチャンクがない
+        assert!(extract_content_hash(TEST_WEBP, "image/webp").is_err());
+
+        let signed = create_signed_content_as("test.webp", TEST_WEBP, "image/webp");
+        assert_eq!(&signed[12..16], b"VP8X");
+
+        let result = verify_c2pa(&signed, "image/webp", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert_eq!(result.content_type, "image/webp");
+
+        // content_hashはActive Manifestの署名から得られる
+        let hash = extract_content_hash(&signed, "image/webp").unwrap();
+        assert_eq!(
+            hash,
+            title_crypto::content_hash_from_manifest_signature(&result.active_manifest_signature)
+        );
+
+        let graph = build_provenance_graph(&signed, "image/webp", "", 1000, DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!(graph.nodes[0].node_type, "final");
+        assert_eq!(graph.nodes[0].id, format_content_hash(&hash));
+    }
+
+    /// 画像・音声・動画のいずれでも、Active Manifestの署名から同じ手順でcontent_hashが得られることを確認
+    #[test]
+    fn test_content_hash_across_media_types() {
+        let cases: [(&str, &[u8], &str); 5] = [
+            ("image.jpg", TEST_IMAGE, "image/jpeg"),
+            ("image.webp", TEST_WEBP, "image/webp"),
+            ("audio.wav", TEST_WAV, "audio/wav"),
+            ("audio.mp3", TEST_MP3, "audio/mpeg"),
+            ("video.mp4", TEST_MP4, "video/mp4"),
+        ];
+
+        let mut hashes = Vec::new();
+        for (title, source, mime_type) in cases {
+            // 署名前のコンテンツにはManifestがない
+            assert!(extract_content_hash(source, mime_type).is_err(), "{mime_type}");
+
+            let signed = create_signed_content_as(title, source, mime_type);
+            if mime_type == "audio/mpeg" {
+                // MP3のManifestはID3タグに格納される
+                assert!(signed.starts_with(b"ID3"));
+            }
+            let result = verify_c2pa(&signed, mime_type, &[], DEFAULT_MAX_MANIFEST_STORE_BYTES)
+                .unwrap_or_else(|e| panic!("{mime_type}: {e}"));
+            assert_eq!(result.content_type, mime_type);
+
+            // 格納位置からも同じ署名を再抽出できる
+            let location = &result.manifest_location;
+            let store_offset = location.store_offset.expect("ストアの位置") as usize;
+            let start = store_offset + location.signature_offset as usize;
+            assert_eq!(
+                &signed[start..start + location.signature_length as usize],
+                result.active_manifest_signature.as_slice(),
+                "{mime_type}"
+            );
+
+            let hash = extract_content_hash(&signed, mime_type).unwrap();
+            assert_eq!(hash.len(), 32);
+            assert_eq!(
+                hash,
+                title_crypto::content_hash_from_manifest_signature(&result.active_manifest_signature),
+                "{mime_type}"
+            );
+
+            let graph =
+                build_provenance_graph(&signed, mime_type, "", 1000, DEFAULT_MAX_MANIFEST_STORE_BYTES)
+                    .unwrap();
+            assert_eq!(graph.nodes[0].id, format_content_hash(&hash), "{mime_type}");
+            hashes.push(hash);
+        }
+
+        // 署名ごとに異なるcontent_hashとなる
+        for (i, a) in hashes.iter().enumerate() {
+            assert!(hashes[i + 1..].iter().all(|b| a != b));
+        }
+    }
+
+    #[test]
+    fn test_oversized_manifest_store_rejected_before_parsing() {
+        let signed = create_signed_content("test-store-limit.jpg");
+        let store_size = c2pa::jumbf_io::load_jumbf_from_memory("image/jpeg", &signed)
+            .unwrap()
+            .len() as u64;
+
+        // 上限ちょうどは許可
+        verify_c2pa(&signed, "image/jpeg", &[], store_size).unwrap();
+        build_provenance_graph(&signed, "image/jpeg", "", 1000, store_size).unwrap();
+
+        // 上限を1バイトでも超えるストアは、グラフサイズ上限に関わらず構築前に拒否する
+        match verify_c2pa(&signed, "image/jpeg", &[], store_size - 1) {
+            Err(CoreError::ManifestStoreTooLarge { size, max }) => {
+                assert_eq!(size, store_size);
+                assert_eq!(max, store_size - 1);
+            }
+            other => panic!("ManifestStoreTooLargeが期待される: {other:?}"),
+        }
+        match build_provenance_graph(&signed, "image/jpeg", "", 0, store_size - 1) {
+            Err(CoreError::ManifestStoreTooLarge { .. }) => {}
+            other => panic!("ManifestStoreTooLargeが期待される: {other:?}"),
+        }
+    }
+
+    /// ヘッダから読んだストアの宣言長が、各フォーマットで抽出したJUMBFの長さと一致することを確認
+    #[test]
+    fn test_declared_store_size_matches_extracted_jumbf() {
+        for (source, bytes, mime_type) in [
+            ("image.jpg", TEST_IMAGE, "image/jpeg"),
+            ("image.webp", TEST_WEBP, "image/webp"),
+            ("audio.wav", TEST_WAV, "audio/wav"),
+            ("audio.mp3", TEST_MP3, "audio/mpeg"),
+            ("video.mp4", TEST_MP4, "video/mp4"),
+        ] {
+            let signed = create_signed_content_as(source, bytes, mime_type);
+            let extracted = c2pa::jumbf_io::load_jumbf_from_memory(mime_type, &signed)
+                .unwrap()
+                .len() as u64;
+            match store_size::declared_store_size(&mut Cursor::new(&signed), mime_type).unwrap() {
+                store_size::DeclaredSize::Found(store) => {
+                    assert_eq!(store.size, extracted, "{mime_type}");
+                    // 格納位置からストアをそのまま読み出せる
+                    let offset = store.offset.expect("ストアの位置") as usize;
+                    assert_eq!(
+                        c2pa::jumbf_io::load_jumbf_from_memory(mime_type, &signed).unwrap(),
+                        &signed[offset..offset + extracted as usize],
+                        "{mime_type}"
+                    );
+                }
+                _ => panic!("{mime_type}: ストアが見つからない"),
+            }
+
+            // 署名前のコンテンツにはストアがない
+            assert!(matches!(
+                store_size::declared_store_size(&mut Cursor::new(bytes), mime_type).unwrap(),
+                store_size::DeclaredSize::NotFound
+            ));
+        }
+    }
+
     #[test]
     fn test_build_provenance_graph_size_exceeded() {
         let signed = create_signed_content("test-limit.jpg");
         // max_graph_size=0で必ず超過する
-        let result = build_provenance_graph(&signed, "image/jpeg", 0);
+        let result = build_provenance_graph(&signed, "image/jpeg", "", 0, DEFAULT_MAX_MANIFEST_STORE_BYTES);
         assert!(result.is_err());
         match result {
             Err(CoreError::GraphSizeExceeded { .. }) => {} // 期待通り
@@ -560,6 +1914,429 @@ mod tests {
         }
     }
 
+    /// 幅の広いグラフは、上限を超えた時点で走査を打ち切ることを確認
+    #[test]
+    fn test_build_provenance_graph_wide_manifest_terminates_early() {
+        use c2pa::Builder;
+
+        const WIDTH: usize = 12;
+        let manifest_json = serde_json::json!({
+            "title": "wide.jpg",
+            "format": "image/jpeg",
+            "claim_generator_info": [{"name": "title-core-test", "version": "0.1.0"}]
+        })
+        .to_string();
+        let mut builder = Builder::from_json(&manifest_json).unwrap();
+        for i in 0..WIDTH {
+            let ingredient = create_signed_content(&format!("ingredient-{i}.jpg"));
+            let ingredient_json = serde_json::json!({
+                "title": format!("ingredient-{i}.jpg"),
+                "relationship": "inputTo"
+            })
+            .to_string();
+            builder
+                .add_ingredient_from_stream(&ingredient_json, "image/jpeg", &mut Cursor::new(ingredient))
+                .unwrap();
+        }
+        let mut dest = Cursor::new(Vec::new());
+        builder
+            .sign(test_signer().as_ref(), "image/jpeg", &mut Cursor::new(TEST_IMAGE), &mut dest)
+            .unwrap();
+        let wide = dest.into_inner();
+
+        // 全体: ルート1 + ingredient WIDTH ノード + WIDTH リンク
+        let graph =
+            build_provenance_graph(&wide, "image/jpeg", "", 1000, DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert_eq!(graph.nodes.len() + graph.links.len(), 1 + 2 * WIDTH);
+
+        // 上限5: ルート + 2つ目のingredient（ノード3 + リンク2 = 5）の次、3つ目で打ち切る
+        match build_provenance_graph(&wide, "image/jpeg", "", 5, DEFAULT_MAX_MANIFEST_STORE_BYTES) {
+            Err(CoreError::GraphSizeExceeded { nodes_and_links, max }) => {
+                assert_eq!(max, 5);
+                assert_eq!(nodes_and_links, 7, "全体を構築せず超過時点で打ち切るべき");
+            }
+            other => panic!("GraphSizeExceededが期待される: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_verify_c2pa_lists_assertion_labels() {
+        let manifest_json = serde_json::json!({
+            "title": "assertions.jpg",
+            "format": "image/jpeg",
+            "claim_generator_info": [{"name": "title-core-test", "version": "0.1.0"}],
+            "assertions": [
+                {
+                    "label": "c2pa.training-mining",
+                    "data": {"entries": {"c2pa.ai_training": {"use": "notAllowed"}}}
+                },
+                {
+                    "label": "stds.schema-org.CreativeWork",
+                    "data": {"@context": "https://schema.org", "@type": "CreativeWork"}
+                }
+            ]
+        })
+        .to_string();
+        let mut builder = c2pa::Builder::from_json(&manifest_json).unwrap();
+        let mut dest = Cursor::new(Vec::new());
+        builder
+            .sign(test_signer().as_ref(), "image/jpeg", &mut Cursor::new(TEST_IMAGE), &mut dest)
+            .unwrap();
+
+        let signed = dest.into_inner();
+        let result = verify_c2pa(&signed, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert_eq!(
+            result.assertion_labels,
+            vec!["c2pa.training-mining", "stds.schema-org.CreativeWork"]
+        );
+        // 検証を伴わない列挙も同じ結果になる
+        assert_eq!(
+            active_assertion_labels(&signed, "image/jpeg", DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap(),
+            result.assertion_labels
+        );
+    }
+
+    #[test]
+    fn test_verify_c2pa_extracts_actions_in_order() {
+        let manifest_json = serde_json::json!({
+            "title": "actions.jpg",
+            "format": "image/jpeg",
+            "claim_generator_info": [{"name": "title-core-test", "version": "0.1.0"}],
+            "assertions": [{
+                "label": "c2pa.actions",
+                "data": {"actions": [
+                    {"action": "c2pa.created", "digitalSourceType": "http://cv.iptc.org/newscodes/digitalsourcetype/digitalCapture"},
+                    {"action": "c2pa.color_adjustments"},
+                    {"action": "c2pa.cropped"}
+                ]}
+            }]
+        })
+        .to_string();
+        let mut builder = c2pa::Builder::from_json(&manifest_json).unwrap();
+        let mut dest = Cursor::new(Vec::new());
+        builder
+            .sign(test_signer().as_ref(), "image/jpeg", &mut Cursor::new(TEST_IMAGE), &mut dest)
+            .unwrap();
+
+        let result = verify_c2pa(&dest.into_inner(), "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert_eq!(
+            result.actions,
+            vec!["c2pa.created", "c2pa.color_adjustments", "c2pa.cropped"]
+        );
+
+        // アクションを持たないManifestでは空
+        let result = verify_c2pa(&create_signed_content("no-actions.jpg"), "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert!(result.actions.is_empty());
+    }
+
+    /// `c2pa.actions` を持つC2PA署名済みJPEGを作成する。
+    fn create_signed_content_with_actions(title: &str, actions: serde_json::Value) -> Vec<u8> {
+        let manifest_json = serde_json::json!({
+            "title": title,
+            "format": "image/jpeg",
+            "claim_generator_info": [{"name": "title-core-test", "version": "0.1.0"}],
+            "assertions": [{"label": "c2pa.actions", "data": {"actions": actions}}]
+        })
+        .to_string();
+        let mut builder = c2pa::Builder::from_json(&manifest_json).unwrap();
+        let mut dest = Cursor::new(Vec::new());
+        builder
+            .sign(test_signer().as_ref(), "image/jpeg", &mut Cursor::new(TEST_IMAGE), &mut dest)
+            .unwrap();
+        dest.into_inner()
+    }
+
+    #[test]
+    fn test_verify_c2pa_classifies_ai_generated() {
+        // 生成AIで作成
+        let generated = create_signed_content_with_actions(
+            "generated.jpg",
+            serde_json::json!([{
+                "action": "c2pa.created",
+                "digitalSourceType": "http://cv.iptc.org/newscodes/digitalsourcetype/trainedAlgorithmicMedia"
+            }]),
+        );
+        let result = verify_c2pa(&generated, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert_eq!(result.ai_generated, Some(true));
+
+        // カメラで撮影し、人手で編集
+        let captured = create_signed_content_with_actions(
+            "camera.jpg",
+            serde_json::json!([
+                {"action": "c2pa.created", "digitalSourceType": "http://cv.iptc.org/newscodes/digitalsourcetype/digitalCapture"},
+                {"action": "c2pa.cropped"}
+            ]),
+        );
+        let result = verify_c2pa(&captured, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert_eq!(result.ai_generated, Some(false));
+
+        // 生成AIコンテンツを素材に含む場合は、親Manifestに記載がなくても生成AIと判定する
+        let composed = create_signed_content_with_ingredient("composed.jpg", &generated);
+        let result = verify_c2pa(&composed, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert_eq!(result.ai_generated, Some(true));
+
+        // 作成アクションがない・種別が未記載の場合は判定不能
+        let result = verify_c2pa(&create_signed_content("no-actions.jpg"), "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert_eq!(result.ai_generated, None);
+        let untyped = create_signed_content_with_actions(
+            "untyped.jpg",
+            serde_json::json!([{"action": "c2pa.created"}]),
+        );
+        let result = verify_c2pa(&untyped, "image/jpeg", &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert_eq!(result.ai_generated, None);
+    }
+
+    /// サイドカー（.c2pa）Manifestを生成する。コンテンツ本体には埋め込まない。
+    fn create_sidecar_manifest(title: &str) -> Vec<u8> {
+        let manifest_json = serde_json::json!({
+            "title": title,
+            "format": "image/jpeg",
+            "claim_generator_info": [{"name": "title-core-test", "version": "0.1.0"}]
+        })
+        .to_string();
+        let mut builder = c2pa::Builder::from_json(&manifest_json).unwrap();
+        builder.set_no_embed(true);
+        let mut dest = Cursor::new(Vec::new());
+        builder
+            .sign(test_signer().as_ref(), "image/jpeg", &mut Cursor::new(TEST_IMAGE), &mut dest)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_verify_c2pa_manifest_only_matching_hash() {
+        use sha2::Digest;
+
+        let sidecar = create_sidecar_manifest("sidecar.jpg");
+        // 埋め込みなしのManifestでは、ハードバインディングはコンテンツ本体全体のSHA-256
+        let asserted = sha2::Sha256::digest(TEST_IMAGE);
+
+        let result = verify_c2pa_manifest_only(&sidecar, &asserted, &[], DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        // 本体がないためMIMEタイプはManifestに記録がある場合のみ判明する
+        assert_eq!(result.content_type, "application/octet-stream");
+        // サイドカーはストアそのものなので位置はファイル先頭基準
+        let location = result.manifest_location;
+        assert_eq!(location.store_offset, Some(0));
+        let sig_start = location.signature_offset as usize;
+        assert_eq!(
+            &sidecar[sig_start..sig_start + location.signature_length as usize],
+            result.active_manifest_signature.as_slice()
+        );
+
+        // content_hashは埋め込み時と同じくActive Manifestの署名から得られる
+        let graph = build_provenance_graph(&sidecar, SIDECAR_MIME_TYPE, "", 1000, DEFAULT_MAX_MANIFEST_STORE_BYTES).unwrap();
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!(
+            graph.nodes[0].id,
+            format_content_hash(&title_crypto::content_hash_from_manifest_signature(
+                &result.active_manifest_signature
+            ))
+        );
+    }
+
+    #[test]
+    fn test_verify_c2pa_manifest_only_mismatching_hash() {
+        use sha2::Digest;
+
+        let sidecar = create_sidecar_manifest("sidecar.jpg");
+        let asserted = sha2::Sha256::digest(b"other content");
+
+        match verify_c2pa_manifest_only(&sidecar, &asserted, &[], DEFAULT_MAX_MANIFEST_STORE_BYTES) {
+            Err(CoreError::HardBindingMismatch(msg)) => {
+                assert!(msg.contains(&hex::encode(asserted)), "{msg}");
+            }
+            other => panic!("HardBindingMismatchが期待されます: {other:?}"),
+        }
+    }
+
+    /// 同じ署名者のManifestは、埋め込み・サイドカーのどちらで検証しても同じ検証状態と
+    /// `is_valid` になることを確認
+    #[test]
+    fn test_verify_c2pa_manifest_only_is_valid_matches_embedded() {
+        use sha2::Digest;
+
+        let embedded = verify_c2pa(
+            &create_signed_content("embedded.jpg"),
+            "image/jpeg",
+            &[],
+            DEFAULT_MAX_MANIFEST_STORE_BYTES,
+        )
+        .unwrap();
+        let sidecar = verify_c2pa_manifest_only(
+            &create_sidecar_manifest("sidecar.jpg"),
+            &sha2::Sha256::digest(TEST_IMAGE),
+            &[],
+            DEFAULT_MAX_MANIFEST_STORE_BYTES,
+        )
+        .unwrap();
+
+        assert_eq!(sidecar.validation_state, embedded.validation_state);
+        assert_eq!(sidecar.is_valid, embedded.is_valid);
+        for result in [&embedded, &sidecar] {
+            assert_eq!(result.is_valid, result.validation_state.is_valid());
+        }
+    }
+
+    #[test]
+    fn test_verify_c2pa_manifest_only_rejects_non_manifest() {
+        assert!(matches!(
+            verify_c2pa_manifest_only(TEST_IMAGE, &[0u8; 32], &[], DEFAULT_MAX_MANIFEST_STORE_BYTES),
+            Err(CoreError::C2paVerificationFailed(_))
+        ));
+    }
+
+    /// ルートから深さ `depth` の完全 `fanout` 分木の来歴グラフを生成する。
+    fn synthetic_graph(depth: usize, fanout: usize) -> ProvenanceGraph {
+        let mut nodes = vec![GraphNode {
+            id: "n0".to_string(),
+            node_type: "final".to_string(),
+            claim_generators: Vec::new(),
+            manifest_unresolved: false,
+        }];
+        let mut links = Vec::new();
+        let mut frontier = vec!["n0".to_string()];
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for parent in &frontier {
+                for _ in 0..fanout {
+                    let id = format!("n{}", nodes.len());
+                    nodes.push(GraphNode {
+                        id: id.clone(),
+                        node_type: "ingredient".to_string(),
+                        claim_generators: Vec::new(),
+                        manifest_unresolved: false,
+                    });
+                    links.push(GraphLink {
+                        source: id.clone(),
+                        target: parent.clone(),
+                        role: "image/jpeg".to_string(),
+                    });
+                    next.push(id);
+                }
+            }
+            frontier = next;
+        }
+        ProvenanceGraph { nodes, links }
+    }
+
+    #[test]
+    fn test_truncate_provenance_graph_keeps_nearest_ingredients() {
+        // 1 + 3 + 9 + 27 = 40ノード
+        let graph = synthetic_graph(3, 3);
+        assert_eq!(graph.nodes.len(), 40);
+
+        let (truncated, was_truncated) = truncate_provenance_graph(graph, 5);
+        assert!(was_truncated);
+        assert_eq!(truncated.nodes.len(), 5);
+
+        // ルートと深さ1の素材3つが優先され、残り1つは深さ2から採用される
+        let ids: Vec<&str> = truncated.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, ["n0", "n1", "n2", "n3", "n4"]);
+
+        // 全リンクの両端が採用ノードに含まれる（有効な部分グラフ）
+        assert_eq!(truncated.links.len(), 4);
+        for link in &truncated.links {
+            assert!(ids.contains(&link.source.as_str()));
+            assert!(ids.contains(&link.target.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_truncate_provenance_graph_within_limit_is_unchanged() {
+        let graph = synthetic_graph(2, 2);
+        let (result, was_truncated) = truncate_provenance_graph(graph, 7);
+        assert!(!was_truncated);
+        assert_eq!(result.nodes.len(), 7);
+        assert_eq!(result.links.len(), 6);
+    }
+
+    #[test]
+    fn test_provenance_graph_merkle_root_stable() {
+        let graph = synthetic_graph(2, 2);
+        let root = provenance_graph_merkle_root(&graph);
+        assert_eq!(root, provenance_graph_merkle_root(&synthetic_graph(2, 2)));
+
+        // ノード・リンクの並び順に依存しない
+        let mut reordered = synthetic_graph(2, 2);
+        reordered.nodes.reverse();
+        reordered.links.reverse();
+        assert_eq!(root, provenance_graph_merkle_root(&reordered));
+
+        // ノードを追加するとrootが変わる
+        let mut extended = synthetic_graph(2, 2);
+        extended.nodes.push(GraphNode {
+            id: "extra".to_string(),
+            node_type: "ingredient".to_string(),
+            claim_generators: Vec::new(),
+            manifest_unresolved: false,
+        });
+        assert_ne!(root, provenance_graph_merkle_root(&extended));
+    }
+
+    #[test]
+    fn test_canonicalize_provenance_graph_sorts_nodes_and_links() {
+        let mut graph = synthetic_graph(2, 2);
+        graph.nodes.reverse();
+        graph.links.rotate_left(3);
+        canonicalize_provenance_graph(&mut graph);
+
+        assert!(graph.nodes.windows(2).all(|w| w[0].id <= w[1].id));
+        assert!(graph.links.windows(2).all(|w| {
+            (&w[0].source, &w[0].target, &w[0].role) <= (&w[1].source, &w[1].target, &w[1].role)
+        }));
+
+        // 並び順だけが異なる同等のグラフは同じ正規形になる
+        let mut other = synthetic_graph(2, 2);
+        other.links.reverse();
+        canonicalize_provenance_graph(&mut other);
+        assert_eq!(graph.nodes, other.nodes);
+        assert_eq!(graph.links, other.links);
+    }
+
+    #[test]
+    fn test_provenance_graph_membership_proof() {
+        let graph = synthetic_graph(2, 2);
+        let root = provenance_graph_merkle_root(&graph);
+        let leaves = provenance_graph_leaves(&graph);
+
+        let leaf = graph_link_leaf(&graph.links[0]);
+        let index = leaves.iter().position(|l| *l == leaf).unwrap();
+        let proof = title_crypto::merkle_proof(&leaves, index).unwrap();
+        assert!(title_crypto::verify_merkle_proof(&leaf, &proof, &root));
+    }
+
+    #[test]
+    fn test_graph_leaves_hash_canonical_json() {
+        // 第三者の検証者が再現する葉データ（正規化JSON）のバイト列に固定する
+        let node = GraphNode {
+            id: "0xabc".to_string(),
+            node_type: "final".to_string(),
+            claim_generators: Vec::new(),
+            manifest_unresolved: false,
+        };
+        assert_eq!(
+            graph_node_leaf(&node),
+            title_crypto::merkle_leaf_hash(br#"["node","0xabc","final"]"#)
+        );
+        let link = GraphLink {
+            source: "0xa".to_string(),
+            target: "0xb".to_string(),
+            role: "素材\u{1f}".to_string(),
+        };
+        assert_eq!(
+            graph_link_leaf(&link),
+            title_crypto::merkle_leaf_hash("[\"link\",\"0xa\",\"0xb\",\"素材\\u001f\"]".as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_truncate_provenance_graph_always_keeps_root() {
+        let (result, was_truncated) = truncate_provenance_graph(synthetic_graph(1, 2), 0);
+        assert!(was_truncated);
+        assert_eq!(result.nodes.len(), 1);
+        assert_eq!(result.nodes[0].node_type, "final");
+        assert!(result.links.is_empty());
+    }
+
     // ----- 重複解決テスト -----
 
     #[test]
@@ -703,6 +2480,63 @@ mod tests {
         assert_eq!(winner.id, "with_tsa");
     }
 
+    #[test]
+    fn test_resolve_duplicate_empty_list_policies_pick_different_winners() {
+        let tokens = vec![
+            TokenRecord {
+                id: "with_tsa".into(),
+                tsa_timestamp: Some(500),
+                tsa_cert_hash: Some("any_key".into()),
+                solana_block_time: 2000,
+                is_burned: false,
+            },
+            TokenRecord {
+                id: "earlier_register".into(),
+                tsa_timestamp: None,
+                tsa_cert_hash: None,
+                solana_block_time: 1000,
+                is_burned: false,
+            },
+        ];
+
+        // 全TSAを信頼: TSA timestamp 500 < 1000 → TSA持ちが勝つ
+        let trust_all =
+            resolve_duplicate_with_policy(&tokens, &[], TsaTrustPolicy::TrustAllWhenEmpty);
+        assert_eq!(trust_all.unwrap().id, "with_tsa");
+
+        // どのTSAも信頼しない: 全トークンがblock timeで比較され、先に登録した方が勝つ
+        let trust_none =
+            resolve_duplicate_with_policy(&tokens, &[], TsaTrustPolicy::TrustNoneWhenEmpty);
+        assert_eq!(trust_none.unwrap().id, "earlier_register");
+    }
+
+    #[test]
+    fn test_resolve_duplicate_policy_ignored_when_trusted_list_set() {
+        // 非空の信頼リストでは空リストのポリシーは影響しない
+        let tokens = vec![
+            TokenRecord {
+                id: "trusted_tsa".into(),
+                tsa_timestamp: Some(500),
+                tsa_cert_hash: Some("trusted_key".into()),
+                solana_block_time: 2000,
+                is_burned: false,
+            },
+            TokenRecord {
+                id: "earlier_register".into(),
+                tsa_timestamp: None,
+                tsa_cert_hash: None,
+                solana_block_time: 1000,
+                is_burned: false,
+            },
+        ];
+        let trusted = vec!["trusted_key".to_string()];
+
+        for policy in [TsaTrustPolicy::TrustAllWhenEmpty, TsaTrustPolicy::TrustNoneWhenEmpty] {
+            let winner = resolve_duplicate_with_policy(&tokens, &trusted, policy).unwrap();
+            assert_eq!(winner.id, "trusted_tsa");
+        }
+    }
+
     #[test]
     fn test_resolve_duplicate_tsa_without_cert_hash_ignored_when_trusted_list_set() {
         // TSAタイムスタンプはあるがcert_hashがNone + 非空信頼リスト → TSA無視
@@ -745,3 +2579,4 @@ mod tests {
         assert!(s.ends_with("cd"));
     }
 }
+
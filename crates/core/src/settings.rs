@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # C2PA検証設定の固定
+//!
+//! 仕様書 §2.1
+//!
+//! c2pa-rsの既定値はライブラリのバージョンや環境変数によって変わり得るため、
+//! 全ノードで同一の検証ポリシーとなるよう、TEEが使用する設定をここで明示的に固定する。
+//! C2PA検証（`verify_c2pa`, `build_provenance_graph`）はすべて
+//! [`verification_context`] が返すContextを使用する。
+
+use std::sync::{Arc, OnceLock};
+
+use crate::CoreError;
+
+/// TEEが使用するC2PA検証設定（JSON）。
+/// 仕様書 §2.1
+///
+/// - `verify_after_reading`: 読み込み時に署名・ハードバインディングを必ず検証する
+/// - `verify_trust` / `verify_timestamp_trust`: 信頼リスト照合を行う（結果は検証状態に反映され、
+///   TSAの信頼判定はプロトコル側の `trusted_tsa_keys` で行う）
+/// - `ocsp_fetch` / `remote_manifest_fetch`: TEEから外部への暗黙の通信を行わない
+///   （外部通信はすべてプロキシ経由で明示的に行う）
+/// - `strict_v1_validation`: 最新の検証規則を使用する
+pub const C2PA_VERIFICATION_SETTINGS: &str = r#"{
+    "verify": {
+        "verify_after_reading": true,
+        "verify_after_sign": false,
+        "verify_trust": true,
+        "verify_timestamp_trust": true,
+        "ocsp_fetch": false,
+        "remote_manifest_fetch": false,
+        "skip_ingredient_conflict_resolution": false,
+        "strict_v1_validation": false
+    }
+}"#;
+
+/// 固定設定を適用したContext（初回利用時に構築）。
+static VERIFICATION_CONTEXT: OnceLock<Result<Arc<c2pa::Context>, String>> = OnceLock::new();
+
+/// 固定設定を適用したC2PA Contextを返す。
+/// 仕様書 §2.1
+pub fn verification_context() -> Result<Arc<c2pa::Context>, CoreError> {
+    VERIFICATION_CONTEXT
+        .get_or_init(|| {
+            c2pa::Context::new()
+                .with_settings(C2PA_VERIFICATION_SETTINGS)
+                .map(c2pa::Context::into_shared)
+                .map_err(|e| e.to_string())
+        })
+        .clone()
+        .map_err(|e| {
+            CoreError::C2paVerificationFailed(format!("C2PA検証設定の読み込みに失敗: {e}"))
+        })
+}
+
+/// 起動時にC2PA検証設定を読み込み、適用内容を返す。
+/// 設定が不正な場合はノードを起動させないため、TEEの起動シーケンスで呼び出す。
+pub fn init_verification_settings() -> Result<c2pa::settings::Settings, CoreError> {
+    Ok(verification_context()?.settings().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinned_settings_applied() {
+        let settings = init_verification_settings().unwrap();
+        assert!(settings.verify.verify_after_reading);
+        assert!(!settings.verify.verify_after_sign);
+        assert!(!settings.verify.ocsp_fetch);
+        assert!(!settings.verify.remote_manifest_fetch);
+        assert!(!settings.verify.strict_v1_validation);
+
+        // 同一のContextが共有される
+        let a = verification_context().unwrap();
+        let b = verification_context().unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+    }
+}
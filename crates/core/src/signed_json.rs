@@ -0,0 +1,364 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Core signed_jsonの構築
+//!
+//! 仕様書 §5.1 Step 4
+//!
+//! C2PA検証結果と来歴グラフからCorePayload・attributesを組み立て、
+//! `{payload, attributes}` の正規化JSONに署名したsigned_jsonを返す。
+//!
+//! 署名鍵・Attestation等のTEE固有の情報は引数で受け取り、HTTP・TEEランタイムに依存しない。
+//! 署名対象に検証時刻に依存する値は含めないため、同じ入力からは常に同じsigned_jsonが得られる。
+
+use std::collections::HashSet;
+
+use base58::ToBase58;
+use base64::Engine;
+use title_types::{Attribute, CorePayload, SignedJson, SignedJsonCore};
+
+use crate::{format_content_hash, C2paVerificationResult, CoreError, ProvenanceGraph};
+
+/// signed_jsonに記録するTEEの情報。
+/// 仕様書 §5.1 Step 4
+#[derive(Debug, Clone, Copy)]
+pub struct TeeMeta<'a> {
+    /// TEEの種別（例: `aws_nitro`）
+    pub tee_type: &'a str,
+    /// TEE署名用公開鍵（Ed25519、32バイト）
+    pub tee_pubkey: &'a [u8],
+    /// Attestation Document
+    pub attestation: &'a [u8],
+}
+
+/// Core signed_jsonの構築オプション。
+/// 仕様書 §2.1, §5.1 Step 4
+#[derive(Debug, Clone, Default)]
+pub struct CoreSignedJsonOptions {
+    /// 返却するノード数の上限。超える場合はルートから近い順に切り詰め、`truncated: true` を付与する
+    pub max_returned_nodes: Option<usize>,
+    /// manifest-onlyモードの結果か
+    pub manifest_only: bool,
+    /// リンクをノードの添字で参照するコンパクト表現（`compact_links`）で返すか
+    pub compact_links: bool,
+    /// 自己署名の署名者証明書に `trust_level: "self_signed"` を付与するか
+    pub report_self_signed_trust_level: bool,
+}
+
+/// Core signed_jsonを構築し、`signer` で署名する。
+/// 仕様書 §5.1 Step 4
+///
+/// `signer` には署名ドメイン（[`title_crypto::SignatureDomain::SignedJson`]）のタグを付与した
+/// 署名対象バイト列が渡され、Ed25519署名（64バイト）を返す。
+/// `content_hash` はデプロイメント名前空間（仕様書 §2.1）適用後の値を渡す。
+pub fn build_core_signed_json(
+    c2pa_result: &C2paVerificationResult,
+    content_hash: [u8; 32],
+    graph: ProvenanceGraph,
+    owner_wallet: &str,
+    signer: &dyn Fn(&[u8]) -> Vec<u8>,
+    tee_meta: TeeMeta<'_>,
+    options: CoreSignedJsonOptions,
+) -> Result<SignedJson, CoreError> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let content_hash_hex = format_content_hash(&content_hash);
+
+    // ノード・リンクを正規順序に揃えてから切り詰め・ペイロード構築を行う（仕様書 §2.2）
+    let mut graph = graph;
+    crate::canonicalize_provenance_graph(&mut graph);
+
+    let (graph, truncated) = match options.max_returned_nodes {
+        Some(max_nodes) => crate::truncate_provenance_graph(graph, max_nodes),
+        None => (graph, false),
+    };
+
+    // 返却するグラフのMerkle root（オンチェーンに記録し、ノード・リンクの包含証明に使う）
+    let graph_root_hex = format_content_hash(&crate::provenance_graph_merkle_root(&graph));
+
+    // コンパクト表現ではリンクを正規順序のノード一覧の添字で参照する（仕様書 §2.2）
+    let (links, compact_links) = if options.compact_links {
+        let compact = title_types::compact_graph_links(&graph.nodes, &graph.links)
+            .map_err(CoreError::SignedJsonBuildFailed)?;
+        (Vec::new(), compact)
+    } else {
+        (graph.links, Vec::new())
+    };
+
+    // CorePayload構築
+    let payload = CorePayload {
+        content_hash: content_hash_hex.clone(),
+        content_type: c2pa_result.content_type.clone(),
+        creator_wallet: owner_wallet.to_string(),
+        tsa_timestamp: c2pa_result.tsa_info.as_ref().map(|t| t.timestamp),
+        tsa_pubkey_hash: c2pa_result.tsa_info.as_ref().and_then(|t| t.cert_hash.clone()),
+        tsa_token_data: c2pa_result
+            .tsa_info
+            .as_ref()
+            .map(|t| b64.encode(&t.raw_token)),
+        tsa_trusted: c2pa_result.tsa_info.as_ref().map(|t| t.trusted),
+        nodes: graph.nodes,
+        links,
+        compact_links,
+        truncated,
+        manifest_only: options.manifest_only,
+        actions: c2pa_result.actions.clone(),
+        ai_generated: c2pa_result.ai_generated,
+    };
+
+    // attributes構築（cNFTオンチェーンメタデータ用）
+    let mut attributes = vec![
+        Attribute {
+            trait_type: "protocol".to_string(),
+            value: title_types::PROTOCOL_VERSION.to_string(),
+        },
+        Attribute {
+            trait_type: "content_hash".to_string(),
+            value: content_hash_hex,
+        },
+        Attribute {
+            trait_type: "content_type".to_string(),
+            value: c2pa_result.content_type.clone(),
+        },
+        Attribute {
+            trait_type: "graph_root".to_string(),
+            value: graph_root_hex,
+        },
+        Attribute {
+            trait_type: "ai_generated".to_string(),
+            value: match c2pa_result.ai_generated {
+                Some(true) => "true",
+                Some(false) => "false",
+                None => "unknown",
+            }
+            .to_string(),
+        },
+    ];
+
+    // 署名者証明書の有効期限を絶対時刻で常に付与する。cNFT属性は不変のため、
+    // "期限切れ・期限間近" のような判定時刻に依存する値は署名対象に含めない
+    // 仕様書 §2.1
+    if let Some(validity) = c2pa_result.signer_cert_validity {
+        attributes.push(Attribute {
+            trait_type: "signer_cert_not_after".to_string(),
+            value: validity.not_after_rfc3339(),
+        });
+    }
+
+    // 自己署名の署名者証明書を、他の信頼されない署名と区別して記録する
+    // 仕様書 §2.1
+    if options.report_self_signed_trust_level && c2pa_result.signer_self_signed {
+        attributes.push(Attribute {
+            trait_type: "trust_level".to_string(),
+            value: "self_signed".to_string(),
+        });
+    }
+
+    ensure_unique_trait_types(&attributes).map_err(CoreError::SignedJsonBuildFailed)?;
+
+    let payload_value = serde_json::to_value(&payload)
+        .map_err(|e| CoreError::SignedJsonBuildFailed(format!("payloadシリアライズエラー: {e}")))?;
+    let attributes_value = serde_json::to_value(&attributes)
+        .map_err(|e| CoreError::SignedJsonBuildFailed(format!("attributesシリアライズエラー: {e}")))?;
+
+    // 署名対象: payload + attributes の正規化JSON（signed_jsonドメインのタグを付与）
+    let sign_target = serde_json::json!({
+        "payload": payload_value,
+        "attributes": attributes_value,
+    });
+    let signature = signer(&title_crypto::domain_separated_message(
+        title_crypto::SignatureDomain::SignedJson,
+        &title_types::canonical_json(&sign_target),
+    ));
+
+    Ok(SignedJson {
+        core: SignedJsonCore {
+            protocol: title_types::PROTOCOL_VERSION.to_string(),
+            tee_type: tee_meta.tee_type.to_string(),
+            tee_pubkey: tee_meta.tee_pubkey.to_base58(),
+            tee_signature: b64.encode(&signature),
+            tee_attestation: b64.encode(tee_meta.attestation),
+        },
+        payload: payload_value,
+        attributes,
+    })
+}
+
+/// signed_jsonの `attributes` の `trait_type` が重複していないことを確認する。
+/// 仕様書 §5.1 Step 4-5
+///
+/// 重複した `trait_type` はMetaplex・利用者側で扱いが一貫しないため、署名前に拒否する。
+/// attributesはTEEが構築するため、重複はTEE内部の不整合（実装上の誤り）を意味する。
+pub fn ensure_unique_trait_types(attributes: &[Attribute]) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for attribute in attributes {
+        if !seen.insert(attribute.trait_type.as_str()) {
+            return Err(format!(
+                "内部エラー: attributesのtrait_typeが重複しています: {}",
+                attribute.trait_type
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signer_cert::CertValidity;
+    use crate::ManifestLocation;
+    use title_crypto::{Ed25519Signature, Ed25519SigningKey, SignatureDomain};
+    use title_types::{GraphLink, GraphNode};
+
+    fn c2pa_result() -> C2paVerificationResult {
+        C2paVerificationResult {
+            is_valid: true,
+            validation_state: crate::C2paValidationState::Valid,
+            active_manifest_signature: vec![0xAB; 64],
+            content_type: "image/jpeg".to_string(),
+            tsa_info: None,
+            signer_cert_validity: Some(CertValidity {
+                not_before: 0,
+                not_after: 1_000,
+            }),
+            signer_self_signed: true,
+            manifest_location: ManifestLocation {
+                store_offset: None,
+                manifest_offset: 0,
+                manifest_length: 0,
+                signature_offset: 0,
+                signature_length: 0,
+            },
+            assertion_labels: Vec::new(),
+            actions: vec!["c2pa.created".to_string()],
+            ai_generated: Some(false),
+        }
+    }
+
+    fn graph() -> ProvenanceGraph {
+        let node = |id: &str, node_type: &str| GraphNode {
+            id: id.to_string(),
+            node_type: node_type.to_string(),
+            claim_generators: Vec::new(),
+            manifest_unresolved: false,
+        };
+        ProvenanceGraph {
+            nodes: vec![node("0xroot", "final"), node("0xingredient", "ingredient")],
+            links: vec![GraphLink {
+                source: "0xingredient".to_string(),
+                target: "0xroot".to_string(),
+                role: "image/jpeg".to_string(),
+            }],
+        }
+    }
+
+    /// テスト用の署名鍵で構築したsigned_jsonの署名が、tee_pubkeyで検証できることを確認
+    #[test]
+    fn test_build_core_signed_json_signature_verifies() {
+        let signing_key = Ed25519SigningKey::from_bytes(&[7u8; 32]);
+        let pubkey = signing_key.verifying_key().to_bytes();
+        let signer = |message: &[u8]| title_crypto::ed25519_sign(&signing_key, message).to_bytes().to_vec();
+        let tee_meta = TeeMeta {
+            tee_type: "mock",
+            tee_pubkey: &pubkey,
+            attestation: b"attestation",
+        };
+        let content_hash = title_crypto::sha256(b"content");
+
+        let signed_json = build_core_signed_json(
+            &c2pa_result(),
+            content_hash,
+            graph(),
+            "wallet",
+            &signer,
+            tee_meta,
+            CoreSignedJsonOptions {
+                report_self_signed_trust_level: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(signed_json.core.tee_type, "mock");
+        assert_eq!(signed_json.core.tee_pubkey, pubkey.to_base58());
+        assert_eq!(signed_json.payload["content_hash"], format_content_hash(&content_hash));
+        assert_eq!(signed_json.payload["creator_wallet"], "wallet");
+        assert_eq!(signed_json.payload["nodes"].as_array().unwrap().len(), 2);
+        let traits: Vec<_> = signed_json
+            .attributes
+            .iter()
+            .map(|a| (a.trait_type.as_str(), a.value.as_str()))
+            .collect();
+        assert!(traits.contains(&("signer_cert_not_after", "1970-01-01T00:16:40Z")));
+        assert!(traits.contains(&("trust_level", "self_signed")));
+
+        // 署名対象: {payload, attributes} の正規化JSON
+        let sign_target = serde_json::json!({
+            "payload": signed_json.payload,
+            "attributes": signed_json.attributes,
+        });
+        let signature: [u8; 64] = b64_decode(&signed_json.core.tee_signature).try_into().unwrap();
+        title_crypto::ed25519_verify_in_domain(
+            &signing_key.verifying_key(),
+            SignatureDomain::SignedJson,
+            &title_types::canonical_json(&sign_target),
+            &Ed25519Signature::from_bytes(&signature),
+        )
+        .expect("tee_signatureはtee_pubkeyで検証できるべき");
+
+        // 返却ノード数を切り詰めた場合も署名対象に反映される
+        let truncated = build_core_signed_json(
+            &c2pa_result(),
+            content_hash,
+            graph(),
+            "wallet",
+            &signer,
+            tee_meta,
+            CoreSignedJsonOptions {
+                max_returned_nodes: Some(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(truncated.payload["truncated"], true);
+        assert_ne!(truncated.core.tee_signature, signed_json.core.tee_signature);
+    }
+
+    /// コンパクト表現のリンクが通常の表現と同じ論理グラフに展開されることを確認
+    #[test]
+    fn test_build_core_signed_json_compact_links_roundtrip() {
+        let build = |compact_links: bool| {
+            let signed_json = build_core_signed_json(
+                &c2pa_result(),
+                [0u8; 32],
+                graph(),
+                "wallet",
+                &|_| vec![0u8; 64],
+                TeeMeta {
+                    tee_type: "mock",
+                    tee_pubkey: &[0u8; 32],
+                    attestation: &[],
+                },
+                CoreSignedJsonOptions {
+                    compact_links,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            serde_json::from_value::<CorePayload>(signed_json.payload).unwrap()
+        };
+        let verbose = build(false);
+        let compact = build(true);
+
+        assert!(verbose.compact_links.is_empty());
+        assert!(compact.links.is_empty());
+        assert_eq!(compact.compact_links.len(), verbose.links.len());
+        assert_eq!(compact.nodes, verbose.nodes);
+        assert_eq!(compact.graph_links().unwrap(), verbose.graph_links().unwrap());
+        assert!(
+            serde_json::to_vec(&compact).unwrap().len() < serde_json::to_vec(&verbose).unwrap().len()
+        );
+    }
+
+    fn b64_decode(s: &str) -> Vec<u8> {
+        base64::engine::general_purpose::STANDARD.decode(s).unwrap()
+    }
+}
@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # 署名者証明書の有効期間・自己署名判定
+//!
+//! 仕様書 §2.1
+//!
+//! Active Manifestの署名者証明書（COSE_Sign1の `x5chain` 先頭）の有効期間を抽出し、
+//! 期限切れ・期限間近の判定を行う。
+//!
+//! 証明書が期限切れでもC2PAの構造としては有効であり、TSAタイムスタンプにより
+//! 署名時点での有効性が証明される場合もあるため、検証自体は失敗させず警告として扱う。
+//!
+//! あわせて、署名者証明書が自己署名（issuer == subject）かを判定する。自己署名の
+//! コンテンツは構造的には有効（`Valid`）だが、信頼リストに連なる（`Trusted`）ことはない。
+
+use coset::{CborSerializable, TaggedCborSerializable};
+use der::Decode;
+
+use crate::CoreError;
+
+/// COSEヘッダ `x5chain` のラベル（RFC 9360）
+const X5CHAIN_LABEL: i64 = 33;
+
+/// 期限間近とみなす既定の残り日数
+pub const DEFAULT_EXPIRY_WARNING_DAYS: u64 = 30;
+
+/// 署名者証明書の有効期間（Unix epoch秒）。
+/// 仕様書 §2.1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CertValidity {
+    /// 有効期間の開始（notBefore）
+    pub not_before: u64,
+    /// 有効期間の終了（notAfter）
+    pub not_after: u64,
+}
+
+/// 署名者証明書の有効期限に関する警告。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertExpiryWarning {
+    /// 有効期限を過ぎている
+    Expired,
+    /// 有効期限まで閾値以内
+    ExpiringSoon,
+}
+
+impl CertExpiryWarning {
+    /// レスポンス・ログに用いる文字列表現
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Expired => "expired",
+            Self::ExpiringSoon => "expiring_soon",
+        }
+    }
+}
+
+impl CertValidity {
+    /// `now` 時点での有効期限の警告を返す。
+    /// 有効期限までの残りが `threshold_secs` 以内なら [`CertExpiryWarning::ExpiringSoon`]。
+    pub fn expiry_warning(&self, now: u64, threshold_secs: u64) -> Option<CertExpiryWarning> {
+        if now >= self.not_after {
+            Some(CertExpiryWarning::Expired)
+        } else if self.not_after - now <= threshold_secs {
+            Some(CertExpiryWarning::ExpiringSoon)
+        } else {
+            None
+        }
+    }
+
+    /// 有効期間の終了（notAfter）をRFC 3339形式（UTC、例: `2025-01-01T00:00:00Z`）で返す。
+    /// 判定時刻に依存しない絶対時刻のため、不変なcNFT属性値として用いる。
+    pub fn not_after_rfc3339(&self) -> String {
+        der::DateTime::from_unix_duration(std::time::Duration::from_secs(self.not_after))
+            .map(|dt| dt.to_string())
+            .unwrap_or_else(|_| self.not_after.to_string())
+    }
+}
+
+/// COSE署名バイト列から署名者証明書（`x5chain` 先頭）の有効期間を抽出する。
+/// 仕様書 §2.1
+///
+/// `x5chain` はprotectedヘッダ、なければunprotectedヘッダから探す。
+/// ヘッダが存在しない場合は `Ok(None)` を返す。
+pub fn extract_signer_validity(cose_bytes: &[u8]) -> Result<Option<CertValidity>, CoreError> {
+    let Some(cert) = signer_certificate(cose_bytes)? else {
+        return Ok(None);
+    };
+    let validity = &cert.tbs_certificate.validity;
+    Ok(Some(CertValidity {
+        not_before: validity.not_before.to_unix_duration().as_secs(),
+        not_after: validity.not_after.to_unix_duration().as_secs(),
+    }))
+}
+
+/// COSE署名バイト列の署名者証明書が自己署名（issuer == subject）かを判定する。
+/// 仕様書 §2.1
+///
+/// `x5chain` が存在しない場合はfalseを返す。
+pub fn is_signer_self_signed(cose_bytes: &[u8]) -> Result<bool, CoreError> {
+    Ok(signer_certificate(cose_bytes)?.is_some_and(|cert| {
+        cert.tbs_certificate.issuer == cert.tbs_certificate.subject
+    }))
+}
+
+/// COSE署名バイト列から署名者証明書（`x5chain` 先頭）をパースする。
+fn signer_certificate(cose_bytes: &[u8]) -> Result<Option<x509_cert::Certificate>, CoreError> {
+    let sign1: coset::CoseSign1 = coset::CoseSign1::from_tagged_slice(cose_bytes)
+        .or_else(|_| coset::CoseSign1::from_slice(cose_bytes))
+        .map_err(|e| {
+            CoreError::C2paVerificationFailed(format!("COSE_Sign1パースエラー: {e}"))
+        })?;
+
+    let x5chain = find_x5chain(&sign1.protected.header.rest)
+        .or_else(|| find_x5chain(&sign1.unprotected.rest));
+    let leaf = match x5chain {
+        Some(ciborium::Value::Bytes(der)) => der,
+        Some(ciborium::Value::Array(certs)) => match certs.into_iter().next() {
+            Some(ciborium::Value::Bytes(der)) => der,
+            _ => {
+                return Err(CoreError::C2paVerificationFailed(
+                    "x5chainの先頭が証明書ではありません".to_string(),
+                ))
+            }
+        },
+        Some(_) => {
+            return Err(CoreError::C2paVerificationFailed(
+                "x5chainの形式が不正です".to_string(),
+            ))
+        }
+        None => return Ok(None),
+    };
+
+    x509_cert::Certificate::from_der(&leaf)
+        .map(Some)
+        .map_err(|e| CoreError::C2paVerificationFailed(format!("署名者証明書のパースに失敗: {e}")))
+}
+
+/// COSEヘッダのrestフィールドから `x5chain` を検索する。
+fn find_x5chain(rest: &[(coset::Label, ciborium::Value)]) -> Option<ciborium::Value> {
+    rest.iter().find_map(|(label, value)| match label {
+        coset::Label::Int(X5CHAIN_LABEL) => Some(value.clone()),
+        _ => None,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// テスト
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    #[test]
+    fn test_expiry_warning() {
+        let validity = CertValidity {
+            not_before: 0,
+            not_after: 100 * DAY,
+        };
+        assert_eq!(validity.expiry_warning(10 * DAY, 30 * DAY), None);
+        assert_eq!(
+            validity.expiry_warning(80 * DAY, 30 * DAY),
+            Some(CertExpiryWarning::ExpiringSoon)
+        );
+        assert_eq!(
+            validity.expiry_warning(100 * DAY, 30 * DAY),
+            Some(CertExpiryWarning::Expired)
+        );
+        // 閾値0では期限切れのみ警告する
+        assert_eq!(validity.expiry_warning(99 * DAY, 0), None);
+    }
+
+    #[test]
+    fn test_not_after_rfc3339() {
+        let validity = CertValidity {
+            not_before: 0,
+            not_after: 1_735_689_600,
+        };
+        assert_eq!(validity.not_after_rfc3339(), "2025-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_no_x5chain() {
+        let sign1 = coset::CoseSign1Builder::new()
+            .payload(vec![1, 2, 3])
+            .build();
+        let cose_bytes = sign1.to_vec().unwrap();
+        assert_eq!(extract_signer_validity(&cose_bytes).unwrap(), None);
+        assert!(!is_signer_self_signed(&cose_bytes).unwrap());
+    }
+}
@@ -0,0 +1,346 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! コンテナ内のマニフェストストア（JUMBF）の宣言サイズと格納位置の読み取り。
+//! 仕様書 §2.1
+//!
+//! ストアのサイズ上限は c2pa-rs がストアをメモリに載せる前に確認する必要がある。
+//! 本モジュールはコンテナのセグメント・チャンク・ボックスのヘッダのみを読み、
+//! ストアを格納する JUMBF superbox の LBox（宣言長）とファイル内の開始位置を返す。
+//! ストア本体は読み込まない。
+//!
+//! 入力は攻撃者が自由に作れるバイト列であるため、長さの計算はすべて検査付きで行う。
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// JUMBF superbox タイプ "jumb"
+const BOX_TYPE_JUMB: &[u8; 4] = b"jumb";
+
+/// C2PA マニフェストストアの JUMBF description UUID の先頭4バイト（"c2pa"）
+const C2PA_STORE_UUID_PREFIX: &[u8; 4] = b"c2pa";
+
+/// BMFF の C2PA uuid ボックスの UUID（d8fec3d6-1b0e-483c-9297-5828877ec481）
+const BMFF_C2PA_UUID: [u8; 16] = [
+    0xD8, 0xFE, 0xC3, 0xD6, 0x1B, 0x0E, 0x48, 0x3C, 0x92, 0x97, 0x58, 0x28, 0x87, 0x7E, 0xC4,
+    0x81,
+];
+
+/// ID3v2 GEOB フレームでマニフェストストアを示す MIME タイプ（旧名を含む）
+const ID3_C2PA_MIMES: [&[u8]; 2] = [b"application/c2pa", b"application/x-c2pa-manifest-store"];
+
+/// ID3v2 GEOB フレームのヘッダ部（文字列3つ）の読み取り上限
+const MAX_GEOB_HEADER: usize = 1024;
+
+/// ヘッダから読み取ったストアの位置と宣言長。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DeclaredStore {
+    /// ファイル内での JUMBF superbox の開始位置。
+    /// ストアがファイル内に連続して格納されている場合のみ `Some`
+    /// （複数の APP11 セグメントに分割された JPEG、圧縮・非同期化された ID3 フレーム等では `None`）。
+    pub(crate) offset: Option<u64>,
+    /// JUMBF superbox の宣言長
+    pub(crate) size: u64,
+}
+
+/// 宣言サイズの読み取り結果。
+pub(crate) enum DeclaredSize {
+    /// ストアが見つかった
+    Found(DeclaredStore),
+    /// ストアがない
+    NotFound,
+    /// ヘッダのみでは判定できないフォーマット
+    Unsupported,
+}
+
+/// コンテナ内のマニフェストストアの宣言サイズと格納位置を返す。
+///
+/// JPEG・PNG・RIFF（WebP/WAV）・BMFF（MP4）・MP3（ID3v2）に対応する。
+/// 構造が壊れている場合は `NotFound` とし、後続の c2pa-rs による読み込みでエラーとする。
+pub(crate) fn declared_store_size<R: Read + Seek>(
+    content: &mut R,
+    mime_type: &str,
+) -> io::Result<DeclaredSize> {
+    content.rewind()?;
+    let size = match mime_type {
+        "image/jpeg" => jpeg_store_size(content),
+        "image/png" => png_store_size(content),
+        "image/webp" | "audio/wav" | "audio/wave" | "audio/x-wav" => riff_store_size(content),
+        "video/mp4" | "audio/mp4" | "image/heic" | "image/heif" | "image/avif"
+        | "video/quicktime" => bmff_store_size(content),
+        "audio/mpeg" => id3_store_size(content),
+        _ => return Ok(DeclaredSize::Unsupported),
+    };
+    match size {
+        Ok(Some(store)) => Ok(DeclaredSize::Found(store)),
+        Ok(None) => Ok(DeclaredSize::NotFound),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(DeclaredSize::NotFound),
+        Err(e) => Err(e),
+    }
+}
+
+/// JPEG: APP11 セグメントの JUMBF（CI "JP"）のうち C2PA ストアの superbox を探す。
+///
+/// ストアが複数のセグメントに分割されている場合（宣言長が先頭セグメントに収まらない場合）、
+/// ファイル内で連続しないため格納位置は `None` とする。
+fn jpeg_store_size<R: Read + Seek>(r: &mut R) -> io::Result<Option<DeclaredStore>> {
+    if read_array::<2, _>(r)? != [0xFF, 0xD8] {
+        return Ok(None);
+    }
+    loop {
+        // マーカー（0xFFの詰め物を読み飛ばす）
+        if read_u8(r)? != 0xFF {
+            return Ok(None);
+        }
+        let mut marker = read_u8(r)?;
+        while marker == 0xFF {
+            marker = read_u8(r)?;
+        }
+        match marker {
+            // SOS・EOI以降にAPPセグメントはない
+            0xDA | 0xD9 => return Ok(None),
+            // 長さを持たないマーカー
+            0x01 | 0xD0..=0xD7 => continue,
+            _ => {}
+        }
+        let segment_start = r.stream_position()?;
+        let length = u64::from(u16::from_be_bytes(read_array(r)?));
+        if length < 2 {
+            return Ok(None);
+        }
+        if marker == 0xEB && length >= 2 + 2 + 2 + 4 + 8 + 8 + 16 {
+            // CI(2) En(2) Z(4) の後に JUMBF が続く。先頭パケット（Z=1）のみがボックスヘッダを持つ
+            let ci = read_array::<2, _>(r)?;
+            let _en = read_array::<2, _>(r)?;
+            let z = u32::from_be_bytes(read_array(r)?);
+            if &ci == b"JP" && z == 1 {
+                let store_start = r.stream_position()?;
+                if let Some(size) = jumbf_store_size(r)? {
+                    // セグメント長(2) + CI(2) En(2) Z(4) を除いた部分に JUMBF が入る
+                    let in_segment = length - 2 - 8;
+                    return Ok(Some(DeclaredStore {
+                        offset: (size <= in_segment).then_some(store_start),
+                        size,
+                    }));
+                }
+            }
+        }
+        r.seek(SeekFrom::Start(segment_start + length))?;
+    }
+}
+
+/// PNG: "caBX" チャンクのデータがストアそのもの。
+fn png_store_size<R: Read + Seek>(r: &mut R) -> io::Result<Option<DeclaredStore>> {
+    if &read_array::<8, _>(r)? != b"\x89PNG\r\n\x1a\n" {
+        return Ok(None);
+    }
+    loop {
+        let length = u64::from(u32::from_be_bytes(read_array(r)?));
+        let chunk_type = read_array::<4, _>(r)?;
+        match &chunk_type {
+            b"caBX" => {
+                return Ok(Some(DeclaredStore {
+                    offset: Some(r.stream_position()?),
+                    size: length,
+                }))
+            }
+            b"IEND" => return Ok(None),
+            _ => {}
+        }
+        // データ + CRC(4)
+        r.seek(SeekFrom::Current(checked_offset(length + 4)?))?;
+    }
+}
+
+/// RIFF（WebP/WAV）: トップレベルの "C2PA" チャンクのデータがストアそのもの。
+fn riff_store_size<R: Read + Seek>(r: &mut R) -> io::Result<Option<DeclaredStore>> {
+    if &read_array::<4, _>(r)? != b"RIFF" {
+        return Ok(None);
+    }
+    // RIFFサイズ(4) + フォームタイプ(4)
+    r.seek(SeekFrom::Current(8))?;
+    loop {
+        let chunk_id = read_array::<4, _>(r)?;
+        let size = u64::from(u32::from_le_bytes(read_array(r)?));
+        if &chunk_id == b"C2PA" {
+            return Ok(Some(DeclaredStore {
+                offset: Some(r.stream_position()?),
+                size,
+            }));
+        }
+        // 奇数長のチャンクは1バイトのパディングを持つ
+        r.seek(SeekFrom::Current(checked_offset(size + (size & 1))?))?;
+    }
+}
+
+/// BMFF（MP4）: トップレベルの C2PA uuid ボックス（purpose "manifest"）内の JUMBF superbox。
+fn bmff_store_size<R: Read + Seek>(r: &mut R) -> io::Result<Option<DeclaredStore>> {
+    loop {
+        let box_start = r.stream_position()?;
+        let size32 = u64::from(u32::from_be_bytes(read_array(r)?));
+        let box_type = read_array::<4, _>(r)?;
+        let size = match size32 {
+            1 => u64::from_be_bytes(read_array(r)?),
+            // 0 はファイル末尾までのボックス
+            0 => r.seek(SeekFrom::End(0))? - box_start,
+            n => n,
+        };
+        if &box_type == b"uuid" && read_array::<16, _>(r)? == BMFF_C2PA_UUID {
+            // version/flags(4) + purpose（NUL終端） + merkleオフセット(8)
+            r.seek(SeekFrom::Current(4))?;
+            let purpose = read_cstr(r, 64)?;
+            if purpose == b"manifest" {
+                r.seek(SeekFrom::Current(8))?;
+                return read_superbox(r, true);
+            }
+        }
+        let end = box_start
+            .checked_add(size)
+            .filter(|&end| end > box_start)
+            .ok_or_else(|| invalid("BMFFボックスのサイズが不正です"))?;
+        r.seek(SeekFrom::Start(end))?;
+    }
+}
+
+/// MP3: ID3v2 タグの GEOB フレーム（MIME "application/c2pa"）内の JUMBF。
+///
+/// 圧縮・暗号化・非同期化等のフォーマットフラグを持つフレームでは、ストアがそのままの
+/// バイト列で格納されないため格納位置は `None` とする。
+fn id3_store_size<R: Read + Seek>(r: &mut R) -> io::Result<Option<DeclaredStore>> {
+    let header = read_array::<10, _>(r)?;
+    if &header[..3] != b"ID3" {
+        return Ok(None);
+    }
+    let version = header[3];
+    let flags = header[5];
+    let tag_end = 10 + syncsafe(&header[6..10]);
+    // 非同期化されたタグはヘッダのみでは読めない
+    if flags & 0x80 != 0 {
+        return Ok(None);
+    }
+    if flags & 0x40 != 0 {
+        // 拡張ヘッダ（v2.4はサイズに自身を含む、v2.3は含まない）
+        let ext = read_array::<4, _>(r)?;
+        let skip = if version >= 4 {
+            syncsafe(&ext).saturating_sub(4)
+        } else {
+            u64::from(u32::from_be_bytes(ext))
+        };
+        r.seek(SeekFrom::Current(checked_offset(skip)?))?;
+    }
+    while r.stream_position()? + 10 <= tag_end {
+        let frame_id = read_array::<4, _>(r)?;
+        if frame_id[0] == 0 {
+            // パディング
+            return Ok(None);
+        }
+        let size_bytes = read_array::<4, _>(r)?;
+        let size = if version >= 4 {
+            syncsafe(&size_bytes)
+        } else {
+            u64::from(u32::from_be_bytes(size_bytes))
+        };
+        let frame_flags = read_array::<2, _>(r)?;
+        let data_start = r.stream_position()?;
+        if &frame_id == b"GEOB" {
+            let encoding = read_u8(r)?;
+            let mime = read_cstr(r, MAX_GEOB_HEADER)?;
+            if ID3_C2PA_MIMES.contains(&mime.as_slice()) {
+                // ファイル名・説明（UTF-16はNUL2バイトで終端）
+                let wide = matches!(encoding, 1 | 2);
+                skip_encoded_str(r, wide)?;
+                skip_encoded_str(r, wide)?;
+                return read_superbox(r, frame_flags[1] == 0);
+            }
+        }
+        r.seek(SeekFrom::Start(data_start + size))?;
+    }
+    Ok(None)
+}
+
+/// 現在位置の JUMBF superbox が C2PA ストアであれば宣言長を返す。
+fn jumbf_store_size<R: Read>(r: &mut R) -> io::Result<Option<u64>> {
+    let size32 = u64::from(u32::from_be_bytes(read_array(r)?));
+    if &read_array::<4, _>(r)? != BOX_TYPE_JUMB {
+        return Ok(None);
+    }
+    let size = if size32 == 1 {
+        u64::from_be_bytes(read_array(r)?)
+    } else {
+        size32
+    };
+    // 先頭の jumd ボックスの UUID でストアかを判定する
+    let _jumd_header = read_array::<8, _>(r)?;
+    let uuid = read_array::<16, _>(r)?;
+    Ok((uuid[..4] == C2PA_STORE_UUID_PREFIX[..]).then_some(size))
+}
+
+/// 現在位置の JUMBF superbox の位置と宣言長を返す（種別の判定はコンテナ側で済んでいる場合）。
+/// `contiguous` が偽の場合、格納位置は `None` とする。
+fn read_superbox<R: Read + Seek>(r: &mut R, contiguous: bool) -> io::Result<Option<DeclaredStore>> {
+    let store_start = r.stream_position()?;
+    let size32 = u64::from(u32::from_be_bytes(read_array(r)?));
+    if &read_array::<4, _>(r)? != BOX_TYPE_JUMB {
+        return Ok(None);
+    }
+    let size = if size32 == 1 {
+        u64::from_be_bytes(read_array(r)?)
+    } else {
+        size32
+    };
+    Ok(Some(DeclaredStore {
+        offset: contiguous.then_some(store_start),
+        size,
+    }))
+}
+
+fn read_u8<R: Read>(r: &mut R) -> io::Result<u8> {
+    Ok(read_array::<1, _>(r)?[0])
+}
+
+fn read_array<const N: usize, R: Read>(r: &mut R) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// NUL終端文字列を `max` バイトまで読む（NULは含まない）。
+fn read_cstr<R: Read>(r: &mut R, max: usize) -> io::Result<Vec<u8>> {
+    let mut s = Vec::new();
+    loop {
+        let b = read_u8(r)?;
+        if b == 0 {
+            return Ok(s);
+        }
+        if s.len() >= max {
+            return Err(invalid("文字列が長すぎます"));
+        }
+        s.push(b);
+    }
+}
+
+/// ID3v2 のエンコーディング付き文字列を読み飛ばす。
+fn skip_encoded_str<R: Read>(r: &mut R, wide: bool) -> io::Result<()> {
+    if !wide {
+        return read_cstr(r, MAX_GEOB_HEADER).map(|_| ());
+    }
+    for _ in 0..MAX_GEOB_HEADER {
+        if read_array::<2, _>(r)? == [0, 0] {
+            return Ok(());
+        }
+    }
+    Err(invalid("文字列が長すぎます"))
+}
+
+/// ID3v2 の syncsafe 整数（各バイト7ビット）。
+fn syncsafe(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0u64, |acc, &b| (acc << 7) | u64::from(b & 0x7F))
+}
+
+fn checked_offset(n: u64) -> io::Result<i64> {
+    i64::try_from(n).map_err(|_| invalid("サイズが不正です"))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
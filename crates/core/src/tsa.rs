@@ -34,6 +34,20 @@ pub struct TsaInfo {
     pub cert_hash: Option<String>,
     /// 生のRFC 3161トークンバイト（将来の独立検証用）
     pub raw_token: Vec<u8>,
+    /// `cert_hash` が信頼TSAリストに含まれる場合にtrue。
+    /// 信頼されないTSAのタイムスタンプも破棄せず、このフラグで区別する。
+    pub trusted: bool,
+}
+
+/// TSA証明書ハッシュが信頼TSAリストに含まれるかを判定する。
+/// 仕様書 §2.4
+///
+/// GlobalConfigの `trusted_tsa_keys` は `0x` 付きhex、[`TsaInfo::cert_hash`] は
+/// プレフィックスなしhexのため、`0x` の有無と大文字小文字を無視して比較する。
+pub fn is_trusted_tsa(cert_hash: &str, trusted_tsa_keys: &[String]) -> bool {
+    let normalize = |s: &str| s.trim_start_matches("0x").to_ascii_lowercase();
+    let cert_hash = normalize(cert_hash);
+    trusted_tsa_keys.iter().any(|key| normalize(key) == cert_hash)
 }
 
 /// COSE署名バイト列からTSAタイムスタンプを抽出する。
@@ -43,7 +57,12 @@ pub struct TsaInfo {
 /// RFC 3161トークンからTSA証明済み時刻を取得する。
 ///
 /// ヘッダが存在しない場合は `Ok(None)` を返す（TSAなし）。
-pub fn extract_tsa_from_cose(cose_bytes: &[u8]) -> Result<Option<TsaInfo>, CoreError> {
+/// TSA証明書ハッシュが `trusted_tsa_keys` に含まれる場合のみ [`TsaInfo::trusted`] がtrueになる
+/// （リストが空なら常にfalse）。
+pub fn extract_tsa_from_cose(
+    cose_bytes: &[u8],
+    trusted_tsa_keys: &[String],
+) -> Result<Option<TsaInfo>, CoreError> {
     // COSE_Sign1をデシリアライズ（タグ付き/なし両方に対応）
     let sign1: coset::CoseSign1 =
         coset::CoseSign1::from_tagged_slice(cose_bytes)
@@ -77,11 +96,15 @@ pub fn extract_tsa_from_cose(cose_bytes: &[u8]) -> Result<Option<TsaInfo>, CoreE
 
     let raw_token = token.val.clone();
     let (timestamp, cert_hash) = parse_tst_token_der(&raw_token)?;
+    let trusted = cert_hash
+        .as_deref()
+        .is_some_and(|hash| is_trusted_tsa(hash, trusted_tsa_keys));
 
     Ok(Some(TsaInfo {
         timestamp,
         cert_hash,
         raw_token,
+        trusted,
     }))
 }
 
@@ -403,13 +426,13 @@ mod tests {
             .build();
         let cose_bytes = sign1.to_vec().unwrap();
 
-        let result = extract_tsa_from_cose(&cose_bytes).unwrap();
+        let result = extract_tsa_from_cose(&cose_bytes, &[]).unwrap();
         assert!(result.is_none());
     }
 
     #[test]
     fn test_extract_tsa_from_cose_invalid_bytes() {
-        let result = extract_tsa_from_cose(&[0x00, 0x01, 0x02]);
+        let result = extract_tsa_from_cose(&[0x00, 0x01, 0x02], &[]);
         assert!(result.is_err());
     }
 
@@ -551,11 +574,55 @@ mod tests {
         ));
         let cose_bytes = sign1.to_vec().unwrap();
 
-        let result = extract_tsa_from_cose(&cose_bytes).unwrap().unwrap();
+        let result = extract_tsa_from_cose(&cose_bytes, &[]).unwrap().unwrap();
 
         // sigTst2 のタイムスタンプ (2024-01-01) が使われるべき
         assert_eq!(result.timestamp, 1704067200); // 2024-01-01T00:00:00Z
         // sigTst のタイムスタンプ (2024-06-01) ではない
         assert_ne!(result.timestamp, 1717200000);
     }
+
+    // ----- 信頼TSA判定テスト -----
+
+    #[test]
+    fn test_extract_tsa_trusted_flag() {
+        // 仕様書 §2.4: 信頼リスト外のTSAもタイムスタンプは保持し、trustedフラグで区別する
+        let cert = wrap_sequence(&[0x02, 0x01, 0x01]);
+        let token = build_tst_token(b"20240101000000Z", Some(&cert));
+        let mut sign1 = coset::CoseSign1Builder::new()
+            .payload(vec![1, 2, 3])
+            .build();
+        sign1.unprotected.rest.push((
+            coset::Label::Text("sigTst2".into()),
+            ciborium::Value::Map(vec![(
+                ciborium::Value::Text("tstTokens".into()),
+                ciborium::Value::Array(vec![ciborium::Value::Map(vec![(
+                    ciborium::Value::Text("val".into()),
+                    ciborium::Value::Bytes(token),
+                )])]),
+            )]),
+        ));
+        let cose_bytes = sign1.to_vec().unwrap();
+        let cert_hash = hex::encode(Sha256::digest(&cert));
+
+        // GlobalConfig形式（0x付き・大文字）の信頼リストに含まれる発行者
+        let trusted_keys = vec![format!("0x{}", cert_hash.to_uppercase())];
+        let trusted = extract_tsa_from_cose(&cose_bytes, &trusted_keys)
+            .unwrap()
+            .unwrap();
+        assert!(trusted.trusted);
+        assert_eq!(trusted.cert_hash.as_deref(), Some(cert_hash.as_str()));
+
+        // 信頼リストに含まれない発行者: タイムスタンプは破棄されない
+        let other_keys = vec![format!("0x{}", "ab".repeat(32))];
+        let untrusted = extract_tsa_from_cose(&cose_bytes, &other_keys)
+            .unwrap()
+            .unwrap();
+        assert!(!untrusted.trusted);
+        assert_eq!(untrusted.timestamp, 1704067200);
+
+        // 信頼リストが空の場合は信頼しない
+        let empty = extract_tsa_from_cose(&cose_bytes, &[]).unwrap().unwrap();
+        assert!(!empty.trusted);
+    }
 }
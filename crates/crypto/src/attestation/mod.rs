@@ -14,9 +14,20 @@
 //! | `aws_nitro` | COSE Sign1 + CBOR | PCR0, PCR1, PCR2 |
 //! | `amd_sev_snp` | AMD SEV-SNP Report | MEASUREMENT |
 //! | `intel_tdx` | Intel TDX Quote | MRTD, RTMR0〜RTMR3 |
+//!
+//! ## 測定値ログ
+//! [`verify_measurements_detailed`] は測定値ごとの期待値・実測値（hex）と照合結果を
+//! 構造化ログとして出力する（一致は `debug`、不一致は `warn`）。
+//! 出力の有無は呼び出し側の `tracing` サブスクライバ（`RUST_LOG` 等）で制御する。
+//! 出力するのは公開情報である測定値のみで、鍵やユーザーデータは含めない。
+//!
+//! ## 受け入れポリシー
+//! 検証済みの結果を受け入れるかの判定（ピン留めする測定値・鮮度・TEE種別）は
+//! [`policy::TrustPolicy`] として分離し、運用者ごとに差し替えられる。
 
 #[cfg(feature = "vendor-aws")]
 pub mod nitro;
+pub mod policy;
 
 use std::collections::BTreeMap;
 
@@ -104,23 +115,118 @@ pub fn verify_attestation(
     }
 }
 
-/// 測定値が期待値と一致するか確認する。
+/// 測定値1件の照合結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeasurementCheck {
+    /// 測定値キー（例: `"PCR0"`）
+    pub key: String,
+    /// 期待値
+    pub expected: Vec<u8>,
+    /// 実測値（Attestation Documentに存在しない場合は `None`）
+    pub actual: Option<Vec<u8>>,
+}
+
+impl MeasurementCheck {
+    /// 実測値が期待値と一致するか。
+    pub fn passed(&self) -> bool {
+        self.actual.as_ref() == Some(&self.expected)
+    }
+}
+
+/// 測定値照合の詳細結果。
+/// 仕様書 §5.2 Step 4.1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeasurementVerification {
+    /// TEE種別
+    pub tee_type: String,
+    /// 期待値の各キーに対する照合結果（キー順）
+    pub checks: Vec<MeasurementCheck>,
+}
+
+impl MeasurementVerification {
+    /// 全測定値が一致したか。
+    pub fn is_valid(&self) -> bool {
+        self.checks.iter().all(MeasurementCheck::passed)
+    }
+
+    /// 一致しなかった測定値。
+    pub fn failures(&self) -> impl Iterator<Item = &MeasurementCheck> {
+        self.checks.iter().filter(|c| !c.passed())
+    }
+}
+
+/// 測定値を期待値と照合し、測定値ごとの結果を返す。
 /// 仕様書 §5.2 Step 4.1 — Global Config の expected_measurements と照合
 ///
 /// `expected_measurements` のキー名はTEE種別に対応:
 /// - AWS Nitro: `"PCR0"`, `"PCR1"`, `"PCR2"`
 /// - AMD SEV-SNP: `"MEASUREMENT"`
 /// - Intel TDX: `"MRTD"`, `"RTMR0"` 〜 `"RTMR3"`
+///
+/// 照合結果は測定値ごとに構造化ログとして出力する。
+pub fn verify_measurements_detailed(
+    result: &AttestationResult,
+    expected_measurements: &BTreeMap<String, Vec<u8>>,
+) -> MeasurementVerification {
+    let verification = MeasurementVerification {
+        tee_type: result.tee_type.clone(),
+        checks: expected_measurements
+            .iter()
+            .map(|(key, expected)| MeasurementCheck {
+                key: key.clone(),
+                expected: expected.clone(),
+                actual: result.measurements.get(key).cloned(),
+            })
+            .collect(),
+    };
+
+    log_measurement_verification(&verification);
+    verification
+}
+
+/// 測定値照合結果を測定値ごとに構造化ログとして出力する。
+fn log_measurement_verification(verification: &MeasurementVerification) {
+    for check in &verification.checks {
+        let expected = hex::encode(&check.expected);
+        let actual = check.actual.as_deref().map(hex::encode).unwrap_or_default();
+        if check.passed() {
+            tracing::debug!(
+                tee_type = %verification.tee_type,
+                key = %check.key,
+                expected = %expected,
+                actual = %actual,
+                passed = true,
+                "測定値照合"
+            );
+        } else {
+            tracing::warn!(
+                tee_type = %verification.tee_type,
+                key = %check.key,
+                expected = %expected,
+                actual = %actual,
+                passed = false,
+                "測定値照合"
+            );
+        }
+    }
+    tracing::debug!(
+        tee_type = %verification.tee_type,
+        checked = verification.checks.len(),
+        failed = verification.failures().count(),
+        passed = verification.is_valid(),
+        "測定値照合の結果"
+    );
+}
+
+/// 測定値が期待値と一致するか確認する。
+/// 仕様書 §5.2 Step 4.1
+///
+/// 測定値ごとの結果が必要な場合は [`verify_measurements_detailed`] を使用する。
 pub fn verify_measurements(
     result: &AttestationResult,
     expected_measurements: &BTreeMap<String, Vec<u8>>,
 ) -> bool {
-    expected_measurements.iter().all(|(key, expected)| {
-        result
-            .measurements
-            .get(key)
-            .map_or(false, |actual| actual == expected)
-    })
+    verify_measurements_detailed(result, expected_measurements).is_valid()
 }
 
 /// 公開鍵が期待値と一致するか確認する。
@@ -129,7 +235,25 @@ pub fn verify_public_key(result: &AttestationResult, expected_pubkey: &[u8]) ->
     result
         .public_key
         .as_ref()
-        .map_or(false, |pk| pk == expected_pubkey)
+        .is_some_and(|pk| pk == expected_pubkey)
+}
+
+#[cfg(feature = "vendor-aws")]
+impl From<nitro::NitroAttestationResult> for AttestationResult {
+    fn from(nitro: nitro::NitroAttestationResult) -> Self {
+        let mut measurements = BTreeMap::new();
+        for (idx, value) in &nitro.pcrs {
+            measurements.insert(format!("PCR{}", idx), value.clone());
+        }
+        Self {
+            tee_type: "aws_nitro".to_string(),
+            measurements,
+            public_key: nitro.public_key,
+            user_data: nitro.user_data,
+            nonce: nitro.nonce,
+            timestamp: Some(nitro.timestamp),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -180,6 +304,73 @@ mod tests {
         assert!(verify_measurements(&result, &BTreeMap::new()));
     }
 
+    #[test]
+    fn test_verify_measurements_detailed_reports_each_key() {
+        let result = sample_result();
+        let mut expected = BTreeMap::new();
+        expected.insert("PCR0".into(), vec![0u8; 48]);
+        expected.insert("PCR1".into(), vec![7u8; 48]);
+        expected.insert("PCR9".into(), vec![0u8; 48]);
+
+        let verification = verify_measurements_detailed(&result, &expected);
+        assert_eq!(verification.tee_type, "test");
+        assert!(!verification.is_valid());
+        let passed: Vec<(&str, bool)> = verification
+            .checks
+            .iter()
+            .map(|c| (c.key.as_str(), c.passed()))
+            .collect();
+        assert_eq!(passed, vec![("PCR0", true), ("PCR1", false), ("PCR9", false)]);
+        assert_eq!(verification.checks[2].actual, None);
+    }
+
+    /// 出力を共有バッファに書き込むログライター
+    #[derive(Clone, Default)]
+    struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_measurement_logging_reports_mismatch() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .with_max_level(tracing::Level::DEBUG)
+            .finish();
+
+        let result = sample_result();
+        let mut expected = BTreeMap::new();
+        expected.insert("PCR0".into(), vec![0u8; 48]);
+        expected.insert("PCR1".into(), vec![0xabu8; 48]);
+
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(!verify_measurements(&result, &expected));
+        });
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let mismatch = output
+            .lines()
+            .find(|l| l.contains("key=PCR1"))
+            .expect("PCR1のログが出力されていません");
+        assert!(mismatch.contains("WARN"));
+        assert!(mismatch.contains("tee_type=test"));
+        assert!(mismatch.contains(&format!("expected={}", "ab".repeat(48))));
+        assert!(mismatch.contains(&format!("actual={}", "01".repeat(48))));
+        assert!(mismatch.contains("passed=false"));
+        assert!(output.lines().any(|l| l.contains("key=PCR0") && l.contains("passed=true")));
+    }
+
     #[test]
     fn test_verify_public_key_match() {
         let result = sample_result();
@@ -205,21 +396,3 @@ mod tests {
         assert!(matches!(err, AttestationError::UnsupportedTeeType(_)));
     }
 }
-
-#[cfg(feature = "vendor-aws")]
-impl From<nitro::NitroAttestationResult> for AttestationResult {
-    fn from(nitro: nitro::NitroAttestationResult) -> Self {
-        let mut measurements = BTreeMap::new();
-        for (idx, value) in &nitro.pcrs {
-            measurements.insert(format!("PCR{}", idx), value.clone());
-        }
-        Self {
-            tee_type: "aws_nitro".to_string(),
-            measurements,
-            public_key: nitro.public_key,
-            user_data: nitro.user_data,
-            nonce: nitro.nonce,
-            timestamp: Some(nitro.timestamp),
-        }
-    }
-}
@@ -130,7 +130,7 @@ pub fn verify_pcr_values(
         result
             .pcrs
             .get(idx)
-            .map_or(false, |actual| actual == expected)
+            .is_some_and(|actual| actual == expected)
     })
 }
 
@@ -140,7 +140,7 @@ pub fn verify_public_key(result: &NitroAttestationResult, expected_pubkey: &[u8]
     result
         .public_key
         .as_ref()
-        .map_or(false, |pk| pk == expected_pubkey)
+        .is_some_and(|pk| pk == expected_pubkey)
 }
 
 // ─────────────────────────────────────────────
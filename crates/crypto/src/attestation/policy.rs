@@ -0,0 +1,202 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Attestation受け入れポリシー
+//!
+//! 仕様書 §5.2 Step 4.1
+//!
+//! Attestation Documentのパース・署名検証（[`super::verify_attestation`]）とは分離して、
+//! 検証済みの [`AttestationResult`] を受け入れるかどうかを判定する。
+//! ピン留めする測定値・鮮度・許可するTEE種別は運用者ごとに異なるため、
+//! [`TrustPolicy`] を実装して検証側に差し込む。
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+
+use super::{verify_measurements_detailed, AttestationResult};
+
+/// Attestation受け入れポリシー違反。
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum PolicyError {
+    /// 許可されていないTEE種別
+    #[error("許可されていないTEE種別: {0}")]
+    DisallowedTeeType(String),
+    /// 必須の測定値がAttestationに含まれない
+    #[error("必須の測定値がありません: {0}")]
+    MissingMeasurement(String),
+    /// 測定値が期待値と一致しない
+    #[error("測定値が期待値と一致しません: {}", .0.join(", "))]
+    MeasurementMismatch(Vec<String>),
+    /// 鮮度の判定に必要なタイムスタンプがない
+    #[error("Attestationにタイムスタンプがありません")]
+    MissingTimestamp,
+    /// Attestationが古すぎる
+    #[error("Attestationが古すぎます: {age_ms}ms経過 (上限: {max_age_ms}ms)")]
+    Stale {
+        /// 生成からの経過時間（ミリ秒）
+        age_ms: u64,
+        /// 許容する経過時間（ミリ秒）
+        max_age_ms: u64,
+    },
+}
+
+/// Attestation受け入れポリシー。
+/// 仕様書 §5.2 Step 4.1
+pub trait TrustPolicy: Debug + Send + Sync {
+    /// 検証済みのAttestationを受け入れるか判定する。
+    fn evaluate(&self, result: &AttestationResult) -> Result<(), PolicyError>;
+}
+
+/// 既定のポリシー: 期待測定値に指定されたキーのみを照合する。
+/// 期待測定値が空であれば常に受け入れる（従来の照合と同一の挙動）。
+#[derive(Debug, Clone, Default)]
+pub struct DefaultTrustPolicy {
+    /// 期待測定値（例: `"PCR0"` → 48バイト）
+    pub expected_measurements: BTreeMap<String, Vec<u8>>,
+}
+
+impl DefaultTrustPolicy {
+    /// 期待測定値を指定してポリシーを作成する。
+    pub fn new(expected_measurements: BTreeMap<String, Vec<u8>>) -> Self {
+        Self {
+            expected_measurements,
+        }
+    }
+}
+
+impl TrustPolicy for DefaultTrustPolicy {
+    fn evaluate(&self, result: &AttestationResult) -> Result<(), PolicyError> {
+        let verification = verify_measurements_detailed(result, &self.expected_measurements);
+        if verification.is_valid() {
+            Ok(())
+        } else {
+            Err(PolicyError::MeasurementMismatch(
+                verification.failures().map(|c| c.key.clone()).collect(),
+            ))
+        }
+    }
+}
+
+/// 運用者向けの設定可能なポリシー。
+/// 既定のポリシーに加え、TEE種別の許可リスト・必須測定値・鮮度を判定する。
+#[derive(Debug, Clone, Default)]
+pub struct OperatorTrustPolicy {
+    /// 許可するTEE種別（`None` は全て許可）
+    pub allowed_tee_types: Option<BTreeSet<String>>,
+    /// Attestationに必ず含まれるべき測定値のキー
+    pub required_measurements: BTreeSet<String>,
+    /// 期待測定値
+    pub expected_measurements: BTreeMap<String, Vec<u8>>,
+    /// Attestation生成からの許容経過時間（ミリ秒、`None` は判定しない）
+    pub max_age_ms: Option<u64>,
+}
+
+impl OperatorTrustPolicy {
+    /// 現在時刻 `now_ms`（Unix ms）を基準に判定する。
+    pub fn evaluate_at(&self, result: &AttestationResult, now_ms: u64) -> Result<(), PolicyError> {
+        if let Some(allowed) = &self.allowed_tee_types {
+            if !allowed.contains(&result.tee_type) {
+                return Err(PolicyError::DisallowedTeeType(result.tee_type.clone()));
+            }
+        }
+        if let Some(key) = self
+            .required_measurements
+            .iter()
+            .find(|key| !result.measurements.contains_key(*key))
+        {
+            return Err(PolicyError::MissingMeasurement(key.clone()));
+        }
+        DefaultTrustPolicy::new(self.expected_measurements.clone()).evaluate(result)?;
+        if let Some(max_age_ms) = self.max_age_ms {
+            let timestamp = result.timestamp.ok_or(PolicyError::MissingTimestamp)?;
+            let age_ms = now_ms.saturating_sub(timestamp);
+            if age_ms > max_age_ms {
+                return Err(PolicyError::Stale { age_ms, max_age_ms });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TrustPolicy for OperatorTrustPolicy {
+    fn evaluate(&self, result: &AttestationResult) -> Result<(), PolicyError> {
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.evaluate_at(result, now_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// PCR2を含まないNitro形式の結果
+    fn result_without_pcr2() -> AttestationResult {
+        AttestationResult {
+            tee_type: "aws_nitro".into(),
+            measurements: BTreeMap::from([
+                ("PCR0".to_string(), vec![0u8; 48]),
+                ("PCR1".to_string(), vec![1u8; 48]),
+            ]),
+            public_key: Some(vec![42u8; 32]),
+            user_data: None,
+            nonce: None,
+            timestamp: Some(1_700_000_000_000),
+        }
+    }
+
+    #[test]
+    fn test_strict_policy_rejects_missing_pcr2_lenient_accepts() {
+        let result = result_without_pcr2();
+
+        let strict = OperatorTrustPolicy {
+            required_measurements: BTreeSet::from(["PCR0".into(), "PCR1".into(), "PCR2".into()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            strict.evaluate(&result),
+            Err(PolicyError::MissingMeasurement("PCR2".into()))
+        );
+
+        let lenient = DefaultTrustPolicy::new(BTreeMap::from([("PCR0".into(), vec![0u8; 48])]));
+        assert_eq!(lenient.evaluate(&result), Ok(()));
+    }
+
+    #[test]
+    fn test_default_policy_reports_mismatched_keys() {
+        let policy = DefaultTrustPolicy::new(BTreeMap::from([
+            ("PCR0".into(), vec![0u8; 48]),
+            ("PCR1".into(), vec![9u8; 48]),
+        ]));
+        assert_eq!(
+            policy.evaluate(&result_without_pcr2()),
+            Err(PolicyError::MeasurementMismatch(vec!["PCR1".into()]))
+        );
+    }
+
+    #[test]
+    fn test_operator_policy_tee_type_and_freshness() {
+        let result = result_without_pcr2();
+        let policy = OperatorTrustPolicy {
+            allowed_tee_types: Some(BTreeSet::from(["aws_nitro".into()])),
+            max_age_ms: Some(60_000),
+            ..Default::default()
+        };
+        assert_eq!(policy.evaluate_at(&result, 1_700_000_030_000), Ok(()));
+        assert_eq!(
+            policy.evaluate_at(&result, 1_700_000_090_000),
+            Err(PolicyError::Stale {
+                age_ms: 90_000,
+                max_age_ms: 60_000
+            })
+        );
+
+        let mut other = result.clone();
+        other.tee_type = "intel_tdx".into();
+        assert_eq!(
+            policy.evaluate_at(&other, 1_700_000_000_000),
+            Err(PolicyError::DisallowedTeeType("intel_tdx".into()))
+        );
+    }
+}
@@ -119,15 +119,199 @@ pub fn ed25519_verify(
         .map_err(|_| CryptoError::SignatureVerifyError)
 }
 
+/// 署名ドメイン。
+/// 仕様書 §5.1 Step 4, §6.2
+///
+/// 同一の鍵で署名した異なる構造体の署名が互いに流用されないよう、
+/// 署名対象の先頭にドメインごとに固定のタグを付与する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureDomain {
+    /// signed_jsonの `tee_signature`（署名対象: `{payload, attributes}`）
+    SignedJson,
+    /// Gateway認証の `gateway_signature`（署名対象: `GatewayAuthSignTarget`）
+    GatewayAuth,
+}
+
+impl SignatureDomain {
+    /// ドメイン分離タグ（NUL終端）。
+    pub const fn tag(self) -> &'static [u8] {
+        match self {
+            Self::SignedJson => b"Title-v1-signed-json\0",
+            Self::GatewayAuth => b"Title-v1-gateway-auth\0",
+        }
+    }
+}
+
+/// ドメイン分離タグを付与した署名対象バイト列を構築する。
+/// `tag || message`
+pub fn domain_separated_message(domain: SignatureDomain, message: &[u8]) -> Vec<u8> {
+    let tag = domain.tag();
+    let mut bytes = Vec::with_capacity(tag.len() + message.len());
+    bytes.extend_from_slice(tag);
+    bytes.extend_from_slice(message);
+    bytes
+}
+
+/// 署名ドメインを指定したEd25519署名。
+pub fn ed25519_sign_in_domain(
+    signing_key: &Ed25519SigningKey,
+    domain: SignatureDomain,
+    message: &[u8],
+) -> Ed25519Signature {
+    ed25519_sign(signing_key, &domain_separated_message(domain, message))
+}
+
+/// 署名ドメインを指定したEd25519署名検証。
+pub fn ed25519_verify_in_domain(
+    verifying_key: &Ed25519VerifyingKey,
+    domain: SignatureDomain,
+    message: &[u8],
+    signature: &Ed25519Signature,
+) -> Result<(), CryptoError> {
+    ed25519_verify(
+        verifying_key,
+        &domain_separated_message(domain, message),
+        signature,
+    )
+}
+
 /// SHA-256ハッシュ計算。
 pub fn sha256(data: &[u8]) -> [u8; 32] {
     Sha256::digest(data).into()
 }
 
+/// content_hashの算出に用いるハッシュアルゴリズム（[`content_hash_with_namespace`] の実装と一致する）。
+/// 仕様書 §2.1
+pub const CONTENT_HASH_ALGORITHM: &str = "SHA-256";
+
 /// Active Manifestの署名からcontent_hashを計算する。
 /// 仕様書 §2.1: `content_hash = SHA-256(Active Manifestの署名)`
+///
+/// 既定（空）の名前空間での計算。名前空間を設定したデプロイメントでは
+/// [`content_hash_with_namespace`] を使用する。
 pub fn content_hash_from_manifest_signature(manifest_signature: &[u8]) -> [u8; 32] {
-    sha256(manifest_signature)
+    content_hash_with_namespace("", manifest_signature)
+}
+
+/// 指定したデプロイメント名前空間でcontent_hashを計算する。
+/// 仕様書 §2.1
+///
+/// - 名前空間が空: `SHA-256(署名)`（後方互換）
+/// - 名前空間あり: `SHA-256(len(namespace) as u32 BE || namespace || 署名)`
+///
+/// 長さを前置することで、名前空間と署名の境界の曖昧さによる衝突を防ぐ。
+pub fn content_hash_with_namespace(namespace: &str, manifest_signature: &[u8]) -> [u8; 32] {
+    if namespace.is_empty() {
+        return sha256(manifest_signature);
+    }
+    let mut hasher = Sha256::new();
+    hasher.update((namespace.len() as u32).to_be_bytes());
+    hasher.update(namespace.as_bytes());
+    hasher.update(manifest_signature);
+    hasher.finalize().into()
+}
+
+// ---------------------------------------------------------------------------
+// Merkle木
+// ---------------------------------------------------------------------------
+
+/// Merkle木の葉ハッシュのドメインタグ
+const MERKLE_LEAF_TAG: u8 = 0x00;
+
+/// Merkle木の内部ノードハッシュのドメインタグ
+const MERKLE_NODE_TAG: u8 = 0x01;
+
+/// Merkle木の葉ハッシュ: `SHA-256(0x00 || data)`。
+/// 仕様書 §2.2
+///
+/// 葉と内部ノードでタグを分け、内部ノードを葉として偽装する第二原像攻撃を防ぐ。
+pub fn merkle_leaf_hash(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([MERKLE_LEAF_TAG]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// Merkle木の内部ノードハッシュ: `SHA-256(0x01 || left || right)`。
+fn merkle_node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([MERKLE_NODE_TAG]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Merkle木の1段上を計算する。奇数個の場合は末尾をそのまま持ち上げる。
+fn merkle_next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => merkle_node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!("chunks(2)は1要素または2要素を返す"),
+        })
+        .collect()
+}
+
+/// 葉ハッシュ列（順序どおり）からMerkle rootを計算する。
+/// 仕様書 §2.2
+///
+/// 各段で隣接する2つを結合し、奇数個の場合は末尾をそのまま次段に持ち上げる。
+/// 葉が空の場合は `SHA-256("")` を返す。
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return sha256(&[]);
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = merkle_next_level(&level);
+    }
+    level[0]
+}
+
+/// Merkle包含証明の1段分。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProofStep {
+    /// 兄弟ノードのハッシュ
+    pub sibling: [u8; 32],
+    /// 兄弟ノードが左側にあるか
+    pub sibling_is_left: bool,
+}
+
+/// `index` 番目の葉の包含証明を生成する。`index` が範囲外の場合は `None`。
+/// 仕様書 §2.2
+pub fn merkle_proof(leaves: &[[u8; 32]], index: usize) -> Option<Vec<MerkleProofStep>> {
+    if index >= leaves.len() {
+        return None;
+    }
+    let mut proof = Vec::new();
+    let mut level = leaves.to_vec();
+    let mut index = index;
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            proof.push(MerkleProofStep {
+                sibling: level[sibling],
+                sibling_is_left: sibling < index,
+            });
+        }
+        level = merkle_next_level(&level);
+        index /= 2;
+    }
+    Some(proof)
+}
+
+/// 包含証明を検証し、`leaf` が `root` のMerkle木に含まれるかを返す。
+/// 仕様書 §2.2
+pub fn verify_merkle_proof(leaf: &[u8; 32], proof: &[MerkleProofStep], root: &[u8; 32]) -> bool {
+    let computed = proof.iter().fold(*leaf, |acc, step| {
+        if step.sibling_is_left {
+            merkle_node_hash(&step.sibling, &acc)
+        } else {
+            merkle_node_hash(&acc, &step.sibling)
+        }
+    });
+    computed == *root
 }
 
 #[cfg(test)]
@@ -167,10 +351,6 @@ mod tests {
         assert_eq!(decrypted, plaintext);
     }
 
-    // -----------------------------------------------------------------------
-    // AES-GCM エラーケース
-    // -----------------------------------------------------------------------
-
     #[test]
     fn test_aes_gcm_wrong_key_fails() {
         let key1 = [1u8; 32];
@@ -231,6 +411,43 @@ mod tests {
         assert!(ed25519_verify(&key2.verifying_key(), message, &signature).is_err());
     }
 
+    #[test]
+    fn test_ed25519_signature_bound_to_domain() {
+        let signing_key = Ed25519SigningKey::generate(&mut rand::rngs::OsRng);
+        let verifying_key = signing_key.verifying_key();
+        let message = br#"{"payload":{},"attributes":[]}"#;
+
+        let signature = ed25519_sign_in_domain(&signing_key, SignatureDomain::SignedJson, message);
+        assert!(ed25519_verify_in_domain(
+            &verifying_key,
+            SignatureDomain::SignedJson,
+            message,
+            &signature
+        )
+        .is_ok());
+        // 別ドメインでは検証できない
+        assert!(ed25519_verify_in_domain(
+            &verifying_key,
+            SignatureDomain::GatewayAuth,
+            message,
+            &signature
+        )
+        .is_err());
+        // タグなしのメッセージとしても検証できない
+        assert!(ed25519_verify(&verifying_key, message, &signature).is_err());
+    }
+
+    #[test]
+    fn test_signature_domain_tags_are_distinct() {
+        let signed_json = SignatureDomain::SignedJson.tag();
+        let gateway_auth = SignatureDomain::GatewayAuth.tag();
+        assert_ne!(signed_json, gateway_auth);
+        // 一方が他方の接頭辞にならない（NUL終端）
+        assert!(!signed_json.starts_with(gateway_auth));
+        assert!(!gateway_auth.starts_with(signed_json));
+        assert_eq!(signed_json.last(), Some(&0));
+    }
+
     // -----------------------------------------------------------------------
     // SHA-256
     // -----------------------------------------------------------------------
@@ -260,4 +477,44 @@ mod tests {
         let signature = b"mock cose signature bytes";
         assert_eq!(content_hash_from_manifest_signature(signature), sha256(signature));
     }
+
+    #[test]
+    fn test_content_hash_namespace() {
+        let signature = b"mock cose signature bytes";
+        // 空の名前空間は従来の計算式と一致する
+        assert_eq!(content_hash_with_namespace("", signature), sha256(signature));
+
+        // 名前空間ありは決定論的で、名前空間なし・別名前空間と異なる
+        let private = content_hash_with_namespace("private-instance", signature);
+        assert_eq!(private, content_hash_with_namespace("private-instance", signature));
+        assert_ne!(private, sha256(signature));
+        assert_ne!(private, content_hash_with_namespace("other-instance", signature));
+    }
+
+    // -----------------------------------------------------------------------
+    // Merkle木
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn test_merkle_proof_roundtrip() {
+        let leaves: Vec<[u8; 32]> = (0u8..5).map(|i| merkle_leaf_hash(&[i])).collect();
+        let root = merkle_root(&leaves);
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = merkle_proof(&leaves, i).unwrap();
+            assert!(verify_merkle_proof(leaf, &proof, &root), "leaf {i}");
+        }
+
+        // 含まれない葉は検証に失敗する
+        let proof = merkle_proof(&leaves, 0).unwrap();
+        assert!(!verify_merkle_proof(&merkle_leaf_hash(b"other"), &proof, &root));
+        assert!(merkle_proof(&leaves, leaves.len()).is_none());
+    }
+
+    #[test]
+    fn test_merkle_root_single_leaf_is_leaf() {
+        let leaf = merkle_leaf_hash(b"only");
+        assert_eq!(merkle_root(&[leaf]), leaf);
+        assert_eq!(merkle_root(&[]), sha256(&[]));
+    }
 }
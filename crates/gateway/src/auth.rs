@@ -4,13 +4,13 @@
 //!
 //! 仕様書 §6.2
 //!
-//! Gateway秘密鍵によるリクエスト署名の構築とTEEへのリクエスト中継。
+//! Gateway秘密鍵によるリクエスト署名の構築。
+//! TEEへの送信は [`crate::tee_client::TeeClient`] が行う。
 
 use base64::Engine;
-use ed25519_dalek::{Signer, SigningKey as Ed25519SigningKey};
+use ed25519_dalek::SigningKey as Ed25519SigningKey;
 use title_types::*;
 
-use crate::config::GatewayState;
 use crate::error::GatewayError;
 
 /// Base64エンジン（Standard）
@@ -34,10 +34,16 @@ pub(crate) fn build_gateway_auth_wrapper(
         resource_limits: resource_limits.clone(),
     };
 
-    let sign_bytes = serde_json::to_vec(&sign_target)
+    let sign_target = serde_json::to_value(&sign_target)
         .map_err(|e| GatewayError::Internal(format!("署名対象のシリアライズに失敗: {e}")))?;
-
-    let signature = signing_key.sign(&sign_bytes);
+    let sign_bytes = title_types::canonical_json(&sign_target);
+
+    // Gateway認証ドメインのタグを付与して署名（signed_json署名との流用を防ぐ）
+    let signature = title_crypto::ed25519_sign_in_domain(
+        signing_key,
+        title_crypto::SignatureDomain::GatewayAuth,
+        &sign_bytes,
+    );
     let signature_b64 = b64().encode(signature.to_bytes());
 
     Ok(GatewayAuthWrapper {
@@ -48,44 +54,3 @@ pub(crate) fn build_gateway_auth_wrapper(
         gateway_signature: signature_b64,
     })
 }
-
-/// TEEにリクエストを中継する。
-/// 仕様書 §6.2: Gateway認証署名を付与してTEEにリクエストを転送する。
-pub(crate) async fn relay_to_tee(
-    state: &GatewayState,
-    path: &str,
-    body: serde_json::Value,
-) -> Result<serde_json::Value, GatewayError> {
-    let wrapper = build_gateway_auth_wrapper(
-        &state.signing_key,
-        "POST",
-        path,
-        body,
-        Some(state.default_resource_limits.clone()),
-    )?;
-
-    let url = format!("{}{}", state.tee_endpoint, path);
-    let response = state
-        .http_client
-        .post(&url)
-        .json(&wrapper)
-        .send()
-        .await
-        .map_err(|e| GatewayError::TeeRelay(format!("HTTP送信失敗: {e}")))?;
-
-    let status = response.status();
-    let response_body = response
-        .text()
-        .await
-        .map_err(|e| GatewayError::TeeRelay(format!("レスポンス読み取り失敗: {e}")))?;
-
-    if !status.is_success() {
-        return Err(GatewayError::TeeRelay(format!(
-            "TEEがエラーを返しました: HTTP {} - {}",
-            status, response_body
-        )));
-    }
-
-    serde_json::from_str(&response_body)
-        .map_err(|e| GatewayError::TeeRelay(format!("レスポンスのパースに失敗: {e}")))
-}
@@ -6,9 +6,13 @@
 //!
 //! 環境変数からの設定読み込みとGatewayの共有状態の定義。
 
+use std::collections::HashSet;
+
 use ed25519_dalek::SigningKey as Ed25519SigningKey;
 use title_types::*;
 
+use crate::idempotency::IdempotencyCache;
+use crate::limiter::ClientConcurrencyLimiter;
 use crate::storage::{SignedJsonStorageRouter, TempStorage};
 
 /// Gatewayの共有状態。
@@ -42,4 +46,21 @@ pub struct GatewayState {
     pub max_upload_size: u64,
     /// 署名付きURLの有効期限（秒）
     pub presign_expiry_secs: u32,
+    /// APIキーごと（匿名クライアントは共有）の同時リクエスト数リミッター
+    pub client_limiter: ClientConcurrencyLimiter,
+    /// /sign-and-mint の冪等性キーごとのレスポンスキャッシュ
+    /// （保持期間: 環境変数 `IDEMPOTENCY_TTL_SECS`）
+    pub idempotency_cache: IdempotencyCache,
+    /// TEEレスポンスの読み取り上限（バイト）
+    /// （環境変数 `TEE_MAX_RESPONSE_BYTES`）
+    pub tee_max_response_bytes: usize,
+    /// 登録済みのAPIキー（`GATEWAY_API_KEYS` と `PRIORITY_API_KEYS` の和集合）。
+    /// 空でない場合、これ以外のキーを付与したリクエストは401で拒否する（空の場合は匿名として扱う）
+    pub api_keys: HashSet<String>,
+    /// /verify を高優先度で処理するAPIキー
+    /// （環境変数 `PRIORITY_API_KEYS`、カンマ区切り）
+    pub priority_api_keys: HashSet<String>,
+    /// content_hashのデプロイメント名前空間（環境変数 `CONTENT_HASH_NAMESPACE`、TEEと同じ値）。
+    /// 仕様書 §2.1
+    pub content_hash_namespace: String,
 }
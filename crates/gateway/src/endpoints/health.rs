@@ -3,6 +3,8 @@
 //! # GET /health
 //!
 //! ノードのステータスとcapabilitiesを返す。
+//! クライアントは `capabilities` のプロトコル・アルゴリズム・ABIバージョンを確認し、
+//! 互換性のないノードを利用対象から除外できる。
 
 use std::sync::Arc;
 
@@ -15,6 +17,11 @@ use crate::config::GatewayState;
 ///
 /// `capabilities.store_signed_json` が `true` の場合、
 /// `/sign-and-mint` で `signed_json` 本体を受け取り保存を代行できる。
+///
+/// `protocol_version` / `signature_algorithm` はノードが話すプロトコルの版と署名アルゴリズム
+/// （`title-types` の定数）、`content_hash_algorithm` は `title-crypto` の実装、
+/// `wasm_abi_versions` は `title-wasm-abi` が定めるホストの対応ABIバージョン一覧を表す。
+/// `content_hash_namespace` はcontent_hashのデプロイメント名前空間（空の場合は名前空間なし）。
 pub async fn handle_health(
     State(state): State<Arc<GatewayState>>,
 ) -> Json<serde_json::Value> {
@@ -22,6 +29,11 @@ pub async fn handle_health(
         "status": "ok",
         "capabilities": {
             "store_signed_json": state.signed_json_storage.is_some(),
+            "protocol_version": title_types::PROTOCOL_VERSION,
+            "signature_algorithm": title_types::SIGNATURE_ALGORITHM,
+            "content_hash_algorithm": title_crypto::CONTENT_HASH_ALGORITHM,
+            "wasm_abi_versions": title_wasm_abi::SUPPORTED_ABI_VERSIONS,
+            "content_hash_namespace": state.content_hash_namespace,
         }
     }))
 }
@@ -12,7 +12,7 @@ pub mod sign_and_mint;
 
 pub use health::handle_health;
 pub use upload_url::handle_upload_url;
-pub use verify::handle_verify;
+pub use verify::{handle_cancel_verify, handle_verify};
 pub use sign::handle_sign;
 pub use sign_and_mint::handle_sign_and_mint;
 #[cfg(test)]
@@ -6,18 +6,33 @@
 //!
 //! sign + ブロードキャスト代行。
 //! signed_json本体の保存代行にも対応（ノード運営者のオプション機能）。
+//!
+//! Solana関連の失敗は、RPCに到達できない場合（再試行可能、503）と
+//! トランザクションが拒否された場合（422）を区別して返す。
+//! ブロードキャストは一時的な障害に対して有限回・バックオフ付きで再送する。
+//! デバッグのため、エラーメッセージには使用した `recent_blockhash` を付与する。
+//!
+//! `Idempotency-Key` ヘッダが付与された場合、キーをリクエスト本文に束縛して保持期間のあいだ予約し、
+//! 同じキーでの再試行には保持したレスポンスを返すか、最終署名後に失敗していれば
+//! 同一の署名済みトランザクションを再ブロードキャストする（二重mintの防止）。
 
 use std::sync::Arc;
 
 use axum::extract::State;
-use axum::Json;
+use axum::http::HeaderMap;
+use axum::{Extension, Json};
 use base64::Engine;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use title_types::*;
 
-use crate::auth::{b64, relay_to_tee};
+use crate::auth::b64;
 use crate::config::GatewayState;
 use crate::error::GatewayError;
+use crate::fee;
+use crate::idempotency::{Reservation, SignedBatch, IDEMPOTENCY_KEY_HEADER};
+use crate::limiter::ClientId;
+use crate::solana_rpc;
+use crate::tee_client::TeeClient;
 
 // ---------------------------------------------------------------------------
 // Gateway固有のリクエスト型（signed_json本体対応）
@@ -28,7 +43,7 @@ use crate::error::GatewayError;
 /// `signed_json_uri` と `signed_json` の2パターンに対応:
 /// - `signed_json_uri`: クライアントが事前に保存済みのURI
 /// - `signed_json`: Gatewayに保存を代行させる場合のJSON本体
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct SignAndMintItem {
     /// オフチェーンストレージのURI（既にsigned_jsonが保存されている場合）
     #[serde(default)]
@@ -39,7 +54,7 @@ pub(crate) struct SignAndMintItem {
 }
 
 /// /sign-and-mint リクエスト（Gateway固有、signed_json本体対応）。
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct SignAndMintInput {
     /// Base58エンコードされたBlockhash（空の場合はGatewayが自動取得）
     #[serde(default)]
@@ -61,10 +76,64 @@ pub(crate) struct SignAndMintInput {
 ///
 /// `signed_json` 本体が渡された場合、Gatewayが保存を代行しURIに変換してからTEEに中継する。
 /// この機能は `signed_json_storage` が設定されている場合のみ利用可能。
+///
+/// `Idempotency-Key` ヘッダ付きのリクエストは、同じクライアント・同じキー・同じ本文で処理済みであれば
+/// 保持したレスポンスをそのまま返し、最終署名後に失敗していれば同一のトランザクションを再送する。
+/// 同じキーのリクエストが処理中、または別の本文で使用済みの場合は409を返す。
 pub async fn handle_sign_and_mint(
     State(state): State<Arc<GatewayState>>,
+    Extension(client_id): Extension<ClientId>,
+    headers: HeaderMap,
     Json(input): Json<SignAndMintInput>,
 ) -> Result<Json<SignAndMintResponse>, GatewayError> {
+    let Some(key) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        let batch = sign_transactions(&state, input).await?;
+        return broadcast(&state, &batch).await.map(Json);
+    };
+    let key = key.to_str().map_err(|_| {
+        GatewayError::BadRequest("Idempotency-Keyが不正です".to_string())
+    })?;
+    let fingerprint = request_fingerprint(&input)?;
+
+    let reservation = state.idempotency_cache.reserve(&client_id, key, fingerprint)?;
+    let (guard, batch) = match reservation {
+        Reservation::Cached(response) => {
+            tracing::info!(
+                idempotency_key = %key,
+                "処理済みのIdempotency-Keyです。保持したレスポンスを返します"
+            );
+            return Ok(Json(response));
+        }
+        Reservation::Rebroadcast(guard, batch) => {
+            tracing::info!(
+                idempotency_key = %key,
+                "署名済みのIdempotency-Keyです。同一のトランザクションを再送します"
+            );
+            (guard, batch)
+        }
+        Reservation::Reserved(mut guard) => {
+            let batch = sign_transactions(&state, input).await?;
+            guard.record_signed(&batch);
+            (guard, batch)
+        }
+    };
+    let response = broadcast(&state, &batch).await?;
+    guard.complete(&response);
+    Ok(Json(response))
+}
+
+/// Idempotency-Keyを束縛するリクエスト本文のハッシュ（正規化JSONのSHA-256）。
+fn request_fingerprint(input: &SignAndMintInput) -> Result<[u8; 32], GatewayError> {
+    let value = serde_json::to_value(input)
+        .map_err(|e| GatewayError::Internal(format!("リクエストのシリアライズに失敗: {e}")))?;
+    Ok(title_crypto::sha256(&title_types::canonical_json(&value)))
+}
+
+/// TEEの部分署名済みトランザクションを取得し、Gatewayウォレットで最終署名する。
+async fn sign_transactions(
+    state: &GatewayState,
+    input: SignAndMintInput,
+) -> Result<SignedBatch, GatewayError> {
     let solana_rpc_url = state
         .solana_rpc_url
         .as_ref()
@@ -122,17 +191,16 @@ pub async fn handle_sign_and_mint(
             "method": "getLatestBlockhash",
             "params": [{"commitment": "confirmed"}]
         });
-        let rpc_response = state
-            .http_client
-            .post(solana_rpc_url)
-            .json(&rpc_request)
-            .send()
-            .await
-            .map_err(|e| GatewayError::Solana(format!("blockhash取得失敗: {e}")))?;
-        let rpc_body: serde_json::Value = rpc_response
-            .json()
-            .await
-            .map_err(|e| GatewayError::Solana(format!("blockhashレスポンスのパースに失敗: {e}")))?;
+        let rpc_body =
+            solana_rpc::call(&state.http_client, solana_rpc_url, &rpc_request, "blockhash取得")
+                .await?;
+        if let Some(error) = rpc_body.get("error") {
+            return Err(if solana_rpc::is_retryable_rpc_error(error) {
+                GatewayError::SolanaUnavailable(format!("blockhash取得失敗: {error}"))
+            } else {
+                GatewayError::Solana(format!("blockhash取得失敗: {error}"))
+            });
+        }
         let blockhash = rpc_body
             .pointer("/result/value/blockhash")
             .and_then(|v| v.as_str())
@@ -143,29 +211,43 @@ pub async fn handle_sign_and_mint(
     }
 
     // Step 2: TEEの/signに中継
-    let body_value = serde_json::to_value(&body)
-        .map_err(|e| GatewayError::Internal(format!("リクエストのシリアライズに失敗: {e}")))?;
-
-    let result = relay_to_tee(&state, "/sign", body_value).await?;
-    let sign_response: SignResponse = serde_json::from_value(result)
-        .map_err(|e| GatewayError::TeeRelay(format!("SignResponseのパースに失敗: {e}")))?;
-
-    // Step 3: 各partial_txにGatewayウォレットで署名+ブロードキャスト
-    let mut tx_signatures = Vec::new();
+    let sign_response = TeeClient::from_state(state).sign(&body).await?;
 
+    // Step 3: partial_txをデコード
+    let mut txs = Vec::with_capacity(sign_response.partial_txs.len());
     for partial_tx_b64 in &sign_response.partial_txs {
         let tx_bytes = b64().decode(partial_tx_b64).map_err(|e| {
             GatewayError::TeeRelay(format!("partial_txのBase64デコードに失敗: {e}"))
         })?;
 
-        let mut tx: solana_sdk::transaction::Transaction =
+        let tx: solana_sdk::transaction::Transaction =
             bincode::deserialize(&tx_bytes).map_err(|e| {
                 GatewayError::TeeRelay(format!(
                     "トランザクションのデシリアライズに失敗: {e}"
                 ))
             })?;
+        txs.push(tx);
+    }
+
+    // Step 4: ブロードキャスト前にGatewayウォレットの残高を確認
+    // 残高不足のまま送信すると一部のみミントされ、RPCから不明瞭なエラーが返るため事前に拒否する
+    let recent_blockhash = body.recent_blockhash.as_str();
+    let mut estimate = fee::MintCostEstimate::default();
+    for tx in &txs {
+        estimate = estimate
+            + fee::fetch_mint_cost(&state.http_client, solana_rpc_url, tx)
+                .await
+                .map_err(|e| with_blockhash(e, recent_blockhash))?;
+    }
+    let balance =
+        fee::fetch_balance(&state.http_client, solana_rpc_url, &gateway_keypair.pubkey())
+            .await
+            .map_err(|e| with_blockhash(e, recent_blockhash))?;
+    fee::ensure_sufficient_balance(balance, &estimate)?;
 
-        // Gatewayウォレットで署名（未署名のスロットに署名）
+    // Step 5: 各partial_txにGatewayウォレットで署名
+    let mut transactions = Vec::with_capacity(txs.len());
+    for mut tx in txs {
         let gateway_pubkey = gateway_keypair.pubkey();
 
         // Gatewayの公開鍵に対応する署名スロットを特定
@@ -187,46 +269,48 @@ pub async fn handle_sign_and_mint(
         let sig = gateway_keypair.sign_message(&message_bytes);
         tx.signatures[sig_index] = sig;
 
-        // Solana RPCにブロードキャスト
         let tx_serialized = bincode::serialize(&tx)
             .map_err(|e| GatewayError::Internal(format!("トランザクションのシリアライズに失敗: {e}")))?;
-        let tx_b64 = b64().encode(&tx_serialized);
-
-        let rpc_request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "sendTransaction",
-            "params": [tx_b64, {"encoding": "base64", "skipPreflight": true, "preflightCommitment": "confirmed"}]
-        });
+        transactions.push((b64().encode(&tx_serialized), tx.signatures[0].to_string()));
+    }
 
-        let rpc_response = state
-            .http_client
-            .post(solana_rpc_url)
-            .json(&rpc_request)
-            .send()
-            .await
-            .map_err(|e| GatewayError::Solana(format!("RPC送信失敗: {e}")))?;
+    Ok(SignedBatch {
+        recent_blockhash: body.recent_blockhash,
+        transactions,
+    })
+}
 
-        let rpc_body: serde_json::Value = rpc_response
-            .json()
-            .await
-            .map_err(|e| GatewayError::Solana(format!("RPCレスポンスのパースに失敗: {e}")))?;
+/// 最終署名済みのトランザクションをSolanaにブロードキャストする。
+///
+/// 署名は固定のため再送しても二重にmintされない（再試行はsolana_rpc::send_transaction）。
+async fn broadcast(
+    state: &GatewayState,
+    batch: &SignedBatch,
+) -> Result<SignAndMintResponse, GatewayError> {
+    let solana_rpc_url = state
+        .solana_rpc_url
+        .as_ref()
+        .ok_or_else(|| GatewayError::Internal("SOLANA_RPC_URLが設定されていません".to_string()))?;
 
-        if let Some(error) = rpc_body.get("error") {
-            return Err(GatewayError::Solana(format!(
-                "トランザクションのブロードキャストに失敗: {error}"
-            )));
-        }
+    let mut tx_signatures = Vec::with_capacity(batch.transactions.len());
+    for (tx_b64, signature) in &batch.transactions {
+        let tx_sig =
+            solana_rpc::send_transaction(&state.http_client, solana_rpc_url, tx_b64, signature)
+                .await
+                .map_err(|e| with_blockhash(e, &batch.recent_blockhash))?;
+        tx_signatures.push(tx_sig);
+    }
 
-        let tx_sig = rpc_body
-            .get("result")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| {
-                GatewayError::Solana("RPCレスポンスにresultがありません".to_string())
-            })?;
+    Ok(SignAndMintResponse { tx_signatures })
+}
 
-        tx_signatures.push(tx_sig.to_string());
+/// Solana関連のエラーメッセージに使用した `recent_blockhash` を付与する。
+fn with_blockhash(error: GatewayError, recent_blockhash: &str) -> GatewayError {
+    let suffix = format!(" (recent_blockhash: {recent_blockhash})");
+    match error {
+        GatewayError::Solana(m) => GatewayError::Solana(m + &suffix),
+        GatewayError::SolanaUnavailable(m) => GatewayError::SolanaUnavailable(m + &suffix),
+        GatewayError::TransactionRejected(m) => GatewayError::TransactionRejected(m + &suffix),
+        other => other,
     }
-
-    Ok(Json(SignAndMintResponse { tx_signatures }))
 }
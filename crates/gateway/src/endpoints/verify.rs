@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
-//! # POST /verify
+//! # POST /verify, DELETE /verify/{token}
 //!
 //! 仕様書 §6.2
 //!
@@ -8,26 +8,106 @@
 
 use std::sync::Arc;
 
-use axum::extract::State;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Extension;
 use axum::Json;
 use title_types::*;
 
-use crate::auth::relay_to_tee;
 use crate::config::GatewayState;
 use crate::error::GatewayError;
+use crate::limiter::ClientId;
+use crate::tee_client::TeeClient;
 
 /// POST /verify — TEEへのリクエスト中継 + Gateway認証署名付与。
 /// 仕様書 §6.2
 ///
 /// クライアントのVerifyRequestをGateway認証で包み、TEEに中継する。
 /// TEEからのレスポンス（暗号化済み）をそのままクライアントに返す。
+/// `priority` は認証済みのAPIキーに応じて [`effective_priority`] で決定してから中継する。
+/// `cancel_token` はクライアントごとに [`scoped_cancel_token`] で変換してから中継する。
 pub async fn handle_verify(
     State(state): State<Arc<GatewayState>>,
-    Json(body): Json<VerifyRequest>,
-) -> Result<Json<serde_json::Value>, GatewayError> {
-    let body_value = serde_json::to_value(&body)
-        .map_err(|e| GatewayError::Internal(format!("リクエストのシリアライズに失敗: {e}")))?;
+    Extension(client_id): Extension<ClientId>,
+    Json(mut body): Json<VerifyRequest>,
+) -> Result<Json<EncryptedResponse>, GatewayError> {
+    if let Some(token) = &body.cancel_token {
+        validate_cancel_token(token)?;
+        body.cancel_token = Some(scoped_cancel_token(&client_id, token));
+    }
+    let is_priority_client = client_id
+        .api_key()
+        .is_some_and(|key| state.priority_api_keys.contains(key));
+    body.priority = effective_priority(body.priority, is_priority_client);
+    let response = TeeClient::from_state(&state).verify(&body).await?;
+    Ok(Json(response))
+}
+
+/// DELETE /verify/{token} — 処理中の検証の取り消しをTEEに中継する。
+/// 仕様書 §6.2, §6.4
+///
+/// `cancel_token` を指定して開始した検証を打ち切り、TEEの予約済みリソースを解放させる。
+/// 取り消せるのは同じクライアントが同じ `download_url` で開始した検証のみ。
+pub async fn handle_cancel_verify(
+    State(state): State<Arc<GatewayState>>,
+    Extension(client_id): Extension<ClientId>,
+    Path(token): Path<String>,
+    Json(body): Json<CancelVerifyRequest>,
+) -> Result<StatusCode, GatewayError> {
+    validate_cancel_token(&token)?;
+    let token = scoped_cancel_token(&client_id, &token);
+    TeeClient::from_state(&state)
+        .cancel_verify(&token, &body)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// クライアント指定の `cancel_token` を、クライアント（APIキー）ごとに独立したトークンに変換する。
+/// 仕様書 §6.2, §6.4
+///
+/// 別のAPIキーのクライアントは同じトークンを指定しても他者の検証を取り消せない。
+/// 匿名クライアントは同じスコープを共有するが、TEEはトークンを `download_url` にも束縛する。
+fn scoped_cancel_token(client_id: &ClientId, token: &str) -> String {
+    let scoped = serde_json::json!([client_id.api_key(), token]);
+    hex::encode(title_crypto::sha256(&title_types::canonical_json(&scoped)))
+}
+
+/// TEEに中継する処理優先度を決定する。
+/// 仕様書 §6.2, §6.4
+///
+/// 優先APIキー（`PRIORITY_API_KEYS`）のクライアントは未指定時に `high` となり、任意の優先度を指定できる。
+/// それ以外のクライアントが指定した `high` は `normal` に引き下げる（`low` はそのまま）。
+fn effective_priority(
+    requested: Option<VerifyPriority>,
+    is_priority_client: bool,
+) -> Option<VerifyPriority> {
+    if is_priority_client {
+        return Some(requested.unwrap_or(VerifyPriority::High));
+    }
+    match requested {
+        Some(VerifyPriority::High) => Some(VerifyPriority::Normal),
+        other => other,
+    }
+}
 
-    let result = relay_to_tee(&state, "/verify", body_value).await?;
-    Ok(Json(result))
+/// `cancel_token` の最大長
+const MAX_CANCEL_TOKEN_LEN: usize = 128;
+
+/// `cancel_token` の形式を検証する（英数字・`-`・`_` の1〜128文字）。
+/// 仕様書 §6.2
+///
+/// トークンはTEEへの中継パスに埋め込むため、パスとして解釈される文字を拒否する。
+fn validate_cancel_token(token: &str) -> Result<(), GatewayError> {
+    let valid = !token.is_empty()
+        && token.len() <= MAX_CANCEL_TOKEN_LEN
+        && token
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if valid {
+        Ok(())
+    } else {
+        Err(GatewayError::BadRequest(
+            "cancel_tokenは英数字・-・_ の1〜128文字で指定してください".into(),
+        ))
+    }
 }
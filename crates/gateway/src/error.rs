@@ -4,7 +4,7 @@
 //!
 //! 仕様書 §6.2
 
-use axum::http::StatusCode;
+use axum::http::{header, HeaderValue, StatusCode};
 
 /// Gatewayエラー型。
 /// 仕様書 §6.2
@@ -19,12 +19,45 @@ pub enum GatewayError {
     /// Solana RPC エラー
     #[error("Solana RPC エラー: {0}")]
     Solana(String),
+    /// Solana RPCに到達できない（再試行可能）
+    #[error("Solana RPCに到達できません（再試行してください）: {0}")]
+    SolanaUnavailable(String),
+    /// トランザクションがSolanaに拒否された
+    #[error("トランザクションが拒否されました: {0}")]
+    TransactionRejected(String),
     /// 内部エラー
     #[error("内部エラー: {0}")]
     Internal(String),
     /// 不正なリクエスト
     #[error("不正なリクエスト: {0}")]
     BadRequest(String),
+    /// Gatewayウォレットの残高不足
+    #[error("残高不足: {0}")]
+    InsufficientFunds(String),
+    /// クライアントの同時リクエスト数上限超過
+    #[error("リクエストが多すぎます: {0}")]
+    TooManyRequests(String),
+    /// TEEがクライアント起因・再試行可能なエラーを返した
+    /// （ペイロードがまだ取得できない: 425、恒久的に見つからない: 404、署名レート超過: 503 等）。
+    /// TEEのステータスコード・エラーメッセージ・`Retry-After` ヘッダをそのまま返す。
+    #[error("{message}")]
+    TeeRejected {
+        /// TEEが返したステータスコード
+        status: StatusCode,
+        /// TEEのエラーメッセージ
+        message: String,
+        /// TEEが返した `Retry-After` ヘッダ
+        retry_after: Option<HeaderValue>,
+    },
+    /// 対象が存在しない（取り消し対象の検証がTEEで処理中でない等）
+    #[error("{0}")]
+    NotFound(String),
+    /// 同じIdempotency-Keyのリクエストが処理中
+    #[error("{0}")]
+    Conflict(String),
+    /// APIキーが未登録、または匿名アクセスが無効
+    #[error("認証エラー: {0}")]
+    Unauthorized(String),
 }
 
 impl axum::response::IntoResponse for GatewayError {
@@ -35,7 +68,26 @@ impl axum::response::IntoResponse for GatewayError {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
             GatewayError::Solana(_) => StatusCode::BAD_GATEWAY,
+            GatewayError::SolanaUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            GatewayError::TransactionRejected(_) => StatusCode::UNPROCESSABLE_ENTITY,
             GatewayError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            GatewayError::InsufficientFunds(_) => StatusCode::SERVICE_UNAVAILABLE,
+            GatewayError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            GatewayError::TeeRejected {
+                status,
+                retry_after,
+                ..
+            } => {
+                let (status, retry_after) = (*status, retry_after.clone());
+                let mut response = (status, self.to_string()).into_response();
+                if let Some(retry_after) = retry_after {
+                    response.headers_mut().insert(header::RETRY_AFTER, retry_after);
+                }
+                return response;
+            }
+            GatewayError::NotFound(_) => StatusCode::NOT_FOUND,
+            GatewayError::Conflict(_) => StatusCode::CONFLICT,
+            GatewayError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
         };
         (status, self.to_string()).into_response()
     }
@@ -59,6 +111,14 @@ mod tests {
                 GatewayError::Solana("t".into()),
                 StatusCode::BAD_GATEWAY,
             ),
+            (
+                GatewayError::SolanaUnavailable("t".into()),
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+            (
+                GatewayError::TransactionRejected("t".into()),
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
             (
                 GatewayError::Internal("t".into()),
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -67,6 +127,25 @@ mod tests {
                 GatewayError::BadRequest("t".into()),
                 StatusCode::BAD_REQUEST,
             ),
+            (
+                GatewayError::InsufficientFunds("t".into()),
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+            (
+                GatewayError::TooManyRequests("t".into()),
+                StatusCode::TOO_MANY_REQUESTS,
+            ),
+            (
+                GatewayError::TeeRejected {
+                    status: StatusCode::TOO_EARLY,
+                    message: "t".into(),
+                    retry_after: None,
+                },
+                StatusCode::TOO_EARLY,
+            ),
+            (GatewayError::NotFound("t".into()), StatusCode::NOT_FOUND),
+            (GatewayError::Conflict("t".into()), StatusCode::CONFLICT),
+            (GatewayError::Unauthorized("t".into()), StatusCode::UNAUTHORIZED),
         ];
 
         for (error, expected_status) in cases {
@@ -78,4 +157,17 @@ mod tests {
             );
         }
     }
+
+    /// TEEのステータスコードと `Retry-After` ヘッダがそのまま中継されることを確認
+    #[test]
+    fn test_tee_rejected_forwards_retry_after() {
+        let response = GatewayError::TeeRejected {
+            status: StatusCode::TOO_EARLY,
+            message: "t".into(),
+            retry_after: Some(HeaderValue::from_static("1")),
+        }
+        .into_response();
+        assert_eq!(response.status(), StatusCode::TOO_EARLY);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "1");
+    }
 }
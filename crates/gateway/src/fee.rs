@@ -0,0 +1,241 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Solana手数料見積もり
+//!
+//! 仕様書 §6.2
+//!
+//! `/sign-and-mint` ではGatewayウォレットがfee payerとなるため、
+//! ブロードキャスト前にミントに必要なSOLを見積もり、残高不足を検出する。
+//! 手数料はSolana RPC `getFeeForMessage` でクラスタの現行fee parametersから取得する。
+
+use base64::Engine;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+
+use crate::auth::b64;
+use crate::error::GatewayError;
+use crate::solana_rpc;
+
+/// 1署名あたりの基本手数料（lamports）。
+/// RPCがメッセージのblockhashを認識せず `getFeeForMessage` が手数料を返さない場合の見積もりに用いる。
+pub const DEFAULT_LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+/// ミントトランザクションのコスト見積もり。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MintCostEstimate {
+    /// トランザクション手数料（lamports）
+    pub fee_lamports: u64,
+    /// 新規アカウントのrent（lamports）
+    pub rent_lamports: u64,
+}
+
+impl MintCostEstimate {
+    /// 手数料とrentの合計（lamports）。
+    pub fn total_lamports(&self) -> u64 {
+        self.fee_lamports.saturating_add(self.rent_lamports)
+    }
+}
+
+impl std::ops::Add for MintCostEstimate {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            fee_lamports: self.fee_lamports.saturating_add(rhs.fee_lamports),
+            rent_lamports: self.rent_lamports.saturating_add(rhs.rent_lamports),
+        }
+    }
+}
+
+/// Bubblegum V2 MintV2トランザクションのコストをRPCで見積もる。
+/// 仕様書 §5.1 Step 9-10
+///
+/// 手数料は `getFeeForMessage`（[`fetch_fee_for_message`]）の値。RPCがメッセージのblockhashを
+/// 認識しない場合は `署名数 × DEFAULT_LAMPORTS_PER_SIGNATURE`（[`estimate_mint_cost`]）で見積もる。
+/// cNFTはMerkle Treeのリーフとして記録され新規アカウントを作成しないため、rentは0となる。
+pub async fn fetch_mint_cost(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    tx: &Transaction,
+) -> Result<MintCostEstimate, GatewayError> {
+    match fetch_fee_for_message(client, rpc_url, tx).await? {
+        Some(fee_lamports) => Ok(MintCostEstimate {
+            fee_lamports,
+            rent_lamports: 0,
+        }),
+        None => {
+            tracing::warn!("getFeeForMessageが手数料を返しませんでした。署名数から見積もります");
+            Ok(estimate_mint_cost(tx, DEFAULT_LAMPORTS_PER_SIGNATURE))
+        }
+    }
+}
+
+/// Bubblegum V2 MintV2トランザクションのコストを署名数から見積もる。
+/// 仕様書 §5.1 Step 9-10
+///
+/// 手数料は `署名数 × lamports_per_signature`。rentは0。
+/// TEEが構築するトランザクションはcompute unit priceを設定しないため、優先手数料は含めない。
+pub fn estimate_mint_cost(tx: &Transaction, lamports_per_signature: u64) -> MintCostEstimate {
+    let num_signatures = tx.message.header.num_required_signatures as u64;
+    MintCostEstimate {
+        fee_lamports: num_signatures.saturating_mul(lamports_per_signature),
+        rent_lamports: 0,
+    }
+}
+
+/// Solana RPC `getFeeForMessage` でトランザクションの手数料（lamports）を取得する。
+/// 仕様書 §6.2
+///
+/// RPCがメッセージのblockhashを認識しない場合（`value: null`）は `None` を返す。
+pub async fn fetch_fee_for_message(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    tx: &Transaction,
+) -> Result<Option<u64>, GatewayError> {
+    let rpc_request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "getFeeForMessage",
+        "params": [b64().encode(tx.message.serialize()), {"commitment": "confirmed"}]
+    });
+    let rpc_body = solana_rpc::call(client, rpc_url, &rpc_request, "手数料取得").await?;
+
+    if let Some(error) = rpc_body.get("error") {
+        if solana_rpc::is_retryable_rpc_error(error) {
+            return Err(GatewayError::SolanaUnavailable(format!("手数料取得失敗: {error}")));
+        }
+        return Err(GatewayError::Solana(format!("手数料取得失敗: {error}")));
+    }
+
+    match rpc_body.pointer("/result/value") {
+        Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .map(Some)
+            .ok_or_else(|| GatewayError::Solana(format!("RPCレスポンスの手数料が不正です: {value}"))),
+        None => Err(GatewayError::Solana("RPCレスポンスに手数料がありません".to_string())),
+    }
+}
+
+/// Solana RPC `getBalance` でウォレット残高（lamports）を取得する。
+pub async fn fetch_balance(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    pubkey: &Pubkey,
+) -> Result<u64, GatewayError> {
+    let rpc_request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "getBalance",
+        "params": [pubkey.to_string(), {"commitment": "confirmed"}]
+    });
+    let rpc_body = solana_rpc::call(client, rpc_url, &rpc_request, "残高取得").await?;
+
+    if let Some(error) = rpc_body.get("error") {
+        if solana_rpc::is_retryable_rpc_error(error) {
+            return Err(GatewayError::SolanaUnavailable(format!("残高取得失敗: {error}")));
+        }
+        return Err(GatewayError::Solana(format!("残高取得失敗: {error}")));
+    }
+
+    rpc_body
+        .pointer("/result/value")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| GatewayError::Solana("RPCレスポンスに残高がありません".to_string()))
+}
+
+/// 残高が見積もりコストを満たすことを確認する。
+pub fn ensure_sufficient_balance(
+    balance_lamports: u64,
+    estimate: &MintCostEstimate,
+) -> Result<(), GatewayError> {
+    let required = estimate.total_lamports();
+    if balance_lamports < required {
+        return Err(GatewayError::InsufficientFunds(format!(
+            "Gatewayウォレットの残高が不足しています（必要: {required} lamports, 残高: {balance_lamports} lamports）"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::message::Message;
+
+    /// 署名者2名（fee payer + TEE）のトランザクションを構築する
+    fn two_signer_tx() -> Transaction {
+        let payer = Pubkey::new_unique();
+        let tee = Pubkey::new_unique();
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new_readonly(tee, true)],
+        );
+        Transaction::new_unsigned(Message::new_with_blockhash(&[ix], Some(&payer), &Hash::default()))
+    }
+
+    #[test]
+    fn test_estimate_mint_cost_counts_signatures() {
+        let estimate = estimate_mint_cost(&two_signer_tx(), DEFAULT_LAMPORTS_PER_SIGNATURE);
+        assert_eq!(estimate.fee_lamports, 2 * DEFAULT_LAMPORTS_PER_SIGNATURE);
+        assert_eq!(estimate.rent_lamports, 0);
+
+        let total = estimate + estimate;
+        assert_eq!(total.total_lamports(), 4 * DEFAULT_LAMPORTS_PER_SIGNATURE);
+    }
+
+    /// `getFeeForMessage` に固定の応答を返すモックRPCを起動し、URLを返す
+    async fn spawn_fee_rpc(value: serde_json::Value) -> String {
+        let rpc = axum::Router::new().route(
+            "/rpc",
+            axum::routing::post(move |axum::Json(req): axum::Json<serde_json::Value>| {
+                let value = value.clone();
+                async move {
+                    assert_eq!(req["method"], "getFeeForMessage");
+                    // メッセージはBase64で渡される
+                    assert!(b64().decode(req["params"][0].as_str().unwrap()).is_ok());
+                    axum::Json(serde_json::json!({
+                        "jsonrpc": "2.0", "id": 1,
+                        "result": { "context": { "slot": 1 }, "value": value }
+                    }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, rpc).await.unwrap();
+        });
+        format!("http://127.0.0.1:{port}/rpc")
+    }
+
+    #[tokio::test]
+    async fn test_fetch_mint_cost_uses_rpc_fee() {
+        let client = reqwest::Client::new();
+        let url = spawn_fee_rpc(serde_json::json!(12_345)).await;
+        let estimate = fetch_mint_cost(&client, &url, &two_signer_tx()).await.unwrap();
+        assert_eq!(estimate.fee_lamports, 12_345);
+        assert_eq!(estimate.rent_lamports, 0);
+
+        // blockhashを認識しない場合は署名数から見積もる
+        let url = spawn_fee_rpc(serde_json::Value::Null).await;
+        let estimate = fetch_mint_cost(&client, &url, &two_signer_tx()).await.unwrap();
+        assert_eq!(estimate.fee_lamports, 2 * DEFAULT_LAMPORTS_PER_SIGNATURE);
+    }
+
+    #[test]
+    fn test_ensure_sufficient_balance() {
+        let estimate = MintCostEstimate {
+            fee_lamports: 10_000,
+            rent_lamports: 0,
+        };
+        assert!(ensure_sufficient_balance(10_000, &estimate).is_ok());
+        assert!(matches!(
+            ensure_sufficient_balance(9_999, &estimate),
+            Err(GatewayError::InsufficientFunds(_))
+        ));
+    }
+}
@@ -0,0 +1,423 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # /sign-and-mint の冪等性キー
+//!
+//! 仕様書 §6.2
+//!
+//! ネットワーク断の後にクライアントが `/sign-and-mint` を再試行すると、TEEが新しい
+//! 部分署名済みトランザクションを発行し、二重にmintされ得る。クライアントが
+//! `Idempotency-Key` ヘッダを付与した場合、キーを保持期間のあいだ予約し続ける。
+//!
+//! - 成功したレスポンスは保持し、同じキーでの再試行にはそのまま返す
+//! - Gatewayの最終署名後に失敗・切断した場合は、Gatewayが最終署名したトランザクションを保持し、
+//!   同じキーでの再試行では新たに署名させずに同一のトランザクションを再ブロードキャストする
+//!   （署名が固定されるため二重にmintされない）
+//! - Gatewayの最終署名前に失敗した場合のみ予約を取り消し、同じキーで再試行できる
+//!
+//! キーはクライアントごとに独立した名前空間を持ち、リクエスト本文のハッシュに束縛される。
+//! 同じキーを別の本文で再利用した場合は拒否する。
+//! 保持するエントリ数には上限があり、期限切れのエントリは期限順のキューから償却O(1)で掃除する。
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use title_types::SignAndMintResponse;
+
+use crate::error::GatewayError;
+use crate::limiter::ClientId;
+
+/// 冪等性キーを指定するHTTPヘッダ名。
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// レスポンスの保持期間のデフォルト（秒）。
+pub const DEFAULT_IDEMPOTENCY_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// 同時に保持するキーの上限のデフォルト。
+pub const DEFAULT_IDEMPOTENCY_MAX_ENTRIES: usize = 100_000;
+
+/// 冪等性キーの最大長（バイト）。
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Gatewayが最終署名済みのトランザクション群（再ブロードキャスト用）。
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedBatch {
+    /// 使用した `recent_blockhash`（エラーメッセージ用）
+    pub recent_blockhash: String,
+    /// `(Base64エンコードされたトランザクション, 先頭の署名)` の一覧
+    pub transactions: Vec<(String, String)>,
+}
+
+/// `(クライアント, Idempotency-Key)`。キーはクライアントごとに独立する。
+type CacheKey = (ClientId, String);
+
+/// キーごとの状態。
+enum State {
+    /// 同じキーのリクエストが処理中
+    InFlight,
+    /// 最終署名後に失敗した。再試行では同じトランザクションを再ブロードキャストする
+    Signed(SignedBatch),
+    /// 処理済み。レスポンスを再利用する
+    Completed(SignAndMintResponse),
+}
+
+/// キーごとのエントリ。
+struct Entry {
+    /// リクエスト本文のハッシュ
+    fingerprint: [u8; 32],
+    /// 保持期限
+    expires_at: Instant,
+    state: State,
+}
+
+/// キャッシュの内部状態。
+#[derive(Default)]
+struct Inner {
+    /// `(クライアント, Idempotency-Key)` → エントリ
+    entries: HashMap<CacheKey, Entry>,
+    /// `(期限, キー)` の期限順キュー。保持期間が一定のため追加順に並ぶ
+    expiry: VecDeque<(Instant, CacheKey)>,
+}
+
+impl Inner {
+    /// 期限切れのエントリを先頭から掃除する。
+    /// 期限が更新されたエントリの古いキュー要素は読み捨てる。
+    fn purge_expired(&mut self, now: Instant) {
+        while let Some((expires_at, _)) = self.expiry.front() {
+            if *expires_at > now {
+                break;
+            }
+            let (_, key) = self.expiry.pop_front().expect("front exists");
+            if self.entries.get(&key).is_some_and(|e| e.expires_at <= now) {
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    /// エントリを保存し、期限キューに登録する。
+    fn insert(&mut self, key: &CacheKey, fingerprint: [u8; 32], expires_at: Instant, state: State) {
+        self.entries.insert(
+            key.clone(),
+            Entry {
+                fingerprint,
+                expires_at,
+                state,
+            },
+        );
+        self.expiry.push_back((expires_at, key.clone()));
+    }
+}
+
+/// `/sign-and-mint` の冪等性キーの予約とレスポンスキャッシュ。
+/// 仕様書 §6.2
+pub struct IdempotencyCache {
+    /// キーの保持期間
+    ttl: Duration,
+    /// 同時に保持するキーの上限
+    max_entries: usize,
+    inner: Arc<Mutex<Inner>>,
+}
+
+/// [`IdempotencyCache::reserve`] の結果。
+pub enum Reservation {
+    /// 同じキーで処理済み。保持していたレスポンスを返す
+    Cached(SignAndMintResponse),
+    /// 同じキーで最終署名後に失敗していた。保持していたトランザクションを再ブロードキャストする
+    Rebroadcast(IdempotencyGuard, SignedBatch),
+    /// 初回のリクエスト。処理完了時に [`IdempotencyGuard::complete`] で結果を保存する
+    Reserved(IdempotencyGuard),
+}
+
+/// 処理中のキーの予約。
+///
+/// `complete` せずにDropされた場合（エラー・クライアント切断）、
+/// [`IdempotencyGuard::record_signed`] 済みであれば署名済みトランザクションを保持期間まで残し、
+/// そうでなければ予約を取り消す。
+pub struct IdempotencyGuard {
+    inner: Arc<Mutex<Inner>>,
+    key: CacheKey,
+    fingerprint: [u8; 32],
+    expires_at: Instant,
+    ttl: Duration,
+    signed: Option<SignedBatch>,
+    completed: bool,
+}
+
+impl IdempotencyCache {
+    /// 新しいキャッシュを作成する。
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries,
+            inner: Arc::new(Mutex::new(Inner::default())),
+        }
+    }
+
+    /// クライアントの冪等性キーを予約する。
+    ///
+    /// `fingerprint` はリクエスト本文のハッシュ。処理済みのキーは保持中のレスポンスを、
+    /// 署名後に失敗したキーは再ブロードキャスト用のトランザクションを、未使用のキーは予約を返す。
+    /// 同じキーのリクエストが処理中、または別の本文で使用済みの場合は `GatewayError::Conflict`、
+    /// 保持数が上限に達している場合は `GatewayError::TooManyRequests` を返す。
+    pub fn reserve(
+        &self,
+        client_id: &ClientId,
+        key: &str,
+        fingerprint: [u8; 32],
+    ) -> Result<Reservation, GatewayError> {
+        validate_key(key)?;
+        let cache_key = (client_id.clone(), key.to_string());
+
+        let mut inner = self.inner.lock().unwrap();
+        let now = Instant::now();
+        inner.purge_expired(now);
+        if inner
+            .entries
+            .get(&cache_key)
+            .is_some_and(|e| e.expires_at <= now)
+        {
+            inner.entries.remove(&cache_key);
+        }
+
+        let guard = |expires_at, signed| IdempotencyGuard {
+            inner: Arc::clone(&self.inner),
+            key: cache_key.clone(),
+            fingerprint,
+            expires_at,
+            ttl: self.ttl,
+            signed,
+            completed: false,
+        };
+
+        if let Some(entry) = inner.entries.get_mut(&cache_key) {
+            if entry.fingerprint != fingerprint {
+                return Err(GatewayError::Conflict(format!(
+                    "Idempotency-Keyは別のリクエスト本文で使用済みです: {key}"
+                )));
+            }
+            return match &entry.state {
+                State::Completed(response) => Ok(Reservation::Cached(response.clone())),
+                State::InFlight => Err(GatewayError::Conflict(format!(
+                    "同じIdempotency-Keyのリクエストを処理中です: {key}"
+                ))),
+                State::Signed(batch) => {
+                    let batch = batch.clone();
+                    entry.state = State::InFlight;
+                    Ok(Reservation::Rebroadcast(
+                        guard(entry.expires_at, Some(batch.clone())),
+                        batch,
+                    ))
+                }
+            };
+        }
+
+        if inner.entries.len() >= self.max_entries {
+            return Err(GatewayError::TooManyRequests(
+                "Idempotency-Keyの保持数が上限に達しています".to_string(),
+            ));
+        }
+        let expires_at = now + self.ttl;
+        inner.insert(&cache_key, fingerprint, expires_at, State::InFlight);
+        Ok(Reservation::Reserved(guard(expires_at, None)))
+    }
+}
+
+impl IdempotencyGuard {
+    /// TEEの署名済みトランザクションにGatewayが最終署名した時点で呼び出す。
+    /// 以降にエラー・切断が起きても予約は取り消されず、再試行では同じトランザクションを送る。
+    pub fn record_signed(&mut self, batch: &SignedBatch) {
+        self.signed = Some(batch.clone());
+    }
+
+    /// 処理結果を保存し、保持期間内の再試行に返せるようにする。
+    pub fn complete(mut self, response: &SignAndMintResponse) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.insert(
+            &self.key,
+            self.fingerprint,
+            Instant::now() + self.ttl,
+            State::Completed(response.clone()),
+        );
+        self.completed = true;
+    }
+}
+
+impl Drop for IdempotencyGuard {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        match self.signed.take() {
+            Some(batch) => {
+                inner.insert(
+                    &self.key,
+                    self.fingerprint,
+                    self.expires_at,
+                    State::Signed(batch),
+                );
+            }
+            None => {
+                inner.entries.remove(&self.key);
+            }
+        }
+    }
+}
+
+/// 冪等性キーを検証する（1〜255バイトの表示可能なASCII）。
+fn validate_key(key: &str) -> Result<(), GatewayError> {
+    if key.is_empty()
+        || key.len() > MAX_IDEMPOTENCY_KEY_LEN
+        || !key.bytes().all(|b| b.is_ascii_graphic())
+    {
+        return Err(GatewayError::BadRequest(format!(
+            "Idempotency-Keyは1〜{MAX_IDEMPOTENCY_KEY_LEN}文字の表示可能なASCIIで指定してください"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: [u8; 32] = [1u8; 32];
+
+    fn response(sig: &str) -> SignAndMintResponse {
+        SignAndMintResponse {
+            tx_signatures: vec![sig.to_string()],
+        }
+    }
+
+    fn batch() -> SignedBatch {
+        SignedBatch {
+            recent_blockhash: "blockhash".to_string(),
+            transactions: vec![("dHg=".to_string(), "sig-1".to_string())],
+        }
+    }
+
+    fn client(name: &str) -> ClientId {
+        ClientId::ApiKey(format!("client-{name}"))
+    }
+
+    fn cache() -> IdempotencyCache {
+        IdempotencyCache::new(Duration::from_secs(60), DEFAULT_IDEMPOTENCY_MAX_ENTRIES)
+    }
+
+    /// 処理中は競合、完了後は保存したレスポンス、署名前の失敗では予約が取り消されることを確認
+    #[test]
+    fn test_reserve_lifecycle() {
+        let cache = cache();
+
+        let Reservation::Reserved(guard) = cache.reserve(&client("a"), "key-1", BODY).unwrap() else {
+            panic!("初回は予約されるべき");
+        };
+        let err = cache.reserve(&client("a"), "key-1", BODY).err().unwrap();
+        assert!(matches!(err, GatewayError::Conflict(_)));
+        // 別クライアントの同名キーは独立
+        assert!(matches!(
+            cache.reserve(&client("b"), "key-1", BODY).unwrap(),
+            Reservation::Reserved(_)
+        ));
+
+        guard.complete(&response("sig-1"));
+        match cache.reserve(&client("a"), "key-1", BODY).unwrap() {
+            Reservation::Cached(r) => assert_eq!(r, response("sig-1")),
+            _ => panic!("処理済みのキーはキャッシュを返すべき"),
+        }
+
+        // 署名前にDropされた予約は取り消される
+        let reserved = cache.reserve(&client("a"), "key-2", BODY).unwrap();
+        drop(reserved);
+        assert!(matches!(
+            cache.reserve(&client("a"), "key-2", BODY).unwrap(),
+            Reservation::Reserved(_)
+        ));
+    }
+
+    /// 署名後に失敗したキーは予約が残り、再試行では同じトランザクションを返すことを確認
+    #[test]
+    fn test_failure_after_signing_keeps_transactions() {
+        let cache = cache();
+        let Reservation::Reserved(mut guard) = cache.reserve(&client("a"), "key-1", BODY).unwrap()
+        else {
+            panic!("初回は予約されるべき");
+        };
+        guard.record_signed(&batch());
+        drop(guard);
+
+        let Reservation::Rebroadcast(guard, signed) =
+            cache.reserve(&client("a"), "key-1", BODY).unwrap()
+        else {
+            panic!("署名済みのキーは再ブロードキャストされるべき");
+        };
+        assert_eq!(signed, batch());
+        // 再ブロードキャスト中は処理中として扱う
+        assert!(matches!(
+            cache.reserve(&client("a"), "key-1", BODY).err().unwrap(),
+            GatewayError::Conflict(_)
+        ));
+        // 再ブロードキャストも失敗した場合、再び同じトランザクションが残る
+        drop(guard);
+        assert!(matches!(
+            cache.reserve(&client("a"), "key-1", BODY).unwrap(),
+            Reservation::Rebroadcast(_, _)
+        ));
+    }
+
+    /// 同じキーを別の本文で再利用すると拒否されることを確認
+    #[test]
+    fn test_key_bound_to_request_body() {
+        let cache = cache();
+        let Reservation::Reserved(guard) = cache.reserve(&client("a"), "key-1", BODY).unwrap() else {
+            panic!("初回は予約されるべき");
+        };
+        guard.complete(&response("sig-1"));
+        let err = cache.reserve(&client("a"), "key-1", [2u8; 32]).err().unwrap();
+        assert!(matches!(err, GatewayError::Conflict(_)));
+    }
+
+    /// 保持期間を過ぎたキーは再利用されないことを確認
+    #[test]
+    fn test_expired_entry_is_not_reused() {
+        let cache = IdempotencyCache::new(Duration::ZERO, DEFAULT_IDEMPOTENCY_MAX_ENTRIES);
+        let Reservation::Reserved(guard) = cache.reserve(&client("a"), "key-1", BODY).unwrap() else {
+            panic!("初回は予約されるべき");
+        };
+        guard.complete(&response("sig-1"));
+        assert!(matches!(
+            cache.reserve(&client("a"), "key-1", BODY).unwrap(),
+            Reservation::Reserved(_)
+        ));
+        assert!(cache.inner.lock().unwrap().expiry.len() <= 1);
+    }
+
+    /// 保持数が上限に達すると新しいキーを拒否することを確認
+    #[test]
+    fn test_entry_cap() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60), 2);
+        for key in ["key-1", "key-2"] {
+            let Reservation::Reserved(guard) = cache.reserve(&client("a"), key, BODY).unwrap() else {
+                panic!("初回は予約されるべき");
+            };
+            guard.complete(&response(key));
+        }
+        let err = cache.reserve(&client("a"), "key-3", BODY).err().unwrap();
+        assert!(matches!(err, GatewayError::TooManyRequests(_)));
+        // 既存のキーは引き続き参照できる
+        assert!(matches!(
+            cache.reserve(&client("a"), "key-1", BODY).unwrap(),
+            Reservation::Cached(_)
+        ));
+    }
+
+    /// 不正なキーを拒否することを確認
+    #[test]
+    fn test_invalid_key_rejected() {
+        let cache = cache();
+        for key in ["", "has space", &"k".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1)] {
+            let err = cache.reserve(&client("a"), key, BODY).err().unwrap();
+            assert!(matches!(err, GatewayError::BadRequest(_)), "{key:?}");
+        }
+    }
+}
@@ -0,0 +1,308 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # クライアント認証と同時リクエスト数制限
+//!
+//! 仕様書 §6.2
+//!
+//! 単一クライアントが大量の `/verify` を同時に発行し、TEEのメモリセマフォを
+//! 占有することを防ぐため、APIキーごとに同時処理数の上限を設ける。
+//! TEE側のグローバル制限（`max_concurrent_bytes`）とは独立して動作する。
+//!
+//! クライアントは `X-API-Key` ヘッダ、または `apikey` クエリパラメータ（SDKの既定）で
+//! 識別する。Gatewayに登録されたAPIキー（`GATEWAY_API_KEYS` と `PRIORITY_API_KEYS`）のみを
+//! 認証済みのクライアントとして扱い、キーが1つ以上登録されている場合は未登録のキーを401で拒否する。
+//! キーが登録されていないGatewayでは、未登録のキーを付与したリクエストも拒否せず匿名クライアントとして扱う
+//! （SDKはGateway URLの `apikey` をそのまま送信するため、キーを登録していない既存の運用を壊さない）。
+//! キーを付与しないリクエストは匿名クライアントとして、全匿名クライアントで共有する
+//! 専用の枠（`MAX_CONCURRENT_ANONYMOUS_REQUESTS`）を使用する。枠を0にすると匿名アクセスを拒否する。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, Uri};
+use axum::middleware::Next;
+use axum::response::Response;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::config::GatewayState;
+use crate::error::GatewayError;
+
+/// クライアント識別に使用するHTTPヘッダ名。
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// クライアント識別に使用するクエリパラメータ名（SDKの既定）。
+pub const API_KEY_QUERY_PARAM: &str = "apikey";
+
+/// 1クライアントあたりの同時リクエスト数のデフォルト上限。
+pub const DEFAULT_MAX_CONCURRENT_PER_CLIENT: usize = 4;
+
+/// 匿名クライアント全体で共有する同時リクエスト数のデフォルト上限。
+pub const DEFAULT_MAX_CONCURRENT_ANONYMOUS: usize = 16;
+
+/// 認証済みのクライアント識別子。
+/// [`limit_client_concurrency`] がリクエストのextensionに格納する。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClientId {
+    /// 登録済みのAPIキー
+    ApiKey(String),
+    /// APIキーを付与しないクライアント
+    Anonymous,
+}
+
+impl ClientId {
+    /// 登録済みのAPIキーであれば返す。
+    pub fn api_key(&self) -> Option<&str> {
+        match self {
+            ClientId::ApiKey(key) => Some(key),
+            ClientId::Anonymous => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ClientId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // ログにキー全体を出さない
+            ClientId::ApiKey(key) => {
+                let prefix: String = key.chars().take(4).collect();
+                write!(f, "key:{prefix}…")
+            }
+            ClientId::Anonymous => f.write_str("anonymous"),
+        }
+    }
+}
+
+/// APIキーごとの同時リクエスト数リミッター。
+/// 仕様書 §6.2
+///
+/// APIキーごとに `Semaphore` を保持し、匿名クライアントは共有の `Semaphore` を使用する。
+/// 上限を超えたリクエストは待機させずに即座に `GatewayError::TooManyRequests` で拒否する。
+pub struct ClientConcurrencyLimiter {
+    /// 1クライアントあたりの同時リクエスト数上限
+    max_per_client: usize,
+    /// 匿名クライアント全体の同時リクエスト数上限（0の場合は匿名アクセスを拒否）
+    max_anonymous: usize,
+    /// 匿名クライアントの共有枠
+    anonymous: Arc<Semaphore>,
+    /// APIキー → セマフォ
+    slots: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl ClientConcurrencyLimiter {
+    /// 新しいリミッターを作成する。
+    ///
+    /// # 引数
+    /// - `max_per_client`: 1クライアントあたりの同時リクエスト数上限（0の場合は1として扱う）
+    /// - `max_anonymous`: 匿名クライアント全体の同時リクエスト数上限（0の場合は匿名アクセスを拒否）
+    pub fn new(max_per_client: usize, max_anonymous: usize) -> Self {
+        Self {
+            max_per_client: max_per_client.max(1),
+            max_anonymous,
+            anonymous: Arc::new(Semaphore::new(max_anonymous)),
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// クライアントの処理枠を1つ確保する。
+    ///
+    /// 返却された許可証がDropされると枠が解放される。
+    /// 枠が残っていない場合は `GatewayError::TooManyRequests`、
+    /// 匿名アクセスが無効な場合は `GatewayError::Unauthorized` を返す。
+    pub fn try_acquire(&self, client_id: &ClientId) -> Result<OwnedSemaphorePermit, GatewayError> {
+        let (semaphore, limit) = match client_id {
+            ClientId::Anonymous if self.max_anonymous == 0 => {
+                return Err(GatewayError::Unauthorized("APIキーが必要です".to_string()));
+            }
+            ClientId::Anonymous => (Arc::clone(&self.anonymous), self.max_anonymous),
+            ClientId::ApiKey(key) => {
+                let mut slots = self.slots.lock().unwrap();
+                // 許可証を保持していないクライアントのエントリを掃除する（メモリの無制限な増加を防止）。
+                // 許可証が生存している間はArcの参照カウントが2以上になる。
+                slots.retain(|_, sem| Arc::strong_count(sem) > 1);
+                let semaphore = Arc::clone(
+                    slots
+                        .entry(key.clone())
+                        .or_insert_with(|| Arc::new(Semaphore::new(self.max_per_client))),
+                );
+                (semaphore, self.max_per_client)
+            }
+        };
+
+        semaphore.try_acquire_owned().map_err(|_| {
+            GatewayError::TooManyRequests(format!("同時リクエスト数の上限に達しました（上限: {limit}）"))
+        })
+    }
+}
+
+/// リクエストのAPIキーを登録済みのキーと照合し、クライアントを識別する。
+/// 仕様書 §6.2
+///
+/// `X-API-Key` ヘッダを優先し、なければ `apikey` クエリパラメータを使用する。
+/// キーがなければ [`ClientId::Anonymous`]、未登録のキーは `GatewayError::Unauthorized` とする。
+/// ただし `api_keys` が空（キー未登録）の場合、未登録のキーは [`ClientId::Anonymous`] とする。
+pub(crate) fn authenticate(
+    api_keys: &HashSet<String>,
+    headers: &HeaderMap,
+    uri: &Uri,
+) -> Result<ClientId, GatewayError> {
+    let from_header = headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let from_query = || {
+        uri.query()?
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(name, _)| *name == API_KEY_QUERY_PARAM)
+            .map(|(_, value)| percent_decode(value))
+    };
+    match from_header.or_else(from_query).filter(|k| !k.is_empty()) {
+        None => Ok(ClientId::Anonymous),
+        Some(key) if api_keys.contains(&key) => Ok(ClientId::ApiKey(key)),
+        Some(_) if api_keys.is_empty() => Ok(ClientId::Anonymous),
+        Some(_) => Err(GatewayError::Unauthorized("APIキーが不正です".to_string())),
+    }
+}
+
+/// クエリパラメータ値のパーセントエンコーディング（`+` は空白）を復号する。
+fn percent_decode(value: &str) -> String {
+    let hex = |b: u8| (b as char).to_digit(16).map(|d| d as u8);
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let decoded = match bytes[i] {
+            b'%' if i + 2 < bytes.len() => hex(bytes[i + 1]).zip(hex(bytes[i + 2])),
+            _ => None,
+        };
+        match (decoded, bytes[i]) {
+            (Some((hi, lo)), _) => {
+                out.push(hi << 4 | lo);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => out.push(b' '),
+            (None, b) => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// クライアントを認証し、同時リクエスト数制限を適用するミドルウェア。
+/// 仕様書 §6.2
+///
+/// 識別した [`ClientId`] はリクエストのextensionに格納し、ハンドラから参照できるようにする。
+/// 処理枠はレスポンスの生成が完了するまで保持される。
+pub async fn limit_client_concurrency(
+    State(state): State<Arc<GatewayState>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, GatewayError> {
+    let client_id = authenticate(&state.api_keys, request.headers(), request.uri())?;
+    let _permit = state.client_limiter.try_acquire(&client_id).inspect_err(|_| {
+        tracing::warn!(client_id = %client_id, "クライアントの同時リクエスト数上限に達しました");
+    })?;
+    request.extensions_mut().insert(client_id);
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(k: &str) -> ClientId {
+        ClientId::ApiKey(k.to_string())
+    }
+
+    /// 上限まで枠を保持したクライアントは拒否され、別クライアントは処理できることを確認
+    #[test]
+    fn test_client_at_limit_is_throttled_while_other_proceeds() {
+        let limiter = ClientConcurrencyLimiter::new(2, DEFAULT_MAX_CONCURRENT_ANONYMOUS);
+
+        let _a1 = limiter.try_acquire(&key("key-a")).unwrap();
+        let _a2 = limiter.try_acquire(&key("key-a")).unwrap();
+
+        // key-aは上限に達している → 429
+        let err = limiter.try_acquire(&key("key-a")).unwrap_err();
+        assert!(matches!(err, GatewayError::TooManyRequests(_)));
+
+        // key-bは独立した枠を持つ
+        assert!(limiter.try_acquire(&key("key-b")).is_ok());
+    }
+
+    /// 許可証のDropで枠が解放されることを確認
+    #[test]
+    fn test_permit_released_on_drop() {
+        let limiter = ClientConcurrencyLimiter::new(1, DEFAULT_MAX_CONCURRENT_ANONYMOUS);
+
+        let permit = limiter.try_acquire(&key("key-a")).unwrap();
+        assert!(limiter.try_acquire(&key("key-a")).is_err());
+
+        drop(permit);
+        assert!(limiter.try_acquire(&key("key-a")).is_ok());
+    }
+
+    /// 上限0は1として扱われることを確認
+    #[test]
+    fn test_zero_limit_treated_as_one() {
+        let limiter = ClientConcurrencyLimiter::new(0, DEFAULT_MAX_CONCURRENT_ANONYMOUS);
+        assert_eq!(limiter.max_per_client, 1);
+        assert!(limiter.try_acquire(&key("key-a")).is_ok());
+    }
+
+    /// 匿名クライアントは専用の共有枠を使い、枠0では拒否されることを確認
+    #[test]
+    fn test_anonymous_bucket() {
+        let limiter = ClientConcurrencyLimiter::new(1, 2);
+        let _n1 = limiter.try_acquire(&ClientId::Anonymous).unwrap();
+        let _n2 = limiter.try_acquire(&ClientId::Anonymous).unwrap();
+        let err = limiter.try_acquire(&ClientId::Anonymous).unwrap_err();
+        assert!(matches!(err, GatewayError::TooManyRequests(_)));
+        // 匿名の枠が埋まっていても登録済みのキーは処理できる
+        assert!(limiter.try_acquire(&key("key-a")).is_ok());
+
+        let closed = ClientConcurrencyLimiter::new(1, 0);
+        let err = closed.try_acquire(&ClientId::Anonymous).unwrap_err();
+        assert!(matches!(err, GatewayError::Unauthorized(_)));
+    }
+
+    /// ヘッダ・クエリパラメータのAPIキーを登録済みのキーと照合することを確認
+    #[test]
+    fn test_authenticate() {
+        let api_keys: HashSet<String> = ["key-a".to_string(), "key b".to_string()].into();
+        let uri = |s: &str| s.parse::<Uri>().unwrap();
+        let mut with_header = HeaderMap::new();
+        with_header.insert(API_KEY_HEADER, "key-a".parse().unwrap());
+
+        let cases = [
+            (HeaderMap::new(), uri("/verify"), Some(ClientId::Anonymous)),
+            (with_header.clone(), uri("/verify"), Some(key("key-a"))),
+            (HeaderMap::new(), uri("/verify?apikey=key-a"), Some(key("key-a"))),
+            (HeaderMap::new(), uri("/verify?x=1&apikey=key%20b"), Some(key("key b"))),
+            // ヘッダが優先される
+            (with_header, uri("/verify?apikey=unknown"), Some(key("key-a"))),
+            (HeaderMap::new(), uri("/verify?apikey=unknown"), None),
+        ];
+        for (headers, uri, expected) in cases {
+            let result = authenticate(&api_keys, &headers, &uri);
+            match expected {
+                Some(id) => assert_eq!(result.unwrap(), id, "{uri}"),
+                None => assert!(matches!(result, Err(GatewayError::Unauthorized(_))), "{uri}"),
+            }
+        }
+
+        // キーが登録されていない場合、未登録のキーは拒否せず匿名クライアントとして扱う
+        let empty = HashSet::new();
+        for uri in [uri("/verify?apikey=unknown"), uri("/verify")] {
+            assert_eq!(authenticate(&empty, &HeaderMap::new(), &uri).unwrap(), ClientId::Anonymous);
+        }
+        let mut unknown_header = HeaderMap::new();
+        unknown_header.insert(API_KEY_HEADER, "unknown".parse().unwrap());
+        assert_eq!(
+            authenticate(&empty, &unknown_header, &uri("/verify")).unwrap(),
+            ClientId::Anonymous
+        );
+    }
+}
@@ -6,7 +6,7 @@
 //!
 //! ## 役割
 //! - クライアント認証（APIキー管理）
-//! - レート制限
+//! - レート制限（APIキーごとの同時リクエスト数制限を含む）
 //! - Temporary Storageへの署名付きURL発行
 //! - リクエストごとのリソース制限の付与
 //! - TEEへのリクエスト中継
@@ -17,14 +17,20 @@
 //! - `POST /verify` — TEEへのリクエスト中継 + Gateway認証署名付与
 //! - `POST /sign` — TEEへのリクエスト中継
 //! - `POST /sign-and-mint` — sign + ブロードキャスト代行
+//!
 //! NOTE: ノード情報はオンチェーン (GlobalConfig + TeeNodeAccount PDA) で管理。§6.2
 
 mod auth;
 mod config;
 mod endpoints;
 pub mod error;
+mod fee;
+mod idempotency;
+mod limiter;
 mod onchain;
+mod solana_rpc;
 pub mod storage;
+mod tee_client;
 
 use std::sync::Arc;
 
@@ -199,6 +205,66 @@ async fn main() -> anyhow::Result<()> {
         None => hardcoded_limits,
     };
 
+    // クライアント単位の同時リクエスト数上限（§6.2）
+    let max_concurrent_per_client = std::env::var("MAX_CONCURRENT_REQUESTS_PER_CLIENT")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(limiter::DEFAULT_MAX_CONCURRENT_PER_CLIENT);
+    let max_concurrent_anonymous = std::env::var("MAX_CONCURRENT_ANONYMOUS_REQUESTS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(limiter::DEFAULT_MAX_CONCURRENT_ANONYMOUS);
+    tracing::info!(
+        max_concurrent_per_client,
+        max_concurrent_anonymous,
+        "クライアント単位・匿名クライアント全体の同時リクエスト数上限"
+    );
+
+    // /sign-and-mint の冪等性キーの保持期間（§6.2）
+    let idempotency_ttl_secs = std::env::var("IDEMPOTENCY_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(idempotency::DEFAULT_IDEMPOTENCY_TTL_SECS);
+    let idempotency_max_entries = std::env::var("IDEMPOTENCY_MAX_ENTRIES")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(idempotency::DEFAULT_IDEMPOTENCY_MAX_ENTRIES);
+    tracing::info!(
+        idempotency_ttl_secs,
+        idempotency_max_entries,
+        "Idempotency-Keyの保持期間と保持数上限"
+    );
+
+    let tee_max_response_bytes = std::env::var("TEE_MAX_RESPONSE_BYTES")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(tee_client::DEFAULT_TEE_MAX_RESPONSE_BYTES);
+    tracing::info!(tee_max_response_bytes, "TEEレスポンスの読み取り上限");
+
+    // /verify を高優先度で処理するAPIキー（§6.4）
+    let priority_api_keys: std::collections::HashSet<String> = std::env::var("PRIORITY_API_KEYS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(str::to_string)
+        .collect();
+    tracing::info!(count = priority_api_keys.len(), "高優先度APIキーを設定しました");
+
+    // 登録済みのAPIキー（§6.2）。優先APIキーも登録済みとして扱う
+    let api_keys: std::collections::HashSet<String> = std::env::var("GATEWAY_API_KEYS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(str::to_string)
+        .chain(priority_api_keys.iter().cloned())
+        .collect();
+    tracing::info!(count = api_keys.len(), "APIキーを登録しました");
+
+    // content_hashのデプロイメント名前空間（§2.1）。TEEと同じ値を /health で公開する
+    let content_hash_namespace = std::env::var("CONTENT_HASH_NAMESPACE").unwrap_or_default();
+
     let state = Arc::new(GatewayState {
         tee_endpoint,
         http_client,
@@ -211,14 +277,39 @@ async fn main() -> anyhow::Result<()> {
         on_chain_resource_limits,
         max_upload_size: 2 * 1024 * 1024 * 1024, // 2GB
         presign_expiry_secs: 3600,
+        client_limiter: limiter::ClientConcurrencyLimiter::new(
+            max_concurrent_per_client,
+            max_concurrent_anonymous,
+        ),
+        idempotency_cache: idempotency::IdempotencyCache::new(
+            std::time::Duration::from_secs(idempotency_ttl_secs),
+            idempotency_max_entries,
+        ),
+        tee_max_response_bytes,
+        api_keys,
+        priority_api_keys,
+        content_hash_namespace,
     });
 
-    let app = axum::Router::new()
-        .route("/health", axum::routing::get(endpoints::handle_health))
-        .route("/upload-url", axum::routing::post(endpoints::handle_upload_url))
+    // TEEに中継するエンドポイントにはクライアント単位の同時リクエスト数制限を適用
+    let relay_routes = axum::Router::new()
         .route("/verify", axum::routing::post(endpoints::handle_verify))
         .route("/sign", axum::routing::post(endpoints::handle_sign))
         .route("/sign-and-mint", axum::routing::post(endpoints::handle_sign_and_mint))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            limiter::limit_client_concurrency,
+        ));
+
+    let app = axum::Router::new()
+        .route("/health", axum::routing::get(endpoints::handle_health))
+        .route("/upload-url", axum::routing::post(endpoints::handle_upload_url))
+        // 取り消しは処理中の/verifyと並行して届くため、同時リクエスト数制限の対象外とする
+        .route(
+            "/verify/{token}",
+            axum::routing::delete(endpoints::handle_cancel_verify),
+        )
+        .merge(relay_routes)
         .with_state(state);
 
     let addr = "0.0.0.0:3000";
@@ -239,7 +330,8 @@ mod tests {
     use storage::{PresignedUrls, TempStorage};
 
     use axum::extract::State;
-    use axum::Json;
+    use axum::http::HeaderMap;
+    use axum::{Extension, Json};
     use base64::Engine;
 
     /// テスト用のモックTempStorage。
@@ -262,13 +354,21 @@ mod tests {
 
     /// テスト用GatewayStateを構築するヘルパー
     fn test_state(tee_endpoint: &str) -> Arc<GatewayState> {
+        test_state_with_storage(tee_endpoint, Box::new(MockTempStorage))
+    }
+
+    /// 指定のTempStorageでテスト用GatewayStateを構築するヘルパー
+    fn test_state_with_storage(
+        tee_endpoint: &str,
+        temp_storage: Box<dyn TempStorage>,
+    ) -> Arc<GatewayState> {
         let signing_key = Ed25519SigningKey::generate(&mut rand::rngs::OsRng);
 
         Arc::new(GatewayState {
             tee_endpoint: tee_endpoint.to_string(),
             http_client: reqwest::Client::new(),
             signing_key,
-            temp_storage: Box::new(MockTempStorage),
+            temp_storage,
             signed_json_storage: None,
             solana_rpc_url: None,
             solana_keypair: None,
@@ -284,9 +384,37 @@ mod tests {
             on_chain_resource_limits: None,
             max_upload_size: 1024,
             presign_expiry_secs: 3600,
+            client_limiter: limiter::ClientConcurrencyLimiter::new(
+                limiter::DEFAULT_MAX_CONCURRENT_PER_CLIENT,
+                limiter::DEFAULT_MAX_CONCURRENT_ANONYMOUS,
+            ),
+            idempotency_cache: idempotency::IdempotencyCache::new(
+                std::time::Duration::from_secs(idempotency::DEFAULT_IDEMPOTENCY_TTL_SECS),
+                idempotency::DEFAULT_IDEMPOTENCY_MAX_ENTRIES,
+            ),
+            tee_max_response_bytes: tee_client::DEFAULT_TEE_MAX_RESPONSE_BYTES,
+            api_keys: Default::default(),
+            priority_api_keys: Default::default(),
+            content_hash_namespace: String::new(),
         })
     }
 
+    /// /health がプロトコル版・アルゴリズム・ABIバージョンをcapabilitiesとして返すことを確認
+    #[tokio::test]
+    async fn test_health_reports_protocol_capabilities() {
+        let state = test_state("http://127.0.0.1:1");
+        let Json(body) = handle_health(State(state)).await;
+
+        assert_eq!(body["status"], "ok");
+        let capabilities = &body["capabilities"];
+        assert_eq!(capabilities["store_signed_json"], false);
+        assert_eq!(capabilities["protocol_version"], "Title-v1");
+        assert_eq!(capabilities["signature_algorithm"], "Ed25519");
+        assert_eq!(capabilities["content_hash_algorithm"], "SHA-256");
+        assert_eq!(capabilities["wasm_abi_versions"], serde_json::json!([1, 2]));
+        assert_eq!(capabilities["content_hash_namespace"], "");
+    }
+
     /// Gateway認証ラッパーの署名が正しく構築・検証できることを確認
     #[test]
     fn test_gateway_auth_roundtrip() {
@@ -328,14 +456,21 @@ mod tests {
             body: wrapper.body.clone(),
             resource_limits: wrapper.resource_limits.clone(),
         };
-        let sign_bytes = serde_json::to_vec(&sign_target).unwrap();
+        let sign_bytes =
+            title_types::canonical_json(&serde_json::to_value(&sign_target).unwrap());
 
         let sig_bytes = b64().decode(&wrapper.gateway_signature).unwrap();
         let sig_arr: [u8; 64] = sig_bytes.try_into().unwrap();
         let signature = ed25519_dalek::Signature::from_bytes(&sig_arr);
 
         assert!(
-            title_crypto::ed25519_verify(&verifying_key, &sign_bytes, &signature).is_ok(),
+            title_crypto::ed25519_verify_in_domain(
+                &verifying_key,
+                title_crypto::SignatureDomain::GatewayAuth,
+                &sign_bytes,
+                &signature
+            )
+            .is_ok(),
             "Gateway署名の検証に失敗"
         );
     }
@@ -359,14 +494,21 @@ mod tests {
             body: wrapper.body.clone(),
             resource_limits: wrapper.resource_limits.clone(),
         };
-        let sign_bytes = serde_json::to_vec(&sign_target).unwrap();
+        let sign_bytes =
+            title_types::canonical_json(&serde_json::to_value(&sign_target).unwrap());
 
         let sig_bytes = b64().decode(&wrapper.gateway_signature).unwrap();
         let sig_arr: [u8; 64] = sig_bytes.try_into().unwrap();
         let signature = ed25519_dalek::Signature::from_bytes(&sig_arr);
 
         assert!(
-            title_crypto::ed25519_verify(&other_verifying_key, &sign_bytes, &signature).is_err(),
+            title_crypto::ed25519_verify_in_domain(
+                &other_verifying_key,
+                title_crypto::SignatureDomain::GatewayAuth,
+                &sign_bytes,
+                &signature
+            )
+            .is_err(),
             "異なる公開鍵での検証が成功してしまった"
         );
     }
@@ -415,6 +557,45 @@ mod tests {
         assert!(response.expires_at > 0);
     }
 
+    /// /upload-urlで発行したURLでアップロード・ダウンロードし、バイト列が一致することを確認
+    #[tokio::test]
+    async fn test_upload_url_roundtrip_with_in_memory_storage() {
+        let storage = storage::InMemoryTempStorage::start().await;
+        let state = test_state_with_storage("http://localhost:4000", Box::new(storage));
+
+        let response = handle_upload_url(
+            State(state),
+            Json(UploadUrlRequest {
+                content_size: 512,
+                content_type: "application/octet-stream".to_string(),
+            }),
+        )
+        .await
+        .unwrap()
+        .0;
+
+        // 暗号化ペイロードを模したランダムなバイト列
+        let payload: Vec<u8> = (0..512).map(|_| rand::random::<u8>()).collect();
+        let client = reqwest::Client::new();
+        let put = client
+            .put(&response.upload_url)
+            .body(payload.clone())
+            .send()
+            .await
+            .unwrap();
+        assert!(put.status().is_success());
+
+        let downloaded = client
+            .get(&response.download_url)
+            .send()
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        assert_eq!(downloaded.to_vec(), payload);
+    }
+
     /// モックTEEサーバーを起動し、/verify中継が正しく動作することを確認
     #[tokio::test]
     async fn test_verify_relay() {
@@ -447,17 +628,27 @@ mod tests {
 
         let result = handle_verify(
             State(state),
+            Extension(limiter::ClientId::Anonymous),
             Json(VerifyRequest {
                 download_url: "http://example.com/payload".to_string(),
                 processor_ids: vec!["core-c2pa".to_string()],
+                max_graph_size: None,
+                max_returned_nodes: None,
+                include_assertions: false,
+                include_preview_hash: false,
+                include_claim_generators: false,
+                compact_graph: false,
+                cancel_token: None,
+                priority: None,
+                depends_on: Default::default(),
             }),
         )
         .await;
 
         assert!(result.is_ok(), "handle_verify failed: {:?}", result.err());
         let response = result.unwrap().0;
-        assert!(response.get("nonce").is_some());
-        assert!(response.get("ciphertext").is_some());
+        assert_eq!(response.nonce, "dGVzdG5vbmNlMTIz");
+        assert_eq!(response.ciphertext, "ZW5jcnlwdGVk");
     }
 
     /// モックTEEサーバーを起動し、/sign中継が正しく動作することを確認
@@ -523,9 +714,19 @@ mod tests {
 
         let result = handle_verify(
             State(state),
+            Extension(limiter::ClientId::Anonymous),
             Json(VerifyRequest {
                 download_url: "http://example.com/payload".to_string(),
                 processor_ids: vec!["core-c2pa".to_string()],
+                max_graph_size: None,
+                max_returned_nodes: None,
+                include_assertions: false,
+                include_preview_hash: false,
+                include_claim_generators: false,
+                compact_graph: false,
+                cancel_token: None,
+                priority: None,
+                depends_on: Default::default(),
             }),
         )
         .await;
@@ -536,6 +737,187 @@ mod tests {
         assert_eq!(response.status(), axum::http::StatusCode::BAD_GATEWAY);
     }
 
+    /// /verify のpriorityが優先APIキーに応じて決定されてTEEに中継されることを確認
+    #[tokio::test]
+    async fn test_verify_relays_priority_by_api_key() {
+        let relayed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let captured = relayed.clone();
+        let mock_tee = axum::Router::new().route(
+            "/verify",
+            axum::routing::post(move |Json(body): Json<serde_json::Value>| {
+                let captured = captured.clone();
+                async move {
+                    captured.lock().unwrap().push(body["body"]["priority"].clone());
+                    Json(serde_json::json!({ "nonce": "bm9uY2U=", "ciphertext": "Y3Q=" }))
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, mock_tee).await.unwrap();
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let mut state = Arc::into_inner(test_state(&format!("http://127.0.0.1:{port}"))).unwrap();
+        state.priority_api_keys.insert("premium-key".to_string());
+        state.api_keys.insert("premium-key".to_string());
+        let state = Arc::new(state);
+
+        for (api_key, requested) in [
+            (None, Some(VerifyPriority::High)),
+            (None, Some(VerifyPriority::Low)),
+            (Some("premium-key"), None),
+            (Some("premium-key"), Some(VerifyPriority::Low)),
+        ] {
+            // SDKと同じく `apikey` クエリパラメータでキーを渡す
+            let uri: axum::http::Uri = match api_key {
+                Some(key) => format!("/verify?apikey={key}").parse().unwrap(),
+                None => "/verify".parse().unwrap(),
+            };
+            let client_id =
+                limiter::authenticate(&state.api_keys, &HeaderMap::new(), &uri).unwrap();
+            let result = handle_verify(
+                State(state.clone()),
+                Extension(client_id),
+                Json(VerifyRequest {
+                    download_url: "http://example.com/payload".to_string(),
+                    processor_ids: vec!["core-c2pa".to_string()],
+                    max_graph_size: None,
+                    max_returned_nodes: None,
+                    include_assertions: false,
+                    include_preview_hash: false,
+                    include_claim_generators: false,
+                    compact_graph: false,
+                    cancel_token: None,
+                    priority: requested,
+                    depends_on: Default::default(),
+                }),
+            )
+            .await;
+            assert!(result.is_ok(), "handle_verify failed: {:?}", result.err());
+        }
+
+        // 優先APIキー以外のhighはnormalに引き下げ、優先APIキーの未指定はhighとする
+        assert_eq!(
+            *relayed.lock().unwrap(),
+            vec![
+                serde_json::json!("normal"),
+                serde_json::json!("low"),
+                serde_json::json!("high"),
+                serde_json::json!("low"),
+            ]
+        );
+    }
+
+    /// パスとして解釈される文字を含むcancel_tokenはTEEに中継せず拒否されることを確認
+    #[tokio::test]
+    async fn test_cancel_verify_rejects_invalid_token() {
+        // 到達不能なTEEエンドポイント（中継されればBAD_GATEWAYとなる）
+        let state = test_state("http://127.0.0.1:1");
+
+        for token in ["", "../sign", "a/b", &"x".repeat(129)] {
+            let err = endpoints::handle_cancel_verify(
+                State(state.clone()),
+                Extension(limiter::ClientId::Anonymous),
+                axum::extract::Path(token.to_string()),
+                Json(CancelVerifyRequest {
+                    download_url: "https://storage.example/payload".to_string(),
+                }),
+            )
+            .await
+            .unwrap_err();
+            assert!(matches!(err, error::GatewayError::BadRequest(_)), "{token}: {err}");
+        }
+    }
+
+    /// cancel_tokenはクライアントごとに独立したトークンとして中継され、
+    /// 取り消しも同じクライアントのトークンにのみ作用することを確認
+    #[tokio::test]
+    async fn test_cancel_token_is_scoped_per_client() {
+        let relayed = Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+        let captured = relayed.clone();
+        let cancelled = Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+        let captured_cancel = cancelled.clone();
+        let mock_tee = axum::Router::new()
+            .route(
+                "/verify",
+                axum::routing::post(move |Json(body): Json<serde_json::Value>| {
+                    let captured = captured.clone();
+                    async move {
+                        let token = body["body"]["cancel_token"].as_str().unwrap().to_string();
+                        captured.lock().unwrap().push(token);
+                        Json(serde_json::json!({ "nonce": "bm9uY2U=", "ciphertext": "Y3Q=" }))
+                    }
+                }),
+            )
+            .route(
+                "/verify/{token}",
+                axum::routing::delete(
+                    move |axum::extract::Path(token): axum::extract::Path<String>| {
+                        let captured = captured_cancel.clone();
+                        async move {
+                            captured.lock().unwrap().push(token);
+                            axum::http::StatusCode::NO_CONTENT
+                        }
+                    },
+                ),
+            );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, mock_tee).await.unwrap();
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let state = test_state(&format!("http://127.0.0.1:{port}"));
+
+        let clients = [
+            limiter::ClientId::ApiKey("key-a".to_string()),
+            limiter::ClientId::ApiKey("key-b".to_string()),
+            limiter::ClientId::Anonymous,
+        ];
+        for client_id in &clients {
+            let _ = handle_verify(
+                State(state.clone()),
+                Extension(client_id.clone()),
+                Json(VerifyRequest {
+                    download_url: "http://example.com/payload".to_string(),
+                    processor_ids: vec!["core-c2pa".to_string()],
+                    max_graph_size: None,
+                    max_returned_nodes: None,
+                    include_assertions: false,
+                    include_preview_hash: false,
+                    include_claim_generators: false,
+                    compact_graph: false,
+                    cancel_token: Some("job-1".to_string()),
+                    priority: None,
+                    depends_on: Default::default(),
+                }),
+            )
+            .await
+            .unwrap();
+        }
+        endpoints::handle_cancel_verify(
+            State(state.clone()),
+            Extension(clients[0].clone()),
+            axum::extract::Path("job-1".to_string()),
+            Json(CancelVerifyRequest {
+                download_url: "http://example.com/payload".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        let relayed = relayed.lock().unwrap().clone();
+        assert!(relayed.iter().all(|token| token != "job-1"));
+        assert_ne!(relayed[0], relayed[1]);
+        assert_ne!(relayed[0], relayed[2]);
+        assert_ne!(relayed[1], relayed[2]);
+        assert_eq!(*cancelled.lock().unwrap(), vec![relayed[0].clone()]);
+    }
+
     /// /sign-and-mint — SOLANA_RPC_URL未設定時にエラーが返ることを確認
     #[tokio::test]
     async fn test_sign_and_mint_no_rpc_url() {
@@ -544,6 +926,8 @@ mod tests {
 
         let result = handle_sign_and_mint(
             State(state),
+            Extension(limiter::ClientId::Anonymous),
+            HeaderMap::new(),
             Json(endpoints::SignAndMintInput {
                 recent_blockhash: "11111111111111111111111111111111".to_string(),
                 requests: vec![endpoints::SignAndMintItem {
@@ -588,10 +972,24 @@ mod tests {
             on_chain_resource_limits: None,
             max_upload_size: 1024,
             presign_expiry_secs: 3600,
+            client_limiter: limiter::ClientConcurrencyLimiter::new(
+                limiter::DEFAULT_MAX_CONCURRENT_PER_CLIENT,
+                limiter::DEFAULT_MAX_CONCURRENT_ANONYMOUS,
+            ),
+            idempotency_cache: idempotency::IdempotencyCache::new(
+                std::time::Duration::from_secs(idempotency::DEFAULT_IDEMPOTENCY_TTL_SECS),
+                idempotency::DEFAULT_IDEMPOTENCY_MAX_ENTRIES,
+            ),
+            tee_max_response_bytes: tee_client::DEFAULT_TEE_MAX_RESPONSE_BYTES,
+            api_keys: Default::default(),
+            priority_api_keys: Default::default(),
+            content_hash_namespace: String::new(),
         });
 
         let result = handle_sign_and_mint(
             State(state),
+            Extension(limiter::ClientId::Anonymous),
+            HeaderMap::new(),
             Json(endpoints::SignAndMintInput {
                 recent_blockhash: "11111111111111111111111111111111".to_string(),
                 requests: vec![endpoints::SignAndMintItem {
@@ -635,10 +1033,24 @@ mod tests {
             on_chain_resource_limits: None,
             max_upload_size: 1024,
             presign_expiry_secs: 3600,
+            client_limiter: limiter::ClientConcurrencyLimiter::new(
+                limiter::DEFAULT_MAX_CONCURRENT_PER_CLIENT,
+                limiter::DEFAULT_MAX_CONCURRENT_ANONYMOUS,
+            ),
+            idempotency_cache: idempotency::IdempotencyCache::new(
+                std::time::Duration::from_secs(idempotency::DEFAULT_IDEMPOTENCY_TTL_SECS),
+                idempotency::DEFAULT_IDEMPOTENCY_MAX_ENTRIES,
+            ),
+            tee_max_response_bytes: tee_client::DEFAULT_TEE_MAX_RESPONSE_BYTES,
+            api_keys: Default::default(),
+            priority_api_keys: Default::default(),
+            content_hash_namespace: String::new(),
         });
 
         let result = handle_sign_and_mint(
             State(state),
+            Extension(limiter::ClientId::Anonymous),
+            HeaderMap::new(),
             Json(endpoints::SignAndMintInput {
                 recent_blockhash: "11111111111111111111111111111111".to_string(),
                 requests: vec![endpoints::SignAndMintItem {
@@ -682,10 +1094,24 @@ mod tests {
             on_chain_resource_limits: None,
             max_upload_size: 1024,
             presign_expiry_secs: 3600,
+            client_limiter: limiter::ClientConcurrencyLimiter::new(
+                limiter::DEFAULT_MAX_CONCURRENT_PER_CLIENT,
+                limiter::DEFAULT_MAX_CONCURRENT_ANONYMOUS,
+            ),
+            idempotency_cache: idempotency::IdempotencyCache::new(
+                std::time::Duration::from_secs(idempotency::DEFAULT_IDEMPOTENCY_TTL_SECS),
+                idempotency::DEFAULT_IDEMPOTENCY_MAX_ENTRIES,
+            ),
+            tee_max_response_bytes: tee_client::DEFAULT_TEE_MAX_RESPONSE_BYTES,
+            api_keys: Default::default(),
+            priority_api_keys: Default::default(),
+            content_hash_namespace: String::new(),
         });
 
         let result = handle_sign_and_mint(
             State(state),
+            Extension(limiter::ClientId::Anonymous),
+            HeaderMap::new(),
             Json(endpoints::SignAndMintInput {
                 recent_blockhash: "11111111111111111111111111111111".to_string(),
                 requests: vec![endpoints::SignAndMintItem {
@@ -703,4 +1129,548 @@ mod tests {
             "バリデーションエラーメッセージが期待と異なる: {err_msg}"
         );
     }
+
+    /// TEEが返す部分署名済みトランザクション（fee payer = Gatewayウォレット, 署名者2名）を生成する。
+    fn test_partial_tx(gateway_keypair: &solana_sdk::signer::keypair::Keypair) -> String {
+        use solana_sdk::signer::Signer;
+
+        let tee_pubkey = solana_sdk::pubkey::Pubkey::new_unique();
+        let ix = solana_sdk::instruction::Instruction::new_with_bytes(
+            solana_sdk::pubkey::Pubkey::new_unique(),
+            &[],
+            vec![solana_sdk::instruction::AccountMeta::new_readonly(tee_pubkey, true)],
+        );
+        let message = solana_sdk::message::Message::new_with_blockhash(
+            &[ix],
+            Some(&gateway_keypair.pubkey()),
+            &solana_sdk::hash::Hash::default(),
+        );
+        b64().encode(bincode::serialize(&solana_sdk::transaction::Transaction::new_unsigned(message)).unwrap())
+    }
+
+    /// sign-and-mint用のGatewayStateを生成する。
+    fn sign_and_mint_state(
+        tee_endpoint: &str,
+        solana_rpc_url: &str,
+        gateway_keypair: solana_sdk::signer::keypair::Keypair,
+    ) -> Arc<GatewayState> {
+        Arc::new(GatewayState {
+            tee_endpoint: tee_endpoint.to_string(),
+            http_client: reqwest::Client::new(),
+            signing_key: Ed25519SigningKey::generate(&mut rand::rngs::OsRng),
+            temp_storage: Box::new(MockTempStorage),
+            signed_json_storage: None,
+            solana_rpc_url: Some(solana_rpc_url.to_string()),
+            solana_keypair: Some(gateway_keypair),
+            default_resource_limits: ResourceLimits {
+                max_single_content_bytes: Some(1024),
+                max_concurrent_bytes: None,
+                min_upload_speed_bytes: None,
+                base_processing_time_sec: None,
+                max_global_timeout_sec: None,
+                chunk_read_timeout_sec: None,
+                c2pa_max_graph_size: None,
+            },
+            on_chain_resource_limits: None,
+            max_upload_size: 1024,
+            presign_expiry_secs: 3600,
+            client_limiter: limiter::ClientConcurrencyLimiter::new(
+                limiter::DEFAULT_MAX_CONCURRENT_PER_CLIENT,
+                limiter::DEFAULT_MAX_CONCURRENT_ANONYMOUS,
+            ),
+            idempotency_cache: idempotency::IdempotencyCache::new(
+                std::time::Duration::from_secs(idempotency::DEFAULT_IDEMPOTENCY_TTL_SECS),
+                idempotency::DEFAULT_IDEMPOTENCY_MAX_ENTRIES,
+            ),
+            tee_max_response_bytes: tee_client::DEFAULT_TEE_MAX_RESPONSE_BYTES,
+            api_keys: Default::default(),
+            priority_api_keys: Default::default(),
+            content_hash_namespace: String::new(),
+        })
+    }
+
+    /// 部分署名済みトランザクションを返すモックTEE（/sign）を起動し、ポートを返す。
+    async fn spawn_mock_tee_sign(partial_tx: String) -> u16 {
+        let mock = axum::Router::new().route(
+            "/sign",
+            axum::routing::post(move || {
+                let partial_tx = partial_tx.clone();
+                async move { Json(serde_json::json!({ "partial_txs": [partial_tx] })) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, mock).await.unwrap();
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        port
+    }
+
+    /// 単一のsigned_json_uriを持つsign-and-mintリクエストを生成する。
+    fn sign_and_mint_input(recent_blockhash: &str) -> endpoints::SignAndMintInput {
+        endpoints::SignAndMintInput {
+            recent_blockhash: recent_blockhash.to_string(),
+            requests: vec![endpoints::SignAndMintItem {
+                signed_json_uri: "ar://test".to_string(),
+                signed_json: None,
+            }],
+        }
+    }
+
+    /// /sign-and-mint — Gatewayウォレットの残高不足時にブロードキャスト前に拒否されることを確認
+    #[tokio::test]
+    async fn test_sign_and_mint_insufficient_balance() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let gateway_keypair = solana_sdk::signer::keypair::Keypair::new();
+
+        let partial_tx = test_partial_tx(&gateway_keypair);
+
+        // モックTEE（/sign）+ モックSolana RPC（/rpc, 残高1 lamport）
+        let broadcasted = Arc::new(AtomicBool::new(false));
+        let broadcasted_rpc = broadcasted.clone();
+        let mock = axum::Router::new()
+            .route(
+                "/sign",
+                axum::routing::post(move || {
+                    let partial_tx = partial_tx.clone();
+                    async move { Json(serde_json::json!({ "partial_txs": [partial_tx] })) }
+                }),
+            )
+            .route(
+                "/rpc",
+                axum::routing::post(move |Json(req): Json<serde_json::Value>| {
+                    let broadcasted = broadcasted_rpc.clone();
+                    async move {
+                        match req["method"].as_str().unwrap() {
+                            "getBalance" => Json(serde_json::json!({
+                                "jsonrpc": "2.0", "id": 1,
+                                "result": { "context": { "slot": 1 }, "value": 1 }
+                            })),
+                            "getFeeForMessage" => Json(serde_json::json!({
+                                "jsonrpc": "2.0", "id": 1,
+                                "result": { "context": { "slot": 1 }, "value": 7777 }
+                            })),
+                            _ => {
+                                broadcasted.store(true, Ordering::SeqCst);
+                                Json(serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": "sig" }))
+                            }
+                        }
+                    }
+                }),
+            );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, mock).await.unwrap();
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let state = sign_and_mint_state(
+            &format!("http://127.0.0.1:{port}"),
+            &format!("http://127.0.0.1:{port}/rpc"),
+            gateway_keypair,
+        );
+
+        let result = handle_sign_and_mint(
+            State(state),
+            Extension(limiter::ClientId::Anonymous),
+            HeaderMap::new(),
+            Json(endpoints::SignAndMintInput {
+                recent_blockhash: "11111111111111111111111111111111".to_string(),
+                requests: vec![endpoints::SignAndMintItem {
+                    signed_json_uri: "ar://test".to_string(),
+                    signed_json: None,
+                }],
+            }),
+        )
+        .await;
+
+        let err = result.unwrap_err();
+        assert!(
+            matches!(err, error::GatewayError::InsufficientFunds(_)),
+            "残高不足エラーが期待される: {err}"
+        );
+        // 必要額はgetFeeForMessageが返した手数料
+        assert!(err.to_string().contains("必要: 7777 lamports"), "{err}");
+        assert!(
+            !broadcasted.load(Ordering::SeqCst),
+            "残高不足時にブロードキャストしてはならない"
+        );
+    }
+
+    /// /sign-and-mint — Solana RPCに到達できない場合、再試行可能な503と使用したblockhashを返すことを確認
+    #[tokio::test]
+    async fn test_sign_and_mint_rpc_unreachable() {
+        let gateway_keypair = solana_sdk::signer::keypair::Keypair::new();
+        let tee_port = spawn_mock_tee_sign(test_partial_tx(&gateway_keypair)).await;
+
+        // バインド後に解放したポート（接続拒否される）
+        let closed_port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+
+        let state = sign_and_mint_state(
+            &format!("http://127.0.0.1:{tee_port}"),
+            &format!("http://127.0.0.1:{closed_port}/rpc"),
+            gateway_keypair,
+        );
+        let blockhash = "11111111111111111111111111111111";
+
+        let err = handle_sign_and_mint(
+            State(state),
+            Extension(limiter::ClientId::Anonymous),
+            HeaderMap::new(),
+            Json(sign_and_mint_input(blockhash)),
+        )
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, error::GatewayError::SolanaUnavailable(_)),
+            "RPC到達不能エラーが期待される: {err}"
+        );
+        let msg = err.to_string();
+        assert!(msg.contains("再試行"), "再試行可能であることが明示されるべき: {msg}");
+        assert!(msg.contains(blockhash), "使用したblockhashが含まれるべき: {msg}");
+
+        let response = axum::response::IntoResponse::into_response(err);
+        assert_eq!(response.status(), axum::http::StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    /// /sign-and-mint — sendTransactionが一時的に失敗した場合、同一トランザクションを再送して
+    /// 署名を1つだけ返すことを確認
+    #[tokio::test]
+    async fn test_sign_and_mint_retries_transient_send_failure() {
+        use axum::response::IntoResponse;
+        use std::sync::Mutex;
+
+        let gateway_keypair = solana_sdk::signer::keypair::Keypair::new();
+        let tee_port = spawn_mock_tee_sign(test_partial_tx(&gateway_keypair)).await;
+
+        // 1回目のsendTransactionは503、2回目は成功
+        let sent: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let sent_rpc = sent.clone();
+        let rpc = axum::Router::new().route(
+            "/rpc",
+            axum::routing::post(move |Json(req): Json<serde_json::Value>| {
+                let sent = sent_rpc.clone();
+                async move {
+                    match req["method"].as_str().unwrap() {
+                        "getBalance" => Json(serde_json::json!({
+                            "jsonrpc": "2.0", "id": 1,
+                            "result": { "context": { "slot": 1 }, "value": 1_000_000_000u64 }
+                        }))
+                        .into_response(),
+                        "getFeeForMessage" => Json(serde_json::json!({
+                            "jsonrpc": "2.0", "id": 1,
+                            "result": { "context": { "slot": 1 }, "value": 10_000 }
+                        }))
+                        .into_response(),
+                        _ => {
+                            let mut sent = sent.lock().unwrap();
+                            sent.push(req["params"][0].as_str().unwrap().to_string());
+                            if sent.len() == 1 {
+                                axum::http::StatusCode::SERVICE_UNAVAILABLE.into_response()
+                            } else {
+                                Json(serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": "sig-1" }))
+                                    .into_response()
+                            }
+                        }
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, rpc).await.unwrap();
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let state = sign_and_mint_state(
+            &format!("http://127.0.0.1:{tee_port}"),
+            &format!("http://127.0.0.1:{rpc_port}/rpc"),
+            gateway_keypair,
+        );
+
+        let response = handle_sign_and_mint(
+            State(state),
+            Extension(limiter::ClientId::Anonymous),
+            HeaderMap::new(),
+            Json(sign_and_mint_input("11111111111111111111111111111111")),
+        )
+        .await
+        .unwrap()
+        .0;
+        assert_eq!(response.tx_signatures, vec!["sig-1".to_string()]);
+
+        // 再送は同一の署名済みトランザクション
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0], sent[1]);
+    }
+
+    /// /sign-and-mint — 同じIdempotency-Keyでの再試行は再ブロードキャストせず、
+    /// 同一のレスポンスを返すことを確認
+    #[tokio::test]
+    async fn test_sign_and_mint_idempotency_key_single_broadcast() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let gateway_keypair = solana_sdk::signer::keypair::Keypair::new();
+        let tee_port = spawn_mock_tee_sign(test_partial_tx(&gateway_keypair)).await;
+
+        let broadcasts = Arc::new(AtomicUsize::new(0));
+        let broadcasts_rpc = broadcasts.clone();
+        let rpc = axum::Router::new().route(
+            "/rpc",
+            axum::routing::post(move |Json(req): Json<serde_json::Value>| {
+                let broadcasts = broadcasts_rpc.clone();
+                async move {
+                    match req["method"].as_str().unwrap() {
+                        "getBalance" => Json(serde_json::json!({
+                            "jsonrpc": "2.0", "id": 1,
+                            "result": { "context": { "slot": 1 }, "value": 1_000_000_000u64 }
+                        })),
+                        "getFeeForMessage" => Json(serde_json::json!({
+                            "jsonrpc": "2.0", "id": 1,
+                            "result": { "context": { "slot": 1 }, "value": 10_000 }
+                        })),
+                        _ => {
+                            let n = broadcasts.fetch_add(1, Ordering::SeqCst) + 1;
+                            Json(serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": format!("sig-{n}") }))
+                        }
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, rpc).await.unwrap();
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let state = sign_and_mint_state(
+            &format!("http://127.0.0.1:{tee_port}"),
+            &format!("http://127.0.0.1:{rpc_port}/rpc"),
+            gateway_keypair,
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(idempotency::IDEMPOTENCY_KEY_HEADER, "mint-42".parse().unwrap());
+
+        let mut responses = Vec::new();
+        for _ in 0..2 {
+            let response = handle_sign_and_mint(
+                State(state.clone()),
+                Extension(limiter::ClientId::Anonymous),
+                headers.clone(),
+                Json(sign_and_mint_input("11111111111111111111111111111111")),
+            )
+            .await
+            .unwrap()
+            .0;
+            responses.push(response);
+        }
+
+        assert_eq!(broadcasts.load(Ordering::SeqCst), 1, "ブロードキャストは1回のみであるべき");
+        assert_eq!(responses[0], responses[1]);
+        assert_eq!(responses[0].tx_signatures, vec!["sig-1".to_string()]);
+
+        // キーなしのリクエストは従来どおり毎回ブロードキャストする
+        let _ = handle_sign_and_mint(
+            State(state),
+            Extension(limiter::ClientId::Anonymous),
+            HeaderMap::new(),
+            Json(sign_and_mint_input("11111111111111111111111111111111")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(broadcasts.load(Ordering::SeqCst), 2);
+    }
+
+    /// /sign-and-mint — 最終署名後にブロードキャストが失敗した場合、同じIdempotency-Keyでの
+    /// 再試行はTEEに再署名させず同一のトランザクションを再送し、別の本文では拒否することを確認
+    #[tokio::test]
+    async fn test_sign_and_mint_idempotency_key_rebroadcasts_after_failure() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Mutex;
+
+        let gateway_keypair = solana_sdk::signer::keypair::Keypair::new();
+        let partial_tx = test_partial_tx(&gateway_keypair);
+        let tee_calls = Arc::new(AtomicUsize::new(0));
+        let tee_calls_mock = tee_calls.clone();
+        let tee = axum::Router::new().route(
+            "/sign",
+            axum::routing::post(move || {
+                let partial_tx = partial_tx.clone();
+                tee_calls_mock.fetch_add(1, Ordering::SeqCst);
+                async move { Json(serde_json::json!({ "partial_txs": [partial_tx] })) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let tee_port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, tee).await.unwrap();
+        });
+
+        // 1回目のsendTransactionは拒否、2回目以降は成功
+        let sent = Arc::new(Mutex::new(Vec::<String>::new()));
+        let sent_rpc = sent.clone();
+        let rpc = axum::Router::new().route(
+            "/rpc",
+            axum::routing::post(move |Json(req): Json<serde_json::Value>| {
+                let sent = sent_rpc.clone();
+                async move {
+                    if req["method"] == "getBalance" {
+                        return Json(serde_json::json!({
+                            "jsonrpc": "2.0", "id": 1,
+                            "result": { "context": { "slot": 1 }, "value": 1_000_000_000u64 }
+                        }));
+                    }
+                    if req["method"] == "getFeeForMessage" {
+                        return Json(serde_json::json!({
+                            "jsonrpc": "2.0", "id": 1,
+                            "result": { "context": { "slot": 1 }, "value": 10_000 }
+                        }));
+                    }
+                    let mut sent = sent.lock().unwrap();
+                    sent.push(req["params"][0].as_str().unwrap().to_string());
+                    if sent.len() == 1 {
+                        Json(serde_json::json!({
+                            "jsonrpc": "2.0", "id": 1,
+                            "error": { "code": -32002, "message": "Transaction simulation failed" }
+                        }))
+                    } else {
+                        Json(serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": "sig-1" }))
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, rpc).await.unwrap();
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let state = sign_and_mint_state(
+            &format!("http://127.0.0.1:{tee_port}"),
+            &format!("http://127.0.0.1:{rpc_port}/rpc"),
+            gateway_keypair,
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            idempotency::IDEMPOTENCY_KEY_HEADER,
+            "mint-7".parse().unwrap(),
+        );
+        let blockhash = "11111111111111111111111111111111";
+
+        let err = handle_sign_and_mint(
+            State(state.clone()),
+            Extension(limiter::ClientId::Anonymous),
+            headers.clone(),
+            Json(sign_and_mint_input(blockhash)),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            matches!(err, error::GatewayError::TransactionRejected(_)),
+            "{err}"
+        );
+
+        // 別の本文での再利用は拒否される
+        let mut other = sign_and_mint_input(blockhash);
+        other.requests[0].signed_json_uri = "ar://other".to_string();
+        let err = handle_sign_and_mint(
+            State(state.clone()),
+            Extension(limiter::ClientId::Anonymous),
+            headers.clone(),
+            Json(other),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, error::GatewayError::Conflict(_)), "{err}");
+
+        let response = handle_sign_and_mint(
+            State(state),
+            Extension(limiter::ClientId::Anonymous),
+            headers,
+            Json(sign_and_mint_input(blockhash)),
+        )
+        .await
+        .unwrap()
+        .0;
+        assert_eq!(response.tx_signatures, vec!["sig-1".to_string()]);
+        assert_eq!(
+            tee_calls.load(Ordering::SeqCst),
+            1,
+            "TEEへの署名要求は1回のみであるべき"
+        );
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(
+            sent[0], sent[1],
+            "再試行は同一のトランザクションを再送するべき"
+        );
+    }
+
+    /// /sign-and-mint — RPCがトランザクションを拒否した場合、422を返すことを確認
+    #[tokio::test]
+    async fn test_sign_and_mint_transaction_rejected() {
+        let gateway_keypair = solana_sdk::signer::keypair::Keypair::new();
+        let tee_port = spawn_mock_tee_sign(test_partial_tx(&gateway_keypair)).await;
+
+        let rpc = axum::Router::new().route(
+            "/rpc",
+            axum::routing::post(|Json(req): Json<serde_json::Value>| async move {
+                match req["method"].as_str().unwrap() {
+                    "getBalance" => Json(serde_json::json!({
+                        "jsonrpc": "2.0", "id": 1,
+                        "result": { "context": { "slot": 1 }, "value": 1_000_000_000u64 }
+                    })),
+                    "getFeeForMessage" => Json(serde_json::json!({
+                        "jsonrpc": "2.0", "id": 1,
+                        "result": { "context": { "slot": 1 }, "value": 10_000 }
+                    })),
+                    _ => Json(serde_json::json!({
+                        "jsonrpc": "2.0", "id": 1,
+                        "error": { "code": -32002, "message": "Transaction simulation failed: Blockhash not found" }
+                    })),
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, rpc).await.unwrap();
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let state = sign_and_mint_state(
+            &format!("http://127.0.0.1:{tee_port}"),
+            &format!("http://127.0.0.1:{rpc_port}/rpc"),
+            gateway_keypair,
+        );
+        let blockhash = "11111111111111111111111111111111";
+
+        let err = handle_sign_and_mint(
+            State(state),
+            Extension(limiter::ClientId::Anonymous),
+            HeaderMap::new(),
+            Json(sign_and_mint_input(blockhash)),
+        )
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, error::GatewayError::TransactionRejected(_)),
+            "トランザクション拒否エラーが期待される: {err}"
+        );
+        assert!(err.to_string().contains(blockhash));
+
+        let response = axum::response::IntoResponse::into_response(err);
+        assert_eq!(response.status(), axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+    }
 }
@@ -312,9 +312,7 @@ mod tests {
         data.extend_from_slice(wasm_source.as_bytes());
 
         // ResourceLimitsOnChain: all None
-        for _ in 0..7 {
-            data.push(0x00);
-        }
+        data.extend_from_slice(&[0x00; 7]);
 
         let limits = parse_resource_limits(&data).unwrap();
         assert_eq!(limits.max_single_content_bytes, None);
@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Solana JSON-RPC 呼び出し
+//!
+//! 仕様書 §6.2
+//!
+//! RPCノードに到達できない（接続失敗・タイムアウト・5xx/429）場合は
+//! 再試行可能な [`GatewayError::SolanaUnavailable`] に、
+//! それ以外の失敗は [`GatewayError::Solana`] に分類する。
+//! JSON-RPCの `error` フィールドの解釈は呼び出し側に委ねる
+//! （`sendTransaction` のみ [`send_transaction`] が再試行を含めて解釈する）。
+
+use std::time::Duration;
+
+use crate::error::GatewayError;
+
+/// JSON-RPCエラーのうち、RPCノード側の一時的な障害を表すコード。
+/// -32005: Node is unhealthy / -32603: Internal error
+const RETRYABLE_RPC_ERROR_CODES: &[i64] = &[-32005, -32603];
+
+/// `sendTransaction` の最大試行回数（初回を含む）。
+pub const SEND_TRANSACTION_MAX_ATTEMPTS: u32 = 3;
+
+/// `sendTransaction` 再試行の初回待機時間。以降は試行ごとに2倍にする。
+pub const SEND_TRANSACTION_BASE_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Solana JSON-RPCを呼び出し、レスポンスボディを返す。
+/// `context` はエラーメッセージに付与する処理名（例: "blockhash取得"）。
+pub async fn call(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    request: &serde_json::Value,
+    context: &str,
+) -> Result<serde_json::Value, GatewayError> {
+    let response = client
+        .post(rpc_url)
+        .json(request)
+        .send()
+        .await
+        .map_err(|e| GatewayError::SolanaUnavailable(format!("{context}に失敗: {e}")))?;
+
+    let status = response.status();
+    if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(GatewayError::SolanaUnavailable(format!(
+            "{context}に失敗: RPCがHTTP {status}を返しました"
+        )));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| GatewayError::Solana(format!("{context}のレスポンスのパースに失敗: {e}")))
+}
+
+/// JSON-RPCの `error` がRPCノード側の一時的な障害を表すかを判定する。
+pub fn is_retryable_rpc_error(error: &serde_json::Value) -> bool {
+    error
+        .get("code")
+        .and_then(|c| c.as_i64())
+        .is_some_and(|code| RETRYABLE_RPC_ERROR_CODES.contains(&code))
+}
+
+/// JSON-RPCの `error` が「トランザクションは処理済み」を表すかを判定する。
+/// 先の送信がRPCに届いていた場合、再送に対してこのエラーが返る。
+pub fn is_already_processed(error: &serde_json::Value) -> bool {
+    let message_matches = error
+        .get("message")
+        .and_then(|m| m.as_str())
+        .is_some_and(|m| m.contains("already been processed"));
+    message_matches || error.pointer("/data/err").and_then(|e| e.as_str()) == Some("AlreadyProcessed")
+}
+
+/// 署名済みトランザクションを `sendTransaction` で送信し、トランザクション署名を返す。
+/// 仕様書 §6.2
+///
+/// 同一の署名済みトランザクションは署名が固定されるため、再送しても二重に処理されない。
+/// RPCに到達できない場合（接続失敗・5xx・429）と一時的なJSON-RPCエラーの場合は、
+/// 最大 [`SEND_TRANSACTION_MAX_ATTEMPTS`] 回まで指数バックオフで再送する。
+/// 「処理済み」が返った場合は先の送信が取り込まれたものとして `signature` を返す。
+/// それ以外のJSON-RPCエラーは再送せず [`GatewayError::TransactionRejected`] とする。
+pub async fn send_transaction(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    tx_b64: &str,
+    signature: &str,
+) -> Result<String, GatewayError> {
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "sendTransaction",
+        "params": [tx_b64, {"encoding": "base64", "skipPreflight": true, "preflightCommitment": "confirmed"}]
+    });
+
+    let mut attempt = 1;
+    loop {
+        let retryable = match call(client, rpc_url, &request, "RPC送信").await {
+            Ok(body) => match body.get("error") {
+                None => {
+                    return body
+                        .get("result")
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string)
+                        .ok_or_else(|| {
+                            GatewayError::Solana("RPCレスポンスにresultがありません".to_string())
+                        });
+                }
+                Some(error) if is_already_processed(error) => {
+                    tracing::info!(signature, attempt, "トランザクションは処理済みです");
+                    return Ok(signature.to_string());
+                }
+                Some(error) => {
+                    let message = format!("トランザクションのブロードキャストに失敗: {error}");
+                    if !is_retryable_rpc_error(error) {
+                        return Err(GatewayError::TransactionRejected(message));
+                    }
+                    GatewayError::SolanaUnavailable(message)
+                }
+            },
+            Err(e @ GatewayError::SolanaUnavailable(_)) => e,
+            Err(e) => return Err(e),
+        };
+
+        if attempt >= SEND_TRANSACTION_MAX_ATTEMPTS {
+            return Err(retryable);
+        }
+        let backoff = SEND_TRANSACTION_BASE_BACKOFF * 2u32.pow(attempt - 1);
+        tracing::warn!(signature, attempt, ?backoff, "sendTransactionを再試行します: {retryable}");
+        tokio::time::sleep(backoff).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_retryable_rpc_error() {
+        assert!(is_retryable_rpc_error(&serde_json::json!({"code": -32005, "message": "Node is unhealthy"})));
+        assert!(!is_retryable_rpc_error(&serde_json::json!({"code": -32002, "message": "Transaction simulation failed"})));
+        assert!(!is_retryable_rpc_error(&serde_json::json!({"message": "no code"})));
+    }
+
+    #[test]
+    fn test_is_already_processed() {
+        assert!(is_already_processed(&serde_json::json!({
+            "code": -32002,
+            "message": "Transaction simulation failed: This transaction has already been processed"
+        })));
+        assert!(is_already_processed(&serde_json::json!({
+            "code": -32002, "message": "Transaction simulation failed", "data": {"err": "AlreadyProcessed"}
+        })));
+        assert!(!is_already_processed(&serde_json::json!({
+            "code": -32002, "message": "Transaction simulation failed: Blockhash not found"
+        })));
+    }
+}
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # インメモリ Temporary Storage 実装（テスト用）
+//!
+//! 仕様書 §6.3
+//!
+//! `HashMap` にオブジェクトを保持し、`title-temp-storage` サーバーと同じ
+//! `PUT/GET /objects/{key}` を提供する小さなHTTPサーバーを内蔵する。
+//! 署名付きURLの発行からアップロード・ダウンロードまでを外部ストレージなしで再現できる。
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+
+use super::{PresignedUrls, TempStorage};
+use crate::error::GatewayError;
+
+/// オブジェクトキー → 保存データ
+type ObjectMap = Arc<RwLock<HashMap<String, Vec<u8>>>>;
+
+/// インメモリのTemporary Storage実装。
+/// 仕様書 §6.3
+pub struct InMemoryTempStorage {
+    /// 内蔵HTTPサーバーのベースURL（例: `http://127.0.0.1:12345`）
+    base_url: String,
+    /// 保存済みオブジェクト
+    objects: ObjectMap,
+}
+
+impl InMemoryTempStorage {
+    /// ローカルホストの空きポートで内蔵HTTPサーバーを起動し、ストレージを構築する。
+    pub async fn start() -> Self {
+        let objects: ObjectMap = Arc::default();
+
+        let app = axum::Router::new()
+            .route(
+                "/objects/{*key}",
+                axum::routing::put(handle_put).get(handle_get),
+            )
+            .with_state(objects.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        Self {
+            base_url: format!("http://127.0.0.1:{port}"),
+            objects,
+        }
+    }
+
+    /// 保存済みオブジェクトを取得する。
+    pub fn get(&self, object_key: &str) -> Option<Vec<u8>> {
+        self.objects.read().unwrap().get(object_key).cloned()
+    }
+}
+
+#[async_trait::async_trait]
+impl TempStorage for InMemoryTempStorage {
+    /// 内蔵HTTPサーバーへのURLを生成する。
+    /// 仕様書 §6.3
+    async fn generate_presigned_urls(
+        &self,
+        object_key: &str,
+        _expiry_secs: u32,
+    ) -> Result<PresignedUrls, GatewayError> {
+        let url = format!("{}/objects/{}", self.base_url, object_key);
+        Ok(PresignedUrls {
+            upload_url: url.clone(),
+            download_url: url,
+        })
+    }
+}
+
+/// PUT /objects/{key} — ボディをそのまま保存する
+async fn handle_put(
+    State(objects): State<ObjectMap>,
+    Path(key): Path<String>,
+    body: Bytes,
+) -> StatusCode {
+    objects.write().unwrap().insert(key, body.to_vec());
+    StatusCode::OK
+}
+
+/// GET /objects/{key} — 保存済みのボディを返す
+async fn handle_get(
+    State(objects): State<ObjectMap>,
+    Path(key): Path<String>,
+) -> impl IntoResponse {
+    match objects.read().unwrap().get(&key) {
+        Some(data) => Ok(data.clone()),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_upload_and_download_roundtrip() {
+        let storage = InMemoryTempStorage::start().await;
+        let urls = storage
+            .generate_presigned_urls("uploads/payload.bin", 3600)
+            .await
+            .unwrap();
+
+        // 全バイト値を含むバイナリで往復を確認
+        let payload: Vec<u8> = (0..=255u8).cycle().take(4096).collect();
+        let client = reqwest::Client::new();
+        let put = client
+            .put(&urls.upload_url)
+            .body(payload.clone())
+            .send()
+            .await
+            .unwrap();
+        assert!(put.status().is_success());
+        assert_eq!(storage.get("uploads/payload.bin"), Some(payload.clone()));
+
+        let downloaded = client.get(&urls.download_url).send().await.unwrap();
+        assert!(downloaded.status().is_success());
+        assert_eq!(downloaded.bytes().await.unwrap().to_vec(), payload);
+    }
+
+    #[tokio::test]
+    async fn test_download_missing_object() {
+        let storage = InMemoryTempStorage::start().await;
+        let urls = storage.generate_presigned_urls("missing", 3600).await.unwrap();
+
+        let response = reqwest::get(&urls.download_url).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+}
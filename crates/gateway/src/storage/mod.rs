@@ -24,6 +24,12 @@ pub mod local;
 #[cfg(feature = "vendor-local")]
 pub use local::LocalTempStorage;
 
+#[cfg(test)]
+pub mod memory;
+
+#[cfg(test)]
+pub use memory::InMemoryTempStorage;
+
 use crate::error::GatewayError;
 
 /// Temporary Storageの署名付きURL生成結果。
@@ -0,0 +1,562 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # TEE HTTPクライアント
+//!
+//! 仕様書 §6.2, §6.4
+//!
+//! GatewayからTEEへのHTTP呼び出しを型付きメソッドとして提供する。
+//! エンドポイントのパス・Gateway認証ラッパーの構築・レスポンスのパースをここに集約し、
+//! 各ハンドラがワイヤ形式を直接扱わないようにする。
+
+use ed25519_dalek::SigningKey as Ed25519SigningKey;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use title_types::*;
+
+use crate::auth::build_gateway_auth_wrapper;
+use crate::config::GatewayState;
+use crate::error::GatewayError;
+
+/// TEEレスポンスの読み取り上限のデフォルト（32MiB）。
+///
+/// 最大の正当なレスポンスは、グラフ上限いっぱいの来歴グラフと拡張結果を含む
+/// /verify の暗号化済みレスポンス（Base64）であり、これを十分に収める値とする。
+pub(crate) const DEFAULT_TEE_MAX_RESPONSE_BYTES: usize = 32 * 1024 * 1024;
+
+/// TEEのエラーレスポンスをGatewayエラーに変換する。
+/// 仕様書 §6.2
+///
+/// クライアント起因のエラー（4xx）と過負荷（503）は、ステータスコード・本文・`Retry-After` を
+/// そのままクライアントに中継する（アップロード直後の425、恒久的な不在の404等を区別できるように）。
+/// Gateway認証の失敗（401）とその他の5xxはGateway・TEE側の問題のため [`GatewayError::TeeRelay`] とする。
+fn tee_error(
+    status: reqwest::StatusCode,
+    response_body: String,
+    retry_after: Option<reqwest::header::HeaderValue>,
+) -> GatewayError {
+    let pass_through = (status.is_client_error() && status != reqwest::StatusCode::UNAUTHORIZED)
+        || status == reqwest::StatusCode::SERVICE_UNAVAILABLE;
+    if pass_through {
+        return GatewayError::TeeRejected {
+            status,
+            message: response_body,
+            retry_after,
+        };
+    }
+    GatewayError::TeeRelay(format!(
+        "TEEがエラーを返しました: HTTP {} - {}",
+        status, response_body
+    ))
+}
+
+/// TEEのHTTP APIクライアント。
+/// 仕様書 §6.2
+pub(crate) struct TeeClient<'a> {
+    /// HTTPクライアント
+    http_client: &'a reqwest::Client,
+    /// TEEのエンドポイントURL
+    endpoint: &'a str,
+    /// Gateway認証用Ed25519秘密鍵
+    signing_key: &'a Ed25519SigningKey,
+    /// Gateway認証ラッパーに付与するリソース制限
+    resource_limits: &'a ResourceLimits,
+    /// レスポンスボディの読み取り上限（バイト）
+    max_response_bytes: usize,
+}
+
+impl<'a> TeeClient<'a> {
+    pub(crate) fn new(
+        http_client: &'a reqwest::Client,
+        endpoint: &'a str,
+        signing_key: &'a Ed25519SigningKey,
+        resource_limits: &'a ResourceLimits,
+    ) -> Self {
+        Self {
+            http_client,
+            endpoint,
+            signing_key,
+            resource_limits,
+            max_response_bytes: DEFAULT_TEE_MAX_RESPONSE_BYTES,
+        }
+    }
+
+    /// レスポンスボディの読み取り上限を設定する。
+    pub(crate) fn with_max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    /// Gatewayの共有状態からクライアントを構築する。
+    pub(crate) fn from_state(state: &'a GatewayState) -> Self {
+        Self::new(
+            &state.http_client,
+            &state.tee_endpoint,
+            &state.signing_key,
+            &state.default_resource_limits,
+        )
+        .with_max_response_bytes(state.tee_max_response_bytes)
+    }
+
+    /// POST /verify — Gateway認証付きで検証を依頼し、暗号化済みレスポンスを返す。
+    /// 仕様書 §6.2, §6.4
+    pub(crate) async fn verify(
+        &self,
+        request: &VerifyRequest,
+    ) -> Result<EncryptedResponse, GatewayError> {
+        self.post_authenticated("/verify", request).await
+    }
+
+    /// DELETE /verify/{token} — Gateway認証付きで処理中の検証の取り消しを依頼する。
+    /// 仕様書 §6.2, §6.4
+    ///
+    /// TEEに該当する処理中の検証がない場合は [`GatewayError::NotFound`] を返す。
+    pub(crate) async fn cancel_verify(
+        &self,
+        token: &str,
+        request: &CancelVerifyRequest,
+    ) -> Result<(), GatewayError> {
+        let path = format!("/verify/{token}");
+        let body = serde_json::to_value(request)
+            .map_err(|e| GatewayError::Internal(format!("リクエストのシリアライズに失敗: {e}")))?;
+        let wrapper =
+            build_gateway_auth_wrapper(self.signing_key, "DELETE", &path, body, None)?;
+        let url = format!("{}{}", self.endpoint, path);
+        let response = self
+            .http_client
+            .delete(&url)
+            .json(&wrapper)
+            .send()
+            .await
+            .map_err(|e| GatewayError::TeeRelay(format!("HTTP送信失敗: {e}")))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let retry_after = response.headers().get(reqwest::header::RETRY_AFTER).cloned();
+        let response_body = self.read_body(response).await.unwrap_or_default();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(GatewayError::NotFound(response_body));
+        }
+        Err(tee_error(status, response_body, retry_after))
+    }
+
+    /// POST /sign — Gateway認証付きで部分署名済みトランザクションの構築を依頼する。
+    /// 仕様書 §6.2, §6.4
+    pub(crate) async fn sign(&self, request: &SignRequest) -> Result<SignResponse, GatewayError> {
+        self.post_authenticated("/sign", request).await
+    }
+
+    /// リクエストをGateway認証ラッパーで包んで送信する。
+    /// 仕様書 §6.2: Gateway認証署名を付与してTEEにリクエストを転送する。
+    async fn post_authenticated<Req: Serialize, Resp: DeserializeOwned>(
+        &self,
+        path: &str,
+        request: &Req,
+    ) -> Result<Resp, GatewayError> {
+        let body = serde_json::to_value(request)
+            .map_err(|e| GatewayError::Internal(format!("リクエストのシリアライズに失敗: {e}")))?;
+        let wrapper = build_gateway_auth_wrapper(
+            self.signing_key,
+            "POST",
+            path,
+            body,
+            Some(self.resource_limits.clone()),
+        )?;
+        self.post(path, &wrapper).await
+    }
+
+    /// TEEにJSONをPOSTし、成功レスポンスを指定の型にパースする。
+    async fn post<Req: Serialize, Resp: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &Req,
+    ) -> Result<Resp, GatewayError> {
+        let url = format!("{}{}", self.endpoint, path);
+        let response = self
+            .http_client
+            .post(&url)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| GatewayError::TeeRelay(format!("HTTP送信失敗: {e}")))?;
+
+        let status = response.status();
+        let retry_after = response.headers().get(reqwest::header::RETRY_AFTER).cloned();
+        let response_body = self.read_body(response).await?;
+        if !status.is_success() {
+            return Err(tee_error(status, response_body, retry_after));
+        }
+
+        serde_json::from_str(&response_body).map_err(|e| {
+            GatewayError::TeeRelay(format!("{path} のレスポンスのパースに失敗: {e}"))
+        })
+    }
+
+    /// レスポンスボディを上限まで読み取る。
+    ///
+    /// 侵害・不具合のあるTEEが巨大なボディを返してもGatewayのメモリを使い果たさないよう、
+    /// Content-Lengthで事前に拒否し、チャンク単位の読み取り中も累計サイズを検査する。
+    async fn read_body(&self, mut response: reqwest::Response) -> Result<String, GatewayError> {
+        let limit = self.max_response_bytes;
+        let too_large = || {
+            GatewayError::TeeRelay(format!(
+                "TEEのレスポンスが上限（{limit}バイト）を超えています"
+            ))
+        };
+        if response.content_length().is_some_and(|len| len > limit as u64) {
+            return Err(too_large());
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| GatewayError::TeeRelay(format!("レスポンス読み取り失敗: {e}")))?
+        {
+            if body.len() + chunk.len() > limit {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        String::from_utf8(body)
+            .map_err(|e| GatewayError::TeeRelay(format!("レスポンス読み取り失敗: {e}")))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// テスト
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Json;
+
+    /// モックTEEサーバーを起動し、エンドポイントURLを返す。
+    async fn spawn_mock_tee(router: axum::Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+        format!("http://127.0.0.1:{port}")
+    }
+
+    fn test_limits() -> ResourceLimits {
+        ResourceLimits {
+            max_single_content_bytes: Some(1024),
+            max_concurrent_bytes: None,
+            min_upload_speed_bytes: None,
+            base_processing_time_sec: None,
+            max_global_timeout_sec: None,
+            chunk_read_timeout_sec: None,
+            c2pa_max_graph_size: None,
+        }
+    }
+
+    /// Gateway認証ラッパーの署名を検証し、内側のbodyを返す。
+    fn verify_wrapper(
+        wrapper: &GatewayAuthWrapper,
+        signing_key: &Ed25519SigningKey,
+    ) -> serde_json::Value {
+        let expected = build_gateway_auth_wrapper(
+            signing_key,
+            &wrapper.method,
+            &wrapper.path,
+            wrapper.body.clone(),
+            wrapper.resource_limits.clone(),
+        )
+        .unwrap();
+        assert_eq!(wrapper.gateway_signature, expected.gateway_signature);
+        wrapper.body.clone()
+    }
+
+    #[tokio::test]
+    async fn test_verify_round_trip() {
+        let signing_key = Ed25519SigningKey::generate(&mut rand::rngs::OsRng);
+        let key = signing_key.clone();
+        let endpoint = spawn_mock_tee(axum::Router::new().route(
+            "/verify",
+            axum::routing::post(move |Json(wrapper): Json<GatewayAuthWrapper>| async move {
+                assert_eq!(wrapper.path, "/verify");
+                assert_eq!(
+                    wrapper.resource_limits.as_ref().unwrap().max_single_content_bytes,
+                    Some(1024)
+                );
+                let request: VerifyRequest =
+                    serde_json::from_value(verify_wrapper(&wrapper, &key)).unwrap();
+                assert_eq!(request.processor_ids, vec!["core-c2pa".to_string()]);
+                Json(EncryptedResponse {
+                    nonce: "bm9uY2U=".into(),
+                    ciphertext: "Y2lwaGVy".into(),
+                })
+            }),
+        ))
+        .await;
+
+        let http_client = reqwest::Client::new();
+        let limits = test_limits();
+        let client = TeeClient::new(&http_client, &endpoint, &signing_key, &limits);
+        let response = client
+            .verify(&VerifyRequest {
+                download_url: "http://example.com/payload".into(),
+                processor_ids: vec!["core-c2pa".into()],
+                max_graph_size: None,
+                max_returned_nodes: None,
+                include_assertions: false,
+                include_preview_hash: false,
+                include_claim_generators: false,
+                compact_graph: false,
+                cancel_token: None,
+                priority: None,
+                depends_on: Default::default(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.nonce, "bm9uY2U=");
+        assert_eq!(response.ciphertext, "Y2lwaGVy");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_verify_signs_path_and_maps_not_found() {
+        let signing_key = Ed25519SigningKey::generate(&mut rand::rngs::OsRng);
+        let key = signing_key.clone();
+        let endpoint = spawn_mock_tee(axum::Router::new().route(
+            "/verify/{token}",
+            axum::routing::delete(
+                move |axum::extract::Path(token): axum::extract::Path<String>,
+                      Json(wrapper): Json<GatewayAuthWrapper>| async move {
+                    assert_eq!(wrapper.method, "DELETE");
+                    assert_eq!(wrapper.path, format!("/verify/{token}"));
+                    assert_eq!(wrapper.body["download_url"], "https://storage.example/payload");
+                    verify_wrapper(&wrapper, &key);
+                    if token == "job-1" {
+                        axum::http::StatusCode::NO_CONTENT
+                    } else {
+                        axum::http::StatusCode::NOT_FOUND
+                    }
+                },
+            ),
+        ))
+        .await;
+
+        let http_client = reqwest::Client::new();
+        let limits = test_limits();
+        let client = TeeClient::new(&http_client, &endpoint, &signing_key, &limits);
+        let request = CancelVerifyRequest {
+            download_url: "https://storage.example/payload".to_string(),
+        };
+        client.cancel_verify("job-1", &request).await.unwrap();
+        assert!(matches!(
+            client.cancel_verify("job-2", &request).await,
+            Err(GatewayError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sign_round_trip() {
+        let signing_key = Ed25519SigningKey::generate(&mut rand::rngs::OsRng);
+        let key = signing_key.clone();
+        let endpoint = spawn_mock_tee(axum::Router::new().route(
+            "/sign",
+            axum::routing::post(move |Json(wrapper): Json<GatewayAuthWrapper>| async move {
+                assert_eq!(wrapper.path, "/sign");
+                let request: SignRequest =
+                    serde_json::from_value(verify_wrapper(&wrapper, &key)).unwrap();
+                assert_eq!(request.requests[0].signed_json_uri, "ar://test");
+                Json(SignResponse {
+                    partial_txs: vec!["dGVzdHR4".into()],
+                })
+            }),
+        ))
+        .await;
+
+        let http_client = reqwest::Client::new();
+        let limits = test_limits();
+        let client = TeeClient::new(&http_client, &endpoint, &signing_key, &limits);
+        let response = client
+            .sign(&SignRequest {
+                recent_blockhash: "11111111111111111111111111111111".into(),
+                requests: vec![SignRequestItem {
+                    signed_json_uri: "ar://test".into(),
+                }],
+                fee_payer: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.partial_txs, vec!["dGVzdHR4".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_unexpected_response_shape_is_relay_error() {
+        let endpoint = spawn_mock_tee(axum::Router::new().route(
+            "/sign",
+            axum::routing::post(|| async { Json(serde_json::json!({"unexpected": true})) }),
+        ))
+        .await;
+
+        let http_client = reqwest::Client::new();
+        let signing_key = Ed25519SigningKey::generate(&mut rand::rngs::OsRng);
+        let limits = test_limits();
+        let client = TeeClient::new(&http_client, &endpoint, &signing_key, &limits);
+        let result = client
+            .sign(&SignRequest {
+                recent_blockhash: "11111111111111111111111111111111".into(),
+                requests: vec![],
+                fee_payer: None,
+            })
+            .await;
+        match result {
+            Err(GatewayError::TeeRelay(msg)) => assert!(msg.contains("/sign"), "{msg}"),
+            other => panic!("TeeRelayエラーを期待: {other:?}"),
+        }
+    }
+
+    /// TEEのクライアント向けエラーはステータスコードと `Retry-After` を保って中継され、
+    /// それ以外はTeeRelayになることを確認
+    #[tokio::test]
+    async fn test_tee_errors_are_passed_through() {
+        let endpoint = spawn_mock_tee(axum::Router::new().route(
+            "/verify",
+            axum::routing::post(|Json(wrapper): Json<GatewayAuthWrapper>| async move {
+                use axum::response::IntoResponse;
+                let url = wrapper.body["download_url"].as_str().unwrap().to_string();
+                match url.as_str() {
+                    "http://example.com/early" => (
+                        axum::http::StatusCode::TOO_EARLY,
+                        [(axum::http::header::RETRY_AFTER, "1")],
+                        "再試行してください",
+                    )
+                        .into_response(),
+                    "http://example.com/missing" => {
+                        (axum::http::StatusCode::NOT_FOUND, "見つかりません").into_response()
+                    }
+                    _ => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, "内部エラー")
+                        .into_response(),
+                }
+            }),
+        ))
+        .await;
+
+        let http_client = reqwest::Client::new();
+        let signing_key = Ed25519SigningKey::generate(&mut rand::rngs::OsRng);
+        let limits = test_limits();
+        let client = TeeClient::new(&http_client, &endpoint, &signing_key, &limits);
+        let request = |download_url: &str| VerifyRequest {
+            download_url: download_url.into(),
+            processor_ids: vec!["core-c2pa".into()],
+            max_graph_size: None,
+            max_returned_nodes: None,
+            include_assertions: false,
+            include_preview_hash: false,
+            include_claim_generators: false,
+            compact_graph: false,
+            cancel_token: None,
+            priority: None,
+            depends_on: Default::default(),
+        };
+
+        match client.verify(&request("http://example.com/early")).await {
+            Err(GatewayError::TeeRejected {
+                status,
+                message,
+                retry_after,
+            }) => {
+                assert_eq!(status, reqwest::StatusCode::TOO_EARLY);
+                assert_eq!(message, "再試行してください");
+                assert_eq!(retry_after.unwrap(), "1");
+            }
+            other => panic!("TeeRejectedエラーを期待: {other:?}"),
+        }
+        match client.verify(&request("http://example.com/missing")).await {
+            Err(GatewayError::TeeRejected {
+                status,
+                retry_after,
+                ..
+            }) => {
+                assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+                assert!(retry_after.is_none());
+            }
+            other => panic!("TeeRejectedエラーを期待: {other:?}"),
+        }
+        assert!(matches!(
+            client.verify(&request("http://example.com/other")).await,
+            Err(GatewayError::TeeRelay(_))
+        ));
+    }
+
+    /// 上限を超えるレスポンスはパースせずTeeRelayエラーになることを確認
+    #[tokio::test]
+    async fn test_oversized_response_is_rejected() {
+        let endpoint = spawn_mock_tee(axum::Router::new().route(
+            "/sign",
+            axum::routing::post(|| async {
+                Json(SignResponse {
+                    partial_txs: vec!["A".repeat(4096)],
+                })
+            }),
+        ))
+        .await;
+
+        let http_client = reqwest::Client::new();
+        let signing_key = Ed25519SigningKey::generate(&mut rand::rngs::OsRng);
+        let limits = test_limits();
+        let client = TeeClient::new(&http_client, &endpoint, &signing_key, &limits)
+            .with_max_response_bytes(1024);
+
+        let result = client
+            .sign(&SignRequest {
+                recent_blockhash: "11111111111111111111111111111111".into(),
+                requests: vec![],
+                fee_payer: None,
+            })
+            .await;
+        match result {
+            Err(GatewayError::TeeRelay(msg)) => assert!(msg.contains("上限"), "{msg}"),
+            other => panic!("TeeRelayエラーを期待: {other:?}"),
+        }
+
+        // Content-Lengthを付けないchunkedボディも読み取り中に打ち切る
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 64 * 1024];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n")
+                .await;
+            for _ in 0..8 {
+                let chunk = format!("400\r\n{}\r\n", "A".repeat(1024));
+                if stream.write_all(chunk.as_bytes()).await.is_err() {
+                    return;
+                }
+            }
+            let _ = stream.write_all(b"0\r\n\r\n").await;
+        });
+        let endpoint = format!("http://127.0.0.1:{port}");
+        let client = TeeClient::new(&http_client, &endpoint, &signing_key, &limits)
+            .with_max_response_bytes(1024);
+        let result = client
+            .verify(&VerifyRequest {
+                download_url: "http://example.com/payload".into(),
+                processor_ids: vec!["core-c2pa".into()],
+                max_graph_size: None,
+                max_returned_nodes: None,
+                include_assertions: false,
+                include_preview_hash: false,
+                include_claim_generators: false,
+                compact_graph: false,
+                cancel_token: None,
+                priority: None,
+                depends_on: Default::default(),
+            })
+            .await;
+        match result {
+            Err(GatewayError::TeeRelay(msg)) => assert!(msg.contains("上限"), "{msg}"),
+            other => panic!("TeeRelayエラーを期待: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # 転送先アドレスの検査
+//!
+//! 仕様書 §6.4
+//!
+//! クライアントが指定したURL（Extension補助入力の `ref_url` 等）を取得する際のSSRF対策。
+//! TEEはDNSを解決できないため、ホスト名の名前解決後のアドレス検査はプロキシが行う。
+//! 解決した全てのアドレスが外部向けであることを確認し、接続先をそのアドレスに固定する
+//! （検査後の再解決によるDNSリバインディングを防ぐ）。
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// 内部向け（ループバック・プライベート・リンクローカル等）のアドレスかどうか。
+pub fn is_internal_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_internal_v4(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_internal_v4(v4),
+            None => is_internal_v6(v6),
+        },
+    }
+}
+
+fn is_internal_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        // 100.64.0.0/10（Carrier-Grade NAT）
+        || (a == 100 && (b & 0xC0) == 64)
+}
+
+fn is_internal_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // fc00::/7（ユニークローカル）
+        || (first & 0xFE00) == 0xFC00
+        // fe80::/10（リンクローカル）
+        || (first & 0xFFC0) == 0xFE80
+}
+
+/// URLのホストを名前解決し、全てのアドレスが外部向けであることを確認する。
+/// 仕様書 §6.4
+///
+/// 解決したアドレスの1つでも `is_internal` に該当すれば拒否する。
+/// 返却したアドレスに接続先を固定して使用すること。
+pub async fn resolve_external(
+    url: &reqwest::Url,
+    is_internal: fn(IpAddr) -> bool,
+) -> Result<(String, Vec<SocketAddr>), String> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| "ホストが指定されていません".to_string())?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| format!("ポートを決定できません: {url}"))?;
+    // IPv6リテラルは角括弧付きで返る
+    let lookup_host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((lookup_host, port))
+        .await
+        .map_err(|e| format!("名前解決に失敗: {host}: {e}"))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("名前解決の結果がありません: {host}"));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| is_internal(addr.ip())) {
+        return Err(format!("内部向けのアドレスには転送できません: {host} ({})", addr.ip()));
+    }
+    Ok((host.to_string(), addrs))
+}
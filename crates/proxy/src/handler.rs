@@ -5,15 +5,40 @@
 //! 仕様書 §6.4
 //!
 //! TEEから受け取ったHTTPリクエストを外部に転送し、レスポンスを返す。
+//!
+//! メソッド `GET_EXTERNAL` はクライアント指定のURLを取得するためのGETで、
+//! 名前解決後のアドレスが外部向けであることを確認し、リダイレクトに追従しない（SSRF対策）。
+
+use std::net::IpAddr;
+
+use crate::{address, protocol};
+
+/// クライアント指定のURLを外部向けアドレスに限定して取得するメソッド名。
+/// 仕様書 §6.4
+pub const EXTERNAL_GET_METHOD: &str = "GET_EXTERNAL";
 
-use crate::protocol;
+/// 転送先レスポンスボディの既定の上限（バイト）。
+/// TEEのコンテンツサイズ上限（`DEFAULT_MAX_SINGLE_CONTENT_BYTES`）と揃える。
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 2 * 1024 * 1024 * 1024;
 
 /// TEEから受け取ったHTTPリクエストを外部に転送し、レスポンスを返す。
 /// 仕様書 §6.4
 ///
-/// HTTPメソッドサポート: GET, POST。
+/// HTTPメソッドサポート: GET, POST, [`EXTERNAL_GET_METHOD`]。
 /// 未サポートのメソッドはステータス400を返す。
-pub async fn forward_http(method: &str, url: &str, body: &[u8]) -> (u32, Vec<u8>) {
+///
+/// レスポンスボディが `max_response_bytes` を超える場合は読み取りを中断し、
+/// ステータス502を返す（上限を超えてバッファしない）。
+pub async fn forward_http(
+    method: &str,
+    url: &str,
+    body: &[u8],
+    max_response_bytes: usize,
+) -> (u32, Vec<u8>) {
+    if method == EXTERNAL_GET_METHOD {
+        return forward_external_get(url, max_response_bytes, address::is_internal_ip).await;
+    }
+
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(120))
         .build()
@@ -36,10 +61,57 @@ pub async fn forward_http(method: &str, url: &str, body: &[u8]) -> (u32, Vec<u8>
         }
     };
 
+    relay_response(result, max_response_bytes).await
+}
+
+/// クライアント指定のURLを外部向けアドレスに限定してGETする。
+/// 仕様書 §6.4
+///
+/// 名前解決した全てのアドレスが外部向けでない場合はステータス403を返し、接続しない。
+/// 接続先は検査したアドレスに固定する。リダイレクトには追従せず、3xxをそのまま返す
+/// （リダイレクト先の内部アドレスへの到達を防ぐ）。
+pub async fn forward_external_get(
+    url: &str,
+    max_response_bytes: usize,
+    is_internal: fn(IpAddr) -> bool,
+) -> (u32, Vec<u8>) {
+    let parsed = match reqwest::Url::parse(url) {
+        Ok(u) => u,
+        Err(e) => return (400, format!("Invalid URL: {e}").into_bytes()),
+    };
+    let (host, addrs) = match address::resolve_external(&parsed, is_internal).await {
+        Ok(v) => v,
+        Err(msg) => {
+            tracing::warn!("転送を拒否しました: {}", msg);
+            return (403, msg.into_bytes());
+        }
+    };
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(120))
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve_to_addrs(&host, &addrs)
+        .build()
+        .expect("reqwestクライアントの構築に失敗");
+
+    relay_response(client.get(parsed).send().await, max_response_bytes).await
+}
+
+/// 転送先のレスポンスをステータスとボディ（上限付き）に変換する。
+async fn relay_response(
+    result: reqwest::Result<reqwest::Response>,
+    max_response_bytes: usize,
+) -> (u32, Vec<u8>) {
     match result {
         Ok(resp) => {
             let status = resp.status().as_u16() as u32;
-            let body_bytes = resp.bytes().await.unwrap_or_default().to_vec();
+            let body_bytes = match read_body_limited(resp, max_response_bytes).await {
+                Ok(b) => b,
+                Err(msg) => {
+                    tracing::error!("{}", msg);
+                    return (502, msg.into_bytes());
+                }
+            };
             tracing::info!(
                 "HTTP転送完了: status={}, body={} bytes",
                 status,
@@ -55,20 +127,54 @@ pub async fn forward_http(method: &str, url: &str, body: &[u8]) -> (u32, Vec<u8>
     }
 }
 
+/// レスポンスボディを上限付きでチャンク単位に読み取る。
+/// Content-Lengthが上限を超える場合は読み取り前に拒否する。
+async fn read_body_limited(
+    mut resp: reqwest::Response,
+    max_response_bytes: usize,
+) -> Result<Vec<u8>, String> {
+    let too_large = || format!("Response too large: exceeds {} bytes", max_response_bytes);
+
+    if resp
+        .content_length()
+        .is_some_and(|len| len > max_response_bytes as u64)
+    {
+        return Err(too_large());
+    }
+
+    let mut body = Vec::new();
+    loop {
+        match resp.chunk().await {
+            Ok(Some(chunk)) => {
+                if body.len() + chunk.len() > max_response_bytes {
+                    return Err(too_large());
+                }
+                body.extend_from_slice(&chunk);
+            }
+            Ok(None) => return Ok(body),
+            // ボディ読み取り失敗時は空ボディとして扱う
+            Err(e) => {
+                tracing::warn!("レスポンスボディ読み取りエラー: {}", e);
+                return Ok(Vec::new());
+            }
+        }
+    }
+}
+
 /// TCP経由の接続を処理する（開発環境 / テスト用）。
 /// 仕様書 §6.4
 ///
 /// 本番環境と同一のlength-prefixedプロトコルを使用。
 #[cfg(any(not(target_os = "linux"), test))]
-pub async fn handle_tcp_connection(mut stream: tokio::net::TcpStream) {
-    let method = match protocol::read_string_async(&mut stream).await {
+pub async fn handle_tcp_connection(mut stream: tokio::net::TcpStream, max_response_bytes: usize) {
+    let method = match protocol::read_string_async(&mut stream, "method").await {
         Ok(m) => m,
         Err(e) => {
             tracing::error!("メソッド読み取りエラー: {}", e);
             return;
         }
     };
-    let url = match protocol::read_string_async(&mut stream).await {
+    let url = match protocol::read_string_async(&mut stream, "url").await {
         Ok(u) => u,
         Err(e) => {
             tracing::error!("URL読み取りエラー: {}", e);
@@ -85,7 +191,7 @@ pub async fn handle_tcp_connection(mut stream: tokio::net::TcpStream) {
 
     tracing::info!("{} {} (body: {} bytes)", method, url, body.len());
 
-    let (status, resp_body) = forward_http(&method, &url, &body).await;
+    let (status, resp_body) = forward_http(&method, &url, &body, max_response_bytes).await;
 
     if let Err(e) = protocol::write_response_async(&mut stream, status, &resp_body).await {
         tracing::error!("レスポンス書き込みエラー: {}", e);
@@ -97,12 +203,12 @@ pub async fn handle_tcp_connection(mut stream: tokio::net::TcpStream) {
 ///
 /// ブロッキングI/Oは `spawn_blocking` でラップし、HTTP転送は非同期で行う。
 #[cfg(target_os = "linux")]
-pub async fn handle_vsock_connection(stream: vsock::VsockStream) {
+pub async fn handle_vsock_connection(stream: vsock::VsockStream, max_response_bytes: usize) {
     // vsockストリームからリクエストを読み取り（ブロッキング）
     let result = tokio::task::spawn_blocking(move || {
         let mut s = stream;
-        let method = protocol::read_string_sync(&mut s)?;
-        let url = protocol::read_string_sync(&mut s)?;
+        let method = protocol::read_string_sync(&mut s, "method")?;
+        let url = protocol::read_string_sync(&mut s, "url")?;
         let body = protocol::read_bytes_sync(&mut s)?;
         Ok::<_, std::io::Error>((s, method, url, body))
     })
@@ -123,7 +229,7 @@ pub async fn handle_vsock_connection(stream: vsock::VsockStream) {
     tracing::info!("{} {} (body: {} bytes)", method, url, body.len());
 
     // 非同期でHTTP転送
-    let (status, resp_body) = forward_http(&method, &url, &body).await;
+    let (status, resp_body) = forward_http(&method, &url, &body, max_response_bytes).await;
 
     // vsockストリームにレスポンスを書き戻し（ブロッキング）
     let result = tokio::task::spawn_blocking(move || {
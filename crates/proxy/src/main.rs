@@ -10,7 +10,16 @@
 //! ## プラットフォーム
 //! - 本番環境(vendor-aws): vsock port 8000 でリッスン
 //! - 開発環境: TCP 127.0.0.1:8000 でリッスン
+//!
+//! ## SSRF対策
+//! メソッド `GET_EXTERNAL` の転送では、名前解決後のアドレスが外部向けであることを確認し、
+//! リダイレクトに追従しない（[`handler::forward_external_get`]）。
+//!
+//! ## 環境変数
+//! - `PROXY_MAX_RESPONSE_BYTES`: 転送先レスポンスボディの上限（バイト）。
+//!   超過時はステータス502を返す。既定は [`handler::DEFAULT_MAX_RESPONSE_BYTES`]。
 
+mod address;
 mod handler;
 mod protocol;
 
@@ -22,12 +31,25 @@ const VSOCK_PORT: u32 = 8000;
 #[cfg(not(all(target_os = "linux", feature = "vendor-aws")))]
 const TCP_ADDR: &str = "127.0.0.1:8000";
 
+/// 環境変数 `PROXY_MAX_RESPONSE_BYTES` からレスポンスボディの上限を読み取る。
+fn max_response_bytes_from_env() -> anyhow::Result<usize> {
+    let max = match std::env::var("PROXY_MAX_RESPONSE_BYTES") {
+        Ok(v) => v
+            .parse()
+            .map_err(|e| anyhow::anyhow!("PROXY_MAX_RESPONSE_BYTESが不正です: {e}"))?,
+        Err(_) => handler::DEFAULT_MAX_RESPONSE_BYTES,
+    };
+    tracing::info!("レスポンスボディ上限: {} bytes", max);
+    Ok(max)
+}
+
 /// 本番環境: vsockでリッスン (vendor-aws feature)
 #[cfg(all(target_os = "linux", feature = "vendor-aws"))]
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
     tracing::info!("TEE HTTPプロキシを vsock port {} で起動します", VSOCK_PORT);
+    let max_response_bytes = max_response_bytes_from_env()?;
 
     let listener =
         vsock::VsockListener::bind_with_cid_port(vsock::VMADDR_CID_ANY, VSOCK_PORT)?;
@@ -51,7 +73,7 @@ async fn main() -> anyhow::Result<()> {
     });
 
     while let Some(stream) = rx.recv().await {
-        tokio::spawn(handler::handle_vsock_connection(stream));
+        tokio::spawn(handler::handle_vsock_connection(stream, max_response_bytes));
     }
 
     Ok(())
@@ -66,13 +88,14 @@ async fn main() -> anyhow::Result<()> {
         "TEE HTTPプロキシを TCP {} で起動します（開発環境）",
         TCP_ADDR
     );
+    let max_response_bytes = max_response_bytes_from_env()?;
 
     let listener = tokio::net::TcpListener::bind(TCP_ADDR).await?;
 
     loop {
         let (stream, addr) = listener.accept().await?;
         tracing::info!("TCP接続受付: {}", addr);
-        tokio::spawn(handler::handle_tcp_connection(stream));
+        tokio::spawn(handler::handle_tcp_connection(stream, max_response_bytes));
     }
 }
 
@@ -84,12 +107,18 @@ mod tests {
 
     /// テスト用モックHTTPサーバーを起動し、ポート番号を返す。
     async fn start_mock_server() -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let redirect_target = format!("http://127.0.0.1:{port}/test");
         let app = Router::new()
             .route("/test", get(|| async { "hello" }))
-            .route("/echo", post(|body: Bytes| async move { body }));
+            .route("/echo", post(|body: Bytes| async move { body }))
+            .route("/large", get(|| async { vec![0u8; 64 * 1024] }))
+            .route(
+                "/redirect",
+                get(move || async move { axum::response::Redirect::to(&redirect_target) }),
+            );
 
-        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
-        let port = listener.local_addr().unwrap().port();
         tokio::spawn(async move {
             axum::serve(listener, app).await.unwrap();
         });
@@ -98,12 +127,17 @@ mod tests {
 
     /// テスト用プロキシサーバーを起動し、ポート番号を返す。
     async fn start_proxy() -> u16 {
+        start_proxy_with_limit(handler::DEFAULT_MAX_RESPONSE_BYTES).await
+    }
+
+    /// レスポンスボディ上限を指定してテスト用プロキシサーバーを起動する。
+    async fn start_proxy_with_limit(max_response_bytes: usize) -> u16 {
         let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
         let port = listener.local_addr().unwrap().port();
         tokio::spawn(async move {
             loop {
                 let (stream, _) = listener.accept().await.unwrap();
-                tokio::spawn(handler::handle_tcp_connection(stream));
+                tokio::spawn(handler::handle_tcp_connection(stream, max_response_bytes));
             }
         });
         port
@@ -196,6 +230,27 @@ mod tests {
             .contains("Unsupported method"));
     }
 
+    /// URLフィールドの不正なUTF-8が同期・非同期の両経路で同一のエラーになることを確認
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_invalid_utf8_url_rejected_consistently() {
+        let invalid_url: &[u8] = b"http://example.com/\xff\xfe";
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&(invalid_url.len() as u32).to_be_bytes());
+        frame.extend_from_slice(invalid_url);
+
+        let async_err = protocol::read_string_async(&mut frame.as_slice(), "url")
+            .await
+            .unwrap_err();
+        let sync_err =
+            protocol::read_string_sync(&mut std::io::Cursor::new(&frame), "url").unwrap_err();
+
+        assert_eq!(async_err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(sync_err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(async_err.to_string(), sync_err.to_string());
+        assert!(async_err.to_string().starts_with("urlが有効なUTF-8ではありません"));
+    }
+
     /// 転送先が到達不能な場合に500が返ることを確認
     #[tokio::test]
     async fn test_forward_unreachable() {
@@ -215,4 +270,88 @@ mod tests {
             .unwrap()
             .contains("Proxy error"));
     }
+
+    /// レスポンスボディが上限を超える場合にバッファせず502が返ることを確認
+    #[tokio::test]
+    async fn test_response_exceeding_limit_rejected() {
+        let server_port = start_mock_server().await;
+        let proxy_port = start_proxy_with_limit(16 * 1024).await;
+
+        let mut stream =
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", proxy_port))
+                .await
+                .unwrap();
+
+        let url = format!("http://127.0.0.1:{}/large", server_port);
+        write_request(&mut stream, "GET", &url, &[]).await;
+
+        let (status, body) = read_response(&mut stream).await;
+        assert_eq!(status, 502);
+        assert!(String::from_utf8(body)
+            .unwrap()
+            .contains("Response too large"));
+
+        // 上限内のレスポンスは従来通り転送される
+        let mut stream =
+            tokio::net::TcpStream::connect(format!("127.0.0.1:{}", proxy_port))
+                .await
+                .unwrap();
+        let url = format!("http://127.0.0.1:{}/test", server_port);
+        write_request(&mut stream, "GET", &url, &[]).await;
+        let (status, body) = read_response(&mut stream).await;
+        assert_eq!(status, 200);
+        assert_eq!(body, b"hello");
+    }
+
+    /// GET_EXTERNALは名前解決後のアドレスを検査し、内部向けに解決されるホスト名を拒否することを確認
+    #[tokio::test]
+    async fn test_external_get_rejects_host_resolving_to_internal_address() {
+        let server_port = start_mock_server().await;
+        let proxy_port = start_proxy().await;
+
+        for url in [
+            // ホスト名は名前解決後のアドレス（127.0.0.1 / ::1）で判定される
+            format!("http://localhost:{server_port}/test"),
+            "http://10.0.0.1/".to_string(),
+            "http://169.254.169.254/latest/meta-data/".to_string(),
+            "http://[::ffff:169.254.169.254]/".to_string(),
+        ] {
+            let mut stream =
+                tokio::net::TcpStream::connect(format!("127.0.0.1:{}", proxy_port))
+                    .await
+                    .unwrap();
+            write_request(&mut stream, handler::EXTERNAL_GET_METHOD, &url, &[]).await;
+
+            let (status, body) = read_response(&mut stream).await;
+            assert_eq!(status, 403, "{url} は拒否されるべき");
+            assert!(String::from_utf8(body).unwrap().contains("内部向け"));
+        }
+    }
+
+    /// GET_EXTERNALはリダイレクトに追従せず、3xxをそのまま返すことを確認
+    #[tokio::test]
+    async fn test_external_get_does_not_follow_redirect() {
+        /// モックサーバー（ループバック）への接続だけを許可する検査
+        fn internal_except_loopback(ip: std::net::IpAddr) -> bool {
+            !ip.is_loopback() && address::is_internal_ip(ip)
+        }
+
+        let server_port = start_mock_server().await;
+        let url = format!("http://127.0.0.1:{server_port}/redirect");
+
+        let (status, body) = handler::forward_external_get(
+            &url,
+            handler::DEFAULT_MAX_RESPONSE_BYTES,
+            internal_except_loopback,
+        )
+        .await;
+        assert_eq!(status, 303);
+        assert_ne!(body, b"hello");
+
+        // 通常のGETは従来どおりリダイレクト先を取得する
+        let (status, body) =
+            handler::forward_http("GET", &url, &[], handler::DEFAULT_MAX_RESPONSE_BYTES).await;
+        assert_eq!(status, 200);
+        assert_eq!(body, b"hello");
+    }
 }
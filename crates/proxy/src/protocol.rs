@@ -16,6 +16,20 @@
 //! [4B: status_code][4B: body_len][body]
 //! ```
 
+/// length-prefixed文字列フィールド（method / url）のバイト列をUTF-8として検証する。
+/// 仕様書 §6.4
+///
+/// 不正なバイト列を置換文字に置き換えて読み進めると、転送先URLが送信側の意図と
+/// 食い違うため、同期・非同期いずれの経路でもフィールド名を含む `InvalidData` エラーとする。
+pub fn decode_utf8_field(field: &str, buf: Vec<u8>) -> std::io::Result<String> {
+    String::from_utf8(buf).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{field}が有効なUTF-8ではありません: {e}"),
+        )
+    })
+}
+
 // ─────────────────────────────────────────────
 // 非同期I/O（TCP経路: 開発環境 / テスト用）
 // ─────────────────────────────────────────────
@@ -30,15 +44,18 @@ pub async fn read_u32_async<R: tokio::io::AsyncRead + Unpin>(r: &mut R) -> std::
     Ok(u32::from_be_bytes(buf))
 }
 
-/// length-prefixed文字列を読み取る。
+/// length-prefixed文字列を読み取る。`field` はエラーメッセージに用いるフィールド名。
 /// 仕様書 §6.4
 #[cfg(any(not(target_os = "linux"), test))]
-pub async fn read_string_async<R: tokio::io::AsyncRead + Unpin>(r: &mut R) -> std::io::Result<String> {
+pub async fn read_string_async<R: tokio::io::AsyncRead + Unpin>(
+    r: &mut R,
+    field: &str,
+) -> std::io::Result<String> {
     use tokio::io::AsyncReadExt;
     let len = read_u32_async(r).await? as usize;
     let mut buf = vec![0u8; len];
     r.read_exact(&mut buf).await?;
-    String::from_utf8(buf).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    decode_utf8_field(field, buf)
 }
 
 /// length-prefixedバイト列を読み取る。
@@ -81,14 +98,14 @@ pub fn read_u32_sync(r: &mut impl std::io::Read) -> std::io::Result<u32> {
     Ok(u32::from_be_bytes(buf))
 }
 
-/// length-prefixed文字列を同期的に読み取る。
+/// length-prefixed文字列を同期的に読み取る。`field` はエラーメッセージに用いるフィールド名。
 /// 仕様書 §6.4
 #[cfg(target_os = "linux")]
-pub fn read_string_sync(r: &mut impl std::io::Read) -> std::io::Result<String> {
+pub fn read_string_sync(r: &mut impl std::io::Read, field: &str) -> std::io::Result<String> {
     let len = read_u32_sync(r)? as usize;
     let mut buf = vec![0u8; len];
     r.read_exact(&mut buf)?;
-    String::from_utf8(buf).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    decode_utf8_field(field, buf)
 }
 
 /// length-prefixedバイト列を同期的に読み取る。
@@ -109,7 +126,6 @@ pub fn write_response_sync(
     status: u32,
     body: &[u8],
 ) -> std::io::Result<()> {
-    use std::io::Write;
     w.write_all(&status.to_be_bytes())?;
     w.write_all(&(body.len() as u32).to_be_bytes())?;
     w.write_all(body)?;
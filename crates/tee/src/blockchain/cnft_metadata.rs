@@ -0,0 +1,178 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # cNFTメタデータの構築
+//!
+//! 仕様書 §5.1 Step 11
+//!
+//! signed_jsonからmint用の [`CnftMetadata`] を構築する。
+//! シンボルはCoreでは `TITLE`、Extensionでは `extension_id` ごとに設定されたシンボル
+//! （未設定の場合はIDを大文字化して切り詰めたもの）となる。
+
+use std::collections::HashMap;
+
+use title_types::{CnftMetadata, SignedJson};
+
+/// Core cNFTのシンボル。
+pub const CORE_SYMBOL: &str = "TITLE";
+
+/// Metaplexメタデータのシンボルの最大長。
+pub const MAX_SYMBOL_LEN: usize = 10;
+
+/// Extension ID → cNFTシンボルの対応表。
+/// 仕様書 §5.1 Step 11
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionSymbols {
+    symbols: HashMap<String, String>,
+}
+
+impl ExtensionSymbols {
+    /// `phash-v1=PHASH,c2pa-training-v1=TRAINING` 形式の設定を解釈する。
+    ///
+    /// シンボルは空でなく、[`MAX_SYMBOL_LEN`] 文字以内でなければならない。
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut symbols = HashMap::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (extension_id, symbol) = entry
+                .split_once('=')
+                .map(|(id, sym)| (id.trim(), sym.trim()))
+                .ok_or_else(|| format!("extension_id=シンボル の形式ではありません: {entry}"))?;
+            if extension_id.is_empty() || symbol.is_empty() {
+                return Err(format!("extension_idとシンボルは空にできません: {entry}"));
+            }
+            if symbol.chars().count() > MAX_SYMBOL_LEN {
+                return Err(format!(
+                    "シンボルが長すぎます（最大{MAX_SYMBOL_LEN}文字）: {symbol}"
+                ));
+            }
+            symbols.insert(extension_id.to_string(), symbol.to_string());
+        }
+        Ok(Self { symbols })
+    }
+
+    /// 設定済みのExtension数を返す。
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// 設定が空かどうか。
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// シンボルが設定されたExtension IDを返す。
+    pub fn extension_ids(&self) -> impl Iterator<Item = &str> {
+        self.symbols.keys().map(String::as_str)
+    }
+
+    /// Extensionのシンボルを返す。未設定の場合は [`default_extension_symbol`]。
+    pub fn symbol_for(&self, extension_id: &str) -> String {
+        self.symbols
+            .get(extension_id)
+            .cloned()
+            .unwrap_or_else(|| default_extension_symbol(extension_id))
+    }
+}
+
+/// Extension IDから既定のシンボルを導出する（大文字化し [`MAX_SYMBOL_LEN`] 文字に切り詰める）。
+pub fn default_extension_symbol(extension_id: &str) -> String {
+    extension_id
+        .chars()
+        .flat_map(char::to_uppercase)
+        .take(MAX_SYMBOL_LEN)
+        .collect()
+}
+
+/// signed_jsonからcNFTメタデータを構築する。
+/// 仕様書 §5.1 Step 11
+///
+/// - name: `Title #{content_hashの先頭8桁}`
+/// - symbol: Coreは [`CORE_SYMBOL`]、Extensionは `payload.extension_id` に対応するシンボル
+/// - uri: signed_jsonのオフチェーンURI
+pub fn build_cnft_metadata(
+    signed_json: &SignedJson,
+    signed_json_uri: &str,
+    extension_symbols: &ExtensionSymbols,
+) -> Result<CnftMetadata, String> {
+    let content_hash = signed_json
+        .payload
+        .get("content_hash")
+        .and_then(|v| v.as_str())
+        .ok_or("signed_json.payload.content_hashが見つかりません")?;
+    let hash_suffix = if content_hash.len() > 2 {
+        &content_hash[2..content_hash.len().min(10)]
+    } else {
+        content_hash
+    };
+
+    let symbol = if signed_json.core.protocol == "Title-Extension-v1" {
+        let extension_id = signed_json
+            .payload
+            .get("extension_id")
+            .and_then(|v| v.as_str())
+            .ok_or("signed_json.payload.extension_idが見つかりません")?;
+        extension_symbols.symbol_for(extension_id)
+    } else {
+        CORE_SYMBOL.to_string()
+    };
+
+    Ok(CnftMetadata {
+        name: format!("Title #{hash_suffix}"),
+        symbol,
+        uri: signed_json_uri.to_string(),
+        attributes: signed_json.attributes.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use title_types::SignedJsonCore;
+
+    fn signed_json(protocol: &str, payload: serde_json::Value) -> SignedJson {
+        SignedJson {
+            core: SignedJsonCore {
+                protocol: protocol.to_string(),
+                tee_type: "mock".to_string(),
+                tee_pubkey: String::new(),
+                tee_signature: String::new(),
+                tee_attestation: String::new(),
+            },
+            payload,
+            attributes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_extension_symbol_configured_and_default() {
+        let symbols = ExtensionSymbols::parse("phash-v1=PHASH, c2pa-training-v1=TRAINING").unwrap();
+        assert_eq!(symbols.len(), 2);
+
+        let phash = signed_json(
+            "Title-Extension-v1",
+            serde_json::json!({"content_hash": "0x1234abcdef567890", "extension_id": "phash-v1"}),
+        );
+        let metadata = build_cnft_metadata(&phash, "ar://phash", &symbols).unwrap();
+        assert_eq!(metadata.symbol, "PHASH");
+        assert_eq!(metadata.name, "Title #1234abcd");
+
+        // 未設定のIDは大文字化・切り詰めた既定値
+        let unmapped = signed_json(
+            "Title-Extension-v1",
+            serde_json::json!({"content_hash": "0x1234abcdef567890", "extension_id": "cawg-identity-v1"}),
+        );
+        let metadata = build_cnft_metadata(&unmapped, "ar://cawg", &symbols).unwrap();
+        assert_eq!(metadata.symbol, "CAWG-IDENT");
+
+        let core = signed_json("Title-v1", serde_json::json!({"content_hash": "0x1234abcdef567890"}));
+        let metadata = build_cnft_metadata(&core, "ar://core", &symbols).unwrap();
+        assert_eq!(metadata.symbol, CORE_SYMBOL);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_symbols() {
+        assert!(ExtensionSymbols::parse("").unwrap().is_empty());
+        assert!(ExtensionSymbols::parse("phash-v1").is_err());
+        assert!(ExtensionSymbols::parse("phash-v1=").is_err());
+        assert!(ExtensionSymbols::parse("phash-v1=ABCDEFGHIJK").is_err());
+    }
+}
@@ -4,7 +4,9 @@
 //!
 //! 仕様書 §5.1, §6.4
 //!
-//! Solana上のBubblegum V2 (cNFT) トランザクション構築を行う。
+//! Solana上のBubblegum V2 (cNFT) トランザクション・メタデータ構築と、mint前のMerkle Tree容量確認を行う。
 
 #[allow(deprecated)] // solana-sdk 2.x のsystem_instruction/system_program非推奨警告を抑制
 pub mod solana_tx;
+pub mod cnft_metadata;
+pub mod tree_capacity;
@@ -17,6 +17,7 @@ use solana_sdk::{
     transaction::Transaction,
 };
 use std::str::FromStr;
+use title_types::CnftMetadata;
 
 // ---------------------------------------------------------------------------
 // プログラムID (V2)
@@ -73,6 +74,63 @@ pub fn merkle_tree_account_size(max_depth: u32, max_buffer_size: u32) -> usize {
     header_size + tree_header + b * change_log_size + path_size
 }
 
+/// spl-account-compressionのConcurrentMerkleTreeがサポートする (max_depth, max_buffer_size) の組。
+/// これ以外の組み合わせはオンチェーンの初期化で失敗する。
+pub const ALLOWED_TREE_PARAMS: &[(u32, u32)] = &[
+    (3, 8),
+    (5, 8),
+    (6, 16),
+    (7, 16),
+    (8, 16),
+    (9, 16),
+    (10, 32),
+    (11, 32),
+    (12, 32),
+    (13, 32),
+    (14, 64),
+    (14, 256),
+    (14, 1024),
+    (14, 2048),
+    (15, 64),
+    (16, 64),
+    (17, 64),
+    (18, 64),
+    (19, 64),
+    (20, 64),
+    (20, 256),
+    (20, 1024),
+    (20, 2048),
+    (24, 64),
+    (24, 256),
+    (24, 512),
+    (24, 1024),
+    (24, 2048),
+    (26, 512),
+    (26, 1024),
+    (26, 2048),
+    (30, 512),
+    (30, 1024),
+    (30, 2048),
+];
+
+/// (max_depth, max_buffer_size) がサポートされた組み合わせであることを確認する。
+/// 仕様書 §6.5 Merkle Tree
+///
+/// 不正な場合は有効な組み合わせの一覧を含むエラーメッセージを返す。
+pub fn validate_tree_params(max_depth: u32, max_buffer_size: u32) -> Result<(), String> {
+    if ALLOWED_TREE_PARAMS.contains(&(max_depth, max_buffer_size)) {
+        return Ok(());
+    }
+    let allowed = ALLOWED_TREE_PARAMS
+        .iter()
+        .map(|(d, b)| format!("({d}, {b})"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(format!(
+        "サポートされていない (max_depth, max_buffer_size) の組み合わせです: ({max_depth}, {max_buffer_size})。有効な組み合わせ: {allowed}"
+    ))
+}
+
 /// Solanaのrent-exempt minimum lamportsを計算する。
 /// `(128 + data_len) * 6960`
 pub fn rent_exempt_minimum(data_len: usize) -> u64 {
@@ -149,6 +207,7 @@ pub fn build_create_tree_tx(
 /// Bubblegum V2 MintV2 トランザクションを構築する。
 /// 仕様書 §5.1 Step 9-10, §6.5 Merkle Tree
 ///
+/// メタデータは [`super::cnft_metadata::build_cnft_metadata`] で構築したものを使用する。
 /// core_collectionが指定された場合、MPL-Coreコレクションへのミントを行う。
 /// TEEがcollection_authorityとtree_creator_or_delegateの両方を兼ねる。
 ///
@@ -157,12 +216,12 @@ pub fn build_create_tree_tx(
 ///
 /// 署名者: fee_payer (fee payer), tee_signing_pubkey (tree delegate + collection authority)
 /// TEEはtee_signing_pubkeyで部分署名する。fee_payerは後から署名を追加する。
+#[allow(clippy::too_many_arguments)]
 pub fn build_mint_v2_tx(
     tree_pubkey: &Pubkey,
     tee_signing_pubkey: &Pubkey,
     creator_wallet: &Pubkey,
-    content_hash: &str,
-    signed_json_uri: &str,
+    metadata: &CnftMetadata,
     core_collection: Option<&Pubkey>,
     blockhash: &solana_sdk::hash::Hash,
     fee_payer: Option<&Pubkey>,
@@ -170,18 +229,11 @@ pub fn build_mint_v2_tx(
     let payer = fee_payer.unwrap_or(creator_wallet);
     let (tree_config, _) = derive_tree_config(tree_pubkey);
 
-    // cNFTメタデータ構築（仕様書 §5.1 Step 11）
-    let hash_suffix = if content_hash.len() > 2 {
-        &content_hash[2..content_hash.len().min(10)]
-    } else {
-        content_hash
-    };
-    let name = format!("Title #{hash_suffix}");
-
+    // cNFTメタデータ（仕様書 §5.1 Step 11）
     let metadata = MetadataArgsV2 {
-        name,
-        symbol: "TITLE".to_string(),
-        uri: signed_json_uri.to_string(),
+        name: metadata.name.clone(),
+        symbol: metadata.symbol.clone(),
+        uri: metadata.uri.clone(),
         seller_fee_basis_points: 0,
         primary_sale_happened: false,
         is_mutable: false,
@@ -279,6 +331,15 @@ mod tests {
         assert_eq!(merkle_tree_account_size(20, 1024), 697080);
     }
 
+    #[test]
+    fn test_validate_tree_params_accepts_small_trees() {
+        // spl-account-compressionがサポートする小規模Treeの組み合わせ
+        for (depth, buffer) in [(6, 16), (7, 16), (8, 16), (9, 16), (10, 32), (11, 32), (12, 32), (13, 32)] {
+            validate_tree_params(depth, buffer).unwrap();
+        }
+        assert!(validate_tree_params(10, 16).is_err());
+    }
+
     #[test]
     fn test_derive_tree_config() {
         let tree = Pubkey::new_unique();
@@ -315,6 +376,15 @@ mod tests {
         assert_eq!(tx.message.instructions.len(), 3);
     }
 
+    fn test_metadata() -> CnftMetadata {
+        CnftMetadata {
+            name: "Title #1234abcd".to_string(),
+            symbol: "TITLE".to_string(),
+            uri: "ar://test_uri".to_string(),
+            attributes: vec![],
+        }
+    }
+
     #[test]
     fn test_build_mint_v2_tx_without_collection() {
         let tree = Pubkey::new_unique();
@@ -326,8 +396,7 @@ mod tests {
             &tree,
             &tee_signer,
             &creator,
-            "0x1234abcdef567890",
-            "ar://test_uri",
+            &test_metadata(),
             None,
             &blockhash,
             None,
@@ -351,8 +420,7 @@ mod tests {
             &tree,
             &tee_signer,
             &creator,
-            "0x1234abcdef567890",
-            "ar://test_uri",
+            &test_metadata(),
             Some(&collection),
             &blockhash,
             None,
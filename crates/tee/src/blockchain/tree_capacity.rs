@@ -0,0 +1,135 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Merkle Treeの容量確認
+//!
+//! 仕様書 §6.5
+//!
+//! 満杯のMerkle Treeへのmintはオンチェーンで失敗し、クライアントが手数料を失う。
+//! /signはバッチのmint数をTreeごとに合計し、BubblegumのTreeConfig（`num_minted` /
+//! `total_mint_capacity`）をSolana RPC経由で取得して、残り容量が足りなければ拒否する。
+
+use base64::Engine;
+use mpl_bubblegum::accounts::TreeConfig;
+use solana_sdk::pubkey::Pubkey;
+
+use super::solana_tx::derive_tree_config;
+use crate::infra::proxy_client::ProxyHttpClient;
+
+/// RPCレスポンスの上限（TreeConfigアカウントのgetAccountInfo応答は数百バイト）
+const RPC_MAX_RESPONSE_BYTES: u64 = 64 * 1024;
+
+/// RPC呼び出し1回のタイムアウト（容量確認は/signの前段で行うため短くする）
+const RPC_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// 容量確認全体（再試行を含む）のタイムアウト
+pub const CAPACITY_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Merkle Treeの発行済み数と容量。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeCapacity {
+    /// 発行済みcNFT数
+    pub num_minted: u64,
+    /// 発行可能な最大数（2^max_depth）
+    pub total_mint_capacity: u64,
+}
+
+impl TreeCapacity {
+    /// 残りmint可能数。
+    pub fn remaining(&self) -> u64 {
+        self.total_mint_capacity.saturating_sub(self.num_minted)
+    }
+}
+
+/// TreeConfigアカウントのデータから容量を読み取る。
+pub fn parse_tree_config(data: &[u8]) -> Result<TreeCapacity, String> {
+    let config =
+        TreeConfig::from_bytes(data).map_err(|e| format!("TreeConfigのデコードに失敗: {e}"))?;
+    Ok(TreeCapacity {
+        num_minted: config.num_minted,
+        total_mint_capacity: config.total_mint_capacity,
+    })
+}
+
+/// プロキシ経由でSolana RPC `getAccountInfo` を呼び出し、Merkle Treeの容量を取得する。
+/// 仕様書 §6.5
+///
+/// 再試行を含めて [`CAPACITY_CHECK_TIMEOUT`] 以内に応答がない場合はエラーとする。
+pub async fn fetch_tree_capacity(
+    proxy_addr: &str,
+    rpc_url: &str,
+    tree: &Pubkey,
+) -> Result<TreeCapacity, String> {
+    tokio::time::timeout(CAPACITY_CHECK_TIMEOUT, fetch_tree_capacity_inner(proxy_addr, rpc_url, tree))
+        .await
+        .map_err(|_| {
+            format!("{}秒以内にRPCの応答がありません", CAPACITY_CHECK_TIMEOUT.as_secs())
+        })?
+}
+
+async fn fetch_tree_capacity_inner(
+    proxy_addr: &str,
+    rpc_url: &str,
+    tree: &Pubkey,
+) -> Result<TreeCapacity, String> {
+    let (tree_config, _) = derive_tree_config(tree);
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "getAccountInfo",
+        "params": [tree_config.to_string(), { "encoding": "base64" }],
+    });
+    let body = serde_json::to_vec(&request).map_err(|e| format!("RPCリクエストの構築に失敗: {e}"))?;
+
+    let response = ProxyHttpClient::new(proxy_addr)
+        .with_max_response_bytes(RPC_MAX_RESPONSE_BYTES)
+        .with_timeout(RPC_TIMEOUT)
+        .post(rpc_url, &body)
+        .await
+        .map_err(|e| format!("RPC呼び出しに失敗: {e}"))?;
+
+    let json: serde_json::Value = serde_json::from_slice(&response.body)
+        .map_err(|e| format!("RPCレスポンスのパースに失敗: {e}"))?;
+    if let Some(error) = json.get("error") {
+        return Err(format!("RPCエラー: {error}"));
+    }
+    let data_b64 = json["result"]["value"]["data"][0]
+        .as_str()
+        .ok_or_else(|| format!("TreeConfigアカウントが見つかりません: {tree_config}"))?;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(data_b64)
+        .map_err(|e| format!("アカウントデータのBase64デコードに失敗: {e}"))?;
+
+    parse_tree_config(&data)
+}
+
+/// テスト用: 指定した発行済み数・容量のTreeConfigアカウントデータを構築する。
+#[cfg(test)]
+pub(crate) fn tree_config_bytes(num_minted: u64, total_mint_capacity: u64) -> Vec<u8> {
+    let mut data = vec![0u8; 8 + 32 + 32];
+    data.extend_from_slice(&total_mint_capacity.to_le_bytes());
+    data.extend_from_slice(&num_minted.to_le_bytes());
+    // is_public, is_decompressible (Disabled), version (V2)
+    data.extend_from_slice(&[0, 1, 1]);
+    data.resize(TreeConfig::LEN, 0);
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tree_config() {
+        let capacity = parse_tree_config(&tree_config_bytes(3, 16384)).unwrap();
+        assert_eq!(
+            capacity,
+            TreeCapacity {
+                num_minted: 3,
+                total_mint_capacity: 16384
+            }
+        );
+        assert_eq!(capacity.remaining(), 16381);
+        assert_eq!(parse_tree_config(&tree_config_bytes(16384, 16384)).unwrap().remaining(), 0);
+        assert!(parse_tree_config(&[0u8; 10]).is_err());
+    }
+}
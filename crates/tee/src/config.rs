@@ -7,11 +7,15 @@
 //! TEEサーバーの共有状態の定義。
 //! `GatewayState`（`crates/gateway/src/config.rs`）と同パターン。
 
-use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use solana_sdk::pubkey::Pubkey;
 
+use crate::extension_registry::ExtensionRegistry;
+use crate::infra::admission::PriorityAdmission;
+use crate::infra::sign_rate::SignRateLimiter;
+use crate::infra::inflight::InflightVerifies;
+use crate::infra::missing_objects::MissingObjects;
 use crate::runtime::TeeRuntime;
 use crate::wasm_loader::WasmLoader;
 
@@ -57,8 +61,87 @@ pub struct TeeAppState {
     /// 統合リソースプール（raw binary + decoded data の単一予算管理）。
     /// 仕様書 §6.4, §7.1
     pub resource_pool: Arc<title_wasm_host::ResourcePool>,
-    /// 信頼されたExtension IDの一覧。
-    /// 仕様書 §6.4 不正WASMインジェクション防御
-    /// Noneの場合は全Extension許可（開発環境用）、Someの場合は一覧にあるIDのみ許可。
-    pub trusted_extension_ids: Option<HashSet<String>>,
+    /// Extensionレジストリ（環境変数 TRUSTED_EXTENSIONS / EXTENSION_SYMBOLS で設定）。
+    /// 仕様書 §5.1 Step 11, §6.4 不正WASMインジェクション防御
+    /// 実行を許可するExtensionとcNFTシンボルを保持する。許可一覧が未設定の場合は全Extension許可（開発環境用）。
+    pub extension_registry: ExtensionRegistry,
+    /// /signで並行処理するsigned_jsonの最大数（環境変数 SIGN_CONCURRENCY で設定）。
+    /// 仕様書 §6.4
+    pub sign_concurrency: usize,
+    /// /signでsigned_json 1件の取得に許容する最大時間（秒、環境変数 SIGN_FETCH_TIMEOUT_SECS で設定）。
+    /// 仕様書 §6.4 /signフェーズでの防御
+    /// resource_limitsから算出される動的タイムアウトより短い場合のみ適用される。
+    pub sign_fetch_timeout_secs: u64,
+    /// コンパイル済みWASMモジュールのキャッシュ（環境変数 WASM_MODULE_CACHE_SIZE で容量、
+    /// WASM_MODULE_CACHE_MAX_BYTES でメモリ上限を設定）。
+    /// 仕様書 §7.1
+    /// Noneの場合はExtension実行のたびにコンパイルする。
+    pub wasm_module_cache: Option<Arc<title_wasm_host::ModuleCache>>,
+    /// 事前インスタンス化プール（環境変数 WASM_INSTANCE_POOL で有効化）。
+    /// 仕様書 §7.1
+    /// プールで実行するのは実行設定で `pooled` を指定したExtension（`wasm_hash` で固定済み）のみで、
+    /// それ以外のExtension、およびNoneの場合はExtension実行のたびに新規Store・Instanceを作成する（既定）。
+    pub wasm_instance_pool: Option<Arc<crate::infra::wasm_pool::WasmInstancePool>>,
+    /// Extension出力を共通エンベロープに正規化するか（環境変数 NORMALIZE_EXTENSION_OUTPUT で設定）。
+    /// 仕様書 §5.1 Step 5
+    /// falseの場合はWASM出力をそのまま `ExtensionPayload.result` に埋め込む。
+    pub normalize_extension_output: bool,
+    /// Extension結果（WASM出力）の最大サイズ（環境変数 EXTENSION_MAX_RESULT_BYTES で設定）。
+    /// 仕様書 §5.1 Step 5
+    /// 各Extensionの結果がこのサイズを超える場合はエラーとする。
+    pub max_extension_result_bytes: usize,
+    /// Extension補助入力の最大サイズ（環境変数 EXTENSION_MAX_INPUT_BYTES で設定）。
+    /// 仕様書 §6.4, §7.1
+    /// `extension_inputs` のいずれかの値がこのサイズを超える場合、実行前に拒否する。
+    pub max_extension_input_bytes: usize,
+    /// mint前にMerkle Treeの容量を確認するSolana RPCのURL（環境変数 TREE_CAPACITY_RPC_URL で設定）。
+    /// 仕様書 §6.5
+    /// Noneの場合は容量確認を行わない。RPCはプロキシ経由で呼び出す。
+    pub tree_capacity_rpc_url: Option<String>,
+    /// 信頼するTSA証明書のSHA-256ハッシュ一覧（環境変数 TRUSTED_TSA_KEYS で設定）。
+    /// 仕様書 §2.4
+    /// C2PA署名のTSAタイムスタンプの発行者がこの一覧に含まれる場合のみ `tsa_trusted` をtrueとする。
+    pub trusted_tsa_keys: Vec<String>,
+    /// content_hashのデプロイメント名前空間（環境変数 CONTENT_HASH_NAMESPACE で設定、既定: 空）。
+    /// 仕様書 §2.1
+    /// /verifyで算出するcontent_hashに混ぜ込み、/tree-infoで公開する。
+    pub content_hash_namespace: String,
+    /// 署名者証明書の期限間近警告の閾値（秒、環境変数 SIGNER_CERT_EXPIRY_WARNING_DAYS で日数を設定）。
+    /// 仕様書 §2.1, §5.1 Step 4
+    /// 期限切れ、または残りがこの期間以内の場合に /verify レスポンスの `signer_cert_warning` とログで警告する。
+    /// 判定は検証時刻に依存するため署名対象には含めない（Core cNFTの属性 `signer_cert_not_after` は常に付与する）。
+    pub signer_cert_expiry_warning_secs: u64,
+    /// 自己署名の署名者証明書を信頼レベルとして区別するか（環境変数 SELF_SIGNED_TRUST_LEVEL で設定）。
+    /// 仕様書 §2.1, §5.1 Step 4
+    /// 有効時、署名者証明書が自己署名ならCore cNFTの属性 `trust_level: "self_signed"` を付与する。
+    pub report_self_signed_trust_level: bool,
+    /// Core処理で受け入れるC2PA検証状態の下限（環境変数 C2PA_MIN_VALIDATION_STATE で設定）。
+    /// 仕様書 §2.1, §5.1 Step 4
+    /// 既定の `Invalid` は検証状態による拒否を行わない（ハードバインディングは常に検証する）。
+    pub min_c2pa_validation_state: title_core::C2paValidationState,
+    /// 処理を拒否するcontent_hashの一覧（環境変数 CONTENT_DENYLIST / CONTENT_DENYLIST_FILE で設定）。
+    /// 仕様書 §6.4
+    /// 一致したコンテンツはCore/Extensionの実行前に、理由を含まないエラーで拒否する。
+    pub content_denylist: crate::infra::denylist::ContentDenylist,
+    /// C2PAマニフェストストア（JUMBF）の最大サイズ（環境変数 C2PA_MAX_MANIFEST_STORE_BYTES で設定）。
+    /// 仕様書 §2.1
+    /// コンテンツ・サイドカーのストアがこのサイズを超える場合、C2PAの解析前に拒否する。
+    pub max_manifest_store_bytes: u64,
+    /// `cancel_token` 付きで処理中の検証タスク。
+    /// 仕様書 §6.4
+    /// `DELETE /verify/{token}` で該当タスクを打ち切り、予約済みメモリを解放する。
+    pub inflight_verifies: InflightVerifies,
+    /// /verify の優先度付き受付（処理枠数は環境変数 MAX_CONCURRENT_VERIFIES で設定）。
+    /// 仕様書 §6.4
+    /// 枠が埋まっている間は、優先度の高いリクエストから受け付ける。
+    pub verify_admission: PriorityAdmission,
+    /// 署名操作のレート制限（環境変数 SIGN_RATE_LIMIT で1秒あたりの署名数を設定）。
+    /// 仕様書 §6.4
+    /// 上限を超える /verify・/sign は署名を行わずに503で拒否する。
+    pub sign_rate_limiter: SignRateLimiter,
+    /// Temporary Storageで404となったダウンロードURLの記録（猶予期間は環境変数
+    /// MISSING_OBJECT_GRACE_SECS で設定）。
+    /// 仕様書 §6.4
+    /// 最初の404から猶予期間内は425（再試行可能）、過ぎた場合は404（恒久的な不在）を返す。
+    pub missing_objects: MissingObjects,
 }
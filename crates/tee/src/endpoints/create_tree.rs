@@ -47,6 +47,11 @@ pub async fn handle_create_tree(
     let request: CreateTreeRequest = serde_json::from_value(body)
         .map_err(|e| TeeError::BadRequest(format!("CreateTreeRequestのパースに失敗: {e}")))?;
 
+    // Treeパラメータの検証（仕様書 §6.5）
+    // 不正な組み合わせはオンチェーンで失敗するため、トランザクション構築前に拒否する。
+    solana_tx::validate_tree_params(request.max_depth, request.max_buffer_size)
+        .map_err(TeeError::BadRequest)?;
+
     // recent_blockhash（Base58デコード）
     let blockhash = solana_sdk::hash::Hash::from_str(&request.recent_blockhash)
         .map_err(|e| TeeError::BadRequest(format!("recent_blockhashのBase58デコードに失敗: {e}")))?;
@@ -151,6 +156,7 @@ pub async fn handle_create_tree(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::endpoints::test_helpers::test_state;
     use crate::runtime::mock::MockRuntime;
     use crate::runtime::TeeRuntime;
     use solana_sdk::transaction::Transaction;
@@ -164,17 +170,8 @@ mod tests {
         rt.generate_ext_tree_keypair();
 
         Arc::new(TeeAppState {
-            runtime: Box::new(rt),
             state: RwLock::new(TeeState::Inactive),
-            proxy_addr: "127.0.0.1:0".to_string(),
-            core_tree_address: RwLock::new(None),
-            ext_tree_address: RwLock::new(None),
-            core_collection_mint: None,
-            ext_collection_mint: None,
-            gateway_pubkey: None,
-            wasm_loader: None,
-            resource_pool: Arc::new(title_wasm_host::ResourcePool::new(1024 * 1024 * 1024)),
-            trusted_extension_ids: None,
+            ..test_state(rt)
         })
     }
 
@@ -239,6 +236,34 @@ mod tests {
         assert!(ext_addr.is_some());
     }
 
+    /// サポートされない (max_depth, max_buffer_size) の組が400で拒否され、
+    /// 有効な組では成功することを確認
+    #[tokio::test]
+    async fn test_create_tree_validates_tree_params() {
+        let state = make_test_state();
+
+        let body = serde_json::json!({
+            "max_depth": 20,
+            "max_buffer_size": 100,
+            "recent_blockhash": "11111111111111111111111111111111",
+        });
+        let result = handle_create_tree(State(state.clone()), Json(body)).await;
+        match result {
+            Err(TeeError::BadRequest(msg)) => assert!(msg.contains("(20, 64)"), "{msg}"),
+            other => panic!("BadRequestが期待されます: {:?}", other.map(|r| r.0)),
+        }
+        // 拒否された場合は状態遷移しない
+        assert_eq!(*state.state.read().await, TeeState::Inactive);
+
+        let body = serde_json::json!({
+            "max_depth": 14,
+            "max_buffer_size": 64,
+            "recent_blockhash": "11111111111111111111111111111111",
+        });
+        assert!(handle_create_tree(State(state.clone()), Json(body)).await.is_ok());
+        assert_eq!(*state.state.read().await, TeeState::Active);
+    }
+
     /// active状態での二度目の/create-tree呼び出しが409を返すことを確認
     #[tokio::test]
     async fn test_create_tree_already_active() {
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # /metrics エンドポイント
+//!
+//! 仕様書 §6.4
+//!
+//! ノード運用者向けのモニタリング情報を返す。公開情報のみを含み、
+//! 鍵・コンテンツ等の秘密情報は含めない。
+
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::Json;
+
+use crate::config::TeeAppState;
+
+/// /metrics エンドポイントハンドラ。
+/// 仕様書 §6.4, §7.1
+///
+/// - `wasm_module_cache`: コンパイル済みWASMモジュールキャッシュの統計
+///   （`hits`, `misses`, `hit_rate`, `entries`, `capacity`, `bytes`, `max_bytes`）。キャッシュ無効時は `null`。
+/// - `wasm_instance_pool`: 事前インスタンス化プールの状態（`slots`, `prepared`）。無効時は `null`。
+pub async fn handle_metrics(State(state): State<Arc<TeeAppState>>) -> Json<serde_json::Value> {
+    let wasm_module_cache = state.wasm_module_cache.as_ref().map(|cache| {
+        let stats = cache.stats();
+        let lookups = stats.hits + stats.misses;
+        let hit_rate = if lookups == 0 {
+            0.0
+        } else {
+            stats.hits as f64 / lookups as f64
+        };
+        serde_json::json!({
+            "hits": stats.hits,
+            "misses": stats.misses,
+            "hit_rate": hit_rate,
+            "entries": stats.entries,
+            "capacity": stats.capacity,
+            "bytes": stats.bytes,
+            "max_bytes": stats.max_bytes,
+        })
+    });
+
+    let wasm_instance_pool = state.wasm_instance_pool.as_ref().map(|pool| {
+        let pool = pool.pool();
+        serde_json::json!({
+            "slots": pool.slots(),
+            "prepared": pool.prepared_count(),
+        })
+    });
+
+    Json(serde_json::json!({
+        "wasm_module_cache": wasm_module_cache,
+        "wasm_instance_pool": wasm_instance_pool,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TeeState;
+    use crate::endpoints::test_helpers::test_state;
+    use crate::runtime::mock::MockRuntime;
+    use tokio::sync::RwLock;
+
+    fn make_test_state(cache: Option<Arc<title_wasm_host::ModuleCache>>) -> Arc<TeeAppState> {
+        Arc::new(TeeAppState {
+            state: RwLock::new(TeeState::Inactive),
+            wasm_module_cache: cache,
+            ..test_state(MockRuntime::new())
+        })
+    }
+
+    /// 同一モジュールを2回実行するとヒット数が増えることを確認
+    #[tokio::test]
+    async fn test_metrics_reports_wasm_module_cache_hits() {
+        let cache = Arc::new(title_wasm_host::ModuleCache::new(4).unwrap());
+        let state = make_test_state(Some(Arc::clone(&cache)));
+
+        let wasm = wat::parse_str(
+            r#"(module
+            (memory (export "memory") 1)
+            (data (i32.const 1024) "\02\00\00\00{}")
+            (func (export "process") (result i32) (i32.const 1024))
+        )"#,
+        )
+        .unwrap();
+        let runner = title_wasm_host::WasmRunner::new(
+            10_000_000,
+            16 * 1024 * 1024,
+            title_wasm_host::DEFAULT_MAX_HOST_CALLS,
+        )
+        .with_module_cache(Arc::clone(&cache));
+
+        runner.execute(&wasm, b"content", None, "process").unwrap();
+        let Json(before) = handle_metrics(State(state.clone())).await;
+        assert_eq!(before["wasm_module_cache"]["hits"], 0);
+        assert_eq!(before["wasm_module_cache"]["misses"], 1);
+
+        runner.execute(&wasm, b"content", None, "process").unwrap();
+        let Json(after) = handle_metrics(State(state)).await;
+        assert_eq!(after["wasm_module_cache"]["hits"], 1);
+        assert_eq!(after["wasm_module_cache"]["misses"], 1);
+        assert_eq!(after["wasm_module_cache"]["entries"], 1);
+        assert_eq!(after["wasm_module_cache"]["capacity"], 4);
+        assert_eq!(after["wasm_module_cache"]["hit_rate"], 0.5);
+        assert!(after["wasm_module_cache"]["bytes"].as_u64().unwrap() > 0);
+        assert!(after["wasm_module_cache"]["max_bytes"].is_null());
+    }
+
+    /// キャッシュ無効時はnullを返すことを確認
+    #[tokio::test]
+    async fn test_metrics_without_cache() {
+        let Json(metrics) = handle_metrics(State(make_test_state(None))).await;
+        assert!(metrics["wasm_module_cache"].is_null());
+    }
+}
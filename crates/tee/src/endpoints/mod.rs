@@ -5,20 +5,44 @@
 //! 仕様書 §6.4
 
 pub mod create_tree;
+pub mod metrics;
 pub mod register_node;
+pub mod self_test;
 pub mod sign;
+pub mod tree_info;
 pub mod verify;
 
 #[cfg(test)]
 pub(crate) mod test_helpers;
 
 pub use create_tree::handle_create_tree;
+pub use metrics::handle_metrics;
 pub use register_node::handle_register_node;
+pub use self_test::handle_self_test;
 pub use sign::handle_sign;
-pub use verify::handle_verify;
+pub use tree_info::handle_tree_info;
+pub use verify::{handle_cancel_verify, handle_verify};
+
+use std::str::FromStr;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::TeeError;
 
 /// Base64エンジン（Standard）。
 /// 全エンドポイントで共通使用。
 pub(crate) fn b64() -> base64::engine::GeneralPurpose {
     base64::engine::general_purpose::STANDARD
 }
+
+/// ウォレットアドレス（Base58エンコードされた32バイトのEd25519公開鍵）を検証する。
+/// 仕様書 §5.1 Step 9
+///
+/// cNFTの宛先となるため、トランザクション構築前に検証し不正値は `BadRequest` とする。
+pub(crate) fn parse_wallet_pubkey(field: &str, wallet: &str) -> Result<Pubkey, TeeError> {
+    Pubkey::from_str(wallet).map_err(|e| {
+        TeeError::BadRequest(format!(
+            "{field}はBase58エンコードされた32バイトの公開鍵である必要があります: {e}"
+        ))
+    })
+}
@@ -49,11 +49,6 @@ fn anchor_disc_register_tee_node() -> [u8; 8] {
     disc
 }
 
-/// GlobalConfig PDA導出。seeds = [b"global-config"]
-fn find_global_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[b"global-config"], program_id)
-}
-
 /// TeeNodeAccount PDA導出。seeds = [b"tee-node", &signing_pubkey]
 fn find_tee_node_pda(signing_pubkey: &[u8; 32], program_id: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[b"tee-node", signing_pubkey.as_ref()], program_id)
@@ -129,7 +124,7 @@ pub async fn handle_register_node(
     let mpl_core_program = Pubkey::from_str("CoREENxT6tW1HoK8ypY1SxRMZTcVPm7R94rH4PZNhX7d").unwrap();
 
     // PDA導出
-    let (global_config_pda, _) = find_global_config_pda(&program_id);
+    let (global_config_pda, _) = title_types::pda::global_config_pda(&program_id);
     let (tee_node_pda, _) = find_tee_node_pda(&signing_pubkey_bytes, &program_id);
 
     // MeasurementEntry構築: key=[u8;16] (null-padded), value=[u8;48]
@@ -225,6 +220,7 @@ pub async fn handle_register_node(
 mod tests {
     use super::*;
     use crate::config::TeeState;
+    use crate::endpoints::test_helpers::test_state;
     use crate::runtime::mock::MockRuntime;
     use crate::runtime::TeeRuntime;
     use tokio::sync::RwLock;
@@ -237,17 +233,8 @@ mod tests {
         rt.generate_ext_tree_keypair();
 
         Arc::new(TeeAppState {
-            runtime: Box::new(rt),
             state: RwLock::new(TeeState::Inactive),
-            proxy_addr: "127.0.0.1:0".to_string(),
-            core_tree_address: RwLock::new(None),
-            ext_tree_address: RwLock::new(None),
-            core_collection_mint: None,
-            ext_collection_mint: None,
-            gateway_pubkey: None,
-            wasm_loader: None,
-            resource_pool: Arc::new(title_wasm_host::ResourcePool::new(1024 * 1024 * 1024)),
-            trusted_extension_ids: None,
+            ..test_state(rt)
         })
     }
 
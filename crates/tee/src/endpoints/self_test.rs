@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # /self-test エンドポイント
+//!
+//! 仕様書 §6.4
+//!
+//! 信頼済みExtension（`TRUSTED_EXTENSIONS`）ごとにWASMモジュールをロードし、
+//! 組み込みの小さなフィクスチャで実行して結果を返す。
+//! WASM_DIR・WASM_BASE_URLの設定ミスやバイナリの欠落・破損を、
+//! 実際の /verify が失敗する前にオペレーターが検出するために使用する。
+
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::Json;
+
+use title_types::{ExtensionSelfTestResult, SelfTestResponse};
+use title_wasm_host::WasmError;
+
+use crate::config::TeeAppState;
+use crate::error::TeeError;
+
+use super::verify::{acquire_instance_slot, extension_runner, format_content_hash};
+
+/// 自己診断用のフィクスチャ（8x8グレースケールPNG）。
+const SELF_TEST_FIXTURE: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
+    0x52, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0xe1,
+    0x64, 0xe1, 0x57, 0x00, 0x00, 0x00, 0x30, 0x49, 0x44, 0x41, 0x54, 0x78, 0xda, 0x63, 0x60,
+    0x50, 0x70, 0x48, 0x68, 0x58, 0x70, 0xe0, 0x01, 0x83, 0x80, 0x41, 0x40, 0xc1, 0x84, 0x0d,
+    0x17, 0x3e, 0xc0, 0x05, 0x18, 0xa0, 0x02, 0x02, 0x0c, 0x50, 0x01, 0x05, 0x06, 0xa8, 0x80,
+    0x01, 0x03, 0x54, 0xc0, 0x81, 0x01, 0x2a, 0x10, 0x00, 0x00, 0x27, 0x84, 0x1e, 0x01, 0x97,
+    0xc0, 0x75, 0x00, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+/// /self-test エンドポイントハンドラ。
+/// 仕様書 §6.4
+///
+/// Gateway認証を要求する（WASM実行のコストがかかるため）。
+/// `TRUSTED_EXTENSIONS` が未設定の場合は対象を列挙できないため503を返す。
+pub async fn handle_self_test(
+    State(state): State<Arc<TeeAppState>>,
+    body: Bytes,
+) -> Result<Json<SelfTestResponse>, TeeError> {
+    let body: serde_json::Value = if body.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| TeeError::BadRequest(format!("リクエストのパースに失敗: {e}")))?
+    };
+
+    // Gateway署名の検証（§6.2）
+    crate::infra::gateway_auth::verify_gateway_auth(state.gateway_pubkey.as_ref(), &body)
+        .map_err(|(_, msg)| TeeError::Unauthorized(msg))?;
+    if state.gateway_pubkey.is_some() && body["path"] != "/self-test" {
+        return Err(TeeError::Unauthorized(
+            "Gateway認証の対象パスが一致しません".into(),
+        ));
+    }
+
+    let extension_ids = state.extension_registry.trusted_ids().ok_or_else(|| {
+        TeeError::InvalidState(
+            "TRUSTED_EXTENSIONSが未設定のため、自己診断の対象Extensionを列挙できません".into(),
+        )
+    })?;
+
+    let mut extensions = Vec::with_capacity(extension_ids.len());
+    for extension_id in extension_ids {
+        let error = run_extension(&state, extension_id).await.err();
+        extensions.push(ExtensionSelfTestResult {
+            extension_id: extension_id.to_string(),
+            ok: error.is_none(),
+            error,
+        });
+    }
+
+    Ok(Json(SelfTestResponse {
+        ok: extensions.iter().all(|e| e.ok),
+        extensions,
+    }))
+}
+
+/// Extensionをロードし、フィクスチャで実行する。
+///
+/// モジュールが自らエラーコードで応答した場合（対応外フォーマット等）は、
+/// フィクスチャの内容に依存する結果でありモジュール自体は動作しているため成功とみなす。
+async fn run_extension(state: &TeeAppState, extension_id: &str) -> Result<(), String> {
+    let loader = state
+        .wasm_loader
+        .as_ref()
+        .ok_or_else(|| "WASMローダーが設定されていません".to_string())?;
+    let wasm_binary = loader.load(extension_id).await?;
+
+    let wasm_hash = format_content_hash(&title_crypto::sha256(&wasm_binary.bytes));
+    state
+        .extension_registry
+        .check_wasm_hash(extension_id, &wasm_hash)?;
+
+    let _slot = acquire_instance_slot(state, extension_id).await;
+    match extension_runner(state, extension_id).execute_with_mime(
+        &wasm_binary.bytes,
+        SELF_TEST_FIXTURE,
+        Some("image/png"),
+        None,
+        state.extension_registry.export_name(extension_id),
+    ) {
+        Ok(_) => Ok(()),
+        Err(WasmError::UnsupportedFormat | WasmError::InvalidInput) => Ok(()),
+        Err(e) => Err(format!("WASM実行エラー: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TeeState;
+    use crate::endpoints::test_helpers::test_state;
+    use crate::runtime::mock::MockRuntime;
+    use crate::runtime::TeeRuntime;
+    use tokio::sync::RwLock;
+
+    fn make_test_state(wasm_dir: &std::path::Path, trusted: &[&str]) -> Arc<TeeAppState> {
+        let rt = MockRuntime::new();
+        rt.generate_signing_keypair();
+
+        Arc::new(TeeAppState {
+            state: RwLock::new(TeeState::Inactive),
+            wasm_loader: Some(Box::new(crate::wasm_loader::FileLoader::new(
+                wasm_dir.to_str().unwrap().to_string(),
+            ))),
+            extension_registry: crate::extension_registry::ExtensionRegistry::parse(
+                Some(&trusted.join(",")),
+                None,
+                None,
+            )
+            .unwrap(),
+            ..test_state(rt)
+        })
+    }
+
+    /// 実行できるExtensionとバイナリが存在しないExtensionを区別して報告することを確認
+    #[tokio::test]
+    async fn test_self_test_reports_good_and_missing_extension() {
+        let test_wasm = wat::parse_str(
+            r#"(module
+            (import "env" "get_content_length" (func $len (result i32)))
+            (memory (export "memory") 1)
+            ;; 結果: {"phash":"test"} = 16バイト
+            (data (i32.const 1024) "\10\00\00\00{\"phash\":\"test\"}")
+            (func (export "alloc") (param i32) (result i32) (i32.const 4096))
+            (func (export "process") (result i32)
+                (drop (call $len))
+                (i32.const 1024)
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let wasm_dir = std::env::temp_dir().join("title-test-wasm-self-test");
+        let _ = std::fs::create_dir_all(&wasm_dir);
+        std::fs::write(wasm_dir.join("phash-v1.wasm"), &test_wasm).unwrap();
+
+        let state = make_test_state(&wasm_dir, &["phash-v1", "missing-v1"]);
+        let response = handle_self_test(State(state), Bytes::new()).await.unwrap().0;
+        let _ = std::fs::remove_dir_all(&wasm_dir);
+
+        assert!(!response.ok);
+        assert_eq!(response.extensions.len(), 2);
+        // extension_idの昇順
+        let missing = &response.extensions[0];
+        assert_eq!(missing.extension_id, "missing-v1");
+        assert!(!missing.ok);
+        assert!(missing.error.is_some());
+        let good = &response.extensions[1];
+        assert_eq!(good.extension_id, "phash-v1");
+        assert!(good.ok, "{:?}", good.error);
+        assert!(good.error.is_none());
+    }
+}
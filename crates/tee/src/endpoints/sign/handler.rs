@@ -2,6 +2,7 @@
 
 //! /sign ハンドラ実装
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -11,13 +12,16 @@ use base64::Engine;
 use ed25519_dalek::VerifyingKey;
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
 use title_types::{SignRequest, SignResponse, SignedJson};
 
 use crate::config::{TeeAppState, TeeState};
 use crate::error::TeeError;
+use crate::infra::denylist::REFUSAL_MESSAGE;
 use crate::infra::security::{self, SecurityError};
-use crate::blockchain::solana_tx;
+use crate::blockchain::{cnft_metadata, solana_tx, tree_capacity};
 use crate::endpoints::b64;
 
 /// /sign エンドポイントハンドラ。
@@ -42,6 +46,10 @@ pub async fn handle_sign(
     let request: SignRequest = serde_json::from_value(inner_body)
         .map_err(|e| TeeError::BadRequest(format!("SignRequestのパースに失敗: {e}")))?;
 
+    // 署名レートの制限（§6.4）。requestsごとに1回署名するため、件数分をまとめて予約する。
+    // 予約は部分署名済みトランザクションを返却する場合のみ確定し、失敗時は返却される
+    let reservation = state.sign_rate_limiter.acquire(request.requests.len())?;
+
     // fee_payer（sign-and-mint時にGatewayウォレットをfee payerとして使用）
     let fee_payer_pubkey = match &request.fee_payer {
         Some(fp) => Some(Pubkey::from_str(fp)
@@ -50,7 +58,7 @@ pub async fn handle_sign(
     };
 
     // resource_limitsの適用（§6.4）
-    let limits = security::resolve_limits(resource_limits.as_ref());
+    let limits = security::resolve_limits(resource_limits.as_ref())?;
     let chunk_timeout = Duration::from_secs(limits.chunk_read_timeout_sec);
 
     // recent_blockhash（Base58デコード）
@@ -71,131 +79,281 @@ pub async fn handle_sign(
     let total_content_estimate = request.requests.len() as u64 * security::MAX_SIGNED_JSON_SIZE;
     let global_timeout = security::compute_dynamic_timeout(&limits, total_content_estimate);
 
-    let partial_txs = tokio::time::timeout(global_timeout, async {
-    let mut partial_txs = Vec::new();
-
-    for item in &request.requests {
-        // Step 1: signed_json_uriからJSONをフェッチ（セキュア化: サイズ制限+チャンクタイムアウト+セマフォ）
-        // 仕様書 §6.4 /signフェーズでの防御（Verify on Sign）
-        // ダウンロード全体にグローバルタイムアウトを適用
-        let download_timeout =
-            security::compute_dynamic_timeout(&limits, security::MAX_SIGNED_JSON_SIZE);
-        let (proxy_response, _sign_ticket) = tokio::time::timeout(
-            download_timeout,
-            security::proxy_get_secured(
-                &state.proxy_addr,
-                &item.signed_json_uri,
-                security::MAX_SIGNED_JSON_SIZE,
-                chunk_timeout,
-                &state.resource_pool,
-            ),
+    // 各アイテムで共有する署名コンテキスト
+    let ctx = Arc::new(SignContext {
+        blockhash,
+        tee_signing_pubkey,
+        verifying_key,
+        fee_payer: fee_payer_pubkey,
+        // ノード設定の取得タイムアウトで上限を設け、応答の遅いURIで/signが滞留しないようにする
+        download_timeout: security::compute_dynamic_timeout(
+            &limits,
+            security::MAX_SIGNED_JSON_SIZE,
         )
-        .await
-        .map_err(|_| TeeError::Timeout)?
-        .map_err(|e| match &e {
-            SecurityError::PayloadTooLarge { .. } => TeeError::PayloadTooLarge(format!("signed_jsonのサイズが上限を超えています: {e}")),
-            SecurityError::MemoryLimitExceeded => TeeError::ServiceUnavailable(e.to_string()),
-            SecurityError::ChunkReadTimeout { .. } => TeeError::Timeout,
-            SecurityError::ProxyError(status) => {
-                TeeError::BadGateway(format!("オフチェーンストレージがエラーを返しました: HTTP {status}"))
+        .min(Duration::from_secs(state.sign_fetch_timeout_secs)),
+        chunk_timeout,
+    });
+
+    // 同時処理数を制限しつつ各アイテムを並行処理する（仕様書 §6.4）
+    // 結果はリクエスト順に回収するため、partial_txsの順序はrequestsと一致する
+    let concurrency = Arc::new(Semaphore::new(state.sign_concurrency.max(1)));
+
+    // タスクはJoinSetで保持し、タイムアウト・失敗時にJoinSetのDropで未完了のタスクを打ち切る
+    let partial_txs = tokio::time::timeout(global_timeout, async {
+        let item_count = request.requests.len();
+        let mut tasks = JoinSet::new();
+        for (index, item) in request.requests.into_iter().enumerate() {
+            let state = Arc::clone(&state);
+            let ctx = Arc::clone(&ctx);
+            let concurrency = Arc::clone(&concurrency);
+            tasks.spawn(async move {
+                let result = async {
+                    let _permit = concurrency.acquire_owned().await.map_err(|_| {
+                        TeeError::Internal("同時処理数セマフォが閉じられました".into())
+                    })?;
+                    sign_item(&state, &ctx, &item.signed_json_uri).await
+                }
+                .await;
+                (index, result)
+            });
+        }
+
+        // 1件でも失敗した場合はバッチ全体を失敗とし、失敗したアイテムをインデックス付きで報告する。
+        // 残りのアイテムはJoinSetのDropで打ち切られる
+        let mut signed: Vec<Option<SignedItem>> = (0..item_count).map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            let (index, result) =
+                joined.map_err(|e| TeeError::Internal(format!("署名タスクの実行に失敗: {e}")))?;
+            match result {
+                Ok(item) => signed[index] = Some(item),
+                Err(e) => {
+                    tracing::warn!(index, error = %e, "signリクエストの処理に失敗");
+                    return Err(with_item_index(e, index));
+                }
             }
-            _ => TeeError::BadGateway(format!("signed_jsonの取得に失敗: {e}")),
-        })?;
-
-        // signed_jsonをパース
-        let signed_json: SignedJson = serde_json::from_slice(&proxy_response.body)
-            .map_err(|e| TeeError::BadRequest(format!("signed_jsonのパースに失敗: {e}")))?;
-
-        // protocolに応じてTree/Collectionを選択（仕様書 §6.5）
-        let is_extension = signed_json.core.protocol == "Title-Extension-v1";
-        let tree_address_bytes = if is_extension {
-            let addr = state.ext_tree_address.read().await;
-            addr.ok_or(TeeError::Internal(
-                "Extension Merkle Treeが未作成です。先に/create-treeを呼び出してください".into(),
-            ))?
-        } else {
-            let addr = state.core_tree_address.read().await;
-            addr.ok_or(TeeError::Internal(
-                "Core Merkle Treeが未作成です。先に/create-treeを呼び出してください".into(),
-            ))?
-        };
-        let tree_pubkey = Pubkey::new_from_array(tree_address_bytes);
-        let collection_mint = if is_extension {
-            state.ext_collection_mint.as_ref()
-        } else {
-            state.core_collection_mint.as_ref()
-        };
-
-        // Step 2: tee_signatureを自身の公開鍵で検証
-        // 仕様書 §6.4: 自身が生成したsigned_jsonであることの確認
-        // TEE再起動（鍵ローテーション）後は旧signed_jsonが自動的に拒否される
-        let sig_bytes = b64().decode(&signed_json.core.tee_signature)
-            .map_err(|e| TeeError::BadRequest(format!("tee_signatureのBase64デコードに失敗: {e}")))?;
-        let sig_arr: [u8; 64] = sig_bytes.try_into()
-            .map_err(|_| TeeError::BadRequest("tee_signatureは64バイトである必要があります".into()))?;
-        let ed_signature = ed25519_dalek::Signature::from_bytes(&sig_arr);
-
-        // 署名対象を再構築して検証
-        let sign_target = serde_json::json!({
-            "payload": signed_json.payload,
-            "attributes": signed_json.attributes,
-        });
-        let sign_bytes = serde_json::to_vec(&sign_target)
-            .map_err(|e| TeeError::Internal(format!("署名対象のシリアライズに失敗: {e}")))?;
-
-        verifying_key
-            .verify_strict(&sign_bytes, &ed_signature)
-            .map_err(|_| TeeError::Forbidden(
-                "tee_signatureの検証に失敗しました。TEEが再起動した可能性があります".into(),
-            ))?;
-
-        // Step 3: Bubblegum V2 cNFT発行トランザクション構築
-        // creator_walletを取得（仕様書 §5.1 Step 9）
-        let creator_wallet_str = signed_json
-            .payload
-            .get("creator_wallet")
-            .and_then(|v| v.as_str())
-            .ok_or(TeeError::BadRequest("signed_json.payload.creator_walletが見つかりません".into()))?;
-        let creator_wallet = Pubkey::from_str(creator_wallet_str)
-            .map_err(|e| TeeError::BadRequest(format!("creator_walletのBase58デコードに失敗: {e}")))?;
-
-        // content_hashを取得
-        let content_hash = signed_json
-            .payload
-            .get("content_hash")
-            .and_then(|v| v.as_str())
-            .ok_or(TeeError::BadRequest("signed_json.payload.content_hashが見つかりません".into()))?;
-
-        // Bubblegum V2 MintV2 トランザクション構築（仕様書 §5.1 Step 9-10）
-        let mut tx = solana_tx::build_mint_v2_tx(
-            &tree_pubkey,
-            &tee_signing_pubkey,
-            &creator_wallet,
-            content_hash,
-            &item.signed_json_uri,
-            collection_mint,
-            &blockhash,
-            fee_payer_pubkey.as_ref(),
-        );
-
-        // Step 4: TEE秘密鍵で部分署名
-        let message_bytes = tx.message.serialize();
-        let tee_sig = state.runtime.sign(&message_bytes);
-
-        solana_tx::apply_partial_signature(&mut tx, &tee_signing_pubkey, &tee_sig)
-            .map_err(|e| TeeError::Internal(format!("TEE署名の適用に失敗: {e}")))?;
-
-        // Step 5: 部分署名済みトランザクションを返却
-        let tx_bytes = solana_tx::serialize_transaction(&tx)
-            .map_err(|e| TeeError::Internal(format!("トランザクションのシリアライズに失敗: {e}")))?;
-
-        partial_txs.push(b64().encode(&tx_bytes));
-    }
+        }
+
+        let signed: Vec<SignedItem> = signed.into_iter().flatten().collect();
 
-    Ok::<Vec<String>, TeeError>(partial_txs)
+        // Merkle Treeの容量確認（仕様書 §6.5）。個々のアイテムではなくバッチ全体のmint数で判定する
+        check_tree_capacity(&state, &signed).await?;
+
+        Ok(signed.into_iter().map(|item| item.tx).collect::<Vec<_>>())
     })
     .await
     .map_err(|_| TeeError::Timeout)??;
 
+    reservation.commit();
     Ok(Json(SignResponse { partial_txs }))
 }
+
+/// 各signアイテムで共有する署名コンテキスト。
+struct SignContext {
+    /// トランザクションに使用するrecent_blockhash
+    blockhash: solana_sdk::hash::Hash,
+    /// TEE署名用公開鍵（tree delegate + collection authority）
+    tee_signing_pubkey: Pubkey,
+    /// tee_signature検証用キー
+    verifying_key: VerifyingKey,
+    /// fee payer（sign-and-mint時のGatewayウォレット）
+    fee_payer: Option<Pubkey>,
+    /// signed_json 1件あたりのダウンロードタイムアウト
+    download_timeout: Duration,
+    /// チャンク読み取りタイムアウト
+    chunk_timeout: Duration,
+}
+
+/// エラーメッセージに失敗したアイテムのインデックスを付与する。
+fn with_item_index(error: TeeError, index: usize) -> TeeError {
+    let prefix = format!("requests[{index}]");
+    match error {
+        TeeError::BadRequest(m) => TeeError::BadRequest(format!("{prefix}: {m}")),
+        TeeError::Internal(m) => TeeError::Internal(format!("{prefix}: {m}")),
+        TeeError::PayloadTooLarge(m) => TeeError::PayloadTooLarge(format!("{prefix}: {m}")),
+        TeeError::BadGateway(m) => TeeError::BadGateway(format!("{prefix}: {m}")),
+        TeeError::Forbidden(m) => TeeError::Forbidden(format!("{prefix}: {m}")),
+        TeeError::ServiceUnavailable(m) => {
+            TeeError::ServiceUnavailable(format!("{prefix}: {m}"))
+        }
+        other => other,
+    }
+}
+
+/// 1件分の部分署名済みcNFT発行トランザクション。
+struct SignedItem {
+    /// Base64エンコードされた部分署名済みトランザクション
+    tx: String,
+    /// mint先のMerkle Tree
+    tree: Pubkey,
+}
+
+/// バッチのmint数をTreeごとに合計し、Merkle Treeの残り容量と照合する。
+/// 仕様書 §6.5
+///
+/// 容量を超えるmintはオンチェーンで失敗するため、部分署名済みトランザクションを返す前に拒否する。
+/// RPCに到達できない・応答しない場合は確認をスキップする（mint自体はオンチェーンで検証される）。
+async fn check_tree_capacity(state: &TeeAppState, items: &[SignedItem]) -> Result<(), TeeError> {
+    let Some(rpc_url) = &state.tree_capacity_rpc_url else {
+        return Ok(());
+    };
+    let mut mints: BTreeMap<Pubkey, u64> = BTreeMap::new();
+    for item in items {
+        *mints.entry(item.tree).or_default() += 1;
+    }
+    for (tree, count) in mints {
+        match tree_capacity::fetch_tree_capacity(&state.proxy_addr, rpc_url, &tree).await {
+            Ok(capacity) if capacity.remaining() < count => {
+                return Err(TeeError::Conflict(format!(
+                    "Merkle Treeの残り容量が不足しています（{}/{}件発行済み、このバッチで{count}件）: \
+                     {tree}。新しいTreeを作成してください",
+                    capacity.num_minted, capacity.total_mint_capacity
+                )));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!(error = %e, tree = %tree, "Merkle Treeの容量確認に失敗しました");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 1件のsigned_jsonを検証し、部分署名済みcNFT発行トランザクションを返す。
+/// 仕様書 §6.4 /signフェーズ
+async fn sign_item(
+    state: &TeeAppState,
+    ctx: &SignContext,
+    signed_json_uri: &str,
+) -> Result<SignedItem, TeeError> {
+    // Step 1: signed_json_uriからJSONをフェッチ（セキュア化: サイズ制限+チャンクタイムアウト+セマフォ）
+    // 仕様書 §6.4 /signフェーズでの防御（Verify on Sign）
+    // ダウンロード全体にタイムアウトを適用し、サイズ上限はチャンク受信ごとに検査する
+    let fetch_timed_out = || {
+        TeeError::BadGateway(format!(
+            "signed_jsonの取得がタイムアウトしました（{}秒）: {signed_json_uri}",
+            ctx.download_timeout.as_secs()
+        ))
+    };
+    let (proxy_response, _sign_ticket) = tokio::time::timeout(
+        ctx.download_timeout,
+        security::proxy_get_secured(
+            &state.proxy_addr,
+            signed_json_uri,
+            security::MAX_SIGNED_JSON_SIZE,
+            ctx.chunk_timeout,
+            &state.resource_pool,
+        ),
+    )
+    .await
+    .map_err(|_| fetch_timed_out())?
+    .map_err(|e| match &e {
+        SecurityError::PayloadTooLarge { .. } => TeeError::PayloadTooLarge(format!("signed_jsonのサイズが上限を超えています: {e}")),
+        SecurityError::MemoryLimitExceeded => TeeError::ServiceUnavailable(e.to_string()),
+        SecurityError::ChunkReadTimeout { .. } => fetch_timed_out(),
+        SecurityError::ProxyError(status) => {
+            TeeError::BadGateway(format!("オフチェーンストレージがエラーを返しました: HTTP {status}"))
+        }
+        _ => TeeError::BadGateway(format!("signed_jsonの取得に失敗: {e}")),
+    })?;
+
+    // signed_jsonをパース
+    let signed_json: SignedJson = serde_json::from_slice(&proxy_response.body)
+        .map_err(|e| TeeError::BadRequest(format!("signed_jsonのパースに失敗: {e}")))?;
+
+    // 拒否リストの照合（仕様書 §6.4）。テイクダウン前に取得したsigned_jsonのmintも拒否する
+    if let Some(content_hash) = signed_json.payload.get("content_hash").and_then(|v| v.as_str()) {
+        if state.content_denylist.contains_hex(content_hash) {
+            return Err(TeeError::Forbidden(REFUSAL_MESSAGE.to_string()));
+        }
+    }
+
+    // creator_walletを取得・検証（仕様書 §5.1 Step 9）
+    let creator_wallet_str = signed_json
+        .payload
+        .get("creator_wallet")
+        .and_then(|v| v.as_str())
+        .ok_or(TeeError::BadRequest("signed_json.payload.creator_walletが見つかりません".into()))?;
+    let creator_wallet = crate::endpoints::parse_wallet_pubkey("creator_wallet", creator_wallet_str)?;
+
+    // protocolに応じてTree/Collectionを選択（仕様書 §6.5）
+    let is_extension = signed_json.core.protocol == "Title-Extension-v1";
+    let tree_address_bytes = if is_extension {
+        let addr = state.ext_tree_address.read().await;
+        addr.ok_or(TeeError::Internal(
+            "Extension Merkle Treeが未作成です。先に/create-treeを呼び出してください".into(),
+        ))?
+    } else {
+        let addr = state.core_tree_address.read().await;
+        addr.ok_or(TeeError::Internal(
+            "Core Merkle Treeが未作成です。先に/create-treeを呼び出してください".into(),
+        ))?
+    };
+    let tree_pubkey = Pubkey::new_from_array(tree_address_bytes);
+
+    let collection_mint = if is_extension {
+        state.ext_collection_mint.as_ref()
+    } else {
+        state.core_collection_mint.as_ref()
+    };
+
+    // Step 2: tee_signatureを自身の公開鍵で検証
+    // 仕様書 §6.4: 自身が生成したsigned_jsonであることの確認
+    // TEE再起動（鍵ローテーション）後は旧signed_jsonが自動的に拒否される
+    let sig_bytes = b64().decode(&signed_json.core.tee_signature)
+        .map_err(|e| TeeError::BadRequest(format!("tee_signatureのBase64デコードに失敗: {e}")))?;
+    let sig_arr: [u8; 64] = sig_bytes.try_into()
+        .map_err(|_| TeeError::BadRequest("tee_signatureは64バイトである必要があります".into()))?;
+    let ed_signature = ed25519_dalek::Signature::from_bytes(&sig_arr);
+
+    // 署名対象を再構築して検証（signed_jsonドメインのタグを付与）
+    let sign_target = serde_json::json!({
+        "payload": signed_json.payload,
+        "attributes": signed_json.attributes,
+    });
+    let sign_bytes = title_crypto::domain_separated_message(
+        title_crypto::SignatureDomain::SignedJson,
+        &title_types::canonical_json(&sign_target),
+    );
+
+    ctx.verifying_key
+        .verify_strict(&sign_bytes, &ed_signature)
+        .map_err(|_| TeeError::Forbidden(
+            "tee_signatureの検証に失敗しました。TEEが再起動した可能性があります".into(),
+        ))?;
+
+    // Step 3: Bubblegum V2 cNFT発行トランザクション構築
+    // cNFTメタデータ構築（仕様書 §5.1 Step 11）
+    let metadata = cnft_metadata::build_cnft_metadata(
+        &signed_json,
+        signed_json_uri,
+        state.extension_registry.symbols(),
+    )
+    .map_err(TeeError::BadRequest)?;
+
+    // Bubblegum V2 MintV2 トランザクション構築（仕様書 §5.1 Step 9-10）
+    let mut tx = solana_tx::build_mint_v2_tx(
+        &tree_pubkey,
+        &ctx.tee_signing_pubkey,
+        &creator_wallet,
+        &metadata,
+        collection_mint,
+        &ctx.blockhash,
+        ctx.fee_payer.as_ref(),
+    );
+
+    // Step 4: TEE秘密鍵で部分署名
+    let message_bytes = tx.message.serialize();
+    let tee_sig = state.runtime.sign(&message_bytes);
+
+    solana_tx::apply_partial_signature(&mut tx, &ctx.tee_signing_pubkey, &tee_sig)
+        .map_err(|e| TeeError::Internal(format!("TEE署名の適用に失敗: {e}")))?;
+
+    // Step 5: 部分署名済みトランザクションを返却
+    let tx_bytes = solana_tx::serialize_transaction(&tx)
+        .map_err(|e| TeeError::Internal(format!("トランザクションのシリアライズに失敗: {e}")))?;
+
+    Ok(SignedItem {
+        tx: b64().encode(&tx_bytes),
+        tree: tree_pubkey,
+    })
+}
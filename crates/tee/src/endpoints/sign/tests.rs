@@ -13,17 +13,25 @@ use crate::config::{TeeAppState, TeeState};
 use crate::error::TeeError;
 use crate::runtime::mock::MockRuntime;
 use crate::runtime::TeeRuntime;
-use crate::endpoints::test_helpers::{start_mock_storage, start_inline_proxy};
+use crate::endpoints::test_helpers::{
+    start_endless_http_server, start_inline_proxy, start_mock_storage, start_mock_storage_delayed,
+    start_mock_storage_multi, test_state,
+};
 
 use super::handler::handle_sign;
 use crate::endpoints::b64;
 
 /// テスト用のsigned_jsonを手動構築する
 fn build_test_signed_json(rt: &MockRuntime) -> SignedJson {
+    build_test_signed_json_for_wallet(rt, "11111111111111111111111111111112")
+}
+
+/// 指定したcreator_walletでテスト用のsigned_jsonを手動構築する
+fn build_test_signed_json_for_wallet(rt: &MockRuntime, creator_wallet: &str) -> SignedJson {
     let payload = serde_json::json!({
         "content_hash": "0x1234abcdef567890aabbccdd11223344556677889900aabbccddeeff00112233",
         "content_type": "image/jpeg",
-        "creator_wallet": "11111111111111111111111111111112",
+        "creator_wallet": creator_wallet,
         "nodes": [{"id": "0x1234abcd", "type": "final"}],
         "links": [],
     });
@@ -45,9 +53,9 @@ fn build_test_signed_json(rt: &MockRuntime) -> SignedJson {
         "payload": payload,
         "attributes": attributes_value,
     });
-    let sign_bytes = serde_json::to_vec(&sign_target).unwrap();
+    let sign_bytes = title_types::canonical_json(&sign_target);
 
-    let signature = rt.sign(&sign_bytes);
+    let signature = rt.sign(&title_crypto::domain_separated_message(title_crypto::SignatureDomain::SignedJson, &sign_bytes));
     let tee_pubkey_b58 = base58::ToBase58::to_base58(rt.signing_pubkey().as_slice());
     let attestation = rt.get_attestation();
 
@@ -84,17 +92,10 @@ async fn test_sign_roundtrip() {
     let tree_pubkey_bytes: [u8; 32] = rt.tree_pubkey().try_into().unwrap();
 
     let state = Arc::new(TeeAppState {
-        runtime: Box::new(rt),
-        state: RwLock::new(TeeState::Active),
         proxy_addr: format!("127.0.0.1:{proxy_port}"),
         core_tree_address: RwLock::new(Some(tree_pubkey_bytes)),
         ext_tree_address: RwLock::new(Some(tree_pubkey_bytes)),
-        core_collection_mint: None,
-        ext_collection_mint: None,
-        gateway_pubkey: None,
-        wasm_loader: None,
-        resource_pool: Arc::new(title_wasm_host::ResourcePool::new(1024 * 1024 * 1024)),
-        trusted_extension_ids: None,
+        ..test_state(rt)
     });
 
     let body = serde_json::json!({
@@ -120,6 +121,114 @@ async fn test_sign_roundtrip() {
     assert_eq!(tx.message.instructions.len(), 1);
 }
 
+/// 指定したTreeConfigアカウントデータを getAccountInfo で返すモックRPCを起動する
+async fn start_mock_rpc(account_data: Vec<u8>) -> u16 {
+    use axum::routing::post;
+
+    let data_b64 = b64().encode(account_data);
+    let app = axum::Router::new().route(
+        "/",
+        post(move || {
+            let data_b64 = data_b64.clone();
+            async move {
+                Json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": { "value": { "data": [data_b64, "base64"] } },
+                }))
+            }
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    port
+}
+
+/// Merkle Treeが満杯の場合、トランザクションを生成せずに拒否されることを確認
+#[tokio::test]
+async fn test_sign_rejects_full_tree() {
+    let rt = MockRuntime::new();
+    rt.generate_signing_keypair();
+    rt.generate_encryption_keypair();
+    rt.generate_tree_keypair();
+
+    let signed_json_bytes = serde_json::to_vec(&build_test_signed_json(&rt)).unwrap();
+    let storage_port = start_mock_storage("/signed_json", signed_json_bytes).await;
+    let proxy_port = start_inline_proxy().await;
+    let rpc_port =
+        start_mock_rpc(crate::blockchain::tree_capacity::tree_config_bytes(16384, 16384)).await;
+
+    let tree_pubkey_bytes: [u8; 32] = rt.tree_pubkey().try_into().unwrap();
+
+    let state = Arc::new(TeeAppState {
+        proxy_addr: format!("127.0.0.1:{proxy_port}"),
+        core_tree_address: RwLock::new(Some(tree_pubkey_bytes)),
+        ext_tree_address: RwLock::new(Some(tree_pubkey_bytes)),
+        tree_capacity_rpc_url: Some(format!("http://127.0.0.1:{rpc_port}/")),
+        ..test_state(rt)
+    });
+
+    let body = serde_json::json!({
+        "recent_blockhash": "11111111111111111111111111111111",
+        "requests": [{
+            "signed_json_uri": format!("http://127.0.0.1:{storage_port}/signed_json"),
+        }],
+    });
+
+    let err = handle_sign(State(state), Json(body)).await.err().unwrap();
+    match err {
+        TeeError::Conflict(msg) => assert!(msg.contains("Merkle Treeの残り容量が不足しています"), "{msg}"),
+        other => panic!("Conflictを期待: {other:?}"),
+    }
+}
+
+/// 容量の判定がアイテム単位ではなくバッチ全体のmint数で行われることを確認
+#[tokio::test]
+async fn test_sign_checks_tree_capacity_for_whole_batch() {
+    let rt = MockRuntime::new();
+    rt.generate_signing_keypair();
+    rt.generate_encryption_keypair();
+    rt.generate_tree_keypair();
+
+    let signed_json_bytes = serde_json::to_vec(&build_test_signed_json(&rt)).unwrap();
+    let storage_port = start_mock_storage("/signed_json", signed_json_bytes).await;
+    let proxy_port = start_inline_proxy().await;
+    // 残り1件
+    let rpc_port =
+        start_mock_rpc(crate::blockchain::tree_capacity::tree_config_bytes(16383, 16384)).await;
+
+    let tree_pubkey_bytes: [u8; 32] = rt.tree_pubkey().try_into().unwrap();
+    let state = Arc::new(TeeAppState {
+        proxy_addr: format!("127.0.0.1:{proxy_port}"),
+        core_tree_address: RwLock::new(Some(tree_pubkey_bytes)),
+        ext_tree_address: RwLock::new(Some(tree_pubkey_bytes)),
+        tree_capacity_rpc_url: Some(format!("http://127.0.0.1:{rpc_port}/")),
+        ..test_state(rt)
+    });
+    let uri = format!("http://127.0.0.1:{storage_port}/signed_json");
+    let body = |count: usize| {
+        serde_json::json!({
+            "recent_blockhash": "11111111111111111111111111111111",
+            "requests": vec![serde_json::json!({ "signed_json_uri": uri }); count],
+        })
+    };
+
+    // 1件は残り容量に収まる
+    let response = handle_sign(State(state.clone()), Json(body(1))).await.unwrap().0;
+    assert_eq!(response.partial_txs.len(), 1);
+
+    // 2件は個々には収まるが、合計が残り容量を超える
+    match handle_sign(State(state), Json(body(2))).await.err().unwrap() {
+        TeeError::Conflict(msg) => assert!(msg.contains("このバッチで2件"), "{msg}"),
+        other => panic!("Conflictを期待: {other:?}"),
+    }
+}
+
 /// TEE再起動（鍵ローテーション）後に旧signed_jsonが拒否されることを確認
 #[tokio::test]
 async fn test_sign_rejects_wrong_key() {
@@ -144,17 +253,10 @@ async fn test_sign_rejects_wrong_key() {
     let tree_pubkey_bytes: [u8; 32] = new_rt.tree_pubkey().try_into().unwrap();
 
     let state = Arc::new(TeeAppState {
-        runtime: Box::new(new_rt),
-        state: RwLock::new(TeeState::Active),
         proxy_addr: format!("127.0.0.1:{proxy_port}"),
         core_tree_address: RwLock::new(Some(tree_pubkey_bytes)),
         ext_tree_address: RwLock::new(Some(tree_pubkey_bytes)),
-        core_collection_mint: None,
-        ext_collection_mint: None,
-        gateway_pubkey: None,
-        wasm_loader: None,
-        resource_pool: Arc::new(title_wasm_host::ResourcePool::new(1024 * 1024 * 1024)),
-        trusted_extension_ids: None,
+        ..test_state(new_rt)
     });
 
     let body = serde_json::json!({
@@ -188,17 +290,10 @@ async fn test_sign_rejects_oversized() {
     let tree_pubkey_bytes: [u8; 32] = rt.tree_pubkey().try_into().unwrap();
 
     let state = Arc::new(TeeAppState {
-        runtime: Box::new(rt),
-        state: RwLock::new(TeeState::Active),
         proxy_addr: format!("127.0.0.1:{proxy_port}"),
         core_tree_address: RwLock::new(Some(tree_pubkey_bytes)),
         ext_tree_address: RwLock::new(Some(tree_pubkey_bytes)),
-        core_collection_mint: None,
-        ext_collection_mint: None,
-        gateway_pubkey: None,
-        wasm_loader: None,
-        resource_pool: Arc::new(title_wasm_host::ResourcePool::new(1024 * 1024 * 1024)),
-        trusted_extension_ids: None,
+        ..test_state(rt)
     });
 
     let body = serde_json::json!({
@@ -213,6 +308,64 @@ async fn test_sign_rejects_oversized() {
     assert!(matches!(result.unwrap_err(), TeeError::PayloadTooLarge(_)));
 }
 
+/// signed_json_uriの応答が遅い場合、取得タイムアウトで打ち切られることを確認
+#[tokio::test]
+async fn test_sign_fetch_timeout() {
+    let rt = MockRuntime::new();
+    rt.generate_signing_keypair();
+    rt.generate_encryption_keypair();
+    rt.generate_tree_keypair();
+
+    let signed_json = serde_json::to_vec(&build_test_signed_json(&rt)).unwrap();
+    let storage_port = start_mock_storage_delayed(
+        "/signed_json",
+        signed_json,
+        std::time::Duration::from_secs(30),
+    )
+    .await;
+    let proxy_port = start_inline_proxy().await;
+    let mut state = build_active_state(rt, proxy_port, 1);
+    Arc::get_mut(&mut state).unwrap().sign_fetch_timeout_secs = 1;
+
+    let body = serde_json::json!({
+        "recent_blockhash": "11111111111111111111111111111111",
+        "requests": [{
+            "signed_json_uri": format!("http://127.0.0.1:{storage_port}/signed_json"),
+        }],
+    });
+
+    let started = std::time::Instant::now();
+    let err = handle_sign(State(state), Json(body)).await.unwrap_err();
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    assert!(matches!(err, TeeError::BadGateway(_)), "{err}");
+    assert!(err.to_string().contains("タイムアウト"), "{err}");
+}
+
+/// Content-Lengthのない終わらないレスポンスも、受信中にサイズ上限で打ち切られることを確認
+#[tokio::test]
+async fn test_sign_rejects_oversized_stream() {
+    let rt = MockRuntime::new();
+    rt.generate_signing_keypair();
+    rt.generate_encryption_keypair();
+    rt.generate_tree_keypair();
+
+    let storage_port = start_endless_http_server().await;
+    let mut state = build_active_state(rt, 0, 1);
+    Arc::get_mut(&mut state).unwrap().proxy_addr = "direct".to_string();
+    let pool = Arc::clone(&state.resource_pool);
+
+    let body = serde_json::json!({
+        "recent_blockhash": "11111111111111111111111111111111",
+        "requests": [{
+            "signed_json_uri": format!("http://127.0.0.1:{storage_port}/signed_json"),
+        }],
+    });
+
+    let err = handle_sign(State(state), Json(body)).await.unwrap_err();
+    assert!(matches!(err, TeeError::PayloadTooLarge(_)), "{err}");
+    assert_eq!(pool.total_used(), 0);
+}
+
 /// inactive状態での/sign呼び出しが503を返すことを確認
 #[tokio::test]
 async fn test_sign_inactive_returns_503() {
@@ -222,17 +375,8 @@ async fn test_sign_inactive_returns_503() {
     rt.generate_tree_keypair();
 
     let state = Arc::new(TeeAppState {
-        runtime: Box::new(rt),
         state: RwLock::new(TeeState::Inactive),
-        proxy_addr: "127.0.0.1:0".to_string(),
-        core_tree_address: RwLock::new(None),
-        ext_tree_address: RwLock::new(None),
-        core_collection_mint: None,
-        ext_collection_mint: None,
-        gateway_pubkey: None,
-        wasm_loader: None,
-        resource_pool: Arc::new(title_wasm_host::ResourcePool::new(1024 * 1024 * 1024)),
-        trusted_extension_ids: None,
+        ..test_state(rt)
     });
 
     let body = serde_json::json!({
@@ -244,3 +388,235 @@ async fn test_sign_inactive_returns_503() {
     assert!(result.is_err());
     assert!(matches!(result.unwrap_err(), TeeError::InvalidState(_)));
 }
+
+/// 複数アイテム用のActive状態のTeeAppStateを構築する
+fn build_active_state(rt: MockRuntime, proxy_port: u16, sign_concurrency: usize) -> Arc<TeeAppState> {
+    let tree_pubkey_bytes: [u8; 32] = rt.tree_pubkey().try_into().unwrap();
+    Arc::new(TeeAppState {
+        proxy_addr: format!("127.0.0.1:{proxy_port}"),
+        core_tree_address: RwLock::new(Some(tree_pubkey_bytes)),
+        ext_tree_address: RwLock::new(Some(tree_pubkey_bytes)),
+        sign_concurrency,
+        ..test_state(rt)
+    })
+}
+
+/// 並行処理してもpartial_txsの順序がrequestsの順序と一致することを確認
+#[tokio::test]
+async fn test_sign_batch_preserves_order() {
+    let rt = MockRuntime::new();
+    rt.generate_signing_keypair();
+    rt.generate_encryption_keypair();
+    rt.generate_tree_keypair();
+
+    let signed_json_bytes = serde_json::to_vec(&build_test_signed_json(&rt)).unwrap();
+    let paths: Vec<String> = (0..5).map(|i| format!("/signed_json_{i}")).collect();
+    let routes = paths.iter().map(|p| (p.clone(), signed_json_bytes.clone())).collect();
+
+    let storage_port = start_mock_storage_multi(routes).await;
+    let proxy_port = start_inline_proxy().await;
+    let state = build_active_state(rt, proxy_port, 2);
+
+    let uris: Vec<String> = paths
+        .iter()
+        .map(|p| format!("http://127.0.0.1:{storage_port}{p}"))
+        .collect();
+    let body = serde_json::json!({
+        "recent_blockhash": "11111111111111111111111111111111",
+        "requests": uris.iter().map(|u| serde_json::json!({"signed_json_uri": u})).collect::<Vec<_>>(),
+    });
+
+    let response = handle_sign(State(state), Json(body)).await.unwrap().0;
+    assert_eq!(response.partial_txs.len(), uris.len());
+
+    // 各トランザクションのメタデータURIが対応するリクエストのURIであること
+    for (tx_b64, uri) in response.partial_txs.iter().zip(&uris) {
+        let tx_bytes = b64().decode(tx_b64).unwrap();
+        assert!(
+            tx_bytes.windows(uri.len()).any(|w| w == uri.as_bytes()),
+            "partial_txにURI {uri} が含まれていません"
+        );
+    }
+}
+
+/// 1件の不正なsigned_jsonがインデックス付きで報告されることを確認
+#[tokio::test]
+async fn test_sign_batch_reports_failed_item_index() {
+    let rt = MockRuntime::new();
+    rt.generate_signing_keypair();
+    rt.generate_encryption_keypair();
+    rt.generate_tree_keypair();
+
+    // 別のTEE鍵で署名されたsigned_json
+    let other_rt = MockRuntime::new();
+    other_rt.generate_signing_keypair();
+    other_rt.generate_encryption_keypair();
+
+    let valid = serde_json::to_vec(&build_test_signed_json(&rt)).unwrap();
+    let forged = serde_json::to_vec(&build_test_signed_json(&other_rt)).unwrap();
+    let routes = vec![
+        ("/signed_json_0".to_string(), valid.clone()),
+        ("/signed_json_1".to_string(), forged),
+        ("/signed_json_2".to_string(), valid),
+    ];
+
+    let storage_port = start_mock_storage_multi(routes).await;
+    let proxy_port = start_inline_proxy().await;
+    let state = build_active_state(rt, proxy_port, crate::infra::security::DEFAULT_SIGN_CONCURRENCY);
+
+    let body = serde_json::json!({
+        "recent_blockhash": "11111111111111111111111111111111",
+        "requests": (0..3)
+            .map(|i| serde_json::json!({
+                "signed_json_uri": format!("http://127.0.0.1:{storage_port}/signed_json_{i}"),
+            }))
+            .collect::<Vec<_>>(),
+    });
+
+    let err = handle_sign(State(state), Json(body)).await.unwrap_err();
+    assert!(matches!(err, TeeError::Forbidden(_)));
+    assert!(err.to_string().contains("requests[1]"), "unexpected error: {err}");
+}
+
+/// 不正なcreator_walletがトランザクション構築前にBadRequestで拒否されることを確認
+#[tokio::test]
+async fn test_sign_rejects_invalid_creator_wallet() {
+    let rt = MockRuntime::new();
+    rt.generate_signing_keypair();
+    rt.generate_encryption_keypair();
+    rt.generate_tree_keypair();
+
+    let invalid = serde_json::to_vec(&build_test_signed_json_for_wallet(&rt, "not-a-wallet")).unwrap();
+    let valid = serde_json::to_vec(&build_test_signed_json(&rt)).unwrap();
+    let storage_port = start_mock_storage_multi(vec![
+        ("/invalid".to_string(), invalid),
+        ("/valid".to_string(), valid),
+    ])
+    .await;
+    let proxy_port = start_inline_proxy().await;
+    let state = build_active_state(rt, proxy_port, 1);
+
+    let body = |path: &str| {
+        serde_json::json!({
+            "recent_blockhash": "11111111111111111111111111111111",
+            "requests": [{
+                "signed_json_uri": format!("http://127.0.0.1:{storage_port}{path}"),
+            }],
+        })
+    };
+
+    match handle_sign(State(state.clone()), Json(body("/invalid"))).await {
+        Err(TeeError::BadRequest(msg)) => assert!(msg.contains("creator_wallet"), "{msg}"),
+        other => panic!("BadRequestが期待されましたが {:?}", other.map(|_| ())),
+    }
+
+    let result = handle_sign(State(state), Json(body("/valid"))).await;
+    assert!(result.is_ok(), "handle_sign failed: {:?}", result.err());
+}
+
+/// 署名レートの上限を超える/signが、署名を行わずに503で拒否されることを確認。
+/// 失敗したリクエストは署名レートを消費しない
+#[tokio::test]
+async fn test_sign_rate_limit_throttles() {
+    let rt = MockRuntime::new();
+    rt.generate_signing_keypair();
+    rt.generate_encryption_keypair();
+    rt.generate_tree_keypair();
+
+    let signed_json_bytes = serde_json::to_vec(&build_test_signed_json(&rt)).unwrap();
+    let storage_port = start_mock_storage("/signed_json", signed_json_bytes).await;
+    let proxy_port = start_inline_proxy().await;
+    let mut state = build_active_state(rt, proxy_port, 1);
+    Arc::get_mut(&mut state).unwrap().sign_rate_limiter =
+        crate::infra::sign_rate::SignRateLimiter::parse(Some("2"), None).unwrap();
+
+    let body = serde_json::json!({
+        "recent_blockhash": "11111111111111111111111111111111",
+        "requests": [{
+            "signed_json_uri": format!("http://127.0.0.1:{storage_port}/signed_json"),
+        }],
+    });
+
+    // 失敗するリクエスト（取得できないsigned_json）を上限以上に送っても、後続は制限されない
+    let failing_body = serde_json::json!({
+        "recent_blockhash": "11111111111111111111111111111111",
+        "requests": [{
+            "signed_json_uri": format!("http://127.0.0.1:{storage_port}/missing"),
+        }],
+    });
+    for _ in 0..5 {
+        let err = handle_sign(State(Arc::clone(&state)), Json(failing_body.clone()))
+            .await
+            .unwrap_err();
+        assert!(!matches!(err, TeeError::ServiceUnavailable(_)), "{err:?}");
+    }
+
+    for _ in 0..2 {
+        let result = handle_sign(State(Arc::clone(&state)), Json(body.clone())).await;
+        assert!(result.is_ok(), "handle_sign failed: {:?}", result.err());
+    }
+    let err = handle_sign(State(state), Json(body)).await.unwrap_err();
+    assert!(matches!(err, TeeError::ServiceUnavailable(_)), "{err:?}");
+}
+
+/// 1件が失敗した時点で、応答の遅い他アイテムの完了を待たずにバッチが失敗することを確認
+#[tokio::test]
+async fn test_sign_batch_fails_fast_and_aborts_remaining_items() {
+    let rt = MockRuntime::new();
+    rt.generate_signing_keypair();
+    rt.generate_encryption_keypair();
+    rt.generate_tree_keypair();
+
+    let signed_json = serde_json::to_vec(&build_test_signed_json(&rt)).unwrap();
+    let slow_port = start_mock_storage_delayed(
+        "/signed_json",
+        signed_json,
+        std::time::Duration::from_secs(30),
+    )
+    .await;
+    let bad_port = start_mock_storage("/signed_json", b"not json".to_vec()).await;
+    let proxy_port = start_inline_proxy().await;
+    let state = build_active_state(rt, proxy_port, 2);
+
+    let body = serde_json::json!({
+        "recent_blockhash": "11111111111111111111111111111111",
+        "requests": [
+            {"signed_json_uri": format!("http://127.0.0.1:{bad_port}/signed_json")},
+            {"signed_json_uri": format!("http://127.0.0.1:{slow_port}/signed_json")},
+        ],
+    });
+
+    let started = std::time::Instant::now();
+    let err = handle_sign(State(state), Json(body)).await.unwrap_err();
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    assert!(err.to_string().contains("requests[0]"), "{err}");
+}
+
+/// 拒否リストに登録されたcontent_hashのsigned_jsonは、取得済みであってもmintされないことを確認
+#[tokio::test]
+async fn test_sign_rejects_denylisted_content_hash() {
+    let rt = MockRuntime::new();
+    rt.generate_signing_keypair();
+    rt.generate_encryption_keypair();
+    rt.generate_tree_keypair();
+
+    let signed_json = build_test_signed_json(&rt);
+    let content_hash = signed_json.payload["content_hash"].as_str().unwrap().to_string();
+    let storage_port =
+        start_mock_storage("/signed_json", serde_json::to_vec(&signed_json).unwrap()).await;
+    let proxy_port = start_inline_proxy().await;
+    let mut state = build_active_state(rt, proxy_port, 1);
+    Arc::get_mut(&mut state).unwrap().content_denylist =
+        crate::infra::denylist::ContentDenylist::parse(&content_hash).unwrap();
+
+    let body = serde_json::json!({
+        "recent_blockhash": "11111111111111111111111111111111",
+        "requests": [{
+            "signed_json_uri": format!("http://127.0.0.1:{storage_port}/signed_json"),
+        }],
+    });
+
+    let err = handle_sign(State(state), Json(body)).await.unwrap_err();
+    assert!(matches!(err, TeeError::Forbidden(_)), "{err:?}");
+    assert!(err.to_string().contains(crate::infra::denylist::REFUSAL_MESSAGE), "{err}");
+}
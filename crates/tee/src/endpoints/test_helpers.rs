@@ -2,7 +2,53 @@
 
 //! # エンドポイントテスト用共通ヘルパー
 //!
-//! verify, signテストで共有するモックサーバー群。
+//! verify, signテストで共有するモックサーバー群と、エンドポイントテスト用の共有状態。
+
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+use crate::config::{TeeAppState, TeeState};
+use crate::runtime::TeeRuntime;
+
+/// テスト用の共有状態を既定値で構築する（Active状態、各設定はノード既定値）。
+///
+/// テストごとに必要なフィールドのみ構造体更新構文で上書きする:
+/// `TeeAppState { proxy_addr, ..test_state(rt) }`
+pub fn test_state(runtime: impl TeeRuntime + 'static) -> TeeAppState {
+    TeeAppState {
+        runtime: Box::new(runtime),
+        state: RwLock::new(TeeState::Active),
+        proxy_addr: "127.0.0.1:0".to_string(),
+        core_tree_address: RwLock::new(None),
+        ext_tree_address: RwLock::new(None),
+        core_collection_mint: None,
+        ext_collection_mint: None,
+        gateway_pubkey: None,
+        wasm_loader: None,
+        resource_pool: Arc::new(title_wasm_host::ResourcePool::new(1024 * 1024 * 1024)),
+        extension_registry: Default::default(),
+        sign_concurrency: crate::infra::security::DEFAULT_SIGN_CONCURRENCY,
+        sign_fetch_timeout_secs: crate::infra::security::DEFAULT_SIGN_FETCH_TIMEOUT_SEC,
+        wasm_module_cache: None,
+        wasm_instance_pool: None,
+        normalize_extension_output: false,
+        max_extension_result_bytes: crate::infra::security::DEFAULT_MAX_EXTENSION_RESULT_BYTES,
+        max_extension_input_bytes: crate::infra::security::DEFAULT_MAX_EXTENSION_INPUT_BYTES,
+        tree_capacity_rpc_url: None,
+        trusted_tsa_keys: Vec::new(),
+        content_hash_namespace: String::new(),
+        signer_cert_expiry_warning_secs: 30 * 24 * 60 * 60,
+        report_self_signed_trust_level: false,
+        min_c2pa_validation_state: Default::default(),
+        content_denylist: Default::default(),
+        max_manifest_store_bytes: title_core::DEFAULT_MAX_MANIFEST_STORE_BYTES,
+        inflight_verifies: Default::default(),
+        verify_admission: Default::default(),
+        sign_rate_limiter: Default::default(),
+        missing_objects: Default::default(),
+    }
+}
 
 /// テスト用モックHTTPサーバーを起動し、指定パスで指定データを返す。
 pub async fn start_mock_storage(path: &str, data: Vec<u8>) -> u16 {
@@ -25,6 +71,58 @@ pub async fn start_mock_storage(path: &str, data: Vec<u8>) -> u16 {
     port
 }
 
+/// テスト用モックHTTPサーバーを起動し、指定時間待ってから指定データを返す。
+pub async fn start_mock_storage_delayed(
+    path: &str,
+    data: Vec<u8>,
+    delay: std::time::Duration,
+) -> u16 {
+    use axum::routing::get;
+
+    let app = axum::Router::new().route(
+        path,
+        get(move || {
+            let d = data.clone();
+            async move {
+                tokio::time::sleep(delay).await;
+                d
+            }
+        }),
+    );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    port
+}
+
+/// テスト用モックHTTPサーバーを起動し、複数のパスでそれぞれのデータを返す。
+pub async fn start_mock_storage_multi(routes: Vec<(String, Vec<u8>)>) -> u16 {
+    use axum::routing::get;
+
+    let mut app = axum::Router::new();
+    for (path, data) in routes {
+        app = app.route(
+            &path,
+            get(move || {
+                let d = data.clone();
+                async move { d }
+            }),
+        );
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    port
+}
+
 /// テスト用インラインプロキシを起動する。
 /// proxy crateのTCPフォールバックと同等のlength-prefixedプロトコルでHTTPリクエストを転送する。
 pub async fn start_inline_proxy() -> u16 {
@@ -64,7 +162,15 @@ pub async fn start_inline_proxy() -> u16 {
                 let client = reqwest::Client::new();
                 let result = match method.as_str() {
                     "GET" => client.get(&url).send().await,
-                    "POST" => client.post(&url).body(body).send().await,
+                    "POST" => {
+                        // 本番のプロキシ（title-proxy）と同じくJSONとして転送する
+                        client
+                            .post(&url)
+                            .header("Content-Type", "application/json")
+                            .body(body)
+                            .send()
+                            .await
+                    }
                     _ => {
                         stream.write_all(&400u32.to_be_bytes()).await.unwrap();
                         let msg = b"Unsupported method";
@@ -104,3 +210,101 @@ pub async fn start_inline_proxy() -> u16 {
     tokio::time::sleep(std::time::Duration::from_millis(50)).await;
     port
 }
+
+/// テスト用固定応答プロキシを起動する。
+/// URLに関わらず全てのリクエストに200と指定データを返す（外部ホストへの取得を模擬する）。
+pub async fn start_static_proxy(data: Vec<u8>) -> u16 {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let data = data.clone();
+            tokio::spawn(async move {
+                // method, url, body の3フィールドを読み捨てる
+                for _ in 0..3 {
+                    let mut buf4 = [0u8; 4];
+                    stream.read_exact(&mut buf4).await.unwrap();
+                    let mut field = vec![0u8; u32::from_be_bytes(buf4) as usize];
+                    stream.read_exact(&mut field).await.unwrap();
+                }
+                stream.write_all(&200u32.to_be_bytes()).await.unwrap();
+                stream
+                    .write_all(&(data.len() as u32).to_be_bytes())
+                    .await
+                    .unwrap();
+                stream.write_all(&data).await.unwrap();
+            });
+        }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    port
+}
+
+/// テスト用の応答途中で停止するプロキシを起動する。
+/// 全てのリクエストに200と `declared_size` を宣言し、`sent` バイトだけ送信した後は
+/// 接続を保持したまま応答しない（長時間のダウンロードを模擬する）。
+pub async fn start_stalling_proxy(declared_size: u32, sent: usize) -> u16 {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                // method, url, body の3フィールドを読み捨てる
+                for _ in 0..3 {
+                    let mut buf4 = [0u8; 4];
+                    stream.read_exact(&mut buf4).await.unwrap();
+                    let mut field = vec![0u8; u32::from_be_bytes(buf4) as usize];
+                    stream.read_exact(&mut field).await.unwrap();
+                }
+                stream.write_all(&200u32.to_be_bytes()).await.unwrap();
+                stream.write_all(&declared_size.to_be_bytes()).await.unwrap();
+                stream.write_all(&vec![0u8; sent]).await.unwrap();
+                std::future::pending::<()>().await;
+            });
+        }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    port
+}
+
+/// テスト用の終わらないHTTPレスポンスを返すサーバーを起動する。
+/// Content-Lengthを付けずにchunked転送で64KBのチャンクを送り続ける（無限ストリームを模擬する）。
+pub async fn start_endless_http_server() -> u16 {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                // リクエストヘッダを読み捨てる
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let header = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+                if stream.write_all(header).await.is_err() {
+                    return;
+                }
+                let chunk = vec![b'a'; 64 * 1024];
+                let chunk_header = format!("{:x}\r\n", chunk.len());
+                // クライアントが切断するまで送信を続ける
+                loop {
+                    if stream.write_all(chunk_header.as_bytes()).await.is_err()
+                        || stream.write_all(&chunk).await.is_err()
+                        || stream.write_all(b"\r\n").await.is_err()
+                    {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    port
+}
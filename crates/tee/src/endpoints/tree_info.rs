@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # /tree-info エンドポイント
+//!
+//! 仕様書 §6.4 Step 2
+//!
+//! active状態のTEEの現在のMerkle Treeアドレスと公開鍵を返す。
+//! `/create-tree` のレスポンスを失ったクライアント・Gatewayが、
+//! 後続の `/sign` 等の構築に必要な値を再取得するために使用する。
+//! content_hashのデプロイメント名前空間（仕様書 §2.1）も併せて公開する。
+
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::Json;
+use base64::Engine;
+use solana_sdk::pubkey::Pubkey;
+
+use title_types::TreeInfoResponse;
+
+use crate::config::{TeeAppState, TeeState};
+use crate::error::TeeError;
+
+use super::b64;
+
+/// /tree-info エンドポイントハンドラ。
+/// 仕様書 §6.4 Step 2
+///
+/// active状態になる前（`/create-tree` 呼び出し前）は503を返す。
+pub async fn handle_tree_info(
+    State(state): State<Arc<TeeAppState>>,
+) -> Result<Json<TreeInfoResponse>, TeeError> {
+    {
+        let current = state.state.read().await;
+        if *current != TeeState::Active {
+            return Err(TeeError::InvalidState("TEEはまだactive状態ではありません".into()));
+        }
+    }
+
+    let core_tree = state
+        .core_tree_address
+        .read()
+        .await
+        .ok_or_else(|| TeeError::Internal("Core Merkle Treeが未作成です".into()))?;
+    let ext_tree = state
+        .ext_tree_address
+        .read()
+        .await
+        .ok_or_else(|| TeeError::Internal("Extension Merkle Treeが未作成です".into()))?;
+
+    let signing_pubkey_bytes: [u8; 32] = state
+        .runtime
+        .signing_pubkey()
+        .try_into()
+        .map_err(|_| TeeError::Internal("署名用公開鍵の取得に失敗".into()))?;
+
+    Ok(Json(TreeInfoResponse {
+        core_tree_address: Pubkey::new_from_array(core_tree).to_string(),
+        ext_tree_address: Pubkey::new_from_array(ext_tree).to_string(),
+        signing_pubkey: Pubkey::new_from_array(signing_pubkey_bytes).to_string(),
+        encryption_pubkey: b64().encode(state.runtime.encryption_pubkey()),
+        content_hash_namespace: state.content_hash_namespace.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::handle_create_tree;
+    use crate::endpoints::test_helpers::test_state;
+    use crate::runtime::mock::MockRuntime;
+    use crate::runtime::TeeRuntime;
+    use tokio::sync::RwLock;
+
+    fn make_test_state() -> Arc<TeeAppState> {
+        let rt = MockRuntime::new();
+        rt.generate_signing_keypair();
+        rt.generate_encryption_keypair();
+        rt.generate_tree_keypair();
+        rt.generate_ext_tree_keypair();
+
+        Arc::new(TeeAppState {
+            state: RwLock::new(TeeState::Inactive),
+            ..test_state(rt)
+        })
+    }
+
+    /// /tree-info の値が /create-tree のレスポンスと一致することを確認
+    #[tokio::test]
+    async fn test_tree_info_matches_create_tree() {
+        let state = make_test_state();
+
+        // active前は503
+        let result = handle_tree_info(State(state.clone())).await;
+        assert!(matches!(result, Err(TeeError::InvalidState(_))));
+
+        let body = serde_json::json!({
+            "max_depth": 20,
+            "max_buffer_size": 64,
+            "recent_blockhash": "11111111111111111111111111111111",
+        });
+        let created = handle_create_tree(State(state.clone()), Json(body))
+            .await
+            .unwrap()
+            .0;
+
+        let info = handle_tree_info(State(state)).await.unwrap().0;
+        assert_eq!(info.core_tree_address, created.core_tree_address);
+        assert_eq!(info.ext_tree_address, created.ext_tree_address);
+        assert_eq!(info.signing_pubkey, created.signing_pubkey);
+        assert_eq!(info.encryption_pubkey, created.encryption_pubkey);
+        assert_eq!(info.content_hash_namespace, "");
+    }
+}
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Extensionの連鎖実行
+//!
+//! 仕様書 §5.1 Step 5, §7.1
+//!
+//! `VerifyRequest.depends_on` で上流を指定したExtensionは、上流Extensionの結果（WASM出力JSON、
+//! 正規化JSONのバイト列）を補助入力（`extension_input`）として受け取る。
+//! 上流の結果は `extension_input_hash` として依存側のsigned_jsonに束縛される。
+//!
+//! 依存関係は実行前に検証し、上流が依存側より先に実行される順序を決定する。
+//! 循環する依存関係は拒否する。
+
+use std::collections::BTreeMap;
+
+use crate::error::TeeError;
+
+use super::CORE_PROCESSOR_ID;
+
+/// 訪問状態（循環検出用）
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    Unvisited,
+    InProgress,
+    Done,
+}
+
+/// `processor_ids` の実行順序（添字）を返す。
+/// 仕様書 §5.1 Step 5
+///
+/// 上流を依存側より先に並べ、それ以外はリクエスト順を保つ。以下の場合は `BadRequest` を返す。
+/// - 依存元・依存先が `processor_ids` に含まれないExtension、またはCoreである
+/// - 依存関係が循環している
+/// - 依存側にクライアント指定の補助入力（`extension_inputs`）がある（補助入力は上流の結果で置き換わるため）
+pub(crate) fn execution_order(
+    processor_ids: &[String],
+    depends_on: &BTreeMap<String, String>,
+    client_inputs: Option<&serde_json::Map<String, serde_json::Value>>,
+) -> Result<Vec<usize>, TeeError> {
+    let index_of = |id: &str| processor_ids.iter().position(|p| p == id);
+
+    for (dependent, upstream) in depends_on {
+        for id in [dependent, upstream] {
+            if id == CORE_PROCESSOR_ID || index_of(id).is_none() {
+                return Err(TeeError::BadRequest(format!(
+                    "depends_onにはprocessor_idsに含まれるExtensionのみ指定できます: {id}"
+                )));
+            }
+        }
+        if client_inputs.is_some_and(|inputs| inputs.contains_key(dependent)) {
+            return Err(TeeError::BadRequest(format!(
+                "depends_onを指定したExtensionにはextension_inputsを指定できません: {dependent}"
+            )));
+        }
+    }
+
+    let mut visits = vec![Visit::Unvisited; processor_ids.len()];
+    let mut order = Vec::with_capacity(processor_ids.len());
+    for start in 0..processor_ids.len() {
+        // 上流をたどり、未実行の上流から順に実行順序へ追加する
+        let mut path = Vec::new();
+        let mut current = Some(start);
+        while let Some(index) = current {
+            match visits[index] {
+                Visit::Done => break,
+                Visit::InProgress => {
+                    return Err(TeeError::BadRequest(format!(
+                        "depends_onが循環しています: {}",
+                        processor_ids[index]
+                    )));
+                }
+                Visit::Unvisited => {
+                    visits[index] = Visit::InProgress;
+                    path.push(index);
+                    current = depends_on
+                        .get(&processor_ids[index])
+                        .and_then(|upstream| index_of(upstream));
+                }
+            }
+        }
+        for index in path.into_iter().rev() {
+            visits[index] = Visit::Done;
+            order.push(index);
+        }
+    }
+    Ok(order)
+}
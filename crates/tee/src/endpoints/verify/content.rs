@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # リクエストスコープのコンテンツ解析結果
+//!
+//! 仕様書 §2.1, §5.1 Step 4-5
+//!
+//! 1回の `/verify` で複数のプロセッサ（Core + Extension）が同一コンテンツを扱うため、
+//! C2PA検証結果と content_hash をリクエスト内で一度だけ計算し、全プロセッサで共有する。
+//! 検証時に解析したReader・JUMBFも保持し、来歴グラフの構築で再解析しない。
+//! manifest-onlyモードでは、本体の代わりにサイドカーManifestを同じ方法で扱う。
+
+use std::sync::OnceLock;
+
+use title_core::{C2paVerificationResult, ParsedC2pa, ProvenanceGraph};
+
+/// 1リクエスト分のコンテンツと、その解析結果のメモ化。
+/// 仕様書 §2.1, §5.1 Step 4-5
+pub(crate) struct ContentContext<'a> {
+    /// コンテンツの生データ（manifest-onlyモードではサイドカーManifest）
+    bytes: &'a [u8],
+    /// マジックバイトから検出したMIMEタイプ
+    mime_type: &'a str,
+    /// 信頼するTSA証明書ハッシュ一覧（仕様書 §2.4）
+    trusted_tsa_keys: &'a [String],
+    /// content_hashのデプロイメント名前空間（仕様書 §2.1）
+    namespace: &'a str,
+    /// マニフェストストア（JUMBF）の最大サイズ（仕様書 §2.1）
+    max_manifest_store_bytes: u64,
+    /// manifest-onlyモードでクライアントが主張するコンテンツ本体のハッシュ（仕様書 §5.1 Step 4）
+    asserted_hash: Option<&'a [u8]>,
+    /// C2PA検証結果と解析済みのC2PAデータ（初回アクセス時に計算。失敗もキャッシュする）
+    c2pa: OnceLock<Result<(C2paVerificationResult, ParsedC2pa), String>>,
+    /// content_hash（C2PA検証結果から導出）
+    content_hash: OnceLock<[u8; 32]>,
+    /// `verify_c2pa` の呼び出し回数（テスト用の計測）
+    #[cfg(test)]
+    c2pa_verifications: std::sync::atomic::AtomicUsize,
+}
+
+impl<'a> ContentContext<'a> {
+    pub(crate) fn new(
+        bytes: &'a [u8],
+        mime_type: &'a str,
+        trusted_tsa_keys: &'a [String],
+        namespace: &'a str,
+        max_manifest_store_bytes: u64,
+    ) -> Self {
+        Self {
+            bytes,
+            mime_type,
+            trusted_tsa_keys,
+            namespace,
+            max_manifest_store_bytes,
+            asserted_hash: None,
+            c2pa: OnceLock::new(),
+            content_hash: OnceLock::new(),
+            #[cfg(test)]
+            c2pa_verifications: std::sync::atomic::AtomicUsize::new(0),
+        }
+    }
+
+    /// manifest-onlyモード: サイドカーManifestと主張ハッシュで検証するコンテキストを作成する。
+    /// 仕様書 §5.1 Step 4
+    pub(crate) fn manifest_only(
+        sidecar: &'a [u8],
+        asserted_hash: &'a [u8],
+        trusted_tsa_keys: &'a [String],
+        namespace: &'a str,
+        max_manifest_store_bytes: u64,
+    ) -> Self {
+        Self {
+            asserted_hash: Some(asserted_hash),
+            ..Self::new(
+                sidecar,
+                title_core::SIDECAR_MIME_TYPE,
+                trusted_tsa_keys,
+                namespace,
+                max_manifest_store_bytes,
+            )
+        }
+    }
+
+    /// manifest-onlyモード（本体を見ていない）かどうか。
+    pub(crate) fn is_manifest_only(&self) -> bool {
+        self.asserted_hash.is_some()
+    }
+
+    /// コンテンツの生データを返す。
+    pub(crate) fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// コンテンツのMIMEタイプを返す。
+    pub(crate) fn mime_type(&self) -> &'a str {
+        self.mime_type
+    }
+
+    /// C2PA検証結果を返す。リクエスト内で `verify_c2pa` は一度だけ実行される。
+    /// 仕様書 §2.1
+    pub(crate) fn c2pa(&self) -> Result<&C2paVerificationResult, String> {
+        self.parsed().map(|(result, _)| result)
+    }
+
+    /// 検証時に解析したC2PAデータから来歴グラフを構築する（Reader・JUMBFを再解析しない）。
+    /// 仕様書 §2.2
+    pub(crate) fn provenance_graph(&self, max_graph_size: usize) -> Result<ProvenanceGraph, String> {
+        let (_, parsed) = self.parsed()?;
+        title_core::build_provenance_graph_from_parsed(parsed, self.namespace, max_graph_size)
+            .map_err(|e| format!("来歴グラフ構築エラー: {e}"))
+    }
+
+    fn parsed(&self) -> Result<&(C2paVerificationResult, ParsedC2pa), String> {
+        self.c2pa
+            .get_or_init(|| {
+                #[cfg(test)]
+                self.c2pa_verifications
+                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                match self.asserted_hash {
+                    Some(asserted_hash) => title_core::verify_c2pa_manifest_only_parsed(
+                        self.bytes,
+                        asserted_hash,
+                        self.trusted_tsa_keys,
+                        self.max_manifest_store_bytes,
+                    ),
+                    None => title_core::verify_c2pa_parsed(
+                        self.bytes,
+                        self.mime_type,
+                        self.trusted_tsa_keys,
+                        self.max_manifest_store_bytes,
+                    ),
+                }
+                .map_err(|e| format!("C2PA検証エラー: {e}"))
+            })
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+
+    /// Active Manifestの署名とデプロイメント名前空間から導出した content_hash を返す。
+    /// 仕様書 §2.1
+    pub(crate) fn content_hash(&self) -> Result<[u8; 32], String> {
+        if let Some(hash) = self.content_hash.get() {
+            return Ok(*hash);
+        }
+        let hash = title_crypto::content_hash_with_namespace(
+            self.namespace,
+            &self.c2pa()?.active_manifest_signature,
+        );
+        Ok(*self.content_hash.get_or_init(|| hash))
+    }
+
+    /// `verify_c2pa` が実行された回数を返す。
+    #[cfg(test)]
+    pub(crate) fn c2pa_verifications(&self) -> usize {
+        self.c2pa_verifications
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
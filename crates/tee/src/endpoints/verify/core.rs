@@ -4,106 +4,159 @@
 //!
 //! 仕様書 §2.1, §2.2, §5.1 Step 4
 
-use base58::ToBase58;
-use base64::Engine;
-
-use title_types::{Attribute, CorePayload, SignedJson, SignedJsonCore};
+use title_core::signed_json::{build_core_signed_json, CoreSignedJsonOptions, TeeMeta};
+use title_types::SignedJson;
 
 use crate::config::TeeAppState;
 
-use super::format_content_hash;
-use crate::endpoints::b64;
+use super::content::ContentContext;
+
+/// Core処理の結果。
+#[derive(Debug)]
+pub(crate) struct CoreOutput {
+    /// Core signed_json
+    pub signed_json: SignedJson,
+    /// Active Manifestのアサーションラベル一覧（署名対象外）
+    pub assertion_labels: Vec<String>,
+    /// 検証時点での署名者証明書の期限警告（署名対象外）
+    pub signer_cert_warning: Option<title_core::signer_cert::CertExpiryWarning>,
+}
 
 /// Core処理: C2PA検証 + 来歴グラフ構築 + signed_json生成。
 /// 仕様書 §2.1, §2.2, §5.1 Step 4
+///
+/// `max_returned_nodes` が指定された場合、グラフ全体を構築・検証した上で
+/// 返却するノードをルートから近い順に切り詰め、`truncated: true` を付与する。
+///
+/// C2PA検証結果とcontent_hashは `content` にメモ化され、同一リクエストのExtensionと共有される。
+/// `include_claim_generators` がfalseの場合、ノードの生成ツール情報を除去し、
+/// 従来と同一の署名対象を維持する。
+/// `compact_graph` がtrueの場合、リンクをノードの添字で参照するコンパクト表現で返す。
+/// manifest-onlyモードのコンテキストでは、サイドカーManifestを検証し `manifest_only: true` を付与する。
 pub(crate) fn process_core(
     state: &TeeAppState,
-    content_bytes: &[u8],
-    mime_type: &str,
+    content: &ContentContext<'_>,
     owner_wallet: &str,
     max_graph_size: usize,
-) -> Result<SignedJson, String> {
+    max_returned_nodes: Option<usize>,
+    include_claim_generators: bool,
+    compact_graph: bool,
+) -> Result<CoreOutput, String> {
     // C2PA検証
-    let c2pa_result = title_core::verify_c2pa(content_bytes, mime_type)
-        .map_err(|e| format!("C2PA検証エラー: {e}"))?;
-
-    // content_hash計算
-    let content_hash =
-        title_crypto::content_hash_from_manifest_signature(&c2pa_result.active_manifest_signature);
-    let content_hash_hex = format_content_hash(&content_hash);
-
-    // 来歴グラフ構築
-    let graph = title_core::build_provenance_graph(content_bytes, mime_type, max_graph_size)
-        .map_err(|e| format!("来歴グラフ構築エラー: {e}"))?;
-
-    // CorePayload構築
-    let payload = CorePayload {
-        content_hash: content_hash_hex.clone(),
-        content_type: c2pa_result.content_type.clone(),
-        creator_wallet: owner_wallet.to_string(),
-        tsa_timestamp: c2pa_result.tsa_info.as_ref().map(|t| t.timestamp),
-        tsa_pubkey_hash: c2pa_result.tsa_info.as_ref().and_then(|t| t.cert_hash.clone()),
-        tsa_token_data: c2pa_result
-            .tsa_info
-            .as_ref()
-            .map(|t| b64().encode(&t.raw_token)),
-        nodes: graph.nodes,
-        links: graph.links,
-    };
-
-    // attributes構築（cNFTオンチェーンメタデータ用）
-    // 仕様書 §5.1 Step 4
-    let attributes = vec![
-        Attribute {
-            trait_type: "protocol".to_string(),
-            value: "Title-v1".to_string(),
-        },
-        Attribute {
-            trait_type: "content_hash".to_string(),
-            value: content_hash_hex,
-        },
-        Attribute {
-            trait_type: "content_type".to_string(),
-            value: c2pa_result.content_type,
-        },
-    ];
-
-    // Step 6. signed_json構築 + TEE秘密鍵で署名（tee_signature）
-    // 仕様書 §5.1 Step 4
-    let payload_value = serde_json::to_value(&payload).map_err(|e| format!("payloadシリアライズエラー: {e}"))?;
-    let attributes_value =
-        serde_json::to_value(&attributes).map_err(|e| format!("attributesシリアライズエラー: {e}"))?;
+    let c2pa_result = content.c2pa()?;
+    ensure_validation_state(state, c2pa_result)?;
+    let content_hash = content.content_hash()?;
+
+    // 来歴グラフ構築（検証時に解析したReader・JUMBFを再利用する）
+    let graph = content.provenance_graph(max_graph_size)?;
+    let graph = with_claim_generators(graph, include_claim_generators);
+
+    let signed_json = sign_core_payload(
+        state,
+        c2pa_result,
+        content_hash,
+        graph,
+        owner_wallet,
+        max_returned_nodes,
+        content.is_manifest_only(),
+        compact_graph,
+    )?;
+    Ok(CoreOutput {
+        signed_json,
+        assertion_labels: c2pa_result.assertion_labels.clone(),
+        signer_cert_warning: signer_cert_warning(state, c2pa_result),
+    })
+}
 
-    // 署名対象: payload + attributes の正規化JSON
-    let sign_target = serde_json::json!({
-        "payload": payload_value,
-        "attributes": attributes_value,
-    });
-    let sign_bytes =
-        serde_json::to_vec(&sign_target).map_err(|e| format!("署名対象のシリアライズエラー: {e}"))?;
+/// C2PA検証状態がノードの受け入れ下限（`min_c2pa_validation_state`）以上であることを確認する。
+/// 仕様書 §2.1
+fn ensure_validation_state(
+    state: &TeeAppState,
+    c2pa_result: &title_core::C2paVerificationResult,
+) -> Result<(), String> {
+    if c2pa_result.validation_state < state.min_c2pa_validation_state {
+        return Err(format!(
+            "C2PA検証状態がノードの要件を満たしません: {}（要件: {}以上）",
+            c2pa_result.validation_state.as_str(),
+            state.min_c2pa_validation_state.as_str()
+        ));
+    }
+    Ok(())
+}
 
-    // TEE秘密鍵で署名
-    let signature = state.runtime.sign(&sign_bytes);
+/// 検証時点で署名者証明書が期限切れ・期限間近であれば警告を返す（検証自体は失敗させない）。
+/// 仕様書 §2.1
+///
+/// 判定は現在時刻とノードの閾値（`signer_cert_expiry_warning_secs`）に依存するため、
+/// signed_jsonには含めずレスポンスのメタデータとログにのみ出力する。
+fn signer_cert_warning(
+    state: &TeeAppState,
+    c2pa_result: &title_core::C2paVerificationResult,
+) -> Option<title_core::signer_cert::CertExpiryWarning> {
+    let validity = c2pa_result.signer_cert_validity?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let warning = validity.expiry_warning(now, state.signer_cert_expiry_warning_secs)?;
+    tracing::warn!(
+        warning = warning.as_str(),
+        not_after = %validity.not_after_rfc3339(),
+        "C2PA署名者証明書の有効期限に関する警告"
+    );
+    Some(warning)
+}
 
-    // TEE公開鍵（Base58エンコード）
-    let tee_pubkey_b58 = state.runtime.signing_pubkey().to_base58();
+/// `include_claim_generators` がfalseの場合、各ノードの生成ツール情報を除去する。
+/// 仕様書 §2.2
+fn with_claim_generators(
+    mut graph: title_core::ProvenanceGraph,
+    include_claim_generators: bool,
+) -> title_core::ProvenanceGraph {
+    if !include_claim_generators {
+        for node in &mut graph.nodes {
+            node.claim_generators.clear();
+        }
+    }
+    graph
+}
 
-    // Attestation Document（Base64エンコード）
+/// CorePayloadを構築し、TEE秘密鍵で署名したsigned_jsonを返す。
+/// 仕様書 §5.1 Step 4
+///
+/// signed_jsonの組み立ては [`title_core::signed_json::build_core_signed_json`] が行い、
+/// ここではTEEランタイムの署名鍵・Attestationとノード設定を渡す。
+#[allow(clippy::too_many_arguments)]
+fn sign_core_payload(
+    state: &TeeAppState,
+    c2pa_result: &title_core::C2paVerificationResult,
+    content_hash: [u8; 32],
+    graph: title_core::ProvenanceGraph,
+    owner_wallet: &str,
+    max_returned_nodes: Option<usize>,
+    manifest_only: bool,
+    compact_graph: bool,
+) -> Result<SignedJson, String> {
+    let tee_pubkey = state.runtime.signing_pubkey();
     let attestation = state.runtime.get_attestation();
-    let attestation_b64 = b64().encode(&attestation);
 
-    // signed_json組み立て
-    let signed_json = SignedJson {
-        core: SignedJsonCore {
-            protocol: "Title-v1".to_string(),
-            tee_type: state.runtime.tee_type().to_string(),
-            tee_pubkey: tee_pubkey_b58,
-            tee_signature: b64().encode(&signature),
-            tee_attestation: attestation_b64,
+    build_core_signed_json(
+        c2pa_result,
+        content_hash,
+        graph,
+        owner_wallet,
+        &|message| state.runtime.sign(message),
+        TeeMeta {
+            tee_type: state.runtime.tee_type(),
+            tee_pubkey: &tee_pubkey,
+            attestation: &attestation,
         },
-        payload: payload_value,
-        attributes,
-    };
-
-    Ok(signed_json)
+        CoreSignedJsonOptions {
+            max_returned_nodes,
+            manifest_only,
+            compact_links: compact_graph,
+            report_self_signed_trust_level: state.report_self_signed_trust_level,
+        },
+    )
+    .map_err(|e| e.to_string())
 }
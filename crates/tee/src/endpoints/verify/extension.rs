@@ -10,23 +10,53 @@ use base64::Engine;
 use title_types::{Attribute, ExtensionPayload, SignedJson, SignedJsonCore};
 
 use crate::config::TeeAppState;
+use crate::error::TeeError;
+use crate::infra::security::{EXTENSION_FUEL_LIMIT, EXTENSION_MEMORY_LIMIT_BYTES};
 
+use super::content::ContentContext;
+use super::ensure_unique_trait_types;
 use super::format_content_hash;
+use super::normalize::normalize_extension_output;
 use crate::endpoints::b64;
 
+/// Extension処理の結果。
+pub(crate) struct ExtensionOutput {
+    /// Extension signed_json
+    pub signed_json: serde_json::Value,
+    /// Extension結果（WASM出力）のシリアライズ後のバイト数
+    pub result_size: u64,
+    /// Extension結果（署名対象のWASM出力）。依存するExtensionへの入力に用いる
+    pub result: serde_json::Value,
+}
+
 /// Extension処理: WASM実行 + Extension signed_json生成。
 /// 仕様書 §3.1, §5.1 Step 5, §7.1
 ///
 /// WASMバイナリはWasmLoaderトレイト経由で取得する。
-/// エクスポート関数名は標準化された `process` を使用する。
+/// Extensionレジストリの実行設定に従い、対象外のMIMEタイプ・登録と異なるWASMバイナリを拒否し、
+/// エクスポート関数名（既定: `process`）・許可するホスト関数・制限値を適用する。
+/// Extension結果（WASM出力）のシリアライズ後のサイズが
+/// `max_extension_result_bytes`（実行設定の `max_result_bytes` が小さければそちら）を超える場合はエラーとする。
+/// content_hashは `content` にメモ化されたC2PA検証結果から取得する。
+/// `extension_input` は解決済み（参照は取得・ハッシュ照合済み）の補助入力バイト列。
+/// `interrupt` を指定した場合、検証の取り消し時に実行中のWASMを中断する。
 pub(crate) async fn process_extension(
     state: &TeeAppState,
-    content_bytes: &[u8],
-    mime_type: &str,
+    content: &ContentContext<'_>,
     owner_wallet: &str,
     extension_id: &str,
-    extension_input: Option<&serde_json::Value>,
-) -> Result<serde_json::Value, String> {
+    extension_input: Option<&[u8]>,
+    interrupt: Option<&title_wasm_host::InterruptHandle>,
+) -> Result<ExtensionOutput, String> {
+    // 対象MIMEタイプの確認（WASMのロード前に拒否する）
+    let spec = state.extension_registry.spec(extension_id);
+    if !spec.accepts_mime(content.mime_type()) {
+        return Err(format!(
+            "Extension {extension_id} はMIMEタイプ {} に対応していません",
+            content.mime_type()
+        ));
+    }
+
     // WASMローダーを取得
     let loader = state
         .wasm_loader
@@ -39,53 +69,67 @@ pub(crate) async fn process_extension(
     // WASMバイナリのSHA-256ハッシュを計算
     let wasm_hash = title_crypto::sha256(&wasm_binary.bytes);
     let wasm_hash_hex = format_content_hash(&wasm_hash);
-
-    // Extension補助入力をシリアライズ
-    let ext_input_bytes = extension_input
-        .map(|v| serde_json::to_vec(v))
-        .transpose()
-        .map_err(|e| format!("extension_inputのシリアライズに失敗: {e}"))?;
+    state
+        .extension_registry
+        .check_wasm_hash(extension_id, &wasm_hash_hex)?;
 
     // extension_inputのハッシュ（存在する場合）
-    let ext_input_hash = ext_input_bytes.as_ref().map(|bytes| {
+    let ext_input_hash = extension_input.map(|bytes| {
         let hash = title_crypto::sha256(bytes);
         format_content_hash(&hash)
     });
 
     // WASMランナーで実行（仕様書 §7.1）
-    // 標準エクスポート関数名 "process" を使用
-    let runner = title_wasm_host::WasmRunner::with_resource_pool(
-        1_000_000_000, // Fuel制限: 10億命令
-        64 * 1024 * 1024, // Memory制限: 64MB
-        std::sync::Arc::clone(&state.resource_pool),
-    );
-
+    // プール実行の場合はスロットを非同期に確保してから実行する（実行完了まで保持）
+    let _slot = acquire_instance_slot(state, extension_id).await;
+    let runner = extension_runner(state, extension_id);
+    let runner = match interrupt {
+        Some(interrupt) => runner.with_interrupt(interrupt.clone()),
+        None => runner,
+    };
     let wasm_result = runner
-        .execute(
+        .execute_with_mime(
             &wasm_binary.bytes,
-            content_bytes,
-            ext_input_bytes.as_deref(),
-            crate::wasm_loader::STANDARD_EXPORT_NAME,
+            content.bytes(),
+            Some(content.mime_type()),
+            extension_input,
+            state.extension_registry.export_name(extension_id),
         )
         .map_err(|e| format!("WASM実行エラー: {e}"))?;
 
-    // content_hash計算（C2PA検証結果から取得）
-    let c2pa_result = title_core::verify_c2pa(content_bytes, mime_type)
-        .map_err(|e| format!("C2PA検証エラー: {e}"))?;
-    let content_hash =
-        title_crypto::content_hash_from_manifest_signature(&c2pa_result.active_manifest_signature);
+    // 共通エンベロープへの正規化（有効時のみ。署名対象は正規化後の出力）
+    let output = if state.normalize_extension_output {
+        normalize_extension_output(extension_id, wasm_result.output)
+    } else {
+        wasm_result.output
+    };
+
+    // 結果サイズの計測と上限チェック（結果はcNFTメタデータとなるため、mint前にサイズを確定させる）
+    // 署名対象と同じ正規化JSON（仕様書 §5.1）のバイト数で計測する
+    let result_size = title_types::canonical_json(&output).len();
+    let max_result_bytes = spec
+        .max_result_bytes
+        .map_or(state.max_extension_result_bytes, |max| max.min(state.max_extension_result_bytes));
+    if result_size > max_result_bytes {
+        return Err(format!(
+            "Extension結果のサイズが上限を超えています: {result_size} bytes (上限: {max_result_bytes} bytes)"
+        ));
+    }
+
+    // content_hash計算（C2PA検証結果から取得。同一リクエストのCoreと共有）
+    let content_hash = content.content_hash()?;
     let content_hash_hex = format_content_hash(&content_hash);
 
     // ExtensionPayload構築（仕様書 §5.1 Step 5）
     let payload = ExtensionPayload {
         content_hash: content_hash_hex.clone(),
-        content_type: mime_type.to_string(),
+        content_type: content.mime_type().to_string(),
         creator_wallet: owner_wallet.to_string(),
         extension_id: extension_id.to_string(),
         wasm_source: wasm_binary.source.clone(),
         wasm_hash: wasm_hash_hex.clone(),
         extension_input_hash: ext_input_hash.clone(),
-        result: wasm_result.output,
+        result: output.clone(),
     };
 
     // attributes構築
@@ -104,6 +148,8 @@ pub(crate) async fn process_extension(
         },
     ];
 
+    ensure_unique_trait_types(&attributes)?;
+
     // 署名対象の構築と署名（仕様書 §5.1 Step 5）
     // Core signed_jsonと同じSignedJson構造体を使用し、構造を統一する
     let payload_value = serde_json::to_value(&payload)
@@ -111,14 +157,13 @@ pub(crate) async fn process_extension(
     let attributes_value = serde_json::to_value(&attributes)
         .map_err(|e| format!("attributesシリアライズエラー: {e}"))?;
 
-    let sign_target = serde_json::json!({
-        "payload": payload_value,
-        "attributes": attributes_value,
-    });
-    let sign_bytes = serde_json::to_vec(&sign_target)
-        .map_err(|e| format!("署名対象のシリアライズエラー: {e}"))?;
+    // content_hashを必ず署名対象に含め、結果を対象コンテンツに束縛する
+    let sign_bytes = build_extension_sign_bytes(&payload_value, &attributes_value)?;
 
-    let signature = state.runtime.sign(&sign_bytes);
+    let signature = state.runtime.sign(&title_crypto::domain_separated_message(
+        title_crypto::SignatureDomain::SignedJson,
+        &sign_bytes,
+    ));
     let tee_pubkey_b58 = state.runtime.signing_pubkey().to_base58();
     let attestation_b64 = b64().encode(state.runtime.get_attestation());
 
@@ -139,5 +184,112 @@ pub(crate) async fn process_extension(
     let signed_json_value = serde_json::to_value(&signed_json)
         .map_err(|e| format!("signed_jsonシリアライズエラー: {e}"))?;
 
-    Ok(signed_json_value)
+    Ok(ExtensionOutput {
+        signed_json: signed_json_value,
+        result_size: result_size as u64,
+        result: output,
+    })
+}
+
+/// Extension実行用のWASMランナーを構築する。
+/// 仕様書 §7.1
+///
+/// ノード共通のResourcePoolを共有し、モジュールキャッシュが有効な場合はそれを使用する。
+/// インスタンスプールは実行設定で `pooled` を指定したExtensionにのみ使用する
+/// （`pooled` は `wasm_hash` の登録が必須で、実行前にバイナリのハッシュが照合される）。
+/// プールを使用するランナーの実行前には [`acquire_instance_slot`] でスロットを確保すること。
+/// Extensionレジストリの実行設定がある場合は、許可するホスト関数とFuel・Memory制限（ノード共通の上限以下）を適用する。
+/// C2PAを解析するホスト関数opにはノードのマニフェストストア上限を適用する。
+/// モジュールが自己申告したExtension IDは計算関数の実行前に `extension_id` と照合する
+/// （ローダーが別のExtensionのバイナリを返す設定ミスを検出する）。
+pub(crate) fn extension_runner(
+    state: &TeeAppState,
+    extension_id: &str,
+) -> title_wasm_host::WasmRunner {
+    let spec = state.extension_registry.spec(extension_id);
+    let fuel_limit = spec
+        .fuel_limit
+        .map_or(EXTENSION_FUEL_LIMIT, |fuel| fuel.min(EXTENSION_FUEL_LIMIT));
+    let memory_limit = spec.memory_limit_bytes.map_or(EXTENSION_MEMORY_LIMIT_BYTES, |bytes| {
+        bytes.min(EXTENSION_MEMORY_LIMIT_BYTES)
+    });
+    let runner = title_wasm_host::WasmRunner::with_resource_pool(
+        fuel_limit,
+        memory_limit,
+        std::sync::Arc::clone(&state.resource_pool),
+    )
+    .with_expected_extension_id(extension_id)
+    .with_max_manifest_store_bytes(state.max_manifest_store_bytes);
+    let runner = match &spec.capabilities {
+        Some(capabilities) => runner.with_allowed_host_imports(capabilities.iter().cloned()),
+        None => runner,
+    };
+    let runner = match &state.wasm_module_cache {
+        Some(cache) => runner.with_module_cache(std::sync::Arc::clone(cache)),
+        None => runner,
+    };
+    match &state.wasm_instance_pool {
+        Some(pool) if spec.pooled => runner.with_instance_pool(std::sync::Arc::clone(pool.pool())),
+        _ => runner,
+    }
+}
+
+/// [`extension_runner`] がインスタンスプールを使用する場合に、プールのスロットを非同期に確保する。
+/// 仕様書 §7.1
+///
+/// プール内の同期的なスロット待機で非同期ワーカーをブロックしないよう、実行前に確保する。
+/// プールを使用しない場合は `None`。
+pub(crate) async fn acquire_instance_slot(
+    state: &TeeAppState,
+    extension_id: &str,
+) -> Option<tokio::sync::OwnedSemaphorePermit> {
+    match &state.wasm_instance_pool {
+        Some(pool) if state.extension_registry.spec(extension_id).pooled => {
+            Some(pool.acquire().await)
+        }
+        _ => None,
+    }
+}
+
+/// Extension補助入力（`extension_inputs` の各値）のサイズを検証する。
+/// 仕様書 §6.4, §7.1
+///
+/// インラインの補助入力は正規化JSON（[`title_types::canonical_json`]）としてWASMに渡される
+/// （`get_extension_input`）ため、そのバイト数が `max_bytes` を超える値があれば実行前に拒否する。
+pub(crate) fn check_extension_input_sizes(
+    extension_inputs: Option<&serde_json::Map<String, serde_json::Value>>,
+    max_bytes: usize,
+) -> Result<(), TeeError> {
+    for (extension_id, input) in extension_inputs.into_iter().flatten() {
+        let size = title_types::canonical_json(input).len();
+        if size > max_bytes {
+            return Err(TeeError::PayloadTooLarge(format!(
+                "extension_input ({extension_id}) のサイズが上限を超えています: {size} bytes (上限: {max_bytes} bytes)"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Extension signed_jsonの署名対象バイト列を構築する。
+/// 仕様書 §5.1 Step 5
+///
+/// 署名対象は `{"payload": ..., "attributes": ...}` の正規化JSON。
+/// 署名・検証時には [`title_crypto::SignatureDomain::SignedJson`] のタグを付与する。
+/// `payload.content_hash` が欠落・空の場合はエラーとし、
+/// Extension結果が常に特定のコンテンツに束縛された状態で署名されることを保証する。
+pub(crate) fn build_extension_sign_bytes(
+    payload: &serde_json::Value,
+    attributes: &serde_json::Value,
+) -> Result<Vec<u8>, String> {
+    match payload.get("content_hash").and_then(|v| v.as_str()) {
+        Some(hash) if !hash.is_empty() => {}
+        _ => return Err("payload.content_hashが署名対象に含まれていません".to_string()),
+    }
+
+    let sign_target = serde_json::json!({
+        "payload": payload,
+        "attributes": attributes,
+    });
+    Ok(title_types::canonical_json(&sign_target))
 }
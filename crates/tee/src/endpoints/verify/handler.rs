@@ -11,23 +11,37 @@
 //! 4. ペイロードを復号（ハイブリッド暗号化の逆操作）
 //! 5. processor_idsに基づきCore/Extension処理を実行
 //! 6. レスポンスを暗号化して返却
+//!
+//! `cancel_token` 指定時はStep 2以降を独立したタスクで実行し、
+//! `DELETE /verify/{token}` による取り消し（実行中のWASMの中断を含む）を受け付ける。
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
-use axum::extract::State;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
 use axum::Json;
 use base64::Engine;
+use title_wasm_host::InterruptHandle;
 use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
 
+use title_types::codec;
 use title_types::{
-    EncryptedPayload, EncryptedResponse, ProcessorResult, VerifyRequest, VerifyResponse,
+    EncryptedPayload, EncryptedResponse, ProcessorResult, ResourceLimits, VerifyRequest,
+    VerifyResponse,
 };
 
 use crate::config::{TeeAppState, TeeState};
 use crate::error::TeeError;
+use crate::infra::denylist::REFUSAL_MESSAGE;
+use crate::infra::inflight::cancel_key;
 use crate::infra::security::{self, SecurityError};
+use crate::runtime::TeeRuntime;
 
+use super::content::ContentContext;
+use super::input_ref::ResolvedExtensionInputs;
 use super::{detect_mime_type, CORE_PROCESSOR_ID};
 use crate::endpoints::b64;
 
@@ -53,8 +67,118 @@ pub async fn handle_verify(
     let request: VerifyRequest = serde_json::from_value(inner_body)
         .map_err(|e| TeeError::BadRequest(format!("VerifyRequestのパースに失敗: {e}")))?;
 
+    // 署名レートの制限（§6.4）。processor_idsごとに1回署名するため、件数分をまとめて予約する。
+    // 予約は署名を返却する場合のみ確定し、エラー・取り消しで終了した場合は返却される
+    let reservation = state.sign_rate_limiter.acquire(request.processor_ids.len())?;
+    let response = run_verify(&state, request, resource_limits).await?;
+    reservation.commit();
+    Ok(response)
+}
+
+/// 検証処理を実行する。`cancel_token` 指定時は取り消し可能なタスクとして実行する。
+/// 仕様書 §6.4
+async fn run_verify(
+    state: &Arc<TeeAppState>,
+    request: VerifyRequest,
+    resource_limits: Option<ResourceLimits>,
+) -> Result<Json<EncryptedResponse>, TeeError> {
+    let Some(token) = request.cancel_token.clone() else {
+        return process_verify(Arc::clone(state), request, resource_limits, None).await;
+    };
+
+    // cancel_token指定時は独立したタスクで実行し、DELETE /verify/{token} で打ち切れるようにする。
+    // トークンはペイロード（download_url）に束縛して登録する
+    // 仕様書 §6.4
+    let key = cancel_key(&token, &request.download_url);
+    let interrupt = InterruptHandle::new();
+    let task = tokio::spawn(process_verify(
+        Arc::clone(state),
+        request,
+        resource_limits,
+        Some(interrupt.clone()),
+    ));
+    let Some(_guard) = state
+        .inflight_verifies
+        .register(key, task.abort_handle(), interrupt.clone())
+    else {
+        task.abort();
+        return Err(TeeError::Conflict(format!(
+            "cancel_tokenが同じ検証が処理中です: {token}"
+        )));
+    };
+    let result = task.await;
+    // 中断されたWASMはエラーとして戻るため、打ち切りより先にタスクが完了することがある
+    if interrupt.is_interrupted() || matches!(&result, Err(e) if e.is_cancelled()) {
+        tracing::info!(token = %token, "検証処理を取り消しました");
+        return Err(TeeError::Cancelled);
+    }
+    result.unwrap_or_else(|e| Err(TeeError::Internal(format!("検証タスクが異常終了しました: {e}"))))
+}
+
+/// DELETE /verify/{token} — `cancel_token` を指定して開始した処理中の検証を取り消す。
+/// 仕様書 §6.4
+///
+/// 検証タスクを打ち切り、実行中のWASMを中断する。タスクが保持するResourcePoolのチケットが
+/// Dropされ予約済みメモリが解放される。bodyはGateway認証ラッパーで、本文に取り消し対象の
+/// `download_url` を含める（トークンはペイロードに束縛されている）。
+/// Gateway認証が無効（`gateway_pubkey` 未設定）のノードでは取り消しを受け付けない。
+pub async fn handle_cancel_verify(
+    State(state): State<Arc<TeeAppState>>,
+    Path(token): Path<String>,
+    body: Bytes,
+) -> Result<StatusCode, TeeError> {
+    let Some(gateway_pubkey) = state.gateway_pubkey.as_ref() else {
+        return Err(TeeError::Forbidden(
+            "Gateway認証が無効なノードでは検証の取り消しを受け付けません".into(),
+        ));
+    };
+    let body: serde_json::Value = serde_json::from_slice(&body)
+        .map_err(|e| TeeError::BadRequest(format!("リクエストのパースに失敗: {e}")))?;
+
+    // Gateway署名の検証（§6.2）。署名対象のメソッド・パスで取り消し対象のトークンを束縛する
+    let (inner_body, _) =
+        crate::infra::gateway_auth::verify_gateway_auth(Some(gateway_pubkey), &body)
+            .map_err(|(_, msg)| TeeError::Unauthorized(msg))?;
+    if body["method"] != "DELETE" || body["path"] != format!("/verify/{token}") {
+        return Err(TeeError::Unauthorized(
+            "Gateway認証の対象メソッド・パスが一致しません".into(),
+        ));
+    }
+    let download_url = inner_body
+        .get("download_url")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| TeeError::BadRequest("download_urlを指定してください".into()))?;
+
+    if state.inflight_verifies.cancel(&cancel_key(&token, download_url)) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(TeeError::NotFound(format!(
+            "処理中の検証が見つかりません: {token}"
+        )))
+    }
+}
+
+/// リクエスト受付後の検証処理（Step 2以降）。
+/// 仕様書 §1.1 Phase 1, §6.4
+///
+/// `interrupt` は取り消し時にExtensionのWASM実行を中断するためのハンドル。
+async fn process_verify(
+    state: Arc<TeeAppState>,
+    request: VerifyRequest,
+    resource_limits: Option<ResourceLimits>,
+    interrupt: Option<InterruptHandle>,
+) -> Result<Json<EncryptedResponse>, TeeError> {
+    // 処理枠を優先度順に確保する（§6.4）。枠はレスポンスの生成完了まで保持する
+    let _admission = state
+        .verify_admission
+        .acquire(request.priority.unwrap_or_default())
+        .await;
+
     // Step 2. resource_limitsの完全適用（§6.4 処理上限の管理）
-    let limits = security::resolve_limits(resource_limits.as_ref());
+    let limits = security::resolve_limits(resource_limits.as_ref())?;
+    // クライアント指定の来歴グラフ上限はノード上限を超えない範囲でのみ適用する
+    let max_graph_size = limits.effective_max_graph_size(request.max_graph_size);
+    let max_returned_nodes = request.max_returned_nodes.map(|n| n as usize);
     let chunk_timeout = Duration::from_secs(limits.chunk_read_timeout_sec);
 
     // Step 3. download_urlからプロキシ経由で暗号化ペイロードを取得
@@ -79,6 +203,16 @@ pub async fn handle_verify(
         SecurityError::PayloadTooLarge { .. } => TeeError::PayloadTooLarge(e.to_string()),
         SecurityError::MemoryLimitExceeded => TeeError::ServiceUnavailable(e.to_string()),
         SecurityError::ChunkReadTimeout { .. } => TeeError::Timeout,
+        // アップロード直後はストレージの結果整合性により404となることがあるため、
+        // 最初の404から猶予期間内は再試行可能なエラーとし、過ぎた場合は恒久的な不在とする
+        SecurityError::ProxyError(404)
+            if state.missing_objects.within_grace(&request.download_url) =>
+        {
+            TeeError::TooEarly("Temporary Storageにオブジェクトが見つかりません: HTTP 404".into())
+        }
+        SecurityError::ProxyError(404) => {
+            TeeError::NotFound("Temporary Storageにオブジェクトが見つかりません: HTTP 404".into())
+        }
         SecurityError::ProxyError(status) => {
             TeeError::BadGateway(format!("Temporary Storageがエラーを返しました: HTTP {status}"))
         }
@@ -92,46 +226,48 @@ pub async fn handle_verify(
 
     // Step 4. ペイロード復号（ECDH + HKDF + AES-GCM）
     // 仕様書 §6.4 ハイブリッド暗号化 Step 6-7
-    let eph_pubkey_bytes = b64()
-        .decode(&encrypted_payload.ephemeral_pubkey)
-        .map_err(|e| TeeError::BadRequest(format!("ephemeral_pubkeyのBase64デコードに失敗: {e}")))?;
-    let eph_pubkey_arr: [u8; 32] = eph_pubkey_bytes.try_into()
-        .map_err(|_| TeeError::BadRequest("ephemeral_pubkeyは32バイトである必要があります".into()))?;
-    let eph_pubkey = X25519PublicKey::from(eph_pubkey_arr);
-
-    let tee_secret_bytes: [u8; 32] = state
-        .runtime
-        .encryption_secret_key()
-        .try_into()
-        .map_err(|_| TeeError::Internal("暗号化用秘密鍵の取得に失敗".into()))?;
-    let tee_secret = StaticSecret::from(tee_secret_bytes);
-
-    // ECDH(tee_sk, eph_pk) → shared_secret
-    let shared_secret = title_crypto::ecdh_derive_shared_secret(&tee_secret, &eph_pubkey);
-    // HKDF → symmetric_key
-    let symmetric_key = title_crypto::hkdf_derive_key(&shared_secret)
-        .map_err(|e| TeeError::Internal(format!("対称鍵の導出に失敗: {e}")))?;
-
-    let nonce_bytes = b64().decode(&encrypted_payload.nonce)
-        .map_err(|e| TeeError::BadRequest(format!("nonceのBase64デコードに失敗: {e}")))?;
-    let nonce: [u8; 12] = nonce_bytes.try_into()
-        .map_err(|_| TeeError::BadRequest("nonceは12バイトである必要があります".into()))?;
-
-    let ciphertext = b64()
-        .decode(&encrypted_payload.ciphertext)
-        .map_err(|e| TeeError::BadRequest(format!("ciphertextのBase64デコードに失敗: {e}")))?;
-    drop(encrypted_payload); // EncryptedPayloadのメモリを早期解放
-
-    // AES-GCM復号
-    let plaintext = title_crypto::aes_gcm_decrypt(&symmetric_key, &nonce, &ciphertext)
-        .map_err(|e| TeeError::BadRequest(format!("ペイロードの復号に失敗: {e}")))?;
-    drop(ciphertext); // 暗号文のメモリを早期解放
+    let (plaintext, symmetric_key) = decrypt_payload(state.runtime.as_ref(), encrypted_payload)?;
 
     // ClientPayloadをパース
     let mut client_payload: title_types::ClientPayload = serde_json::from_slice(&plaintext)
         .map_err(|e| TeeError::BadRequest(format!("ClientPayloadのパースに失敗: {e}")))?;
     drop(plaintext); // 平文JSONのメモリを早期解放
 
+    // owner_walletはcNFTの宛先（creator_wallet）となるため、処理前に検証する（仕様書 §5.1 Step 9）
+    crate::endpoints::parse_wallet_pubkey("owner_wallet", &client_payload.owner_wallet)?;
+
+    // Extensionの依存関係を検証し、実行順序を決定する（仕様書 §5.1 Step 5）
+    let execution_order = super::chain::execution_order(
+        &request.processor_ids,
+        &request.depends_on,
+        client_payload.extension_inputs.as_ref(),
+    )?;
+
+    // Coreのみのリクエスト（来歴グラフのプレビュー用途）は、Extension関連の準備
+    // （補助入力の検証・参照の取得、WASMローダー・ランナー）を一切行わない（仕様書 §5.1 Step 4）
+    let core_only = request.processor_ids.iter().all(|id| id == CORE_PROCESSOR_ID);
+    let extension_inputs = if core_only {
+        ResolvedExtensionInputs::default()
+    } else {
+        // Extension補助入力のサイズ上限（仕様書 §6.4, §7.1）
+        super::extension::check_extension_input_sizes(
+            client_payload.extension_inputs.as_ref(),
+            state.max_extension_input_bytes,
+        )?;
+        // 参照形式の補助入力はWASM実行前に取得し、SHA-256を照合する（仕様書 §7.1）
+        super::input_ref::resolve_extension_inputs(
+            &state,
+            client_payload.extension_inputs.as_ref(),
+            &request.processor_ids,
+            &limits,
+        )
+        .await?
+    };
+
+    // manifest-onlyモード: 本体を受け取らずサイドカーManifestのみで検証する（仕様書 §5.1 Step 4）
+    let manifest_only =
+        super::manifest_only::parse_manifest_only_input(&client_payload, &request.processor_ids)?;
+
     // コンテンツをBase64デコード（content文字列のメモリを早期解放）
     let content_string = std::mem::take(&mut client_payload.content);
     let content_bytes = b64().decode(&content_string)
@@ -140,77 +276,142 @@ pub async fn handle_verify(
 
     // MIMEタイプを検出
     let mime_type = detect_mime_type(&content_bytes);
+    // C2PA検証結果・content_hashは全プロセッサで共有する（リクエスト内で一度だけ計算）
+    // manifest-onlyモードではサイドカーManifestを検証対象とする
+    let content = match &manifest_only {
+        Some(input) => ContentContext::manifest_only(
+            &input.sidecar,
+            &input.asserted_hash,
+            &state.trusted_tsa_keys,
+            &state.content_hash_namespace,
+            state.max_manifest_store_bytes,
+        ),
+        None => ContentContext::new(
+            &content_bytes,
+            mime_type,
+            &state.trusted_tsa_keys,
+            &state.content_hash_namespace,
+            state.max_manifest_store_bytes,
+        ),
+    };
 
     // コンテンツサイズの事後検証（復号後の実データサイズ）
     // 仕様書 §6.4
-    if content_bytes.len() as u64 > limits.max_single_content_bytes {
+    // manifest-onlyモードではサイドカーManifestが処理対象となる
+    let processed_len = manifest_only
+        .as_ref()
+        .map_or(content_bytes.len(), |m| m.sidecar.len());
+    if processed_len as u64 > limits.max_single_content_bytes {
         return Err(TeeError::PayloadTooLarge(format!(
             "コンテンツサイズが上限を超えています: {} bytes (上限: {} bytes)",
-            content_bytes.len(),
+            processed_len,
             limits.max_single_content_bytes
         )));
     }
 
+    // 拒否リストのcontent_hashは、Core/Extensionを実行する前に拒否する（仕様書 §6.4）
+    // manifest-onlyモードではサイドカーから導出したcontent_hashを同じく照合する
+    if !state.content_denylist.is_empty() {
+        if let Ok(content_hash) = content.content_hash() {
+            if state.content_denylist.contains(&content_hash) {
+                return Err(TeeError::Forbidden(REFUSAL_MESSAGE.to_string()));
+            }
+        }
+    }
+
     // 動的グローバルタイムアウト適用（仕様書 §6.4）
-    let global_timeout = security::compute_dynamic_timeout(&limits, content_bytes.len() as u64);
+    let global_timeout = security::compute_dynamic_timeout(&limits, processed_len as u64);
 
     // Step 5. processor_idsに基づくCore/Extension実行（タイムアウト付き）
     // 仕様書 §5.1 Step 4-5
+    // 実行は依存関係の順序で行い、結果はリクエスト順で返す
     let processing_result = tokio::time::timeout(global_timeout, async {
-        let mut results = Vec::new();
+        let mut results: Vec<Option<ProcessorResult>> = vec![None; request.processor_ids.len()];
+        // 依存するExtensionに渡す上流の結果（正規化JSON）
+        let mut upstream_results: HashMap<&str, Vec<u8>> = HashMap::new();
 
-        for processor_id in &request.processor_ids {
+        for index in execution_order {
+            let processor_id = &request.processor_ids[index];
             if processor_id == CORE_PROCESSOR_ID {
                 // Core: C2PA検証 + 来歴グラフ構築
-                let signed_json = super::core::process_core(
+                let output = super::core::process_core(
                     &state,
-                    &content_bytes,
-                    mime_type,
+                    &content,
                     &client_payload.owner_wallet,
-                    limits.c2pa_max_graph_size,
+                    max_graph_size,
+                    max_returned_nodes,
+                    request.include_claim_generators,
+                    request.compact_graph,
                 )
                 .map_err(|e| TeeError::ProcessingFailed(format!("Core処理に失敗: {e}")))?;
 
-                results.push(ProcessorResult {
+                results[index] = Some(ProcessorResult {
                     processor_id: processor_id.clone(),
-                    signed_json: serde_json::to_value(&signed_json)
+                    signed_json: serde_json::to_value(&output.signed_json)
                         .map_err(|e| TeeError::Internal(format!("signed_jsonのシリアライズに失敗: {e}")))?,
+                    result_size: None,
+                    // アサーション一覧は透明性・デバッグ用で、署名対象外（仕様書 §5.1 Step 6）
+                    assertions: request.include_assertions.then_some(output.assertion_labels),
+                    // プレビューハッシュはUI表示用で、署名対象外（仕様書 §5.1 Step 6）
+                    preview_hash: if request.include_preview_hash && manifest_only.is_none() {
+                        compute_preview_hash(&state.resource_pool, &content_bytes)
+                    } else {
+                        None
+                    },
+                    // 期限警告は検証時刻に依存するため、署名対象外のメタデータとして返す（仕様書 §2.1）
+                    signer_cert_warning: output
+                        .signer_cert_warning
+                        .map(|warning| warning.as_str().to_string()),
                 });
             } else {
                 // Extension: WASM実行
                 // 仕様書 §6.4 不正WASMインジェクション防御
-                if let Some(ref trusted) = state.trusted_extension_ids {
-                    if !trusted.contains(processor_id.as_str()) {
-                        return Err(TeeError::Forbidden(format!(
-                            "信頼されていないExtension IDです: {processor_id}。\
-                             TRUSTED_EXTENSIONS環境変数で許可してください"
-                        )));
-                    }
+                if !state.extension_registry.is_trusted(processor_id) {
+                    return Err(TeeError::Forbidden(format!(
+                        "信頼されていないExtension IDです: {processor_id}。\
+                         TRUSTED_EXTENSIONS環境変数で許可してください"
+                    )));
                 }
 
+                // 上流を指定したExtensionは、上流の結果を補助入力として受け取る
                 // 仕様書 §5.1 Step 5, §7.1
-                let signed_json = super::extension::process_extension(
+                let extension_input = match request.depends_on.get(processor_id) {
+                    Some(upstream) => Some(
+                        upstream_results
+                            .get(upstream.as_str())
+                            .map(Vec::as_slice)
+                            .ok_or_else(|| {
+                                TeeError::Internal(format!("上流Extensionの結果がありません: {upstream}"))
+                            })?,
+                    ),
+                    None => extension_inputs.get(processor_id),
+                };
+                let output = super::extension::process_extension(
                     &state,
-                    &content_bytes,
-                    mime_type,
+                    &content,
                     &client_payload.owner_wallet,
                     processor_id,
-                    client_payload
-                        .extension_inputs
-                        .as_ref()
-                        .and_then(|m| m.get(processor_id)),
+                    extension_input,
+                    interrupt.as_ref(),
                 )
                 .await
                 .map_err(|e| TeeError::ProcessingFailed(format!("Extension処理に失敗 ({}): {e}", processor_id)))?;
 
-                results.push(ProcessorResult {
+                if request.depends_on.values().any(|upstream| upstream == processor_id) {
+                    upstream_results.insert(processor_id, title_types::canonical_json(&output.result));
+                }
+                results[index] = Some(ProcessorResult {
                     processor_id: processor_id.clone(),
-                    signed_json,
+                    signed_json: output.signed_json,
+                    result_size: Some(output.result_size),
+                    assertions: None,
+                    preview_hash: None,
+                    signer_cert_warning: None,
                 });
             }
         }
 
-        Ok::<Vec<ProcessorResult>, TeeError>(results)
+        Ok::<Vec<ProcessorResult>, TeeError>(results.into_iter().flatten().collect())
     })
     .await
     .map_err(|_| TeeError::Timeout)?;
@@ -239,3 +440,58 @@ pub async fn handle_verify(
 
     Ok(Json(encrypted_response))
 }
+
+/// 暗号化ペイロードを復号し、平文とレスポンス暗号化用の共通鍵を返す。
+/// 仕様書 §6.4 ハイブリッド暗号化 Step 6-7
+///
+/// 各フィールドのBase64は標準形式に加え、WebCrypto等のブラウザクライアントが用いる
+/// URL-safe形式（パディング有無を問わない）も受け付ける（[`title_types::codec`]）。
+pub(crate) fn decrypt_payload(
+    runtime: &dyn TeeRuntime,
+    encrypted_payload: EncryptedPayload,
+) -> Result<(Vec<u8>, title_crypto::SymmetricKey), TeeError> {
+    let eph_pubkey_arr: [u8; 32] = codec::decode_base64_array(&encrypted_payload.ephemeral_pubkey)
+        .map_err(|e| TeeError::BadRequest(format!("ephemeral_pubkeyが不正です: {e}")))?;
+    let eph_pubkey = X25519PublicKey::from(eph_pubkey_arr);
+
+    let tee_secret_bytes: [u8; 32] = runtime
+        .encryption_secret_key()
+        .try_into()
+        .map_err(|_| TeeError::Internal("暗号化用秘密鍵の取得に失敗".into()))?;
+    let tee_secret = StaticSecret::from(tee_secret_bytes);
+
+    // ECDH(tee_sk, eph_pk) → shared_secret
+    let shared_secret = title_crypto::ecdh_derive_shared_secret(&tee_secret, &eph_pubkey);
+    // HKDF → symmetric_key
+    let symmetric_key = title_crypto::hkdf_derive_key(&shared_secret)
+        .map_err(|e| TeeError::Internal(format!("対称鍵の導出に失敗: {e}")))?;
+
+    let nonce = codec::decode_nonce(&encrypted_payload.nonce)
+        .map_err(|e| TeeError::BadRequest(format!("nonceが不正です: {e}")))?;
+
+    let ciphertext = codec::decode_base64(&encrypted_payload.ciphertext)
+        .map_err(|e| TeeError::BadRequest(format!("ciphertextが不正です: {e}")))?;
+    drop(encrypted_payload); // EncryptedPayloadのメモリを早期解放
+
+    // AES-GCM復号（認証タグは暗号文末尾の16バイト。WebCryptoの出力形式と同一）
+    let plaintext = title_crypto::aes_gcm_decrypt(&symmetric_key, &nonce, &ciphertext)
+        .map_err(|e| TeeError::BadRequest(format!("ペイロードの復号に失敗: {e}")))?;
+    Ok((plaintext, symmetric_key))
+}
+
+/// UI表示用のプレビューハッシュ（16桁hex）を計算する。
+/// 仕様書 §5.1 Step 6
+///
+/// デコード時のピークメモリをResourcePoolで予約し、予約できない場合や
+/// 画像としてデコードできない場合は付与しない（検証自体は失敗させない）。
+fn compute_preview_hash(
+    pool: &Arc<title_wasm_host::ResourcePool>,
+    content: &[u8],
+) -> Option<String> {
+    use title_wasm_host::decode;
+
+    let kind = decode::detect(content)?;
+    let peak_bytes = decode::estimate_peak_bytes(kind, content).ok()?;
+    let _ticket = pool.acquire(peak_bytes)?;
+    decode::preview_hash(content).map(|hash| format!("{hash:016x}"))
+}
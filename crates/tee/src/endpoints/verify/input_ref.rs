@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Extension補助入力の参照解決
+//!
+//! 仕様書 §3.2, §7.1 補助入力の分配
+//!
+//! 大きな参照データ（参照用の知覚ハッシュ集合等）をClientPayloadにインラインで含めると
+//! 暗号化ペイロードが肥大化する。補助入力は `{"ref_url": "...", "sha256": "..."}` 形式の
+//! コンテンツアドレス参照として指定でき、TEEはプロキシ経由で取得し、
+//! SHA-256を照合してからWASMに `extension_input` として渡す。
+//!
+//! 取得したバイト列はそのままWASMに渡すため、`extension_input_hash` は参照の `sha256` と一致する。
+//! インライン入力は正規化JSON（仕様書 §5.1）のバイト列として渡すため、`extension_input_hash` は
+//! クライアントが送ったキーの順序に依存せず、元の入力から第三者が再計算できる。
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use title_wasm_host::Ticket;
+
+use crate::config::TeeAppState;
+use crate::error::TeeError;
+use crate::infra::security::{self, ResolvedLimits, SecurityError};
+
+/// コンテンツアドレス参照による補助入力。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ExtensionInputRef {
+    /// 補助入力の取得先URL（https）
+    pub ref_url: String,
+    /// 補助入力のSHA-256ハッシュ
+    pub sha256: [u8; 32],
+}
+
+/// 補助入力の値が参照形式であれば解釈する。
+/// 仕様書 §7.1
+///
+/// `ref_url` キーを持つオブジェクトを参照とみなす。参照は `ref_url` と `sha256` のみを持ち、
+/// `sha256` は64桁のhex（`0x` プレフィックス可）でなければならない。
+/// 参照でない値（インライン入力）は `None` を返す。
+pub(crate) fn parse_input_ref(
+    extension_id: &str,
+    value: &serde_json::Value,
+) -> Result<Option<ExtensionInputRef>, TeeError> {
+    let Some(obj) = value.as_object().filter(|o| o.contains_key("ref_url")) else {
+        return Ok(None);
+    };
+    let invalid =
+        |msg: &str| TeeError::BadRequest(format!("extension_input ({extension_id}) の参照が不正です: {msg}"));
+
+    if obj.keys().any(|k| k != "ref_url" && k != "sha256") {
+        return Err(invalid("ref_url と sha256 以外のフィールドは指定できません"));
+    }
+    let ref_url = obj["ref_url"]
+        .as_str()
+        .ok_or_else(|| invalid("ref_url は文字列である必要があります"))?;
+    let sha256_hex = obj
+        .get("sha256")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| invalid("sha256 が必要です"))?;
+    let sha256: [u8; 32] = hex::decode(sha256_hex.strip_prefix("0x").unwrap_or(sha256_hex))
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| invalid("sha256 は64桁のhexである必要があります"))?;
+
+    validate_ref_url(ref_url).map_err(|e| invalid(&e))?;
+
+    Ok(Some(ExtensionInputRef {
+        ref_url: ref_url.to_string(),
+        sha256,
+    }))
+}
+
+/// 参照URLを検証する（SSRF対策）。
+/// 仕様書 §6.4
+///
+/// - スキームは `https` のみ
+/// - 認証情報（`user:pass@`）を含むURLは拒否
+/// - `localhost` およびループバック・プライベート・リンクローカル等の
+///   内部向けIPアドレスリテラルは拒否
+///
+/// ホスト名のDNS解決はホスト側プロキシが行うため、名前解決後のアドレス検査とリダイレクトの拒否は
+/// 取得時にプロキシが行う（[`security::proxy_get_external_secured`]）。
+pub(crate) fn validate_ref_url(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("URLのパースに失敗: {e}"))?;
+    if parsed.scheme() != "https" {
+        return Err(format!("https以外のスキームは使用できません: {}", parsed.scheme()));
+    }
+    if !parsed.username().is_empty() || parsed.password().is_some() {
+        return Err("認証情報を含むURLは使用できません".to_string());
+    }
+
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| "ホストが指定されていません".to_string())?;
+    // IPv6リテラルは角括弧付きで返る
+    match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(ip) => check_public_ip(ip)?,
+        Err(_) => {
+            let domain = host.trim_end_matches('.').to_ascii_lowercase();
+            if domain == "localhost" || domain.ends_with(".localhost") {
+                return Err(format!("内部向けのホストは使用できません: {domain}"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// IPアドレスリテラルが外部向けのアドレスであることを確認する。
+fn check_public_ip(ip: IpAddr) -> Result<(), String> {
+    let internal = match ip {
+        IpAddr::V4(v4) => is_internal_v4(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_internal_v4(v4),
+            None => is_internal_v6(v6),
+        },
+    };
+    if internal {
+        return Err(format!("内部向けのアドレスは使用できません: {ip}"));
+    }
+    Ok(())
+}
+
+fn is_internal_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        // 100.64.0.0/10（Carrier-Grade NAT）
+        || (a == 100 && (b & 0xC0) == 64)
+}
+
+fn is_internal_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // fc00::/7（ユニークローカル）
+        || (first & 0xFE00) == 0xFC00
+        // fe80::/10（リンクローカル）
+        || (first & 0xFFC0) == 0xFE80
+}
+
+/// 解決済みの補助入力（extension_id → WASMに渡すバイト列）。
+#[derive(Default)]
+pub(crate) struct ResolvedExtensionInputs {
+    inputs: HashMap<String, Vec<u8>>,
+    /// 参照から取得したデータのメモリ予約（処理完了まで保持する）
+    _tickets: Vec<Ticket>,
+}
+
+impl ResolvedExtensionInputs {
+    /// 指定Extensionの補助入力を返す。
+    pub(crate) fn get(&self, extension_id: &str) -> Option<&[u8]> {
+        self.inputs.get(extension_id).map(Vec::as_slice)
+    }
+}
+
+/// リクエストされたExtensionの補助入力を解決する。
+/// 仕様書 §7.1 補助入力の分配
+///
+/// インライン入力は正規化JSON（[`title_types::canonical_json`]）のバイト列を、参照入力はプロキシ経由で
+/// 外部向けアドレスに限定して取得し、SHA-256を照合したバイト列を返す。参照の取得にはセキュア化されたダウンロード
+/// （サイズ上限 `max_extension_input_bytes`・漸進的予約・チャンクタイムアウト）を適用する。
+/// 取得データはJSONでなければならない。WASM実行前に全ての参照を解決し、
+/// 1件でも失敗すればリクエスト全体を拒否する。
+pub(crate) async fn resolve_extension_inputs(
+    state: &TeeAppState,
+    extension_inputs: Option<&serde_json::Map<String, serde_json::Value>>,
+    processor_ids: &[String],
+    limits: &ResolvedLimits,
+) -> Result<ResolvedExtensionInputs, TeeError> {
+    let mut inputs = HashMap::new();
+    let mut tickets = Vec::new();
+
+    for (extension_id, value) in extension_inputs.into_iter().flatten() {
+        // リクエストされていないExtensionの入力は取得しない
+        if !processor_ids.iter().any(|id| id == extension_id) {
+            continue;
+        }
+
+        let bytes = match parse_input_ref(extension_id, value)? {
+            None => title_types::canonical_json(value),
+            Some(input_ref) => {
+                let (bytes, ticket) =
+                    fetch_input_ref(state, extension_id, &input_ref, limits).await?;
+                tickets.push(ticket);
+                bytes
+            }
+        };
+        inputs.insert(extension_id.clone(), bytes);
+    }
+
+    Ok(ResolvedExtensionInputs {
+        inputs,
+        _tickets: tickets,
+    })
+}
+
+/// 参照された補助入力を取得し、ハッシュとJSON形式を検証する。
+async fn fetch_input_ref(
+    state: &TeeAppState,
+    extension_id: &str,
+    input_ref: &ExtensionInputRef,
+    limits: &ResolvedLimits,
+) -> Result<(Vec<u8>, Ticket), TeeError> {
+    let max_bytes = state.max_extension_input_bytes as u64;
+    let timeout = security::compute_dynamic_timeout(limits, max_bytes);
+
+    let (response, ticket) = tokio::time::timeout(
+        timeout,
+        security::proxy_get_external_secured(
+            &state.proxy_addr,
+            &input_ref.ref_url,
+            max_bytes,
+            std::time::Duration::from_secs(limits.chunk_read_timeout_sec),
+            &state.resource_pool,
+        ),
+    )
+    .await
+    .map_err(|_| TeeError::Timeout)?
+    .map_err(|e| match &e {
+        SecurityError::PayloadTooLarge { size, limit } => TeeError::PayloadTooLarge(format!(
+            "extension_input ({extension_id}) のサイズが上限を超えています: {size} bytes (上限: {limit} bytes)"
+        )),
+        SecurityError::MemoryLimitExceeded => TeeError::ServiceUnavailable(e.to_string()),
+        SecurityError::ChunkReadTimeout { .. } => TeeError::Timeout,
+        _ => TeeError::BadGateway(format!("extension_input ({extension_id}) の取得に失敗: {e}")),
+    })?;
+
+    if title_crypto::sha256(&response.body) != input_ref.sha256 {
+        return Err(TeeError::BadRequest(format!(
+            "extension_input ({extension_id}) のSHA-256が参照と一致しません"
+        )));
+    }
+    serde_json::from_slice::<serde_json::Value>(&response.body).map_err(|e| {
+        TeeError::BadRequest(format!(
+            "extension_input ({extension_id}) の参照先がJSONではありません: {e}"
+        ))
+    })?;
+
+    Ok((response.body, ticket))
+}
@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # manifest-onlyモード
+//!
+//! 仕様書 §5.1 Step 4
+//!
+//! コンテンツ本体を受け取らず、サイドカーManifest（`.c2pa`）と
+//! クライアントが主張するコンテンツハッシュのみでCore検証を行う。
+//! コンテンツを別の場所で保持する統合向けで、巨大な本体の転送を不要にする。
+//! 本体を見ていないため、結果には `manifest_only: true` が付与される。
+
+use base64::Engine;
+use title_types::ClientPayload;
+
+use crate::endpoints::b64;
+use crate::error::TeeError;
+
+use super::CORE_PROCESSOR_ID;
+
+/// manifest-onlyモードの入力（デコード済み）。
+pub(crate) struct ManifestOnlyInput {
+    /// サイドカーManifest（JUMBF）
+    pub sidecar: Vec<u8>,
+    /// クライアントが主張するコンテンツ本体のハッシュ（`c2pa.hash.data` と照合）
+    pub asserted_hash: Vec<u8>,
+}
+
+/// ClientPayloadからmanifest-onlyモードの入力を取り出す。
+/// `asserted_content_hash` が指定されていない場合は通常モードとして `None` を返す。
+///
+/// manifest-onlyモードでは本体を一切受け付けないため、`content` が空でなければ拒否する。
+/// また、Extensionは本体を必要とするため `core-c2pa` 以外のprocessor_idも拒否する。
+pub(crate) fn parse_manifest_only_input(
+    payload: &ClientPayload,
+    processor_ids: &[String],
+) -> Result<Option<ManifestOnlyInput>, TeeError> {
+    let Some(asserted) = payload.asserted_content_hash.as_deref() else {
+        return Ok(None);
+    };
+
+    if !payload.content.is_empty() {
+        return Err(TeeError::BadRequest(
+            "asserted_content_hash指定時（manifest-onlyモード）はcontentを送信できません".into(),
+        ));
+    }
+    if let Some(id) = processor_ids.iter().find(|id| *id != CORE_PROCESSOR_ID) {
+        return Err(TeeError::BadRequest(format!(
+            "manifest-onlyモードではコンテンツ本体が必要なExtensionを実行できません: {id}"
+        )));
+    }
+
+    let sidecar_b64 = payload.sidecar_manifest.as_deref().ok_or_else(|| {
+        TeeError::BadRequest("manifest-onlyモードにはsidecar_manifestが必要です".into())
+    })?;
+    let sidecar = b64()
+        .decode(sidecar_b64)
+        .map_err(|e| TeeError::BadRequest(format!("sidecar_manifestのBase64デコードに失敗: {e}")))?;
+
+    let asserted_hash = hex::decode(asserted.strip_prefix("0x").unwrap_or(asserted))
+        .map_err(|e| TeeError::BadRequest(format!("asserted_content_hashのhexデコードに失敗: {e}")))?;
+    if asserted_hash.is_empty() {
+        return Err(TeeError::BadRequest("asserted_content_hashが空です".into()));
+    }
+
+    Ok(Some(ManifestOnlyInput {
+        sidecar,
+        asserted_hash,
+    }))
+}
@@ -5,18 +5,33 @@
 //! 仕様書 §6.4 /verifyフェーズの内部処理
 //!
 //! ## モジュール構成
-//! - `handler`: メインハンドラ（リクエスト受付・暗号化・復号）
+//! - `handler`: メインハンドラ（リクエスト受付・暗号化・復号・取り消し）
+//! - `chain`: Extensionの連鎖実行（`depends_on`）の検証と実行順序
+//! - `content`: リクエスト内で共有するコンテンツ解析結果（C2PA検証・content_hash）
 //! - `core`: Core処理（C2PA検証 + 来歴グラフ構築）
 //! - `extension`: Extension処理（WASM実行）
+//! - `input_ref`: コンテンツアドレス参照による補助入力の取得・検証
+//! - `normalize`: Extension出力の共通エンベロープへの正規化
+//! - `manifest_only`: サイドカーManifestのみで検証するモードの入力解釈
 
 mod handler;
+mod chain;
+mod content;
 mod core;
 mod extension;
+mod input_ref;
+mod manifest_only;
+mod normalize;
 
-pub use handler::handle_verify;
+pub use handler::{handle_cancel_verify, handle_verify};
+pub(crate) use extension::{acquire_instance_slot, extension_runner};
 
 /// コンテンツのMIMEタイプをマジックバイトから検出する。
 /// 仕様書 §2.1
+///
+/// content_hashはフォーマットに依存しない（Active Manifestの署名から導出する）が、
+/// JUMBFの格納位置はフォーマットごとに異なるため、c2pa-rsのフォーマットハンドラの選択に用いる。
+/// MP3はc2pa-rsがManifestをID3タグに格納するため、署名済みのものは `ID3` で始まる。
 pub(crate) fn detect_mime_type(data: &[u8]) -> &str {
     if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
         "image/jpeg"
@@ -24,6 +39,12 @@ pub(crate) fn detect_mime_type(data: &[u8]) -> &str {
         "image/png"
     } else if data.len() >= 12 && data[8..12] == *b"WEBP" {
         "image/webp"
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && data[8..12] == *b"WAVE" {
+        "audio/wav"
+    } else if data.starts_with(b"ID3") || (data.len() >= 2 && data[0] == 0xFF && data[1] & 0xE0 == 0xE0) {
+        "audio/mpeg"
+    } else if data.len() >= 8 && data[4..8] == *b"ftyp" {
+        "video/mp4"
     } else {
         "application/octet-stream"
     }
@@ -36,6 +57,10 @@ pub(crate) fn format_content_hash(hash: &[u8; 32]) -> String {
     format!("0x{hex}")
 }
 
+/// signed_jsonの `attributes` の `trait_type` が重複していないことを確認する。
+/// 仕様書 §5.1 Step 4-5
+pub(crate) use title_core::signed_json::ensure_unique_trait_types;
+
 /// Core プロセッサID。
 pub(crate) const CORE_PROCESSOR_ID: &str = "core-c2pa";
 
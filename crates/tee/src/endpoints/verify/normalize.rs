@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Extension出力の正規化
+//!
+//! 仕様書 §3.1, §5.1 Step 5
+//!
+//! Extensionごとに異なるWASM出力の形状（フラット、`{"result": ...}` ラップ等）を、
+//! クライアントが一様に扱える共通エンベロープに変換する。
+//! 既定では無効（環境変数 `NORMALIZE_EXTENSION_OUTPUT` で有効化）。
+//!
+//! ## 共通エンベロープ
+//! ```json
+//! {
+//!   "result": {
+//!     "value": <Extensionの主要な値（該当なしの場合 null）>,
+//!     "details": { <主要な値以外のフィールド> }
+//!   }
+//! }
+//! ```
+//! `ExtensionPayload.result` はpayloadにフラット展開されるため、
+//! payload上では `payload.result.value` / `payload.result.details` として現れる。
+
+use serde_json::{Map, Value};
+
+/// 既知のExtensionの主要フィールド名。
+/// 一覧にないExtensionは主要な値を持たず、全フィールドが `details` に入る。
+const PRIMARY_FIELDS: &[(&str, &str)] = &[
+    ("phash-v1", "phash"),
+    ("hardware-google", "hardware_detected"),
+    ("c2pa-training-v1", "training_allowed"),
+    ("c2pa-license-v1", "license"),
+    ("pixel-hash-v1", "pixel_hash"),
+    ("cawg-identity-v1", "identity_present"),
+    ("assertion-list-v1", "assertions"),
+    ("image-quality-v1", "jpeg_quality"),
+];
+
+/// WASM出力を共通エンベロープに正規化する。
+/// 仕様書 §5.1 Step 5
+///
+/// - `{"result": {...}}` のように単一の `result` でラップされた出力は先に展開する
+/// - 既知のExtensionは主要フィールドを `value` に、残りを `details` に移す
+/// - オブジェクト以外の出力はそのまま `value` とする
+pub(crate) fn normalize_extension_output(extension_id: &str, output: Value) -> Value {
+    let output = match output {
+        Value::Object(mut map) if map.len() == 1 && map.contains_key("result") => {
+            map.remove("result").unwrap_or(Value::Null)
+        }
+        other => other,
+    };
+
+    let (value, details) = match output {
+        Value::Object(mut fields) => {
+            let value = PRIMARY_FIELDS
+                .iter()
+                .find(|(id, _)| *id == extension_id)
+                .and_then(|(_, field)| fields.remove(*field))
+                .unwrap_or(Value::Null);
+            (value, fields)
+        }
+        other => (other, Map::new()),
+    };
+
+    serde_json::json!({
+        "result": {
+            "value": value,
+            "details": details,
+        }
+    })
+}
@@ -7,7 +7,13 @@
 //! 全エンドポイントで共通のエラー型。
 //! `GatewayError`（`crates/gateway/src/error.rs`）と同パターン。
 
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
+
+/// `TooEarly` 応答の `Retry-After` ヘッダに設定する再試行までの秒数
+pub const TOO_EARLY_RETRY_AFTER_SECS: u64 = 1;
+
+/// `Cancelled` 応答のステータスコード（nginxの "Client Closed Request" に倣う）
+const CLIENT_CLOSED_REQUEST: u16 = 499;
 
 /// TEEエラー型。
 /// 仕様書 §6.4
@@ -34,6 +40,9 @@ pub enum TeeError {
     /// 外部通信失敗（Proxy/Temporary Storage）
     #[error("外部通信に失敗: {0}")]
     BadGateway(String),
+    /// アップロード直後でTemporary Storageのオブジェクトがまだ参照できない（再試行可能）
+    #[error("ペイロードがまだ取得できません（少し待って再試行してください）: {0}")]
+    TooEarly(String),
     /// 検証・処理失敗（C2PA検証、WASM実行）
     #[error("検証処理に失敗: {0}")]
     ProcessingFailed(String),
@@ -46,6 +55,12 @@ pub enum TeeError {
     /// メモリ制限到達
     #[error("メモリ制限に達しました")]
     ServiceUnavailable(String),
+    /// 対象が存在しない（取り消し対象の検証が処理中でない等）
+    #[error("{0}")]
+    NotFound(String),
+    /// クライアントの取り消し要求により処理を中断した
+    #[error("検証処理は取り消されました")]
+    Cancelled,
 }
 
 impl axum::response::IntoResponse for TeeError {
@@ -60,9 +75,20 @@ impl axum::response::IntoResponse for TeeError {
             TeeError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
             TeeError::Timeout => StatusCode::REQUEST_TIMEOUT,
             TeeError::BadGateway(_) => StatusCode::BAD_GATEWAY,
+            TeeError::TooEarly(_) => {
+                return (
+                    StatusCode::TOO_EARLY,
+                    [(header::RETRY_AFTER, TOO_EARLY_RETRY_AFTER_SECS.to_string())],
+                    self.to_string(),
+                )
+                    .into_response();
+            }
             TeeError::ProcessingFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
             TeeError::Forbidden(_) => StatusCode::FORBIDDEN,
             TeeError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            TeeError::NotFound(_) => StatusCode::NOT_FOUND,
+            TeeError::Cancelled => StatusCode::from_u16(CLIENT_CLOSED_REQUEST)
+                .unwrap_or(StatusCode::BAD_REQUEST),
         };
         (status, self.to_string()).into_response()
     }
@@ -84,10 +110,13 @@ mod tests {
             (TeeError::PayloadTooLarge("t".into()), StatusCode::PAYLOAD_TOO_LARGE),
             (TeeError::Timeout, StatusCode::REQUEST_TIMEOUT),
             (TeeError::BadGateway("t".into()), StatusCode::BAD_GATEWAY),
+            (TeeError::TooEarly("t".into()), StatusCode::TOO_EARLY),
             (TeeError::ProcessingFailed("t".into()), StatusCode::UNPROCESSABLE_ENTITY),
             (TeeError::Forbidden("t".into()), StatusCode::FORBIDDEN),
             (TeeError::Unauthorized("t".into()), StatusCode::UNAUTHORIZED),
             (TeeError::ServiceUnavailable("t".into()), StatusCode::SERVICE_UNAVAILABLE),
+            (TeeError::NotFound("t".into()), StatusCode::NOT_FOUND),
+            (TeeError::Cancelled, StatusCode::from_u16(499).unwrap()),
         ];
 
         for (error, expected_status) in cases {
@@ -99,4 +128,14 @@ mod tests {
             );
         }
     }
+
+    /// TooEarlyにRetry-Afterヘッダが付与されることを確認
+    #[test]
+    fn test_too_early_has_retry_after() {
+        let response = TeeError::TooEarly("t".into()).into_response();
+        assert_eq!(
+            response.headers().get(header::RETRY_AFTER).unwrap(),
+            &TOO_EARLY_RETRY_AFTER_SECS.to_string()
+        );
+    }
 }
@@ -0,0 +1,379 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Extensionレジストリ
+//!
+//! 仕様書 §5.1 Step 5, Step 11, §6.4, §7.1
+//!
+//! ノードが実行を許可するExtension（環境変数 `TRUSTED_EXTENSIONS`）、Extensionごとの
+//! cNFTシンボル（環境変数 `EXTENSION_SYMBOLS`）、およびExtensionごとの実行設定
+//! （環境変数 `EXTENSION_REGISTRY_FILE` のJSON）をまとめて保持する。
+//! 起動時に一度だけ読み込み・検証し、/verify・/sign・/self-test はこのレジストリを参照する。
+//!
+//! ## 実行設定ファイル
+//!
+//! Extension IDをキーとするJSONオブジェクト。すべての項目は省略可能。
+//!
+//! ```json
+//! {
+//!   "phash-v1": {
+//!     "wasm_hash": "0x<WASMバイナリのSHA-256>",
+//!     "export": "process",
+//!     "mime_types": ["image/*"],
+//!     "capabilities": ["read_content_chunk", "decode_content", "read_decoded_chunk"],
+//!     "fuel_limit": 500000000,
+//!     "memory_limit_bytes": 33554432,
+//!     "max_result_bytes": 4096,
+//!     "pooled": true
+//!   }
+//! }
+//! ```
+//!
+//! `pooled` は事前インスタンス化プール（環境変数 `WASM_INSTANCE_POOL`）での実行を
+//! Extensionごとに許可する。プールは信頼済みかつ決定的なモジュールにのみ使用できるため、
+//! `pooled` には `wasm_hash` による実行バイナリの固定を必須とする。
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Deserialize;
+
+use crate::blockchain::cnft_metadata::ExtensionSymbols;
+use crate::endpoints::verify::CORE_PROCESSOR_ID;
+use crate::wasm_loader::STANDARD_EXPORT_NAME;
+
+/// Extensionごとの実行設定（`EXTENSION_REGISTRY_FILE` の各エントリ）。
+/// 仕様書 §6.4, §7.1
+///
+/// 制限値はノード共通の上限を締める方向にのみ働く（大きい値を指定してもノード共通の上限が適用される）。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExtensionSpec {
+    /// 実行を許可するWASMバイナリのSHA-256（"0x" + 小文字hex）。Noneの場合は照合しない
+    #[serde(default)]
+    pub wasm_hash: Option<String>,
+    /// 呼び出すエクスポート関数名。Noneの場合は標準の `process`
+    #[serde(default)]
+    pub export: Option<String>,
+    /// 対象とするMIMEタイプ（`image/*` のような末尾ワイルドカード可）。空の場合は全MIMEタイプ
+    #[serde(default)]
+    pub mime_types: Vec<String>,
+    /// インポートを許可するホスト関数（[`title_wasm_host::HOST_FUNCTIONS`]）。Noneの場合は全ホスト関数
+    #[serde(default)]
+    pub capabilities: Option<Vec<String>>,
+    /// Fuel制限（命令実行数）
+    #[serde(default)]
+    pub fuel_limit: Option<u64>,
+    /// WASM線形メモリの上限（バイト）
+    #[serde(default)]
+    pub memory_limit_bytes: Option<usize>,
+    /// Extension結果（WASM出力）の最大サイズ（バイト）
+    #[serde(default)]
+    pub max_result_bytes: Option<usize>,
+    /// 事前インスタンス化プールで実行するか（`wasm_hash` の登録が必須）。既定はfalse（呼び出しごとに新規Store）
+    #[serde(default)]
+    pub pooled: bool,
+}
+
+/// 実行設定が未登録のExtensionに適用する既定値（制限なし）
+static DEFAULT_SPEC: ExtensionSpec = ExtensionSpec {
+    wasm_hash: None,
+    export: None,
+    mime_types: Vec::new(),
+    capabilities: None,
+    fuel_limit: None,
+    memory_limit_bytes: None,
+    max_result_bytes: None,
+    pooled: false,
+};
+
+impl ExtensionSpec {
+    /// MIMEタイプが対象に含まれるかどうか。
+    pub fn accepts_mime(&self, mime_type: &str) -> bool {
+        self.mime_types.is_empty()
+            || self.mime_types.iter().any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => mime_type.starts_with(prefix),
+                None => pattern == mime_type,
+            })
+    }
+
+    /// 設定値の形式を検証する。
+    fn validate(&self, extension_id: &str) -> Result<(), String> {
+        if let Some(hash) = &self.wasm_hash {
+            let valid = hash.strip_prefix("0x").is_some_and(|hex| {
+                hex.len() == 64 && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+            });
+            if !valid {
+                return Err(format!(
+                    "{extension_id}: wasm_hashは\"0x\" + 64桁の小文字hexで指定してください: {hash}"
+                ));
+            }
+        }
+        if self.export.as_deref().is_some_and(str::is_empty) {
+            return Err(format!("{extension_id}: exportが空です"));
+        }
+        if let Some(pattern) = self
+            .mime_types
+            .iter()
+            .find(|m| m.is_empty() || m.trim_end_matches('*').contains('*'))
+        {
+            return Err(format!("{extension_id}: mime_typesの形式が不正です: {pattern:?}"));
+        }
+        for capability in self.capabilities.iter().flatten() {
+            if !title_wasm_host::HOST_FUNCTIONS.contains(&capability.as_str()) {
+                return Err(format!("{extension_id}: 未知のホスト関数です: {capability}"));
+            }
+        }
+        if self.fuel_limit == Some(0)
+            || self.memory_limit_bytes == Some(0)
+            || self.max_result_bytes == Some(0)
+        {
+            return Err(format!("{extension_id}: 制限値に0は指定できません"));
+        }
+        // プール実行は固定されたバイナリ（信頼済みかつ決定的であることを確認したもの）に限る
+        if self.pooled && self.wasm_hash.is_none() {
+            return Err(format!("{extension_id}: pooledを有効にするにはwasm_hashの登録が必要です"));
+        }
+        Ok(())
+    }
+}
+
+/// ノードが扱うExtensionのレジストリ。
+/// 仕様書 §6.4 不正WASMインジェクション防御
+///
+/// 既定値（`TRUSTED_EXTENSIONS` 未設定）は全Extensionの実行を許可する（開発環境用）。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionRegistry {
+    /// 実行を許可するExtension ID。Noneの場合は全Extensionを許可する
+    trusted: Option<BTreeSet<String>>,
+    /// Extension ID → cNFTシンボルの対応表
+    symbols: ExtensionSymbols,
+    /// Extension ID → 実行設定
+    specs: BTreeMap<String, ExtensionSpec>,
+}
+
+impl ExtensionRegistry {
+    /// `TRUSTED_EXTENSIONS`（カンマ区切り）、`EXTENSION_SYMBOLS`、および実行設定（JSON）から
+    /// レジストリを構築する。
+    ///
+    /// 以下の場合はエラーを返す。
+    /// - Extension IDが英数字・`-`・`_` 以外を含む、またはCoreのprocessor_idである
+    /// - シンボルの形式が不正（[`ExtensionSymbols::parse`]）
+    /// - 実行設定のJSONが不正、または値が不正（未知のホスト関数、0の制限値など）
+    /// - 許可一覧がある場合に、一覧に含まれないExtensionのシンボル・実行設定がある
+    pub fn parse(
+        trusted: Option<&str>,
+        symbols: Option<&str>,
+        specs: Option<&str>,
+    ) -> Result<Self, String> {
+        let trusted = trusted
+            .map(|spec| {
+                spec.split(',')
+                    .map(str::trim)
+                    .filter(|id| !id.is_empty())
+                    .map(|id| validate_extension_id(id).map(|()| id.to_string()))
+                    .collect::<Result<BTreeSet<_>, _>>()
+            })
+            .transpose()?;
+        let symbols = symbols.map(ExtensionSymbols::parse).transpose()?.unwrap_or_default();
+        let specs: BTreeMap<String, ExtensionSpec> = specs
+            .map(|json| {
+                serde_json::from_str(json).map_err(|e| format!("Extensionの実行設定が不正です: {e}"))
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        for extension_id in symbols.extension_ids() {
+            validate_extension_id(extension_id)?;
+            if trusted.as_ref().is_some_and(|t| !t.contains(extension_id)) {
+                return Err(format!(
+                    "TRUSTED_EXTENSIONSに含まれないExtensionのシンボルが設定されています: {extension_id}"
+                ));
+            }
+        }
+        for (extension_id, spec) in &specs {
+            validate_extension_id(extension_id)?;
+            if trusted.as_ref().is_some_and(|t| !t.contains(extension_id)) {
+                return Err(format!(
+                    "TRUSTED_EXTENSIONSに含まれないExtensionの実行設定があります: {extension_id}"
+                ));
+            }
+            spec.validate(extension_id)?;
+        }
+        Ok(Self { trusted, symbols, specs })
+    }
+
+    /// 許可一覧で実行できるExtensionが制限されているかどうか。
+    pub fn is_restricted(&self) -> bool {
+        self.trusted.is_some()
+    }
+
+    /// Extensionの実行が許可されているかどうか。
+    pub fn is_trusted(&self, extension_id: &str) -> bool {
+        self.trusted
+            .as_ref()
+            .is_none_or(|trusted| trusted.contains(extension_id))
+    }
+
+    /// 許可一覧のExtension ID（昇順）。全Extensionを許可している場合はNone。
+    pub fn trusted_ids(&self) -> Option<Vec<&str>> {
+        self.trusted
+            .as_ref()
+            .map(|trusted| trusted.iter().map(String::as_str).collect())
+    }
+
+    /// Extensionのシンボル対応表を返す。
+    pub fn symbols(&self) -> &ExtensionSymbols {
+        &self.symbols
+    }
+
+    /// 実行設定が登録されたExtensionの数。
+    pub fn spec_count(&self) -> usize {
+        self.specs.len()
+    }
+
+    /// Extensionの実行設定を返す。未登録の場合は既定値（制限なし）。
+    pub fn spec(&self, extension_id: &str) -> &ExtensionSpec {
+        self.specs.get(extension_id).unwrap_or(&DEFAULT_SPEC)
+    }
+
+    /// 事前インスタンス化プールでの実行を許可されたExtension ID（昇順）。
+    pub fn pooled_ids(&self) -> Vec<&str> {
+        self.specs
+            .iter()
+            .filter(|(_, spec)| spec.pooled)
+            .map(|(id, _)| id.as_str())
+            .collect()
+    }
+
+    /// Extensionの呼び出すエクスポート関数名。
+    pub fn export_name(&self, extension_id: &str) -> &str {
+        self.spec(extension_id)
+            .export
+            .as_deref()
+            .unwrap_or(STANDARD_EXPORT_NAME)
+    }
+
+    /// ロードしたWASMバイナリのハッシュが登録値と一致することを確認する（未登録なら照合しない）。
+    pub fn check_wasm_hash(&self, extension_id: &str, wasm_hash: &str) -> Result<(), String> {
+        match &self.spec(extension_id).wasm_hash {
+            Some(expected) if expected != wasm_hash => Err(format!(
+                "WASMバイナリのハッシュが登録値と一致しません: {extension_id}（登録={expected}, 実際={wasm_hash}）"
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Extension IDの形式を検証する（WASMの取得パス・URLに用いるため、区切り文字等を許さない）。
+fn validate_extension_id(extension_id: &str) -> Result<(), String> {
+    if extension_id == CORE_PROCESSOR_ID {
+        return Err(format!("CoreのIDはExtensionとして登録できません: {extension_id}"));
+    }
+    if !extension_id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Extension IDは英数字・-・_のみで指定してください: {extension_id}"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_lookup_known_and_unknown_extension() {
+        let registry = ExtensionRegistry::parse(
+            Some("phash-v1, c2pa-training-v1,"),
+            Some("phash-v1=PHASH"),
+            Some(
+                r#"{"phash-v1": {
+                    "wasm_hash": "0x00000000000000000000000000000000000000000000000000000000000000ab",
+                    "export": "compute",
+                    "mime_types": ["image/*", "video/mp4"],
+                    "capabilities": ["read_content_chunk", "get_content_length"],
+                    "fuel_limit": 1000,
+                    "memory_limit_bytes": 65536,
+                    "max_result_bytes": 1024,
+                    "pooled": true
+                }}"#,
+            ),
+        )
+        .unwrap();
+        assert!(registry.is_restricted());
+        assert_eq!(registry.trusted_ids(), Some(vec!["c2pa-training-v1", "phash-v1"]));
+
+        assert!(registry.is_trusted("phash-v1"));
+        assert_eq!(registry.symbols().symbol_for("phash-v1"), "PHASH");
+        assert!(registry.is_trusted("c2pa-training-v1"));
+        assert_eq!(registry.symbols().symbol_for("c2pa-training-v1"), "C2PA-TRAIN");
+
+        assert!(!registry.is_trusted("evil-ext"));
+
+        // 実行設定
+        assert_eq!(registry.spec_count(), 1);
+        let phash = registry.spec("phash-v1");
+        assert_eq!(registry.export_name("phash-v1"), "compute");
+        assert!(phash.accepts_mime("image/png"));
+        assert!(phash.accepts_mime("video/mp4"));
+        assert!(!phash.accepts_mime("audio/wav"));
+        assert_eq!(phash.fuel_limit, Some(1000));
+        assert_eq!(phash.memory_limit_bytes, Some(65536));
+        assert_eq!(phash.max_result_bytes, Some(1024));
+        assert_eq!(
+            phash.capabilities.as_deref(),
+            Some(&["read_content_chunk".to_string(), "get_content_length".to_string()][..])
+        );
+        let pinned = "0x00000000000000000000000000000000000000000000000000000000000000ab";
+        assert!(registry.check_wasm_hash("phash-v1", pinned).is_ok());
+        assert!(registry.check_wasm_hash("phash-v1", &format!("0x{}", "0".repeat(64))).is_err());
+        assert!(phash.pooled);
+        assert_eq!(registry.pooled_ids(), vec!["phash-v1"]);
+
+        // 実行設定のないExtensionは既定値（制限なし）
+        let training = registry.spec("c2pa-training-v1");
+        assert_eq!(training, &ExtensionSpec::default());
+        assert_eq!(registry.export_name("c2pa-training-v1"), STANDARD_EXPORT_NAME);
+        assert!(training.accepts_mime("audio/wav"));
+        assert!(registry.check_wasm_hash("c2pa-training-v1", pinned).is_ok());
+
+        // 許可一覧がない場合は全Extensionを許可する
+        let open = ExtensionRegistry::default();
+        assert!(!open.is_restricted());
+        assert!(open.is_trusted("evil-ext"));
+        assert_eq!(open.trusted_ids(), None);
+    }
+
+    #[test]
+    fn test_registry_rejects_invalid_config() {
+        assert!(ExtensionRegistry::parse(Some("../phash-v1"), None, None).is_err());
+        assert!(ExtensionRegistry::parse(Some("core-c2pa"), None, None).is_err());
+        assert!(ExtensionRegistry::parse(Some("phash-v1"), Some("other-v1=OTHER"), None).is_err());
+        assert!(ExtensionRegistry::parse(None, Some("phash-v1=ABCDEFGHIJK"), None).is_err());
+
+        // 実行設定の不正
+        for specs in [
+            "not json",
+            r#"{"other-v1": {}}"#,
+            r#"{"phash-v1": {"unknown": 1}}"#,
+            r#"{"phash-v1": {"wasm_hash": "0xABCD"}}"#,
+            r#"{"phash-v1": {"export": ""}}"#,
+            r#"{"phash-v1": {"mime_types": ["image/*/x*"]}}"#,
+            r#"{"phash-v1": {"capabilities": ["open_socket"]}}"#,
+            r#"{"phash-v1": {"fuel_limit": 0}}"#,
+            // ハッシュを固定しないExtensionはプールで実行できない
+            r#"{"phash-v1": {"pooled": true}}"#,
+        ] {
+            assert!(
+                ExtensionRegistry::parse(Some("phash-v1"), None, Some(specs)).is_err(),
+                "{specs}"
+            );
+        }
+
+        // 空の許可一覧はすべてのExtensionを拒否する
+        let none_trusted = ExtensionRegistry::parse(Some(""), None, None).unwrap();
+        assert!(!none_trusted.is_trusted("phash-v1"));
+    }
+}
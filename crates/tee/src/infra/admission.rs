@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # 優先度付きの検証受付
+//!
+//! 仕様書 §6.4
+//!
+//! /verify の同時処理数を処理枠（permit）で制限し、枠が埋まっている間に到着したリクエストは
+//! 優先度ごとの待ち行列に並べる。枠が解放されると、優先度の高い待ち行列の先頭から順に受け付ける
+//! （同じ優先度内では到着順）。これにより、一括処理のリクエストが枠を待っている間でも、
+//! 後から到着した対話的なリクエストが先に処理を開始できる。
+//!
+//! 受付後のメモリ予約（ResourcePool）は従来どおりで、枠はその手前の順序付けのみを担う。
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use title_types::VerifyPriority;
+use tokio::sync::oneshot;
+
+/// /verify の同時処理数のデフォルト上限
+pub const DEFAULT_MAX_CONCURRENT_VERIFIES: usize = 16;
+
+/// 優先度ごとの待ち行列の数（`VerifyPriority` の種類数）
+const PRIORITY_LEVELS: usize = 3;
+
+/// 優先度付きの受付制御。
+/// 仕様書 §6.4
+#[derive(Debug)]
+pub struct PriorityAdmission {
+    shared: Arc<Mutex<Shared>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    /// 空いている処理枠の数。待ち行列が空でない間は常に0
+    available: usize,
+    /// 優先度ごとの待ち行列（添字は [`queue_index`]）
+    queues: [VecDeque<oneshot::Sender<AdmissionPermit>>; PRIORITY_LEVELS],
+}
+
+/// 受付済みの処理枠。Drop時に枠を解放し、待機中のリクエストに引き渡す。
+#[derive(Debug)]
+pub struct AdmissionPermit {
+    shared: Arc<Mutex<Shared>>,
+    /// 引き渡しに失敗した（待機側が既にいない）permitは枠を解放しない
+    armed: bool,
+}
+
+impl Default for PriorityAdmission {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT_VERIFIES)
+    }
+}
+
+impl PriorityAdmission {
+    /// 処理枠 `permits` 個で作成する（0の場合は1として扱う）。
+    pub fn new(permits: usize) -> Self {
+        Self {
+            shared: Arc::new(Mutex::new(Shared {
+                available: permits.max(1),
+                queues: Default::default(),
+            })),
+        }
+    }
+
+    /// 処理枠を1つ確保する。枠が空くまで優先度順に待機する。
+    ///
+    /// 待機中にFutureが破棄された場合（取り消し・クライアント切断）、待ち行列の登録は
+    /// 次の引き渡し時に読み飛ばされ、枠は失われない。
+    pub async fn acquire(&self, priority: VerifyPriority) -> AdmissionPermit {
+        let receiver = {
+            let mut shared = self.lock();
+            if shared.available > 0 {
+                shared.available -= 1;
+                return AdmissionPermit {
+                    shared: Arc::clone(&self.shared),
+                    armed: true,
+                };
+            }
+            let (sender, receiver) = oneshot::channel();
+            let queue = &mut shared.queues[queue_index(priority)];
+            // 破棄済みの待機を掃除する（待ち行列の無制限な増加を防止）
+            queue.retain(|s| !s.is_closed());
+            queue.push_back(sender);
+            receiver
+        };
+        // 送信側は `Shared` が保持し、`self` が生存している間は破棄されない
+        receiver
+            .await
+            .unwrap_or_else(|_| unreachable!("待機中に受付制御が破棄されました"))
+    }
+
+    /// 待機中のリクエスト数（優先度の高い順）。
+    #[cfg(test)]
+    fn waiting(&self) -> [usize; PRIORITY_LEVELS] {
+        let shared = self.lock();
+        std::array::from_fn(|i| shared.queues[i].iter().filter(|s| !s.is_closed()).count())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Shared> {
+        self.shared.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        // 優先度の高い待ち行列から、まだ待機している最初のリクエストに枠を引き渡す
+        while let Some(sender) = shared.queues.iter_mut().find_map(VecDeque::pop_front) {
+            let permit = AdmissionPermit {
+                shared: Arc::clone(&self.shared),
+                armed: true,
+            };
+            match sender.send(permit) {
+                Ok(()) => return,
+                Err(mut unclaimed) => unclaimed.armed = false,
+            }
+        }
+        shared.available += 1;
+    }
+}
+
+/// 優先度に対応する待ち行列の添字（高い優先度ほど小さい）。
+fn queue_index(priority: VerifyPriority) -> usize {
+    match priority {
+        VerifyPriority::High => 0,
+        VerifyPriority::Normal => 1,
+        VerifyPriority::Low => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 枠が埋まっている間に並んだ低優先度より、後から来た高優先度が先に受け付けられることを確認
+    #[tokio::test]
+    async fn test_high_priority_admitted_ahead_of_queued_low_priority() {
+        let admission = Arc::new(PriorityAdmission::new(1));
+        let held = admission.acquire(VerifyPriority::Normal).await;
+
+        let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut tasks = Vec::new();
+        for (name, priority) in [
+            ("low-1", VerifyPriority::Low),
+            ("low-2", VerifyPriority::Low),
+            ("high", VerifyPriority::High),
+        ] {
+            let waiter = Arc::clone(&admission);
+            let order_tx = order_tx.clone();
+            tasks.push(tokio::spawn(async move {
+                let _permit = waiter.acquire(priority).await;
+                order_tx.send(name).unwrap();
+            }));
+            // 到着順を確定させる（低優先度が先に並ぶ）
+            while admission.waiting().iter().sum::<usize>() < tasks.len() {
+                tokio::task::yield_now().await;
+            }
+        }
+        assert_eq!(admission.waiting(), [1, 0, 2]);
+
+        drop(held);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        drop(order_tx);
+        let mut order = Vec::new();
+        while let Some(name) = order_rx.recv().await {
+            order.push(name);
+        }
+        assert_eq!(order, ["high", "low-1", "low-2"]);
+    }
+
+    /// 待機中に破棄されたリクエストは枠を消費しないことを確認
+    #[tokio::test]
+    async fn test_abandoned_waiter_does_not_leak_permit() {
+        let admission = PriorityAdmission::new(1);
+        let held = admission.acquire(VerifyPriority::Low).await;
+
+        // 待機中のFutureを破棄する
+        let abandoned = tokio::time::timeout(
+            std::time::Duration::from_millis(10),
+            admission.acquire(VerifyPriority::High),
+        )
+        .await;
+        assert!(abandoned.is_err());
+
+        drop(held);
+        let _permit = admission.acquire(VerifyPriority::Low).await;
+        assert_eq!(admission.waiting(), [0, 0, 0]);
+        assert_eq!(admission.lock().available, 0);
+    }
+}
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # content_hashの拒否リスト
+//!
+//! 仕様書 §6.4
+//!
+//! 法的要請（裁判所命令による削除、違法コンテンツのハッシュ等）によりノード運営者が処理・mintを
+//! 拒否しなければならないcontent_hashの一覧。/verifyはcontent_hashの算出後、Core/Extensionの
+//! 実行前に照合し、一致した場合は拒否する。/signもsigned_jsonのcontent_hashを照合し、
+//! テイクダウン前に取得したsigned_jsonであってもmintトランザクションを生成しない。
+//!
+//! 拒否時のエラーは一覧への登録有無を推測させないよう、理由を含まない汎用的な文言とする。
+
+use std::collections::HashSet;
+
+/// 拒否リストに一致したコンテンツに返すエラー文言（理由を明示しない）。
+pub const REFUSAL_MESSAGE: &str = "このコンテンツは処理できません";
+
+/// 処理を拒否するcontent_hashの一覧。
+/// 仕様書 §6.4
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentDenylist {
+    hashes: HashSet<[u8; 32]>,
+}
+
+impl ContentDenylist {
+    /// content_hash（`0x` 付きまたはなしの64桁hex）の一覧を解釈する。
+    ///
+    /// 区切りはカンマ・空白・改行のいずれでもよい。`#` 以降は行末までコメントとして無視する
+    /// （ファイルから読み込む場合を想定）。
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut hashes = HashSet::new();
+        for line in spec.lines() {
+            let line = line.split_once('#').map_or(line, |(body, _)| body);
+            for entry in line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|e| !e.is_empty())
+            {
+                let hash = decode_content_hash(entry)
+                    .ok_or_else(|| format!("content_hashは32バイトのhexで指定してください: {entry}"))?;
+                hashes.insert(hash);
+            }
+        }
+        Ok(Self { hashes })
+    }
+
+    /// 登録済みのcontent_hash数を返す。
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// 一覧が空かどうか。
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// content_hashが拒否対象かどうか。
+    pub fn contains(&self, content_hash: &[u8; 32]) -> bool {
+        self.hashes.contains(content_hash)
+    }
+
+    /// hex表記（signed_jsonの `content_hash`）のcontent_hashが拒否対象かどうか。
+    /// 32バイトのhexとして解釈できない値は一致しないものとして扱う。
+    pub fn contains_hex(&self, content_hash: &str) -> bool {
+        decode_content_hash(content_hash).is_some_and(|hash| self.contains(&hash))
+    }
+}
+
+/// `0x` 付きまたはなしの64桁hexをcontent_hashとして解釈する。
+fn decode_content_hash(entry: &str) -> Option<[u8; 32]> {
+    let hex_str = entry.strip_prefix("0x").unwrap_or(entry);
+    hex::decode(hex_str).ok()?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_denylist() {
+        let a = format!("0x{}", "ab".repeat(32));
+        let b = "cd".repeat(32);
+        let spec = format!("# takedown 2026-01\n{a}, {b}\n\n{a} # 重複\n");
+        let denylist = ContentDenylist::parse(&spec).unwrap();
+        assert_eq!(denylist.len(), 2);
+        assert!(denylist.contains(&[0xab; 32]));
+        assert!(denylist.contains(&[0xcd; 32]));
+        assert!(!denylist.contains(&[0x00; 32]));
+
+        assert!(ContentDenylist::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_denylist_rejects_malformed_hash() {
+        assert!(ContentDenylist::parse("0x1234").is_err());
+        assert!(ContentDenylist::parse(&"zz".repeat(32)).is_err());
+    }
+}
@@ -27,6 +27,7 @@ fn b64() -> base64::engine::GeneralPurpose {
 /// 受信bodyが GatewayAuthWrapper 形式（`gateway_signature` フィールドあり）なら署名を検証し、
 /// `body` フィールドと `resource_limits` を返す。
 /// 直接リクエスト形式の場合は `gateway_pubkey` が `None` のときのみ許可する。
+/// 署名対象は正規化JSON（`title_types::canonical_json`）で比較するため、キーの並び順には依存しない。
 pub fn verify_gateway_auth(
     gateway_pubkey: Option<&Ed25519VerifyingKey>,
     body: &serde_json::Value,
@@ -48,12 +49,13 @@ pub fn verify_gateway_auth(
                 body: wrapper.body.clone(),
                 resource_limits: wrapper.resource_limits.clone(),
             };
-            let sign_bytes = serde_json::to_vec(&sign_target).map_err(|e| {
+            let sign_target = serde_json::to_value(&sign_target).map_err(|e| {
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     format!("署名対象のシリアライズに失敗: {e}"),
                 )
             })?;
+            let sign_bytes = title_types::canonical_json(&sign_target);
 
             // 署名をデコード
             let sig_bytes = b64().decode(&wrapper.gateway_signature).map_err(|e| {
@@ -70,8 +72,14 @@ pub fn verify_gateway_auth(
             })?;
             let signature = ed25519_dalek::Signature::from_bytes(&sig_arr);
 
-            // Ed25519署名を検証
-            title_crypto::ed25519_verify(pubkey, &sign_bytes, &signature).map_err(|_| {
+            // Ed25519署名を検証（Gateway認証ドメイン）
+            title_crypto::ed25519_verify_in_domain(
+                pubkey,
+                title_crypto::SignatureDomain::GatewayAuth,
+                &sign_bytes,
+                &signature,
+            )
+            .map_err(|_| {
                 (
                     StatusCode::FORBIDDEN,
                     "Gateway署名の検証に失敗しました".to_string(),
@@ -96,7 +104,7 @@ pub fn verify_gateway_auth(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ed25519_dalek::{Signer, SigningKey as Ed25519SigningKey};
+    use ed25519_dalek::SigningKey as Ed25519SigningKey;
 
     /// Gateway署名が正しく検証されることを確認
     #[test]
@@ -122,8 +130,13 @@ mod tests {
             body: body.clone(),
             resource_limits: resource_limits.clone(),
         };
-        let sign_bytes = serde_json::to_vec(&sign_target).unwrap();
-        let signature = signing_key.sign(&sign_bytes);
+        let sign_bytes =
+            title_types::canonical_json(&serde_json::to_value(&sign_target).unwrap());
+        let signature = title_crypto::ed25519_sign_in_domain(
+            &signing_key,
+            title_crypto::SignatureDomain::GatewayAuth,
+            &sign_bytes,
+        );
         let sig_b64 = b64().encode(signature.to_bytes());
 
         let wrapper = serde_json::json!({
@@ -157,8 +170,13 @@ mod tests {
             body: body.clone(),
             resource_limits: None,
         };
-        let sign_bytes = serde_json::to_vec(&sign_target).unwrap();
-        let signature = signing_key.sign(&sign_bytes);
+        let sign_bytes =
+            title_types::canonical_json(&serde_json::to_value(&sign_target).unwrap());
+        let signature = title_crypto::ed25519_sign_in_domain(
+            &signing_key,
+            title_crypto::SignatureDomain::GatewayAuth,
+            &sign_bytes,
+        );
         let sig_b64 = b64().encode(signature.to_bytes());
 
         let wrapper = serde_json::json!({
@@ -175,6 +193,73 @@ mod tests {
         assert_eq!(status, StatusCode::FORBIDDEN);
     }
 
+    /// キーの並び順だけが異なる同じbodyが、署名時と異なる順序で届いても検証できることを確認
+    #[test]
+    fn test_verify_body_with_reordered_keys() {
+        let signing_key = Ed25519SigningKey::generate(&mut rand::rngs::OsRng);
+        let verifying_key = Ed25519VerifyingKey::from(&signing_key);
+
+        let signed_body: serde_json::Value = serde_json::from_str(
+            r#"{"processor_ids":["core-c2pa"],"download_url":"http://example.com","depends_on":{"b":"x","a":"y"}}"#,
+        )
+        .unwrap();
+        let sign_target = GatewayAuthSignTarget {
+            method: "POST".to_string(),
+            path: "/verify".to_string(),
+            body: signed_body,
+            resource_limits: None,
+        };
+        let sign_bytes =
+            title_types::canonical_json(&serde_json::to_value(&sign_target).unwrap());
+        let signature = title_crypto::ed25519_sign_in_domain(
+            &signing_key,
+            title_crypto::SignatureDomain::GatewayAuth,
+            &sign_bytes,
+        );
+
+        // ラッパー・body・ネストしたオブジェクトのいずれもキー順を入れ替えて送信する
+        let wrapper: serde_json::Value = serde_json::from_str(&format!(
+            r#"{{"gateway_signature":"{}","body":{{"depends_on":{{"a":"y","b":"x"}},"download_url":"http://example.com","processor_ids":["core-c2pa"]}},"path":"/verify","method":"POST"}}"#,
+            b64().encode(signature.to_bytes()),
+        ))
+        .unwrap();
+
+        let (inner, _) = verify_gateway_auth(Some(&verifying_key), &wrapper).unwrap();
+        assert_eq!(inner["download_url"], "http://example.com");
+    }
+
+    /// signed_jsonドメインで作成した署名がGateway認証として受理されないことを確認
+    #[test]
+    fn test_verify_rejects_signature_from_other_domain() {
+        let signing_key = Ed25519SigningKey::generate(&mut rand::rngs::OsRng);
+        let verifying_key = Ed25519VerifyingKey::from(&signing_key);
+
+        let body = serde_json::json!({"test": "data"});
+        let sign_target = GatewayAuthSignTarget {
+            method: "POST".to_string(),
+            path: "/verify".to_string(),
+            body: body.clone(),
+            resource_limits: None,
+        };
+        let sign_bytes =
+            title_types::canonical_json(&serde_json::to_value(&sign_target).unwrap());
+        let signature = title_crypto::ed25519_sign_in_domain(
+            &signing_key,
+            title_crypto::SignatureDomain::SignedJson,
+            &sign_bytes,
+        );
+
+        let wrapper = serde_json::json!({
+            "method": "POST",
+            "path": "/verify",
+            "body": body,
+            "gateway_signature": b64().encode(signature.to_bytes()),
+        });
+
+        let (status, _) = verify_gateway_auth(Some(&verifying_key), &wrapper).unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
     /// Gateway認証が必須の場合に署名なしリクエストが拒否されることを確認
     #[test]
     fn test_verify_missing_signature_when_required() {
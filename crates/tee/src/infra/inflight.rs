@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # 処理中の検証タスクの管理
+//!
+//! 仕様書 §6.4
+//!
+//! `cancel_token` 付きの /verify は検証処理を独立したtokioタスクとして実行し、
+//! そのAbortHandleとWASM実行の中断ハンドルを保持する。`DELETE /verify/{token}` で該当タスクを
+//! 打ち切ると、実行中のWASMが中断され、タスクが保持するResourcePoolのチケットがDropされて
+//! 予約済みメモリが解放される。
+//!
+//! 登録のキーは `cancel_token` と `download_url` から導出する（[`cancel_key`]）。
+//! トークンだけを知る第三者は、ペイロードのURLを知らない限り他者の検証を取り消せない。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use title_wasm_host::InterruptHandle;
+use tokio::task::AbortHandle;
+
+/// 登録キー（`cancel_token` と `download_url` のSHA-256）。
+pub type CancelKey = [u8; 32];
+
+/// `cancel_token` を検証対象のペイロード（`download_url`）に束縛した登録キーを導出する。
+/// 仕様書 §6.4
+pub fn cancel_key(token: &str, download_url: &str) -> CancelKey {
+    let bound = serde_json::json!([token, download_url]);
+    title_crypto::sha256(&title_types::canonical_json(&bound))
+}
+
+/// 登録された検証タスク。
+#[derive(Debug)]
+struct Entry {
+    /// tokioタスクの打ち切り用ハンドル
+    task: AbortHandle,
+    /// 実行中のWASMの中断用ハンドル（同期実行中のWASMにはタスクの打ち切りが届かない）
+    interrupt: InterruptHandle,
+}
+
+/// 処理中の検証タスクのレジストリ（キー: [`cancel_key`]）。
+/// 仕様書 §6.4
+#[derive(Debug, Default)]
+pub struct InflightVerifies {
+    tasks: Arc<Mutex<HashMap<CancelKey, Entry>>>,
+}
+
+/// 登録の解除を保証するガード。
+///
+/// Drop時にレジストリから登録を削除し、実行中のWASMを中断してタスクを打ち切る。
+/// クライアント切断によりハンドラのFutureが破棄された場合も、検証タスクとWASM実行が残らない
+/// （同期実行中のWASMにはタスクの打ち切りが届かないため、中断も合わせて行う）。
+/// 完了済みタスクへの中断・打ち切りは何もしない。
+#[derive(Debug)]
+pub struct InflightGuard {
+    tasks: Arc<Mutex<HashMap<CancelKey, Entry>>>,
+    key: CancelKey,
+    handle: AbortHandle,
+}
+
+impl InflightVerifies {
+    /// タスクをキーで登録する。同じキーが処理中の場合は `None` を返す。
+    pub fn register(
+        &self,
+        key: CancelKey,
+        handle: AbortHandle,
+        interrupt: InterruptHandle,
+    ) -> Option<InflightGuard> {
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        if tasks.contains_key(&key) {
+            return None;
+        }
+        tasks.insert(
+            key,
+            Entry {
+                task: handle.clone(),
+                interrupt,
+            },
+        );
+        Some(InflightGuard {
+            tasks: Arc::clone(&self.tasks),
+            key,
+            handle,
+        })
+    }
+
+    /// キーに対応するタスクを打ち切り、実行中のWASMを中断する。該当するタスクがなければ `false` を返す。
+    ///
+    /// 登録の削除は打ち切られたタスクを待つハンドラ側（[`InflightGuard`] のDrop）で行う。
+    pub fn cancel(&self, key: &CancelKey) -> bool {
+        let tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        match tasks.get(key) {
+            Some(entry) => {
+                entry.interrupt.interrupt();
+                entry.task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Drop for InflightGuard {
+    fn drop(&mut self) {
+        let entry = self
+            .tasks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.key);
+        if let Some(entry) = entry {
+            entry.interrupt.interrupt();
+        }
+        self.handle.abort();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// テスト
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_register_rejects_duplicate_and_guard_unregisters() {
+        let inflight = InflightVerifies::default();
+        let task = tokio::spawn(std::future::pending::<()>());
+        let key = cancel_key("job", "https://storage.example/a");
+        let interrupt = InterruptHandle::new();
+
+        let guard = inflight
+            .register(key, task.abort_handle(), interrupt.clone())
+            .unwrap();
+        assert!(inflight
+            .register(key, task.abort_handle(), InterruptHandle::new())
+            .is_none());
+        // 同じトークンでもペイロードが異なれば別の検証として扱う
+        assert!(!inflight.cancel(&cancel_key("job", "https://storage.example/b")));
+        assert!(inflight.cancel(&key));
+        assert!(interrupt.is_interrupted());
+
+        drop(guard);
+        assert!(!inflight.cancel(&key));
+        assert!(task.await.unwrap_err().is_cancelled());
+        // 登録解除後は同じキーを再利用できる
+        let task = tokio::spawn(std::future::pending::<()>());
+        let interrupt = InterruptHandle::new();
+        let guard = inflight
+            .register(key, task.abort_handle(), interrupt.clone())
+            .unwrap();
+
+        // クライアント切断ではcancelを経由せずガードだけがDropされる。WASMも中断されること
+        assert!(!interrupt.is_interrupted());
+        drop(guard);
+        assert!(interrupt.is_interrupted());
+        assert!(task.await.unwrap_err().is_cancelled());
+    }
+}
@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Temporary Storageで見つからないペイロードの猶予管理
+//!
+//! 仕様書 §6.4
+//!
+//! アップロード直後のオブジェクトは、ストレージの結果整合性により一時的に404となることがある。
+//! 一方、未アップロード・削除済み・URLの誤りによる恒久的な不在も同じ404として返る。
+//! ダウンロードURLごとに最初に404を観測した時刻を記録し、猶予期間内は再試行可能（425 Too Early）、
+//! 猶予期間を過ぎても見つからない場合は恒久的な不在（404 Not Found）として扱う。
+//!
+//! 記録はURLのSHA-256で保持する。猶予期間の2倍を過ぎた記録は破棄し（その後の404は新たな
+//! 猶予期間を開始する）、件数が上限に達した場合は最も古い記録から破棄する。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 再試行を促す猶予期間のデフォルト値（秒）
+pub const DEFAULT_MISSING_OBJECT_GRACE_SECS: u64 = 30;
+
+/// 記録するダウンロードURLの最大数
+const MAX_TRACKED_OBJECTS: usize = 10_000;
+
+/// 404を観測したダウンロードURLの記録。
+/// 仕様書 §6.4
+#[derive(Debug)]
+pub struct MissingObjects {
+    grace: Duration,
+    first_seen: Mutex<HashMap<[u8; 32], Instant>>,
+}
+
+impl Default for MissingObjects {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(DEFAULT_MISSING_OBJECT_GRACE_SECS))
+    }
+}
+
+impl MissingObjects {
+    /// 猶予期間を指定して構築する。0の場合、404は常に恒久的な不在として扱う。
+    pub fn new(grace: Duration) -> Self {
+        Self {
+            grace,
+            first_seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `download_url` で404を観測したことを記録し、最初の観測から猶予期間内なら `true` を返す。
+    pub fn within_grace(&self, download_url: &str) -> bool {
+        self.within_grace_at(download_url, Instant::now())
+    }
+
+    fn within_grace_at(&self, download_url: &str, now: Instant) -> bool {
+        let key = title_crypto::sha256(download_url.as_bytes());
+        let retention = self.grace.saturating_mul(2);
+        let mut first_seen = self.first_seen.lock().unwrap_or_else(|e| e.into_inner());
+        first_seen.retain(|_, seen| now.saturating_duration_since(*seen) < retention);
+        if !first_seen.contains_key(&key) && first_seen.len() >= MAX_TRACKED_OBJECTS {
+            let oldest = first_seen
+                .iter()
+                .min_by_key(|(_, seen)| **seen)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                first_seen.remove(&oldest);
+            }
+        }
+        let seen = *first_seen.entry(key).or_insert(now);
+        now.saturating_duration_since(seen) < self.grace
+    }
+}
+
+// ---------------------------------------------------------------------------
+// テスト
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_within_grace_until_window_elapses() {
+        let missing = MissingObjects::new(Duration::from_secs(30));
+        let start = Instant::now();
+        let url = "http://storage/payload";
+
+        assert!(missing.within_grace_at(url, start));
+        assert!(missing.within_grace_at(url, start + Duration::from_secs(29)));
+        // 最初の観測から猶予期間を過ぎると恒久的な不在
+        assert!(!missing.within_grace_at(url, start + Duration::from_secs(30)));
+        // 別のURLは独立に猶予期間を開始する
+        assert!(missing.within_grace_at("http://storage/other", start + Duration::from_secs(30)));
+        // 猶予期間の2倍を過ぎた記録は破棄され、新たな猶予期間を開始する
+        assert!(missing.within_grace_at(url, start + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn test_zero_grace_is_always_permanent() {
+        let missing = MissingObjects::new(Duration::ZERO);
+        assert!(!missing.within_grace("http://storage/payload"));
+        assert!(!missing.within_grace("http://storage/payload"));
+    }
+}
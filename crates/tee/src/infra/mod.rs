@@ -5,10 +5,22 @@
 //! 仕様書 §6.4
 //!
 //! TEEの外部通信・認証・セキュリティに関するモジュール。
+//! - `admission`: 優先度付きの検証受付
+//! - `denylist`: 処理を拒否するcontent_hashの一覧
 //! - `gateway_auth`: Gateway認証検証
+//! - `inflight`: 処理中の検証タスクの管理（取り消し用）
+//! - `missing_objects`: Temporary Storageで見つからないペイロードの猶予管理
 //! - `proxy_client`: TEE外部通信プロキシクライアント
 //! - `security`: DoS対策・リソース制限
+//! - `sign_rate`: 署名操作のレート制限
+//! - `wasm_pool`: WASMインスタンスプールのスロット管理
 
+pub mod admission;
+pub mod denylist;
 pub mod gateway_auth;
+pub mod inflight;
+pub mod missing_objects;
 pub mod proxy_client;
 pub mod security;
+pub mod sign_rate;
+pub mod wasm_pool;
@@ -20,8 +20,40 @@
 //! ## 接続モード
 //! - 本番: PROXY_ADDR(TCP) → socat → vsock → ホスト側proxy
 //! - 開発: PROXY_ADDR="direct" で直接HTTP
+//!
+//! ## 共通クライアント
+//! [`ProxyHttpClient`] はリトライ・タイムアウト・レスポンスサイズ上限を設定可能な
+//! GET/POSTクライアントで、WASMバイナリの取得とSolana RPC呼び出しで共有する。
+//! 非2xxステータス・タイムアウト・サイズ超過は [`ProxyClientError`] に統一して返す。
+//! ペイロードの取得（[`super::security::proxy_get_secured`]）はResourcePoolの漸進的予約を
+//! 伴うため独自の読み取りを行うが、リクエストの送信は [`write_request`] を共有する。
+//!
+//! ## 起動時の疎通確認
+//! [`probe_proxy`] はヘルスチェック用URLへのGETをプロキシ経由で送信し、
+//! プロキシの設定ミス（未起動・ポート違い）を最初の/verify前に検出する。
+
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// クライアント指定のURLを外部向けアドレスに限定して取得するメソッド名。
+/// プロキシが名前解決後のアドレスを検査し、リダイレクトに追従しない（title-proxyと同じ値）。
+pub const EXTERNAL_GET_METHOD: &str = "GET_EXTERNAL";
+
+/// 起動時の疎通確認のタイムアウト
+pub const PROXY_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
 
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+/// リトライ回数のデフォルト（初回を含まない）
+pub const DEFAULT_PROXY_MAX_RETRIES: u32 = 2;
+
+/// 1回のリクエスト（接続〜レスポンス受信）のタイムアウトのデフォルト
+pub const DEFAULT_PROXY_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// レスポンスボディの上限のデフォルト: 32MB
+pub const DEFAULT_PROXY_MAX_RESPONSE_BYTES: u64 = 32 * 1024 * 1024;
+
+/// リトライ間隔の初期値（試行ごとに倍増する）
+const DEFAULT_PROXY_RETRY_DELAY: Duration = Duration::from_millis(500);
 
 /// プロキシ経由のHTTPレスポンス。
 #[derive(Debug)]
@@ -32,120 +64,377 @@ pub struct ProxyResponse {
     pub body: Vec<u8>,
 }
 
-/// Direct HTTPモード: プロキシプロトコルを経由せず直接HTTPリクエストを送信する。
-/// Docker Compose環境（ローカル開発）ではTEEにネットワーク制限がないため、
-/// PROXY_ADDR=direct で直接HTTP通信を行う。
-async fn direct_http_request(
-    method: &str,
-    url: &str,
-    body: &[u8],
-) -> Result<ProxyResponse, std::io::Error> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(120))
-        .build()
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-
-    let result = match method {
-        "GET" => client.get(url).send().await,
-        "POST" => client
-            .post(url)
-            .header("Content-Type", "application/json")
-            .body(body.to_vec())
-            .send()
-            .await,
-        other => {
-            return Ok(ProxyResponse {
-                status: 400,
-                body: format!("Unsupported method: {other}").into_bytes(),
-            });
-        }
-    };
-
-    match result {
-        Ok(resp) => {
-            let status = resp.status().as_u16() as u32;
-            let resp_body = resp
-                .bytes()
-                .await
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?
-                .to_vec();
-            Ok(ProxyResponse {
-                status,
-                body: resp_body,
-            })
+/// [`ProxyHttpClient`] のエラー。
+#[derive(Debug, thiserror::Error)]
+pub enum ProxyClientError {
+    /// 接続・送受信の失敗
+    #[error("IOエラー: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// タイムアウト内に応答が完了しなかった
+    #[error("{}秒以内に応答がありません", .0.as_secs())]
+    Timeout(Duration),
+
+    /// レスポンスボディが上限を超えた
+    #[error("レスポンスサイズが上限を超えています: {size} bytes (上限: {limit} bytes)")]
+    ResponseTooLarge { size: u64, limit: u64 },
+
+    /// 転送先が2xx以外のステータスを返した
+    #[error("HTTPステータス {0}")]
+    Status(u32),
+}
+
+impl ProxyClientError {
+    /// 再試行で解消し得るエラーかどうか。
+    ///
+    /// 接続・送受信の失敗、タイムアウト、5xx・429を一時的な障害とみなす。
+    /// サイズ超過と4xxは再試行しても結果が変わらないため対象外とする。
+    fn is_retryable(&self) -> bool {
+        match self {
+            ProxyClientError::Io(_) | ProxyClientError::Timeout(_) => true,
+            ProxyClientError::Status(status) => *status >= 500 || *status == 429,
+            ProxyClientError::ResponseTooLarge { .. } => false,
         }
-        Err(e) => Err(std::io::Error::new(std::io::ErrorKind::Other, e)),
     }
 }
 
-/// length-prefixedプロトコルでプロキシにHTTPリクエストを送信する。
+/// プロキシ経由のHTTPクライアント。
 /// 仕様書 §6.4
 ///
 /// `proxy_addr` が `"direct"` の場合、プロキシプロトコルを経由せず
 /// 直接HTTPリクエストを送信する（Docker Compose / ローカル開発用）。
-///
 /// それ以外の場合はTCPアドレス（例: "127.0.0.1:8000"）として扱い、
 /// length-prefixedプロトコルでプロキシに接続する。
 /// 本番環境ではTEE VM内のsocatがこのTCPポートをvsockにブリッジする。
-async fn proxy_request(
-    proxy_addr: &str,
+///
+/// 再試行は冪等なリクエスト（GET、読み取り専用のRPC）を前提とする。
+#[derive(Debug, Clone)]
+pub struct ProxyHttpClient {
+    proxy_addr: String,
+    max_retries: u32,
+    timeout: Duration,
+    max_response_bytes: u64,
+    retry_delay: Duration,
+}
+
+impl ProxyHttpClient {
+    /// デフォルト設定のクライアントを作成する。
+    pub fn new(proxy_addr: impl Into<String>) -> Self {
+        Self {
+            proxy_addr: proxy_addr.into(),
+            max_retries: DEFAULT_PROXY_MAX_RETRIES,
+            timeout: DEFAULT_PROXY_REQUEST_TIMEOUT,
+            max_response_bytes: DEFAULT_PROXY_MAX_RESPONSE_BYTES,
+            retry_delay: DEFAULT_PROXY_RETRY_DELAY,
+        }
+    }
+
+    /// リトライ回数（初回を含まない）を設定する。
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// 1回のリクエストのタイムアウトを設定する。
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// レスポンスボディの上限を設定する。
+    pub fn with_max_response_bytes(mut self, max_response_bytes: u64) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    /// リトライ間隔の初期値を設定する。
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// HTTP GETリクエストを送信する。
+    /// 仕様書 §6.4
+    pub async fn get(&self, url: &str) -> Result<ProxyResponse, ProxyClientError> {
+        self.request("GET", url, &[]).await
+    }
+
+    /// HTTP POSTリクエスト（JSONボディ）を送信する。
+    /// 仕様書 §6.4
+    pub async fn post(&self, url: &str, body: &[u8]) -> Result<ProxyResponse, ProxyClientError> {
+        self.request("POST", url, body).await
+    }
+
+    /// リクエストを送信し、一時的な障害の場合は指数バックオフで再試行する。
+    async fn request(
+        &self,
+        method: &str,
+        url: &str,
+        body: &[u8],
+    ) -> Result<ProxyResponse, ProxyClientError> {
+        let mut attempt = 0;
+        loop {
+            let result = match tokio::time::timeout(self.timeout, self.send(method, url, body)).await {
+                Ok(Ok(response)) if (200..300).contains(&response.status) => Ok(response),
+                Ok(Ok(response)) => Err(ProxyClientError::Status(response.status)),
+                Ok(Err(e)) => Err(e),
+                Err(_) => Err(ProxyClientError::Timeout(self.timeout)),
+            };
+            match result {
+                Err(e) if e.is_retryable() && attempt < self.max_retries => {
+                    let delay = self.retry_delay.saturating_mul(1 << attempt.min(16));
+                    tracing::debug!(error = %e, url, attempt, "プロキシ経由のリクエストを再試行します");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                other => return other,
+            }
+        }
+    }
+
+    /// 1回分のリクエストを送信する。
+    async fn send(
+        &self,
+        method: &str,
+        url: &str,
+        body: &[u8],
+    ) -> Result<ProxyResponse, ProxyClientError> {
+        // Direct HTTPモード: プロキシを経由せず直接リクエスト
+        if self.proxy_addr == "direct" {
+            return self.send_direct(method, url, body).await;
+        }
+
+        // TEE VM内ではsocatがTCP→vsockをブリッジするため、常にTCP接続を使用する
+        let mut stream = tokio::net::TcpStream::connect(&self.proxy_addr).await?;
+        write_request(&mut stream, method, url, body).await?;
+
+        // response: status
+        let mut buf4 = [0u8; 4];
+        stream.read_exact(&mut buf4).await?;
+        let status = u32::from_be_bytes(buf4);
+
+        // response: body（宣言サイズを確認してから確保する）
+        stream.read_exact(&mut buf4).await?;
+        let body_len = u32::from_be_bytes(buf4) as u64;
+        if body_len > self.max_response_bytes {
+            return Err(ProxyClientError::ResponseTooLarge {
+                size: body_len,
+                limit: self.max_response_bytes,
+            });
+        }
+        let mut resp_body = vec![0u8; body_len as usize];
+        if body_len > 0 {
+            stream.read_exact(&mut resp_body).await?;
+        }
+
+        Ok(ProxyResponse {
+            status,
+            body: resp_body,
+        })
+    }
+
+    /// Direct HTTPモード: プロキシプロトコルを経由せず直接HTTPリクエストを送信する。
+    /// Docker Compose環境（ローカル開発）ではTEEにネットワーク制限がないため、
+    /// PROXY_ADDR=direct で直接HTTP通信を行う。
+    async fn send_direct(
+        &self,
+        method: &str,
+        url: &str,
+        body: &[u8],
+    ) -> Result<ProxyResponse, ProxyClientError> {
+        let client = reqwest::Client::new();
+        let request = match method {
+            "POST" => client
+                .post(url)
+                .header("Content-Type", "application/json")
+                .body(body.to_vec()),
+            _ => client.get(url),
+        };
+        let mut resp = request.send().await.map_err(std::io::Error::other)?;
+        let status = resp.status().as_u16() as u32;
+
+        // 受信したチャンクごとにサイズを検査し、上限を超えた時点で打ち切る
+        let mut resp_body = Vec::new();
+        while let Some(chunk) = resp.chunk().await.map_err(std::io::Error::other)? {
+            let received = (resp_body.len() + chunk.len()) as u64;
+            if received > self.max_response_bytes {
+                return Err(ProxyClientError::ResponseTooLarge {
+                    size: received,
+                    limit: self.max_response_bytes,
+                });
+            }
+            resp_body.extend_from_slice(&chunk);
+        }
+
+        Ok(ProxyResponse {
+            status,
+            body: resp_body,
+        })
+    }
+}
+
+/// length-prefixedプロトコルのリクエストを送信する。
+/// 仕様書 §6.4
+///
+/// `[4B: method_len][method][4B: url_len][url][4B: body_len][body]`
+pub(crate) async fn write_request<W: AsyncWrite + Unpin>(
+    stream: &mut W,
     method: &str,
     url: &str,
     body: &[u8],
-) -> Result<ProxyResponse, std::io::Error> {
-    // Direct HTTPモード: プロキシを経由せず直接リクエスト
-    if proxy_addr == "direct" {
-        return direct_http_request(method, url, body).await;
-    }
-
-    // TEE VM内ではsocatがTCP→vsockをブリッジするため、常にTCP接続を使用する
-    let mut stream = tokio::net::TcpStream::connect(proxy_addr).await?;
-
-    // method
-    let method_bytes = method.as_bytes();
-    stream
-        .write_all(&(method_bytes.len() as u32).to_be_bytes())
-        .await?;
-    stream.write_all(method_bytes).await?;
-
-    // url
-    let url_bytes = url.as_bytes();
-    stream
-        .write_all(&(url_bytes.len() as u32).to_be_bytes())
-        .await?;
-    stream.write_all(url_bytes).await?;
-
-    // body
-    stream
-        .write_all(&(body.len() as u32).to_be_bytes())
-        .await?;
-    if !body.is_empty() {
-        stream.write_all(body).await?;
-    }
-    stream.flush().await?;
-
-    // response: status
-    let mut buf4 = [0u8; 4];
-    stream.read_exact(&mut buf4).await?;
-    let status = u32::from_be_bytes(buf4);
-
-    // response: body
-    stream.read_exact(&mut buf4).await?;
-    let body_len = u32::from_be_bytes(buf4) as usize;
-    let mut resp_body = vec![0u8; body_len];
-    if body_len > 0 {
-        stream.read_exact(&mut resp_body).await?;
-    }
-
-    Ok(ProxyResponse {
-        status,
-        body: resp_body,
-    })
+) -> Result<(), std::io::Error> {
+    for field in [method.as_bytes(), url.as_bytes(), body] {
+        stream.write_all(&(field.len() as u32).to_be_bytes()).await?;
+        if !field.is_empty() {
+            stream.write_all(field).await?;
+        }
+    }
+    stream.flush().await
 }
 
-/// プロキシ経由でHTTP GETリクエストを送信する。
+/// プロキシ疎通確認の結果。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyProbe {
+    /// プロキシが応答した（転送先が返したHTTPステータス）
+    Reachable(u32),
+    /// プロキシに到達できない、またはタイムアウトした
+    Unreachable(String),
+}
+
+/// プロキシ経由で `health_url` にGETを送信し、プロキシの疎通を確認する。
 /// 仕様書 §6.4
-pub async fn proxy_get(proxy_addr: &str, url: &str) -> Result<ProxyResponse, std::io::Error> {
-    proxy_request(proxy_addr, "GET", url, &[]).await
+///
+/// プロキシが応答すれば転送先のステータスに関わらず `Reachable` とする
+/// （転送先の障害はプロキシ自体の設定ミスとは区別する）。
+pub async fn probe_proxy(proxy_addr: &str, health_url: &str, timeout: Duration) -> ProxyProbe {
+    let client = ProxyHttpClient::new(proxy_addr)
+        .with_max_retries(0)
+        .with_timeout(timeout);
+    match client.get(health_url).await {
+        Ok(response) => ProxyProbe::Reachable(response.status),
+        Err(ProxyClientError::Status(status)) => ProxyProbe::Reachable(status),
+        Err(e) => ProxyProbe::Unreachable(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endpoints::test_helpers::{start_inline_proxy, start_mock_storage};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_probe_proxy_unbound_address() {
+        // 一度bindしたポートを解放し、未使用のアドレスを得る
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let probe = probe_proxy(&addr.to_string(), "http://127.0.0.1:1/health", PROXY_PROBE_TIMEOUT).await;
+        assert!(matches!(probe, ProxyProbe::Unreachable(_)), "{probe:?}");
+    }
+
+    #[tokio::test]
+    async fn test_probe_proxy_reachable() {
+        let storage_port = start_mock_storage("/health", b"ok".to_vec()).await;
+        let proxy_port = start_inline_proxy().await;
+
+        let probe = probe_proxy(
+            &format!("127.0.0.1:{proxy_port}"),
+            &format!("http://127.0.0.1:{storage_port}/health"),
+            PROXY_PROBE_TIMEOUT,
+        )
+        .await;
+        assert_eq!(probe, ProxyProbe::Reachable(200));
+    }
+
+    /// 先頭 `failures` 回は503を返し、以降は200と "ok" を返すモックサーバーを起動する。
+    /// 受信したリクエスト数を返すカウンタを併せて返す。
+    async fn start_flaky_storage(failures: usize) -> (u16, Arc<AtomicUsize>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&count);
+        let app = axum::Router::new().route(
+            "/data",
+            axum::routing::get(move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if n < failures {
+                        (axum::http::StatusCode::SERVICE_UNAVAILABLE, "busy")
+                    } else {
+                        (axum::http::StatusCode::OK, "ok")
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (port, count)
+    }
+
+    #[tokio::test]
+    async fn test_proxy_http_client_get_success() {
+        let storage_port = start_mock_storage("/data", b"hello".to_vec()).await;
+        let proxy_port = start_inline_proxy().await;
+
+        let client = ProxyHttpClient::new(format!("127.0.0.1:{proxy_port}"));
+        let response = client
+            .get(&format!("http://127.0.0.1:{storage_port}/data"))
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"hello");
+    }
+
+    /// 上限を超えるレスポンスは再試行せずに拒否することを確認
+    #[tokio::test]
+    async fn test_proxy_http_client_size_limit() {
+        let storage_port = start_mock_storage("/data", vec![0u8; 2048]).await;
+        let proxy_port = start_inline_proxy().await;
+
+        let client = ProxyHttpClient::new(format!("127.0.0.1:{proxy_port}"))
+            .with_max_response_bytes(1024)
+            .with_retry_delay(Duration::from_millis(1));
+        let err = client
+            .get(&format!("http://127.0.0.1:{storage_port}/data"))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ProxyClientError::ResponseTooLarge { size: 2048, limit: 1024 }),
+            "{err:?}"
+        );
+    }
+
+    /// 一時的な5xxはリトライ回数の範囲で再試行し、使い切ると最後のステータスを返すことを確認
+    #[tokio::test]
+    async fn test_proxy_http_client_retries_transient_status() {
+        let proxy_port = start_inline_proxy().await;
+        let proxy_addr = format!("127.0.0.1:{proxy_port}");
+
+        let (storage_port, count) = start_flaky_storage(2).await;
+        let client = ProxyHttpClient::new(proxy_addr.clone())
+            .with_max_retries(2)
+            .with_retry_delay(Duration::from_millis(1));
+        let response = client
+            .get(&format!("http://127.0.0.1:{storage_port}/data"))
+            .await
+            .unwrap();
+        assert_eq!(response.body, b"ok");
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+
+        let (storage_port, count) = start_flaky_storage(2).await;
+        let client = ProxyHttpClient::new(proxy_addr)
+            .with_max_retries(1)
+            .with_retry_delay(Duration::from_millis(1));
+        let err = client
+            .get(&format!("http://127.0.0.1:{storage_port}/data"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProxyClientError::Status(503)), "{err:?}");
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
 }
 
@@ -19,6 +19,7 @@ use title_types::ResourceLimits;
 use title_wasm_host::{ResourcePool, Ticket};
 
 use super::proxy_client::ProxyResponse;
+use crate::error::TeeError;
 
 // ---------------------------------------------------------------------------
 // デフォルトリソース制限 (仕様書 §6.4 処理上限の管理)
@@ -53,6 +54,29 @@ pub const CHUNK_SIZE: usize = 64 * 1024;
 /// 仕様書 §6.4 /signフェーズでの防御
 pub const MAX_SIGNED_JSON_SIZE: u64 = 1024 * 1024;
 
+/// /signで並行処理するsigned_jsonの最大数
+pub const DEFAULT_SIGN_CONCURRENCY: usize = 4;
+
+/// /signでsigned_json 1件の取得に許容する最大時間（秒）。
+/// 仕様書 §6.4 /signフェーズでの防御
+pub const DEFAULT_SIGN_FETCH_TIMEOUT_SEC: u64 = 10;
+
+/// Extension実行1回あたりのFuel制限（10億命令）。
+/// 仕様書 §7.1
+pub const EXTENSION_FUEL_LIMIT: u64 = 1_000_000_000;
+
+/// Extension実行1回あたりのWASM線形メモリ上限（64MB）。
+/// 仕様書 §7.1
+pub const EXTENSION_MEMORY_LIMIT_BYTES: usize = 64 * 1024 * 1024;
+
+/// Extension補助入力（`extension_inputs` の各値）の最大サイズ（1MB）。
+/// 仕様書 §6.4, §7.1
+pub const DEFAULT_MAX_EXTENSION_INPUT_BYTES: usize = 1024 * 1024;
+
+/// Extension結果（WASM出力）の最大サイズ（64KB）。
+/// 仕様書 §5.1 Step 5
+pub const DEFAULT_MAX_EXTENSION_RESULT_BYTES: usize = 64 * 1024;
+
 // ---------------------------------------------------------------------------
 // 解決済みリソース制限
 // ---------------------------------------------------------------------------
@@ -69,10 +93,12 @@ pub struct ResolvedLimits {
     pub c2pa_max_graph_size: usize,
 }
 
-/// Gateway提供のresource_limitsをデフォルト値で補完する。
+/// Gateway提供のresource_limitsをデフォルト値で補完し、値の妥当性を検証する。
 /// 仕様書 §6.4
-pub fn resolve_limits(rl: Option<&ResourceLimits>) -> ResolvedLimits {
-    match rl {
+///
+/// 補完後の値が不正な組み合わせ（[`ResolvedLimits::validate`]）の場合は `BadRequest` を返す。
+pub fn resolve_limits(rl: Option<&ResourceLimits>) -> Result<ResolvedLimits, TeeError> {
+    let limits = match rl {
         Some(rl) => ResolvedLimits {
             max_single_content_bytes: rl
                 .max_single_content_bytes
@@ -106,6 +132,63 @@ pub fn resolve_limits(rl: Option<&ResourceLimits>) -> ResolvedLimits {
             chunk_read_timeout_sec: DEFAULT_CHUNK_READ_TIMEOUT_SEC,
             c2pa_max_graph_size: DEFAULT_C2PA_MAX_GRAPH_SIZE as usize,
         },
+    };
+    limits.validate()?;
+    Ok(limits)
+}
+
+impl ResolvedLimits {
+    /// リソース制限の値が処理可能な範囲にあるかを検証する。
+    /// 仕様書 §6.4
+    ///
+    /// - サイズ・速度・タイムアウト・グラフ上限は0を許さない（0の転送速度は動的タイムアウトの除数となる）
+    /// - 単体コンテンツの上限は同時処理可能な合計データ量を超えない
+    /// - 固定オーバーヘッド時間・チャンク読み取りタイムアウトはグローバルタイムアウトを超えない
+    pub fn validate(&self) -> Result<(), TeeError> {
+        let non_zero = [
+            ("max_single_content_bytes", self.max_single_content_bytes),
+            ("max_concurrent_bytes", self.max_concurrent_bytes),
+            ("min_upload_speed_bytes", self.min_upload_speed_bytes),
+            ("max_global_timeout_sec", self.max_global_timeout_sec),
+            ("chunk_read_timeout_sec", self.chunk_read_timeout_sec),
+            ("c2pa_max_graph_size", self.c2pa_max_graph_size as u64),
+        ];
+        if let Some((name, _)) = non_zero.iter().find(|(_, value)| *value == 0) {
+            return Err(TeeError::BadRequest(format!(
+                "resource_limits.{name}には1以上の値を指定してください"
+            )));
+        }
+        if self.max_single_content_bytes > self.max_concurrent_bytes {
+            return Err(TeeError::BadRequest(format!(
+                "resource_limits.max_single_content_bytes（{}）がmax_concurrent_bytes（{}）を超えています",
+                self.max_single_content_bytes, self.max_concurrent_bytes
+            )));
+        }
+        for (name, value) in [
+            ("base_processing_time_sec", self.base_processing_time_sec),
+            ("chunk_read_timeout_sec", self.chunk_read_timeout_sec),
+        ] {
+            if value > self.max_global_timeout_sec {
+                return Err(TeeError::BadRequest(format!(
+                    "resource_limits.{name}（{value}秒）がmax_global_timeout_sec（{}秒）を超えています",
+                    self.max_global_timeout_sec
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// クライアント指定の来歴グラフ上限を適用した実効上限を返す。
+    /// 仕様書 §6.4
+    ///
+    /// `min(ノード上限, クライアント指定)` とし、クライアントが上限を引き上げることはできない。
+    pub fn effective_max_graph_size(&self, client_max_graph_size: Option<u64>) -> usize {
+        match client_max_graph_size {
+            Some(client) => self
+                .c2pa_max_graph_size
+                .min(usize::try_from(client).unwrap_or(usize::MAX)),
+            None => self.c2pa_max_graph_size,
+        }
     }
 }
 
@@ -168,6 +251,43 @@ pub async fn proxy_get_secured(
     max_size_bytes: u64,
     chunk_timeout: Duration,
     pool: &Arc<ResourcePool>,
+) -> Result<(ProxyResponse, Ticket), SecurityError> {
+    proxy_fetch_secured(proxy_addr, "GET", url, max_size_bytes, chunk_timeout, pool).await
+}
+
+/// 外部向けアドレスに限定したセキュア化されたプロキシGETリクエスト。
+/// 仕様書 §6.4
+///
+/// クライアント指定のURL（Extension補助入力の `ref_url`）の取得に用いる。
+/// プロキシは名前解決後の全アドレスが外部向けであることを確認してから接続し、
+/// リダイレクトに追従しない（3xxは非200として [`SecurityError::ProxyError`] になる）。
+/// 防御は [`proxy_get_secured`] と同じ。Direct HTTPモードではアドレス検査を行わない（ローカル開発用）。
+pub async fn proxy_get_external_secured(
+    proxy_addr: &str,
+    url: &str,
+    max_size_bytes: u64,
+    chunk_timeout: Duration,
+    pool: &Arc<ResourcePool>,
+) -> Result<(ProxyResponse, Ticket), SecurityError> {
+    proxy_fetch_secured(
+        proxy_addr,
+        super::proxy_client::EXTERNAL_GET_METHOD,
+        url,
+        max_size_bytes,
+        chunk_timeout,
+        pool,
+    )
+    .await
+}
+
+/// 指定メソッドでプロキシにGETを送信し、三層防御を適用してレスポンスを読み取る。
+async fn proxy_fetch_secured(
+    proxy_addr: &str,
+    method: &str,
+    url: &str,
+    max_size_bytes: u64,
+    chunk_timeout: Duration,
+    pool: &Arc<ResourcePool>,
 ) -> Result<(ProxyResponse, Ticket), SecurityError> {
     // Direct HTTPモード: プロキシプロトコルを経由せず直接HTTPリクエスト
     if proxy_addr == "direct" {
@@ -178,24 +298,7 @@ pub async fn proxy_get_secured(
     let mut stream = tokio::net::TcpStream::connect(proxy_addr).await?;
 
     // GETリクエスト送信
-    {
-        use tokio::io::AsyncWriteExt;
-        let method = b"GET";
-        stream
-            .write_all(&(method.len() as u32).to_be_bytes())
-            .await?;
-        stream.write_all(method).await?;
-
-        let url_bytes = url.as_bytes();
-        stream
-            .write_all(&(url_bytes.len() as u32).to_be_bytes())
-            .await?;
-        stream.write_all(url_bytes).await?;
-
-        // 空のbody
-        stream.write_all(&0u32.to_be_bytes()).await?;
-        stream.flush().await?;
-    }
+    super::proxy_client::write_request(&mut stream, method, url, &[]).await?;
 
     // レスポンスステータス読み取り
     let mut buf4 = [0u8; 4];
@@ -271,6 +374,9 @@ pub async fn proxy_get_secured(
 /// Direct HTTPモードのセキュア化されたGETリクエスト。
 /// PROXY_ADDR=direct の場合に使用。reqwestで直接取得しつつ
 /// サイズ制限とResourcePool予約を適用する。
+///
+/// Content-Lengthがない（chunked転送等の）レスポンスでも、受信しながらサイズを検査し、
+/// 上限を超えた時点で読み取りを打ち切る。
 async fn proxy_get_secured_direct(
     url: &str,
     max_size_bytes: u64,
@@ -279,13 +385,13 @@ async fn proxy_get_secured_direct(
     let client = reqwest::Client::builder()
         .timeout(Duration::from_secs(120))
         .build()
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        .map_err(std::io::Error::other)?;
 
-    let resp = client
+    let mut resp = client
         .get(url)
         .send()
         .await
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        .map_err(std::io::Error::other)?;
 
     let status = resp.status().as_u16() as u32;
     if status != 200 {
@@ -302,28 +408,23 @@ async fn proxy_get_secured_direct(
         }
     }
 
-    let body = resp
-        .bytes()
-        .await
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?
-        .to_vec();
-
-    // サイズ制限の最終チェック
-    if body.len() as u64 > max_size_bytes {
-        return Err(SecurityError::PayloadTooLarge {
-            size: body.len() as u64,
-            limit: max_size_bytes,
-        });
+    // 受信したチャンクごとにサイズ検査と漸進的予約を行う
+    let mut body = Vec::new();
+    let ticket = pool.ticket();
+    while let Some(chunk) = resp.chunk().await.map_err(std::io::Error::other)? {
+        let received = (body.len() + chunk.len()) as u64;
+        if received > max_size_bytes {
+            return Err(SecurityError::PayloadTooLarge {
+                size: received,
+                limit: max_size_bytes,
+            });
+        }
+        if !ticket.extend(chunk.len()) {
+            return Err(SecurityError::MemoryLimitExceeded);
+        }
+        body.extend_from_slice(&chunk);
     }
 
-    // ResourcePool で予約（Ticket 発行）— body 分のメモリを追跡
-    let ticket = if !body.is_empty() {
-        pool.acquire(body.len())
-            .ok_or(SecurityError::MemoryLimitExceeded)?
-    } else {
-        pool.ticket()
-    };
-
     Ok((ProxyResponse { status, body }, ticket))
 }
 
@@ -333,7 +434,7 @@ mod tests {
 
     #[test]
     fn test_resolve_limits_default() {
-        let limits = resolve_limits(None);
+        let limits = resolve_limits(None).unwrap();
         assert_eq!(limits.max_single_content_bytes, DEFAULT_MAX_SINGLE_CONTENT_BYTES);
         assert_eq!(limits.chunk_read_timeout_sec, DEFAULT_CHUNK_READ_TIMEOUT_SEC);
         assert_eq!(limits.c2pa_max_graph_size, DEFAULT_C2PA_MAX_GRAPH_SIZE as usize);
@@ -350,7 +451,7 @@ mod tests {
             chunk_read_timeout_sec: Some(5),
             c2pa_max_graph_size: Some(500),
         };
-        let limits = resolve_limits(Some(&rl));
+        let limits = resolve_limits(Some(&rl)).unwrap();
         assert_eq!(limits.max_single_content_bytes, 1024);
         assert_eq!(limits.max_concurrent_bytes, 2048);
         assert_eq!(limits.min_upload_speed_bytes, DEFAULT_MIN_UPLOAD_SPEED_BYTES);
@@ -359,9 +460,18 @@ mod tests {
         assert_eq!(limits.c2pa_max_graph_size, 500);
     }
 
+    #[test]
+    fn test_effective_max_graph_size_never_exceeds_node_limit() {
+        let limits = resolve_limits(None).unwrap();
+        let node_limit = DEFAULT_C2PA_MAX_GRAPH_SIZE as usize;
+        assert_eq!(limits.effective_max_graph_size(None), node_limit);
+        assert_eq!(limits.effective_max_graph_size(Some(5)), 5);
+        assert_eq!(limits.effective_max_graph_size(Some(u64::MAX)), node_limit);
+    }
+
     #[test]
     fn test_compute_dynamic_timeout() {
-        let limits = resolve_limits(None);
+        let limits = resolve_limits(None).unwrap();
         // 0バイト: BaseTime = 30秒
         let t0 = compute_dynamic_timeout(&limits, 0);
         assert_eq!(t0, Duration::from_secs(30));
@@ -386,7 +496,7 @@ mod tests {
             chunk_read_timeout_sec: None,
             c2pa_max_graph_size: None,
         };
-        let limits = resolve_limits(Some(&rl));
+        let limits = resolve_limits(Some(&rl)).unwrap();
 
         // 50MB at 512KB/s: 10 + 100 = 110秒
         let t = compute_dynamic_timeout(&limits, 50 * 1024 * 1024);
@@ -397,6 +507,70 @@ mod tests {
         assert_eq!(t2, Duration::from_secs(120));
     }
 
+    /// すべての項目をデフォルト値に委ねるresource_limits
+    fn no_overrides() -> ResourceLimits {
+        ResourceLimits {
+            max_single_content_bytes: None,
+            max_concurrent_bytes: None,
+            min_upload_speed_bytes: None,
+            base_processing_time_sec: None,
+            max_global_timeout_sec: None,
+            chunk_read_timeout_sec: None,
+            c2pa_max_graph_size: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_limits_rejects_zero_upload_speed() {
+        let rl = ResourceLimits {
+            min_upload_speed_bytes: Some(0),
+            ..no_overrides()
+        };
+        match resolve_limits(Some(&rl)) {
+            Err(TeeError::BadRequest(msg)) => assert!(msg.contains("min_upload_speed_bytes")),
+            Err(e) => panic!("BadRequestであるべき: {e}"),
+            Ok(_) => panic!("転送速度0は拒否されるべき"),
+        }
+
+        // 検証を経ずに構築された値でも、動的タイムアウトの計算はゼロ除算しない
+        let limits = ResolvedLimits {
+            min_upload_speed_bytes: 0,
+            ..resolve_limits(None).unwrap()
+        };
+        assert_eq!(
+            compute_dynamic_timeout(&limits, 10 * 1024 * 1024),
+            Duration::from_secs(DEFAULT_MAX_GLOBAL_TIMEOUT_SEC)
+        );
+    }
+
+    #[test]
+    fn test_resolve_limits_rejects_inconsistent_combinations() {
+        let zero_size = ResourceLimits {
+            max_single_content_bytes: Some(0),
+            ..no_overrides()
+        };
+        let single_over_concurrent = ResourceLimits {
+            max_single_content_bytes: Some(4096),
+            max_concurrent_bytes: Some(1024),
+            ..no_overrides()
+        };
+        let base_over_global = ResourceLimits {
+            base_processing_time_sec: Some(120),
+            max_global_timeout_sec: Some(60),
+            ..no_overrides()
+        };
+        let chunk_over_global = ResourceLimits {
+            max_global_timeout_sec: Some(10),
+            ..no_overrides()
+        };
+        for rl in [zero_size, single_over_concurrent, base_over_global, chunk_over_global] {
+            assert!(
+                matches!(resolve_limits(Some(&rl)), Err(TeeError::BadRequest(_))),
+                "{rl:?}"
+            );
+        }
+    }
+
     #[tokio::test]
     async fn test_proxy_get_secured_size_limit() {
         use tokio::io::AsyncWriteExt;
@@ -0,0 +1,273 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # 署名レートの制限
+//!
+//! 仕様書 §6.4
+//!
+//! TEEの署名用秘密鍵が無制限の速度で駆動されないよう、署名操作の回数をトークンバケットで制限する。
+//! Gateway側のレート制限とは独立に適用されるため、侵害された・過剰なGatewayであっても
+//! 設定値を超える速度で署名を引き出すことはできない。
+//!
+//! 1リクエストで生成する署名数（/verify は `processor_ids` の件数、/sign は `requests` の件数）を
+//! 処理の開始前にまとめて予約し、予約できない場合は処理を行わずに503を返す。
+//! 予約は署名を返却したリクエストでのみ確定し、処理が失敗・取り消しされた場合は返却する
+//! （失敗するリクエストで後続の正常なリクエストが制限されないようにする）。
+//! バケットの容量（バースト）は既定で1秒分（`signs_per_sec`）。容量を超える署名数を要求する
+//! リクエストは待っても成功しないため、503ではなく400で即座に拒否する。
+
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::error::TeeError;
+
+/// 署名操作のレート制限（トークンバケット）。
+/// 仕様書 §6.4
+///
+/// 既定値（環境変数 `SIGN_RATE_LIMIT` 未設定）は制限しない。
+/// バケットの容量は環境変数 `SIGN_RATE_BURST` で設定する（未設定の場合は1秒分）。
+#[derive(Debug, Default)]
+pub struct SignRateLimiter {
+    /// Noneの場合は制限しない
+    bucket: Option<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// 1秒あたりの補充量
+    rate: f64,
+    /// バケットの容量（1リクエストで予約できる署名数の上限）
+    capacity: f64,
+    /// 現在のトークン数
+    tokens: f64,
+    /// 最後に補充した時刻
+    refilled_at: Instant,
+}
+
+impl SignRateLimiter {
+    /// 1秒あたり `signs_per_sec` 回、最大 `burst` 回まで連続した署名を許可するレート制限を作成する。
+    pub fn new(signs_per_sec: NonZeroU32, burst: NonZeroU32) -> Self {
+        let capacity = f64::from(burst.get());
+        Self {
+            bucket: Some(Mutex::new(Bucket {
+                rate: f64::from(signs_per_sec.get()),
+                capacity,
+                tokens: capacity,
+                refilled_at: Instant::now(),
+            })),
+        }
+    }
+
+    /// 環境変数の値（`SIGN_RATE_LIMIT` / `SIGN_RATE_BURST`）からレート制限を作成する。
+    ///
+    /// `rate` が `None` の場合は制限しない。値は1以上の整数でなければならず、
+    /// 不正な値は設定ミスとしてエラーにする（黙って制限を無効化しない）。
+    pub fn parse(rate: Option<&str>, burst: Option<&str>) -> Result<Self, String> {
+        let parse = |name: &str, value: &str| {
+            value.trim().parse::<NonZeroU32>().map_err(|_| {
+                format!("{name}は1以上の整数で指定してください: {value:?}")
+            })
+        };
+        let Some(rate) = rate else {
+            if burst.is_some() {
+                return Err("SIGN_RATE_BURSTはSIGN_RATE_LIMITと併せて指定してください".into());
+            }
+            return Ok(Self::default());
+        };
+        let signs_per_sec = parse("SIGN_RATE_LIMIT", rate)?;
+        let burst = match burst {
+            Some(burst) => parse("SIGN_RATE_BURST", burst)?,
+            None => signs_per_sec,
+        };
+        Ok(Self::new(signs_per_sec, burst))
+    }
+
+    /// バケットの容量（1リクエストで予約できる署名数の上限）を返す。制限しない場合は `None`。
+    pub fn burst(&self) -> Option<u32> {
+        let bucket = self.bucket.as_ref()?.lock().unwrap_or_else(|e| e.into_inner());
+        Some(bucket.capacity as u32)
+    }
+
+    /// `signatures` 回分の署名を予約する。
+    ///
+    /// 一時的に上限を超える場合は `ServiceUnavailable`、バケットの容量を超えていて
+    /// 再試行しても成功しない場合は `BadRequest` を返す。
+    /// 返却された予約は、署名を返すときに [`SignReservation::commit`] で確定する。
+    pub fn acquire(&self, signatures: usize) -> Result<SignReservation<'_>, TeeError> {
+        self.acquire_at(signatures, Instant::now())
+    }
+
+    fn acquire_at(&self, signatures: usize, now: Instant) -> Result<SignReservation<'_>, TeeError> {
+        let Some(bucket) = &self.bucket else {
+            // 制限しない場合は返却するものがない
+            return Ok(SignReservation {
+                limiter: self,
+                signatures: 0.0,
+            });
+        };
+        let mut bucket = bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let elapsed = now.saturating_duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * bucket.rate).min(bucket.capacity);
+        bucket.refilled_at = now;
+
+        let requested = signatures as f64;
+        if requested > bucket.capacity {
+            return Err(TeeError::BadRequest(format!(
+                "1リクエストの署名数（{signatures}）が署名レートのバースト上限（{}）を超えています",
+                bucket.capacity
+            )));
+        }
+        if requested > bucket.tokens {
+            return Err(TeeError::ServiceUnavailable(
+                "署名レートの上限に達しました。しばらく待ってから再試行してください".into(),
+            ));
+        }
+        bucket.tokens -= requested;
+        Ok(SignReservation {
+            limiter: self,
+            signatures: requested,
+        })
+    }
+
+    /// 確定しなかった予約をバケットに戻す（容量は超えない）。
+    fn release(&self, signatures: f64) {
+        let Some(bucket) = &self.bucket else {
+            return;
+        };
+        let mut bucket = bucket.lock().unwrap_or_else(|e| e.into_inner());
+        bucket.tokens = (bucket.tokens + signatures).min(bucket.capacity);
+    }
+}
+
+/// [`SignRateLimiter::acquire`] で予約した署名数。
+/// 仕様書 §6.4
+///
+/// [`commit`](Self::commit) せずにDropされた場合（エラー・取り消しでリクエストが終了した場合）は、
+/// 予約をバケットに返却する。
+#[must_use = "署名を返すときにcommitしないと予約は返却されます"]
+#[derive(Debug)]
+pub struct SignReservation<'a> {
+    limiter: &'a SignRateLimiter,
+    signatures: f64,
+}
+
+impl SignReservation<'_> {
+    /// 署名を返却したリクエストとして予約を確定する。
+    pub fn commit(mut self) {
+        self.signatures = 0.0;
+    }
+}
+
+impl Drop for SignReservation<'_> {
+    fn drop(&mut self) {
+        if self.signatures > 0.0 {
+            self.limiter.release(self.signatures);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn new_limiter(signs_per_sec: u32, burst: u32) -> SignRateLimiter {
+        SignRateLimiter::new(
+            NonZeroU32::new(signs_per_sec).unwrap(),
+            NonZeroU32::new(burst).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_sign_rate_limiter_throttles_above_limit() {
+        let limiter = new_limiter(3, 3);
+        let start = Instant::now();
+
+        // 容量（1秒分）までは受け付け、それを超えると拒否する
+        for _ in 0..3 {
+            limiter.acquire_at(1, start).unwrap().commit();
+        }
+        assert!(matches!(
+            limiter.acquire_at(1, start),
+            Err(TeeError::ServiceUnavailable(_))
+        ));
+
+        // 時間の経過に応じて補充される
+        let later = start + Duration::from_millis(400);
+        limiter.acquire_at(1, later).unwrap().commit();
+        assert!(limiter.acquire_at(1, later).is_err());
+
+        // 複数の署名はまとめて予約し、不足する場合は1つも消費しない
+        let refilled = later + Duration::from_secs(10);
+        limiter.acquire_at(2, refilled).unwrap().commit();
+        assert!(matches!(
+            limiter.acquire_at(2, refilled),
+            Err(TeeError::ServiceUnavailable(_))
+        ));
+        limiter.acquire_at(1, refilled).unwrap().commit();
+        assert!(limiter.acquire_at(1, refilled).is_err());
+    }
+
+    #[test]
+    fn test_sign_rate_limiter_releases_uncommitted_reservation() {
+        let limiter = new_limiter(2, 2);
+        let start = Instant::now();
+
+        // 確定せずにDropした予約（失敗・取り消しされたリクエスト）は返却される
+        for _ in 0..10 {
+            drop(limiter.acquire_at(2, start).unwrap());
+        }
+        limiter.acquire_at(1, start).unwrap().commit();
+
+        // 確定した予約は返却されない
+        let reservation = limiter.acquire_at(1, start).unwrap();
+        assert!(matches!(
+            limiter.acquire_at(1, start),
+            Err(TeeError::ServiceUnavailable(_))
+        ));
+
+        // 処理中に補充された後の返却は容量を超えない
+        let later = start + Duration::from_secs(10);
+        limiter.acquire_at(0, later).unwrap().commit();
+        drop(reservation);
+        limiter.acquire_at(2, later).unwrap().commit();
+        assert!(limiter.acquire_at(1, later).is_err());
+    }
+
+    #[test]
+    fn test_sign_rate_limiter_rejects_batch_above_burst() {
+        // 容量を超える署名数は、バケットが満杯でも再試行しても成功しないため400とする
+        let limiter = new_limiter(3, 3);
+        assert!(matches!(limiter.acquire(4), Err(TeeError::BadRequest(_))));
+        limiter.acquire(3).unwrap().commit();
+
+        // バースト上限を引き上げれば、レートを超えない範囲で大きなバッチを受け付ける
+        let limiter = new_limiter(3, 10);
+        limiter.acquire(10).unwrap().commit();
+        assert!(matches!(limiter.acquire(1), Err(TeeError::ServiceUnavailable(_))));
+    }
+
+    #[test]
+    fn test_sign_rate_limiter_parse() {
+        assert_eq!(SignRateLimiter::parse(None, None).unwrap().burst(), None);
+        assert_eq!(SignRateLimiter::parse(Some("5"), None).unwrap().burst(), Some(5));
+        assert_eq!(SignRateLimiter::parse(Some("5"), Some("20")).unwrap().burst(), Some(20));
+        for (rate, burst) in [
+            (Some("0"), None),
+            (Some("abc"), None),
+            (Some("-1"), None),
+            (Some("5"), Some("0")),
+            (None, Some("5")),
+        ] {
+            assert!(SignRateLimiter::parse(rate, burst).is_err(), "{rate:?} {burst:?}");
+        }
+    }
+
+    #[test]
+    fn test_sign_rate_limiter_default_is_unlimited() {
+        let limiter = SignRateLimiter::default();
+        for _ in 0..10_000 {
+            limiter.acquire(16).unwrap().commit();
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # WASMインスタンスプールのスロット管理
+//!
+//! 仕様書 §7.1
+//!
+//! [`title_wasm_host::InstancePool`] は空きスロットがない場合に呼び出しスレッドをブロックして待機する。
+//! TEEでは非同期タスクからWASMを実行するため、プールのスロット数と同数のセマフォで
+//! 実行前に非同期にスロットを確保し、プール内の同期待機が発生しないようにする。
+//! 待機中に検証が取り消された場合は、タスクの打ち切りにより待機も終了する。
+
+use std::sync::Arc;
+
+use title_wasm_host::InstancePool;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// 非同期のスロット確保付きのインスタンスプール。
+/// 仕様書 §7.1
+#[derive(Debug)]
+pub struct WasmInstancePool {
+    pool: Arc<InstancePool>,
+    slots: Arc<Semaphore>,
+}
+
+impl WasmInstancePool {
+    /// プールのスロット数と同数の確保枠で作成する。
+    pub fn new(pool: InstancePool) -> Self {
+        let slots = Arc::new(Semaphore::new(pool.slots() as usize));
+        Self {
+            pool: Arc::new(pool),
+            slots,
+        }
+    }
+
+    /// インスタンスプールを返す。
+    pub fn pool(&self) -> &Arc<InstancePool> {
+        &self.pool
+    }
+
+    /// スロットを1つ確保する。空きがない場合は返却されるまで非同期に待機する。
+    ///
+    /// 確保した枠はプール実行の完了まで保持すること（Dropで返却される）。
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        Arc::clone(&self.slots)
+            .acquire_owned()
+            .await
+            .expect("スロットのセマフォは閉じられない")
+    }
+}
+
+// ---------------------------------------------------------------------------
+// テスト
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire_waits_asynchronously_for_free_slot() {
+        let pool = Arc::new(WasmInstancePool::new(InstancePool::new(1, 1024 * 1024).unwrap()));
+        let permit = pool.acquire().await;
+
+        let waiter = tokio::spawn({
+            let pool = Arc::clone(&pool);
+            async move { drop(pool.acquire().await) }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished(), "空きスロットがないのに確保できた");
+
+        drop(permit);
+        waiter.await.unwrap();
+    }
+}
@@ -17,8 +17,8 @@ mod endpoints;
 pub mod infra;
 mod blockchain;
 pub mod wasm_loader;
+mod extension_registry;
 
-use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use solana_sdk::pubkey::Pubkey;
@@ -51,9 +51,48 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
+    // C2PA検証設定を固定（仕様書 §2.1）。全ノードで同一の検証ポリシーを保証する
+    let c2pa_settings = title_core::settings::init_verification_settings()?;
+    tracing::info!(
+        verify_after_reading = c2pa_settings.verify.verify_after_reading,
+        ocsp_fetch = c2pa_settings.verify.ocsp_fetch,
+        remote_manifest_fetch = c2pa_settings.verify.remote_manifest_fetch,
+        "C2PA検証設定を読み込みました"
+    );
+
     let proxy_addr =
         std::env::var("PROXY_ADDR").unwrap_or_else(|_| "127.0.0.1:8000".to_string());
 
+    // プロキシの疎通確認（仕様書 §6.4）
+    // PROXY_HEALTH_URL が設定されている場合のみ実施。PROXY_REQUIRED=1 なら到達不能時に起動を中止する
+    if let Some(health_url) = std::env::var("PROXY_HEALTH_URL").ok().filter(|s| !s.is_empty()) {
+        let proxy_required = std::env::var("PROXY_REQUIRED")
+            .is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"));
+        match infra::proxy_client::probe_proxy(
+            &proxy_addr,
+            &health_url,
+            infra::proxy_client::PROXY_PROBE_TIMEOUT,
+        )
+        .await
+        {
+            infra::proxy_client::ProxyProbe::Reachable(status) => {
+                tracing::info!(proxy_addr = %proxy_addr, status, "プロキシの疎通を確認しました");
+            }
+            infra::proxy_client::ProxyProbe::Unreachable(reason) if proxy_required => {
+                anyhow::bail!(
+                    "プロキシに到達できません ({proxy_addr}): {reason}。PROXY_REQUIRED=1のため起動を中止します"
+                );
+            }
+            infra::proxy_client::ProxyProbe::Unreachable(reason) => {
+                tracing::warn!(
+                    proxy_addr = %proxy_addr,
+                    reason = %reason,
+                    "プロキシに到達できません。/verify等の外部通信は失敗します"
+                );
+            }
+        }
+    }
+
     // MPL-Coreコレクションアドレス（仕様書 §5.2, §6.5）
     // Core cNFT用とExtension cNFT用で別コレクションを使用
     let core_collection_mint = std::env::var("CORE_COLLECTION_MINT")
@@ -115,16 +154,226 @@ async fn main() -> anyhow::Result<()> {
     let resource_pool = Arc::new(title_wasm_host::ResourcePool::new(max_concurrent_bytes));
     tracing::info!(max_concurrent_bytes, "ResourcePool初期化");
 
-    // 信頼されたExtension ID（仕様書 §6.4 不正WASMインジェクション防御）
-    // TRUSTED_EXTENSIONS=phash-v1,hardware-google,c2pa-training-v1,c2pa-license-v1
-    let trusted_extension_ids = std::env::var("TRUSTED_EXTENSIONS").ok().map(|s| {
-        let ids: HashSet<String> = s.split(',').map(|id| id.trim().to_string()).filter(|id| !id.is_empty()).collect();
-        tracing::info!(extensions = ?ids, "信頼されたExtension一覧を設定しました");
-        ids
-    });
-    if trusted_extension_ids.is_none() {
-        tracing::warn!("TRUSTED_EXTENSIONSが未設定です。全Extension実行を許可します（開発環境用）");
+    // Extensionレジストリ（仕様書 §5.1 Step 11, §6.4 不正WASMインジェクション防御）
+    // TRUSTED_EXTENSIONS=phash-v1,hardware-google,c2pa-training-v1,c2pa-license-v1,pixel-hash-v1,cawg-identity-v1
+    // EXTENSION_SYMBOLS=phash-v1=PHASH,c2pa-training-v1=TRAINING
+    // EXTENSION_REGISTRY_FILE=/etc/title/extensions.json（Extensionごとの実行設定）
+    let extension_specs = match std::env::var("EXTENSION_REGISTRY_FILE").ok().filter(|s| !s.is_empty()) {
+        Some(path) => Some(
+            std::fs::read_to_string(&path)
+                .map_err(|e| anyhow::anyhow!("EXTENSION_REGISTRY_FILEを読み込めません（{path}）: {e}"))?,
+        ),
+        None => None,
+    };
+    let extension_registry = extension_registry::ExtensionRegistry::parse(
+        std::env::var("TRUSTED_EXTENSIONS").ok().as_deref(),
+        std::env::var("EXTENSION_SYMBOLS").ok().as_deref(),
+        extension_specs.as_deref(),
+    )
+    .map_err(|e| anyhow::anyhow!("Extensionレジストリの設定が不正です: {e}"))?;
+    match extension_registry.trusted_ids() {
+        Some(ids) => tracing::info!(extensions = ?ids, "信頼されたExtension一覧を設定しました"),
+        None => tracing::warn!("TRUSTED_EXTENSIONSが未設定です。全Extension実行を許可します（開発環境用）"),
+    }
+    if !extension_registry.symbols().is_empty() {
+        tracing::info!(count = extension_registry.symbols().len(), "Extensionシンボルを設定しました");
+    }
+    if extension_registry.spec_count() > 0 {
+        tracing::info!(count = extension_registry.spec_count(), "Extensionの実行設定を読み込みました");
+    }
+
+    // /signの並行処理数（仕様書 §6.4）
+    let sign_concurrency: usize = std::env::var("SIGN_CONCURRENCY")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(infra::security::DEFAULT_SIGN_CONCURRENCY);
+    tracing::info!(sign_concurrency, "/sign並行処理数を設定しました");
+
+    // /signのsigned_json取得タイムアウト（仕様書 §6.4）
+    let sign_fetch_timeout_secs: u64 = std::env::var("SIGN_FETCH_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(infra::security::DEFAULT_SIGN_FETCH_TIMEOUT_SEC);
+    tracing::info!(sign_fetch_timeout_secs, "/signのsigned_json取得タイムアウトを設定しました");
+
+    // content_hashのデプロイメント名前空間（仕様書 §2.1、既定: 空）
+    let content_hash_namespace = std::env::var("CONTENT_HASH_NAMESPACE").unwrap_or_default();
+    if !content_hash_namespace.is_empty() {
+        tracing::info!(namespace = %content_hash_namespace, "content_hashの名前空間を設定しました");
+    }
+
+    // コンパイル済みWASMモジュールのキャッシュ（仕様書 §7.1）
+    let wasm_module_cache_size: usize = std::env::var("WASM_MODULE_CACHE_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(title_wasm_host::DEFAULT_MODULE_CACHE_CAPACITY);
+    let mut wasm_module_cache = title_wasm_host::ModuleCache::new(wasm_module_cache_size)?;
+    let wasm_module_cache_max_bytes: Option<usize> = std::env::var("WASM_MODULE_CACHE_MAX_BYTES")
+        .ok()
+        .and_then(|s| s.parse().ok());
+    if let Some(max_bytes) = wasm_module_cache_max_bytes {
+        wasm_module_cache = wasm_module_cache.with_max_bytes(max_bytes);
     }
+    let wasm_module_cache = Arc::new(wasm_module_cache);
+    tracing::info!(
+        wasm_module_cache_size,
+        ?wasm_module_cache_max_bytes,
+        "WASMモジュールキャッシュを初期化しました"
+    );
+
+    // 事前インスタンス化プール（仕様書 §7.1、既定は無効 = 呼び出しごとに新規Store）
+    // プールで実行するのは実行設定で `pooled` を指定した（wasm_hashで固定済みの）Extensionのみ
+    let wasm_instance_pool = if std::env::var("WASM_INSTANCE_POOL")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+    {
+        let slots: u32 = std::env::var("WASM_INSTANCE_POOL_SLOTS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(title_wasm_host::DEFAULT_INSTANCE_POOL_SLOTS);
+        let max_prepared: usize = std::env::var("WASM_INSTANCE_POOL_MAX_PREPARED")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(title_wasm_host::DEFAULT_INSTANCE_POOL_MAX_PREPARED);
+        let pool = title_wasm_host::InstancePool::new(
+            slots,
+            infra::security::EXTENSION_MEMORY_LIMIT_BYTES,
+        )?
+        .with_max_prepared(max_prepared);
+        tracing::info!(
+            slots,
+            max_prepared,
+            pooled = ?extension_registry.pooled_ids(),
+            "WASMインスタンスプールを有効化しました"
+        );
+        Some(Arc::new(infra::wasm_pool::WasmInstancePool::new(pool)))
+    } else {
+        if !extension_registry.pooled_ids().is_empty() {
+            tracing::warn!(
+                pooled = ?extension_registry.pooled_ids(),
+                "WASM_INSTANCE_POOLが無効のため、pooledのExtensionも新規Storeで実行します"
+            );
+        }
+        None
+    };
+
+    // Extension出力の共通エンベロープ正規化（仕様書 §5.1 Step 5、既定は無効）
+    let normalize_extension_output = std::env::var("NORMALIZE_EXTENSION_OUTPUT")
+        .is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"));
+    if normalize_extension_output {
+        tracing::info!("Extension出力の正規化を有効化しました");
+    }
+
+    // Extension結果の最大サイズ（仕様書 §5.1 Step 5）
+    let max_extension_result_bytes: usize = std::env::var("EXTENSION_MAX_RESULT_BYTES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(infra::security::DEFAULT_MAX_EXTENSION_RESULT_BYTES);
+    tracing::info!(max_extension_result_bytes, "Extension結果の最大サイズを設定しました");
+
+    // Extension補助入力の最大サイズ（仕様書 §6.4, §7.1）
+    let max_extension_input_bytes: usize = std::env::var("EXTENSION_MAX_INPUT_BYTES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(infra::security::DEFAULT_MAX_EXTENSION_INPUT_BYTES);
+    tracing::info!(max_extension_input_bytes, "Extension補助入力の最大サイズを設定しました");
+
+    // mint前のMerkle Tree容量確認（仕様書 §6.5、未設定なら確認しない）
+    let tree_capacity_rpc_url = std::env::var("TREE_CAPACITY_RPC_URL")
+        .ok()
+        .filter(|s| !s.is_empty());
+    if let Some(ref url) = tree_capacity_rpc_url {
+        tracing::info!(url, "/signでMerkle Treeの容量を確認します");
+    }
+
+    // 信頼するTSA証明書ハッシュ（仕様書 §2.4、GlobalConfig.trusted_tsa_keys と同じ形式）
+    // TRUSTED_TSA_KEYS=0x<sha256 hex>,0x<sha256 hex>
+    let trusted_tsa_keys: Vec<String> = std::env::var("TRUSTED_TSA_KEYS")
+        .map(|s| s.split(',').map(|k| k.trim().to_string()).filter(|k| !k.is_empty()).collect())
+        .unwrap_or_default();
+    if !trusted_tsa_keys.is_empty() {
+        tracing::info!(count = trusted_tsa_keys.len(), "信頼するTSA一覧を設定しました");
+    }
+
+    // 署名者証明書の期限間近警告の閾値（仕様書 §2.1、既定30日）
+    let signer_cert_expiry_warning_days: u64 = std::env::var("SIGNER_CERT_EXPIRY_WARNING_DAYS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(title_core::signer_cert::DEFAULT_EXPIRY_WARNING_DAYS);
+    tracing::info!(signer_cert_expiry_warning_days, "署名者証明書の期限警告閾値を設定しました");
+    let signer_cert_expiry_warning_secs = signer_cert_expiry_warning_days * 24 * 60 * 60;
+
+    // 自己署名の署名者証明書を信頼レベルとして区別する（仕様書 §2.1、既定は無効）
+    let report_self_signed_trust_level = std::env::var("SELF_SIGNED_TRUST_LEVEL")
+        .is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"));
+    if report_self_signed_trust_level {
+        tracing::info!("自己署名の署名者証明書を信頼レベルとして区別します");
+    }
+
+    // Core処理で受け入れるC2PA検証状態の下限（仕様書 §2.1、既定は検証状態で拒否しない）
+    // C2PA_MIN_VALIDATION_STATE=invalid|valid|trusted
+    let min_c2pa_validation_state = match std::env::var("C2PA_MIN_VALIDATION_STATE") {
+        Ok(spec) => title_core::C2paValidationState::parse(&spec)
+            .map_err(|e| anyhow::anyhow!("C2PA_MIN_VALIDATION_STATEが不正です: {e}"))?,
+        Err(_) => Default::default(),
+    };
+    tracing::info!(
+        min_c2pa_validation_state = min_c2pa_validation_state.as_str(),
+        "C2PA検証状態の下限を設定しました"
+    );
+
+    // 処理を拒否するcontent_hashの一覧（仕様書 §6.4、法的要請によるテイクダウン用）
+    // CONTENT_DENYLIST=0x<content_hash>,0x<content_hash>
+    // CONTENT_DENYLIST_FILE=/etc/title/denylist.txt（1行1件、# 以降はコメント）
+    let mut denylist_spec = std::env::var("CONTENT_DENYLIST").unwrap_or_default();
+    if let Some(path) = std::env::var("CONTENT_DENYLIST_FILE").ok().filter(|s| !s.is_empty()) {
+        let file = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("CONTENT_DENYLIST_FILEを読み込めません（{path}）: {e}"))?;
+        denylist_spec.push('\n');
+        denylist_spec.push_str(&file);
+    }
+    let content_denylist = infra::denylist::ContentDenylist::parse(&denylist_spec)
+        .map_err(|e| anyhow::anyhow!("content_hashの拒否リストが不正です: {e}"))?;
+    if !content_denylist.is_empty() {
+        tracing::info!(count = content_denylist.len(), "content_hashの拒否リストを設定しました");
+    }
+
+    // C2PAマニフェストストア（JUMBF）の最大サイズ（仕様書 §2.1）
+    let max_manifest_store_bytes: u64 = std::env::var("C2PA_MAX_MANIFEST_STORE_BYTES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(title_core::DEFAULT_MAX_MANIFEST_STORE_BYTES);
+    tracing::info!(max_manifest_store_bytes, "マニフェストストアの最大サイズを設定しました");
+
+    // /verify の同時処理数（仕様書 §6.4）
+    let max_concurrent_verifies: usize = std::env::var("MAX_CONCURRENT_VERIFIES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(infra::admission::DEFAULT_MAX_CONCURRENT_VERIFIES);
+    tracing::info!(max_concurrent_verifies, "/verifyの同時処理数を設定しました");
+
+    // 署名操作のレート制限（仕様書 §6.4）
+    // SIGN_RATE_LIMIT=100（1秒あたりの署名数、未設定の場合は制限しない）
+    // SIGN_RATE_BURST=200（連続して許可する署名数の上限、未設定の場合はSIGN_RATE_LIMITと同じ）
+    let sign_rate_limiter = infra::sign_rate::SignRateLimiter::parse(
+        std::env::var("SIGN_RATE_LIMIT").ok().as_deref(),
+        std::env::var("SIGN_RATE_BURST").ok().as_deref(),
+    )
+    .map_err(|e| anyhow::anyhow!("署名レートの設定が不正です: {e}"))?;
+    if let Some(burst) = sign_rate_limiter.burst() {
+        tracing::info!(burst, "署名レートの上限を設定しました");
+    }
+
+    // Temporary Storageの404を再試行可能とみなす猶予期間（仕様書 §6.4、既定30秒）
+    // MISSING_OBJECT_GRACE_SECS=0 の場合、404は常に恒久的な不在として返す
+    let missing_object_grace_secs: u64 = match std::env::var("MISSING_OBJECT_GRACE_SECS") {
+        Ok(s) => s.parse().map_err(|e| {
+            anyhow::anyhow!("MISSING_OBJECT_GRACE_SECSの値が不正です: {s} ({e})")
+        })?,
+        Err(_) => infra::missing_objects::DEFAULT_MISSING_OBJECT_GRACE_SECS,
+    };
+    tracing::info!(missing_object_grace_secs, "Temporary Storageの404の猶予期間を設定しました");
 
     let shared_state = Arc::new(TeeAppState {
         runtime,
@@ -137,7 +386,28 @@ async fn main() -> anyhow::Result<()> {
         gateway_pubkey,
         wasm_loader,
         resource_pool,
-        trusted_extension_ids,
+        extension_registry,
+        sign_concurrency,
+        sign_fetch_timeout_secs,
+        wasm_module_cache: Some(wasm_module_cache),
+        wasm_instance_pool,
+        normalize_extension_output,
+        max_extension_result_bytes,
+        max_extension_input_bytes,
+        tree_capacity_rpc_url,
+        trusted_tsa_keys,
+        content_hash_namespace,
+        signer_cert_expiry_warning_secs,
+        report_self_signed_trust_level,
+        min_c2pa_validation_state,
+        content_denylist,
+        max_manifest_store_bytes,
+        inflight_verifies: Default::default(),
+        verify_admission: infra::admission::PriorityAdmission::new(max_concurrent_verifies),
+        sign_rate_limiter,
+        missing_objects: infra::missing_objects::MissingObjects::new(std::time::Duration::from_secs(
+            missing_object_grace_secs,
+        )),
     });
 
     // Step 1: 鍵生成 (仕様書 §6.4)
@@ -154,10 +424,14 @@ async fn main() -> anyhow::Result<()> {
     // axumルーターの構築
     let app = axum::Router::new()
         .route("/health", axum::routing::get(|| async { "ok" }))
+        .route("/metrics", axum::routing::get(endpoints::handle_metrics))
         .route("/create-tree", axum::routing::post(endpoints::handle_create_tree))
         .route("/register-node", axum::routing::post(endpoints::handle_register_node))
+        .route("/tree-info", axum::routing::get(endpoints::handle_tree_info))
         .route("/verify", axum::routing::post(endpoints::handle_verify))
+        .route("/verify/{token}", axum::routing::delete(endpoints::handle_cancel_verify))
         .route("/sign", axum::routing::post(endpoints::handle_sign))
+        .route("/self-test", axum::routing::post(endpoints::handle_self_test))
         .with_state(shared_state);
 
     let addr = "0.0.0.0:4000";
@@ -76,7 +76,7 @@ impl TeeRuntime for MockRuntime {
     /// メモリ内でX25519暗号化用キーペアを生成する。
     /// 仕様書 §6.4 Step 1
     fn generate_encryption_keypair(&self) {
-        let secret = StaticSecret::random_from_rng(&mut rand::rngs::OsRng);
+        let secret = StaticSecret::random_from_rng(rand::rngs::OsRng);
         let mut guard = self.encryption_secret.write().unwrap();
         *guard = Some(secret);
     }
@@ -246,7 +246,7 @@ mod tests {
             .expect("公開鍵は32バイト");
 
         // クライアント側のエフェメラルキーペアを生成
-        let client_secret = StaticSecret::random_from_rng(&mut rand::rngs::OsRng);
+        let client_secret = StaticSecret::random_from_rng(rand::rngs::OsRng);
         let client_pubkey = X25519PublicKey::from(&client_secret);
 
         // TEE側: ECDH(tee_sk, client_pk)
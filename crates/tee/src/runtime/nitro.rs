@@ -449,7 +449,7 @@ mod tests {
             .expect("公開鍵は32バイト");
 
         // クライアント側のエフェメラルキーペアを生成
-        let client_secret = StaticSecret::random_from_rng(&mut rand::rngs::OsRng);
+        let client_secret = StaticSecret::random_from_rng(rand::rngs::OsRng);
         let client_pubkey = X25519PublicKey::from(&client_secret);
 
         // TEE側: ECDH(tee_sk, client_pk)
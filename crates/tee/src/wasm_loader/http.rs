@@ -10,6 +10,8 @@
 use std::future::Future;
 use std::pin::Pin;
 
+use crate::infra::proxy_client::ProxyHttpClient;
+
 use super::WasmBinary;
 use super::WasmLoader;
 
@@ -19,8 +21,8 @@ use super::WasmLoader;
 /// TEEはネットワーク隔離されているため、プロキシ経由で取得する。
 /// URL形式: `{base_url}/{extension_id}.wasm`
 pub struct HttpLoader {
-    /// プロキシのアドレス
-    proxy_addr: String,
+    /// プロキシ経由のHTTPクライアント
+    client: ProxyHttpClient,
     /// WASMバイナリのベースURL
     base_url: String,
 }
@@ -33,7 +35,7 @@ impl HttpLoader {
     /// - `base_url`: WASMバイナリのベースURL（例: "https://arweave.net/wasm"）
     pub fn new(proxy_addr: String, base_url: String) -> Self {
         Self {
-            proxy_addr,
+            client: ProxyHttpClient::new(proxy_addr),
             base_url,
         }
     }
@@ -46,15 +48,11 @@ impl WasmLoader for HttpLoader {
     ) -> Pin<Box<dyn Future<Output = Result<WasmBinary, String>> + Send + 'a>> {
         Box::pin(async move {
             let url = format!("{}/{extension_id}.wasm", self.base_url);
-            let response = crate::infra::proxy_client::proxy_get(&self.proxy_addr, &url)
+            let response = self
+                .client
+                .get(&url)
                 .await
                 .map_err(|e| format!("WASM取得に失敗 ({url}): {e}"))?;
-            if response.status != 200 {
-                return Err(format!(
-                    "WASM取得でHTTPエラー: ステータス {} ({url})",
-                    response.status
-                ));
-            }
             if response.body.is_empty() {
                 return Err(format!("WASM取得: 空のレスポンス ({url})"));
             }
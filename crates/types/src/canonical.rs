@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # 正規化JSON
+//!
+//! 仕様書 §5.1
+//!
+//! 署名・ハッシュの対象となるJSONを、実装に依存しない一意のバイト列に変換する。
+//! 規則はRFC 8785（JSON Canonicalization Scheme）に従い（整数の表記のみ規則5による）、
+//! クライアントが他言語で同じバイト列を再現できるようにする。
+//!
+//! ## 規則
+//! 1. 空白・改行を一切含めない（`,` `:` の前後にも入れない）
+//! 2. オブジェクトのキーはUTF-16コード単位の辞書順に並べる（ネストしたオブジェクトも再帰的に）
+//! 3. 配列は要素の順序を保持する
+//! 4. 文字列は `"` `\` と制御文字（U+0000〜U+001F）のみをエスケープする。
+//!    `\b` `\f` `\n` `\r` `\t` は短縮形、その他の制御文字は小文字hexの `\u00xx`。
+//!    非ASCII文字はエスケープせずUTF-8のまま出力する
+//! 5. 整数はそのまま10進表記する。浮動小数点数はECMAScriptの `Number.prototype.toString`
+//!    （RFC 8785 §3.2.2.3）と同じく、往復可能な最短の桁を10進指数が21以下なら小数表記、
+//!    それ以外は指数表記とする（例: `1.0` → `1`、`-0.0` → `0`、`1.5`、`0.1`、
+//!    `9007199254740992.0` → `9007199254740992`、`1e21` → `1e+21`、`1e-7` → `1e-7`）
+//! 6. `true` `false` `null` はそのまま出力する
+//!
+//! serde_jsonの `Map` は依存クレートの機能（`preserve_order`）次第で挿入順を保持するため、
+//! 署名対象のシリアライズに `serde_json::to_vec` を直接用いてはならない。
+
+use std::io::Write;
+
+/// JSON値を正規化JSONのバイト列に変換する。
+/// 仕様書 §5.1
+///
+/// 同じ値は実装・キーの挿入順に関わらず常に同じバイト列になる。規則はモジュール文書を参照。
+pub fn canonical_json(value: &serde_json::Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_value(&mut out, value);
+    out
+}
+
+fn write_value(out: &mut Vec<u8>, value: &serde_json::Value) {
+    match value {
+        serde_json::Value::Null => out.extend_from_slice(b"null"),
+        serde_json::Value::Bool(b) => out.extend_from_slice(if *b { b"true" } else { b"false" }),
+        serde_json::Value::Number(n) => write_number(out, n),
+        serde_json::Value::String(s) => write_string(out, s),
+        serde_json::Value::Array(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_value(out, item);
+            }
+            out.push(b']');
+        }
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push(b'{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_string(out, key);
+                out.push(b':');
+                write_value(out, item);
+            }
+            out.push(b'}');
+        }
+    }
+}
+
+fn write_number(out: &mut Vec<u8>, n: &serde_json::Number) {
+    match n.as_f64() {
+        Some(f) if !(n.is_u64() || n.is_i64()) => write_f64(out, f),
+        _ => {
+            // Vec<u8>への書き込みは失敗しない
+            let _ = write!(out, "{n}");
+        }
+    }
+}
+
+/// 浮動小数点数をECMAScriptの `Number.prototype.toString` と同じ表記で書き込む（規則5）。
+fn write_f64(out: &mut Vec<u8>, f: f64) {
+    if f == 0.0 {
+        out.push(b'0');
+        return;
+    }
+    if f < 0.0 {
+        out.push(b'-');
+    }
+    // `{:e}` は往復可能な最短の桁を `d.ddde±x` 形式で出力する
+    let sci = format!("{:e}", f.abs());
+    let (mantissa, exponent) = sci.split_once('e').unwrap_or((&sci, "0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    // 値 = 0.<digits> × 10^n
+    let n = exponent.parse::<i32>().unwrap_or(0) + 1;
+
+    if k <= n && n <= 21 {
+        out.extend_from_slice(digits.as_bytes());
+        out.extend(std::iter::repeat_n(b'0', (n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        let (int_part, frac_part) = digits.split_at(n as usize);
+        let _ = write!(out, "{int_part}.{frac_part}");
+    } else if -6 < n && n <= 0 {
+        out.extend_from_slice(b"0.");
+        out.extend(std::iter::repeat_n(b'0', (-n) as usize));
+        out.extend_from_slice(digits.as_bytes());
+    } else {
+        let (first, rest) = digits.split_at(1);
+        out.extend_from_slice(first.as_bytes());
+        if !rest.is_empty() {
+            let _ = write!(out, ".{rest}");
+        }
+        let e = n - 1;
+        let _ = write!(out, "e{}{}", if e < 0 { '-' } else { '+' }, e.abs());
+    }
+}
+
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    // serde_jsonの文字列エスケープは規則4と一致する
+    let _ = serde_json::to_writer(&mut *out, s);
+}
+
+// ---------------------------------------------------------------------------
+// テスト
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn canonical_str(value: &serde_json::Value) -> String {
+        String::from_utf8(canonical_json(value)).unwrap()
+    }
+
+    #[test]
+    fn test_keys_sorted_regardless_of_insertion_order() {
+        let mut a = serde_json::Map::new();
+        a.insert("b".into(), json!(1));
+        a.insert("a".into(), json!(2));
+        let mut b = serde_json::Map::new();
+        b.insert("a".into(), json!(2));
+        b.insert("b".into(), json!(1));
+
+        let a = serde_json::Value::Object(a);
+        let b = serde_json::Value::Object(b);
+        assert_eq!(canonical_json(&a), canonical_json(&b));
+        assert_eq!(canonical_str(&a), r#"{"a":2,"b":1}"#);
+    }
+
+    #[test]
+    fn test_nested_objects_and_arrays() {
+        let value = json!({
+            "z": [3, {"y": null, "x": true}, "s"],
+            "a": {"d": {"c": false}, "b": []},
+        });
+        assert_eq!(
+            canonical_str(&value),
+            r#"{"a":{"b":[],"d":{"c":false}},"z":[3,{"x":true,"y":null},"s"]}"#
+        );
+    }
+
+    #[test]
+    fn test_unicode_strings_and_key_order() {
+        // U+FF61（BMP）はUTF-16で0xFF61、U+1F600（絵文字）はサロゲート0xD83Dから始まるため先に並ぶ
+        // （UTF-8のバイト順では逆になる）
+        let value = json!({"\u{ff61}": 1, "\u{1f600}": 2, "é": 3});
+        assert_eq!(
+            canonical_str(&value),
+            "{\"é\":3,\"\u{1f600}\":2,\"\u{ff61}\":1}"
+        );
+        // 制御文字のみエスケープし、非ASCIIはUTF-8のまま出力する
+        let value = json!("a\"b\\c\n\t\u{1}\u{7f}日本");
+        assert_eq!(canonical_str(&value), "\"a\\\"b\\\\c\\n\\t\\u0001\u{7f}日本\"");
+    }
+
+    #[test]
+    fn test_number_formatting() {
+        let value = json!([0, -1, u64::MAX, i64::MIN, 1.0, -0.0, 1.5, 1e300, 0.1, 9007199254740992.0]);
+        assert_eq!(
+            canonical_str(&value),
+            "[0,-1,18446744073709551615,-9223372036854775808,1,0,1.5,1e+300,0.1,9007199254740992]"
+        );
+    }
+
+    /// RFC 8785 付録Bの数値表記（ECMAScriptの `Number.prototype.toString`）と一致する
+    #[test]
+    fn test_float_formatting_matches_es6() {
+        let cases: [(f64, &str); 14] = [
+            (1e20, "100000000000000000000"),
+            (1e21, "1e+21"),
+            (1.5e21, "1.5e+21"),
+            (123456789012345680000.0, "123456789012345680000"),
+            (9007199254740994.0, "9007199254740994"),
+            (-1.5e22, "-1.5e+22"),
+            (0.000001, "0.000001"),
+            (1e-7, "1e-7"),
+            (-1.234e-7, "-1.234e-7"),
+            (0.00012, "0.00012"),
+            (1.7976931348623157e308, "1.7976931348623157e+308"),
+            (5e-324, "5e-324"),
+            (333333333.3333333, "333333333.3333333"),
+            (4.5, "4.5"),
+        ];
+        for (f, expected) in cases {
+            assert_eq!(canonical_str(&json!(f)), expected, "{f:e}");
+        }
+    }
+}
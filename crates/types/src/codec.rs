@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Base58 / Base64 コーデック
+//!
+//! 仕様書 §5.1, §6.4
+//!
+//! 公開鍵（Base58）、署名・nonce（Base64）を固定長のバイト配列にデコードする。
+//! 文字種と長さを検証し、どちらで失敗したかを [`CodecError`] で区別する。
+//!
+//! Base64は標準形式（RFC 4648 §4）を基本とし、WebCrypto等のブラウザクライアントが用いる
+//! URL-safe形式（§5、パディング有無を問わない）も受け付ける。
+
+use base58::{FromBase58, FromBase58Error};
+use base64::Engine;
+
+/// デコードエラー。
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CodecError {
+    /// Base58として不正な文字を含む
+    #[error("Base58として不正な文字 {character:?} が含まれています（位置 {position}）")]
+    InvalidBase58Character {
+        /// 不正な文字
+        character: char,
+        /// 文字の位置
+        position: usize,
+    },
+    /// Base58としてデコードできない
+    #[error("Base58のデコードに失敗しました")]
+    InvalidBase58,
+    /// Base64としてデコードできない（標準形式・URL-safe形式のいずれでもない）
+    #[error("Base64のデコードに失敗: {0}")]
+    InvalidBase64(String),
+    /// デコード後のバイト長が期待値と異なる
+    #[error("{expected}バイトである必要があります（実際: {actual}バイト）")]
+    InvalidLength {
+        /// 期待するバイト長
+        expected: usize,
+        /// デコード後のバイト長
+        actual: usize,
+    },
+}
+
+/// Base58の公開鍵（Ed25519 / Solanaアドレス）を32バイトにデコードする。
+/// 仕様書 §5.1
+pub fn decode_pubkey(value: &str) -> Result<[u8; 32], CodecError> {
+    let bytes = value.from_base58().map_err(|e| match e {
+        FromBase58Error::InvalidBase58Character(character, position) => {
+            CodecError::InvalidBase58Character {
+                character,
+                position,
+            }
+        }
+        FromBase58Error::InvalidBase58Length => CodecError::InvalidBase58,
+    })?;
+    into_array(bytes)
+}
+
+/// Base64のEd25519署名を64バイトにデコードする。
+/// 仕様書 §5.1
+pub fn decode_signature(value: &str) -> Result<[u8; 64], CodecError> {
+    decode_base64_array(value)
+}
+
+/// Base64のAES-GCM nonceを12バイトにデコードする。
+/// 仕様書 §6.4
+pub fn decode_nonce(value: &str) -> Result<[u8; 12], CodecError> {
+    decode_base64_array(value)
+}
+
+/// Base64を固定長 `N` バイトにデコードする。
+/// 仕様書 §6.4
+pub fn decode_base64_array<const N: usize>(value: &str) -> Result<[u8; N], CodecError> {
+    into_array(decode_base64(value)?)
+}
+
+/// Base64を可変長のバイト列にデコードする（暗号文等）。
+/// 仕様書 §6.4
+///
+/// 標準形式でのデコードを試み、失敗した場合はURL-safe形式で再試行する。
+/// 両方失敗した場合は標準形式のエラーを返す。
+pub fn decode_base64(value: &str) -> Result<Vec<u8>, CodecError> {
+    const URL_SAFE_INDIFFERENT: base64::engine::GeneralPurpose =
+        base64::engine::GeneralPurpose::new(
+            &base64::alphabet::URL_SAFE,
+            base64::engine::GeneralPurposeConfig::new()
+                .with_decode_padding_mode(base64::engine::DecodePaddingMode::Indifferent),
+        );
+    base64::engine::general_purpose::STANDARD
+        .decode(value)
+        .or_else(|e| URL_SAFE_INDIFFERENT.decode(value).map_err(|_| e))
+        .map_err(|e| CodecError::InvalidBase64(e.to_string()))
+}
+
+fn into_array<const N: usize>(bytes: Vec<u8>) -> Result<[u8; N], CodecError> {
+    let actual = bytes.len();
+    bytes.try_into().map_err(|_| CodecError::InvalidLength {
+        expected: N,
+        actual,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// テスト
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base58::ToBase58;
+
+    fn b64(bytes: &[u8]) -> String {
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    }
+
+    #[test]
+    fn test_decode_pubkey() {
+        let key = [7u8; 32];
+        assert_eq!(decode_pubkey(&key.to_base58()).unwrap(), key);
+
+        assert_eq!(
+            decode_pubkey(&[7u8; 31].to_base58()).unwrap_err(),
+            CodecError::InvalidLength { expected: 32, actual: 31 }
+        );
+        assert_eq!(
+            decode_pubkey(&[7u8; 33].to_base58()).unwrap_err(),
+            CodecError::InvalidLength { expected: 32, actual: 33 }
+        );
+        // Base58は0・O・I・lを含まない
+        assert_eq!(
+            decode_pubkey("11O1").unwrap_err(),
+            CodecError::InvalidBase58Character { character: 'O', position: 2 }
+        );
+    }
+
+    #[test]
+    fn test_decode_signature() {
+        let sig = [0xABu8; 64];
+        assert_eq!(decode_signature(&b64(&sig)).unwrap(), sig);
+
+        assert_eq!(
+            decode_signature(&b64(&[0u8; 63])).unwrap_err(),
+            CodecError::InvalidLength { expected: 64, actual: 63 }
+        );
+        assert!(matches!(
+            decode_signature("not base64!").unwrap_err(),
+            CodecError::InvalidBase64(_)
+        ));
+    }
+
+    #[test]
+    fn test_decode_nonce() {
+        let nonce = [0xFBu8; 12];
+        assert_eq!(decode_nonce(&b64(&nonce)).unwrap(), nonce);
+        // URL-safe形式（パディングなし）も受け付ける
+        let url_safe = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(nonce);
+        assert!(url_safe.contains('-') || url_safe.contains('_'));
+        assert_eq!(decode_nonce(&url_safe).unwrap(), nonce);
+
+        assert_eq!(
+            decode_nonce(&b64(&[0u8; 16])).unwrap_err(),
+            CodecError::InvalidLength { expected: 12, actual: 16 }
+        );
+        assert_eq!(
+            decode_nonce("").unwrap_err(),
+            CodecError::InvalidLength { expected: 12, actual: 0 }
+        );
+    }
+}
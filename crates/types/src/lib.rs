@@ -7,10 +7,32 @@
 //! ## エンコーディング規則
 //! - Base58: Solanaアドレス、公開鍵（人間が読みやすく、紛らわしい文字を除外）
 //! - Base64: バイナリデータ（暗号文、署名等）
+//! - 正規化JSON: 署名・ハッシュ対象のJSON（[`canonical_json`]）
+//!
+//! 固定長の公開鍵・署名・nonceのデコードは [`codec`] を用いる。
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use serde::{Deserialize, Serialize};
 
+mod canonical;
+pub mod codec;
+#[cfg(feature = "solana")]
+pub mod pda;
+
+pub use canonical::canonical_json;
+
+// ---------------------------------------------------------------------------
+// プロトコル識別子・アルゴリズム (仕様書 §5.1)
+// ---------------------------------------------------------------------------
+
+/// Core signed_jsonのプロトコル識別子。
+/// 仕様書 §5.1 Step 4
+pub const PROTOCOL_VERSION: &str = "Title-v1";
+
+/// TEE署名（tee_signature）のアルゴリズム。
+/// 仕様書 §5.1 Step 4
+pub const SIGNATURE_ALGORITHM: &str = "Ed25519";
+
 // ---------------------------------------------------------------------------
 // signed_json 構造 (仕様書 §5.1 Step 4, Step 5)
 // ---------------------------------------------------------------------------
@@ -67,10 +89,49 @@ pub struct CorePayload {
     /// Base64エンコードされたRFC 3161トークン（存在する場合）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tsa_token_data: Option<String>,
+    /// TSAタイムスタンプの発行者が信頼TSAリストに含まれるか（TSAタイムスタンプが存在する場合）。
+    /// 仕様書 §2.4
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tsa_trusted: Option<bool>,
     /// 来歴グラフのノード一覧
     pub nodes: Vec<GraphNode>,
-    /// 来歴グラフのリンク一覧
+    /// 来歴グラフのリンク一覧（`compact_links` を返す場合は空）
     pub links: Vec<GraphLink>,
+    /// 来歴グラフのリンク一覧のコンパクト表現。`VerifyRequest.compact_graph` 指定時のみ付与され、
+    /// その場合 `links` は空となる。論理的なリンク一覧は [`CorePayload::graph_links`] で得る。
+    /// 仕様書 §2.2
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compact_links: Vec<CompactGraphLink>,
+    /// `max_returned_nodes` により来歴グラフが切り詰められた場合にtrue。
+    /// 仕様書 §5.1 Step 4
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// コンテンツ本体を受け取らずサイドカーManifestのみで検証した場合にtrue。
+    /// ハードバインディングはクライアントが主張したハッシュとの照合のみで、本体は検証されていない。
+    /// 仕様書 §5.1 Step 4
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub manifest_only: bool,
+    /// Active Manifestの `c2pa.actions` に記録された編集アクションの種別（記録順）。
+    /// 例: `["c2pa.created", "c2pa.color_adjustments"]`
+    /// 仕様書 §5.1 Step 4
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<String>,
+    /// 生成AIで作成されたコンテンツか（`c2pa.actions` の `digitalSourceType` から判定）。
+    /// 判定不能（unknown）の場合は省略される。
+    /// 仕様書 §5.1 Step 4
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_generated: Option<bool>,
+}
+
+impl CorePayload {
+    /// 来歴グラフのリンク一覧を返す。コンパクト表現の場合は `nodes` を参照して展開する。
+    /// 仕様書 §2.2
+    pub fn graph_links(&self) -> Result<Vec<GraphLink>, String> {
+        if self.compact_links.is_empty() {
+            return Ok(self.links.clone());
+        }
+        expand_graph_links(&self.nodes, &self.compact_links)
+    }
 }
 
 /// Extension用ペイロード。WASM実行結果を含む。
@@ -110,6 +171,30 @@ pub struct GraphNode {
     /// ノードタイプ ("final" or "ingredient")
     #[serde(rename = "type")]
     pub node_type: String,
+    /// このノードのManifestを生成したツール（`claim_generator_info`）の一覧。
+    /// `VerifyRequest.include_claim_generators` 指定時のみ付与される。
+    /// 来歴グラフのMerkle葉（`graph_root`）には含まれない。
+    /// 仕様書 §2.2
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub claim_generators: Vec<ClaimGenerator>,
+    /// ingredientがActive Manifestのラベルを持つにもかかわらず、そのManifestを
+    /// マニフェストストアから解決できなかったことを示す（不整合なManifestの兆候）。
+    /// このノードは葉として扱われ、その素材は走査されない。
+    /// 来歴グラフのMerkle葉（`graph_root`）には含まれない。
+    /// 仕様書 §2.2
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub manifest_unresolved: bool,
+}
+
+/// C2PA Manifestの `claim_generator_info` の1要素（ツール名とバージョン）。
+/// 仕様書 §2.2
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimGenerator {
+    /// ツール名
+    pub name: String,
+    /// ツールのバージョン
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
 }
 
 /// 来歴グラフのリンク。素材→派生の関係を表すエッジ。
@@ -124,6 +209,74 @@ pub struct GraphLink {
     pub role: String,
 }
 
+/// 来歴グラフのリンクのコンパクト表現。ノードをcontent_hashの代わりに
+/// ノード一覧（`CorePayload.nodes`）内の添字で参照する。
+/// 仕様書 §2.2
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactGraphLink {
+    /// 素材ノードの添字
+    pub source: u32,
+    /// 派生物ノードの添字
+    pub target: u32,
+    /// 関係の種類 (例: "audio", "image")
+    pub role: String,
+}
+
+/// リンク一覧をノード一覧の添字で参照するコンパクト表現に変換する。
+/// 仕様書 §2.2
+///
+/// リンクの両端はノード一覧に含まれていなければならない。
+pub fn compact_graph_links(
+    nodes: &[GraphNode],
+    links: &[GraphLink],
+) -> Result<Vec<CompactGraphLink>, String> {
+    let index: HashMap<&str, u32> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.id.as_str(), i as u32))
+        .collect();
+    let index_of = |id: &str| {
+        index
+            .get(id)
+            .copied()
+            .ok_or_else(|| format!("リンクの参照先ノードがノード一覧にありません: {id}"))
+    };
+    links
+        .iter()
+        .map(|link| {
+            Ok(CompactGraphLink {
+                source: index_of(&link.source)?,
+                target: index_of(&link.target)?,
+                role: link.role.clone(),
+            })
+        })
+        .collect()
+}
+
+/// コンパクト表現のリンク一覧を、ノードのcontent_hashで参照する通常の表現に戻す。
+/// 仕様書 §2.2
+pub fn expand_graph_links(
+    nodes: &[GraphNode],
+    links: &[CompactGraphLink],
+) -> Result<Vec<GraphLink>, String> {
+    let id_of = |index: u32| {
+        nodes
+            .get(index as usize)
+            .map(|node| node.id.clone())
+            .ok_or_else(|| format!("リンクのノード添字が範囲外です: {index}"))
+    };
+    links
+        .iter()
+        .map(|link| {
+            Ok(GraphLink {
+                source: id_of(link.source)?,
+                target: id_of(link.target)?,
+                role: link.role.clone(),
+            })
+        })
+        .collect()
+}
+
 // ---------------------------------------------------------------------------
 // Global Config (仕様書 §5.2 Step 1)
 // ---------------------------------------------------------------------------
@@ -213,14 +366,22 @@ pub struct EncryptedPayload {
 pub struct ClientPayload {
     /// Base58エンコードされたSolanaウォレットアドレス
     pub owner_wallet: String,
-    /// Base64エンコードされたコンテンツバイナリ
+    /// Base64エンコードされたコンテンツバイナリ（manifest-onlyモードでは空）
+    #[serde(default)]
     pub content: String,
     /// Base64エンコードされた.c2paファイル（Optional）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sidecar_manifest: Option<String>,
     /// Extension補助入力（Optional）。キーはextension_id。
+    /// 値はインラインのJSON、または `{"ref_url", "sha256"}` 形式のコンテンツアドレス参照。
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extension_inputs: Option<serde_json::Map<String, serde_json::Value>>,
+    /// クライアントが主張するコンテンツ本体のハッシュ（hex）。
+    /// 指定するとmanifest-onlyモードとなり、`content` を送らず `sidecar_manifest` のみで検証する。
+    /// 値はManifestのハードバインディング（`c2pa.hash.data`）のハッシュ値と照合される。
+    /// 仕様書 §5.1 Step 4
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asserted_content_hash: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -235,6 +396,71 @@ pub struct VerifyRequest {
     pub download_url: String,
     /// 実行する検証の識別子リスト
     pub processor_ids: Vec<String>,
+    /// 来歴グラフのノード+エッジ数上限（Optional）。
+    /// ノードの上限（`c2pa_max_graph_size`）より小さい場合のみ有効で、上限を引き上げることはできない。
+    /// 仕様書 §6.4
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_graph_size: Option<u64>,
+    /// 返却する来歴グラフのノード数上限（Optional）。
+    /// グラフ全体の構築・検証は行った上で、超過分はルートから近い順に切り詰めて返す。
+    /// 仕様書 §5.1 Step 4
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_returned_nodes: Option<u64>,
+    /// trueの場合、Core結果にActive Manifestのアサーションラベル一覧を付与する（署名対象外）。
+    /// 仕様書 §5.1 Step 6
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_assertions: bool,
+    /// trueの場合、Core結果にUI表示用のプレビューハッシュを付与する（署名対象外）。
+    /// 仕様書 §5.1 Step 6
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_preview_hash: bool,
+    /// trueの場合、Coreの来歴グラフの各ノードに `claim_generators` を付与する（署名対象）。
+    /// 仕様書 §2.2, §5.1 Step 4
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_claim_generators: bool,
+    /// trueの場合、Coreの来歴グラフのリンクをノードの添字で参照するコンパクト表現
+    /// （`compact_links`）で返す（署名対象）。省略時は従来どおりcontent_hashで参照する。
+    /// 仕様書 §2.2, §5.1 Step 4
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compact_graph: bool,
+    /// 処理中の検証を取り消すためのクライアント指定トークン（Optional）。
+    /// 指定した場合、処理中に `DELETE /verify/{cancel_token}`（本文に同じ `download_url`）で中断できる。
+    /// 仕様書 §6.4
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancel_token: Option<String>,
+    /// 処理の優先度（Optional、未指定は `normal`）。
+    /// TEEの処理枠が埋まっている場合、優先度の高いリクエストから受け付ける。
+    /// Gatewayは優先APIキー以外からの `high` を `normal` に引き下げて中継する。
+    /// 仕様書 §6.2, §6.4
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<VerifyPriority>,
+    /// Extensionの依存関係（Optional）。キーは依存側、値は上流のExtension ID（いずれも `processor_ids` に含める）。
+    /// 上流の結果（WASM出力JSON）が依存側の `extension_input` として渡される。循環は拒否される。
+    /// 仕様書 §5.1 Step 5, §7.1
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub depends_on: BTreeMap<String, String>,
+}
+
+/// /verify の処理優先度。
+/// 仕様書 §6.4
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyPriority {
+    /// バッチ処理等の一括リクエスト
+    Low,
+    /// 通常のリクエスト
+    #[default]
+    Normal,
+    /// 対話的・優先契約のリクエスト
+    High,
+}
+
+/// DELETE /verify/{token} リクエスト。
+/// 仕様書 §6.2, §6.4
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CancelVerifyRequest {
+    /// 取り消す検証の `download_url`（`cancel_token` はこのペイロードに束縛される）
+    pub download_url: String,
 }
 
 /// /verify レスポンス（復号後）。
@@ -252,6 +478,28 @@ pub struct ProcessorResult {
     pub processor_id: String,
     /// TEEが生成したsigned_json
     pub signed_json: serde_json::Value,
+    /// Extension結果（WASM出力）のシリアライズ後のバイト数。
+    /// cNFTメタデータのサイズをmint前に把握するために使用する（Extensionのみ）。
+    /// 仕様書 §5.1 Step 6
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result_size: Option<u64>,
+    /// Active Manifestに含まれるアサーションのラベル一覧（Coreのみ、`include_assertions` 指定時）。
+    /// 透明性・デバッグ用の情報で、signed_jsonの署名対象には含まれない。
+    /// 仕様書 §5.1 Step 6
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assertions: Option<Vec<String>>,
+    /// 縮小画像から算出した64bitのプレビューハッシュ（16桁hex、Coreのみ、`include_preview_hash` 指定時）。
+    /// 再エンコードに対して安定したUI表示用の識別子で、暗号学的な `content_hash` とは異なる。
+    /// signed_jsonの署名対象には含まれない。画像としてデコードできない場合は付与されない。
+    /// 仕様書 §5.1 Step 6
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_hash: Option<String>,
+    /// 検証時点での署名者証明書の期限警告（`expired` / `expiring_soon`、Coreのみ）。
+    /// 判定は検証時刻とノードの閾値に依存するため、signed_jsonの署名対象には含めない
+    /// （署名対象には有効期限の絶対時刻 `signer_cert_not_after` を記録する）。
+    /// 仕様書 §2.1, §5.1 Step 6
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer_cert_warning: Option<String>,
 }
 
 /// /sign リクエスト。
@@ -467,6 +715,50 @@ pub struct CreateTreeResponse {
     pub encryption_pubkey: String,
 }
 
+/// /tree-info レスポンス。
+/// 仕様書 §6.4
+///
+/// `/create-tree` のレスポンスのうち、後続の `/sign` 等の構築に必要な値を再取得するために使用する。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeInfoResponse {
+    /// Base58エンコードされたCore Merkle Treeアドレス
+    pub core_tree_address: String,
+    /// Base58エンコードされたExtension Merkle Treeアドレス
+    pub ext_tree_address: String,
+    /// Base58エンコードされたEd25519署名用公開鍵
+    pub signing_pubkey: String,
+    /// Base64エンコードされたX25519暗号化用公開鍵
+    pub encryption_pubkey: String,
+    /// content_hashのデプロイメント名前空間（仕様書 §2.1、空の場合は名前空間なし）
+    #[serde(default)]
+    pub content_hash_namespace: String,
+}
+
+/// /self-test レスポンス。
+/// 仕様書 §6.4
+///
+/// 信頼済みExtensionごとに、モジュールのロードと組み込みフィクスチャでの実行結果を返す。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelfTestResponse {
+    /// 全Extensionが正常に実行できた場合 `true`
+    pub ok: bool,
+    /// Extensionごとの結果（extension_idの昇順）
+    pub extensions: Vec<ExtensionSelfTestResult>,
+}
+
+/// /self-test のExtensionごとの結果。
+/// 仕様書 §6.4
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionSelfTestResult {
+    /// Extension識別子
+    pub extension_id: String,
+    /// ロード・実行に成功した場合 `true`
+    pub ok: bool,
+    /// 失敗理由（`ok` が `false` の場合）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 /// /register-node リクエスト。
 /// 仕様書 §8.2
 ///
@@ -597,6 +889,8 @@ mod tests {
         let node = GraphNode {
             id: "0x1234".into(),
             node_type: "final".into(),
+            claim_generators: Vec::new(),
+            manifest_unresolved: false,
         };
         let json = serde_json::to_value(&node).unwrap();
         assert_eq!(json["type"], "final");
@@ -608,6 +902,8 @@ mod tests {
         let original = GraphNode {
             id: "0xabcd".into(),
             node_type: "ingredient".into(),
+            claim_generators: Vec::new(),
+            manifest_unresolved: false,
         };
         let json_str = serde_json::to_string(&original).unwrap();
         assert!(json_str.contains("\"type\""));
@@ -629,13 +925,25 @@ mod tests {
             tsa_timestamp: None,
             tsa_pubkey_hash: None,
             tsa_token_data: None,
+            tsa_trusted: None,
             nodes: vec![],
             links: vec![],
+            compact_links: vec![],
+            truncated: false,
+            manifest_only: false,
+            actions: vec![],
+            ai_generated: None,
         };
         let json_str = serde_json::to_string(&payload).unwrap();
         assert!(!json_str.contains("tsa_timestamp"));
         assert!(!json_str.contains("tsa_pubkey_hash"));
         assert!(!json_str.contains("tsa_token_data"));
+        assert!(!json_str.contains("tsa_trusted"));
+        assert!(!json_str.contains("truncated"));
+        assert!(!json_str.contains("manifest_only"));
+        assert!(!json_str.contains("actions"));
+        assert!(!json_str.contains("ai_generated"));
+        assert!(!json_str.contains("compact_links"));
     }
 
     #[test]
@@ -647,13 +955,21 @@ mod tests {
             tsa_timestamp: Some(1700000000),
             tsa_pubkey_hash: Some("hash".into()),
             tsa_token_data: Some("dG9rZW4=".into()),
+            tsa_trusted: Some(false),
             nodes: vec![],
             links: vec![],
+            compact_links: vec![],
+            truncated: false,
+            manifest_only: false,
+            actions: vec![],
+            ai_generated: Some(false),
         };
         let json = serde_json::to_value(&payload).unwrap();
         assert_eq!(json["tsa_timestamp"], 1700000000);
+        assert_eq!(json["ai_generated"], false);
         assert_eq!(json["tsa_pubkey_hash"], "hash");
         assert_eq!(json["tsa_token_data"], "dG9rZW4=");
+        assert_eq!(json["tsa_trusted"], false);
     }
 
     #[test]
@@ -698,15 +1014,67 @@ mod tests {
         assert_eq!(link, restored);
     }
 
+    #[test]
+    fn test_compact_graph_links_roundtrip() {
+        let node = |id: &str| GraphNode {
+            id: id.into(),
+            node_type: "ingredient".into(),
+            claim_generators: vec![],
+            manifest_unresolved: false,
+        };
+        let nodes = vec![node("0xaaa"), node("0xbbb"), node("0xccc")];
+        let link = |source: &str, target: &str| GraphLink {
+            source: source.into(),
+            target: target.into(),
+            role: "image".into(),
+        };
+        // 同じ素材を共有する複数のリンク
+        let links = vec![link("0xaaa", "0xbbb"), link("0xaaa", "0xccc"), link("0xbbb", "0xccc")];
+
+        let compact = compact_graph_links(&nodes, &links).unwrap();
+        assert_eq!(compact[1], CompactGraphLink { source: 0, target: 2, role: "image".into() });
+        assert_eq!(expand_graph_links(&nodes, &compact).unwrap(), links);
+
+        // ノード一覧にないcontent_hash・範囲外の添字は拒否する
+        assert!(compact_graph_links(&nodes, &[link("0xaaa", "0xddd")]).is_err());
+        let out_of_range = CompactGraphLink { source: 0, target: 3, role: "image".into() };
+        assert!(expand_graph_links(&nodes, &[out_of_range]).is_err());
+    }
+
     #[test]
     fn test_verify_request_roundtrip() {
         let req = VerifyRequest {
             download_url: "https://example.com/data".into(),
             processor_ids: vec!["core".into(), "phash-v1".into()],
+            max_graph_size: Some(10),
+            max_returned_nodes: Some(5),
+            include_assertions: true,
+            include_preview_hash: true,
+            include_claim_generators: true,
+            compact_graph: true,
+            cancel_token: Some("job-1".into()),
+            priority: Some(VerifyPriority::High),
+            depends_on: BTreeMap::from([("summary-v1".into(), "phash-v1".into())]),
         };
         let json_str = serde_json::to_string(&req).unwrap();
         let restored: VerifyRequest = serde_json::from_str(&json_str).unwrap();
         assert_eq!(req, restored);
+
+        // max_graph_size / max_returned_nodes省略時はNone、include_assertions省略時はfalse
+        let restored: VerifyRequest = serde_json::from_str(
+            r#"{"download_url":"https://example.com/data","processor_ids":["core"]}"#,
+        )
+        .unwrap();
+        assert_eq!(restored.max_graph_size, None);
+        assert_eq!(restored.max_returned_nodes, None);
+        assert!(!restored.include_assertions);
+        assert!(!restored.include_preview_hash);
+        assert!(!restored.include_claim_generators);
+        assert!(!restored.compact_graph);
+        assert_eq!(restored.cancel_token, None);
+        assert_eq!(restored.priority, None);
+        assert!(restored.depends_on.is_empty());
+        assert!(json_str.contains(r#""priority":"high""#));
     }
 
     #[test]
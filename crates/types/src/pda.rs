@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # title-config プログラムのPDA導出
+//!
+//! 仕様書 §8.1
+//!
+//! Anchorプログラム（`programs/title-config`）の `seeds` と同一のシードでPDAを導出する。
+//! TEE・CLI等のクライアントはここを経由してアドレスを求め、導出方法を一箇所に揃える。
+
+use solana_sdk::pubkey::Pubkey;
+
+/// GlobalConfigAccountのPDAシード
+pub const GLOBAL_CONFIG_SEED: &[u8] = b"global-config";
+
+/// GlobalConfigAccountのPDAを導出する。seeds = [b"global-config"]
+/// 仕様書 §8.1
+pub fn global_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GLOBAL_CONFIG_SEED], program_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Anchorの `seeds = [b"global-config"]` で導出されるアドレスと一致することを確認
+    #[test]
+    fn test_global_config_pda_matches_anchor() {
+        let program_id: Pubkey = "5p5Tf93fEbCPZxA1NG48rH9ozDALsVmVVf52QW3VDNoN"
+            .parse()
+            .unwrap();
+        let (pda, bump) = global_config_pda(&program_id);
+        assert_eq!(
+            pda.to_string(),
+            "EnBaoyMoo4LBMGBYBRnen7ajh1UmEr2th7E6RNz2RuYa"
+        );
+        assert_eq!(bump, 255);
+        assert_eq!(
+            Pubkey::create_program_address(&[GLOBAL_CONFIG_SEED, &[bump]], &program_id).unwrap(),
+            pda
+        );
+    }
+}
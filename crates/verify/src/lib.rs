@@ -0,0 +1,540 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Title Protocol signed_json オフライン検証
+//!
+//! 仕様書 §5.1 Step 4, §5.2 Step 4
+//!
+//! TEEが発行したsigned_json（レシート）を、Gateway・TEE・Solanaに依存せず検証する。
+//!
+//! ## 検証手順
+//! 1. `tee_signature` を埋め込みの `tee_pubkey` で検証（signed_jsonドメイン）
+//! 2. `tee_attestation` を検証し、Attestationが `tee_pubkey` を束縛していることを確認
+//! 3. 期待測定値が指定された場合、Attestationの測定値と照合
+//! 4. 受け入れポリシー（[`VerifyOptions::trust_policy`]）が指定された場合、Attestationを評価
+//!
+//! `tee_type = "mock"` のAttestationは署名されていないため、
+//! [`VerifyOptions::allow_mock`] を有効にした場合のみ受け入れる（ローカル開発用）。
+//!
+//! Extensionのsigned_jsonについては、対象コンテンツの `content_hash` に束縛されていること
+//! （[`verify_extension_content_hash`]）と、元の補助入力から再計算した `extension_input_hash`
+//! （[`verify_extension_input`]）を照合できる。
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use base58::FromBase58;
+use base64::Engine;
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::Deserialize;
+
+use title_crypto::attestation::policy::{PolicyError, TrustPolicy};
+use title_crypto::attestation::{self, AttestationError, AttestationResult};
+use title_types::SignedJson;
+
+/// モックTEEの `tee_type`。
+const MOCK_TEE_TYPE: &str = "mock";
+
+/// Extensionのsigned_jsonの `protocol`。
+const EXTENSION_PROTOCOL: &str = "Title-Extension-v1";
+
+/// signed_json検証のエラー型。
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    /// フィールドのデコードに失敗
+    #[error("{field}のデコードに失敗: {reason}")]
+    Decode {
+        /// フィールド名
+        field: &'static str,
+        /// 失敗理由
+        reason: String,
+    },
+    /// tee_signatureが不正
+    #[error("tee_signatureの検証に失敗しました")]
+    InvalidSignature,
+    /// tee_pubkeyが信頼する公開鍵と一致しない
+    #[error("tee_pubkeyが信頼する公開鍵と一致しません: {0}")]
+    UntrustedPubkey(String),
+    /// mock Attestationが許可されていない
+    #[error("tee_type=mockのAttestationは許可されていません（--allow-mockで許可）")]
+    MockNotAllowed,
+    /// Attestation Documentの検証に失敗
+    #[error("Attestation検証に失敗: {0}")]
+    Attestation(#[from] AttestationError),
+    /// Attestationがtee_pubkeyを束縛していない
+    #[error("Attestationの公開鍵がtee_pubkeyと一致しません")]
+    AttestationKeyMismatch,
+    /// 測定値が期待値と一致しない
+    #[error("測定値が期待値と一致しません: {}", .0.join(", "))]
+    MeasurementMismatch(Vec<String>),
+    /// 受け入れポリシーに違反
+    #[error("Attestationが受け入れポリシーに違反: {0}")]
+    Policy(#[from] PolicyError),
+    /// Extensionのsigned_jsonではない
+    #[error("Extensionのsigned_jsonではありません: protocol={0}")]
+    NotExtension(String),
+    /// payloadに `content_hash` が記録されていない
+    #[error("payloadにcontent_hashが記録されていません")]
+    MissingContentHash,
+    /// content_hashが期待値と一致しない
+    #[error("content_hashが一致しません: 期待値={expected}, 記録値={recorded}")]
+    ContentHashMismatch {
+        /// 期待するcontent_hash
+        expected: String,
+        /// signed_jsonに記録されたcontent_hash
+        recorded: String,
+    },
+    /// payloadに `extension_input_hash` が記録されていない
+    #[error("payloadにextension_input_hashが記録されていません（補助入力なしで実行されています）")]
+    MissingExtensionInputHash,
+    /// 補助入力のハッシュが記録値と一致しない
+    #[error("補助入力のハッシュが一致しません: 記録値={recorded}, 再計算値={computed}")]
+    ExtensionInputMismatch {
+        /// payloadに記録された `extension_input_hash`
+        recorded: String,
+        /// 補助入力から再計算したハッシュ
+        computed: String,
+    },
+}
+
+/// 検証オプション。
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+    /// 信頼するTEE署名用公開鍵。指定時は `tee_pubkey` と一致する必要がある。
+    pub trusted_tee_pubkey: Option<[u8; 32]>,
+    /// 期待測定値（例: `"PCR0"` → 48バイト）。空の場合は照合しない。
+    pub expected_measurements: BTreeMap<String, Vec<u8>>,
+    /// `tee_type = "mock"` のAttestationを受け入れるか
+    pub allow_mock: bool,
+    /// 追加で適用する受け入れポリシー（PCRのピン留め・鮮度・TEE種別等）
+    pub trust_policy: Option<Arc<dyn TrustPolicy>>,
+}
+
+/// 検証に成功したsigned_jsonの情報。
+#[derive(Debug, Clone)]
+pub struct ReceiptVerification {
+    /// TEE種別
+    pub tee_type: String,
+    /// TEE署名用公開鍵
+    pub tee_pubkey: [u8; 32],
+    /// Attestationに含まれる測定値
+    pub measurements: BTreeMap<String, Vec<u8>>,
+    /// 照合した測定値のキー
+    pub checked_measurements: Vec<String>,
+}
+
+/// モックTEEのAttestation Document（JSON）。
+/// crates/tee のモックランタイムが生成する形式に対応する。
+#[derive(Deserialize)]
+struct MockAttestationDocument {
+    pcr0: Vec<u8>,
+    pcr1: Vec<u8>,
+    pcr2: Vec<u8>,
+    signing_pubkey: Vec<u8>,
+    encryption_pubkey: Vec<u8>,
+}
+
+/// signed_jsonを検証する。
+/// 仕様書 §5.1 Step 4, §5.2 Step 4
+pub fn verify_receipt(
+    signed_json: &SignedJson,
+    options: &VerifyOptions,
+) -> Result<ReceiptVerification, VerifyError> {
+    let core = &signed_json.core;
+
+    // Step 1. tee_signatureを埋め込みのtee_pubkeyで検証
+    let pubkey_bytes: [u8; 32] = core
+        .tee_pubkey
+        .from_base58()
+        .map_err(|e| decode_error("tee_pubkey", format!("{e:?}")))?
+        .try_into()
+        .map_err(|_| decode_error("tee_pubkey", "32バイトである必要があります".into()))?;
+    let verifying_key = VerifyingKey::from_bytes(&pubkey_bytes)
+        .map_err(|e| decode_error("tee_pubkey", e.to_string()))?;
+
+    let signature_bytes: [u8; 64] = b64()
+        .decode(&core.tee_signature)
+        .map_err(|e| decode_error("tee_signature", e.to_string()))?
+        .try_into()
+        .map_err(|_| decode_error("tee_signature", "64バイトである必要があります".into()))?;
+    let signature = Signature::from_bytes(&signature_bytes);
+
+    let sign_target = serde_json::json!({
+        "payload": signed_json.payload,
+        "attributes": signed_json.attributes,
+    });
+    let sign_bytes = title_types::canonical_json(&sign_target);
+    let message = title_crypto::domain_separated_message(
+        title_crypto::SignatureDomain::SignedJson,
+        &sign_bytes,
+    );
+    verifying_key
+        .verify_strict(&message, &signature)
+        .map_err(|_| VerifyError::InvalidSignature)?;
+
+    if let Some(trusted) = options.trusted_tee_pubkey {
+        if trusted != pubkey_bytes {
+            return Err(VerifyError::UntrustedPubkey(core.tee_pubkey.clone()));
+        }
+    }
+
+    // Step 2. tee_attestationがtee_pubkeyを束縛していることを確認
+    let attestation_bytes = b64()
+        .decode(&core.tee_attestation)
+        .map_err(|e| decode_error("tee_attestation", e.to_string()))?;
+    let attestation_result = if core.tee_type == MOCK_TEE_TYPE {
+        if !options.allow_mock {
+            return Err(VerifyError::MockNotAllowed);
+        }
+        parse_mock_attestation(&attestation_bytes)?
+    } else {
+        attestation::verify_attestation(&core.tee_type, &attestation_bytes)?
+    };
+    if !attestation::verify_public_key(&attestation_result, &pubkey_bytes) {
+        return Err(VerifyError::AttestationKeyMismatch);
+    }
+
+    // Step 3. 期待測定値との照合
+    let measurement_check =
+        attestation::verify_measurements_detailed(&attestation_result, &options.expected_measurements);
+    if !measurement_check.is_valid() {
+        return Err(VerifyError::MeasurementMismatch(
+            measurement_check.failures().map(|c| c.key.clone()).collect(),
+        ));
+    }
+
+    // Step 4. 受け入れポリシーの評価
+    if let Some(policy) = &options.trust_policy {
+        policy.evaluate(&attestation_result)?;
+    }
+
+    Ok(ReceiptVerification {
+        tee_type: core.tee_type.clone(),
+        tee_pubkey: pubkey_bytes,
+        measurements: attestation_result.measurements,
+        checked_measurements: measurement_check.checks.into_iter().map(|c| c.key).collect(),
+    })
+}
+
+/// Extensionのsigned_jsonが期待するコンテンツ（`content_hash`）に束縛されていることを検証する。
+/// 仕様書 §5.1 Step 5
+///
+/// `payload.content_hash` と、`attributes` の `content_hash`（存在する場合）の双方を照合し、
+/// 別コンテンツのExtension結果への差し替えを検出する。
+/// 署名の検証は行わないため、[`verify_receipt`] と併用すること。
+pub fn verify_extension_content_hash(
+    signed_json: &SignedJson,
+    expected_content_hash: &str,
+) -> Result<(), VerifyError> {
+    if signed_json.core.protocol != EXTENSION_PROTOCOL {
+        return Err(VerifyError::NotExtension(signed_json.core.protocol.clone()));
+    }
+    let recorded = signed_json
+        .payload
+        .get("content_hash")
+        .and_then(|v| v.as_str())
+        .ok_or(VerifyError::MissingContentHash)?;
+    let attribute = signed_json
+        .attributes
+        .iter()
+        .find(|a| a.trait_type == "content_hash")
+        .map(|a| a.value.as_str());
+    for recorded in std::iter::once(recorded).chain(attribute) {
+        if !recorded.eq_ignore_ascii_case(expected_content_hash) {
+            return Err(VerifyError::ContentHashMismatch {
+                expected: expected_content_hash.to_string(),
+                recorded: recorded.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 補助入力から `extension_input_hash` を計算する。
+/// 仕様書 §5.1 Step 5, §7.1 補助入力の分配
+///
+/// `input` はクライアントが `extension_inputs[extension_id]` に指定した値。
+/// - インライン入力: 正規化JSON（[`title_types::canonical_json`]）のSHA-256。
+///   キーの順序や空白の違いは結果に影響しない
+/// - 参照入力（`{"ref_url", "sha256"}`）: TEEは取得したバイト列をそのまま用いるため、参照の `sha256`。
+///   参照先の内容自体は [`title_crypto::sha256`] で取得データと照合すること
+pub fn extension_input_hash(input: &serde_json::Value) -> String {
+    let reference = input
+        .as_object()
+        .filter(|o| o.contains_key("ref_url"))
+        .and_then(|o| o.get("sha256"))
+        .and_then(|v| v.as_str());
+    match reference {
+        Some(sha256) => format!("0x{}", sha256.strip_prefix("0x").unwrap_or(sha256).to_ascii_lowercase()),
+        None => format!(
+            "0x{}",
+            hex::encode(title_crypto::sha256(&title_types::canonical_json(input)))
+        ),
+    }
+}
+
+/// Extensionのsigned_jsonに記録された `extension_input_hash` が、元の補助入力と一致することを検証する。
+/// 仕様書 §5.1 Step 5
+///
+/// 署名の検証は行わないため、[`verify_receipt`] と併用すること。
+pub fn verify_extension_input(
+    signed_json: &SignedJson,
+    input: &serde_json::Value,
+) -> Result<(), VerifyError> {
+    let recorded = signed_json
+        .payload
+        .get("extension_input_hash")
+        .and_then(|v| v.as_str())
+        .ok_or(VerifyError::MissingExtensionInputHash)?;
+    let computed = extension_input_hash(input);
+    if !recorded.eq_ignore_ascii_case(&computed) {
+        return Err(VerifyError::ExtensionInputMismatch {
+            recorded: recorded.to_string(),
+            computed,
+        });
+    }
+    Ok(())
+}
+
+/// モックTEEのAttestation Documentを共通結果に変換する。
+fn parse_mock_attestation(document: &[u8]) -> Result<AttestationResult, VerifyError> {
+    let doc: MockAttestationDocument = serde_json::from_slice(document)
+        .map_err(|e| decode_error("tee_attestation", e.to_string()))?;
+    Ok(AttestationResult {
+        tee_type: MOCK_TEE_TYPE.to_string(),
+        measurements: BTreeMap::from([
+            ("PCR0".to_string(), doc.pcr0),
+            ("PCR1".to_string(), doc.pcr1),
+            ("PCR2".to_string(), doc.pcr2),
+        ]),
+        public_key: Some(doc.signing_pubkey),
+        user_data: Some(doc.encryption_pubkey),
+        nonce: None,
+        timestamp: None,
+    })
+}
+
+fn decode_error(field: &'static str, reason: String) -> VerifyError {
+    VerifyError::Decode { field, reason }
+}
+
+fn b64() -> &'static base64::engine::GeneralPurpose {
+    &base64::engine::general_purpose::STANDARD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base58::ToBase58;
+    use ed25519_dalek::{Signer, SigningKey};
+    use title_types::{Attribute, SignedJsonCore};
+
+    /// モックTEEと同じ形式のAttestationを生成する。
+    fn mock_attestation(signing_pubkey: &[u8]) -> String {
+        let doc = serde_json::json!({
+            "module_id": "mock-tee",
+            "pcr0": vec![0u8; 48],
+            "pcr1": vec![0u8; 48],
+            "pcr2": vec![0u8; 48],
+            "signing_pubkey": signing_pubkey,
+            "encryption_pubkey": vec![7u8; 32],
+        });
+        b64().encode(serde_json::to_vec(&doc).unwrap())
+    }
+
+    /// `signing_key` で署名し、`attested_pubkey` を束縛するAttestationを持つレシートを生成する。
+    fn create_receipt(signing_key: &SigningKey, attested_pubkey: &[u8]) -> SignedJson {
+        let payload = serde_json::json!({
+            "content_hash": "0xabcd",
+            "content_type": "image/jpeg",
+            "creator_wallet": "11111111111111111111111111111111",
+            "nodes": [],
+            "links": [],
+        });
+        let attributes = vec![Attribute {
+            trait_type: "protocol".to_string(),
+            value: "Title-v1".to_string(),
+        }];
+        let sign_bytes = title_types::canonical_json(&serde_json::json!({
+            "payload": payload,
+            "attributes": attributes,
+        }));
+        let signature = signing_key.sign(&title_crypto::domain_separated_message(
+            title_crypto::SignatureDomain::SignedJson,
+            &sign_bytes,
+        ));
+
+        SignedJson {
+            core: SignedJsonCore {
+                protocol: "Title-v1".to_string(),
+                tee_type: MOCK_TEE_TYPE.to_string(),
+                tee_pubkey: signing_key.verifying_key().to_bytes().to_base58(),
+                tee_signature: b64().encode(signature.to_bytes()),
+                tee_attestation: mock_attestation(attested_pubkey),
+            },
+            payload,
+            attributes,
+        }
+    }
+
+    fn mock_options() -> VerifyOptions {
+        VerifyOptions {
+            allow_mock: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_valid_receipt() {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let receipt = create_receipt(&key, key.verifying_key().as_bytes());
+
+        let options = VerifyOptions {
+            trusted_tee_pubkey: Some(key.verifying_key().to_bytes()),
+            expected_measurements: BTreeMap::from([("PCR0".to_string(), vec![0u8; 48])]),
+            allow_mock: true,
+            trust_policy: None,
+        };
+        let result = verify_receipt(&receipt, &options).unwrap();
+        assert_eq!(result.tee_type, "mock");
+        assert_eq!(result.tee_pubkey, key.verifying_key().to_bytes());
+        assert_eq!(result.checked_measurements, vec!["PCR0".to_string()]);
+    }
+
+    #[test]
+    fn test_attestation_pubkey_mismatch() {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let other = SigningKey::generate(&mut rand::rngs::OsRng);
+        let receipt = create_receipt(&key, other.verifying_key().as_bytes());
+
+        let err = verify_receipt(&receipt, &mock_options()).unwrap_err();
+        assert!(matches!(err, VerifyError::AttestationKeyMismatch));
+    }
+
+    #[test]
+    fn test_tampered_payload_rejected() {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let mut receipt = create_receipt(&key, key.verifying_key().as_bytes());
+        receipt.payload["creator_wallet"] = serde_json::json!("attacker");
+
+        let err = verify_receipt(&receipt, &mock_options()).unwrap_err();
+        assert!(matches!(err, VerifyError::InvalidSignature));
+    }
+
+    #[test]
+    fn test_mock_requires_opt_in() {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let receipt = create_receipt(&key, key.verifying_key().as_bytes());
+
+        let err = verify_receipt(&receipt, &VerifyOptions::default()).unwrap_err();
+        assert!(matches!(err, VerifyError::MockNotAllowed));
+    }
+
+    #[test]
+    fn test_measurement_mismatch() {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let receipt = create_receipt(&key, key.verifying_key().as_bytes());
+
+        let options = VerifyOptions {
+            expected_measurements: BTreeMap::from([("PCR0".to_string(), vec![1u8; 48])]),
+            ..mock_options()
+        };
+        let err = verify_receipt(&receipt, &options).unwrap_err();
+        match err {
+            VerifyError::MeasurementMismatch(keys) => assert_eq!(keys, vec!["PCR0".to_string()]),
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn test_trust_policy_applied() {
+        use std::collections::BTreeSet;
+        use title_crypto::attestation::policy::OperatorTrustPolicy;
+
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let receipt = create_receipt(&key, key.verifying_key().as_bytes());
+
+        // mock AttestationはPCR0〜2を含むがタイムスタンプを持たない
+        let options = VerifyOptions {
+            trust_policy: Some(Arc::new(OperatorTrustPolicy {
+                required_measurements: BTreeSet::from(["PCR2".to_string()]),
+                ..Default::default()
+            })),
+            ..mock_options()
+        };
+        assert!(verify_receipt(&receipt, &options).is_ok());
+
+        let options = VerifyOptions {
+            trust_policy: Some(Arc::new(OperatorTrustPolicy {
+                max_age_ms: Some(60_000),
+                ..Default::default()
+            })),
+            ..mock_options()
+        };
+        let err = verify_receipt(&receipt, &options).unwrap_err();
+        assert!(matches!(err, VerifyError::Policy(PolicyError::MissingTimestamp)), "{err}");
+    }
+
+    #[test]
+    fn test_extension_input_hash_verifies_original_and_rejects_tampered() {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let mut receipt = create_receipt(&key, key.verifying_key().as_bytes());
+        // TEEがWASMに渡すバイト列（正規化JSON）のハッシュ
+        let canonical = br#"{"hashes":["0x01","0x02"],"threshold":10}"#;
+        receipt.payload["extension_input_hash"] =
+            serde_json::json!(format!("0x{}", hex::encode(title_crypto::sha256(canonical))));
+
+        // キーの順序・空白が異なっても同じ入力として検証できる
+        let original: serde_json::Value =
+            serde_json::from_str(r#"{ "threshold": 10, "hashes": ["0x01", "0x02"] }"#).unwrap();
+        assert!(verify_extension_input(&receipt, &original).is_ok());
+
+        let tampered = serde_json::json!({"threshold": 11, "hashes": ["0x01", "0x02"]});
+        let err = verify_extension_input(&receipt, &tampered).unwrap_err();
+        assert!(matches!(err, VerifyError::ExtensionInputMismatch { .. }), "{err}");
+
+        // 参照入力は参照のsha256と照合する
+        let reference = serde_json::json!({
+            "ref_url": "https://refs.example.com/input.json",
+            "sha256": hex::encode(title_crypto::sha256(canonical)).to_uppercase(),
+        });
+        assert!(verify_extension_input(&receipt, &reference).is_ok());
+
+        // 補助入力なしで実行されたレシート
+        receipt.payload.as_object_mut().unwrap().remove("extension_input_hash");
+        let err = verify_extension_input(&receipt, &original).unwrap_err();
+        assert!(matches!(err, VerifyError::MissingExtensionInputHash));
+    }
+
+    #[test]
+    fn test_extension_content_hash_binding() {
+        let key = SigningKey::generate(&mut rand::rngs::OsRng);
+        let mut receipt = create_receipt(&key, key.verifying_key().as_bytes());
+
+        // Coreのsigned_jsonは対象外
+        let err = verify_extension_content_hash(&receipt, "0xabcd").unwrap_err();
+        assert!(matches!(err, VerifyError::NotExtension(_)), "{err}");
+
+        receipt.core.protocol = EXTENSION_PROTOCOL.to_string();
+        receipt.attributes.push(Attribute {
+            trait_type: "content_hash".to_string(),
+            value: "0xabcd".to_string(),
+        });
+        assert!(verify_extension_content_hash(&receipt, "0xABCD").is_ok());
+
+        // 別コンテンツのExtension結果
+        let err = verify_extension_content_hash(&receipt, "0x1234").unwrap_err();
+        assert!(matches!(err, VerifyError::ContentHashMismatch { .. }), "{err}");
+
+        // attributesだけが別コンテンツを指す
+        receipt.attributes.last_mut().unwrap().value = "0x1234".to_string();
+        let err = verify_extension_content_hash(&receipt, "0xabcd").unwrap_err();
+        assert!(
+            matches!(&err, VerifyError::ContentHashMismatch { recorded, .. } if recorded == "0x1234"),
+            "{err}"
+        );
+
+        receipt.payload.as_object_mut().unwrap().remove("content_hash");
+        let err = verify_extension_content_hash(&receipt, "0xabcd").unwrap_err();
+        assert!(matches!(err, VerifyError::MissingContentHash), "{err}");
+    }
+}
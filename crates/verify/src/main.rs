@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! title-verify: signed_jsonのオフライン検証CLI。
+//!
+//! 仕様書 §5.1 Step 4, §5.2 Step 4
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use base58::{FromBase58, ToBase58};
+use clap::Parser;
+
+use title_types::SignedJson;
+use title_verify::{
+    verify_extension_content_hash, verify_extension_input, verify_receipt, VerifyOptions,
+};
+
+#[derive(Parser)]
+#[command(name = "title-verify", about = "Title Protocol signed_json オフライン検証")]
+struct Args {
+    /// signed_jsonファイルのパス（"-" で標準入力）
+    signed_json: PathBuf,
+    /// 信頼するTEE署名用公開鍵 (Base58)
+    #[arg(long)]
+    tee_pubkey: Option<String>,
+    /// 期待測定値 (JSON文字列, 例: '{"PCR0":"abcd..."}')
+    #[arg(long)]
+    expected_measurements: Option<String>,
+    /// tee_type=mock のAttestationを受け入れる（ローカル開発用）
+    #[arg(long)]
+    allow_mock: bool,
+    /// Extensionに渡した補助入力（extension_inputs[extension_id] のJSONファイル）。
+    /// 指定時は payload の extension_input_hash と照合する
+    #[arg(long)]
+    extension_input: Option<PathBuf>,
+    /// 対象コンテンツのcontent_hash（"0x" + hex）。
+    /// 指定時は Extension の signed_json がこのコンテンツに束縛されていることを照合する
+    #[arg(long)]
+    content_hash: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("検証失敗: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let input = read_input(&args.signed_json)?;
+    let signed_json: SignedJson =
+        serde_json::from_str(&input).map_err(|e| format!("signed_jsonのパースに失敗: {e}"))?;
+
+    let options = VerifyOptions {
+        trusted_tee_pubkey: args.tee_pubkey.as_deref().map(parse_pubkey).transpose()?,
+        expected_measurements: args
+            .expected_measurements
+            .as_deref()
+            .map(parse_measurements)
+            .transpose()?
+            .unwrap_or_default(),
+        allow_mock: args.allow_mock,
+        trust_policy: None,
+    };
+
+    let result = verify_receipt(&signed_json, &options).map_err(|e| e.to_string())?;
+
+    if let Some(content_hash) = &args.content_hash {
+        verify_extension_content_hash(&signed_json, content_hash).map_err(|e| e.to_string())?;
+    }
+    if let Some(path) = &args.extension_input {
+        let input: serde_json::Value = serde_json::from_str(&read_input(path)?)
+            .map_err(|e| format!("補助入力のパースに失敗: {e}"))?;
+        verify_extension_input(&signed_json, &input).map_err(|e| e.to_string())?;
+    }
+
+    println!("OK");
+    println!("  tee_type:   {}", result.tee_type);
+    println!("  tee_pubkey: {}", result.tee_pubkey.to_base58());
+    for (key, value) in &result.measurements {
+        let mark = if result.checked_measurements.contains(key) { " (一致)" } else { "" };
+        println!("  {key}: {}{mark}", hex::encode(value));
+    }
+    if args.content_hash.is_some() {
+        println!("  content_hash: 一致");
+    }
+    if args.extension_input.is_some() {
+        println!("  extension_input_hash: 一致");
+    }
+    Ok(())
+}
+
+fn read_input(path: &PathBuf) -> Result<String, String> {
+    if path.as_os_str() == "-" {
+        let mut buf = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buf)
+            .map_err(|e| format!("標準入力の読み込みに失敗: {e}"))?;
+        Ok(buf)
+    } else {
+        std::fs::read_to_string(path)
+            .map_err(|e| format!("{}の読み込みに失敗: {e}", path.display()))
+    }
+}
+
+fn parse_pubkey(s: &str) -> Result<[u8; 32], String> {
+    s.from_base58()
+        .map_err(|e| format!("--tee-pubkeyのBase58デコードに失敗: {e:?}"))?
+        .try_into()
+        .map_err(|_| "--tee-pubkeyは32バイトである必要があります".to_string())
+}
+
+fn parse_measurements(s: &str) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let raw: BTreeMap<String, String> = serde_json::from_str(s)
+        .map_err(|e| format!("--expected-measurementsのパースに失敗: {e}"))?;
+    raw.into_iter()
+        .map(|(key, value)| {
+            hex::decode(value.trim_start_matches("0x"))
+                .map(|bytes| (key.clone(), bytes))
+                .map_err(|e| format!("{key}のhexデコードに失敗: {e}"))
+        })
+        .collect()
+}
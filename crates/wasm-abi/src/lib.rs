@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Title Protocol WASM ABI
+//!
+//! 仕様書 §7.1
+//!
+//! TEEホスト（`title-wasm-host`）とExtension WASMモジュール（`no_std`）の双方から使用する、
+//! 結果バッファのレイアウト定義。フォーマットはこのクレートにのみ存在する。
+//!
+//! ## 結果バッファフォーマット
+//! `[4B LE: len][bytes...]`
+//!
+//! WASMエクスポート関数（`process`, `title_extension_id`）はこのバッファへのポインタを返し、
+//! ホストは線形メモリから本体を取り出す。
+//!
+//! - WASM側: [`ResultBuffer::write`] で確保・書き込みしてポインタを返す
+//! - ホスト側: [`ResultBuffer::read`] で線形メモリから本体を取り出す
+
+#![no_std]
+
+/// 長さプレフィックスのバイト数。
+pub const HEADER_LEN: usize = 4;
+
+/// ABI v1: 戻り値 `0` のみがエラーを表す。
+pub const WASM_ABI_V1: i32 = 1;
+
+/// ABI v2: 負の戻り値がエラーコードを表す。
+pub const WASM_ABI_V2: i32 = 2;
+
+/// ホストが同時に実行可能なABIバージョンの一覧。
+/// これ以外のバージョンを申告したモジュールは実行前に拒否される。
+pub const SUPPORTED_ABI_VERSIONS: &[i32] = &[WASM_ABI_V1, WASM_ABI_V2];
+
+/// 結果バッファの読み書きエラー。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultBufferError {
+    /// 長さプレフィックスが線形メモリの範囲外
+    InvalidPointer,
+    /// 本体が空、または線形メモリの範囲外
+    InvalidBody,
+    /// 書き込み先の領域が不足している
+    BufferTooSmall,
+}
+
+impl core::fmt::Display for ResultBufferError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidPointer => f.write_str("結果ポインタが不正です"),
+            Self::InvalidBody => f.write_str("結果バッファが不正です"),
+            Self::BufferTooSmall => f.write_str("結果バッファの領域が不足しています"),
+        }
+    }
+}
+
+/// 長さプレフィックス付き結果バッファの本体。
+/// 仕様書 §7.1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResultBuffer<'a> {
+    body: &'a [u8],
+}
+
+impl<'a> ResultBuffer<'a> {
+    /// 本体バイト列から結果バッファを作成する。
+    pub fn new(body: &'a [u8]) -> Self {
+        Self { body }
+    }
+
+    /// 本体バイト列を返す。
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.body
+    }
+
+    /// エンコード後の全長（プレフィックス込み）を返す。
+    pub fn encoded_len(&self) -> usize {
+        HEADER_LEN + self.body.len()
+    }
+
+    /// 本体長に対応する長さプレフィックスを返す。
+    /// 本体を先に書き込んでから前置する場合（ホスト関数の出力を直接受ける場合等）に使用する。
+    pub fn encode_header(body_len: usize) -> [u8; HEADER_LEN] {
+        (body_len as u32).to_le_bytes()
+    }
+
+    /// `out` の先頭に `[4B LE: len][body...]` を書き込み、書き込んだバイト数を返す。
+    pub fn encode_into(&self, out: &mut [u8]) -> Result<usize, ResultBufferError> {
+        let total = self.encoded_len();
+        let dst = out.get_mut(..total).ok_or(ResultBufferError::BufferTooSmall)?;
+        dst[..HEADER_LEN].copy_from_slice(&Self::encode_header(self.body.len()));
+        dst[HEADER_LEN..].copy_from_slice(self.body);
+        Ok(total)
+    }
+
+    /// 線形メモリ `mem` の `ptr` にある結果バッファから本体を取り出す。
+    /// 本体が空の場合はエラーとする。
+    pub fn read(mem: &'a [u8], ptr: u32) -> Result<Self, ResultBufferError> {
+        let start = ptr as usize;
+        let header = mem
+            .get(start..start.saturating_add(HEADER_LEN))
+            .ok_or(ResultBufferError::InvalidPointer)?;
+        let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+
+        let body_start = start + HEADER_LEN;
+        match mem.get(body_start..body_start.saturating_add(len)) {
+            Some(body) if len > 0 => Ok(Self { body }),
+            _ => Err(ResultBufferError::InvalidBody),
+        }
+    }
+
+    /// `alloc` で線形メモリを確保して結果バッファを書き込み、ポインタを返す（WASM側）。
+    /// 確保に失敗した場合（`alloc` が0を返した場合）は `None`。
+    #[cfg(target_arch = "wasm32")]
+    pub fn write(&self, alloc: impl FnOnce(u32) -> u32) -> Option<u32> {
+        let total = self.encoded_len();
+        let ptr = alloc(total as u32);
+        if ptr == 0 {
+            return None;
+        }
+        // SAFETY: ptr は直前の alloc で確保した total バイトの領域（wasm32では線形メモリのアドレス）
+        let out = unsafe { core::slice::from_raw_parts_mut(ptr as *mut u8, total) };
+        self.encode_into(out).ok()?;
+        Some(ptr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_read_roundtrip() {
+        let json = br#"{"phash":"0x00ff"}"#;
+        let mut mem = [0u8; 64];
+        let ptr = 8;
+
+        let written = ResultBuffer::new(json)
+            .encode_into(&mut mem[ptr..])
+            .unwrap();
+        assert_eq!(written, HEADER_LEN + json.len());
+        assert_eq!(&mem[ptr..ptr + HEADER_LEN], &(json.len() as u32).to_le_bytes());
+
+        let read = ResultBuffer::read(&mem, ptr as u32).unwrap();
+        assert_eq!(read.as_bytes(), json);
+    }
+
+    #[test]
+    fn test_read_rejects_invalid_buffers() {
+        let mut mem = [0u8; 16];
+        assert_eq!(ResultBuffer::read(&mem, 14), Err(ResultBufferError::InvalidPointer));
+        // 本体長0
+        assert_eq!(ResultBuffer::read(&mem, 0), Err(ResultBufferError::InvalidBody));
+        // 本体がメモリ外にはみ出す
+        mem[..HEADER_LEN].copy_from_slice(&ResultBuffer::encode_header(100));
+        assert_eq!(ResultBuffer::read(&mem, 0), Err(ResultBufferError::InvalidBody));
+    }
+
+    #[test]
+    fn test_encode_into_rejects_short_output() {
+        let mut out = [0u8; 5];
+        assert_eq!(
+            ResultBuffer::new(b"ab").encode_into(&mut out),
+            Err(ResultBufferError::BufferTooSmall)
+        );
+    }
+}
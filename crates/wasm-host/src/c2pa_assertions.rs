@@ -0,0 +1,169 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # C2PAアサーションラベルの列挙
+//!
+//! 仕様書 §7.1, §7.4
+//!
+//! コンテンツ内のC2PAアクティブマニフェストに含まれるアサーションのラベルを記録順に列挙する。
+//! WASM内で生バイト列を走査してラベルを探すのは不確実なため、ホストがc2pa-rsで解釈して返す。
+//! 列挙規則はCore（[`title_core::active_assertion_labels`]）と共通で、全てのC2PA対応フォーマットを扱う。
+//!
+//! アサーションの内容のパース・署名検証は行わない（ラベルの列挙のみ）。
+
+/// MIMEタイプが不明な場合に仮定するフォーマット
+const DEFAULT_MIME_TYPE: &str = "image/jpeg";
+
+/// アクティブマニフェストに含まれるアサーションのラベルを記録順に返す。
+///
+/// `mime_type` はTEEが検出したコンテンツのMIMEタイプ（不明な場合はJPEGとして扱う）。
+/// マニフェストストアが `max_manifest_store_bytes` を超える場合は解析せずにエラーを返す。
+///
+/// # 戻り値
+/// * `Ok(labels)` - アサーションがない場合は空
+/// * `Err` - 構造エラー（C2PAデータなし、マニフェストストアの上限超過等）
+pub fn list_assertion_labels(
+    content: &[u8],
+    mime_type: Option<&str>,
+    max_manifest_store_bytes: u64,
+) -> Result<Vec<String>, String> {
+    title_core::active_assertion_labels(
+        content,
+        mime_type.unwrap_or(DEFAULT_MIME_TYPE),
+        max_manifest_store_bytes,
+    )
+    .map_err(|e| e.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// テスト
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERTS: &[u8] = include_bytes!("../../../tests/fixtures/certs/chain.pem");
+    const PRIVATE_KEY: &[u8] = include_bytes!("../../../tests/fixtures/certs/ee.key");
+    const TEST_IMAGE: &[u8] = include_bytes!("../../../tests/fixtures/test_4x4.jpg");
+    const DEFAULT_MAX: u64 = title_core::DEFAULT_MAX_MANIFEST_STORE_BYTES;
+
+    /// training-mining・CreativeWorkアサーション付きのC2PA署名済みJPEGを生成する。
+    fn signed_jpeg() -> Vec<u8> {
+        let manifest_json = serde_json::json!({
+            "title": "test-assertions.jpg",
+            "format": "image/jpeg",
+            "claim_generator_info": [{"name": "wasm-host-test", "version": "0.1"}],
+            "assertions": [
+                {
+                    "label": "c2pa.training-mining",
+                    "data": {"entries": {"c2pa.ai_training": {"use": "notAllowed"}}}
+                },
+                {
+                    "label": "stds.schema-org.CreativeWork",
+                    "data": {"@context": "https://schema.org", "@type": "CreativeWork"}
+                }
+            ]
+        })
+        .to_string();
+        let mut builder = c2pa::Builder::from_json(&manifest_json).unwrap();
+        let signer =
+            c2pa::create_signer::from_keys(CERTS, PRIVATE_KEY, c2pa::SigningAlg::Ed25519, None)
+                .unwrap();
+        let mut dest = std::io::Cursor::new(Vec::new());
+        builder
+            .sign(signer.as_ref(), "image/jpeg", &mut std::io::Cursor::new(TEST_IMAGE), &mut dest)
+            .unwrap();
+        dest.into_inner()
+    }
+
+    #[test]
+    fn test_list_assertion_labels() {
+        let labels =
+            list_assertion_labels(&signed_jpeg(), Some("image/jpeg"), DEFAULT_MAX).unwrap();
+        assert!(labels.iter().any(|l| l == "c2pa.training-mining"), "{labels:?}");
+        assert!(labels.iter().any(|l| l == "stds.schema-org.CreativeWork"), "{labels:?}");
+        // Coreと同じ列挙規則（ハードバインディングのアサーションは含まない）
+        assert_eq!(
+            labels,
+            title_core::active_assertion_labels(&signed_jpeg(), "image/jpeg", DEFAULT_MAX).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_manifest_store_size_limit() {
+        let err = list_assertion_labels(&signed_jpeg(), Some("image/jpeg"), 16).unwrap_err();
+        assert!(err.contains("16"), "{err}");
+    }
+
+    #[test]
+    fn test_no_jumbf() {
+        let data = vec![0xFF, 0xD8, 0xFF, 0xD9];
+        assert!(list_assertion_labels(&data, None, DEFAULT_MAX).is_err());
+    }
+
+    /// get_content_featureを `calls` 回呼び出し、最後の出力JSONをそのまま結果として返すWASM。
+    /// エラーコードが返った場合は `{"code": <code>}` を返す。
+    fn host_op_wat(calls: u32) -> Vec<u8> {
+        wat::parse_str(format!(
+            r#"(module
+            (import "env" "get_content_feature" (func $gcf (param i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 256) "{{\"op\":\"c2pa_assertion_labels\",\"max_length\":4096}}")
+            (data (i32.const 512) "{{\"code\":-5}}")
+            (func (export "process") (result i32)
+                (local $len i32)
+                (local $i i32)
+                (loop $again
+                    (local.set $len (call $gcf (i32.const 256) (i32.const 48) (i32.const 1028)))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br_if $again (i32.lt_u (local.get $i) (i32.const {calls}))))
+                (if (i32.lt_s (local.get $len) (i32.const 0))
+                    (then
+                        (i32.store (i32.const 508) (i32.const 11))
+                        (return (i32.const 508))))
+                (i32.store (i32.const 1024) (local.get $len))
+                (i32.const 1024)
+            )
+        )"#
+        ))
+        .unwrap()
+    }
+
+    fn runner() -> crate::WasmRunner {
+        crate::WasmRunner::new(100_000_000, 16 * 1024 * 1024, crate::DEFAULT_MAX_HOST_CALLS)
+    }
+
+    #[test]
+    fn test_host_op_reports_labels() {
+        let result = runner()
+            .execute(&host_op_wat(1), &signed_jpeg(), None, "process")
+            .unwrap();
+        let labels = result.output["assertions"].as_array().unwrap();
+        assert!(labels.contains(&serde_json::json!("c2pa.training-mining")));
+        assert!(labels.contains(&serde_json::json!("stds.schema-org.CreativeWork")));
+    }
+
+    /// 繰り返し呼び出してもC2PA解析は実行ごとに1回で、Fuelの課金も1回分であることを確認
+    #[test]
+    fn test_host_op_parses_once_per_execution() {
+        let content = signed_jpeg();
+        let parse_fuel = (content.len() as u64).div_ceil(1024) * crate::C2PA_PARSE_FUEL_PER_KIB;
+
+        let once = runner().execute(&host_op_wat(1), &content, None, "process").unwrap();
+        let many = runner().execute(&host_op_wat(200), &content, None, "process").unwrap();
+        assert_eq!(once.output, many.output);
+        assert!(once.fuel_consumed >= parse_fuel);
+        // 2回目以降はキャッシュを返すため、解析の課金は繰り返されない（増分はループの命令分のみ）
+        assert!(many.fuel_consumed - once.fuel_consumed < 199 * parse_fuel);
+    }
+
+    /// マニフェストストアの上限を超える場合は解析せずに -5 を返すことを確認
+    #[test]
+    fn test_host_op_enforces_manifest_store_size() {
+        let result = runner()
+            .with_max_manifest_store_bytes(16)
+            .execute(&host_op_wat(1), &signed_jpeg(), None, "process")
+            .unwrap();
+        assert_eq!(result.output, serde_json::json!({ "code": -5 }));
+    }
+}
@@ -67,13 +67,13 @@ const CAI_SIGNATURE_UUID: [u8; 16] = [
 ];
 
 /// JUMBF ボックスタイプ定数
-const BOX_JUMB: u32 = 0x6A75_6D62; // 'jumb'
-const BOX_JUMD: u32 = 0x6A75_6D64; // 'jumd'
-const BOX_CBOR: u32 = 0x6362_6F72; // 'cbor'
+pub(crate) const BOX_JUMB: u32 = 0x6A75_6D62; // 'jumb'
+pub(crate) const BOX_JUMD: u32 = 0x6A75_6D64; // 'jumd'
+pub(crate) const BOX_CBOR: u32 = 0x6362_6F72; // 'cbor'
 
 /// ボックスヘッダを読み取る（size, type）。
 /// 返り値: (box_size, box_type, header_size)
-fn read_box_header(data: &[u8], offset: usize) -> Option<(u64, u32, usize)> {
+pub(crate) fn read_box_header(data: &[u8], offset: usize) -> Option<(u64, u32, usize)> {
     if offset + 8 > data.len() {
         return None;
     }
@@ -183,7 +183,7 @@ fn find_signature_in_manifest(jumbf: &[u8], start: usize, end: usize) -> Option<
 }
 
 /// superbox内から最初のCBOR boxのデータを抽出する。
-fn find_cbor_box(jumbf: &[u8], start: usize, end: usize) -> Option<Vec<u8>> {
+pub(crate) fn find_cbor_box(jumbf: &[u8], start: usize, end: usize) -> Option<Vec<u8>> {
     let mut pos = start;
     while pos < end {
         let (box_size, box_type, box_hdr) = match read_box_header(jumbf, pos) {
@@ -0,0 +1,379 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # CAWG アイデンティティアサーションの検出
+//!
+//! 仕様書 §7.1, §7.4
+//!
+//! コンテンツ内のC2PAアクティブマニフェストから CAWG (Creator Assertions Working Group)
+//! のアイデンティティアサーション（`cawg.identity`）を探し、署名方式と発行者を抽出する。
+//!
+//! ## 抽出フロー
+//! 1. JPEG APP11からJUMBFデータを抽出
+//! 2. アクティブマニフェスト（最後のマニフェスト）のアサーションストアから
+//!    `cawg.identity`（重複時は `cawg.identity__N`）ラベルのアサーションを探す
+//! 3. CBORをパースし `signer_payload.sig_type` と `signature` を取得
+//! 4. 署名方式に応じて発行者を抽出する
+//!    - `cawg.x509.cose`: COSE_Sign1のx5chain先頭（署名者）証明書のIssuer DN
+//!    - `cawg.identity_claims_aggregation`: COSE_Sign1ペイロード（VC）の `issuer`
+//!
+//! アイデンティティアサーションの署名そのものの暗号検証は行わない（存在と発行者の抽出のみ）。
+
+use der::Decode;
+
+use crate::c2pa_cert::{
+    extract_jumbf_from_jpeg, extract_x5chain, find_cbor_box, read_box_header, BOX_JUMB, BOX_JUMD,
+};
+
+/// CAWGアイデンティティアサーションのラベル。
+pub const CAWG_IDENTITY_LABEL: &str = "cawg.identity";
+
+/// X.509証明書 + COSE署名による署名方式。
+pub const CAWG_X509_SIG_TYPE: &str = "cawg.x509.cose";
+
+/// Identity Claims Aggregation（VC）による署名方式。
+pub const CAWG_ICA_SIG_TYPE: &str = "cawg.identity_claims_aggregation";
+
+/// C2PAアサーションストアのラベル。
+pub(crate) const ASSERTION_STORE_LABEL: &str = "c2pa.assertions";
+
+/// 検出されたCAWGアイデンティティアサーション。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CawgIdentity {
+    /// `signer_payload.sig_type`（例: `cawg.x509.cose`）
+    pub sig_type: String,
+    /// 発行者（抽出できない署名方式の場合は None）
+    pub issuer: Option<String>,
+}
+
+/// コンテンツのアクティブマニフェストからCAWGアイデンティティアサーションを探す。
+///
+/// 抽出したJUMBF（マニフェストストア）が `max_manifest_store_bytes` を超える場合は、
+/// 走査せずにエラーを返す。
+///
+/// # 戻り値
+/// * `Ok(Some(_))` - アイデンティティアサーションを検出
+/// * `Ok(None)` - C2PAマニフェストは存在するがアイデンティティアサーションなし
+/// * `Err` - 構造エラー（C2PAデータなし、CBOR不正、マニフェストストアの上限超過等）
+pub fn find_cawg_identity(
+    content: &[u8],
+    max_manifest_store_bytes: u64,
+) -> Result<Option<CawgIdentity>, String> {
+    let jumbf = extract_jumbf_from_jpeg(content)
+        .ok_or_else(|| "JUMBFデータが見つかりません".to_string())?;
+    if jumbf.len() as u64 > max_manifest_store_bytes {
+        return Err(format!(
+            "マニフェストストアのサイズが上限を超えました: {} > {max_manifest_store_bytes} bytes",
+            jumbf.len()
+        ));
+    }
+
+    let (manifest_start, manifest_end) = find_active_manifest(&jumbf)
+        .ok_or_else(|| "アクティブマニフェストが見つかりません".to_string())?;
+
+    let Some((store_start, store_end)) =
+        find_child_superbox(&jumbf, manifest_start, manifest_end, |label| {
+            label == ASSERTION_STORE_LABEL
+        })
+    else {
+        return Ok(None);
+    };
+
+    let Some((assertion_start, assertion_end)) =
+        find_child_superbox(&jumbf, store_start, store_end, is_identity_label)
+    else {
+        return Ok(None);
+    };
+
+    let cbor = find_cbor_box(&jumbf, assertion_start, assertion_end)
+        .ok_or_else(|| "cawg.identityのCBORボックスが見つかりません".to_string())?;
+
+    parse_identity_assertion(&cbor).map(Some)
+}
+
+/// `cawg.identity` または `cawg.identity__N` ラベルかを判定する。
+fn is_identity_label(label: &str) -> bool {
+    match label.strip_prefix(CAWG_IDENTITY_LABEL) {
+        Some("") => true,
+        Some(suffix) => suffix.starts_with("__"),
+        None => false,
+    }
+}
+
+/// アクティブマニフェスト（トップレベルストアの最後のsuperbox）の
+/// 子ボックス範囲（description box直後から末尾まで）を返す。
+pub(crate) fn find_active_manifest(jumbf: &[u8]) -> Option<(usize, usize)> {
+    let (top_size, top_type, top_hdr) = read_box_header(jumbf, 0)?;
+    if top_type != BOX_JUMB {
+        return None;
+    }
+    let top_end = (top_size as usize).min(jumbf.len());
+    let (desc_size, desc_type, _) = read_box_header(jumbf, top_hdr)?;
+    if desc_type != BOX_JUMD {
+        return None;
+    }
+
+    let mut pos = top_hdr + desc_size as usize;
+    let mut last = None;
+    while pos < top_end {
+        let Some((size, box_type, _)) = read_box_header(jumbf, pos) else {
+            break;
+        };
+        if size == 0 {
+            break;
+        }
+        let end = (pos + size as usize).min(top_end);
+        if box_type == BOX_JUMB {
+            last = superbox_contents(jumbf, pos, end).map(|(start, _)| (start, end));
+        }
+        pos = end;
+    }
+    last
+}
+
+/// 指定範囲の子superboxのうち、ラベルが条件を満たす最初のものの
+/// 子ボックス範囲（description box直後から末尾まで）を返す。
+pub(crate) fn find_child_superbox(
+    jumbf: &[u8],
+    start: usize,
+    end: usize,
+    matches: impl Fn(&str) -> bool,
+) -> Option<(usize, usize)> {
+    let mut pos = start;
+    while pos < end {
+        let (size, box_type, _) = read_box_header(jumbf, pos)?;
+        if size == 0 {
+            break;
+        }
+        let box_end = (pos + size as usize).min(end);
+        if box_type == BOX_JUMB {
+            if let Some((contents_start, label)) = superbox_contents(jumbf, pos, box_end) {
+                if label.is_some_and(&matches) {
+                    return Some((contents_start, box_end));
+                }
+            }
+        }
+        pos = box_end;
+    }
+    None
+}
+
+/// superboxのdescription boxを読み、(子ボックス開始位置, ラベル) を返す。
+///
+/// description box: UUID(16B) + toggles(1B) + ラベル（toggles bit1が立っている場合、NUL終端）
+pub(crate) fn superbox_contents(jumbf: &[u8], pos: usize, end: usize) -> Option<(usize, Option<&str>)> {
+    let (_, _, hdr) = read_box_header(jumbf, pos)?;
+    let desc_pos = pos + hdr;
+    let (desc_size, desc_type, desc_hdr) = read_box_header(jumbf, desc_pos)?;
+    if desc_type != BOX_JUMD {
+        return None;
+    }
+    let desc_end = (desc_pos + desc_size as usize).min(end);
+    let toggles_pos = desc_pos + desc_hdr + 16;
+    if toggles_pos >= desc_end {
+        return Some((desc_end, None));
+    }
+
+    let label = if jumbf[toggles_pos] & 0x02 != 0 {
+        let label_bytes = &jumbf[toggles_pos + 1..desc_end];
+        let label_len = label_bytes.iter().position(|&b| b == 0).unwrap_or(label_bytes.len());
+        std::str::from_utf8(&label_bytes[..label_len]).ok()
+    } else {
+        None
+    };
+    Some((desc_end, label))
+}
+
+/// CBORマップから文字列キーの値を取得する。
+fn map_get<'a>(map: &'a [(ciborium::Value, ciborium::Value)], key: &str) -> Option<&'a ciborium::Value> {
+    map.iter()
+        .find(|(k, _)| k.as_text() == Some(key))
+        .map(|(_, v)| v)
+}
+
+/// アイデンティティアサーションのCBORから署名方式と発行者を抽出する。
+fn parse_identity_assertion(cbor: &[u8]) -> Result<CawgIdentity, String> {
+    let value: ciborium::Value = ciborium::from_reader(cbor)
+        .map_err(|e| format!("cawg.identityのCBORパースに失敗: {e}"))?;
+    let map = value
+        .as_map()
+        .ok_or_else(|| "cawg.identityがCBORマップではありません".to_string())?;
+
+    let sig_type = map_get(map, "signer_payload")
+        .and_then(|v| v.as_map())
+        .and_then(|sp| map_get(sp, "sig_type"))
+        .and_then(|v| v.as_text())
+        .ok_or_else(|| "signer_payload.sig_typeが見つかりません".to_string())?
+        .to_string();
+
+    let signature = map_get(map, "signature")
+        .and_then(|v| v.as_bytes())
+        .ok_or_else(|| "signatureが見つかりません".to_string())?;
+
+    let issuer = match sig_type.as_str() {
+        CAWG_X509_SIG_TYPE => Some(x509_issuer(signature)?),
+        CAWG_ICA_SIG_TYPE => Some(ica_issuer(signature)?),
+        _ => None,
+    };
+
+    Ok(CawgIdentity { sig_type, issuer })
+}
+
+/// `cawg.x509.cose`: x5chain先頭（署名者）証明書のIssuer DNを返す。
+fn x509_issuer(cose_sign1: &[u8]) -> Result<String, String> {
+    let certs = extract_x5chain(cose_sign1).map_err(|e| format!("x5chainの抽出に失敗: {e}"))?;
+    let leaf = certs
+        .first()
+        .ok_or_else(|| "x5chainが空です".to_string())?;
+    let cert = x509_cert::Certificate::from_der(leaf)
+        .map_err(|_| "X.509証明書のDERパースに失敗".to_string())?;
+    Ok(cert.tbs_certificate.issuer.to_string())
+}
+
+/// `cawg.identity_claims_aggregation`: COSE_Sign1ペイロード（VC JSON）の `issuer` を返す。
+fn ica_issuer(cose_sign1: &[u8]) -> Result<String, String> {
+    let value: ciborium::Value = ciborium::from_reader(cose_sign1)
+        .map_err(|_| "COSE_Sign1のCBORパースに失敗".to_string())?;
+    let array = match &value {
+        ciborium::Value::Tag(18, inner) => inner.as_array(),
+        other => other.as_array(),
+    }
+    .ok_or_else(|| "COSE_Sign1がCBOR配列ではありません".to_string())?;
+
+    let payload = array
+        .get(2)
+        .and_then(|v| v.as_bytes())
+        .ok_or_else(|| "COSE_Sign1のペイロードが見つかりません".to_string())?;
+    let credential: serde_json::Value = serde_json::from_slice(payload)
+        .map_err(|e| format!("VCのJSONパースに失敗: {e}"))?;
+
+    // issuer はURI文字列または {"id": URI} のいずれか
+    let issuer = &credential["issuer"];
+    issuer
+        .as_str()
+        .or_else(|| issuer["id"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| "VCにissuerが見つかりません".to_string())
+}
+
+// ---------------------------------------------------------------------------
+// テスト
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERTS: &[u8] = include_bytes!("../../../tests/fixtures/certs/chain.pem");
+    const PRIVATE_KEY: &[u8] = include_bytes!("../../../tests/fixtures/certs/ee.key");
+    const TEST_IMAGE: &[u8] = include_bytes!("../../../tests/fixtures/test_4x4.jpg");
+    const DEFAULT_MAX: u64 = title_core::DEFAULT_MAX_MANIFEST_STORE_BYTES;
+
+    /// テスト用C2PA署名済みJPEGを生成する（`with_identity` の場合はCAWGアサーション付き）。
+    fn signed_jpeg(with_identity: bool) -> Vec<u8> {
+        use c2pa::crypto::raw_signature::signer_from_cert_chain_and_private_key;
+        use c2pa::identity::builder::{IdentityAssertionBuilder, IdentityAssertionSigner};
+        use c2pa::identity::x509::X509CredentialHolder;
+
+        let manifest_json = serde_json::json!({
+            "title": "test-cawg.jpg",
+            "format": "image/jpeg",
+            "claim_generator_info": [{"name": "wasm-host-test", "version": "0.1"}]
+        })
+        .to_string();
+        let mut builder = c2pa::Builder::from_json(&manifest_json).unwrap();
+        let mut source = std::io::Cursor::new(TEST_IMAGE);
+        let mut dest = std::io::Cursor::new(Vec::new());
+
+        let raw_signer = || {
+            signer_from_cert_chain_and_private_key(
+                CERTS,
+                PRIVATE_KEY,
+                c2pa::SigningAlg::Ed25519,
+                None,
+            )
+            .unwrap()
+        };
+        let mut signer = IdentityAssertionSigner::new(raw_signer());
+        if with_identity {
+            let holder = X509CredentialHolder::from_raw_signer(raw_signer());
+            signer.add_identity_assertion(IdentityAssertionBuilder::for_credential_holder(holder));
+        }
+        builder
+            .sign(&signer, "image/jpeg", &mut source, &mut dest)
+            .unwrap();
+        dest.into_inner()
+    }
+
+    #[test]
+    fn test_find_x509_identity() {
+        let identity = find_cawg_identity(&signed_jpeg(true), DEFAULT_MAX)
+            .unwrap()
+            .expect("cawg.identityが検出されるべき");
+        assert_eq!(identity.sig_type, CAWG_X509_SIG_TYPE);
+        assert_eq!(identity.issuer.as_deref(), Some("CN=Title Protocol Test CA"));
+    }
+
+    #[test]
+    fn test_no_identity_assertion() {
+        assert_eq!(find_cawg_identity(&signed_jpeg(false), DEFAULT_MAX).unwrap(), None);
+    }
+
+    #[test]
+    fn test_manifest_store_size_limit() {
+        let err = find_cawg_identity(&signed_jpeg(true), 16).unwrap_err();
+        assert!(err.contains("上限"), "{err}");
+    }
+
+    #[test]
+    fn test_no_jumbf() {
+        let data = vec![0xFF, 0xD8, 0xFF, 0xD9];
+        assert!(find_cawg_identity(&data, DEFAULT_MAX).is_err());
+    }
+
+    /// get_content_featureの出力JSONをそのまま結果として返すWASM
+    fn host_op_wat() -> Vec<u8> {
+        wat::parse_str(
+            r#"(module
+            (import "env" "get_content_feature" (func $gcf (param i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 256) "{\"op\":\"c2pa_cawg_identity\",\"max_length\":512}")
+            (func (export "process") (result i32)
+                (local $len i32)
+                (local.set $len (call $gcf (i32.const 256) (i32.const 44) (i32.const 1028)))
+                (if (i32.lt_s (local.get $len) (i32.const 0))
+                    (then (return (i32.const 0))))
+                (i32.store (i32.const 1024) (local.get $len))
+                (i32.const 1024)
+            )
+        )"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_host_op_reports_identity() {
+        let runner =
+            crate::WasmRunner::new(100_000_000, 16 * 1024 * 1024, crate::DEFAULT_MAX_HOST_CALLS);
+
+        let result = runner
+            .execute(&host_op_wat(), &signed_jpeg(true), None, "process")
+            .unwrap();
+        assert_eq!(result.output["identity_present"], true);
+        assert_eq!(result.output["sig_type"], CAWG_X509_SIG_TYPE);
+        assert_eq!(result.output["issuer"], "CN=Title Protocol Test CA");
+
+        let result = runner
+            .execute(&host_op_wat(), &signed_jpeg(false), None, "process")
+            .unwrap();
+        assert_eq!(result.output["identity_present"], false);
+        assert!(result.output["issuer"].is_null());
+    }
+
+    #[test]
+    fn test_identity_label_variants() {
+        assert!(is_identity_label("cawg.identity"));
+        assert!(is_identity_label("cawg.identity__2"));
+        assert!(!is_identity_label("cawg.identity_claims"));
+        assert!(!is_identity_label("c2pa.actions"));
+    }
+}
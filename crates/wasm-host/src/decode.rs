@@ -72,6 +72,146 @@ pub fn decode(kind: DecoderKind, content: &[u8]) -> Result<DecodeResult, i32> {
     }
 }
 
+/// デコード済み画像をグレースケール（ITU-R BT.601）に変換し、
+/// `target_w` × `target_h` にバイリニア補間でリサイズする。
+/// 仕様書 §7.1
+///
+/// 出力は行優先の輝度値（`target_w * target_h` バイト）。
+/// チャネル数・サイズが不正な場合は `None`。
+pub fn grayscale_resize(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    channels: u32,
+    target_w: u32,
+    target_h: u32,
+) -> Option<Vec<u8>> {
+    use image::{DynamicImage, GrayImage, RgbImage, RgbaImage};
+
+    let gray = match channels {
+        1 => GrayImage::from_raw(width, height, data.to_vec())?,
+        3 => DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, data.to_vec())?).to_luma8(),
+        4 => DynamicImage::ImageRgba8(RgbaImage::from_raw(width, height, data.to_vec())?).to_luma8(),
+        _ => return None,
+    };
+
+    let resized = image::imageops::resize(
+        &gray,
+        target_w,
+        target_h,
+        image::imageops::FilterType::Triangle,
+    );
+    Some(resized.into_raw())
+}
+
+/// デコード済み画像をピクセルハッシュ用の正規形に変換する。
+/// 仕様書 §7.1
+///
+/// 1. アルファチャネルを除去しRGBに統一（グレースケールは3チャネルに複製）
+/// 2. `target_w` × `target_h` にバイリニア補間でリサイズ
+/// 3. 各チャネル値の上位 `quant_bits` ビットのみを残す（再エンコードによる微小な誤差を吸収）
+///
+/// 出力は行優先のRGB（`target_w * target_h * 3` バイト）。
+/// 入力が不正（チャネル数・サイズ不一致、`quant_bits` が1〜8の範囲外）の場合は `None`。
+pub fn canonical_rgb(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    channels: u32,
+    target_w: u32,
+    target_h: u32,
+    quant_bits: u32,
+) -> Option<Vec<u8>> {
+    use image::{DynamicImage, GrayImage, RgbImage, RgbaImage};
+
+    if !(1..=8).contains(&quant_bits) || target_w == 0 || target_h == 0 {
+        return None;
+    }
+
+    let rgb = match channels {
+        1 => DynamicImage::ImageLuma8(GrayImage::from_raw(width, height, data.to_vec())?).to_rgb8(),
+        3 => RgbImage::from_raw(width, height, data.to_vec())?,
+        4 => DynamicImage::ImageRgba8(RgbaImage::from_raw(width, height, data.to_vec())?).to_rgb8(),
+        _ => return None,
+    };
+
+    let resized = image::imageops::resize(
+        &rgb,
+        target_w,
+        target_h,
+        image::imageops::FilterType::Triangle,
+    );
+
+    let mask = 0xFFu8 << (8 - quant_bits);
+    Some(resized.into_raw().into_iter().map(|v| v & mask).collect())
+}
+
+// ---------------------------------------------------------------------------
+// プレビューハッシュ
+// ---------------------------------------------------------------------------
+
+/// プレビューハッシュのDCT入力サイズ（32×32グレースケール）
+const PREVIEW_DCT_SIZE: usize = 32;
+/// プレビューハッシュに用いる低周波ブロックサイズ（8×8 = 64bit）
+const PREVIEW_LOW_FREQ: usize = 8;
+
+/// UI表示用の軽量なプレビューハッシュ（64bit）を計算する。
+/// 仕様書 §5.1 Step 6
+///
+/// phash-v1と同じ手順（グレースケール32×32 → 2D DCT → 8×8低周波ブロックを平均と比較）で、
+/// 再エンコードやリサイズに対して安定した値を返す。暗号学的な `content_hash` とは異なり、
+/// コンテンツの同一性を保証するものではない。
+/// 画像としてデコードできない場合は `None`。
+pub fn preview_hash(content: &[u8]) -> Option<u64> {
+    let kind = detect(content)?;
+    let decoded = decode(kind, content).ok()?;
+    let m = &decoded.metadata;
+    let width = u32::from_le_bytes(m.get(0..4)?.try_into().ok()?);
+    let height = u32::from_le_bytes(m.get(4..8)?.try_into().ok()?);
+    let channels = u32::from_le_bytes(m.get(8..12)?.try_into().ok()?);
+    let gray = grayscale_resize(
+        &decoded.data,
+        width,
+        height,
+        channels,
+        PREVIEW_DCT_SIZE as u32,
+        PREVIEW_DCT_SIZE as u32,
+    )?;
+
+    // 分離型2D DCT-II（低周波ブロックのみ計算する）
+    let n = PREVIEW_DCT_SIZE as f64;
+    let basis = |k: usize, i: usize| {
+        let c = if k == 0 { (1.0 / n).sqrt() } else { (2.0 / n).sqrt() };
+        c * (std::f64::consts::PI * (2.0 * i as f64 + 1.0) * k as f64 / (2.0 * n)).cos()
+    };
+    let mut row_dct = [[0.0f64; PREVIEW_LOW_FREQ]; PREVIEW_DCT_SIZE];
+    for (y, row) in row_dct.iter_mut().enumerate() {
+        for (u, out) in row.iter_mut().enumerate() {
+            *out = (0..PREVIEW_DCT_SIZE)
+                .map(|x| gray[y * PREVIEW_DCT_SIZE + x] as f64 * basis(u, x))
+                .sum();
+        }
+    }
+    let mut values = [0.0f64; PREVIEW_LOW_FREQ * PREVIEW_LOW_FREQ];
+    for v in 0..PREVIEW_LOW_FREQ {
+        for u in 0..PREVIEW_LOW_FREQ {
+            values[v * PREVIEW_LOW_FREQ + u] = (0..PREVIEW_DCT_SIZE)
+                .map(|y| row_dct[y][u] * basis(v, y))
+                .sum();
+        }
+    }
+
+    // DC成分を除く63値の平均と比較
+    let mean = values[1..].iter().sum::<f64>() / (values.len() - 1) as f64;
+    Some(
+        values
+            .iter()
+            .enumerate()
+            .filter(|(_, &value)| value > mean)
+            .fold(0u64, |hash, (i, _)| hash | (1u64 << i)),
+    )
+}
+
 // ---------------------------------------------------------------------------
 // 画像デコーダー
 // ---------------------------------------------------------------------------
@@ -193,3 +333,74 @@ mod image_decoder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 量子化ビンの中央に位置する色で構成したブロック画像をJPEGエンコードする。
+    fn block_image_jpeg(quality: u8) -> Vec<u8> {
+        let colors = [[72u8, 136, 200], [200, 72, 136], [136, 200, 72], [40, 104, 168]];
+        let img = image::RgbImage::from_fn(256, 256, |x, y| {
+            image::Rgb(colors[((x / 128) + 2 * (y / 128)) as usize])
+        });
+        let mut buf = Cursor::new(Vec::new());
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality)
+            .encode_image(&img)
+            .unwrap();
+        buf.into_inner()
+    }
+
+    fn canonical_of(content: &[u8]) -> Vec<u8> {
+        let decoded = decode(detect(content).unwrap(), content).unwrap();
+        let m = &decoded.metadata;
+        let width = u32::from_le_bytes([m[0], m[1], m[2], m[3]]);
+        let height = u32::from_le_bytes([m[4], m[5], m[6], m[7]]);
+        let channels = u32::from_le_bytes([m[8], m[9], m[10], m[11]]);
+        canonical_rgb(&decoded.data, width, height, channels, 16, 16, 4).unwrap()
+    }
+
+    /// 異なる品質で再エンコードしたJPEGが同一の正規形になることを確認
+    #[test]
+    fn test_canonical_rgb_stable_across_jpeg_quality() {
+        let high = canonical_of(&block_image_jpeg(95));
+        let low = canonical_of(&block_image_jpeg(70));
+        assert_eq!(high.len(), 16 * 16 * 3);
+        assert_eq!(high, low);
+    }
+
+    /// アルファチャネルが除去され、量子化ビット数の範囲外は拒否されることを確認
+    #[test]
+    fn test_canonical_rgb_strips_alpha_and_validates_bits() {
+        let rgba = vec![0xFFu8, 0x80, 0x7F, 0x00];
+        assert_eq!(canonical_rgb(&rgba, 1, 1, 4, 1, 1, 4).unwrap(), vec![0xF0, 0x80, 0x70]);
+        assert!(canonical_rgb(&rgba, 1, 1, 4, 1, 1, 0).is_none());
+        assert!(canonical_rgb(&rgba, 1, 1, 4, 1, 1, 9).is_none());
+        assert!(canonical_rgb(&rgba, 2, 2, 4, 1, 1, 4).is_none());
+    }
+
+    /// 同一画像の再エンコードはcontent_hashが異なってもプレビューハッシュが近いことを確認
+    #[test]
+    fn test_preview_hash_stable_across_reencode() {
+        use sha2::{Digest, Sha256};
+
+        let img = image::RgbImage::from_fn(256, 256, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, ((x * y) / 256 % 256) as u8])
+        });
+        let encode = |quality: u8| {
+            let mut buf = Cursor::new(Vec::new());
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality)
+                .encode_image(&img)
+                .unwrap();
+            buf.into_inner()
+        };
+        let high = encode(95);
+        let low = encode(60);
+
+        assert_ne!(Sha256::digest(&high), Sha256::digest(&low));
+        let distance = (preview_hash(&high).unwrap() ^ preview_hash(&low).unwrap()).count_ones();
+        assert!(distance <= 4, "ハミング距離が大きすぎます: {distance}");
+
+        assert!(preview_hash(b"not an image").is_none());
+    }
+}
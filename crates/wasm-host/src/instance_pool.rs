@@ -0,0 +1,309 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # InstancePool（事前インスタンス化されたExtensionのプール）
+//!
+//! 仕様書 §7.1
+//!
+//! 既定の実行パスはExtension実行のたびに `Store`・`Linker`・`Instance` を新規作成する。
+//! 安全だがコストが高いため、高スループットが必要な場合に限り本プールを使用できる。
+//!
+//! ## 設計
+//!
+//! - Extension（WASMバイナリのSHA-256）ごとに、ホスト関数を解決済みの `InstancePre` を保持する。
+//!   呼び出しごとのLinker構築・import解決・コンパイルが不要になる。保持数の上限を超えた場合は
+//!   最も長く使われていないExtensionから破棄する（LRU）。
+//! - Engineはwasmtimeのプーリングアロケータを使用し、インスタンス・メモリのスロットを
+//!   事前確保する。スロットは `Store` の破棄時に返却され、次の呼び出しでは
+//!   データセグメントから再初期化された新しいメモリとして再利用される。
+//! - 実行はスロットを1つ確保してから開始する。空きスロットがない場合は、他の実行が
+//!   スロットを返却するまで待機する（中断要求があれば待機を打ち切る）。
+//!
+//! ## 決定性の要件
+//!
+//! プールは **信頼済みかつ決定的な** モジュール（Global Configの `trusted_wasm_modules` に
+//! 登録済みのもの）にのみ使用すること。決定的とは、出力が `content` と `extension_input` のみに
+//! 依存し、同一入力に対して常に同一の結果を返すことを指す。
+//! 呼び出し間でメモリ・グローバル変数はリセットされるが、スロットの再利用や
+//! 事前確保されたメモリ上限（`max_memory_bytes`）の違いで結果が変わるモジュールは
+//! 署名対象の再現性を損なうため、既定の新規Store実行パスを使用すること。
+
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+use wasmtime::{
+    Engine, InstanceAllocationStrategy, InstancePre, Linker, Module, PoolingAllocationConfig,
+};
+
+use crate::{InnerHostState, InterruptHandle, WasmError, WasmRunner};
+
+/// 事前確保するインスタンススロット数のデフォルト値。
+pub const DEFAULT_INSTANCE_POOL_SLOTS: u32 = 32;
+
+/// 保持する事前インスタンス化済みExtension数のデフォルト上限。
+pub const DEFAULT_INSTANCE_POOL_MAX_PREPARED: usize = 16;
+
+/// スロット待機中に中断要求を確認する間隔。
+const SLOT_WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 事前インスタンス化されたExtensionのプール。
+/// 仕様書 §7.1
+pub struct InstancePool {
+    /// プーリングアロケータを使用する共有Engine（Fuel制限有効）
+    engine: Engine,
+    /// 共有Engineに設定したWASM実行スタックの上限（バイト）
+    max_wasm_stack: usize,
+    /// 事前確保したインスタンススロット数（同時実行数の上限）
+    slots: u32,
+    /// 使用中のスロット数
+    slots_in_use: Mutex<u32>,
+    /// スロットの返却通知
+    slot_returned: Condvar,
+    /// 保持する事前インスタンス化済みExtension数の上限
+    max_prepared: usize,
+    /// (WASMバイナリのSHA-256, ホスト関数解決済みの InstancePre)。末尾ほど最近使用された
+    prepared: Mutex<VecDeque<([u8; 32], InstancePre<InnerHostState>)>>,
+}
+
+/// 確保したインスタンススロット。破棄時にスロットを返却する。
+/// 仕様書 §7.1
+pub(crate) struct SlotPermit<'a> {
+    pool: &'a InstancePool,
+}
+
+impl Drop for SlotPermit<'_> {
+    fn drop(&mut self) {
+        let mut in_use = self.pool.slots_in_use.lock().unwrap_or_else(|e| e.into_inner());
+        *in_use -= 1;
+        self.pool.slot_returned.notify_one();
+    }
+}
+
+impl InstancePool {
+    /// 指定スロット数・メモリ上限のInstancePoolを作成する。
+    /// 仕様書 §7.1
+    ///
+    /// # 引数
+    /// - `slots`: 同時に存在できるインスタンス数。超過した実行はスロットの返却を待つ
+    /// - `max_memory_bytes`: 1インスタンスあたりの線形メモリ上限（バイト）
+    ///
+    /// WASM実行スタックの上限は [`crate::DEFAULT_MAX_WASM_STACK`]。
+    pub fn new(slots: u32, max_memory_bytes: usize) -> Result<Self, WasmError> {
+        Self::with_max_wasm_stack(slots, max_memory_bytes, crate::DEFAULT_MAX_WASM_STACK)
+    }
+
+    /// 共有EngineのWASM実行スタック上限（バイト）を指定してInstancePoolを作成する。
+    /// 仕様書 §7.1
+    ///
+    /// このプールを使う [`WasmRunner`] には同じ値を [`WasmRunner::with_max_wasm_stack`] で
+    /// 設定すること（異なる場合は実行時エラー）。
+    pub fn with_max_wasm_stack(
+        slots: u32,
+        max_memory_bytes: usize,
+        max_wasm_stack: usize,
+    ) -> Result<Self, WasmError> {
+        let mut pooling = PoolingAllocationConfig::default();
+        pooling
+            .total_core_instances(slots)
+            .total_memories(slots)
+            .total_tables(slots)
+            .max_memory_size(max_memory_bytes);
+
+        let mut config = WasmRunner::engine_config(max_wasm_stack);
+        config.allocation_strategy(InstanceAllocationStrategy::Pooling(pooling));
+        let engine = Engine::new(&config)
+            .map_err(|e| WasmError::CompileError(format!("Engineの作成に失敗: {e}")))?;
+
+        Ok(Self {
+            engine,
+            max_wasm_stack,
+            slots,
+            slots_in_use: Mutex::new(0),
+            slot_returned: Condvar::new(),
+            max_prepared: DEFAULT_INSTANCE_POOL_MAX_PREPARED,
+            prepared: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    /// 保持する事前インスタンス化済みExtension数の上限を設定する（既定:
+    /// [`DEFAULT_INSTANCE_POOL_MAX_PREPARED`]）。
+    /// 仕様書 §7.1
+    ///
+    /// 上限を超えた場合は最も長く使われていないExtensionから破棄する。
+    pub fn with_max_prepared(mut self, max_prepared: usize) -> Self {
+        self.max_prepared = max_prepared;
+        self
+    }
+
+    /// プールが所有するEngineを返す。
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// 共有Engineに設定したWASM実行スタックの上限（バイト）を返す。
+    pub fn max_wasm_stack(&self) -> usize {
+        self.max_wasm_stack
+    }
+
+    /// 事前確保したインスタンススロット数を返す。
+    pub fn slots(&self) -> u32 {
+        self.slots
+    }
+
+    /// 事前インスタンス化済みのExtension数を返す（モニタリング用）。
+    pub fn prepared_count(&self) -> usize {
+        self.prepared.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// インスタンススロットを1つ確保する。空きがない場合は返却されるまで待機する。
+    /// 仕様書 §7.1
+    ///
+    /// 待機中に `interrupt` が中断された場合は [`WasmError::Interrupted`] を返す。
+    pub(crate) fn acquire_slot(
+        &self,
+        interrupt: Option<&InterruptHandle>,
+    ) -> Result<SlotPermit<'_>, WasmError> {
+        let mut in_use = self.slots_in_use.lock().unwrap_or_else(|e| e.into_inner());
+        while *in_use >= self.slots {
+            if interrupt.is_some_and(InterruptHandle::is_interrupted) {
+                return Err(WasmError::Interrupted);
+            }
+            in_use = self
+                .slot_returned
+                .wait_timeout(in_use, SLOT_WAIT_POLL_INTERVAL)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        *in_use += 1;
+        Ok(SlotPermit { pool: self })
+    }
+
+    /// ホスト関数解決済みの `InstancePre` を取得する。未準備ならコンパイル・リンクして格納する。
+    /// 仕様書 §7.1
+    pub(crate) fn get_or_prepare(
+        &self,
+        wasm_bytes: &[u8],
+    ) -> Result<InstancePre<InnerHostState>, WasmError> {
+        let key: [u8; 32] = Sha256::digest(wasm_bytes).into();
+
+        {
+            let mut prepared = self.prepared.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(pos) = prepared.iter().position(|(k, _)| *k == key) {
+                let entry = prepared.remove(pos).expect("position は範囲内");
+                let pre = entry.1.clone();
+                prepared.push_back(entry);
+                return Ok(pre);
+            }
+        }
+
+        // コンパイル・リンクはロック外で行う（同一モジュールの同時準備は許容する）
+        let module = Module::new(&self.engine, wasm_bytes)
+            .map_err(|e| WasmError::CompileError(e.to_string()))?;
+        let mut linker = Linker::new(&self.engine);
+        WasmRunner::register_host_functions(&mut linker)?;
+        let pre = linker
+            .instantiate_pre(&module)
+            .map_err(|e| WasmError::CompileError(format!("インポートの解決に失敗: {e}")))?;
+
+        if self.max_prepared > 0 {
+            let mut prepared = self.prepared.lock().unwrap_or_else(|e| e.into_inner());
+            if !prepared.iter().any(|(k, _)| *k == key) {
+                while prepared.len() >= self.max_prepared {
+                    prepared.pop_front();
+                }
+                prepared.push_back((key, pre.clone()));
+            }
+        }
+        Ok(pre)
+    }
+}
+
+impl std::fmt::Debug for InstancePool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InstancePool")
+            .field("slots", &self.slots)
+            .field("max_prepared", &self.max_prepared)
+            .field("prepared", &self.prepared_count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepares_each_module_once() {
+        let pool = InstancePool::new(4, 1024 * 1024).unwrap();
+        let wasm = wat::parse_str(r#"(module (func (export "f") (result i32) i32.const 1))"#)
+            .unwrap();
+
+        pool.get_or_prepare(&wasm).unwrap();
+        pool.get_or_prepare(&wasm).unwrap();
+        assert_eq!(pool.prepared_count(), 1);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used_module() {
+        let pool = InstancePool::new(4, 1024 * 1024).unwrap().with_max_prepared(2);
+        let module = |n: i32| {
+            wat::parse_str(format!(r#"(module (func (export "f") (result i32) i32.const {n}))"#))
+                .unwrap()
+        };
+        let (a, b, c) = (module(1), module(2), module(3));
+
+        pool.get_or_prepare(&a).unwrap();
+        pool.get_or_prepare(&b).unwrap();
+        pool.get_or_prepare(&a).unwrap(); // aを最近使用に更新
+        pool.get_or_prepare(&c).unwrap(); // bが破棄される
+        assert_eq!(pool.prepared_count(), 2);
+
+        let keys: Vec<[u8; 32]> = pool
+            .prepared
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(k, _)| *k)
+            .collect();
+        let key = |wasm: &[u8]| -> [u8; 32] { Sha256::digest(wasm).into() };
+        assert_eq!(keys, vec![key(&a), key(&c)]);
+    }
+
+    #[test]
+    fn test_waits_for_free_slot() {
+        let pool = std::sync::Arc::new(InstancePool::new(1, 1024 * 1024).unwrap());
+        let permit = pool.acquire_slot(None).unwrap();
+
+        let waiter = {
+            let pool = std::sync::Arc::clone(&pool);
+            std::thread::spawn(move || pool.acquire_slot(None).map(drop).is_ok())
+        };
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!waiter.is_finished(), "空きスロットがないのに確保できた");
+
+        drop(permit);
+        assert!(waiter.join().unwrap());
+    }
+
+    #[test]
+    fn test_slot_wait_stops_on_interrupt() {
+        let pool = InstancePool::new(1, 1024 * 1024).unwrap();
+        let _permit = pool.acquire_slot(None).unwrap();
+        let interrupt = InterruptHandle::new();
+        interrupt.interrupt();
+
+        let err = pool.acquire_slot(Some(&interrupt)).err().unwrap();
+        assert!(matches!(err, WasmError::Interrupted), "got {err:?}");
+    }
+
+    #[test]
+    fn test_rejects_unknown_import() {
+        let pool = InstancePool::new(4, 1024 * 1024).unwrap();
+        let wasm = wat::parse_str(r#"(module (import "env" "nope" (func)))"#).unwrap();
+
+        let err = pool.get_or_prepare(&wasm).err().unwrap();
+        assert!(matches!(err, WasmError::CompileError(_)), "got {err:?}");
+        assert_eq!(pool.prepared_count(), 0);
+    }
+}
@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # InterruptHandle（実行中のWASMの中断）
+//!
+//! 仕様書 §6.4, §7.1
+//!
+//! WASMの実行は同期的で、実行中はtokioタスクの打ち切りが届かない。
+//! 本ハンドルを [`crate::WasmRunner::with_interrupt`] で設定すると、別スレッドからの
+//! [`InterruptHandle::interrupt`] により実行中のWASMを [`crate::WasmError::Interrupted`] で停止できる。
+//!
+//! ## 設計
+//!
+//! wasmtimeのエポック割り込みを使用する。各Storeは現在のエポック+1を期限とし、
+//! 中断要求はハンドルに紐づいた実行中のEngineのエポックを進める。期限に達したStoreは
+//! 自身のハンドルが中断済みかを確認し、中断済みならトラップ、そうでなければ期限を延長して続行する
+//! （共有Engineで他の実行が中断された場合も誤って停止しない）。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use wasmtime::Engine;
+
+/// 実行中のWASMを外部から中断するためのハンドル。
+/// 仕様書 §6.4, §7.1
+///
+/// クローンは同じ中断状態を共有する。一度中断したハンドルで開始した実行は即座に中断される。
+#[derive(Clone, Default)]
+pub struct InterruptHandle {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// 中断要求の有無
+    interrupted: AtomicBool,
+    /// このハンドルで実行したEngine（中断時にエポックを進める対象）
+    engines: Mutex<Vec<Engine>>,
+}
+
+/// 中断要求によるトラップ。
+#[derive(Debug)]
+pub(crate) struct ExecutionInterrupted;
+
+impl std::fmt::Display for ExecutionInterrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("WASM実行が中断されました")
+    }
+}
+
+impl std::error::Error for ExecutionInterrupted {}
+
+impl std::fmt::Debug for InterruptHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterruptHandle")
+            .field("interrupted", &self.is_interrupted())
+            .finish()
+    }
+}
+
+impl InterruptHandle {
+    /// 新しいハンドルを作成する。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 中断を要求する。実行中のWASMは次のエポック確認で停止する。
+    pub fn interrupt(&self) {
+        self.inner.interrupted.store(true, Ordering::SeqCst);
+        let engines = self.inner.engines.lock().unwrap_or_else(|e| e.into_inner());
+        for engine in engines.iter() {
+            engine.increment_epoch();
+        }
+    }
+
+    /// 中断が要求されたかを返す。
+    pub fn is_interrupted(&self) -> bool {
+        self.inner.interrupted.load(Ordering::SeqCst)
+    }
+
+    /// 実行に使用するEngineを登録する。既に中断済みの場合は `false` を返す。
+    ///
+    /// Storeのエポック期限を設定してから呼ぶこと（登録後の中断要求が期限を必ず超えさせる）。
+    pub(crate) fn attach(&self, engine: &Engine) -> bool {
+        let mut engines = self.inner.engines.lock().unwrap_or_else(|e| e.into_inner());
+        if !engines.iter().any(|e| Engine::same(e, engine)) {
+            engines.push(engine.clone());
+        }
+        !self.is_interrupted()
+    }
+}
@@ -7,27 +7,63 @@
 //! ## 安全性確保 (仕様書 §7.1)
 //! - Fuel制限: 命令実行数の上限（無限ループ防止）
 //! - Memory制限: メモリ使用量の上限（OOM防止）
+//! - ホスト関数呼び出し回数の上限: Fuelとは別軸のホストCPU専有対策
 //! - catch_unwind: パニックをキャッチし、Core処理への影響を遮断
 //!
+//! コンパイル済みモジュールは [`ModuleCache`] を設定した場合に再利用される。
+//! 信頼済みかつ決定的なモジュールに限り、[`InstancePool`] で事前インスタンス化して
+//! 実行できる（既定は呼び出しごとに新規Storeを作成する安全なパス）。
+//!
 //! ## ホスト関数 (仕様書 §7.1)
 //! - `read_content_chunk`: コンテンツのチャンク読み取り
 //! - `get_content_length`: コンテンツの全長取得
 //! - `get_extension_input`: Extension補助入力の取得
-//! - `get_content_feature`: コンテンツの特徴量計算（JSON spec指定: sha256/sha384/sha512）
+//! - `get_extension_input_len` / `get_extension_input_chunk`: Extension補助入力の全長取得・チャンク読み取り
+//! - `get_content_mime`: TEEが検出したコンテンツのMIMEタイプの取得
+//! - `get_content_feature`: コンテンツの特徴量計算（JSON spec指定: sha256/sha384/sha512/blake3/c2pa_cawg_identity/c2pa_assertion_labels 等）
 //! - `hmac_content`: コンテンツのHMAC計算
 //! - `decode_content`: コンテンツのデコード（画像→ピクセル等）
 //! - `read_decoded_chunk`: デコード済みデータのチャンク読み取り
 //! - `get_decoded_length`: デコード済みデータの全長取得
-//! - `get_decoded_feature`: デコード済みデータの特徴量計算（JSON spec指定: grayscale_resize等）
+//! - `get_decoded_feature`: デコード済みデータの特徴量計算（JSON spec指定: grayscale_resize, canonical_rgb）
 //!
 //! ## WASM結果フォーマット
-//! WASMエクスポート関数は結果バッファへのポインタ(u32)を返す。
-//! バッファ形式: `[4B LE: json_len][json_bytes...]`
-
+//! WASMエクスポート関数は結果バッファへのポインタを返す。
+//! エクスポート関数は引数なし、または最大 [`MAX_EXPORT_ARGS`] 個の `i32` 引数を取る（[`WasmRunner::execute_with_args`]）。
+//! バッファ形式: `[4B LE: json_len][json_bytes...]`（`title-wasm-abi` の `ResultBuffer` で定義）
+//!
+//! ## ABIバージョン
+//! - v1（`title_abi_version` 未エクスポート）: 戻り値 `0` はエラー（詳細なし）、それ以外は結果ポインタ。
+//! - v2（`title_abi_version` が `2` を返す）: 戻り値は `i32`。正値は結果ポインタ、
+//!   負値はエラーコード（[`WASM_ERR_OUT_OF_MEMORY`], [`WASM_ERR_UNSUPPORTED_FORMAT`],
+//!   [`WASM_ERR_INVALID_INPUT`]）で、それぞれ個別の [`WasmError`] に変換される。
+//!
+//! ABI移行期間中は旧ABIと新ABIのモジュールが同一ノードで混在するため、
+//! [`SUPPORTED_ABI_VERSIONS`] に含まれる全バージョンを同一の [`WasmRunner`] で同時に扱う。
+//! バージョンはモジュールごとにインスタンス化後に判定し、戻り値の解釈をバージョンに応じて切り替える。
+//! ホスト関数のインポート集合は現行の全バージョンで共通のため、Linkerへの登録は共有する。
+//!
+//! ## Extension IDの自己申告
+//! モジュールは任意で `title_extension_id`（`() -> i32`）をエクスポートし、
+//! 結果バッファと同形式 `[4B LE: len][utf8_bytes...]` で自身のExtension IDを返せる。
+//! ホストは申告されたIDを [`ExtensionResult::declared_extension_id`] として返す。
+//! [`WasmRunner::with_expected_extension_id`] で要求するIDを設定した場合、計算関数の呼び出し前に
+//! 照合し、一致しないモジュールは計算を実行せずに拒否する。
+
+pub mod c2pa_assertions;
 pub mod c2pa_cert;
+pub mod cawg;
 pub mod decode;
+pub mod instance_pool;
+pub mod interrupt;
+pub mod module_cache;
 pub mod resource_pool;
 
+pub use instance_pool::{
+    InstancePool, DEFAULT_INSTANCE_POOL_MAX_PREPARED, DEFAULT_INSTANCE_POOL_SLOTS,
+};
+pub use interrupt::InterruptHandle;
+pub use module_cache::{ModuleCache, ModuleCacheStats, DEFAULT_MODULE_CACHE_CAPACITY};
 pub use resource_pool::{ResourcePool, Ticket};
 
 use std::panic;
@@ -35,7 +71,82 @@ use std::sync::Arc;
 
 use hmac::{Hmac, Mac};
 use sha2::{Digest, Sha256, Sha384, Sha512};
-use wasmtime::{Caller, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime::{
+    Caller, Engine, Func, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap,
+    UpdateDeadline, Val, ValType,
+};
+use title_wasm_abi::ResultBuffer;
+
+/// ABIバージョンを返すエクスポート関数名（`() -> i32`）。
+/// 未エクスポートのモジュールはv1として扱う。
+pub const ABI_VERSION_EXPORT: &str = "title_abi_version";
+
+/// Extension IDを自己申告するエクスポート関数名（`() -> i32`、結果バッファ形式のポインタを返す）。
+/// 未エクスポートのモジュールは申告なしとして扱う。
+pub const EXTENSION_ID_EXPORT: &str = "title_extension_id";
+
+pub use title_wasm_abi::{SUPPORTED_ABI_VERSIONS, WASM_ABI_V1, WASM_ABI_V2};
+
+/// エラーコード（ABI v2）: モジュール内でメモリ確保に失敗した。
+pub const WASM_ERR_OUT_OF_MEMORY: i32 = -1;
+
+/// エラーコード（ABI v2）: コンテンツのフォーマットに対応していない。
+pub const WASM_ERR_UNSUPPORTED_FORMAT: i32 = -2;
+
+/// エラーコード（ABI v2）: コンテンツまたはExtension補助入力が不正。
+pub const WASM_ERR_INVALID_INPUT: i32 = -3;
+
+/// [`WasmRunner::execute_with_args`] でエクスポート関数に渡せる `i32` 引数の最大数
+pub const MAX_EXPORT_ARGS: usize = 4;
+
+/// WASM実行スタックの上限のデフォルト（バイト）。
+/// 仕様書 §7.1
+///
+/// 深い再帰でホストのネイティブスタックを食い潰さないよう、wasmtimeの `max_wasm_stack` に設定する。
+pub const DEFAULT_MAX_WASM_STACK: usize = 512 * 1024;
+
+/// 1回の実行で許可するホスト関数呼び出し回数の既定値。
+/// 仕様書 §7.1
+///
+/// Fuelとは別軸の制限で、極小長の `read_content_chunk` を大量に呼ぶなど、
+/// WASM側の命令数は少ないままホスト側のCPUを専有する実行を打ち切る。
+pub const DEFAULT_MAX_HOST_CALLS: u64 = 1_000_000;
+
+/// C2PA解析を伴うホスト関数opがコンテンツ1KiBあたりに課金するFuel。
+/// 仕様書 §7.1
+///
+/// `c2pa_assertion_labels` / `c2pa_cawg_identity` はホスト側でコンテンツを解析するため、
+/// 実行ごとの初回の解析時にコンテンツ長に比例したFuelを差し引く（結果は実行中キャッシュされ、
+/// 2回目以降の呼び出しは課金しない）。
+pub const C2PA_PARSE_FUEL_PER_KIB: u64 = 64;
+
+/// ホスト関数を提供するインポートモジュール名。
+pub const HOST_MODULE: &str = "env";
+
+/// 提供するホスト関数の一覧（[`HOST_MODULE`] のインポート名）。
+/// 仕様書 §7.1
+///
+/// [`WasmRunner::with_allowed_host_imports`] で許可するホスト関数を制限する際の名前に用いる。
+pub const HOST_FUNCTIONS: &[&str] = &[
+    "read_content_chunk",
+    "get_content_feature",
+    "hmac_content",
+    "get_extension_input",
+    "get_extension_input_len",
+    "get_extension_input_chunk",
+    "get_content_mime",
+    "get_content_length",
+    "decode_content",
+    "read_decoded_chunk",
+    "get_decoded_length",
+    "get_decoded_feature",
+];
+
+/// ホスト関数呼び出し回数の上限超過を表すトラップ理由。
+/// [`WasmError::HostFunctionError`] に分類される。
+#[derive(Debug, thiserror::Error)]
+#[error("host call limit exceeded")]
+struct HostCallLimitExceeded;
 
 /// WASM実行環境のエラー型
 #[derive(Debug, thiserror::Error)]
@@ -46,18 +157,51 @@ pub enum WasmError {
     /// WASMモジュールの実行エラー
     #[error("WASM実行エラー: {0}")]
     ExecutionError(String),
-    /// Fuel制限超過
+    /// Fuel制限超過。
+    /// 課金・モニタリング上は、設定したFuel制限（[`WasmRunner::fuel_limit`]）の全量を消費したものとみなす。
     #[error("Fuel制限を超過しました")]
     FuelExhausted,
     /// Memory制限超過
     #[error("Memory制限を超過しました")]
     MemoryLimitExceeded,
+    /// WASM実行スタックの上限超過（深い再帰など）
+    #[error("WASM実行スタックの上限を超過しました")]
+    StackExhausted,
     /// WASMパニック
     #[error("WASMモジュールがパニックしました: {0}")]
     Panic(String),
     /// ホスト関数エラー
     #[error("ホスト関数エラー: {0}")]
     HostFunctionError(String),
+    /// WASMモジュールがメモリ確保に失敗した（ABI v2）
+    #[error("WASMモジュールがメモリ確保に失敗しました")]
+    OutOfMemory,
+    /// WASMモジュールが対応していないフォーマット（ABI v2）
+    #[error("WASMモジュールが対応していないコンテンツフォーマットです")]
+    UnsupportedFormat,
+    /// WASMモジュールへの入力が不正（ABI v2）
+    #[error("WASMモジュールへの入力が不正です")]
+    InvalidInput,
+    /// 未定義のエラーコード（ABI v2）
+    #[error("WASMモジュールが未定義のエラーコードを返しました: {0}")]
+    UnknownErrorCode(i32),
+    /// コンテンツ長がABI（u32）で表現できない
+    #[error("コンテンツ長がABIの上限（u32）を超えています: {0}バイト")]
+    ContentTooLarge(usize),
+    /// [`InterruptHandle`] による中断
+    #[error("WASM実行が中断されました")]
+    Interrupted,
+    /// 自己申告されたExtension IDが要求と一致しない（[`WasmRunner::with_expected_extension_id`]）
+    #[error("WASMモジュールのExtension IDが要求と一致しません: 要求={expected}, モジュール={declared}")]
+    ExtensionIdMismatch {
+        /// 要求されたExtension ID
+        expected: String,
+        /// モジュールが自己申告したExtension ID
+        declared: String,
+    },
+    /// 許可されていないホスト関数のインポート（[`WasmRunner::with_allowed_host_imports`]）
+    #[error("許可されていないホスト関数をインポートしています: {0}")]
+    ForbiddenHostImport(String),
 }
 
 /// WASM実行結果。
@@ -65,6 +209,56 @@ pub enum WasmError {
 pub struct ExtensionResult {
     /// WASM実行結果のJSON
     pub output: serde_json::Value,
+    /// モジュールが `title_extension_id` で自己申告したExtension ID（未エクスポートなら `None`）
+    pub declared_extension_id: Option<String>,
+    /// 実行に用いたABIバージョン（[`SUPPORTED_ABI_VERSIONS`] のいずれか）
+    pub abi_version: i32,
+    /// 実行で消費したFuel量（ABIバージョン・Extension IDの取得を含む）。課金・モニタリング用。
+    /// Fuel枯渇時は結果が返らず [`WasmError::FuelExhausted`] となり、Fuel制限の全量を消費したとみなす。
+    pub fuel_consumed: u64,
+}
+
+/// モジュールが申告したABIバージョン。戻り値の解釈を切り替える。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AbiVersion {
+    /// `title_abi_version` 未エクスポート
+    V1,
+    /// `title_abi_version` が `2` を返す
+    V2,
+}
+
+impl AbiVersion {
+    /// `title_abi_version` の戻り値（未エクスポートなら `None`）からバージョンを判定する。
+    fn from_declared(declared: Option<i32>) -> Result<Self, WasmError> {
+        match declared.unwrap_or(WASM_ABI_V1) {
+            WASM_ABI_V1 => Ok(Self::V1),
+            WASM_ABI_V2 => Ok(Self::V2),
+            other => Err(WasmError::ExecutionError(format!(
+                "未対応のABIバージョンです: {other}（対応: {SUPPORTED_ABI_VERSIONS:?}）"
+            ))),
+        }
+    }
+
+    fn number(self) -> i32 {
+        match self {
+            Self::V1 => WASM_ABI_V1,
+            Self::V2 => WASM_ABI_V2,
+        }
+    }
+
+    /// 計算関数の戻り値を結果ポインタとして解釈する。
+    /// v1は `0` のみ、v2は `0` と負値（エラーコード）をエラーとする。
+    fn result_pointer(self, ret: i32) -> Result<u32, WasmError> {
+        if self == Self::V2 && ret < 0 {
+            return Err(WasmRunner::classify_error_code(ret));
+        }
+        if ret == 0 {
+            return Err(WasmError::ExecutionError(
+                "WASM関数がエラーを返しました (ptr=0)".to_string(),
+            ));
+        }
+        Ok(ret as u32)
+    }
 }
 
 /// デコード済みコンテンツ。
@@ -90,6 +284,8 @@ struct InnerHostState {
     content: Vec<u8>,
     /// Extension補助入力
     extension_input: Option<Vec<u8>>,
+    /// TEEが検出したコンテンツのMIMEタイプ（未指定の場合は `None`）
+    content_mime: Option<String>,
     /// メモリ制限
     limiter: StoreLimits,
     /// デコード済みコンテンツ（decode_content 呼び出し後に Some）
@@ -101,6 +297,171 @@ struct InnerHostState {
     /// デコード済みデータのメモリ予約チケット（Drop で自動解放）
     /// 仕様書 §7.1
     decode_ticket: Option<Ticket>,
+    /// ホスト関数の呼び出し回数（全ホスト関数の合計）
+    /// 仕様書 §7.1
+    host_calls: u64,
+    /// ホスト関数呼び出し回数の上限
+    max_host_calls: u64,
+    /// C2PA解析前に確認するマニフェストストアの最大サイズ（バイト）
+    /// 仕様書 §2.1
+    max_manifest_store_bytes: u64,
+    /// `c2pa_assertion_labels` の出力JSON（初回の呼び出しで計算し、実行中は再利用する）。
+    /// `Err` は解析の失敗（C2PA構造エラー・マニフェストストアの上限超過）
+    c2pa_assertion_labels: Option<Result<Vec<u8>, String>>,
+    /// `c2pa_cawg_identity` の出力JSON（同上）
+    cawg_identity: Option<Result<Vec<u8>, String>>,
+    /// ホスト関数の処理中に発生した、呼び出し後にFuelとして差し引くコスト
+    /// 仕様書 §7.1
+    pending_fuel: u64,
+}
+
+impl InnerHostState {
+    /// `get_content_length` が報告するコンテンツ全長。
+    /// `execute` でu32に収まることを検証済みのため、切り捨ては発生しない。
+    fn content_length(&self) -> u32 {
+        self.content.len() as u32
+    }
+
+    /// `[offset, offset + length)` を報告済みのコンテンツ全長でクリップした範囲を返す。
+    /// ホスト関数が全長を超えて読み取らないための防御的チェック。
+    /// `offset` が全長を超える場合は `None`。
+    fn content_range(&self, offset: usize, length: usize) -> Option<std::ops::Range<usize>> {
+        let reported_len = self.content_length() as usize;
+        if offset > reported_len {
+            return None;
+        }
+        Some(offset..offset.saturating_add(length).min(reported_len))
+    }
+
+    /// `get_extension_input_len` が報告する補助入力の全長（補助入力なしは0）。
+    fn extension_input_length(&self) -> u32 {
+        self.extension_input.as_ref().map_or(0, |input| input.len() as u32)
+    }
+
+    /// 補助入力の `[offset, offset + length)` を全長でクリップした範囲を返す。
+    /// `offset` が全長を超える場合、または補助入力がない場合は `None`。
+    fn extension_input_range(&self, offset: usize, length: usize) -> Option<std::ops::Range<usize>> {
+        let input_len = self.extension_input.as_ref()?.len();
+        if offset > input_len {
+            return None;
+        }
+        Some(offset..offset.saturating_add(length).min(input_len))
+    }
+
+    /// C2PA解析のコスト（コンテンツ長に比例）をFuelの課金対象に加える。
+    fn charge_c2pa_parse(&mut self) {
+        let kib = (self.content.len() as u64).div_ceil(1024);
+        self.pending_fuel = self
+            .pending_fuel
+            .saturating_add(kib.saturating_mul(C2PA_PARSE_FUEL_PER_KIB));
+    }
+
+    /// `c2pa_assertion_labels` の出力JSONを返す。初回のみ解析し、以降はキャッシュを返す。
+    /// 仕様書 §7.1
+    fn c2pa_assertion_labels_json(&mut self) -> Result<&[u8], &str> {
+        if self.c2pa_assertion_labels.is_none() {
+            self.charge_c2pa_parse();
+            let labels = c2pa_assertions::list_assertion_labels(
+                &self.content,
+                self.content_mime.as_deref(),
+                self.max_manifest_store_bytes,
+            )
+            .map(|labels| serde_json::json!({ "assertions": labels }).to_string().into_bytes());
+            self.c2pa_assertion_labels = Some(labels);
+        }
+        match self.c2pa_assertion_labels.as_ref() {
+            Some(Ok(bytes)) => Ok(bytes),
+            Some(Err(e)) => Err(e),
+            None => unreachable!("直前に計算済み"),
+        }
+    }
+
+    /// `c2pa_cawg_identity` の出力JSONを返す。初回のみ解析し、以降はキャッシュを返す。
+    /// 仕様書 §7.1
+    fn cawg_identity_json(&mut self) -> Result<&[u8], &str> {
+        if self.cawg_identity.is_none() {
+            self.charge_c2pa_parse();
+            let identity = cawg::find_cawg_identity(&self.content, self.max_manifest_store_bytes)
+                .map(|identity| {
+                    serde_json::json!({
+                        "identity_present": identity.is_some(),
+                        "sig_type": identity.as_ref().map(|i| i.sig_type.as_str()),
+                        "issuer": identity.as_ref().and_then(|i| i.issuer.as_deref()),
+                    })
+                    .to_string()
+                    .into_bytes()
+                });
+            self.cawg_identity = Some(identity);
+        }
+        match self.cawg_identity.as_ref() {
+            Some(Ok(bytes)) => Ok(bytes),
+            Some(Err(e)) => Err(e),
+            None => unreachable!("直前に計算済み"),
+        }
+    }
+
+    /// ホスト関数の呼び出しを1回数える。上限を超えた場合はトラップ用のエラーを返す。
+    fn record_host_call(&mut self) -> wasmtime::Result<()> {
+        self.host_calls += 1;
+        if self.host_calls > self.max_host_calls {
+            return Err(HostCallLimitExceeded.into());
+        }
+        Ok(())
+    }
+}
+
+/// ホスト関数の本体 `f` を呼び出し回数の上限付きで実行する。
+/// 仕様書 §7.1
+///
+/// 上限を超えた場合は `f` を実行せずにトラップを発生させ、実行全体を打ち切る。
+/// `f` の処理中に発生したホスト側のコスト（C2PA解析など）は、実行後にFuelから差し引く。
+/// 残りFuelが足りない場合は [`Trap::OutOfFuel`] で打ち切る。
+fn host_call<R>(
+    caller: &mut Caller<'_, InnerHostState>,
+    f: impl FnOnce(&mut Caller<'_, InnerHostState>) -> R,
+) -> wasmtime::Result<R> {
+    caller.data_mut().record_host_call()?;
+    let result = f(caller);
+    let cost = std::mem::take(&mut caller.data_mut().pending_fuel);
+    if cost > 0 {
+        let fuel = caller.get_fuel()?;
+        if fuel < cost {
+            caller.set_fuel(0)?;
+            return Err(Trap::OutOfFuel.into());
+        }
+        caller.set_fuel(fuel - cost)?;
+    }
+    Ok(result)
+}
+
+/// `src` 全体をWASM線形メモリの `dest_ptr` に1回のコピーで書き込み、書き込んだバイト数を返す。
+/// 仕様書 §7.1
+///
+/// `read_content_chunk` / `read_decoded_chunk` の共通コピー経路。チャンクサイズはWASM側が決め、
+/// ホスト側は要求長によらず中間バッファを介さない1回のmemcpyで処理する。
+/// 大きなチャンクはホスト呼び出し回数（呼び出しごとの固定オーバーヘッド）を減らすが、
+/// WASM側にその分のバッファ（線形メモリ）が必要になる。小さなチャンクはメモリ使用量を抑える代わりに
+/// 呼び出し回数が増える。どちらを選んでもホスト側の総コピー量は同じである。
+///
+/// `src` が空、またはコピー先がメモリ範囲外の場合は0を返す（何も書き込まない）。
+fn copy_into_memory(mem_data: &mut [u8], dest_ptr: u32, src: &[u8]) -> u32 {
+    let dest = dest_ptr as usize;
+    let Some(dest_slice) = dest
+        .checked_add(src.len())
+        .and_then(|end| mem_data.get_mut(dest..end))
+    else {
+        return 0;
+    };
+    dest_slice.copy_from_slice(src);
+    src.len() as u32
+}
+
+/// WASMメモリ上の結果バッファ `[4B LE: len][bytes...]` から本体を取り出す。
+/// 仕様書 §7.1（フォーマットは `title-wasm-abi` で定義）
+fn read_result_buffer(mem_data: &[u8], ptr: u32) -> Result<&[u8], WasmError> {
+    ResultBuffer::read(mem_data, ptr)
+        .map(|buffer| buffer.as_bytes())
+        .map_err(|e| WasmError::ExecutionError(e.to_string()))
 }
 
 /// WASM実行ランナー。
@@ -110,28 +471,60 @@ pub struct WasmRunner {
     fuel_limit: u64,
     /// Memory制限（バイト）
     memory_limit: usize,
+    /// WASM実行スタックの上限（バイト）
+    max_wasm_stack: usize,
+    /// 1回の実行で許可するホスト関数呼び出し回数
+    max_host_calls: u64,
+    /// C2PA解析を伴うホスト関数opが受け付けるマニフェストストアの最大サイズ（バイト）
+    max_manifest_store_bytes: u64,
     /// ResourcePool（デコード済みデータのメモリ予算管理用）
     /// 仕様書 §7.1
     resource_pool: Option<Arc<ResourcePool>>,
+    /// コンパイル済みモジュールのキャッシュ（Noneの場合は毎回コンパイル）
+    /// 仕様書 §7.1
+    module_cache: Option<Arc<ModuleCache>>,
+    /// 事前インスタンス化プール（Noneの場合は呼び出しごとに新規Store・Instanceを作成）
+    /// 仕様書 §7.1
+    instance_pool: Option<Arc<InstancePool>>,
+    /// 実行中のWASMを外部から中断するハンドル
+    /// 仕様書 §6.4
+    interrupt: Option<InterruptHandle>,
+    /// インポートを許可するホスト関数（Noneの場合は全ホスト関数を許可）
+    /// 仕様書 §6.4
+    allowed_host_imports: Option<Vec<String>>,
+    /// 要求するExtension ID（Noneの場合は自己申告を照合しない）
+    /// 仕様書 §7.1
+    expected_extension_id: Option<String>,
 }
 
 impl WasmRunner {
-    /// 新しいWasmRunnerを作成する（後方互換）。
+    /// 新しいWasmRunnerを作成する。
     /// 仕様書 §7.1
     ///
     /// # 引数
     /// - `fuel_limit`: 命令実行数の上限（無限ループ防止）
     /// - `memory_limit`: メモリ使用量の上限（バイト、OOM防止）
-    pub fn new(fuel_limit: u64, memory_limit: usize) -> Self {
+    /// - `max_host_calls`: ホスト関数呼び出し回数の上限（Fuelとは別軸のホストCPU専有対策）
+    pub fn new(fuel_limit: u64, memory_limit: usize, max_host_calls: u64) -> Self {
         Self {
             fuel_limit,
             memory_limit,
+            max_wasm_stack: DEFAULT_MAX_WASM_STACK,
+            max_host_calls,
+            max_manifest_store_bytes: title_core::DEFAULT_MAX_MANIFEST_STORE_BYTES,
             resource_pool: None,
+            module_cache: None,
+            instance_pool: None,
+            interrupt: None,
+            allowed_host_imports: None,
+            expected_extension_id: None,
         }
     }
 
     /// ResourcePool付きのWasmRunnerを作成する。
     /// 仕様書 §7.1
+    ///
+    /// ホスト関数呼び出し回数の上限は [`DEFAULT_MAX_HOST_CALLS`]。
     pub fn with_resource_pool(
         fuel_limit: u64,
         memory_limit: usize,
@@ -140,10 +533,132 @@ impl WasmRunner {
         Self {
             fuel_limit,
             memory_limit,
+            max_wasm_stack: DEFAULT_MAX_WASM_STACK,
+            max_host_calls: DEFAULT_MAX_HOST_CALLS,
+            max_manifest_store_bytes: title_core::DEFAULT_MAX_MANIFEST_STORE_BYTES,
             resource_pool: Some(pool),
+            module_cache: None,
+            instance_pool: None,
+            interrupt: None,
+            allowed_host_imports: None,
+            expected_extension_id: None,
         }
     }
 
+    /// 最大 `max_modules` 件のモジュールキャッシュを持つWasmRunnerを作成する。
+    /// 仕様書 §7.1
+    ///
+    /// 同一バイナリの2回目以降の実行はコンパイルを省略する。Fuel制限・Memory制限は
+    /// キャッシュとは独立に実行ごとのStoreへ適用される。メモリ上限を設ける場合や
+    /// 複数のランナーでキャッシュを共有する場合は [`ModuleCache`] を直接作成して
+    /// [`WasmRunner::with_module_cache`] で設定する。
+    pub fn with_cache(
+        fuel_limit: u64,
+        memory_limit: usize,
+        max_modules: usize,
+    ) -> Result<Self, WasmError> {
+        let cache = Arc::new(ModuleCache::new(max_modules)?);
+        Ok(Self::new(fuel_limit, memory_limit, DEFAULT_MAX_HOST_CALLS).with_module_cache(cache))
+    }
+
+    /// コンパイル済みモジュールのキャッシュを設定する。
+    /// 仕様書 §7.1
+    pub fn with_module_cache(mut self, cache: Arc<ModuleCache>) -> Self {
+        self.module_cache = Some(cache);
+        self
+    }
+
+    /// 設定されたモジュールキャッシュを返す（統計情報の参照用）。
+    pub fn module_cache(&self) -> Option<&Arc<ModuleCache>> {
+        self.module_cache.as_ref()
+    }
+
+    /// WASM実行スタックの上限（バイト）を設定する。
+    /// 仕様書 §7.1
+    ///
+    /// 上限を超えた実行は [`WasmError::StackExhausted`] になる。
+    /// [`ModuleCache`]・[`InstancePool`] 使用時は共有Engineの設定と一致させる必要があり、
+    /// 異なる場合は実行時に [`WasmError::CompileError`] を返す
+    /// （[`ModuleCache::with_max_wasm_stack`]・[`InstancePool::with_max_wasm_stack`] 参照）。
+    pub fn with_max_wasm_stack(mut self, max_wasm_stack: usize) -> Self {
+        self.max_wasm_stack = max_wasm_stack;
+        self
+    }
+
+    /// C2PA解析を伴うホスト関数op（`c2pa_assertion_labels` / `c2pa_cawg_identity`）が
+    /// 受け付けるマニフェストストアの最大サイズ（バイト）を設定する。
+    /// 仕様書 §2.1, §7.1
+    ///
+    /// 既定は [`title_core::DEFAULT_MAX_MANIFEST_STORE_BYTES`]。超える場合は解析せずにエラー（-5）を返す。
+    pub fn with_max_manifest_store_bytes(mut self, max_manifest_store_bytes: u64) -> Self {
+        self.max_manifest_store_bytes = max_manifest_store_bytes;
+        self
+    }
+
+    /// 事前インスタンス化プールを設定する。
+    /// 仕様書 §7.1
+    ///
+    /// 信頼済みかつ決定的なモジュールにのみ使用すること（[`instance_pool`] 参照）。
+    /// 設定時は [`ModuleCache`] より優先される。
+    pub fn with_instance_pool(mut self, pool: Arc<InstancePool>) -> Self {
+        self.instance_pool = Some(pool);
+        self
+    }
+
+    /// 実行中のWASMを外部から中断するハンドルを設定する。
+    /// 仕様書 §6.4, §7.1
+    ///
+    /// [`InterruptHandle::interrupt`] を呼ぶと、実行は [`WasmError::Interrupted`] で停止する。
+    pub fn with_interrupt(mut self, interrupt: InterruptHandle) -> Self {
+        self.interrupt = Some(interrupt);
+        self
+    }
+
+    /// インポートを許可するホスト関数を制限する。
+    /// 仕様書 §6.4, §7.1
+    ///
+    /// モジュールが一覧にないホスト関数（[`HOST_FUNCTIONS`]）をインポートしている場合、
+    /// インスタンス化の前に [`WasmError::ForbiddenHostImport`] で拒否する。
+    pub fn with_allowed_host_imports<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_host_imports = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// 要求するExtension IDを設定する。
+    /// 仕様書 §7.1
+    ///
+    /// モジュールが `title_extension_id` で異なるIDを自己申告した場合、計算関数を呼び出す前に
+    /// [`WasmError::ExtensionIdMismatch`] で拒否する（ローダーが別のExtensionのバイナリを返す
+    /// 設定ミスで、そのモジュールの計算を実行しない）。自己申告のないモジュールは照合しない。
+    pub fn with_expected_extension_id(mut self, extension_id: impl Into<String>) -> Self {
+        self.expected_extension_id = Some(extension_id.into());
+        self
+    }
+
+    /// 1回の実行に与えるFuel量（命令実行数の上限）を返す。
+    pub fn fuel_limit(&self) -> u64 {
+        self.fuel_limit
+    }
+
+    /// Fuel制限・スタック上限・エポック割り込みを有効化したwasmtime Configを作成する。
+    pub(crate) fn engine_config(max_wasm_stack: usize) -> wasmtime::Config {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        config.epoch_interruption(true);
+        config.max_wasm_stack(max_wasm_stack);
+        config
+    }
+
+    /// Fuel制限とスタック上限を有効化したwasmtime Engineを作成する。
+    pub(crate) fn create_engine(max_wasm_stack: usize) -> Result<Engine, WasmError> {
+        Engine::new(&Self::engine_config(max_wasm_stack))
+            .map_err(|e| WasmError::CompileError(format!("Engineの作成に失敗: {e}")))
+    }
+
     /// WASMモジュールを実行し、Extension結果を返す。
     /// 仕様書 §7.1
     ///
@@ -161,26 +676,75 @@ impl WasmRunner {
         extension_input: Option<&[u8]>,
         export_name: &str,
     ) -> Result<ExtensionResult, WasmError> {
-        let fuel_limit = self.fuel_limit;
-        let memory_limit = self.memory_limit;
-        let resource_pool = self.resource_pool.clone();
-        let wasm_bytes = wasm_bytes.to_vec();
+        self.execute_with_args(wasm_bytes, content, extension_input, export_name, &[])
+    }
+
+    /// エクスポート関数に `i32` 引数（モード番号・閾値など）を渡してWASMモジュールを実行する。
+    /// 仕様書 §7.1
+    ///
+    /// 引数は最大 [`MAX_EXPORT_ARGS`] 個。エクスポート関数のシグネチャは実行時に検査し、
+    /// 引数の数・型が `(i32 × args.len()) -> i32` と一致しない場合は [`WasmError::ExecutionError`] を返す。
+    /// それ以外は [`WasmRunner::execute`] と同じ。
+    pub fn execute_with_args(
+        &self,
+        wasm_bytes: &[u8],
+        content: &[u8],
+        extension_input: Option<&[u8]>,
+        export_name: &str,
+        args: &[i32],
+    ) -> Result<ExtensionResult, WasmError> {
+        if args.len() > MAX_EXPORT_ARGS {
+            return Err(WasmError::ExecutionError(format!(
+                "エクスポート関数に渡せる引数は最大{MAX_EXPORT_ARGS}個です: {}個",
+                args.len()
+            )));
+        }
+        self.execute_guarded(wasm_bytes, content, None, extension_input, export_name, args)
+    }
+
+    /// コンテンツのMIMEタイプを指定してWASMモジュールを実行する。
+    /// 仕様書 §7.1
+    ///
+    /// `content_mime` はホスト関数 `get_content_mime` でモジュールに公開され、
+    /// モジュールがマジックバイト判定を再実装せずにフォーマットで分岐できるようにする。
+    /// それ以外は [`WasmRunner::execute`] と同じ。
+    pub fn execute_with_mime(
+        &self,
+        wasm_bytes: &[u8],
+        content: &[u8],
+        content_mime: Option<&str>,
+        extension_input: Option<&[u8]>,
+        export_name: &str,
+    ) -> Result<ExtensionResult, WasmError> {
+        self.execute_guarded(wasm_bytes, content, content_mime, extension_input, export_name, &[])
+    }
+
+    /// 入力を検査し、パニックを遮断してWASMモジュールを実行する。
+    /// 仕様書 §7.1
+    fn execute_guarded(
+        &self,
+        wasm_bytes: &[u8],
+        content: &[u8],
+        content_mime: Option<&str>,
+        extension_input: Option<&[u8]>,
+        export_name: &str,
+        args: &[i32],
+    ) -> Result<ExtensionResult, WasmError> {
+        // get_content_length / read_content_chunk はu32でオフセットと長さを扱うため、
+        // 全長を正しく報告できないコンテンツは実行前に拒否する
+        if u32::try_from(content.len()).is_err() {
+            return Err(WasmError::ContentTooLarge(content.len()));
+        }
         let content = content.to_vec();
+        let content_mime = content_mime.map(str::to_string);
         let extension_input = extension_input.map(|v| v.to_vec());
-        let export_name = export_name.to_string();
 
         // catch_unwindでパニック遮断 (仕様書 §7.1)
-        let result = panic::catch_unwind(move || {
-            Self::execute_inner(
-                fuel_limit,
-                memory_limit,
-                resource_pool,
-                &wasm_bytes,
-                content,
-                extension_input,
-                &export_name,
-            )
-        });
+        // ModuleCache・InstancePoolはパニック後も整合性を保つ（Module・InstancePreは
+        // 準備成功後にのみ格納され、ロックのpoisonは無視する）ため、AssertUnwindSafeで境界を越えてよい。
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(move || {
+            self.execute_inner(wasm_bytes, content, content_mime, extension_input, export_name, args)
+        }));
 
         match result {
             Ok(inner) => inner,
@@ -192,11 +756,19 @@ impl WasmRunner {
 
     /// wasmtimeのエラーをWasmErrorに変換する。
     fn classify_error(e: wasmtime::Error) -> WasmError {
-        // Trap型にダウンキャストしてOutOfFuelを検出
-        if let Some(trap) = e.downcast_ref::<Trap>() {
-            if *trap == Trap::OutOfFuel {
-                return WasmError::FuelExhausted;
-            }
+        // ホスト関数内で発生させたトラップ（呼び出し回数の上限超過）
+        if let Some(limit) = e.downcast_ref::<HostCallLimitExceeded>() {
+            return WasmError::HostFunctionError(limit.to_string());
+        }
+        // エポック期限のコールバックで発生させたトラップ（中断要求）
+        if e.downcast_ref::<interrupt::ExecutionInterrupted>().is_some() {
+            return WasmError::Interrupted;
+        }
+        // Trap型にダウンキャストしてOutOfFuel・StackOverflowを検出
+        match e.downcast_ref::<Trap>() {
+            Some(Trap::OutOfFuel) => return WasmError::FuelExhausted,
+            Some(Trap::StackOverflow) => return WasmError::StackExhausted,
+            _ => {}
         }
         let msg = e.to_string();
         if msg.contains("fuel") {
@@ -208,110 +780,243 @@ impl WasmRunner {
         }
     }
 
+    /// ABI v2のエラーコードをWasmErrorに変換する。
+    fn classify_error_code(code: i32) -> WasmError {
+        match code {
+            WASM_ERR_OUT_OF_MEMORY => WasmError::OutOfMemory,
+            WASM_ERR_UNSUPPORTED_FORMAT => WasmError::UnsupportedFormat,
+            WASM_ERR_INVALID_INPUT => WasmError::InvalidInput,
+            other => WasmError::UnknownErrorCode(other),
+        }
+    }
+
+    /// モジュールのホスト関数インポートが許可一覧に含まれることを確認する。
+    fn check_host_imports(&self, module: &Module) -> Result<(), WasmError> {
+        let Some(allowed) = &self.allowed_host_imports else {
+            return Ok(());
+        };
+        match module
+            .imports()
+            .find(|import| import.module() == HOST_MODULE && !allowed.iter().any(|name| name == import.name()))
+        {
+            Some(import) => Err(WasmError::ForbiddenHostImport(import.name().to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// 共有Engineのスタック上限がランナーの設定と一致することを確認する。
+    fn check_shared_max_wasm_stack(&self, shared: usize) -> Result<(), WasmError> {
+        if shared != self.max_wasm_stack {
+            return Err(WasmError::CompileError(format!(
+                "max_wasm_stack（{}バイト）が共有Engineの設定（{shared}バイト）と一致しません",
+                self.max_wasm_stack
+            )));
+        }
+        Ok(())
+    }
+
     /// WASM実行の内部実装。
     /// 仕様書 §7.1
     fn execute_inner(
-        fuel_limit: u64,
-        memory_limit: usize,
-        resource_pool: Option<Arc<ResourcePool>>,
+        &self,
         wasm_bytes: &[u8],
         content: Vec<u8>,
+        content_mime: Option<String>,
         extension_input: Option<Vec<u8>>,
         export_name: &str,
+        args: &[i32],
     ) -> Result<ExtensionResult, WasmError> {
-        // 1. wasmtime Engineを作成（Fuel制限有効化）
-        let mut config = wasmtime::Config::new();
-        config.consume_fuel(true);
+        // 1. wasmtime Engineを用意（Fuel制限有効化、プール・キャッシュ使用時は共有Engine）
+        // 共有Engineのスタック上限がランナーの設定と異なる場合は黙って適用しない
+        let engine = match (&self.instance_pool, &self.module_cache) {
+            (Some(pool), _) => {
+                self.check_shared_max_wasm_stack(pool.max_wasm_stack())?;
+                pool.engine().clone()
+            }
+            (None, Some(cache)) => {
+                self.check_shared_max_wasm_stack(cache.max_wasm_stack())?;
+                cache.engine().clone()
+            }
+            (None, None) => Self::create_engine(self.max_wasm_stack)?,
+        };
 
-        let engine = Engine::new(&config)
-            .map_err(|e| WasmError::CompileError(format!("Engineの作成に失敗: {e}")))?;
+        // プール使用時はインスタンススロットを確保する（空きがなければ返却まで待機）。
+        // Storeより先に宣言し、Storeの破棄後にスロットを返却する
+        let _slot = match &self.instance_pool {
+            Some(pool) => Some(pool.acquire_slot(self.interrupt.as_ref())?),
+            None => None,
+        };
 
         // 2. HostStateを含むStoreを作成（Memory制限付き）
         let limiter = StoreLimitsBuilder::new()
-            .memory_size(memory_limit)
+            .memory_size(self.memory_limit)
             .build();
 
         let inner_state = InnerHostState {
             content,
             extension_input,
+            content_mime,
             limiter,
             decoded: None,
-            resource_pool,
+            resource_pool: self.resource_pool.clone(),
             decode_ticket: None,
+            host_calls: 0,
+            max_host_calls: self.max_host_calls,
+            max_manifest_store_bytes: self.max_manifest_store_bytes,
+            c2pa_assertion_labels: None,
+            cawg_identity: None,
+            pending_fuel: 0,
         };
 
         let mut store = Store::new(&engine, inner_state);
         store
-            .set_fuel(fuel_limit)
+            .set_fuel(self.fuel_limit)
             .map_err(|e| WasmError::ExecutionError(format!("Fuel設定に失敗: {e}")))?;
         store.limiter(|s| &mut s.limiter);
 
-        // 3. ホスト関数をLinkerに登録
-        let mut linker = Linker::new(&engine);
-        Self::register_host_functions(&mut linker)?;
-
-        // 4. WASMバイナリをコンパイル
-        let module =
-            Module::new(&engine, wasm_bytes).map_err(|e| WasmError::CompileError(e.to_string()))?;
-
-        // 5. インスタンス化
-        let instance = linker
-            .instantiate(&mut store, &module)
-            .map_err(Self::classify_error)?;
-
-        // 6. エクスポートされた計算関数を呼び出す
-        let func = instance
-            .get_typed_func::<(), u32>(&mut store, export_name)
-            .map_err(|e| {
-                WasmError::ExecutionError(format!(
-                    "エクスポート関数 '{export_name}' が見つかりません: {e}"
-                ))
-            })?;
+        // 中断要求はEngineのエポックを進めて通知される。期限に達したら自身のハンドルを確認し、
+        // 中断されていなければ（共有Engineでの他の実行の中断など）期限を延長して続行する
+        let interrupt_handle = self.interrupt.clone();
+        store.set_epoch_deadline(1);
+        store.epoch_deadline_callback(move |_| match &interrupt_handle {
+            Some(handle) if handle.is_interrupted() => {
+                Err(wasmtime::Error::new(interrupt::ExecutionInterrupted))
+            }
+            _ => Ok(UpdateDeadline::Continue(1)),
+        });
+        if let Some(handle) = &self.interrupt {
+            if !handle.attach(&engine) {
+                return Err(WasmError::Interrupted);
+            }
+        }
 
-        let result_ptr = func.call(&mut store, ()).map_err(Self::classify_error)?;
+        // 3-5. インスタンス化
+        // プール使用時はホスト関数解決済みのInstancePreから新しいメモリでインスタンス化する
+        let instance = match &self.instance_pool {
+            Some(pool) => {
+                let pre = pool.get_or_prepare(wasm_bytes)?;
+                self.check_host_imports(pre.module())?;
+                pre.instantiate(&mut store).map_err(Self::classify_error)?
+            }
+            None => {
+                // 3. ホスト関数をLinkerに登録
+                let mut linker = Linker::new(&engine);
+                Self::register_host_functions(&mut linker)?;
+
+                // 4. WASMバイナリをコンパイル（キャッシュ使用時はコンパイル済みModuleを再利用）
+                let module = match &self.module_cache {
+                    Some(cache) => cache.get_or_compile(wasm_bytes)?,
+                    None => Module::new(&engine, wasm_bytes)
+                        .map_err(|e| WasmError::CompileError(e.to_string()))?,
+                };
+                self.check_host_imports(&module)?;
+
+                // 5. インスタンス化
+                linker
+                    .instantiate(&mut store, &module)
+                    .map_err(Self::classify_error)?
+            }
+        };
 
-        if result_ptr == 0 {
-            return Err(WasmError::ExecutionError(
-                "WASM関数がエラーを返しました (ptr=0)".to_string(),
-            ));
-        }
+        // 6. ABIバージョンを判定（未エクスポートならv1）
+        let declared_abi_version =
+            match instance.get_typed_func::<(), i32>(&mut store, ABI_VERSION_EXPORT) {
+                Ok(version_func) => Some(
+                    version_func
+                        .call(&mut store, ())
+                        .map_err(Self::classify_error)?,
+                ),
+                Err(_) => None,
+            };
+        let abi_version = AbiVersion::from_declared(declared_abi_version)?;
 
-        // 7. 結果をWASMメモリから読み取り、ExtensionResultとして返す
         let memory = instance.get_memory(&mut store, "memory").ok_or_else(|| {
             WasmError::ExecutionError("memoryエクスポートが見つかりません".to_string())
         })?;
 
-        let mem_data = memory.data(&store);
-        let ptr = result_ptr as usize;
-
-        // [4B LE: json_len][json_bytes...]
-        if ptr + 4 > mem_data.len() {
-            return Err(WasmError::ExecutionError(
-                "結果ポインタが不正です".to_string(),
-            ));
+        // 7. 自己申告されたExtension IDを読み取る（未エクスポートなら申告なし）
+        let declared_extension_id =
+            match instance.get_typed_func::<(), i32>(&mut store, EXTENSION_ID_EXPORT) {
+                Ok(id_func) => {
+                    let id_ptr = id_func.call(&mut store, ()).map_err(Self::classify_error)?;
+                    let id_bytes = read_result_buffer(memory.data(&store), id_ptr as u32)
+                        .map_err(|e| WasmError::ExecutionError(format!("Extension IDの読み取りに失敗: {e}")))?;
+                    let id = std::str::from_utf8(id_bytes).map_err(|e| {
+                        WasmError::ExecutionError(format!("Extension IDがUTF-8ではありません: {e}"))
+                    })?;
+                    Some(id.to_string())
+                }
+                Err(_) => None,
+            };
+        if let (Some(expected), Some(declared)) = (&self.expected_extension_id, &declared_extension_id) {
+            if expected != declared {
+                return Err(WasmError::ExtensionIdMismatch {
+                    expected: expected.clone(),
+                    declared: declared.clone(),
+                });
+            }
         }
 
-        let json_len = u32::from_le_bytes([
-            mem_data[ptr],
-            mem_data[ptr + 1],
-            mem_data[ptr + 2],
-            mem_data[ptr + 3],
-        ]) as usize;
+        // 8. エクスポートされた計算関数を呼び出す
+        let func = instance.get_func(&mut store, export_name).ok_or_else(|| {
+            WasmError::ExecutionError(format!(
+                "エクスポート関数 '{export_name}' が見つかりません"
+            ))
+        })?;
 
-        if json_len == 0 || ptr + 4 + json_len > mem_data.len() {
-            return Err(WasmError::ExecutionError(
-                "結果バッファが不正です".to_string(),
-            ));
-        }
+        let ret = Self::call_export(&mut store, func, export_name, args)?;
+        let result_ptr = abi_version.result_pointer(ret)?;
 
-        let json_bytes = &mem_data[ptr + 4..ptr + 4 + json_len];
+        // 9. 結果をWASMメモリから読み取り、ExtensionResultとして返す
+        let json_bytes = read_result_buffer(memory.data(&store), result_ptr)?;
         let json_str = std::str::from_utf8(json_bytes)
             .map_err(|e| WasmError::ExecutionError(format!("結果がUTF-8ではありません: {e}")))?;
 
         let output: serde_json::Value = serde_json::from_str(json_str)
             .map_err(|e| WasmError::ExecutionError(format!("結果JSONのパースに失敗: {e}")))?;
 
-        Ok(ExtensionResult { output })
+        // 10. 消費Fuel量（開始時のFuelとの差分）
+        let fuel_remaining = store
+            .get_fuel()
+            .map_err(|e| WasmError::ExecutionError(format!("残りFuelの取得に失敗: {e}")))?;
+        let fuel_consumed = self.fuel_limit.saturating_sub(fuel_remaining);
+
+        Ok(ExtensionResult {
+            output,
+            declared_extension_id,
+            abi_version: abi_version.number(),
+            fuel_consumed,
+        })
+    }
+
+    /// エクスポート関数のシグネチャを検査し、`i32` 引数を渡して呼び出す。
+    /// 仕様書 §7.1
+    fn call_export(
+        store: &mut Store<InnerHostState>,
+        func: Func,
+        export_name: &str,
+        args: &[i32],
+    ) -> Result<i32, WasmError> {
+        let ty = func.ty(&*store);
+        let params_match = ty.params().len() == args.len()
+            && ty.params().all(|p| matches!(p, ValType::I32));
+        let results_match =
+            ty.results().len() == 1 && ty.results().all(|r| matches!(r, ValType::I32));
+        if !params_match || !results_match {
+            return Err(WasmError::ExecutionError(format!(
+                "エクスポート関数 '{export_name}' のシグネチャが一致しません: \
+                 引数{}個（i32）を受け取りi32を返す関数が必要です（実際: {ty}）",
+                args.len()
+            )));
+        }
+
+        let params: Vec<Val> = args.iter().map(|&arg| Val::I32(arg)).collect();
+        let mut results = [Val::I32(0)];
+        func.call(&mut *store, &params, &mut results)
+            .map_err(Self::classify_error)?;
+        results[0].i32().ok_or_else(|| {
+            WasmError::ExecutionError(format!("エクスポート関数 '{export_name}' の戻り値がi32ではありません"))
+        })
     }
 
     /// ホスト関数をLinkerに登録する。
@@ -328,30 +1033,23 @@ impl WasmRunner {
                  offset: u32,
                  length: u32,
                  buf_ptr: u32|
-                 -> u32 {
-                    let memory = match caller.get_export("memory") {
-                        Some(ext) => match ext.into_memory() {
-                            Some(m) => m,
+                 -> wasmtime::Result<u32> {
+                    host_call(&mut caller, |caller| {
+                        let memory = match caller.get_export("memory") {
+                            Some(ext) => match ext.into_memory() {
+                                Some(m) => m,
+                                None => return 0,
+                            },
                             None => return 0,
-                        },
-                        None => return 0,
-                    };
-                    let (mem_data, state) = memory.data_and_store_mut(&mut caller);
-
-                    let start = offset as usize;
-                    if start >= state.content.len() {
-                        return 0;
-                    }
-                    let end = (start + length as usize).min(state.content.len());
-                    let chunk_len = end - start;
-
-                    let dest = buf_ptr as usize;
-                    if dest + chunk_len > mem_data.len() {
-                        return 0;
-                    }
-                    mem_data[dest..dest + chunk_len]
-                        .copy_from_slice(&state.content[start..end]);
-                    chunk_len as u32
+                        };
+                        let (mem_data, state) = memory.data_and_store_mut(&mut *caller);
+
+                        let Some(range) = state.content_range(offset as usize, length as usize)
+                        else {
+                            return 0;
+                        };
+                        copy_into_memory(mem_data, buf_ptr, &state.content[range])
+                    })
                 },
             )
             .map_err(|e| {
@@ -360,9 +1058,14 @@ impl WasmRunner {
 
         // get_content_feature(spec_ptr: u32, spec_len: u32, output_ptr: u32) -> i32
         // JSON specに基づいてコンテンツの特徴量を計算する。
-        // spec: {"op":"sha256"}, {"op":"sha256","offset":0,"length":1024}, {"op":"sha384"}, {"op":"sha512"}
+        // spec: {"op":"sha256"}, {"op":"sha256","offset":0,"length":1024}, {"op":"sha384"}, {"op":"sha512"}, {"op":"blake3"},
+        //       {"op":"c2pa_cawg_identity","max_length":1024}（出力はJSON: identity_present/sig_type/issuer）,
+        //       {"op":"c2pa_assertion_labels","max_length":4096}（出力はJSON: assertions）
         // 戻り値: 出力バイト数（正値）またはエラーコード（負値）
-        // -1=specパースエラー/未知op, -2=コンテンツ範囲外, -3=出力バッファ境界外
+        // -1=specパースエラー/未知op, -2=コンテンツ範囲外, -3=出力バッファ境界外,
+        // -4=出力がmax_lengthを超過, -5=C2PA構造エラー（マニフェストストアのサイズ上限超過を含む）
+        // C2PAを解析するop（c2pa_cawg_identity / c2pa_assertion_labels）は実行ごとに初回のみ解析し、
+        // その際にコンテンツ長に比例したFuelを課金する（C2PA_PARSE_FUEL_PER_KIB）
         // 仕様書 §7.1
         linker
             .func_wrap(
@@ -372,75 +1075,106 @@ impl WasmRunner {
                  spec_ptr: u32,
                  spec_len: u32,
                  output_ptr: u32|
-                 -> i32 {
-                    let memory = match caller.get_export("memory") {
-                        Some(ext) => match ext.into_memory() {
-                            Some(m) => m,
+                 -> wasmtime::Result<i32> {
+                    host_call(&mut caller, |caller| {
+                        let memory = match caller.get_export("memory") {
+                            Some(ext) => match ext.into_memory() {
+                                Some(m) => m,
+                                None => return -3,
+                            },
                             None => return -3,
-                        },
-                        None => return -3,
-                    };
-                    let (mem_data, state) = memory.data_and_store_mut(&mut caller);
-
-                    // specをWASMメモリから読み取り
-                    let sp = spec_ptr as usize;
-                    let sl = spec_len as usize;
-                    if sp + sl > mem_data.len() {
-                        return -1;
-                    }
-                    let spec_bytes = &mem_data[sp..sp + sl];
-
-                    // JSONパース
-                    let spec: serde_json::Value = match serde_json::from_slice(spec_bytes) {
-                        Ok(v) => v,
-                        Err(_) => return -1,
-                    };
-                    let op = match spec["op"].as_str() {
-                        Some(s) => s,
-                        None => return -1,
-                    };
-
-                    // offset/lengthの取得（オプショナル）
-                    let offset = spec.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-                    let length = spec.get("length").and_then(|v| v.as_u64());
-
-                    // コンテンツ範囲の検証
-                    if offset > state.content.len() {
-                        return -2;
-                    }
-                    let end = match length {
-                        Some(l) => (offset + l as usize).min(state.content.len()),
-                        None => state.content.len(),
-                    };
-                    let data_slice = &state.content[offset..end];
-
-                    // 特徴量計算（仕様書 §7.1）
-                    let hash_bytes: Vec<u8> = match op {
-                        "sha256" => Sha256::digest(data_slice).to_vec(),
-                        "sha384" => Sha384::digest(data_slice).to_vec(),
-                        "sha512" => Sha512::digest(data_slice).to_vec(),
-                        "c2pa_verify_active_cert_chain" => {
-                            let root_spki_hex = match spec.get("root_spki_hex").and_then(|v| v.as_str()) {
-                                Some(s) => s,
-                                None => return -1,
-                            };
-                            // 証明書チェーン検証はコンテンツ全体が必要
-                            match c2pa_cert::verify_active_cert_chain(&state.content, root_spki_hex) {
-                                Ok(true) => vec![0x01],
-                                Ok(false) => vec![0x00],
-                                Err(_) => return -5, // C2PA構造エラー
+                        };
+                        let (mem_data, state) = memory.data_and_store_mut(&mut *caller);
+
+                        // specをWASMメモリから読み取り
+                        let sp = spec_ptr as usize;
+                        let sl = spec_len as usize;
+                        if sp + sl > mem_data.len() {
+                            return -1;
+                        }
+                        let spec_bytes = &mem_data[sp..sp + sl];
+
+                        // JSONパース
+                        let spec: serde_json::Value = match serde_json::from_slice(spec_bytes) {
+                            Ok(v) => v,
+                            Err(_) => return -1,
+                        };
+                        let op = match spec["op"].as_str() {
+                            Some(s) => s,
+                            None => return -1,
+                        };
+
+                        // offset/lengthの取得（オプショナル）
+                        let offset = spec.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+                        let length = spec.get("length").and_then(|v| v.as_u64());
+
+                        // コンテンツ範囲の検証
+                        let length = length.map_or(usize::MAX, |l| l as usize);
+                        let Some(range) = state.content_range(offset, length) else {
+                            return -2;
+                        };
+                        let data_slice = &state.content[range];
+
+                        // 特徴量計算（仕様書 §7.1）
+                        let hash_bytes: Vec<u8> = match op {
+                            "sha256" => Sha256::digest(data_slice).to_vec(),
+                            "sha384" => Sha384::digest(data_slice).to_vec(),
+                            "sha512" => Sha512::digest(data_slice).to_vec(),
+                            "blake3" => blake3::hash(data_slice).as_bytes().to_vec(),
+                            "c2pa_verify_active_cert_chain" => {
+                                let root_spki_hex = match spec.get("root_spki_hex").and_then(|v| v.as_str()) {
+                                    Some(s) => s,
+                                    None => return -1,
+                                };
+                                // 証明書チェーン検証はコンテンツ全体が必要
+                                match c2pa_cert::verify_active_cert_chain(&state.content, root_spki_hex) {
+                                    Ok(true) => vec![0x01],
+                                    Ok(false) => vec![0x00],
+                                    Err(_) => return -5, // C2PA構造エラー
+                                }
+                            }
+                            "c2pa_cawg_identity" => {
+                                // 出力はJSONのため、WASM側の確保サイズを必須で受け取る
+                                let max_length = match spec.get("max_length").and_then(|v| v.as_u64()) {
+                                    Some(l) => l as usize,
+                                    None => return -1,
+                                };
+                                // 解析は実行ごとに1回のみ（結果はキャッシュする）
+                                let bytes = match state.cawg_identity_json() {
+                                    Ok(bytes) => bytes,
+                                    Err(_) => return -5, // C2PA構造エラー
+                                };
+                                if bytes.len() > max_length {
+                                    return -4;
+                                }
+                                bytes.to_vec()
+                            }
+                            "c2pa_assertion_labels" => {
+                                let max_length = match spec.get("max_length").and_then(|v| v.as_u64()) {
+                                    Some(l) => l as usize,
+                                    None => return -1,
+                                };
+                                // 解析は実行ごとに1回のみ（結果はキャッシュする）
+                                let bytes = match state.c2pa_assertion_labels_json() {
+                                    Ok(bytes) => bytes,
+                                    Err(_) => return -5, // C2PA構造エラー
+                                };
+                                if bytes.len() > max_length {
+                                    return -4;
+                                }
+                                bytes.to_vec()
                             }
+                            _ => return -1, // 未知のop
+                        };
+
+                        // 出力バッファへの書き込み
+                        let dest = output_ptr as usize;
+                        if dest + hash_bytes.len() > mem_data.len() {
+                            return -3;
                         }
-                        _ => return -1, // 未知のop
-                    };
-
-                    // 出力バッファへの書き込み
-                    let dest = output_ptr as usize;
-                    if dest + hash_bytes.len() > mem_data.len() {
-                        return -3;
-                    }
-                    mem_data[dest..dest + hash_bytes.len()].copy_from_slice(&hash_bytes);
-                    hash_bytes.len() as i32
+                        mem_data[dest..dest + hash_bytes.len()].copy_from_slice(&hash_bytes);
+                        hash_bytes.len() as i32
+                    })
                 },
             )
             .map_err(|e| {
@@ -449,8 +1183,8 @@ impl WasmRunner {
 
         // hmac_content(algorithm: u32, key_ptr: u32, key_len: u32, offset: u32, length: u32, out_ptr: u32) -> u32
         // コンテンツの指定範囲に対するHMACを計算する。
-        // algorithm: 0=HMAC-SHA256(32B), 1=HMAC-SHA384(48B), 2=HMAC-SHA512(64B)
-        // key はWASMリニアメモリ上のバイト列。
+        // algorithm: 0=HMAC-SHA256(32B), 1=HMAC-SHA384(48B), 2=HMAC-SHA512(64B), 3=keyed BLAKE3(32B)
+        // key はWASMリニアメモリ上のバイト列（keyed BLAKE3は32バイト固定）。
         // 仕様書 §7.1
         linker
             .func_wrap(
@@ -463,64 +1197,74 @@ impl WasmRunner {
                  offset: u32,
                  length: u32,
                  out_ptr: u32|
-                 -> u32 {
-                    let memory = match caller.get_export("memory") {
-                        Some(ext) => match ext.into_memory() {
-                            Some(m) => m,
+                 -> wasmtime::Result<u32> {
+                    host_call(&mut caller, |caller| {
+                        let memory = match caller.get_export("memory") {
+                            Some(ext) => match ext.into_memory() {
+                                Some(m) => m,
+                                None => return 0,
+                            },
                             None => return 0,
-                        },
-                        None => return 0,
-                    };
-                    let (mem_data, state) = memory.data_and_store_mut(&mut caller);
-
-                    // WASMメモリからHMACキーを読み取る
-                    let kp = key_ptr as usize;
-                    let kl = key_len as usize;
-                    if kp + kl > mem_data.len() {
-                        return 0;
-                    }
-                    let key = &mem_data[kp..kp + kl];
-
-                    // コンテンツの指定範囲を取得
-                    let start = offset as usize;
-                    if start >= state.content.len() {
-                        return 0;
-                    }
-                    let end = (start + length as usize).min(state.content.len());
-                    let data_slice = &state.content[start..end];
-
-                    // HMAC計算（仕様書 §7.1）
-                    let mac_bytes: Vec<u8> = match algorithm {
-                        0 => {
-                            let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(key) else {
-                                return 0;
-                            };
-                            mac.update(data_slice);
-                            mac.finalize().into_bytes().to_vec()
+                        };
+                        let (mem_data, state) = memory.data_and_store_mut(&mut *caller);
+
+                        // WASMメモリからHMACキーを読み取る
+                        let kp = key_ptr as usize;
+                        let kl = key_len as usize;
+                        if kp + kl > mem_data.len() {
+                            return 0;
                         }
-                        1 => {
-                            let Ok(mut mac) = Hmac::<Sha384>::new_from_slice(key) else {
-                                return 0;
-                            };
-                            mac.update(data_slice);
-                            mac.finalize().into_bytes().to_vec()
+                        let key = &mem_data[kp..kp + kl];
+
+                        // コンテンツの指定範囲を取得
+                        let start = offset as usize;
+                        if start >= state.content.len() {
+                            return 0;
                         }
-                        2 => {
-                            let Ok(mut mac) = Hmac::<Sha512>::new_from_slice(key) else {
-                                return 0;
-                            };
-                            mac.update(data_slice);
-                            mac.finalize().into_bytes().to_vec()
+                        let Some(range) = state.content_range(start, length as usize) else {
+                            return 0;
+                        };
+                        let data_slice = &state.content[range];
+
+                        // HMAC計算（仕様書 §7.1）
+                        let mac_bytes: Vec<u8> = match algorithm {
+                            0 => {
+                                let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(key) else {
+                                    return 0;
+                                };
+                                mac.update(data_slice);
+                                mac.finalize().into_bytes().to_vec()
+                            }
+                            1 => {
+                                let Ok(mut mac) = Hmac::<Sha384>::new_from_slice(key) else {
+                                    return 0;
+                                };
+                                mac.update(data_slice);
+                                mac.finalize().into_bytes().to_vec()
+                            }
+                            2 => {
+                                let Ok(mut mac) = Hmac::<Sha512>::new_from_slice(key) else {
+                                    return 0;
+                                };
+                                mac.update(data_slice);
+                                mac.finalize().into_bytes().to_vec()
+                            }
+                            3 => {
+                                let Ok(key) = <&[u8; 32]>::try_from(key) else {
+                                    return 0;
+                                };
+                                blake3::keyed_hash(key, data_slice).as_bytes().to_vec()
+                            }
+                            _ => return 0,
+                        };
+
+                        let dest = out_ptr as usize;
+                        if dest + mac_bytes.len() > mem_data.len() {
+                            return 0;
                         }
-                        _ => return 0,
-                    };
-
-                    let dest = out_ptr as usize;
-                    if dest + mac_bytes.len() > mem_data.len() {
-                        return 0;
-                    }
-                    mem_data[dest..dest + mac_bytes.len()].copy_from_slice(&mac_bytes);
-                    mac_bytes.len() as u32
+                        mem_data[dest..dest + mac_bytes.len()].copy_from_slice(&mac_bytes);
+                        mac_bytes.len() as u32
+                    })
                 },
             )
             .map_err(|e| WasmError::ExecutionError(format!("hmac_contentの登録に失敗: {e}")))?;
@@ -537,145 +1281,253 @@ impl WasmRunner {
                 |mut caller: Caller<'_, InnerHostState>,
                  buf_ptr: u32,
                  buf_len: u32|
-                 -> u32 {
-                    let memory = match caller.get_export("memory") {
-                        Some(ext) => match ext.into_memory() {
-                            Some(m) => m,
+                 -> wasmtime::Result<u32> {
+                    host_call(&mut caller, |caller| {
+                        let memory = match caller.get_export("memory") {
+                            Some(ext) => match ext.into_memory() {
+                                Some(m) => m,
+                                None => return 0,
+                            },
                             None => return 0,
-                        },
-                        None => return 0,
-                    };
-                    let (mem_data, state) = memory.data_and_store_mut(&mut caller);
-
-                    match &state.extension_input {
-                        Some(input) => {
-                            let actual_size = input.len() as u32;
-                            let copy_len = (buf_len as usize).min(input.len());
-                            let dest = buf_ptr as usize;
-                            if dest + copy_len > mem_data.len() {
-                                return actual_size;
+                        };
+                        let (mem_data, state) = memory.data_and_store_mut(&mut *caller);
+
+                        match &state.extension_input {
+                            Some(input) => {
+                                let actual_size = input.len() as u32;
+                                let copy_len = (buf_len as usize).min(input.len());
+                                let dest = buf_ptr as usize;
+                                if dest + copy_len > mem_data.len() {
+                                    return actual_size;
+                                }
+                                mem_data[dest..dest + copy_len]
+                                    .copy_from_slice(&input[..copy_len]);
+                                actual_size
                             }
-                            mem_data[dest..dest + copy_len]
-                                .copy_from_slice(&input[..copy_len]);
-                            actual_size
+                            None => 0,
                         }
-                        None => 0,
-                    }
+                    })
                 },
             )
             .map_err(|e| {
                 WasmError::ExecutionError(format!("get_extension_inputの登録に失敗: {e}"))
             })?;
 
-        // get_content_length() -> u32
-        // コンテンツの全長を返す。
+        // get_extension_input_len() -> u32
+        // Extension補助入力の全長を返す（補助入力が存在しない場合は0）。
+        // 仕様書 §7.1
         linker
             .func_wrap(
                 "env",
-                "get_content_length",
-                |caller: Caller<'_, InnerHostState>| -> u32 {
-                    caller.data().content.len() as u32
+                "get_extension_input_len",
+                |mut caller: Caller<'_, InnerHostState>| -> wasmtime::Result<u32> {
+                    host_call(&mut caller, |caller| {
+                        caller.data().extension_input_length()
+                    })
                 },
             )
             .map_err(|e| {
-                WasmError::ExecutionError(format!("get_content_lengthの登録に失敗: {e}"))
+                WasmError::ExecutionError(format!("get_extension_input_lenの登録に失敗: {e}"))
             })?;
 
-        // decode_content(params_ptr: u32, params_len: u32, metadata_ptr: u32) -> i32
-        // コンテンツをデコードし、InnerHostState.decoded に格納する。
-        // metadata_ptr にデコーダー固有のメタデータを書き込む。
-        // コンテンツ種別（画像・音声等）は自動判定される。
-        // 戻り値: 0=成功, -1=非対応フォーマット, -2=メモリ予算超過, -3=デコードエラー
+        // get_extension_input_chunk(offset: u32, length: u32, buf_ptr: u32) -> u32
+        // Extension補助入力のチャンクを読み取り、WASMメモリにコピーする（read_content_chunkと同じセマンティクス）。
+        // 範囲は全長でクリップされ、全長以降の読み取り・補助入力なしの場合は0を返す。
+        // 大きな補助入力を入力全体分のバッファを確保せずに読むために用いる。
         // 仕様書 §7.1
         linker
             .func_wrap(
                 "env",
-                "decode_content",
+                "get_extension_input_chunk",
                 |mut caller: Caller<'_, InnerHostState>,
-                 _params_ptr: u32,
-                 _params_len: u32,
-                 metadata_ptr: u32|
-                 -> i32 {
-                    // 1. デコーダー自動選択
-                    let kind = {
-                        let state = caller.data();
-                        match crate::decode::detect(&state.content) {
-                            Some(k) => k,
-                            None => return -1, // 非対応フォーマット
-                        }
-                    };
-
-                    // 2. ピークメモリ推定（ヘッダのみ読み、圧縮爆弾対策）
-                    let peak_size = {
-                        let state = caller.data();
-                        match crate::decode::estimate_peak_bytes(kind, &state.content) {
-                            Ok(s) => s,
-                            Err(rc) => return rc,
-                        }
-                    };
-
-                    // 3. 2回目以降の呼び出し: 前回のチケットを解放
-                    {
-                        let state = caller.data_mut();
-                        state.decode_ticket = None;
-                        state.decoded = None;
-                    }
-
-                    // 4. ResourcePool で予約（Ticket 発行）
-                    {
-                        let pool_opt = caller.data().resource_pool.clone();
-                        if let Some(ref pool) = pool_opt {
-                            match pool.acquire(peak_size) {
-                                Some(ticket) => {
-                                    caller.data_mut().decode_ticket = Some(ticket);
-                                }
-                                None => return -2, // メモリ予算超過
-                            }
-                        }
-                    }
-
-                    // 5. フルデコード
-                    let result = {
-                        let state = caller.data();
-                        match crate::decode::decode(kind, &state.content) {
-                            Ok(r) => r,
-                            Err(rc) => return rc,
-                        }
-                    };
-
-                    // 6. メタデータをWASMメモリに書き込み（フォーマット非依存）
-                    let memory = match caller.get_export("memory") {
-                        Some(ext) => match ext.into_memory() {
-                            Some(m) => m,
-                            None => return -3,
-                        },
-                        None => return -3,
-                    };
-                    let mem_data = memory.data_mut(&mut caller);
-                    let mp = metadata_ptr as usize;
-                    if mp + result.metadata.len() > mem_data.len() {
-                        return -3; // metadata_ptrが境界外
-                    }
-                    mem_data[mp..mp + result.metadata.len()]
-                        .copy_from_slice(&result.metadata);
-
-                    // 7. デコード済みデータを格納（メタデータも保持）
-                    let w = u32::from_le_bytes([result.metadata[0], result.metadata[1], result.metadata[2], result.metadata[3]]);
-                    let h = u32::from_le_bytes([result.metadata[4], result.metadata[5], result.metadata[6], result.metadata[7]]);
-                    let ch = u32::from_le_bytes([result.metadata[8], result.metadata[9], result.metadata[10], result.metadata[11]]);
-                    let state = caller.data_mut();
-                    state.decoded = Some(DecodedContent {
-                        data: result.data,
-                        width: w,
-                        height: h,
-                        channels: ch,
-                    });
-
-                    0 // 成功
+                 offset: u32,
+                 length: u32,
+                 buf_ptr: u32|
+                 -> wasmtime::Result<u32> {
+                    host_call(&mut caller, |caller| {
+                        let memory = match caller.get_export("memory") {
+                            Some(ext) => match ext.into_memory() {
+                                Some(m) => m,
+                                None => return 0,
+                            },
+                            None => return 0,
+                        };
+                        let (mem_data, state) = memory.data_and_store_mut(&mut *caller);
+
+                        let Some(range) = state.extension_input_range(offset as usize, length as usize)
+                        else {
+                            return 0;
+                        };
+                        let Some(input) = &state.extension_input else {
+                            return 0;
+                        };
+                        copy_into_memory(mem_data, buf_ptr, &input[range])
+                    })
                 },
             )
             .map_err(|e| {
-                WasmError::ExecutionError(format!("decode_contentの登録に失敗: {e}"))
+                WasmError::ExecutionError(format!("get_extension_input_chunkの登録に失敗: {e}"))
+            })?;
+
+        // get_content_mime(buf_ptr: u32, buf_len: u32) -> u32
+        // TEEが検出したコンテンツのMIMEタイプ（UTF-8）をWASMメモリにコピーする。
+        // 実際のサイズを返す。buf_len未満の場合もサイズのみ返す（データはコピーされない）。
+        // MIMEタイプが指定されていない場合は0を返す。
+        // 仕様書 §7.1
+        linker
+            .func_wrap(
+                "env",
+                "get_content_mime",
+                |mut caller: Caller<'_, InnerHostState>,
+                 buf_ptr: u32,
+                 buf_len: u32|
+                 -> wasmtime::Result<u32> {
+                    host_call(&mut caller, |caller| {
+                        let memory = match caller.get_export("memory") {
+                            Some(ext) => match ext.into_memory() {
+                                Some(m) => m,
+                                None => return 0,
+                            },
+                            None => return 0,
+                        };
+                        let (mem_data, state) = memory.data_and_store_mut(&mut *caller);
+
+                        match &state.content_mime {
+                            Some(mime) => {
+                                let mime = mime.as_bytes();
+                                let actual_size = mime.len() as u32;
+                                if mime.len() > buf_len as usize {
+                                    return actual_size;
+                                }
+                                let dest = buf_ptr as usize;
+                                if dest + mime.len() > mem_data.len() {
+                                    return actual_size;
+                                }
+                                mem_data[dest..dest + mime.len()].copy_from_slice(mime);
+                                actual_size
+                            }
+                            None => 0,
+                        }
+                    })
+                },
+            )
+            .map_err(|e| {
+                WasmError::ExecutionError(format!("get_content_mimeの登録に失敗: {e}"))
+            })?;
+
+        // get_content_length() -> u32
+        // コンテンツの全長を返す。
+        linker
+            .func_wrap(
+                "env",
+                "get_content_length",
+                |mut caller: Caller<'_, InnerHostState>| -> wasmtime::Result<u32> {
+                    host_call(&mut caller, |caller| {
+                        caller.data().content_length()
+                    })
+                },
+            )
+            .map_err(|e| {
+                WasmError::ExecutionError(format!("get_content_lengthの登録に失敗: {e}"))
+            })?;
+
+        // decode_content(params_ptr: u32, params_len: u32, metadata_ptr: u32) -> i32
+        // コンテンツをデコードし、InnerHostState.decoded に格納する。
+        // metadata_ptr にデコーダー固有のメタデータを書き込む。
+        // コンテンツ種別（画像・音声等）は自動判定される。
+        // 戻り値: 0=成功, -1=非対応フォーマット, -2=メモリ予算超過, -3=デコードエラー
+        // 仕様書 §7.1
+        linker
+            .func_wrap(
+                "env",
+                "decode_content",
+                |mut caller: Caller<'_, InnerHostState>,
+                 _params_ptr: u32,
+                 _params_len: u32,
+                 metadata_ptr: u32|
+                 -> wasmtime::Result<i32> {
+                    host_call(&mut caller, |caller| {
+                        // 1. デコーダー自動選択
+                        let kind = {
+                            let state = caller.data();
+                            match crate::decode::detect(&state.content) {
+                                Some(k) => k,
+                                None => return -1, // 非対応フォーマット
+                            }
+                        };
+
+                        // 2. ピークメモリ推定（ヘッダのみ読み、圧縮爆弾対策）
+                        let peak_size = {
+                            let state = caller.data();
+                            match crate::decode::estimate_peak_bytes(kind, &state.content) {
+                                Ok(s) => s,
+                                Err(rc) => return rc,
+                            }
+                        };
+
+                        // 3. 2回目以降の呼び出し: 前回のチケットを解放
+                        {
+                            let state = caller.data_mut();
+                            state.decode_ticket = None;
+                            state.decoded = None;
+                        }
+
+                        // 4. ResourcePool で予約（Ticket 発行）
+                        {
+                            let pool_opt = caller.data().resource_pool.clone();
+                            if let Some(ref pool) = pool_opt {
+                                match pool.acquire(peak_size) {
+                                    Some(ticket) => {
+                                        caller.data_mut().decode_ticket = Some(ticket);
+                                    }
+                                    None => return -2, // メモリ予算超過
+                                }
+                            }
+                        }
+
+                        // 5. フルデコード
+                        let result = {
+                            let state = caller.data();
+                            match crate::decode::decode(kind, &state.content) {
+                                Ok(r) => r,
+                                Err(rc) => return rc,
+                            }
+                        };
+
+                        // 6. メタデータをWASMメモリに書き込み（フォーマット非依存）
+                        let memory = match caller.get_export("memory") {
+                            Some(ext) => match ext.into_memory() {
+                                Some(m) => m,
+                                None => return -3,
+                            },
+                            None => return -3,
+                        };
+                        let mem_data = memory.data_mut(&mut *caller);
+                        let mp = metadata_ptr as usize;
+                        if mp + result.metadata.len() > mem_data.len() {
+                            return -3; // metadata_ptrが境界外
+                        }
+                        mem_data[mp..mp + result.metadata.len()]
+                            .copy_from_slice(&result.metadata);
+
+                        // 7. デコード済みデータを格納（メタデータも保持）
+                        let w = u32::from_le_bytes([result.metadata[0], result.metadata[1], result.metadata[2], result.metadata[3]]);
+                        let h = u32::from_le_bytes([result.metadata[4], result.metadata[5], result.metadata[6], result.metadata[7]]);
+                        let ch = u32::from_le_bytes([result.metadata[8], result.metadata[9], result.metadata[10], result.metadata[11]]);
+                        let state = caller.data_mut();
+                        state.decoded = Some(DecodedContent {
+                            data: result.data,
+                            width: w,
+                            height: h,
+                            channels: ch,
+                        });
+
+                        0 // 成功
+                    })
+                },
+            )
+            .map_err(|e| {
+                WasmError::ExecutionError(format!("decode_contentの登録に失敗: {e}"))
             })?;
 
         // read_decoded_chunk(offset: u32, length: u32, buf_ptr: u32) -> u32
@@ -689,35 +1541,28 @@ impl WasmRunner {
                  offset: u32,
                  length: u32,
                  buf_ptr: u32|
-                 -> u32 {
-                    let memory = match caller.get_export("memory") {
-                        Some(ext) => match ext.into_memory() {
-                            Some(m) => m,
+                 -> wasmtime::Result<u32> {
+                    host_call(&mut caller, |caller| {
+                        let memory = match caller.get_export("memory") {
+                            Some(ext) => match ext.into_memory() {
+                                Some(m) => m,
+                                None => return 0,
+                            },
+                            None => return 0,
+                        };
+                        let (mem_data, state) = memory.data_and_store_mut(&mut *caller);
+
+                        let decoded = match &state.decoded {
+                            Some(d) => d,
                             None => return 0,
-                        },
-                        None => return 0,
-                    };
-                    let (mem_data, state) = memory.data_and_store_mut(&mut caller);
-
-                    let decoded = match &state.decoded {
-                        Some(d) => d,
-                        None => return 0,
-                    };
-
-                    let start = offset as usize;
-                    if start >= decoded.data.len() {
-                        return 0;
-                    }
-                    let end = (start + length as usize).min(decoded.data.len());
-                    let chunk_len = end - start;
-
-                    let dest = buf_ptr as usize;
-                    if dest + chunk_len > mem_data.len() {
-                        return 0;
-                    }
-                    mem_data[dest..dest + chunk_len]
-                        .copy_from_slice(&decoded.data[start..end]);
-                    chunk_len as u32
+                        };
+
+                        let start = offset as usize;
+                        let Some(rest) = decoded.data.get(start..) else {
+                            return 0;
+                        };
+                        copy_into_memory(mem_data, buf_ptr, &rest[..rest.len().min(length as usize)])
+                    })
                 },
             )
             .map_err(|e| {
@@ -731,12 +1576,14 @@ impl WasmRunner {
             .func_wrap(
                 "env",
                 "get_decoded_length",
-                |caller: Caller<'_, InnerHostState>| -> u32 {
-                    caller
-                        .data()
-                        .decoded
-                        .as_ref()
-                        .map_or(0, |d| d.data.len() as u32)
+                |mut caller: Caller<'_, InnerHostState>| -> wasmtime::Result<u32> {
+                    host_call(&mut caller, |caller| {
+                        caller
+                            .data()
+                            .decoded
+                            .as_ref()
+                            .map_or(0, |d| d.data.len() as u32)
+                    })
                 },
             )
             .map_err(|e| {
@@ -746,6 +1593,7 @@ impl WasmRunner {
         // get_decoded_feature(spec_ptr: u32, spec_len: u32, output_ptr: u32) -> i32
         // JSON specに基づいてデコード済みデータの特徴量を計算する。
         // spec: {"op":"grayscale_resize","width":32,"height":32}
+        //       {"op":"canonical_rgb","width":16,"height":16,"quant_bits":4}
         // 戻り値: 出力バイト数（正値）またはエラーコード（負値）
         // -1=specパースエラー/未知op, -3=出力バッファ境界外, -4=デコード未実行, -5=チャネル数不正
         // 仕様書 §7.1
@@ -757,95 +1605,105 @@ impl WasmRunner {
                  spec_ptr: u32,
                  spec_len: u32,
                  output_ptr: u32|
-                 -> i32 {
-                    let memory = match caller.get_export("memory") {
-                        Some(ext) => match ext.into_memory() {
-                            Some(m) => m,
+                 -> wasmtime::Result<i32> {
+                    host_call(&mut caller, |caller| {
+                        let memory = match caller.get_export("memory") {
+                            Some(ext) => match ext.into_memory() {
+                                Some(m) => m,
+                                None => return -3,
+                            },
                             None => return -3,
-                        },
-                        None => return -3,
-                    };
-                    let (mem_data, state) = memory.data_and_store_mut(&mut caller);
-
-                    // specをWASMメモリから読み取り
-                    let sp = spec_ptr as usize;
-                    let sl = spec_len as usize;
-                    if sp + sl > mem_data.len() {
-                        return -1;
-                    }
-                    let spec_bytes = &mem_data[sp..sp + sl];
-
-                    // JSONパース
-                    let spec: serde_json::Value = match serde_json::from_slice(spec_bytes) {
-                        Ok(v) => v,
-                        Err(_) => return -1,
-                    };
-                    let op = match spec["op"].as_str() {
-                        Some(s) => s,
-                        None => return -1,
-                    };
-
-                    // デコード済みデータの存在確認
-                    let decoded = match &state.decoded {
-                        Some(d) => d,
-                        None => return -4,
-                    };
-
-                    match op {
-                        "grayscale_resize" => {
-                            let target_w = match spec.get("width").and_then(|v| v.as_u64()) {
-                                Some(w) => w as u32,
-                                None => return -1,
-                            };
-                            let target_h = match spec.get("height").and_then(|v| v.as_u64()) {
-                                Some(h) => h as u32,
-                                None => return -1,
-                            };
-
-                            // グレースケール変換（ITU-R BT.601）+ リサイズ
-                            use image::{DynamicImage, GrayImage, RgbImage, RgbaImage};
-
-                            let gray_img = match decoded.channels {
-                                1 => match GrayImage::from_raw(decoded.width, decoded.height, decoded.data.clone()) {
-                                    Some(img) => img,
+                        };
+                        let (mem_data, state) = memory.data_and_store_mut(&mut *caller);
+
+                        // specをWASMメモリから読み取り
+                        let sp = spec_ptr as usize;
+                        let sl = spec_len as usize;
+                        if sp + sl > mem_data.len() {
+                            return -1;
+                        }
+                        let spec_bytes = &mem_data[sp..sp + sl];
+
+                        // JSONパース
+                        let spec: serde_json::Value = match serde_json::from_slice(spec_bytes) {
+                            Ok(v) => v,
+                            Err(_) => return -1,
+                        };
+                        let op = match spec["op"].as_str() {
+                            Some(s) => s,
+                            None => return -1,
+                        };
+
+                        // デコード済みデータの存在確認
+                        let decoded = match &state.decoded {
+                            Some(d) => d,
+                            None => return -4,
+                        };
+
+                        match op {
+                            "grayscale_resize" => {
+                                let target_w = match spec.get("width").and_then(|v| v.as_u64()) {
+                                    Some(w) => w as u32,
+                                    None => return -1,
+                                };
+                                let target_h = match spec.get("height").and_then(|v| v.as_u64()) {
+                                    Some(h) => h as u32,
+                                    None => return -1,
+                                };
+
+                                // グレースケール変換（ITU-R BT.601）+ リサイズ
+                                let output = match decode::grayscale_resize(
+                                    &decoded.data,
+                                    decoded.width,
+                                    decoded.height,
+                                    decoded.channels,
+                                    target_w,
+                                    target_h,
+                                ) {
+                                    Some(o) => o,
                                     None => return -5,
-                                },
-                                3 => {
-                                    let rgb = match RgbImage::from_raw(decoded.width, decoded.height, decoded.data.clone()) {
-                                        Some(img) => img,
-                                        None => return -5,
-                                    };
-                                    DynamicImage::ImageRgb8(rgb).to_luma8()
+                                };
+
+                                // WASMメモリに出力
+                                let dest = output_ptr as usize;
+                                if dest + output.len() > mem_data.len() {
+                                    return -3;
                                 }
-                                4 => {
-                                    let rgba = match RgbaImage::from_raw(decoded.width, decoded.height, decoded.data.clone()) {
-                                        Some(img) => img,
-                                        None => return -5,
+                                mem_data[dest..dest + output.len()].copy_from_slice(&output);
+                                output.len() as i32
+                            }
+                            "canonical_rgb" => {
+                                // ピクセルハッシュ用の正規形（アルファ除去 + リサイズ + 量子化）
+                                let field = |name: &str| spec.get(name).and_then(|v| v.as_u64());
+                                let (target_w, target_h, quant_bits) =
+                                    match (field("width"), field("height"), field("quant_bits")) {
+                                        (Some(w), Some(h), Some(q)) => (w as u32, h as u32, q as u32),
+                                        _ => return -1,
                                     };
-                                    DynamicImage::ImageRgba8(rgba).to_luma8()
+
+                                let output = match decode::canonical_rgb(
+                                    &decoded.data,
+                                    decoded.width,
+                                    decoded.height,
+                                    decoded.channels,
+                                    target_w,
+                                    target_h,
+                                    quant_bits,
+                                ) {
+                                    Some(o) => o,
+                                    None => return -5,
+                                };
+
+                                let dest = output_ptr as usize;
+                                if dest + output.len() > mem_data.len() {
+                                    return -3;
                                 }
-                                _ => return -5,
-                            };
-
-                            // バイリニア補間リサイズ
-                            let resized = image::imageops::resize(
-                                &gray_img,
-                                target_w,
-                                target_h,
-                                image::imageops::FilterType::Triangle,
-                            );
-                            let output = resized.into_raw();
-
-                            // WASMメモリに出力
-                            let dest = output_ptr as usize;
-                            if dest + output.len() > mem_data.len() {
-                                return -3;
+                                mem_data[dest..dest + output.len()].copy_from_slice(&output);
+                                output.len() as i32
                             }
-                            mem_data[dest..dest + output.len()].copy_from_slice(&output);
-                            output.len() as i32
+                            _ => -1, // 未知のop
                         }
-                        _ => -1, // 未知のop
-                    }
+                    })
                 },
             )
             .map_err(|e| {
@@ -888,7 +1746,7 @@ mod tests {
         )
         .unwrap();
 
-        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024);
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let content = b"Hello, WASM host!";
         let ext_input = b"{\"key\": \"value\"}";
 
@@ -897,6 +1755,171 @@ mod tests {
             .expect("WASM実行に成功するべき");
 
         assert_eq!(result.output["result"], "ok");
+        assert!(result.fuel_consumed > 0);
+        assert!(result.fuel_consumed < runner.fuel_limit());
+    }
+
+    /// テスト: 消費Fuel量は実行した命令数に応じて増え、同一入力では決定的である
+    #[test]
+    fn test_fuel_consumed_reflects_work() {
+        let looping = |iterations: u32| {
+            wat::parse_str(format!(
+                r#"(module
+                (memory (export "memory") 1)
+                (data (i32.const 1024) "\02\00\00\00{{}}")
+                (func (export "process") (result i32)
+                    (local $i i32)
+                    (loop $l
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br_if $l (i32.lt_u (local.get $i) (i32.const {iterations})))
+                    )
+                    (i32.const 1024)
+                )
+            )"#
+            ))
+            .unwrap()
+        };
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let fuel = |wasm: &[u8]| runner.execute(wasm, b"content", None, "process").unwrap().fuel_consumed;
+
+        let short = fuel(&looping(10));
+        let long = fuel(&looping(1000));
+        assert!(long > short);
+        assert_eq!(long, fuel(&looping(1000)));
+    }
+
+    /// テスト: get_extension_input_len と get_extension_input_chunk で補助入力を小さなチャンクで読み進め、
+    /// 全体を再構成できる。全長以降の読み取りは0を返す
+    /// 仕様書 §7.1
+    #[test]
+    fn test_extension_input_chunked_read() {
+        // 補助入力を7バイトずつ読み、そのまま結果バッファ（オフセット4096）として返すモジュール
+        let wasm = wat::parse_str(
+            r#"(module
+            (import "env" "get_extension_input_len" (func $ext_len (result i32)))
+            (import "env" "get_extension_input_chunk" (func $ext_chunk (param i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (func (export "process") (result i32)
+                (local $len i32)
+                (local $offset i32)
+                (local $n i32)
+                (local.set $len (call $ext_len))
+                (block $done
+                    (loop $read
+                        (local.set $n (call $ext_chunk
+                            (local.get $offset)
+                            (i32.const 7)
+                            (i32.add (i32.const 4100) (local.get $offset))))
+                        (br_if $done (i32.eqz (local.get $n)))
+                        ;; 末尾以外のチャンクは要求長どおり
+                        (if (i32.and
+                                (i32.ne (local.get $n) (i32.const 7))
+                                (i32.ne (i32.add (local.get $offset) (local.get $n)) (local.get $len)))
+                            (then unreachable))
+                        (local.set $offset (i32.add (local.get $offset) (local.get $n)))
+                        (br $read)
+                    )
+                )
+                ;; 読み取った総量が全長と一致し、全長を超える位置からの読み取りは0
+                (if (i32.ne (local.get $offset) (local.get $len)) (then unreachable))
+                (if (i32.ne (call $ext_chunk (i32.add (local.get $len) (i32.const 1)) (i32.const 7) (i32.const 0)) (i32.const 0))
+                    (then unreachable))
+                (i32.store (i32.const 4096) (local.get $len))
+                (i32.const 4096)
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let input = serde_json::json!({ "features": (0..100).collect::<Vec<u32>>() });
+        let input_bytes = serde_json::to_vec(&input).unwrap();
+        assert_ne!(input_bytes.len() % 7, 0);
+
+        let result = runner
+            .execute(&wasm, b"content", Some(&input_bytes), "process")
+            .expect("WASM実行に成功するべき");
+        assert_eq!(result.output, input);
+
+        // 補助入力がない場合、全長・読み取りとも0となり、空の結果バッファ（JSONとして不正）が返る
+        let err = runner.execute(&wasm, b"content", None, "process").unwrap_err();
+        assert!(matches!(err, WasmError::ExecutionError(_)), "got {err:?}");
+    }
+
+    /// テスト: get_content_mime でTEEが検出したMIMEタイプを読み取れる
+    /// 仕様書 §7.1
+    #[test]
+    fn test_get_content_mime() {
+        // MIMEタイプをJSON文字列として結果バッファに書き出すモジュール
+        let wasm = wat::parse_str(
+            r#"(module
+            (import "env" "get_content_mime" (func $mime (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 4096))
+            (func (export "process") (result i32)
+                (local $n i32)
+                (local.set $n (call $mime (i32.const 2053) (i32.const 64)))
+                (i32.store8 (i32.const 2052) (i32.const 34))
+                (i32.store8 (i32.add (i32.const 2053) (local.get $n)) (i32.const 34))
+                (i32.store (i32.const 2048) (i32.add (local.get $n) (i32.const 2)))
+                (i32.const 2048)
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+
+        let result = runner
+            .execute_with_mime(&wasm, &jpeg, Some("image/jpeg"), None, "process")
+            .expect("WASM実行に成功するべき");
+        assert_eq!(result.output, "image/jpeg");
+
+        // MIMEタイプ未指定の場合は0（空文字列）
+        let result = runner.execute(&wasm, &jpeg, None, "process").unwrap();
+        assert_eq!(result.output, "");
+    }
+
+    /// テスト: 許可一覧にないホスト関数をインポートするモジュールは実行前に拒否される
+    /// 仕様書 §6.4, §7.1
+    #[test]
+    fn test_allowed_host_imports() {
+        let wasm = wat::parse_str(
+            r#"(module
+            (import "env" "get_content_length" (func $len (result i32)))
+            (import "env" "get_extension_input" (func $input (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (func (export "process") (result i32)
+                (i32.store (i32.const 2048) (i32.const 1))
+                (i32.store8 (i32.const 2052) (i32.const 48))
+                (i32.const 2048)
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+            .with_allowed_host_imports(["get_content_length"]);
+        match runner.execute(&wasm, b"content", None, "process") {
+            Err(WasmError::ForbiddenHostImport(name)) => assert_eq!(name, "get_extension_input"),
+            other => panic!("ForbiddenHostImportが期待される: {other:?}"),
+        }
+
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+            .with_allowed_host_imports(["get_content_length", "get_extension_input"]);
+        let result = runner.execute(&wasm, b"content", None, "process").unwrap();
+        assert_eq!(result.output, 0);
+
+        // インスタンスプール使用時も同様に拒否する
+        let pool = Arc::new(InstancePool::new(1, 16 * 1024 * 1024).unwrap());
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+            .with_instance_pool(pool)
+            .with_allowed_host_imports(HOST_FUNCTIONS.iter().copied().filter(|&f| f != "get_content_length"));
+        assert!(matches!(
+            runner.execute(&wasm, b"content", None, "process"),
+            Err(WasmError::ForbiddenHostImport(_))
+        ));
     }
 
     /// テスト: Fuel制限超過でエラー
@@ -922,7 +1945,7 @@ mod tests {
         .unwrap();
 
         // 極小のFuel制限
-        let runner = WasmRunner::new(100, 16 * 1024 * 1024);
+        let runner = WasmRunner::new(100, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner.execute(&wasm, b"content", None, "compute_phash");
 
         assert!(result.is_err());
@@ -932,6 +1955,114 @@ mod tests {
         }
     }
 
+    /// テスト: 深い再帰はスタック上限超過として型付きエラーになる
+    /// 仕様書 §7.1
+    #[test]
+    fn test_stack_exhaustion() {
+        let wasm = wat::parse_str(
+            r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 0))
+            (func $recurse (param i32) (result i32)
+                ;; 終了条件のない再帰
+                (i32.add (call $recurse (i32.add (local.get 0) (i32.const 1))) (i32.const 1))
+            )
+            (func (export "process") (result i32)
+                (call $recurse (i32.const 0))
+            )
+        )"#,
+        )
+        .unwrap();
+
+        // デフォルトの上限・縮小した上限のどちらでもStackExhaustedになる
+        for runner in [
+            WasmRunner::new(u64::MAX, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS),
+            WasmRunner::new(u64::MAX, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+                .with_max_wasm_stack(64 * 1024),
+        ] {
+            match runner.execute(&wasm, b"content", None, "process") {
+                Err(WasmError::StackExhausted) => {}
+                other => panic!("StackExhaustedが期待されますが、取得: {other:?}"),
+            }
+        }
+    }
+
+    /// テスト: 実行中のWASM（無限ループ）が別スレッドからの中断要求で停止する
+    /// 仕様書 §6.4, §7.1
+    #[test]
+    fn test_interrupt_stops_running_wasm() {
+        let wasm = wat::parse_str(
+            r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 0))
+            (func (export "process") (result i32)
+                (loop $l (br $l))
+                (i32.const 0)
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let cache = Arc::new(ModuleCache::new(4).unwrap());
+        let handle = InterruptHandle::new();
+        let runner = WasmRunner::new(u64::MAX, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+            .with_module_cache(Arc::clone(&cache))
+            .with_interrupt(handle.clone());
+        let interrupter = {
+            let handle = handle.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                handle.interrupt();
+            })
+        };
+        match runner.execute(&wasm, b"content", None, "process") {
+            Err(WasmError::Interrupted) => {}
+            other => panic!("Interruptedが期待されますが、取得: {other:?}"),
+        }
+        interrupter.join().unwrap();
+
+        // 中断済みのハンドルで開始した実行は即座に中断される
+        assert!(matches!(
+            runner.execute(&wasm, b"content", None, "process"),
+            Err(WasmError::Interrupted)
+        ));
+    }
+
+    /// テスト: キャッシュの共有Engineとスタック上限が異なるランナーは黙って実行せずエラーになる
+    /// 仕様書 §7.1
+    #[test]
+    fn test_max_wasm_stack_must_match_shared_engine() {
+        let wasm = wat::parse_str(
+            r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 0))
+            (func $recurse (param i32) (result i32)
+                (i32.add (call $recurse (i32.add (local.get 0) (i32.const 1))) (i32.const 1))
+            )
+            (func (export "process") (result i32)
+                (call $recurse (i32.const 0))
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let cache = Arc::new(ModuleCache::with_max_wasm_stack(4, 64 * 1024).unwrap());
+        let mismatched = WasmRunner::new(u64::MAX, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+            .with_module_cache(Arc::clone(&cache));
+        match mismatched.execute(&wasm, b"content", None, "process") {
+            Err(WasmError::CompileError(msg)) => assert!(msg.contains("max_wasm_stack")),
+            other => panic!("CompileErrorが期待されますが、取得: {other:?}"),
+        }
+
+        let matched = WasmRunner::new(u64::MAX, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+            .with_max_wasm_stack(64 * 1024)
+            .with_module_cache(cache);
+        match matched.execute(&wasm, b"content", None, "process") {
+            Err(WasmError::StackExhausted) => {}
+            other => panic!("StackExhaustedが期待されますが、取得: {other:?}"),
+        }
+    }
+
     /// テスト: WASMトラップがcatch_unwindで捕捉される
     /// 仕様書 §7.1
     #[test]
@@ -953,7 +2084,7 @@ mod tests {
         )
         .unwrap();
 
-        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024);
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner.execute(&wasm, b"content", None, "compute_phash");
 
         assert!(result.is_err());
@@ -994,7 +2125,7 @@ mod tests {
         )
         .unwrap();
 
-        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024);
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let content = vec![0u8; 42]; // 42バイトのコンテンツ
 
         let result = runner
@@ -1004,6 +2135,136 @@ mod tests {
         assert_eq!(result.output["len"], 42);
     }
 
+    /// テスト: read_content_chunkの読み取り合計がget_content_lengthと一致し、全長を超えて読まない
+    /// 仕様書 §7.1
+    #[test]
+    fn test_chunk_reads_consistent_with_content_length() {
+        // 7バイトずつ読み進め、合計が全長と一致すること、
+        // 終端付近のチャンクが全長でクリップされること、終端以降の読み取りが0を返すことを確認するWASM
+        let wasm = wat::parse_str(
+            r#"(module
+            (import "env" "read_content_chunk" (func $read (param i32 i32 i32) (result i32)))
+            (import "env" "get_content_length" (func $len (result i32)))
+            (import "env" "get_content_feature" (func $gcf (param i32 i32 i32) (result i32)))
+            (import "env" "hmac_content" (func $hmac (param i32 i32 i32 i32 i32 i32) (result i32)))
+            (import "env" "get_extension_input" (func $ext (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            ;; 成功時の結果: {"ok":true} = 11バイト
+            (data (i32.const 1024) "\0b\00\00\00{\"ok\":true}")
+            ;; 失敗時の結果: {"ok":false} = 12バイト
+            (data (i32.const 2048) "\0c\00\00\00{\"ok\":false}")
+            (func (export "alloc") (param i32) (result i32) (i32.const 4096))
+            (func (export "process") (result i32)
+                (local $total i32)
+                (local $n i32)
+                (local $len i32)
+                (local.set $len (call $len))
+                (block $done
+                    (loop $next
+                        (local.set $n (call $read (local.get $total) (i32.const 7) (i32.const 8192)))
+                        (br_if $done (i32.eqz (local.get $n)))
+                        (local.set $total (i32.add (local.get $total) (local.get $n)))
+                        (br $next)
+                    )
+                )
+                (if (result i32)
+                    (i32.and
+                        (i32.and
+                            (i32.eq (local.get $total) (local.get $len))
+                            (i32.eqz (call $read (local.get $len) (i32.const 7) (i32.const 8192))))
+                        (i32.eq
+                            (call $read (i32.sub (local.get $len) (i32.const 3)) (i32.const 7) (i32.const 8192))
+                            (i32.const 3)))
+                    (then (i32.const 1024))
+                    (else (i32.const 2048))
+                )
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        // チャンクサイズ（7）の倍数でない長さ
+        let content: Vec<u8> = (0..100u8).collect();
+
+        let result = runner
+            .execute(&wasm, &content, None, "process")
+            .expect("WASM実行に成功するべき");
+
+        assert_eq!(result.output["ok"], true);
+    }
+
+    /// テスト: 数MBのコンテンツを1回のread_content_chunkで読み取り、内容が一致する
+    /// 仕様書 §7.1
+    #[test]
+    fn test_single_large_chunk_read() {
+        // 全長を1回で読み取り、バイト列のハッシュ（h = h * 31 + b）を補助入力の期待値と比較するWASM
+        let wasm = wat::parse_str(
+            r#"(module
+            (import "env" "read_content_chunk" (func $read (param i32 i32 i32) (result i32)))
+            (import "env" "get_content_length" (func $len (result i32)))
+            (import "env" "get_extension_input" (func $ext (param i32 i32) (result i32)))
+            ;; 5MB（80ページ）
+            (memory (export "memory") 80)
+            (data (i32.const 1024) "\0b\00\00\00{\"ok\":true}")
+            (data (i32.const 2048) "\0c\00\00\00{\"ok\":false}")
+            (func (export "alloc") (param i32) (result i32) (i32.const 4096))
+            (func (export "process") (result i32)
+                (local $len i32)
+                (local $n i32)
+                (local $i i32)
+                (local $h i32)
+                (local.set $len (call $len))
+                (local.set $n (call $read (i32.const 0) (local.get $len) (i32.const 65536)))
+                (drop (call $ext (i32.const 512) (i32.const 4)))
+                (block $done
+                    (loop $next
+                        (br_if $done (i32.ge_u (local.get $i) (local.get $n)))
+                        (local.set $h (i32.add
+                            (i32.mul (local.get $h) (i32.const 31))
+                            (i32.load8_u offset=65536 (local.get $i))))
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br $next)
+                    )
+                )
+                (if (result i32)
+                    (i32.and
+                        (i32.eq (local.get $n) (local.get $len))
+                        (i32.eq (local.get $h) (i32.load (i32.const 512))))
+                    (then (i32.const 1024))
+                    (else (i32.const 2048))
+                )
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let content: Vec<u8> = (0..4 * 1024 * 1024u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+            .collect();
+        let expected = content
+            .iter()
+            .fold(0u32, |h, &b| h.wrapping_mul(31).wrapping_add(b as u32));
+
+        let runner = WasmRunner::new(1_000_000_000, 64 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let result = runner
+            .execute(&wasm, &content, Some(&expected.to_le_bytes()), "process")
+            .expect("WASM実行に成功するべき");
+
+        assert_eq!(result.output["ok"], true);
+    }
+
+    /// テスト: コピー先がメモリ範囲外の場合は何も書き込まず0を返す
+    #[test]
+    fn test_copy_into_memory_bounds() {
+        let mut mem = [0u8; 16];
+        assert_eq!(copy_into_memory(&mut mem, 12, b"abcd"), 4);
+        assert_eq!(&mem[12..], b"abcd");
+        assert_eq!(copy_into_memory(&mut mem, 13, b"abcd"), 0);
+        assert_eq!(copy_into_memory(&mut mem, u32::MAX, b"abcd"), 0);
+        assert_eq!(copy_into_memory(&mut mem, 0, b""), 0);
+    }
+
     /// テスト: get_content_featureがSHA-256を正しく計算する
     /// 仕様書 §7.1
     #[test]
@@ -1041,7 +2302,7 @@ mod tests {
         )
         .unwrap();
 
-        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024);
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner
             .execute(&wasm, b"test data for hashing", None, "compute_phash")
             .expect("WASM実行に成功するべき");
@@ -1089,7 +2350,7 @@ mod tests {
         )
         .unwrap();
 
-        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024);
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner
             .execute(&wasm, b"test data for hmac", None, "compute_phash")
             .expect("WASM実行に成功するべき");
@@ -1097,10 +2358,104 @@ mod tests {
         assert_eq!(result.output["hmac_size"], 32);
     }
 
+    /// ダイジェスト照合用WAT: `call` で得た32バイトの出力を期待値と比較し `{"ok":bool}` を返す。
+    /// `call` はオフセット8192に出力を書き込み、出力バイト数を返す式とする。
+    fn digest_check_wat(call: &str, key: &[u8], expected: &[u8; 32]) -> Vec<u8> {
+        let escape = |bytes: &[u8]| bytes.iter().map(|b| format!("\\{b:02x}")).collect::<String>();
+        wat::parse_str(format!(
+            r#"(module
+            (import "env" "read_content_chunk" (func $read (param i32 i32 i32) (result i32)))
+            (import "env" "get_content_length" (func $len (result i32)))
+            (import "env" "get_content_feature" (func $gcf (param i32 i32 i32) (result i32)))
+            (import "env" "hmac_content" (func $hmac (param i32 i32 i32 i32 i32 i32) (result i32)))
+            (import "env" "get_extension_input" (func $ext (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 256) "{{\"op\":\"blake3\"}}")
+            (data (i32.const 384) "{key}")
+            (data (i32.const 512) "{expected}")
+            (data (i32.const 1024) "\0b\00\00\00{{\"ok\":true}}")
+            (data (i32.const 2048) "\0c\00\00\00{{\"ok\":false}}")
+            (func (export "alloc") (param i32) (result i32) (i32.const 4096))
+            (func (export "compute_phash") (result i32)
+                (if (result i32)
+                    (i32.and
+                        (i32.eq ({call}) (i32.const 32))
+                        (i32.and
+                            (i32.and
+                                (i64.eq (i64.load (i32.const 8192)) (i64.load (i32.const 512)))
+                                (i64.eq (i64.load (i32.const 8200)) (i64.load (i32.const 520))))
+                            (i32.and
+                                (i64.eq (i64.load (i32.const 8208)) (i64.load (i32.const 528)))
+                                (i64.eq (i64.load (i32.const 8216)) (i64.load (i32.const 536))))))
+                    (then (i32.const 1024))
+                    (else (i32.const 2048))
+                )
+            )
+        )"#,
+            key = escape(key),
+            expected = escape(expected),
+        ))
+        .unwrap()
+    }
+
+    /// テスト: get_content_featureがBLAKE3を正しく計算する（phash-v1と同じエクスポート・呼び出し形式）
+    /// 仕様書 §7.1
+    #[test]
+    fn test_get_content_feature_blake3() {
+        let content = b"test data for blake3";
+        let expected = blake3::hash(content);
+        // get_content_feature(spec_ptr=256, spec_len=15, output_ptr=8192)
+        let wasm = digest_check_wat(
+            "call $gcf (i32.const 256) (i32.const 15) (i32.const 8192)",
+            b"",
+            expected.as_bytes(),
+        );
+
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let result = runner
+            .execute(&wasm, content, None, "compute_phash")
+            .expect("WASM実行に成功するべき");
+        assert_eq!(result.output["ok"], true);
+    }
+
+    /// テスト: hmac_contentのalgorithm=3がkeyed BLAKE3を計算し、未サポートの指定は0を返す
+    /// 仕様書 §7.1
+    #[test]
+    fn test_hmac_content_keyed_blake3() {
+        let content = b"test data for keyed blake3";
+        let key = [0x5au8; 32];
+        let expected = blake3::keyed_hash(&key, content);
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let run = |call: &str| {
+            let wasm = digest_check_wat(call, &key, expected.as_bytes());
+            runner
+                .execute(&wasm, content, None, "compute_phash")
+                .expect("WASM実行に成功するべき")
+                .output["ok"]
+                .clone()
+        };
+
+        // hmac_content(algorithm=3, key_ptr=384, key_len=32, offset=0, length=全体, out_ptr=8192)
+        assert_eq!(
+            run("call $hmac (i32.const 3) (i32.const 384) (i32.const 32) (i32.const 0) (i32.const 65535) (i32.const 8192)"),
+            true
+        );
+        // keyed BLAKE3の鍵は32バイト固定
+        assert_eq!(
+            run("call $hmac (i32.const 3) (i32.const 384) (i32.const 16) (i32.const 0) (i32.const 65535) (i32.const 8192)"),
+            false
+        );
+        // 未サポートのalgorithmは0を返す
+        assert_eq!(
+            run("call $hmac (i32.const 4) (i32.const 384) (i32.const 32) (i32.const 0) (i32.const 65535) (i32.const 8192)"),
+            false
+        );
+    }
+
     /// テスト: 不正WASMバイナリでCompileError
     #[test]
     fn test_invalid_wasm_binary() {
-        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024);
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner.execute(b"not wasm", b"content", None, "process");
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), WasmError::CompileError(_)));
@@ -1121,12 +2476,54 @@ mod tests {
         )
         .unwrap();
 
-        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024);
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner.execute(&wasm, b"content", None, "nonexistent_func");
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), WasmError::ExecutionError(_)));
     }
 
+    /// `read_content_chunk` を1バイトずつ `calls` 回呼んでから `{}` を返すWAT
+    fn host_call_loop_wat(calls: u32) -> Vec<u8> {
+        wat::parse_str(format!(
+            r#"(module
+            (import "env" "read_content_chunk" (func $read (param i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 1024) "\02\00\00\00{{}}")
+            (func (export "process") (result i32)
+                (local $i i32)
+                (block $done
+                    (loop $next
+                        (br_if $done (i32.ge_u (local.get $i) (i32.const {calls})))
+                        (drop (call $read (i32.const 0) (i32.const 1) (i32.const 0)))
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br $next)))
+                (i32.const 1024)
+            )
+        )"#
+        ))
+        .unwrap()
+    }
+
+    /// テスト: ホスト関数呼び出し回数が上限を超えるとHostFunctionErrorになる（Fuelとは別軸）
+    #[test]
+    fn test_host_call_limit_exceeded() {
+        let runner = WasmRunner::new(u64::MAX, 16 * 1024 * 1024, 100);
+
+        // 上限ちょうどまでは成功する
+        runner
+            .execute(&host_call_loop_wat(100), b"content", None, "process")
+            .unwrap();
+
+        // 上限+1回目の呼び出しでトラップする（Fuelは十分にある）
+        let err = runner
+            .execute(&host_call_loop_wat(101), b"content", None, "process")
+            .unwrap_err();
+        match err {
+            WasmError::HostFunctionError(msg) => assert_eq!(msg, "host call limit exceeded"),
+            other => panic!("HostFunctionErrorを期待: {other:?}"),
+        }
+    }
+
     /// テスト: WASM関数がptr=0を返した場合のエラー
     #[test]
     fn test_result_ptr_zero() {
@@ -1145,12 +2542,391 @@ mod tests {
         )
         .unwrap();
 
-        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024);
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner.execute(&wasm, b"content", None, "process");
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), WasmError::ExecutionError(_)));
     }
 
+    /// 引数付きエクスポートのテスト用WAT: `process(a, b)` が `a == b` をJSONで返す
+    fn args_wat() -> Vec<u8> {
+        wat::parse_str(
+            r#"(module
+            (memory (export "memory") 1)
+            (data (i32.const 1024) "\0b\00\00\00{\"eq\":true}")
+            (data (i32.const 2048) "\0c\00\00\00{\"eq\":false}")
+            (func (export "process") (param i32 i32) (result i32)
+                (if (result i32) (i32.eq (local.get 0) (local.get 1))
+                    (then (i32.const 1024))
+                    (else (i32.const 2048)))
+            )
+        )"#,
+        )
+        .unwrap()
+    }
+
+    /// テスト: execute_with_argsで渡した引数がエクスポート関数に届く
+    #[test]
+    fn test_execute_with_args() {
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let run = |args: &[i32]| {
+            runner
+                .execute_with_args(&args_wat(), b"content", None, "process", args)
+                .unwrap()
+                .output
+        };
+        assert_eq!(run(&[7, 7]), serde_json::json!({"eq": true}));
+        assert_eq!(run(&[7, -1]), serde_json::json!({"eq": false}));
+    }
+
+    /// テスト: 引数の数がシグネチャと一致しない・上限を超える場合はExecutionError
+    #[test]
+    fn test_execute_with_args_mismatch() {
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        for args in [&[][..], &[1][..], &[1, 2, 3][..], &[1, 2, 3, 4, 5][..]] {
+            let err = runner
+                .execute_with_args(&args_wat(), b"content", None, "process", args)
+                .unwrap_err();
+            assert!(matches!(err, WasmError::ExecutionError(_)), "{args:?}: {err:?}");
+        }
+        // 引数なしのexecuteは引数を取るエクスポートを呼べない
+        let err = runner.execute(&args_wat(), b"content", None, "process").unwrap_err();
+        assert!(matches!(err, WasmError::ExecutionError(_)));
+    }
+
+    /// ABI v2テスト用WAT: `process` が指定した値を返す
+    fn abi_v2_wat(ret: i32) -> Vec<u8> {
+        wat::parse_str(format!(
+            r#"(module
+            (memory (export "memory") 1)
+            (data (i32.const 1024) "\0b\00\00\00{{\"ok\":true}}")
+            (func (export "title_abi_version") (result i32)
+                (i32.const 2)
+            )
+            (func (export "process") (result i32)
+                (i32.const {ret})
+            )
+        )"#
+        ))
+        .unwrap()
+    }
+
+    /// テスト: ABI v2のエラーコードがそれぞれのWasmErrorに変換される
+    #[test]
+    fn test_abi_v2_error_codes() {
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+
+        let err = runner
+            .execute(&abi_v2_wat(WASM_ERR_OUT_OF_MEMORY), b"content", None, "process")
+            .unwrap_err();
+        assert!(matches!(err, WasmError::OutOfMemory), "got {err:?}");
+
+        let err = runner
+            .execute(&abi_v2_wat(WASM_ERR_UNSUPPORTED_FORMAT), b"content", None, "process")
+            .unwrap_err();
+        assert!(matches!(err, WasmError::UnsupportedFormat), "got {err:?}");
+
+        let err = runner
+            .execute(&abi_v2_wat(WASM_ERR_INVALID_INPUT), b"content", None, "process")
+            .unwrap_err();
+        assert!(matches!(err, WasmError::InvalidInput), "got {err:?}");
+
+        let err = runner
+            .execute(&abi_v2_wat(-42), b"content", None, "process")
+            .unwrap_err();
+        assert!(matches!(err, WasmError::UnknownErrorCode(-42)), "got {err:?}");
+    }
+
+    /// テスト: ABI v2で正の戻り値は結果ポインタとして扱われる
+    #[test]
+    fn test_abi_v2_success_pointer() {
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let result = runner
+            .execute(&abi_v2_wat(1024), b"content", None, "process")
+            .unwrap();
+        assert_eq!(result.output, serde_json::json!({"ok": true}));
+        assert_eq!(result.declared_extension_id, None);
+    }
+
+    /// テスト: `title_extension_id` で自己申告されたExtension IDが返される
+    #[test]
+    fn test_declared_extension_id() {
+        let wasm = wat::parse_str(
+            r#"(module
+            (memory (export "memory") 1)
+            (data (i32.const 512) "\08\00\00\00phash-v1")
+            (data (i32.const 1024) "\0b\00\00\00{\"ok\":true}")
+            (func (export "title_extension_id") (result i32)
+                (i32.const 512)
+            )
+            (func (export "process") (result i32)
+                (i32.const 1024)
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let result = runner.execute(&wasm, b"content", None, "process").unwrap();
+        assert_eq!(result.declared_extension_id.as_deref(), Some("phash-v1"));
+    }
+
+    /// テスト: 要求と異なるExtension IDを申告するモジュールは計算関数を呼ばずに拒否される
+    #[test]
+    fn test_expected_extension_id_checked_before_process() {
+        // processはトラップするため、呼ばれればExecutionErrorになる
+        let wasm = wat::parse_str(
+            r#"(module
+            (memory (export "memory") 1)
+            (data (i32.const 512) "\0f\00\00\00hardware-google")
+            (func (export "title_extension_id") (result i32)
+                (i32.const 512)
+            )
+            (func (export "process") (result i32)
+                unreachable
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+            .with_expected_extension_id("phash-v1");
+        match runner.execute(&wasm, b"content", None, "process") {
+            Err(WasmError::ExtensionIdMismatch { expected, declared }) => {
+                assert_eq!(expected, "phash-v1");
+                assert_eq!(declared, "hardware-google");
+            }
+            other => panic!("ExtensionIdMismatchが期待される: {other:?}"),
+        }
+
+        // 一致する場合は計算関数まで実行される
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+            .with_expected_extension_id("hardware-google");
+        assert!(matches!(
+            runner.execute(&wasm, b"content", None, "process"),
+            Err(WasmError::ExecutionError(_))
+        ));
+    }
+
+    /// テスト: ABI v1では負の戻り値はエラーコードとして解釈されない
+    #[test]
+    fn test_abi_v1_negative_return_is_not_error_code() {
+        let wasm = wat::parse_str(
+            r#"(module
+            (memory (export "memory") 1)
+            (func (export "process") (result i32)
+                (i32.const -2)
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let err = runner.execute(&wasm, b"content", None, "process").unwrap_err();
+        assert!(matches!(err, WasmError::ExecutionError(_)), "got {err:?}");
+    }
+
+    /// {"v":N} を結果として返すWAT。`abi_version` がSomeなら `title_abi_version` をエクスポートする。
+    /// `process` は `err_code` が指定されていればそれを返す。
+    fn versioned_module_wat(abi_version: Option<i32>, err_code: Option<i32>) -> Vec<u8> {
+        let n = abi_version.unwrap_or(WASM_ABI_V1);
+        let version_export = abi_version
+            .map(|v| format!(r#"(func (export "title_abi_version") (result i32) (i32.const {v}))"#))
+            .unwrap_or_default();
+        let ret = err_code.unwrap_or(1024);
+        wat::parse_str(format!(
+            r#"(module
+            (memory (export "memory") 1)
+            (data (i32.const 1024) "\07\00\00\00{{\"v\":{n}}}")
+            {version_export}
+            (func (export "process") (result i32)
+                (i32.const {ret})
+            )
+        )"#
+        ))
+        .unwrap()
+    }
+
+    /// テスト: 対応する全ABIバージョンのモジュールを同一のRunnerで交互に実行できる
+    #[test]
+    fn test_supported_abi_versions_share_runner() {
+        let cache = Arc::new(ModuleCache::new(DEFAULT_MODULE_CACHE_CAPACITY).unwrap());
+        let runner =
+            WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+                .with_module_cache(Arc::clone(&cache));
+        let modules: Vec<(i32, Vec<u8>)> = SUPPORTED_ABI_VERSIONS
+            .iter()
+            .map(|&v| {
+                // v1はtitle_abi_versionをエクスポートしない
+                let declared = (v != WASM_ABI_V1).then_some(v);
+                (v, versioned_module_wat(declared, None))
+            })
+            .collect();
+
+        for _ in 0..2 {
+            for (version, wasm) in &modules {
+                let result = runner.execute(wasm, b"content", None, "process").unwrap();
+                assert_eq!(result.abi_version, *version);
+                assert_eq!(result.output["v"], *version);
+            }
+        }
+        assert_eq!(cache.stats().entries, SUPPORTED_ABI_VERSIONS.len());
+    }
+
+    /// テスト: 同一の戻り値でもモジュールのABIバージョンごとに解釈が切り替わる
+    #[test]
+    fn test_abi_versions_interpret_return_independently() {
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let v1 = versioned_module_wat(None, Some(WASM_ERR_UNSUPPORTED_FORMAT));
+        let v2 = versioned_module_wat(Some(WASM_ABI_V2), Some(WASM_ERR_UNSUPPORTED_FORMAT));
+
+        let v2_err = runner.execute(&v2, b"content", None, "process").unwrap_err();
+        assert!(matches!(v2_err, WasmError::UnsupportedFormat), "got {v2_err:?}");
+        let v1_err = runner.execute(&v1, b"content", None, "process").unwrap_err();
+        assert!(matches!(v1_err, WasmError::ExecutionError(_)), "got {v1_err:?}");
+    }
+
+    /// テスト: 未対応のABIバージョンは拒否される
+    #[test]
+    fn test_unsupported_abi_version() {
+        let wasm = wat::parse_str(
+            r#"(module
+            (memory (export "memory") 1)
+            (func (export "title_abi_version") (result i32)
+                (i32.const 99)
+            )
+            (func (export "process") (result i32)
+                (i32.const 1024)
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let err = runner.execute(&wasm, b"content", None, "process").unwrap_err();
+        assert!(matches!(err, WasmError::ExecutionError(_)), "got {err:?}");
+    }
+
+    /// テスト: ModuleCache設定時、同一モジュールの2回目の実行はキャッシュヒットになる
+    #[test]
+    fn test_module_cache_hit_on_second_execution() {
+        let cache = Arc::new(ModuleCache::new(DEFAULT_MODULE_CACHE_CAPACITY).unwrap());
+        let runner =
+            WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+                .with_module_cache(Arc::clone(&cache));
+        let wasm = abi_v2_wat(-3);
+
+        let first = runner.execute(&wasm, b"content", None, "process").unwrap_err();
+        assert!(matches!(first, WasmError::InvalidInput));
+        assert_eq!(cache.stats().hits, 0);
+        assert_eq!(cache.stats().misses, 1);
+
+        let second = runner.execute(&wasm, b"content", None, "process").unwrap_err();
+        assert!(matches!(second, WasmError::InvalidInput));
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.entries, 1);
+    }
+
+    /// テスト: with_cacheで作成したランナーは同一バイナリの2回実行でコンパイルを1回に抑え、
+    /// Fuel制限・Memory制限はキャッシュヒット時も実行ごとに適用される
+    #[test]
+    fn test_with_cache_compiles_once_and_applies_limits_per_execution() {
+        let runner = WasmRunner::with_cache(10_000_000, 16 * 1024 * 1024, 4).unwrap();
+        let wasm = abi_v2_wat(-3);
+        for _ in 0..2 {
+            let err = runner.execute(&wasm, b"content", None, "process").unwrap_err();
+            assert!(matches!(err, WasmError::InvalidInput));
+        }
+        let stats = runner.module_cache().unwrap().stats();
+        assert_eq!((stats.misses, stats.hits), (1, 1));
+
+        // 無限ループはキャッシュヒット時もFuel制限で停止する
+        let looping = wat::parse_str(
+            r#"(module
+            (memory (export "memory") 1)
+            (func (export "process") (result i32)
+                (loop $l (br $l))
+                (i32.const 0)
+            )
+        )"#,
+        )
+        .unwrap();
+        let cache = Arc::clone(runner.module_cache().unwrap());
+        let fuel_runner =
+            WasmRunner::new(10_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+                .with_module_cache(Arc::clone(&cache));
+        for _ in 0..2 {
+            let err = fuel_runner.execute(&looping, b"content", None, "process").unwrap_err();
+            assert!(matches!(err, WasmError::FuelExhausted), "got {err:?}");
+        }
+
+        // 同じキャッシュを共有していても、Memory制限はランナーごとに適用される
+        let growing = wat::parse_str(
+            r#"(module
+            (memory (export "memory") 1)
+            (data (i32.const 1024) "\0b\00\00\00{\"ok\":true}")
+            (func (export "process") (result i32)
+                (if (i32.lt_s (memory.grow (i32.const 16)) (i32.const 0))
+                    (then unreachable))
+                (i32.const 1024)
+            )
+        )"#,
+        )
+        .unwrap();
+        let roomy =
+            WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+                .with_module_cache(Arc::clone(&cache));
+        assert_eq!(roomy.execute(&growing, b"content", None, "process").unwrap().output["ok"], true);
+        let tight =
+            WasmRunner::new(10_000_000, 128 * 1024, DEFAULT_MAX_HOST_CALLS)
+                .with_module_cache(Arc::clone(&cache));
+        assert!(tight.execute(&growing, b"content", None, "process").is_err());
+        assert_eq!(roomy.execute(&growing, b"content", None, "process").unwrap().output["ok"], true);
+        assert_eq!(cache.stats().misses, 3);
+    }
+
+    /// テスト: プール実行と新規Store実行が同一入力に対して同一結果を返し、
+    /// プール実行でも呼び出し間でグローバル変数・メモリがリセットされる
+    #[test]
+    fn test_instance_pool_matches_fresh_store() {
+        // 呼び出し回数（グローバル変数）とコンテンツ長を {"n":C,"l":L} として返すWASM。
+        // グローバル変数がリセットされていれば C は常に 1 になる
+        let wasm = wat::parse_str(
+            r#"(module
+            (import "env" "get_content_length" (func $len (result i32)))
+            (memory (export "memory") 1)
+            (global $calls (mut i32) (i32.const 0))
+            (data (i32.const 1024) "\0d\00\00\00{\"n\":0,\"l\":0}")
+            (func (export "process") (result i32)
+                (global.set $calls (i32.add (global.get $calls) (i32.const 1)))
+                (i32.store8 (i32.const 1033)
+                    (i32.add (i32.load8_u (i32.const 1033)) (global.get $calls)))
+                (i32.store8 (i32.const 1039)
+                    (i32.add (i32.const 0x30) (i32.rem_u (call $len) (i32.const 10))))
+                (i32.const 1024)
+            )
+        )"#,
+        )
+        .unwrap();
+
+        let fresh = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+        let pool = Arc::new(InstancePool::new(4, 16 * 1024 * 1024).unwrap());
+        let pooled =
+            WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS)
+                .with_instance_pool(Arc::clone(&pool));
+
+        for content in [&b"abc"[..], &b"content"[..], &b"abc"[..]] {
+            let expected = fresh.execute(&wasm, content, None, "process").unwrap();
+            let actual = pooled.execute(&wasm, content, None, "process").unwrap();
+            assert_eq!(actual.output, expected.output);
+            assert_eq!(actual.output["n"], 1);
+            assert_eq!(actual.output["l"], content.len() % 10);
+        }
+        assert_eq!(pool.prepared_count(), 1);
+    }
+
     /// テスト: 結果バッファのjson_len=0でエラー
     #[test]
     fn test_result_buffer_zero_length() {
@@ -1171,7 +2947,7 @@ mod tests {
         )
         .unwrap();
 
-        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024);
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner.execute(&wasm, b"content", None, "process");
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), WasmError::ExecutionError(_)));
@@ -1225,7 +3001,7 @@ mod tests {
         let wasm = decode_test_wat();
         let content = include_bytes!("../../../tests/fixtures/test_2x2.png");
 
-        let runner = WasmRunner::new(100_000_000, 64 * 1024 * 1024);
+        let runner = WasmRunner::new(100_000_000, 64 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner
             .execute(&wasm, content, None, "process")
             .expect("WASM実行に成功するべき");
@@ -1241,7 +3017,7 @@ mod tests {
         let wasm = decode_test_wat();
         let content = b"this is not an image file at all";
 
-        let runner = WasmRunner::new(100_000_000, 64 * 1024 * 1024);
+        let runner = WasmRunner::new(100_000_000, 64 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner
             .execute(&wasm, content, None, "process")
             .expect("WASM実行に成功するべき");
@@ -1306,7 +3082,7 @@ mod tests {
         )
         .unwrap();
 
-        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024);
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner
             .execute(&wasm, b"some content", None, "process")
             .expect("WASM実行に成功するべき");
@@ -1350,7 +3126,7 @@ mod tests {
 
         // decode_content がC2PA付きJPEGをデコードできることを検証
         let wasm = decode_test_wat();
-        let runner = WasmRunner::new(100_000_000, 64 * 1024 * 1024);
+        let runner = WasmRunner::new(100_000_000, 64 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner
             .execute(&wasm, &c2pa_content, None, "process")
             .expect("C2PA署名済みJPEGのデコードに成功するべき");
@@ -1411,7 +3187,7 @@ mod tests {
         .unwrap();
 
         let content = include_bytes!("../../../tests/fixtures/test_2x2.png");
-        let runner = WasmRunner::new(100_000_000, 64 * 1024 * 1024);
+        let runner = WasmRunner::new(100_000_000, 64 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner
             .execute(&wasm, content, None, "process")
             .expect("WASM実行に成功するべき");
@@ -1456,7 +3232,7 @@ mod tests {
         )
         .unwrap();
 
-        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024);
+        let runner = WasmRunner::new(10_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
         let result = runner
             .execute(&wasm, b"some content", None, "process")
             .expect("WASM実行に成功するべき");
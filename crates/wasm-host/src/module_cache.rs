@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # ModuleCache（コンパイル済みWASMモジュールのキャッシュ）
+//!
+//! 仕様書 §7.1
+//!
+//! Extension実行のたびにWASMバイナリをコンパイルするコストを避けるため、
+//! WASMバイナリのSHA-256をキーにコンパイル済み `Module` を保持する。
+//!
+//! ## 設計
+//!
+//! wasmtimeの `Module` は生成元の `Engine` でのみインスタンス化できるため、
+//! キャッシュは共有の `Engine` を所有する。容量（モジュール数）またはメモリ上限
+//! （コンパイル済みアーティファクトの合計バイト数）を超えた場合は、最も長く使われていない
+//! モジュールから破棄する（LRU）。ヒット数・ミス数はモニタリング用に公開する。
+//!
+//! ミス時は `Engine::precompile_module` でネイティブコードのアーティファクトを生成し、
+//! そのサイズをメモリ使用量として計上してから `Module::deserialize` で読み込む。
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use sha2::{Digest, Sha256};
+use wasmtime::{Engine, Module};
+
+use crate::WasmError;
+
+/// キャッシュするモジュール数のデフォルト上限。
+pub const DEFAULT_MODULE_CACHE_CAPACITY: usize = 16;
+
+/// キャッシュの統計情報。
+/// 仕様書 §7.1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModuleCacheStats {
+    /// キャッシュヒット数
+    pub hits: u64,
+    /// キャッシュミス数（コンパイル実行数）
+    pub misses: u64,
+    /// 現在キャッシュされているモジュール数
+    pub entries: usize,
+    /// キャッシュ容量（モジュール数）
+    pub capacity: usize,
+    /// キャッシュされているモジュールのアーティファクトの合計バイト数
+    pub bytes: usize,
+    /// メモリ上限（バイト）。Noneの場合はモジュール数のみで制限する
+    pub max_bytes: Option<usize>,
+}
+
+/// キャッシュエントリ。
+struct CacheEntry {
+    /// WASMバイナリのSHA-256
+    key: [u8; 32],
+    /// コンパイル済みModule
+    module: Module,
+    /// コンパイル済みアーティファクトのバイト数
+    size: usize,
+}
+
+/// コンパイル済みWASMモジュールのLRUキャッシュ。
+/// 仕様書 §7.1
+pub struct ModuleCache {
+    /// モジュールのコンパイル・実行に使用する共有Engine（Fuel制限有効）
+    engine: Engine,
+    /// 共有Engineに設定したWASM実行スタックの上限（バイト）
+    max_wasm_stack: usize,
+    /// キャッシュ容量（モジュール数）
+    capacity: usize,
+    /// メモリ上限（アーティファクトの合計バイト数）
+    max_bytes: Option<usize>,
+    /// キャッシュエントリ。末尾ほど最近使用された
+    entries: Mutex<VecDeque<CacheEntry>>,
+    /// キャッシュヒット数
+    hits: AtomicU64,
+    /// キャッシュミス数
+    misses: AtomicU64,
+}
+
+impl ModuleCache {
+    /// 指定容量のModuleCacheを作成する。
+    /// 仕様書 §7.1
+    ///
+    /// WASM実行スタックの上限は [`crate::DEFAULT_MAX_WASM_STACK`]。
+    pub fn new(capacity: usize) -> Result<Self, WasmError> {
+        Self::with_max_wasm_stack(capacity, crate::DEFAULT_MAX_WASM_STACK)
+    }
+
+    /// 共有EngineのWASM実行スタック上限（バイト）を指定してModuleCacheを作成する。
+    /// 仕様書 §7.1
+    ///
+    /// このキャッシュを使う [`crate::WasmRunner`] には同じ値を
+    /// [`crate::WasmRunner::with_max_wasm_stack`] で設定すること（異なる場合は実行時エラー）。
+    pub fn with_max_wasm_stack(capacity: usize, max_wasm_stack: usize) -> Result<Self, WasmError> {
+        Ok(Self {
+            engine: crate::WasmRunner::create_engine(max_wasm_stack)?,
+            max_wasm_stack,
+            capacity,
+            max_bytes: None,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    /// キャッシュのメモリ上限（コンパイル済みアーティファクトの合計バイト数）を設定する。
+    /// 仕様書 §7.1
+    ///
+    /// 単体で上限を超えるモジュールはキャッシュせず、毎回コンパイルする。
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// キャッシュが所有するEngineを返す。
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// 共有Engineに設定したWASM実行スタックの上限（バイト）を返す。
+    pub fn max_wasm_stack(&self) -> usize {
+        self.max_wasm_stack
+    }
+
+    /// コンパイル済みModuleを取得する。未キャッシュならコンパイルして格納する。
+    /// 仕様書 §7.1
+    pub fn get_or_compile(&self, wasm_bytes: &[u8]) -> Result<Module, WasmError> {
+        let key: [u8; 32] = Sha256::digest(wasm_bytes).into();
+
+        {
+            let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(pos) = entries.iter().position(|e| e.key == key) {
+                let entry = entries.remove(pos).expect("position は範囲内");
+                let module = entry.module.clone();
+                entries.push_back(entry);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(module);
+            }
+        }
+
+        // コンパイルはロック外で行う（同一モジュールの同時ミスは許容する）
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let artifact = self
+            .engine
+            .precompile_module(wasm_bytes)
+            .map_err(|e| WasmError::CompileError(e.to_string()))?;
+        // SAFETY: アーティファクトは直前に同一のEngineで生成したもので、改変されていない
+        let module = unsafe { Module::deserialize(&self.engine, &artifact) }
+            .map_err(|e| WasmError::CompileError(e.to_string()))?;
+        let size = artifact.len();
+
+        let fits = self.capacity > 0 && self.max_bytes.is_none_or(|max| size <= max);
+        if fits {
+            let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            if !entries.iter().any(|e| e.key == key) {
+                let mut bytes: usize = entries.iter().map(|e| e.size).sum();
+                while entries.len() >= self.capacity
+                    || self.max_bytes.is_some_and(|max| bytes + size > max)
+                {
+                    let Some(evicted) = entries.pop_front() else { break };
+                    bytes -= evicted.size;
+                }
+                entries.push_back(CacheEntry {
+                    key,
+                    module: module.clone(),
+                    size,
+                });
+            }
+        }
+        Ok(module)
+    }
+
+    /// 現在の統計情報を返す（モニタリング用）。
+    pub fn stats(&self) -> ModuleCacheStats {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        ModuleCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: entries.len(),
+            capacity: self.capacity,
+            bytes: entries.iter().map(|e| e.size).sum(),
+            max_bytes: self.max_bytes,
+        }
+    }
+}
+
+impl std::fmt::Debug for ModuleCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModuleCache")
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wat_module(value: i32) -> Vec<u8> {
+        wat::parse_str(format!(
+            r#"(module (func (export "f") (result i32) i32.const {value}))"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_get_or_compile_counts_hits_and_misses() {
+        let cache = ModuleCache::new(4).unwrap();
+        let wasm = wat_module(1);
+
+        cache.get_or_compile(&wasm).unwrap();
+        cache.get_or_compile(&wasm).unwrap();
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = ModuleCache::new(2).unwrap();
+        let (a, b, c) = (wat_module(1), wat_module(2), wat_module(3));
+
+        cache.get_or_compile(&a).unwrap();
+        cache.get_or_compile(&b).unwrap();
+        cache.get_or_compile(&a).unwrap(); // a を最近使用に
+        cache.get_or_compile(&c).unwrap(); // b が破棄される
+        assert_eq!(cache.stats().entries, 2);
+
+        cache.get_or_compile(&a).unwrap();
+        assert_eq!(cache.stats().hits, 2);
+        cache.get_or_compile(&b).unwrap();
+        assert_eq!(cache.stats().misses, 4);
+    }
+
+    #[test]
+    fn test_evicts_to_stay_within_max_bytes() {
+        let (a, b) = (wat_module(1), wat_module(2));
+        let module_size = {
+            let probe = ModuleCache::new(4).unwrap();
+            probe.get_or_compile(&a).unwrap();
+            probe.stats().bytes
+        };
+        assert!(module_size > 0);
+
+        // 1モジュール分のメモリ上限では、新しいモジュールの格納時に古いものが破棄される
+        let cache = ModuleCache::new(4).unwrap().with_max_bytes(module_size);
+        cache.get_or_compile(&a).unwrap();
+        cache.get_or_compile(&b).unwrap();
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert!(stats.bytes <= module_size);
+        cache.get_or_compile(&a).unwrap();
+        assert_eq!(cache.stats().misses, 3);
+
+        // 上限より大きいモジュールはキャッシュしない
+        let tiny = ModuleCache::new(4).unwrap().with_max_bytes(module_size - 1);
+        tiny.get_or_compile(&a).unwrap();
+        tiny.get_or_compile(&a).unwrap();
+        let stats = tiny.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries, stats.bytes), (0, 2, 0, 0));
+    }
+
+    #[test]
+    fn test_compile_error_is_not_cached() {
+        let cache = ModuleCache::new(4).unwrap();
+        assert!(matches!(
+            cache.get_or_compile(b"not wasm"),
+            Err(WasmError::CompileError(_))
+        ));
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # assertion-list-v1 統合テスト
+//!
+//! コンパイル済み assertion-list-v1.wasm を WasmRunner で実行し、
+//! C2PAアクティブマニフェストのアサーションラベルが列挙されることを検証する。
+//!
+//! ## 前提条件
+//! ```bash
+//! cd wasm/assertion-list-v1 && cargo build --target wasm32-unknown-unknown --release
+//! ```
+//!
+//! WASM バイナリが存在しない場合、テストはスキップされる。
+
+use std::io::Cursor;
+
+use title_wasm_host::{WasmRunner, DEFAULT_MAX_HOST_CALLS};
+
+/// assertion-list-v1.wasm のパス（CARGO_MANIFEST_DIR からの相対）
+const WASM_RELATIVE: &str =
+    "../../wasm/assertion-list-v1/target/wasm32-unknown-unknown/release/assertion_list_v1.wasm";
+
+const CERTS: &[u8] = include_bytes!("../../../tests/fixtures/certs/chain.pem");
+const PRIVATE_KEY: &[u8] = include_bytes!("../../../tests/fixtures/certs/ee.key");
+const TEST_IMAGE: &[u8] = include_bytes!("../../../tests/fixtures/test_4x4.jpg");
+
+/// assertion-list-v1.wasm をロードする。ビルドされていなければ None。
+fn load_assertion_list_wasm() -> Option<Vec<u8>> {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let path = format!("{manifest_dir}/{WASM_RELATIVE}");
+    std::fs::read(path).ok()
+}
+
+/// actions・CreativeWorkアサーション付きのC2PA署名済みJPEGを生成する。
+fn signed_jpeg() -> Vec<u8> {
+    let manifest_json = serde_json::json!({
+        "title": "assertion-list.jpg",
+        "format": "image/jpeg",
+        "claim_generator_info": [{"name": "wasm-host-test", "version": "0.1"}],
+        "assertions": [
+            {
+                "label": "c2pa.actions",
+                "data": {"actions": [{"action": "c2pa.created"}]}
+            },
+            {
+                "label": "stds.schema-org.CreativeWork",
+                "data": {"@context": "https://schema.org", "@type": "CreativeWork"}
+            }
+        ]
+    })
+    .to_string();
+    let mut builder = c2pa::Builder::from_json(&manifest_json).unwrap();
+    let signer =
+        c2pa::create_signer::from_keys(CERTS, PRIVATE_KEY, c2pa::SigningAlg::Ed25519, None)
+            .unwrap();
+
+    let mut dest = Cursor::new(Vec::new());
+    builder
+        .sign(signer.as_ref(), "image/jpeg", &mut Cursor::new(TEST_IMAGE), &mut dest)
+        .unwrap();
+    dest.into_inner()
+}
+
+/// 既知のアサーションラベルが出力に含まれること。
+#[test]
+fn test_assertion_list_known_labels() {
+    let wasm = match load_assertion_list_wasm() {
+        Some(w) => w,
+        None => {
+            eprintln!("SKIP: assertion-list-v1.wasm が見つかりません（先にビルドしてください）");
+            return;
+        }
+    };
+
+    let runner = WasmRunner::new(100_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+    let result = runner
+        .execute(&wasm, &signed_jpeg(), None, "process")
+        .expect("assertion-list-v1 WASM実行に失敗");
+    assert_eq!(result.declared_extension_id.as_deref(), Some("assertion-list-v1"));
+
+    let labels = result.output["assertions"].as_array().expect("assertionsが配列であるべき");
+    assert!(labels.contains(&serde_json::json!("c2pa.actions")), "{labels:?}");
+    assert!(labels.contains(&serde_json::json!("stds.schema-org.CreativeWork")), "{labels:?}");
+}
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # cawg-identity-v1 統合テスト
+//!
+//! コンパイル済み cawg-identity-v1.wasm を WasmRunner で実行し、
+//! CAWGアイデンティティアサーションの有無と発行者の抽出を検証する。
+//!
+//! ## 前提条件
+//! ```bash
+//! cd wasm/cawg-identity-v1 && cargo build --target wasm32-unknown-unknown --release
+//! ```
+//!
+//! WASM バイナリが存在しない場合、テストはスキップされる。
+
+use std::io::Cursor;
+
+use c2pa::crypto::raw_signature::signer_from_cert_chain_and_private_key;
+use c2pa::identity::builder::{IdentityAssertionBuilder, IdentityAssertionSigner};
+use c2pa::identity::x509::X509CredentialHolder;
+use title_wasm_host::{WasmRunner, DEFAULT_MAX_HOST_CALLS};
+
+/// cawg-identity-v1.wasm のパス（CARGO_MANIFEST_DIR からの相対）
+const WASM_RELATIVE: &str =
+    "../../wasm/cawg-identity-v1/target/wasm32-unknown-unknown/release/cawg_identity_v1.wasm";
+
+const CERTS: &[u8] = include_bytes!("../../../tests/fixtures/certs/chain.pem");
+const PRIVATE_KEY: &[u8] = include_bytes!("../../../tests/fixtures/certs/ee.key");
+const TEST_IMAGE: &[u8] = include_bytes!("../../../tests/fixtures/test_4x4.jpg");
+
+/// cawg-identity-v1.wasm をロードする。ビルドされていなければ None。
+fn load_cawg_identity_wasm() -> Option<Vec<u8>> {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let path = format!("{manifest_dir}/{WASM_RELATIVE}");
+    std::fs::read(path).ok()
+}
+
+/// C2PA署名済みJPEGを生成する。`with_identity` の場合はX.509のCAWGアサーションを付与する。
+fn signed_jpeg(with_identity: bool) -> Vec<u8> {
+    let manifest_json = serde_json::json!({
+        "title": "cawg-identity.jpg",
+        "format": "image/jpeg",
+        "claim_generator_info": [{"name": "wasm-host-test", "version": "0.1"}]
+    })
+    .to_string();
+    let mut builder = c2pa::Builder::from_json(&manifest_json).unwrap();
+
+    let raw_signer = || {
+        signer_from_cert_chain_and_private_key(CERTS, PRIVATE_KEY, c2pa::SigningAlg::Ed25519, None)
+            .unwrap()
+    };
+    let mut signer = IdentityAssertionSigner::new(raw_signer());
+    if with_identity {
+        let holder = X509CredentialHolder::from_raw_signer(raw_signer());
+        signer.add_identity_assertion(IdentityAssertionBuilder::for_credential_holder(holder));
+    }
+
+    let mut dest = Cursor::new(Vec::new());
+    builder
+        .sign(&signer, "image/jpeg", &mut Cursor::new(TEST_IMAGE), &mut dest)
+        .unwrap();
+    dest.into_inner()
+}
+
+/// コンテンツに対して cawg-identity-v1 を実行する。
+fn run_cawg_identity(wasm: &[u8], content: &[u8]) -> serde_json::Value {
+    let runner = WasmRunner::new(100_000_000, 16 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+    runner
+        .execute(wasm, content, None, "process")
+        .expect("cawg-identity-v1 WASM実行に失敗")
+        .output
+}
+
+/// CAWGアサーション付きコンテンツで identity_present と発行者が返ること。
+#[test]
+fn test_cawg_identity_present() {
+    let wasm = match load_cawg_identity_wasm() {
+        Some(w) => w,
+        None => {
+            eprintln!("SKIP: cawg-identity-v1.wasm が見つかりません（先にビルドしてください）");
+            return;
+        }
+    };
+
+    let output = run_cawg_identity(&wasm, &signed_jpeg(true));
+    assert_eq!(output["identity_present"], true);
+    assert_eq!(output["issuer"], "CN=Title Protocol Test CA");
+}
+
+/// CAWGアサーションのないコンテンツで identity_present が false となること。
+#[test]
+fn test_cawg_identity_absent() {
+    let wasm = match load_cawg_identity_wasm() {
+        Some(w) => w,
+        None => {
+            eprintln!("SKIP: cawg-identity-v1.wasm が見つかりません");
+            return;
+        }
+    };
+
+    let output = run_cawg_identity(&wasm, &signed_jpeg(false));
+    assert_eq!(output["identity_present"], false);
+    assert!(output["issuer"].is_null());
+}
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # image-quality-v1 統合テスト
+//!
+//! コンパイル済み image-quality-v1.wasm を WasmRunner で実行し、
+//! 同一画像を異なる品質で再エンコードした場合に指標が品質差を反映することを検証する。
+//!
+//! ## 前提条件
+//! ```bash
+//! cd wasm/image-quality-v1 && cargo build --target wasm32-unknown-unknown --release
+//! ```
+//!
+//! WASM バイナリが存在しない場合、テストはスキップされる。
+
+use std::io::Cursor;
+
+use title_wasm_host::{WasmRunner, DEFAULT_MAX_HOST_CALLS};
+
+/// image-quality-v1.wasm のパス（CARGO_MANIFEST_DIR からの相対）
+const WASM_RELATIVE: &str =
+    "../../wasm/image-quality-v1/target/wasm32-unknown-unknown/release/image_quality_v1.wasm";
+
+/// image-quality-v1.wasm をロードする。ビルドされていなければ None。
+fn load_image_quality_wasm() -> Option<Vec<u8>> {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let path = format!("{manifest_dir}/{WASM_RELATIVE}");
+    std::fs::read(path).ok()
+}
+
+/// 画像バイト列から品質指標のJSONを取得する。
+fn run_image_quality(wasm: &[u8], image_bytes: &[u8]) -> serde_json::Value {
+    let runner = WasmRunner::new(100_000_000, 64 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+    runner
+        .execute(wasm, image_bytes, None, "process")
+        .expect("image-quality-v1 WASM実行に失敗")
+        .output
+}
+
+/// 滑らかなグラデーション画像（低品質で再エンコードするとブロック境界が目立つ）。
+fn gradient_image() -> image::RgbImage {
+    image::RgbImage::from_fn(256, 256, |x, y| {
+        image::Rgb([x as u8, y as u8, ((x * y) >> 8) as u8])
+    })
+}
+
+/// 指定品質でJPEGエンコードする。
+fn encode_jpeg(img: &image::RgbImage, quality: u8) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality)
+        .encode_image(img)
+        .unwrap();
+    buf.into_inner()
+}
+
+/// 高品質と低品質の再エンコードで、推定品質とブロックノイズが品質差を反映すること。
+#[test]
+fn test_image_quality_high_vs_low_reencode() {
+    let wasm = match load_image_quality_wasm() {
+        Some(w) => w,
+        None => {
+            eprintln!("SKIP: image-quality-v1.wasm が見つかりません（先にビルドしてください）");
+            return;
+        }
+    };
+
+    let img = gradient_image();
+    let high = run_image_quality(&wasm, &encode_jpeg(&img, 95));
+    let low = run_image_quality(&wasm, &encode_jpeg(&img, 20));
+
+    let high_q = high["jpeg_quality"].as_u64().unwrap();
+    let low_q = low["jpeg_quality"].as_u64().unwrap();
+    assert!(high_q.abs_diff(95) <= 1, "推定品質 {high_q} が95から外れています");
+    assert!(low_q.abs_diff(20) <= 1, "推定品質 {low_q} が20から外れています");
+
+    let high_b = high["blockiness"].as_f64().unwrap();
+    let low_b = low["blockiness"].as_f64().unwrap();
+    assert!(
+        low_b > high_b,
+        "低品質の blockiness ({low_b}) が高品質 ({high_b}) 以下です"
+    );
+
+    // 同一入力に対して決定的
+    assert_eq!(high, run_image_quality(&wasm, &encode_jpeg(&img, 95)));
+}
+
+/// JPEG以外は jpeg_quality が null で、画素指標のみを返すこと。
+#[test]
+fn test_image_quality_png_has_no_jpeg_quality() {
+    let wasm = match load_image_quality_wasm() {
+        Some(w) => w,
+        None => {
+            eprintln!("SKIP: image-quality-v1.wasm が見つかりません");
+            return;
+        }
+    };
+
+    let mut buf = Cursor::new(Vec::new());
+    gradient_image()
+        .write_to(&mut buf, image::ImageFormat::Png)
+        .unwrap();
+    let output = run_image_quality(&wasm, &buf.into_inner());
+
+    assert!(output["jpeg_quality"].is_null());
+    assert!(output["blockiness"].is_f64());
+    assert!(output["noise"].is_f64());
+}
@@ -14,7 +14,7 @@
 
 use std::io::Cursor;
 
-use title_wasm_host::WasmRunner;
+use title_wasm_host::{WasmRunner, DEFAULT_MAX_HOST_CALLS};
 
 /// phash-v1.wasm のパス（CARGO_MANIFEST_DIR からの相対）
 const WASM_RELATIVE: &str =
@@ -29,7 +29,7 @@ fn load_phash_wasm() -> Option<Vec<u8>> {
 
 /// 画像バイト列から pHash を実行し、64bit ハッシュを返す。
 fn run_phash(wasm: &[u8], image_bytes: &[u8]) -> u64 {
-    let runner = WasmRunner::new(100_000_000, 64 * 1024 * 1024);
+    let runner = WasmRunner::new(100_000_000, 64 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
     let result = runner
         .execute(wasm, image_bytes, None, "process")
         .expect("phash-v1 WASM実行に失敗");
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # pixel-hash-v1 統合テスト
+//!
+//! コンパイル済み pixel-hash-v1.wasm を WasmRunner で実行し、
+//! 正規化ピクセルハッシュの決定性と再エンコード耐性を検証する。
+//!
+//! ## 前提条件
+//! ```bash
+//! cd wasm/pixel-hash-v1 && cargo build --target wasm32-unknown-unknown --release
+//! ```
+//!
+//! WASM バイナリが存在しない場合、テストはスキップされる。
+
+use std::io::Cursor;
+
+use title_wasm_host::{WasmRunner, DEFAULT_MAX_HOST_CALLS};
+
+/// pixel-hash-v1.wasm のパス（CARGO_MANIFEST_DIR からの相対）
+const WASM_RELATIVE: &str =
+    "../../wasm/pixel-hash-v1/target/wasm32-unknown-unknown/release/pixel_hash_v1.wasm";
+
+/// pixel-hash-v1.wasm をロードする。ビルドされていなければ None。
+fn load_pixel_hash_wasm() -> Option<Vec<u8>> {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let path = format!("{manifest_dir}/{WASM_RELATIVE}");
+    std::fs::read(path).ok()
+}
+
+/// 画像バイト列から pixel_hash を計算する。
+fn run_pixel_hash(wasm: &[u8], image_bytes: &[u8]) -> String {
+    let runner = WasmRunner::new(100_000_000, 64 * 1024 * 1024, DEFAULT_MAX_HOST_CALLS);
+    let result = runner
+        .execute(wasm, image_bytes, None, "process")
+        .expect("pixel-hash-v1 WASM実行に失敗");
+
+    result.output["pixel_hash"]
+        .as_str()
+        .expect("pixel_hash フィールドが見つからない")
+        .to_string()
+}
+
+/// 量子化ビンの中央に位置する色で構成したブロック画像を生成する。
+fn block_image() -> image::RgbImage {
+    let colors = [[72u8, 136, 200], [200, 72, 136], [136, 200, 72], [40, 104, 168]];
+    image::RgbImage::from_fn(256, 256, |x, y| {
+        image::Rgb(colors[((x / 128) + 2 * (y / 128)) as usize])
+    })
+}
+
+/// 指定品質でJPEGエンコードする。
+fn encode_jpeg(img: &image::RgbImage, quality: u8) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, quality)
+        .encode_image(img)
+        .unwrap();
+    buf.into_inner()
+}
+
+/// 異なる品質で再エンコードしたJPEGが同一の pixel_hash を返すこと。
+#[test]
+fn test_pixel_hash_stable_across_jpeg_quality() {
+    let wasm = match load_pixel_hash_wasm() {
+        Some(w) => w,
+        None => {
+            eprintln!("SKIP: pixel-hash-v1.wasm が見つかりません（先にビルドしてください）");
+            return;
+        }
+    };
+
+    let img = block_image();
+    let high = run_pixel_hash(&wasm, &encode_jpeg(&img, 95));
+    let low = run_pixel_hash(&wasm, &encode_jpeg(&img, 70));
+
+    assert_eq!(high.len(), 64);
+    assert_eq!(high, low, "再エンコードで pixel_hash が変化しました");
+}
+
+/// 異なる画像が異なる pixel_hash を返すこと。
+#[test]
+fn test_pixel_hash_different_images() {
+    let wasm = match load_pixel_hash_wasm() {
+        Some(w) => w,
+        None => {
+            eprintln!("SKIP: pixel-hash-v1.wasm が見つかりません");
+            return;
+        }
+    };
+
+    let img = block_image();
+    let mut flipped = img.clone();
+    image::imageops::flip_horizontal_in_place(&mut flipped);
+
+    let a = run_pixel_hash(&wasm, &encode_jpeg(&img, 90));
+    let b = run_pixel_hash(&wasm, &encode_jpeg(&flipped, 90));
+    assert_ne!(a, b);
+}
@@ -207,7 +207,7 @@ echo "[Step 1/10] WASMモジュールのビルド..."
 WASM_OUTPUT="$PROJECT_ROOT/wasm-modules"
 mkdir -p "$WASM_OUTPUT"
 
-WASM_TARGETS=(phash-v1 hardware-google c2pa-training-v1 c2pa-license-v1)
+WASM_TARGETS=(phash-v1 hardware-google c2pa-training-v1 c2pa-license-v1 pixel-hash-v1 cawg-identity-v1 assertion-list-v1 image-quality-v1)
 
 export OPENSSL_NO_VENDOR=1
 
@@ -325,7 +325,7 @@ else
         CORE_COLLECTION_MINT="$CORE_COLLECTION_MINT" \
         EXT_COLLECTION_MINT="$EXT_COLLECTION_MINT" \
         GATEWAY_PUBKEY="${GATEWAY_PUBKEY:-}" \
-        TRUSTED_EXTENSIONS="${TRUSTED_EXTENSIONS:-phash-v1,hardware-google,c2pa-training-v1,c2pa-license-v1}" \
+        TRUSTED_EXTENSIONS="${TRUSTED_EXTENSIONS:-phash-v1,hardware-google,c2pa-training-v1,c2pa-license-v1,pixel-hash-v1,cawg-identity-v1}" \
         WASM_DIR="$WASM_OUTPUT" \
         nohup ./target/release/title-tee > /tmp/title-tee.log 2>&1 &
       echo "  TEE起動 (MockRuntime, PID=$!)"
@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: Apache-2.0
+
+/**
+ * types.ts のユニットテスト
+ *
+ * コンパクト表現の来歴グラフリンク（compact_links）の展開をテスト。
+ */
+
+import { describe, it } from "node:test";
+import * as assert from "node:assert/strict";
+
+import { graphLinks, type CorePayload } from "../types";
+
+function payload(overrides: Partial<CorePayload>): CorePayload {
+  return {
+    content_hash: "0xaaa",
+    content_type: "image/jpeg",
+    creator_wallet: "wallet",
+    nodes: [
+      { id: "0xaaa", type: "final" },
+      { id: "0xbbb", type: "ingredient" },
+    ],
+    links: [],
+    ...overrides,
+  };
+}
+
+describe("graphLinks", () => {
+  it("returns links as-is for the regular form", () => {
+    const links = [{ source: "0xbbb", target: "0xaaa", role: "image" }];
+    assert.deepEqual(graphLinks(payload({ links })), links);
+  });
+
+  it("expands compact_links against nodes", () => {
+    const p = payload({ compact_links: [{ source: 1, target: 0, role: "image" }] });
+    assert.deepEqual(graphLinks(p), [{ source: "0xbbb", target: "0xaaa", role: "image" }]);
+  });
+
+  it("rejects out-of-range node indices", () => {
+    const p = payload({ compact_links: [{ source: 2, target: 0, role: "image" }] });
+    assert.throws(() => graphLinks(p), /out of range/);
+  });
+});
@@ -26,6 +26,16 @@ import { encryptPayload, decryptResponse } from "./crypto";
 /** Node capabilities advertised via GET /health. */
 export interface NodeCapabilities {
   store_signed_json: boolean;
+  /** Core protocol identifier (e.g. "Title-v1"). Absent on older nodes. */
+  protocol_version?: string;
+  /** TEE signature algorithm (e.g. "Ed25519"). */
+  signature_algorithm?: string;
+  /** Hash algorithm used for content_hash (e.g. "SHA-256"). */
+  content_hash_algorithm?: string;
+  /** Extension WASM ABI versions the TEE can execute. */
+  wasm_abi_versions?: number[];
+  /** Deployment namespace mixed into content_hash ("" = none). */
+  content_hash_namespace?: string;
 }
 
 /** Session with a specific TEE node. */
@@ -39,8 +39,21 @@ export interface CorePayload {
   tsa_pubkey_hash?: string;
   /** RFC 3161 token (Base64). */
   tsa_token_data?: string;
+  /** True when the TSA certificate is in the node's trusted TSA list. Spec §2.4 */
+  tsa_trusted?: boolean;
   nodes: GraphNode[];
+  /** Empty when `compact_links` is returned; use `graphLinks()` to get the links either way. */
   links: GraphLink[];
+  /** Links referencing nodes by index, returned only for `compact_graph` requests. Spec §2.2 */
+  compact_links?: CompactGraphLink[];
+  /** True when the graph was cut down to `max_returned_nodes`. Spec §5.1 Step 4 */
+  truncated?: boolean;
+  /** True when verified from a sidecar manifest without the content body. Spec §5.1 Step 1 */
+  manifest_only?: boolean;
+  /** Edit action types from the active manifest's `c2pa.actions`, in recorded order. Spec §5.1 Step 4 */
+  actions?: string[];
+  /** Whether `c2pa.actions` declares generative-AI creation; omitted when unknown. Spec §5.1 Step 4 */
+  ai_generated?: boolean;
 }
 
 /** Extension payload. Spec §5.1 Step 5 */
@@ -72,6 +85,37 @@ export interface GraphLink {
   role: string;
 }
 
+/** Provenance graph link referencing nodes by their index in `CorePayload.nodes`. Spec §2.2 */
+export interface CompactGraphLink {
+  source: number;
+  target: number;
+  role: string;
+}
+
+/**
+ * Returns the provenance graph links of a Core payload, expanding `compact_links`
+ * against `nodes` when the payload uses the compact form. Spec §2.2
+ *
+ * @throws Error if a compact link references a node index out of range.
+ */
+export function graphLinks(payload: CorePayload): GraphLink[] {
+  if (!payload.compact_links || payload.compact_links.length === 0) {
+    return payload.links;
+  }
+  const idOf = (index: number): string => {
+    const node = payload.nodes[index];
+    if (!node) {
+      throw new Error(`Link node index out of range: ${index}`);
+    }
+    return node.id;
+  };
+  return payload.compact_links.map((link) => ({
+    source: idOf(link.source),
+    target: idOf(link.target),
+    role: link.role,
+  }));
+}
+
 // ---------------------------------------------------------------------------
 // Global Config (Spec §5.2 Step 1)
 // ---------------------------------------------------------------------------
@@ -151,11 +195,13 @@ export interface EncryptedPayload {
 export interface ClientPayload {
   /** Solana wallet address (Base58). */
   owner_wallet: string;
-  /** Content binary (Base64). */
+  /** Content binary (Base64). Empty in manifest-only mode. */
   content: string;
   /** C2PA sidecar manifest (Base64, optional). */
   sidecar_manifest?: string;
   extension_inputs?: Record<string, unknown>;
+  /** Client-asserted content hash (hex). Enables manifest-only mode. Spec §5.1 Step 1 */
+  asserted_content_hash?: string;
 }
 
 // ---------------------------------------------------------------------------
@@ -166,6 +212,19 @@ export interface ClientPayload {
 export interface VerifyRequest {
   download_url: string;
   processor_ids: string[];
+  /** Optional cap on provenance graph nodes + links. Can only lower the node's limit. Spec §6.4 */
+  max_graph_size?: number;
+  /** Optional cap on returned graph nodes; the full graph is still validated. Spec §5.1 Step 4 */
+  max_returned_nodes?: number;
+  /** Return Core graph links as `compact_links` (node indices) instead of content hashes. Spec §2.2 */
+  compact_graph?: boolean;
+  /** Optional token for aborting the in-flight request via DELETE /verify/{token}. Spec §6.4 */
+  cancel_token?: string;
+}
+
+/** DELETE /verify/{token} request body. The token is bound to the same download_url. Spec §6.4 */
+export interface CancelVerifyRequest {
+  download_url: string;
 }
 
 /** /verify response. Spec §5.1 Step 6 */
@@ -177,6 +236,13 @@ export interface VerifyResponse {
 export interface ProcessorResult {
   processor_id: string;
   signed_json: SignedJson;
+  /** Serialized extension result size in bytes (extensions only). Spec §5.1 Step 6 */
+  result_size?: number;
+  /**
+   * Signer certificate expiry warning at verification time (Core only). Not part of the signed data;
+   * the signed attributes always carry the absolute `signer_cert_not_after`. Spec §2.1, §5.1 Step 6
+   */
+  signer_cert_warning?: "expired" | "expiring_soon";
 }
 
 /** /sign request. Spec §5.1 Step 8 */
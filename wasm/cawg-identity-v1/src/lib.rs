@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # cawg-identity-v1 Extension WASM モジュール
+//!
+//! 仕様書 §7.4: C2PAマニフェスト内のCAWGアイデンティティアサーション（`cawg.identity`）の
+//! 有無と発行者を記録する。プラットフォームが「本人確認済み」表示を行うための材料となる。
+//!
+//! ## 処理内容
+//! アサーションの探索・パースはホスト関数 `get_content_feature` の
+//! `c2pa_cawg_identity` op が行い、結果JSONをそのまま出力とする。
+//! 発行者は署名方式に応じて抽出される:
+//! - `cawg.x509.cose`: 署名者証明書のIssuer DN
+//! - `cawg.identity_claims_aggregation`: VCの `issuer`
+//!
+//! アイデンティティアサーション署名の暗号検証は行わない（存在と発行者の抽出のみ）。
+//!
+//! ## 対応フォーマット
+//! JPEG（C2PA JUMBFをAPP11に埋め込んだもの）
+//!
+//! ## ターゲット
+//! `wasm32-unknown-unknown`
+
+#![no_std]
+
+extern crate alloc;
+
+use title_wasm_abi::{ResultBuffer, HEADER_LEN};
+
+#[global_allocator]
+static ALLOC: dlmalloc::GlobalDlmalloc = dlmalloc::GlobalDlmalloc;
+
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+// ---------------------------------------------------------------------------
+// ホスト関数宣言（TEEホストが提供）
+// 仕様書 §7.1
+// ---------------------------------------------------------------------------
+
+extern "C" {
+    /// コンテンツの特徴量を計算する（JSON spec指定）。
+    /// 戻り値: 出力バイト数（正値）またはエラーコード（負値）
+    /// -4=出力がmax_lengthを超過, -5=C2PA構造エラー
+    fn get_content_feature(spec_ptr: u32, spec_len: u32, output_ptr: u32) -> i32;
+}
+
+// ---------------------------------------------------------------------------
+// メモリアロケータ
+// ---------------------------------------------------------------------------
+
+#[no_mangle]
+pub extern "C" fn alloc(size: u32) -> u32 {
+    let layout = core::alloc::Layout::from_size_align(size as usize, 1).unwrap();
+    unsafe { alloc::alloc::alloc(layout) as u32 }
+}
+
+// ---------------------------------------------------------------------------
+// ABI v2（仕様書 §7.1）
+// ---------------------------------------------------------------------------
+
+/// ABIバージョン。v2では `process` の負の戻り値がエラーコードを表す。
+const ABI_VERSION: i32 = 2;
+
+/// エラーコード: メモリ確保に失敗
+const ERR_OUT_OF_MEMORY: i32 = -1;
+
+/// エラーコード: C2PAデータを含まない、または非対応フォーマット
+const ERR_UNSUPPORTED_FORMAT: i32 = -2;
+
+/// エラーコード: アサーションが不正
+const ERR_INVALID_INPUT: i32 = -3;
+
+/// ホストにABIバージョンを通知する。
+#[no_mangle]
+pub extern "C" fn title_abi_version() -> i32 {
+    ABI_VERSION
+}
+
+/// このモジュールのExtension ID。
+const EXTENSION_ID: &str = "cawg-identity-v1";
+
+/// ホストにExtension IDを自己申告する（`[4B LE: len][id_bytes...]` へのポインタ）。
+/// ホストは要求されたExtension IDと照合し、取り違えたモジュールを拒否する。
+#[no_mangle]
+pub extern "C" fn title_extension_id() -> i32 {
+    match ResultBuffer::new(EXTENSION_ID.as_bytes()).write(alloc) {
+        Some(ptr) => ptr as i32,
+        None => ERR_OUT_OF_MEMORY,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// エクスポート関数
+// ---------------------------------------------------------------------------
+
+/// ホスト出力JSONの最大長（バイト）
+const MAX_OUTPUT_LEN: usize = 1024;
+
+/// CAWGアイデンティティアサーションの有無と発行者を返す。
+/// 仕様書 §7.4
+///
+/// 返却JSON:
+/// - `{"identity_present":true,"sig_type":"cawg.x509.cose","issuer":"CN=..."}` — 検出
+/// - `{"identity_present":false,"sig_type":null,"issuer":null}` — 未検出
+#[no_mangle]
+pub extern "C" fn process() -> i32 {
+    let spec = alloc::format!("{{\"op\":\"c2pa_cawg_identity\",\"max_length\":{MAX_OUTPUT_LEN}}}");
+
+    // 結果バッファ: [4B LE: json_len][json_bytes...]
+    // ホスト出力を本体位置に直接受け取り、長さプレフィックスを後から前置する
+    let ptr = alloc((HEADER_LEN + MAX_OUTPUT_LEN) as u32);
+    if ptr == 0 {
+        return ERR_OUT_OF_MEMORY;
+    }
+
+    let rc = unsafe {
+        get_content_feature(spec.as_ptr() as u32, spec.len() as u32, ptr + HEADER_LEN as u32)
+    };
+    match rc {
+        len if len > 0 => {
+            let len_bytes = ResultBuffer::encode_header(len as usize);
+            unsafe {
+                core::ptr::copy_nonoverlapping(len_bytes.as_ptr(), ptr as *mut u8, HEADER_LEN);
+            }
+            ptr as i32
+        }
+        -5 => ERR_UNSUPPORTED_FORMAT,
+        _ => ERR_INVALID_INPUT,
+    }
+}
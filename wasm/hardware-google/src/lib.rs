@@ -17,6 +17,7 @@
 extern crate alloc;
 
 use alloc::string::String;
+use title_wasm_abi::ResultBuffer;
 
 #[global_allocator]
 static ALLOC: dlmalloc::GlobalDlmalloc = dlmalloc::GlobalDlmalloc;
@@ -56,27 +57,42 @@ pub extern "C" fn alloc(size: u32) -> u32 {
     unsafe { alloc::alloc::alloc(layout) as u32 }
 }
 
+// ---------------------------------------------------------------------------
+// ABI v2（仕様書 §7.1）
+// ---------------------------------------------------------------------------
+
+/// ABIバージョン。v2では `process` の負の戻り値がエラーコードを表す。
+const ABI_VERSION: i32 = 2;
+
+/// エラーコード: メモリ確保に失敗
+const ERR_OUT_OF_MEMORY: i32 = -1;
+
+/// ホストにABIバージョンを通知する。
+#[no_mangle]
+pub extern "C" fn title_abi_version() -> i32 {
+    ABI_VERSION
+}
+
+/// このモジュールのExtension ID。
+const EXTENSION_ID: &str = "hardware-google";
+
+/// ホストにExtension IDを自己申告する（`[4B LE: len][id_bytes...]` へのポインタ）。
+/// ホストは要求されたExtension IDと照合し、取り違えたモジュールを拒否する。
+#[no_mangle]
+pub extern "C" fn title_extension_id() -> i32 {
+    write_result(EXTENSION_ID)
+}
+
 // ---------------------------------------------------------------------------
 // 結果バッファ書き込みヘルパー
 // ---------------------------------------------------------------------------
 
 /// JSON文字列を length-prefixed 結果バッファとして書き込み、ポインタを返す。
-fn write_result(json: &str) -> u32 {
-    let json_bytes = json.as_bytes();
-    let total = 4 + json_bytes.len();
-    let ptr = alloc(total as u32);
-    if ptr == 0 {
-        return 0;
-    }
-    let len_bytes = (json_bytes.len() as u32).to_le_bytes();
-    // SAFETY: ptr は直前の alloc(total) で確保した領域。len_bytes(4B) + json_bytes は
-    // total バイト以内。コピー元・先は重複しない。
-    unsafe {
-        let p = ptr as *mut u8;
-        core::ptr::copy_nonoverlapping(len_bytes.as_ptr(), p, 4);
-        core::ptr::copy_nonoverlapping(json_bytes.as_ptr(), p.add(4), json_bytes.len());
+fn write_result(json: &str) -> i32 {
+    match ResultBuffer::new(json.as_bytes()).write(alloc) {
+        Some(ptr) => ptr as i32,
+        None => ERR_OUT_OF_MEMORY,
     }
-    ptr
 }
 
 /// コンテンツ内でバイトパターンを検索する。
@@ -142,7 +158,7 @@ fn find_pattern(pattern: &[u8]) -> bool {
 /// 初期実装: C2PAマニフェスト内のハードウェア関連アサーションマーカーの有無を検出する。
 /// 検出対象: "c2pa.hash.data"（ハードウェアバインディング）、"stds.iptc"（Exif由来メタデータ）
 #[no_mangle]
-pub extern "C" fn process() -> u32 {
+pub extern "C" fn process() -> i32 {
     // ハードウェアバインディングのマーカーを検索
     let has_hash_data = find_pattern(b"c2pa.hash.data");
     // IPTC/Exifメタデータ（カメラ情報を含む可能性）
@@ -0,0 +1,463 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Image Quality Extension WASM モジュール
+//!
+//! 仕様書 §3.2: 画像の品質・圧縮の度合いを表す指標を算出するExtension。
+//! 再圧縮を重ねた低品質コピーの判別や、プラットフォーム側での品質フィルタに用いる。
+//!
+//! ## 指標
+//! - `jpeg_quality`: JPEGの輝度量子化テーブル（DQT, Tq=0）をIJG標準テーブルと比較して
+//!   推定したエンコード品質（1〜100）。JPEG以外、または輝度テーブルがない場合は `null`
+//! - `blockiness`: 8×8ブロック境界をまたぐ隣接画素の輝度差の平均 ÷ ブロック内部の
+//!   隣接画素の輝度差の平均（内部側が1階調未満の場合は1階調とみなす）。
+//!   1.000付近はブロックノイズなし、大きいほど境界が目立つ。画像が8画素以下の場合は `null`
+//! - `noise`: 輝度のラプラシアン `|4p − 上 − 下 − 左 − 右|` の平均。3×3未満の場合は `null`
+//!
+//! 輝度は整数近似 `Y = (77R + 150G + 29B) >> 8` で求め、すべて整数演算で計算する。
+//! 小数は1000倍した整数を小数点以下3桁で出力するため、同一入力に対して常に同一の結果を返す。
+//! 算出方法を変更する場合は別のExtension ID（`image-quality-v2` 等）とすること。
+//!
+//! ## 対応フォーマット
+//! ホスト側の`image`crateが対応する全フォーマット（JPEG, PNG, WebP, GIF, BMP, TIFF等）。
+//! `jpeg_quality` はJPEGのみ。
+//!
+//! ## ターゲット
+//! `wasm32-unknown-unknown`
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec;
+use core::fmt::Write;
+use title_wasm_abi::ResultBuffer;
+
+#[global_allocator]
+static ALLOC: dlmalloc::GlobalDlmalloc = dlmalloc::GlobalDlmalloc;
+
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+// ---------------------------------------------------------------------------
+// ホスト関数宣言（TEEホストが提供）
+// 仕様書 §7.1
+// ---------------------------------------------------------------------------
+
+extern "C" {
+    /// コンテンツのチャンクを読み取る。
+    /// 戻り値: 実際に読み取ったバイト数
+    fn read_content_chunk(offset: u32, length: u32, buf_ptr: u32) -> u32;
+
+    /// コンテンツの全長を取得する。
+    fn get_content_length() -> u32;
+
+    /// コンテンツをネイティブフォーマットでデコードする。
+    /// metadata_ptr: [width:u32 LE, height:u32 LE, channels:u32 LE] を書き込む
+    /// 戻り値: 0=成功, -1=非対応, -2=メモリ超過, -3=デコードエラー
+    fn decode_content(params_ptr: u32, params_len: u32, metadata_ptr: u32) -> i32;
+
+    /// デコード済みデータのチャンクを読み取る。
+    /// 戻り値: 実際に読み取ったバイト数
+    fn read_decoded_chunk(offset: u32, length: u32, buf_ptr: u32) -> u32;
+
+    /// デコード済みデータの全長を取得する。
+    fn get_decoded_length() -> u32;
+}
+
+// ---------------------------------------------------------------------------
+// メモリアロケータ
+// ---------------------------------------------------------------------------
+
+#[no_mangle]
+pub extern "C" fn alloc(size: u32) -> u32 {
+    let layout = core::alloc::Layout::from_size_align(size as usize, 1).unwrap();
+    unsafe { alloc::alloc::alloc(layout) as u32 }
+}
+
+// ---------------------------------------------------------------------------
+// ABI v2（仕様書 §7.1）
+// ---------------------------------------------------------------------------
+
+/// ABIバージョン。v2では `process` の負の戻り値がエラーコードを表す。
+const ABI_VERSION: i32 = 2;
+
+/// エラーコード: メモリ確保に失敗
+const ERR_OUT_OF_MEMORY: i32 = -1;
+
+/// エラーコード: 対応していない画像フォーマット
+const ERR_UNSUPPORTED_FORMAT: i32 = -2;
+
+/// エラーコード: コンテンツが不正（デコード失敗等）
+const ERR_INVALID_INPUT: i32 = -3;
+
+/// ホストにABIバージョンを通知する。
+#[no_mangle]
+pub extern "C" fn title_abi_version() -> i32 {
+    ABI_VERSION
+}
+
+/// このモジュールのExtension ID。
+const EXTENSION_ID: &str = "image-quality-v1";
+
+/// ホストにExtension IDを自己申告する（`[4B LE: len][id_bytes...]` へのポインタ）。
+/// ホストは要求されたExtension IDと照合し、取り違えたモジュールを拒否する。
+#[no_mangle]
+pub extern "C" fn title_extension_id() -> i32 {
+    write_result(EXTENSION_ID)
+}
+
+// ---------------------------------------------------------------------------
+// 結果バッファ書き込みヘルパー
+// ---------------------------------------------------------------------------
+
+fn write_result(json: &str) -> i32 {
+    match ResultBuffer::new(json.as_bytes()).write(|size| alloc(size)) {
+        Some(ptr) => ptr as i32,
+        None => ERR_OUT_OF_MEMORY,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// JPEG品質の推定
+// ---------------------------------------------------------------------------
+
+/// IJG標準の輝度量子化テーブル（ITU-T T.81 Annex K.1、自然順）
+const STD_LUMINANCE_TABLE: [u32; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, //
+    12, 12, 14, 19, 26, 58, 60, 55, //
+    14, 13, 16, 24, 40, 57, 69, 56, //
+    14, 17, 22, 29, 51, 87, 80, 62, //
+    18, 22, 37, 56, 68, 109, 103, 77, //
+    24, 35, 55, 64, 81, 104, 113, 92, //
+    49, 64, 78, 87, 103, 121, 120, 101, //
+    72, 92, 95, 98, 112, 100, 103, 99, //
+];
+
+/// ジグザグ順の位置 → 自然順のインデックス（DQTの値はジグザグ順で格納される）
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, //
+    12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14, 21, 28, //
+    35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, //
+    58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63, //
+];
+
+/// コンテンツの `offset` から `buf` を埋めるまで読み取る。読み取れたバイト数を返す。
+fn read_content_at(offset: usize, buf: &mut [u8]) -> usize {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let want = buf.len() - filled;
+        // SAFETY: 書き込み先は buf の未読領域（want バイト）。read_content_chunk はホスト提供関数。
+        let read = unsafe {
+            read_content_chunk(
+                (offset + filled) as u32,
+                want as u32,
+                buf[filled..].as_mut_ptr() as u32,
+            )
+        } as usize;
+        if read == 0 {
+            break;
+        }
+        // ホスト返値を要求サイズで上限クランプ（バッファ外読取防止）
+        filled += core::cmp::min(read, want);
+    }
+    filled
+}
+
+/// JPEGのマーカーセグメントを走査し、輝度量子化テーブル（Tq=0）を自然順で返す。
+///
+/// SOS（スキャン開始）またはEOIで走査を打ち切る。C2PAのAPP11等の大きなセグメントは
+/// 長さフィールドで読み飛ばすため、マーカー部分のみを読み取る。
+fn find_luminance_table() -> Option<[u32; 64]> {
+    // SAFETY: ホスト関数はwasm-hostが提供し、WASMリニアメモリの範囲内で安全に動作する。
+    let content_len = unsafe { get_content_length() } as usize;
+
+    let mut soi = [0u8; 2];
+    if read_content_at(0, &mut soi) != 2 || soi != [0xFF, 0xD8] {
+        return None;
+    }
+
+    let mut table = None;
+    let mut pos = 2;
+    while pos + 4 <= content_len {
+        let mut header = [0u8; 4];
+        if read_content_at(pos, &mut header) != 4 || header[0] != 0xFF {
+            break;
+        }
+        let marker = header[1];
+        match marker {
+            // フィルバイト
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            // 長さを持たないマーカー（TEM, RSTn）
+            0x01 | 0xD0..=0xD7 => {
+                pos += 2;
+                continue;
+            }
+            // SOS / EOI: 最初のスキャンが用いるテーブルはSOSより前に定義される
+            0xDA | 0xD9 => break,
+            _ => {}
+        }
+
+        let seg_len = u16::from_be_bytes([header[2], header[3]]) as usize;
+        if seg_len < 2 {
+            break;
+        }
+        if marker == 0xDB {
+            let mut seg = vec![0u8; seg_len - 2];
+            if read_content_at(pos + 4, &mut seg) != seg.len() {
+                break;
+            }
+            if let Some(t) = parse_dqt(&seg) {
+                table = Some(t);
+            }
+        }
+        pos += 2 + seg_len;
+    }
+    table
+}
+
+/// DQTセグメント本体（長さフィールドの後ろ）から Tq=0 のテーブルを取り出す。
+/// 1セグメントに複数テーブルが連続する場合にも対応する。
+fn parse_dqt(seg: &[u8]) -> Option<[u32; 64]> {
+    let mut found = None;
+    let mut i = 0;
+    while i < seg.len() {
+        let precision = seg[i] >> 4;
+        let table_id = seg[i] & 0x0F;
+        let entry_size = if precision == 0 { 1 } else { 2 };
+        let end = i + 1 + 64 * entry_size;
+        if end > seg.len() {
+            break;
+        }
+        if table_id == 0 {
+            let mut table = [0u32; 64];
+            for (k, &natural) in ZIGZAG.iter().enumerate() {
+                let at = i + 1 + k * entry_size;
+                table[natural] = if entry_size == 1 {
+                    seg[at] as u32
+                } else {
+                    u16::from_be_bytes([seg[at], seg[at + 1]]) as u32
+                };
+            }
+            found = Some(table);
+        }
+        i = end;
+    }
+    found
+}
+
+/// 輝度量子化テーブルからIJG品質（1〜100）を推定する。
+///
+/// IJGのテーブル生成（品質 q に対するスケール `q < 50 ? 5000/q : 200 − 2q` [%]）の逆算。
+/// 標準テーブルとの比の平均をスケールとみなす。全要素が1のテーブルは品質100とする。
+fn estimate_quality(table: &[u32; 64]) -> u32 {
+    if table.iter().all(|&q| q == 1) {
+        return 100;
+    }
+    // スケール（%）の1000倍
+    let sum: u64 = table
+        .iter()
+        .zip(STD_LUMINANCE_TABLE.iter())
+        .map(|(&q, &std)| q as u64 * 100_000 / std as u64)
+        .sum();
+    let scale_milli = sum / 64;
+    let quality = if scale_milli <= 100_000 {
+        (200_000 - scale_milli + 1_000) / 2_000
+    } else {
+        (5_000_000 + scale_milli / 2) / scale_milli
+    };
+    quality.clamp(1, 100) as u32
+}
+
+// ---------------------------------------------------------------------------
+// 画素指標
+// ---------------------------------------------------------------------------
+
+/// ブロック境界の間隔（JPEGのDCTブロックサイズ）
+const BLOCK_SIZE: usize = 8;
+
+/// 画素指標の集計値
+#[derive(Default)]
+struct PixelStats {
+    /// ブロック境界をまたぐ隣接画素の輝度差の合計と件数
+    boundary_sum: u64,
+    boundary_count: u64,
+    /// ブロック内部の隣接画素の輝度差の合計と件数
+    inner_sum: u64,
+    inner_count: u64,
+    /// ラプラシアン絶対値の合計と件数
+    noise_sum: u64,
+    noise_count: u64,
+}
+
+impl PixelStats {
+    /// 隣接画素の輝度差を集計する。`index` は2画素のうち後ろ側の座標。
+    fn add_diff(&mut self, index: usize, a: u8, b: u8) {
+        let d = a.abs_diff(b) as u64;
+        if index % BLOCK_SIZE == 0 {
+            self.boundary_sum += d;
+            self.boundary_count += 1;
+        } else {
+            self.inner_sum += d;
+            self.inner_count += 1;
+        }
+    }
+
+    /// blockiness の1000倍。ブロック境界がなければ `None`。
+    fn blockiness_milli(&self) -> Option<u64> {
+        if self.boundary_count == 0 || self.inner_count == 0 {
+            return None;
+        }
+        // 内部側の平均差分は1階調を下限とする（平坦な画像での発散を防ぐ）
+        let inner = core::cmp::max(self.inner_sum, self.inner_count) as u128;
+        let milli = self.boundary_sum as u128 * 1000 * self.inner_count as u128
+            / (self.boundary_count as u128 * inner);
+        Some(milli as u64)
+    }
+
+    /// noise の1000倍。対象画素がなければ `None`。
+    fn noise_milli(&self) -> Option<u64> {
+        if self.noise_count == 0 {
+            return None;
+        }
+        Some(self.noise_sum * 1000 / self.noise_count)
+    }
+}
+
+/// 1行分の画素を輝度に変換する。
+fn to_luma(row: &[u8], channels: usize, out: &mut [u8]) {
+    for (px, y) in row.chunks_exact(channels).zip(out.iter_mut()) {
+        *y = if channels == 1 {
+            px[0]
+        } else {
+            ((77 * px[0] as u32 + 150 * px[1] as u32 + 29 * px[2] as u32) >> 8) as u8
+        };
+    }
+}
+
+/// デコード済み画像を1行ずつ読み取り、画素指標を集計する。
+/// 保持するのは直近3行の輝度のみで、画像全体をWASMメモリに展開しない。
+fn compute_pixel_stats(width: usize, height: usize, channels: usize) -> Option<PixelStats> {
+    let row_bytes = width * channels;
+    let mut raw = vec![0u8; row_bytes];
+    let mut up = vec![0u8; width];
+    let mut mid = vec![0u8; width];
+    let mut cur = vec![0u8; width];
+    let mut stats = PixelStats::default();
+
+    for y in 0..height {
+        let mut filled = 0;
+        while filled < row_bytes {
+            let want = row_bytes - filled;
+            // SAFETY: 書き込み先は raw の未読領域（want バイト）。read_decoded_chunk はホスト提供関数。
+            let read = unsafe {
+                read_decoded_chunk(
+                    (y * row_bytes + filled) as u32,
+                    want as u32,
+                    raw[filled..].as_mut_ptr() as u32,
+                )
+            } as usize;
+            if read == 0 {
+                return None;
+            }
+            filled += core::cmp::min(read, want);
+        }
+        to_luma(&raw, channels, &mut cur);
+
+        for x in 1..width {
+            stats.add_diff(x, cur[x - 1], cur[x]);
+        }
+        if y >= 1 {
+            for x in 0..width {
+                stats.add_diff(y, mid[x], cur[x]);
+            }
+        }
+        if y >= 2 {
+            for x in 1..width.saturating_sub(1) {
+                let center = 4 * mid[x] as i32;
+                let around = up[x] as i32 + cur[x] as i32 + mid[x - 1] as i32 + mid[x + 1] as i32;
+                stats.noise_sum += (center - around).unsigned_abs() as u64;
+                stats.noise_count += 1;
+            }
+        }
+
+        core::mem::swap(&mut up, &mut mid);
+        core::mem::swap(&mut mid, &mut cur);
+    }
+    Some(stats)
+}
+
+/// 1000倍した整数値を小数点以下3桁のJSON数値として書き込む（`None` は `null`）。
+fn push_milli(json: &mut String, value: Option<u64>) {
+    match value {
+        Some(v) => {
+            let _ = write!(json, "{}.{:03}", v / 1000, v % 1000);
+        }
+        None => json.push_str("null"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// エクスポート関数
+// ---------------------------------------------------------------------------
+
+/// 画像の品質・圧縮指標を計算する。
+/// 仕様書 §3.2
+///
+/// 結果JSON: {"jpeg_quality":85,"blockiness":1.234,"noise":5.678}
+#[no_mangle]
+pub extern "C" fn process() -> i32 {
+    // 1. JPEGの量子化テーブルから品質を推定（デコード前に生バイトを走査）
+    let jpeg_quality = find_luminance_table().map(|t| estimate_quality(&t));
+
+    // 2. ホスト側でネイティブフォーマットにデコード
+    let mut metadata = [0u8; 12];
+    let rc = unsafe { decode_content(0, 0, metadata.as_mut_ptr() as u32) };
+
+    match rc {
+        0 => {} // 成功
+        -1 => return ERR_UNSUPPORTED_FORMAT,
+        -2 => return ERR_OUT_OF_MEMORY,
+        _ => return ERR_INVALID_INPUT,
+    }
+
+    let width = u32::from_le_bytes([metadata[0], metadata[1], metadata[2], metadata[3]]) as usize;
+    let height = u32::from_le_bytes([metadata[4], metadata[5], metadata[6], metadata[7]]) as usize;
+    let channels =
+        u32::from_le_bytes([metadata[8], metadata[9], metadata[10], metadata[11]]) as usize;
+    if width == 0 || height == 0 || !matches!(channels, 1 | 3 | 4) {
+        return ERR_INVALID_INPUT;
+    }
+    // SAFETY: ホスト関数はwasm-hostが提供し、WASMリニアメモリの範囲内で安全に動作する。
+    let decoded_len = unsafe { get_decoded_length() } as usize;
+    if Some(decoded_len) != width.checked_mul(height).and_then(|n| n.checked_mul(channels)) {
+        return ERR_INVALID_INPUT;
+    }
+
+    // 3. 輝度から画素指標を集計
+    let stats = match compute_pixel_stats(width, height, channels) {
+        Some(s) => s,
+        None => return ERR_INVALID_INPUT,
+    };
+
+    let mut json = String::with_capacity(96);
+    json.push_str("{\"jpeg_quality\":");
+    match jpeg_quality {
+        Some(q) => {
+            let _ = write!(&mut json, "{q}");
+        }
+        None => json.push_str("null"),
+    }
+    json.push_str(",\"blockiness\":");
+    push_milli(&mut json, stats.blockiness_milli());
+    json.push_str(",\"noise\":");
+    push_milli(&mut json, stats.noise_milli());
+    json.push('}');
+
+    write_result(&json)
+}
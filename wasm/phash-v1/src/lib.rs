@@ -27,6 +27,7 @@ extern crate alloc;
 
 use alloc::string::String;
 use core::fmt::Write;
+use title_wasm_abi::ResultBuffer;
 
 #[global_allocator]
 static ALLOC: dlmalloc::GlobalDlmalloc = dlmalloc::GlobalDlmalloc;
@@ -71,24 +72,47 @@ pub extern "C" fn alloc(size: u32) -> u32 {
     unsafe { alloc::alloc::alloc(layout) as u32 }
 }
 
+// ---------------------------------------------------------------------------
+// ABI v2（仕様書 §7.1）
+// ---------------------------------------------------------------------------
+
+/// ABIバージョン。v2では `process` の負の戻り値がエラーコードを表す。
+const ABI_VERSION: i32 = 2;
+
+/// エラーコード: メモリ確保に失敗
+const ERR_OUT_OF_MEMORY: i32 = -1;
+
+/// エラーコード: 対応していない画像フォーマット
+const ERR_UNSUPPORTED_FORMAT: i32 = -2;
+
+/// エラーコード: コンテンツが不正（デコード失敗等）
+const ERR_INVALID_INPUT: i32 = -3;
+
+/// ホストにABIバージョンを通知する。
+#[no_mangle]
+pub extern "C" fn title_abi_version() -> i32 {
+    ABI_VERSION
+}
+
+/// このモジュールのExtension ID。
+const EXTENSION_ID: &str = "phash-v1";
+
+/// ホストにExtension IDを自己申告する（`[4B LE: len][id_bytes...]` へのポインタ）。
+/// ホストは要求されたExtension IDと照合し、取り違えたモジュールを拒否する。
+#[no_mangle]
+pub extern "C" fn title_extension_id() -> i32 {
+    write_result(EXTENSION_ID)
+}
+
 // ---------------------------------------------------------------------------
 // 結果バッファ書き込みヘルパー
 // ---------------------------------------------------------------------------
 
-fn write_result(json: &str) -> u32 {
-    let json_bytes = json.as_bytes();
-    let total = 4 + json_bytes.len();
-    let ptr = alloc(total as u32);
-    if ptr == 0 {
-        return 0;
+fn write_result(json: &str) -> i32 {
+    match ResultBuffer::new(json.as_bytes()).write(alloc) {
+        Some(ptr) => ptr as i32,
+        None => ERR_OUT_OF_MEMORY,
     }
-    let len_bytes = (json_bytes.len() as u32).to_le_bytes();
-    unsafe {
-        let p = ptr as *mut u8;
-        core::ptr::copy_nonoverlapping(len_bytes.as_ptr(), p, 4);
-        core::ptr::copy_nonoverlapping(json_bytes.as_ptr(), p.add(4), json_bytes.len());
-    }
-    ptr
 }
 
 // ---------------------------------------------------------------------------
@@ -185,7 +209,7 @@ fn compute_phash_dct(gray_32x32: &[u8]) -> u64 {
 ///
 /// 結果JSON: {"phash":"<16桁hex>","algorithm":"phash-dct","bits":64}
 #[no_mangle]
-pub extern "C" fn process() -> u32 {
+pub extern "C" fn process() -> i32 {
     // suppress unused warnings
     let _ = (get_extension_input, read_content_chunk, get_content_length);
 
@@ -195,17 +219,9 @@ pub extern "C" fn process() -> u32 {
 
     match rc {
         0 => {} // 成功
-        -1 => {
-            return write_result(
-                "{\"error\":\"unsupported image format (supported: JPEG, PNG, WebP, GIF, BMP, TIFF)\"}",
-            );
-        }
-        -2 => {
-            return write_result("{\"error\":\"memory budget exceeded\"}");
-        }
-        _ => {
-            return write_result("{\"error\":\"decode error\"}");
-        }
+        -1 => return ERR_UNSUPPORTED_FORMAT,
+        -2 => return ERR_OUT_OF_MEMORY,
+        _ => return ERR_INVALID_INPUT,
     }
 
     // 2. ホスト側でgrayscale変換 + 32×32リサイズ
@@ -219,7 +235,7 @@ pub extern "C" fn process() -> u32 {
         )
     };
     if rc != (DCT_SIZE * DCT_SIZE) as i32 {
-        return write_result("{\"error\":\"grayscale_resize failed\"}");
+        return ERR_INVALID_INPUT;
     }
 
     // 3. DCTのみ（1024バイト入力）
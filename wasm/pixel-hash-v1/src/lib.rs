@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Pixel Hash Extension WASM モジュール
+//!
+//! 仕様書 §3.2: 再エンコードに対して安定な正規化ピクセルハッシュを算出するExtension。
+//! Manifest署名に基づく `content_hash` とは独立に、同一画像の重複登録や
+//! 低労力な再投稿（再圧縮・フォーマット変換）の検出に用いる。
+//!
+//! ## アルゴリズム
+//! 1. ホスト側で画像をネイティブフォーマットにデコード（`decode_content`）
+//! 2. ホスト側で正規化（`get_decoded_feature` の `canonical_rgb`）:
+//!    アルファ除去 → 16×16 RGBにリサイズ → 各チャネル上位4ビットに量子化
+//! 3. WASM側で正規化済み768バイトのSHA-256を計算
+//!
+//! 正規化パラメータは固定であり、同一入力に対して常に同一のハッシュを返す。
+//! パラメータを変更する場合は別のExtension ID（`pixel-hash-v2` 等）とすること。
+//!
+//! ## 対応フォーマット
+//! ホスト側の`image`crateが対応する全フォーマット（JPEG, PNG, WebP, GIF, BMP, TIFF等）
+//!
+//! ## ターゲット
+//! `wasm32-unknown-unknown`
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use core::fmt::Write;
+use sha2::{Digest, Sha256};
+use title_wasm_abi::ResultBuffer;
+
+#[global_allocator]
+static ALLOC: dlmalloc::GlobalDlmalloc = dlmalloc::GlobalDlmalloc;
+
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+// ---------------------------------------------------------------------------
+// ホスト関数宣言（TEEホストが提供）
+// 仕様書 §7.1
+// ---------------------------------------------------------------------------
+
+extern "C" {
+    /// コンテンツをネイティブフォーマットでデコードする。
+    /// metadata_ptr: [width:u32 LE, height:u32 LE, channels:u32 LE] を書き込む
+    /// 戻り値: 0=成功, -1=非対応, -2=メモリ超過, -3=デコードエラー
+    fn decode_content(params_ptr: u32, params_len: u32, metadata_ptr: u32) -> i32;
+
+    /// デコード済みデータの特徴量を計算する（JSON spec指定）。
+    /// 戻り値: 出力バイト数（正値）またはエラーコード（負値）
+    fn get_decoded_feature(spec_ptr: u32, spec_len: u32, output_ptr: u32) -> i32;
+}
+
+// ---------------------------------------------------------------------------
+// メモリアロケータ
+// ---------------------------------------------------------------------------
+
+#[no_mangle]
+pub extern "C" fn alloc(size: u32) -> u32 {
+    let layout = core::alloc::Layout::from_size_align(size as usize, 1).unwrap();
+    unsafe { alloc::alloc::alloc(layout) as u32 }
+}
+
+// ---------------------------------------------------------------------------
+// ABI v2（仕様書 §7.1）
+// ---------------------------------------------------------------------------
+
+/// ABIバージョン。v2では `process` の負の戻り値がエラーコードを表す。
+const ABI_VERSION: i32 = 2;
+
+/// エラーコード: メモリ確保に失敗
+const ERR_OUT_OF_MEMORY: i32 = -1;
+
+/// エラーコード: 対応していない画像フォーマット
+const ERR_UNSUPPORTED_FORMAT: i32 = -2;
+
+/// エラーコード: コンテンツが不正（デコード失敗等）
+const ERR_INVALID_INPUT: i32 = -3;
+
+/// ホストにABIバージョンを通知する。
+#[no_mangle]
+pub extern "C" fn title_abi_version() -> i32 {
+    ABI_VERSION
+}
+
+/// このモジュールのExtension ID。
+const EXTENSION_ID: &str = "pixel-hash-v1";
+
+/// ホストにExtension IDを自己申告する（`[4B LE: len][id_bytes...]` へのポインタ）。
+/// ホストは要求されたExtension IDと照合し、取り違えたモジュールを拒否する。
+#[no_mangle]
+pub extern "C" fn title_extension_id() -> i32 {
+    write_result(EXTENSION_ID)
+}
+
+// ---------------------------------------------------------------------------
+// 結果バッファ書き込みヘルパー
+// ---------------------------------------------------------------------------
+
+fn write_result(json: &str) -> i32 {
+    match ResultBuffer::new(json.as_bytes()).write(alloc) {
+        Some(ptr) => ptr as i32,
+        None => ERR_OUT_OF_MEMORY,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 正規化パラメータ（固定）
+// ---------------------------------------------------------------------------
+
+/// 正規化後の一辺のピクセル数
+const CANONICAL_SIZE: usize = 16;
+
+/// 正規化後のチャネル数（RGB、アルファ除去）
+const CANONICAL_CHANNELS: usize = 3;
+
+/// 各チャネルの量子化ビット数
+const QUANT_BITS: u32 = 4;
+
+// ---------------------------------------------------------------------------
+// エクスポート関数
+// ---------------------------------------------------------------------------
+
+/// 正規化ピクセルハッシュを計算する。
+/// 仕様書 §3.2
+///
+/// 結果JSON: {"pixel_hash":"<64桁hex>","algorithm":"sha256-rgb16x16q4"}
+#[no_mangle]
+pub extern "C" fn process() -> i32 {
+    // 1. ホスト側でネイティブフォーマットにデコード
+    let mut metadata = [0u8; 12];
+    let rc = unsafe { decode_content(0, 0, metadata.as_mut_ptr() as u32) };
+
+    match rc {
+        0 => {} // 成功
+        -1 => return ERR_UNSUPPORTED_FORMAT,
+        -2 => return ERR_OUT_OF_MEMORY,
+        _ => return ERR_INVALID_INPUT,
+    }
+
+    // 2. ホスト側で正規化（アルファ除去 + 16×16リサイズ + 4ビット量子化）
+    let spec = alloc::format!(
+        "{{\"op\":\"canonical_rgb\",\"width\":{CANONICAL_SIZE},\"height\":{CANONICAL_SIZE},\"quant_bits\":{QUANT_BITS}}}"
+    );
+    let mut canonical = [0u8; CANONICAL_SIZE * CANONICAL_SIZE * CANONICAL_CHANNELS];
+    let rc = unsafe {
+        get_decoded_feature(
+            spec.as_ptr() as u32,
+            spec.len() as u32,
+            canonical.as_mut_ptr() as u32,
+        )
+    };
+    if rc != canonical.len() as i32 {
+        return ERR_INVALID_INPUT;
+    }
+
+    // 3. 正規化済みピクセル列のSHA-256
+    let digest = Sha256::digest(canonical);
+
+    let mut json = String::with_capacity(128);
+    json.push_str("{\"pixel_hash\":\"");
+    for b in digest.iter() {
+        let _ = write!(&mut json, "{:02x}", b);
+    }
+    json.push_str("\",\"algorithm\":\"sha256-rgb16x16q4\"}");
+
+    write_result(&json)
+}